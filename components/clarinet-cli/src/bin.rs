@@ -11,9 +11,12 @@ extern crate hiro_system_kit;
 
 mod deployments;
 mod devnet;
+mod docs;
 mod frontend;
 mod generate;
+mod interfaces;
 mod lsp;
+mod notebook;
 
 use frontend::cli;
 
@@ -36,6 +36,7 @@ pub fn start_ui(
             Ok(DeploymentEvent::TransactionUpdate(update)) => {
                 app.display_contract_status_update(update);
             }
+            Ok(DeploymentEvent::TransactionsRecorded(_)) => {}
             Ok(DeploymentEvent::DeploymentCompleted) => {
                 break Ok(());
             }
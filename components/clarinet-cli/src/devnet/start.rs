@@ -11,7 +11,7 @@ use hiro_system_kit::{slog, slog_async, slog_term};
 use stacks_network::{
     chainhook_sdk::types::{BitcoinNetwork, StacksNetwork},
     chainhook_sdk::utils::Context,
-    do_run_local_devnet, load_chainhooks, ChainsCoordinatorCommand, DevnetEvent,
+    do_run_local_devnet, lint_chainhooks, load_chainhooks, ChainsCoordinatorCommand, DevnetEvent,
     DevnetOrchestrator, LogData,
 };
 use std::fs::OpenOptions;
@@ -29,6 +29,11 @@ pub fn start(
     ),
     String,
 > {
+    if let Ok(warnings) = lint_chainhooks(&devnet.manifest.location) {
+        for warning in warnings.iter() {
+            eprintln!("{}", format_warn!(warning));
+        }
+    }
     let hooks = match load_chainhooks(
         &devnet.manifest.location,
         &(BitcoinNetwork::Regtest, StacksNetwork::Devnet),
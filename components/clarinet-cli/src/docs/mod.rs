@@ -0,0 +1,317 @@
+use std::collections::BTreeMap;
+
+use clarinet_deployments::contract_size::estimate_contract_publish_len;
+use clarinet_deployments::interface_diff::{
+    error_constants, functions_error_codes, functions_signatures,
+};
+use clarity_events::analysis::StacksTransactionEvent;
+use clarity_events::catalog::{build_contract_catalog, markdown_events};
+use clarity_repl::clarity::costs::ExecutionCost;
+use clarity_repl::clarity::vm::analysis::ContractAnalysis;
+use clarity_repl::clarity::vm::types::FunctionType;
+use clarity_repl::clarity::StacksEpochId;
+use clarity_repl::repl::Session;
+use serde_json::Value as JsonValue;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FunctionDocumentation {
+    pub name: String,
+    pub signature: String,
+    /// Comments preceding the definition of the function
+    pub doc: Vec<String>,
+    pub error_codes: Vec<String>,
+    pub events: Vec<StacksTransactionEvent>,
+    /// Only estimated for read-only functions without arguments
+    pub cost: Option<ExecutionCost>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ContractDocumentation {
+    pub contract_id: String,
+    pub implemented_traits: Vec<String>,
+    pub defined_traits: Vec<String>,
+    pub functions: Vec<FunctionDocumentation>,
+    pub error_constants: BTreeMap<String, String>,
+    pub publish_transaction_len: usize,
+}
+
+fn functions_docs(analysis: &ContractAnalysis) -> BTreeMap<String, Vec<String>> {
+    let mut docs = BTreeMap::new();
+    for expr in analysis.expressions.iter() {
+        let Some(name) = expr
+            .match_list()
+            .and_then(|list| list.get(1))
+            .and_then(|signature| signature.match_list())
+            .and_then(|signature| signature.first())
+            .and_then(|name| name.match_atom())
+        else {
+            continue;
+        };
+        let doc: Vec<String> = expr
+            .pre_comments
+            .iter()
+            .map(|(comment, _)| comment.trim().to_string())
+            .collect();
+        if !doc.is_empty() {
+            docs.insert(name.to_string(), doc);
+        }
+    }
+    docs
+}
+
+/// Document a contract deployed in `session`: its public interface, doc comments,
+/// traits, error codes, emitted events and cost estimates.
+pub fn build_contract_documentation(
+    session: &mut Session,
+    analysis: &mut ContractAnalysis,
+    source: &str,
+) -> ContractDocumentation {
+    let contract_id = analysis.contract_identifier.clone();
+    let catalog = build_contract_catalog(session, analysis);
+    let mut events: BTreeMap<String, Vec<StacksTransactionEvent>> = catalog
+        .functions
+        .into_iter()
+        .map(|function| (function.name, function.events))
+        .collect();
+    let mut docs = functions_docs(analysis);
+    let mut error_codes = functions_error_codes(analysis);
+
+    // costs are estimated in a clone, so that the calls do not end up in the session
+    // history
+    let mut cost_session = session.clone();
+    let sender = contract_id.issuer.to_string();
+
+    let mut functions = vec![];
+    for (name, signature) in functions_signatures(analysis).into_iter() {
+        let function_type = analysis
+            .read_only_function_types
+            .iter()
+            .find(|(function_name, _)| function_name.as_str() == name)
+            .map(|(_, function_type)| function_type);
+        let cost = match function_type {
+            Some(FunctionType::Fixed(function)) if function.args.is_empty() => cost_session
+                .call_contract_fn(&contract_id.to_string(), &name, &[], &sender, false, true)
+                .ok()
+                .and_then(|result| result.cost)
+                .map(|cost| cost.total),
+            _ => None,
+        };
+        functions.push(FunctionDocumentation {
+            doc: docs.remove(&name).unwrap_or_default(),
+            error_codes: error_codes
+                .remove(&name)
+                .map(|codes| codes.into_iter().collect())
+                .unwrap_or_default(),
+            events: events.remove(&name).unwrap_or_default(),
+            name,
+            signature,
+            cost,
+        });
+    }
+
+    ContractDocumentation {
+        contract_id: contract_id.to_string(),
+        implemented_traits: analysis
+            .implemented_traits
+            .iter()
+            .map(|t| format!("{}.{}", t.contract_identifier, t.name))
+            .collect(),
+        defined_traits: analysis
+            .defined_traits
+            .keys()
+            .map(|name| name.to_string())
+            .collect(),
+        functions,
+        error_constants: error_constants(analysis),
+        publish_transaction_len: estimate_contract_publish_len(
+            &contract_id.name,
+            source,
+            analysis.epoch >= StacksEpochId::Epoch21,
+        ),
+    }
+}
+
+impl ContractDocumentation {
+    pub fn to_json(&self) -> JsonValue {
+        json!(self)
+    }
+
+    pub fn contract_name(&self) -> &str {
+        self.contract_id
+            .split_once('.')
+            .map(|(_, name)| name)
+            .unwrap_or(&self.contract_id)
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut output = vec![format!("# {}\n", self.contract_name())];
+        output.push(format!("Contract identifier: `{}`", self.contract_id));
+
+        if !self.implemented_traits.is_empty() {
+            output.push("\n## Implemented traits\n".to_string());
+            for name in self.implemented_traits.iter() {
+                output.push(format!("- `{}`", name));
+            }
+        }
+
+        if !self.defined_traits.is_empty() {
+            output.push("\n## Defined traits\n".to_string());
+            for name in self.defined_traits.iter() {
+                output.push(format!("- `{}`", name));
+            }
+        }
+
+        if !self.functions.is_empty() {
+            output.push("\n## Functions".to_string());
+            for function in self.functions.iter() {
+                output.push(format!("\n### `{}`\n", function.name));
+                output.push(format!("```clarity\n{}\n```", function.signature));
+                if !function.doc.is_empty() {
+                    output.push(format!("\n{}", function.doc.join("\n")));
+                }
+                if !function.error_codes.is_empty() {
+                    let codes: Vec<String> = function
+                        .error_codes
+                        .iter()
+                        .map(|code| format!("`{}`", code))
+                        .collect();
+                    output.push(format!("\nErrors: {}", codes.join(", ")));
+                }
+                if !function.events.is_empty() {
+                    output.push("\nEvents:\n".to_string());
+                    markdown_events(&function.events, &mut output);
+                }
+                if let Some(cost) = &function.cost {
+                    output.push(format!(
+                        "\nCost: runtime {}, read count {}, read length {}",
+                        cost.runtime, cost.read_count, cost.read_length
+                    ));
+                }
+            }
+        }
+
+        if !self.error_constants.is_empty() {
+            output.push("\n## Errors\n".to_string());
+            output.push("| Name | Value |".to_string());
+            output.push("| --- | --- |".to_string());
+            for (name, code) in self.error_constants.iter() {
+                output.push(format!("| {} | `(err {})` |", name, code));
+            }
+        }
+
+        output.push("\n## Deployment\n".to_string());
+        output.push(format!(
+            "Publish transaction length: {} bytes",
+            self.publish_transaction_len
+        ));
+        output.push(String::new());
+        output.join("\n")
+    }
+}
+
+/// Index of the documentation, linking to the page of each contract
+pub fn index_to_json(project_name: &str, contracts: &[ContractDocumentation]) -> JsonValue {
+    let contracts: Vec<JsonValue> = contracts
+        .iter()
+        .map(|contract| {
+            json!({
+                "name": contract.contract_name(),
+                "contract_id": contract.contract_id,
+                "path": format!("./{}.json", contract.contract_name()),
+            })
+        })
+        .collect();
+    json!({ "project": project_name, "contracts": contracts })
+}
+
+/// Index of the documentation, linking to the page of each contract
+pub fn index_to_markdown(project_name: &str, contracts: &[ContractDocumentation]) -> String {
+    let mut output = vec![format!("# {}\n", project_name)];
+    for contract in contracts.iter() {
+        let name = contract.contract_name();
+        output.push(format!("- [{}](./{}.md)", name, name));
+    }
+    output.push(String::new());
+    output.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clarity_repl::clarity::vm::EvaluationResult;
+    use clarity_repl::repl::SessionSettings;
+
+    const SOURCE: [&str; 9] = [
+        "(define-constant ERR_UNAUTHORIZED (err u401))",
+        "(define-data-var count uint u0)",
+        ";; Increment the counter",
+        "(define-public (increment)",
+        "  (begin",
+        "    (asserts! (is-eq tx-sender contract-caller) ERR_UNAUTHORIZED)",
+        "    (print { action: \"increment\" })",
+        "    (ok (var-set count (+ (var-get count) u1)))))",
+        "(define-read-only (get-count) (var-get count))",
+    ];
+
+    fn deploy(source: &str) -> (Session, ContractAnalysis) {
+        let mut session = Session::new(SessionSettings::default());
+        session.update_epoch(StacksEpochId::Epoch25);
+        let analysis = match session.eval(source.to_string(), false).unwrap().result {
+            EvaluationResult::Contract(evaluation) => evaluation.contract.analysis,
+            _ => unreachable!(),
+        };
+        (session, analysis)
+    }
+
+    #[test]
+    fn test_build_contract_documentation() {
+        let source = SOURCE.join("\n");
+        let (mut session, mut analysis) = deploy(&source);
+
+        let documentation = build_contract_documentation(&mut session, &mut analysis, &source);
+        assert_eq!(documentation.functions.len(), 2);
+        let get_count = &documentation.functions[0];
+        assert_eq!(get_count.name, "get-count");
+        assert!(get_count.cost.is_some());
+        let increment = &documentation.functions[1];
+        assert_eq!(increment.doc, vec!["Increment the counter".to_string()]);
+        assert_eq!(increment.error_codes, vec!["u401".to_string()]);
+        assert_eq!(increment.events.len(), 1);
+        assert!(increment.cost.is_none());
+
+        let markdown = documentation.to_markdown();
+        assert!(markdown.contains("### `increment`"));
+        assert!(markdown.contains("| ERR_UNAUTHORIZED | `(err u401)` |"));
+
+        let json = documentation.to_json();
+        assert_eq!(json["functions"][1]["name"], "increment");
+        assert_eq!(json["functions"][1]["error_codes"], json!(["u401"]));
+        assert_eq!(json["error_constants"]["ERR_UNAUTHORIZED"], "u401");
+        let index = index_to_json("counter", std::slice::from_ref(&documentation));
+        assert_eq!(
+            index["contracts"][0]["path"],
+            format!("./{}.json", documentation.contract_name())
+        );
+    }
+
+    #[test]
+    fn test_documentation_is_deterministic() {
+        let source = SOURCE.join("\n");
+        let (mut session, mut analysis) = deploy(&source);
+        let first = build_contract_documentation(&mut session, &mut analysis, &source);
+        // estimating the costs leaves the session untouched, documenting again gives the same pages
+        let again = build_contract_documentation(&mut session, &mut analysis, &source);
+
+        // documenting the contract from another session gives the same pages
+        let (mut other_session, mut other_analysis) = deploy(&source);
+        let other = build_contract_documentation(&mut other_session, &mut other_analysis, &source);
+
+        for documentation in [again, other] {
+            assert_eq!(documentation.to_markdown(), first.to_markdown());
+            assert_eq!(
+                serde_json::to_string(&documentation.to_json()).unwrap(),
+                serde_json::to_string(&first.to_json()).unwrap()
+            );
+        }
+    }
+}
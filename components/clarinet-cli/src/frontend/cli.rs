@@ -5,45 +5,108 @@ use crate::deployments::{
 };
 use crate::devnet::package::{self as Package, ConfigurationPackage};
 use crate::devnet::start::start;
+use crate::docs::{build_contract_documentation, index_to_json, index_to_markdown};
 use crate::generate::{
     self,
     changes::{Changes, TOMLEdition},
+    ContractTemplate,
 };
+use crate::interfaces::{contract_interface_to_json, contract_typescript_bindings};
 use crate::lsp::run_lsp;
+use crate::notebook::{notebook_to_html, notebook_to_markdown, parse_notebook, run_notebook};
 
 use clap::{CommandFactory, Parser, Subcommand};
 use clap_complete::{Generator, Shell};
-use clarinet_deployments::diagnostic_digest::DiagnosticsDigest;
+use clarinet_deployments::contract_size::{
+    check_contract_sizes, ContractSizeReport, MAX_TRANSACTION_LEN,
+};
+use clarinet_deployments::cost_curve::{
+    compute_cost_curve, scaffold_arguments_spec, validate_arguments_spec, ArgumentsSpec,
+};
+use clarinet_deployments::costs_report::{parse_costs_report, summarize_costs};
+use clarinet_deployments::dependency_graph::{dependencies_to_dot, publish_order};
+use clarinet_deployments::diagnostic_digest::{diagnostics_reports, DiagnosticsDigest};
+use clarinet_deployments::differential::{
+    compare_outcomes, run_deployment_outcomes, run_tests_outcomes,
+};
+use clarinet_deployments::error_codes::ErrorCodesRegistry;
+use clarinet_deployments::fuzz::fuzz_contract;
+use clarinet_deployments::interface_diff::{
+    diff_contract_interfaces, diffs_to_markdown, ContractDiff,
+};
+use clarinet_deployments::onchain::bitcoin_devnet::{
+    burn_output, op_return_output, payment_output, DevnetBitcoinRpc, TxOut, Txid,
+};
+use clarinet_deployments::onchain::export::{
+    export_console_transaction, export_unsigned_transactions,
+};
+use clarinet_deployments::onchain::load::{run_load_scenario, LoadScenario};
+use clarinet_deployments::onchain::nonces::{cancel_stuck_nonce, get_address_nonces};
 use clarinet_deployments::onchain::{
     apply_on_chain_deployment, get_initial_transactions_trackers, update_deployment_costs,
-    DeploymentCommand, DeploymentEvent,
+    DeploymentCommand, DeploymentEvent, DeploymentStepHook, TransactionStatus,
+};
+use clarinet_deployments::query::{run_queries_on_api, run_queries_on_session, QueriesSpec};
+use clarinet_deployments::replay::{
+    fetch_contract_call, replay_contract_call, setup_replay_session,
+};
+use clarinet_deployments::requirements::resolve_transitive_requirements;
+use clarinet_deployments::snapshot::{diff_snapshots, take_snapshot, StorageSnapshot};
+use clarinet_deployments::test_runner::{discover_tests, run_tests, to_junit_xml, TestOutcome};
+use clarinet_deployments::types::{
+    DeploymentGenerationArtifacts, DeploymentSpecification, TransactionSpecification,
 };
-use clarinet_deployments::types::{DeploymentGenerationArtifacts, DeploymentSpecification};
+use clarinet_deployments::upgrade::{
+    build_upgrade_deployment, check_storage_compatibility, fetch_deployed_source,
+    simulate_upgrade_deployment, use_deployed_source, ContractUpgrade, MigrationCall,
+};
+use clarinet_deployments::variables::{check_contract_calls_arguments, load_variables};
 use clarinet_deployments::{
-    get_default_deployment_path, load_deployment, setup_session_with_deployment,
+    get_default_deployment_path, initiate_session_from_manifest, load_deployment,
+    load_deployment_with_variables, setup_session_with_deployment,
+};
+use clarinet_files::explorer::ExplorerLinks;
+use clarinet_files::git_hooks::{get_git_hook_gates, install_git_hooks, GitHook, GitHookGate};
+use clarinet_files::keychain::{
+    generate_mnemonic, parse_account_secret, AccountSecret, KeychainAccount, KEYCHAIN_PASSWORD_ENV,
 };
-use clarinet_files::StacksNetwork;
+use clarinet_files::plugins::{run_plugins, LifecycleHook};
+use clarinet_files::principals::{collect_files, rewrite_principals};
 use clarinet_files::{
-    get_manifest_location, FileLocation, NetworkManifest, ProjectManifest, ProjectManifestFile,
-    RequirementConfig,
+    get_manifest_location, AccountConfig, FileLocation, NetworkManifest, ProjectManifest,
+    ProjectManifestFile, RequirementConfig,
 };
+use clarinet_files::{BitcoinNetwork, StacksNetwork};
 use clarity_repl::analysis::call_checker::ContractAnalysis;
+use clarity_repl::clarity::consts::{CHAIN_ID_MAINNET, CHAIN_ID_TESTNET};
+use clarity_repl::clarity::util::hash::{bytes_to_hex, hex_bytes};
+use clarity_repl::clarity::util::secp256k1::{Secp256k1PrivateKey, Secp256k1PublicKey};
 use clarity_repl::clarity::vm::analysis::AnalysisDatabase;
 use clarity_repl::clarity::vm::costs::LimitedCostTracker;
-use clarity_repl::clarity::vm::types::QualifiedContractIdentifier;
+use clarity_repl::clarity::vm::types::{FunctionType, PrincipalData, QualifiedContractIdentifier};
+use clarity_repl::clarity::vm::{ContractName, EvaluationResult, Value};
 use clarity_repl::clarity::ClarityVersion;
-use clarity_repl::frontend::terminal::print_clarity_wasm_warning;
+use clarity_repl::frontend::console_commands::ConsoleCommands;
+use clarity_repl::frontend::terminal::clarity_wasm_warning;
+use clarity_repl::repl::clarity_values::value_to_json;
 use clarity_repl::repl::diagnostic::output_diagnostic;
-use clarity_repl::repl::{ClarityCodeSource, ClarityContract, ContractDeployer, DEFAULT_EPOCH};
+use clarity_repl::repl::post_conditions::{PostConditionMode, PostConditions};
+use clarity_repl::repl::sip018;
+use clarity_repl::repl::{
+    ClarityCodeSource, ClarityContract, ContractDeployer, SessionState, DEFAULT_EPOCH,
+};
 use clarity_repl::{analysis, repl, Terminal};
 use stacks_network::{self, DevnetOrchestrator};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fs::{self, File};
 use std::io::prelude::*;
+use std::path::PathBuf;
+use std::str::FromStr;
 use std::{env, process};
 use toml;
 
 use super::clarinetrc::GlobalSettings;
+use super::simnet_api::{start_simnet_api, SimnetApi};
 
 #[cfg(feature = "telemetry")]
 use super::telemetry::{telemetry_report_event, DeveloperUsageDigest, DeveloperUsageEvent};
@@ -73,6 +136,15 @@ enum Command {
     /// Interact with contracts deployed on Mainnet
     #[clap(subcommand, name = "requirements", aliases = &["requirement"])]
     Requirements(Requirements),
+    /// Manage named accounts, stored encrypted in ~/.clarinet/keychain
+    #[clap(subcommand, name = "accounts", aliases = &["account"])]
+    Accounts(Accounts),
+    /// Hash, sign and verify SIP-018 structured data
+    #[clap(subcommand, name = "sip018")]
+    Sip018(Sip018),
+    /// Install and run the git hooks of the project
+    #[clap(subcommand, name = "hook", aliases = &["hooks"])]
+    Hook(Hook),
     /// Subcommands for working with chainhooks (deprecated)
     #[clap(name = "chainhooks", aliases = &["chainhook"])]
     Chainhooks,
@@ -85,18 +157,43 @@ enum Command {
     /// Check contracts syntax
     #[clap(name = "check", bin_name = "check")]
     Check(Check),
+    /// Run the `test-` functions of the contracts natively, in isolated simnet sessions
+    #[clap(name = "test", bin_name = "test")]
+    Test(RunTests),
+    /// Generate the documentation of the project contracts
+    #[clap(name = "docs", bin_name = "docs")]
+    Docs(Docs),
+    /// Execute the Clarity cells of a markdown notebook on Simnet and render their outputs,
+    /// exits with an error if a cell fails
+    #[clap(name = "notebook", bin_name = "notebook")]
+    Notebook(RunNotebook),
+    /// Print the explorer link of a transaction, an address or a contract
+    #[clap(name = "explorer", bin_name = "explorer")]
+    Explorer(Explorer),
+    /// Run the read-only calls of a queries file and output the results as csv or json
+    #[clap(name = "query", bin_name = "query")]
+    Query(Query),
+    /// Replay a Mainnet/Testnet contract call locally
+    #[clap(name = "replay-tx", bin_name = "replay-tx")]
+    ReplayTx(ReplayTx),
     /// Start a local Devnet network for interacting with your contracts from your browser
     #[clap(name = "integrate", bin_name = "integrate")]
     Integrate(DevnetStart),
     /// Subcommands for Devnet usage
     #[clap(subcommand, name = "devnet")]
     Devnet(Devnet),
+    /// Subcommands for Simnet usage
+    #[clap(subcommand, name = "simnet")]
+    Simnet(Simnet),
     /// Get Clarity autocompletion and inline errors from your code editor (VSCode, vim, emacs, etc)
     #[clap(name = "lsp", bin_name = "lsp")]
     LSP,
     /// Step by step debugging and breakpoints from your code editor (VSCode, vim, emacs, etc)
     #[clap(name = "dap", bin_name = "dap")]
     DAP,
+    /// Serve check, call simulation and plan generation requests over JSON-RPC
+    #[clap(name = "serve", bin_name = "serve")]
+    Serve(Serve),
 }
 
 #[derive(Subcommand, PartialEq, Clone, Debug)]
@@ -108,6 +205,158 @@ enum Devnet {
     /// Start a local Devnet network for interacting with your contracts from your browser
     #[clap(name = "start", bin_name = "start")]
     DevnetStart(DevnetStart),
+
+    /// Send transactions and mine blocks on the Devnet bitcoin node
+    #[clap(subcommand, name = "btc", bin_name = "btc")]
+    Btc(DevnetBtc),
+
+    /// Submit contract-calls from a scenario file and report inclusion latency and throughput
+    #[clap(name = "load", bin_name = "load")]
+    Load(DevnetLoad),
+}
+
+#[derive(Subcommand, PartialEq, Clone, Debug)]
+enum Simnet {
+    /// Serve a subset of the Stacks API (accounts, read-only calls, broadcast) backed by Simnet
+    #[clap(name = "serve", bin_name = "serve")]
+    Serve(SimnetServe),
+    /// Save the state of Simnet, after the deployment plan, to a file
+    #[clap(name = "snapshot", bin_name = "snapshot")]
+    Snapshot(SimnetSnapshot),
+}
+
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct SimnetServe {
+    /// Path to Clarinet.toml
+    #[clap(long = "manifest-path", short = 'm')]
+    pub manifest_path: Option<String>,
+    /// If specified, use this deployment file
+    #[clap(long = "deployment-plan-path", short = 'p')]
+    pub deployment_plan_path: Option<String>,
+    /// Port of the API, on localhost
+    #[clap(long = "port", default_value = "3999")]
+    pub port: u16,
+    /// Execution backend of the contracts, wasm runs them compiled to Wasm (beta)
+    #[clap(long = "runtime", value_enum, default_value = "interpreter")]
+    pub runtime: Runtime,
+}
+
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct SimnetSnapshot {
+    /// Path of the snapshot file
+    #[clap(long = "output", short = 'o', default_value = "simnet-snapshot.json")]
+    pub output: String,
+    /// Path to Clarinet.toml
+    #[clap(long = "manifest-path", short = 'm')]
+    pub manifest_path: Option<String>,
+    /// If specified, use this deployment file
+    #[clap(long = "deployment-plan-path", short = 'p')]
+    pub deployment_plan_path: Option<String>,
+    /// Execution backend of the contracts, wasm runs them compiled to Wasm (beta)
+    #[clap(long = "runtime", value_enum, default_value = "interpreter")]
+    pub runtime: Runtime,
+}
+
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct DevnetLoad {
+    /// Path to the scenario file (rates, senders and calls mix)
+    #[clap(long = "scenario")]
+    pub scenario: String,
+    /// Path to Clarinet.toml
+    #[clap(long = "manifest-path", short = 'm')]
+    pub manifest_path: Option<String>,
+}
+
+#[derive(Subcommand, PartialEq, Clone, Debug)]
+enum DevnetBtc {
+    /// Send BTC to an address
+    #[clap(name = "send", bin_name = "send")]
+    Send(DevnetBtcSend),
+    /// Mine bitcoin blocks
+    #[clap(name = "mine", bin_name = "mine")]
+    Mine(DevnetBtcMine),
+    /// Broadcast a transaction with an OP_RETURN output
+    #[clap(name = "op-return", bin_name = "op-return")]
+    OpReturn(DevnetBtcOpReturn),
+    /// Broadcast a pre-stx and a stack-stx burnchain operation (PoX)
+    #[clap(name = "pox", bin_name = "pox")]
+    Pox(DevnetBtcPox),
+    /// Broadcast a transaction burning BTC (PoB)
+    #[clap(name = "pob", bin_name = "pob")]
+    Pob(DevnetBtcPob),
+}
+
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct DevnetBtcOptions {
+    /// Name of the account funding the transaction (default: deployer)
+    #[clap(long = "account", default_value = "deployer")]
+    pub account: String,
+    /// Fee rate, in sats per byte
+    #[clap(long = "fee-rate", default_value = "10")]
+    pub sats_per_byte: u64,
+    /// Mine a block including the transaction
+    #[clap(long = "mine")]
+    pub mine: bool,
+    /// Path to Clarinet.toml
+    #[clap(long = "manifest-path", short = 'm')]
+    pub manifest_path: Option<String>,
+}
+
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct DevnetBtcSend {
+    /// Recipient address
+    pub recipient: String,
+    /// Amount to send, in sats
+    pub sats_amount: u64,
+    #[clap(flatten)]
+    pub options: DevnetBtcOptions,
+}
+
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct DevnetBtcMine {
+    /// Number of blocks to mine
+    #[clap(default_value = "1")]
+    pub count: u64,
+    /// Address receiving the coinbase rewards (default: devnet miner)
+    #[clap(long = "recipient")]
+    pub recipient: Option<String>,
+    /// Path to Clarinet.toml
+    #[clap(long = "manifest-path", short = 'm')]
+    pub manifest_path: Option<String>,
+}
+
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct DevnetBtcOpReturn {
+    /// Hex encoded payload (80 bytes max)
+    pub payload: String,
+    #[clap(flatten)]
+    pub options: DevnetBtcOptions,
+}
+
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct DevnetBtcPox {
+    /// Amount of uSTX to lock
+    #[clap(long = "amount")]
+    pub ustx_amount: u128,
+    /// Number of reward cycles
+    #[clap(long = "cycles", default_value = "1")]
+    pub num_cycles: u8,
+    /// Bitcoin address receiving the PoX rewards (default: sender)
+    #[clap(long = "pox-address")]
+    pub pox_address: Option<String>,
+    #[clap(flatten)]
+    pub options: DevnetBtcOptions,
+}
+
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct DevnetBtcPob {
+    /// Amount to burn, in sats
+    pub sats_amount: u64,
+    /// Hex encoded payload of an additional OP_RETURN output
+    #[clap(long = "payload")]
+    pub payload: Option<String>,
+    #[clap(flatten)]
+    pub options: DevnetBtcOptions,
 }
 
 #[derive(Subcommand, PartialEq, Clone, Debug)]
@@ -118,6 +367,65 @@ enum Contracts {
     /// Remove files and settings for a contract
     #[clap(name = "rm", bin_name = "rm")]
     RemoveContract(RemoveContract),
+    /// Generate and simulate a deployment plan upgrading a deployed contract to a new version
+    #[clap(name = "upgrade", bin_name = "upgrade")]
+    UpgradeContract(UpgradeContract),
+    /// Report the interface changes of the contracts since a git revision
+    #[clap(name = "diff", bin_name = "diff")]
+    DiffContracts(DiffContracts),
+    /// Evaluate the cost of a function across a grid of argument sizes
+    #[clap(name = "costs", bin_name = "costs")]
+    ContractCosts(ContractCosts),
+    /// Summarize the costs recorded by the tests (vitest run -- --costs) per function
+    #[clap(name = "costs-report", bin_name = "costs-report")]
+    CostsReport(CostsReport),
+    /// Snapshot the data vars and map entries of the contracts after a deployment
+    #[clap(name = "snapshot", bin_name = "snapshot")]
+    SnapshotContracts(SnapshotContracts),
+    /// Compare two storage snapshots, exits with an error if they differ
+    #[clap(name = "diff-snapshots", bin_name = "diff-snapshots")]
+    DiffSnapshots(DiffSnapshots),
+    /// Rewrite the principals hardcoded in contracts, tests and the plan of another network
+    #[clap(name = "retarget", bin_name = "retarget")]
+    RetargetPrincipals(RetargetPrincipals),
+    /// Display the dependencies of the contracts, in deployment order
+    #[clap(name = "graph", bin_name = "graph")]
+    ContractsGraph(ContractsGraph),
+    /// Call the public functions of a contract with random arguments, checking its invariants
+    #[clap(name = "fuzz", bin_name = "fuzz")]
+    FuzzContract(FuzzContract),
+    /// Export the interfaces of the contracts as json, and optionally as TypeScript bindings
+    #[clap(name = "interfaces", bin_name = "interfaces")]
+    ExportInterfaces(ExportInterfaces),
+}
+
+#[derive(Subcommand, PartialEq, Clone, Debug)]
+enum Hook {
+    /// Install git hooks running the gates of [project.git_hooks]
+    #[clap(name = "install", bin_name = "install")]
+    Install(InstallHook),
+    /// Run the gates of a git hook (pre-commit, pre-push)
+    #[clap(name = "run", bin_name = "run")]
+    Run(RunHook),
+}
+
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct InstallHook {
+    /// Replace existing hooks that were not installed by clarinet
+    #[clap(long = "force")]
+    pub force: bool,
+    /// Path to Clarinet.toml
+    #[clap(long = "manifest-path", short = 'm')]
+    pub manifest_path: Option<String>,
+}
+
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct RunHook {
+    /// Name of the git hook
+    pub hook: String,
+    /// Path to Clarinet.toml
+    #[clap(long = "manifest-path", short = 'm')]
+    pub manifest_path: Option<String>,
 }
 
 #[derive(Subcommand, PartialEq, Clone, Debug)]
@@ -127,6 +435,35 @@ enum Requirements {
     AddRequirement(AddRequirement),
 }
 
+#[derive(Subcommand, PartialEq, Clone, Debug)]
+enum Accounts {
+    /// Generate a new account from a random mnemonic
+    #[clap(name = "new", bin_name = "new")]
+    NewAccount(NewAccount),
+    /// Import an account from a mnemonic or a secret key
+    #[clap(name = "import", bin_name = "import")]
+    ImportAccount(ImportAccount),
+    /// List accounts
+    #[clap(name = "list", bin_name = "list", aliases = &["ls"])]
+    ListAccounts,
+    /// Display the addresses of an account
+    #[clap(name = "show", bin_name = "show")]
+    ShowAccount(ShowAccount),
+}
+
+#[derive(Subcommand, PartialEq, Clone, Debug)]
+enum Sip018 {
+    /// Compute the hash of a structured data message
+    #[clap(name = "hash", bin_name = "hash")]
+    Hash(StructuredData),
+    /// Sign a structured data message with an account of the project
+    #[clap(name = "sign", bin_name = "sign")]
+    Sign(SignStructuredData),
+    /// Verify the signature of a structured data message
+    #[clap(name = "verify", bin_name = "verify")]
+    Verify(VerifyStructuredData),
+}
+
 #[allow(clippy::enum_variant_names)]
 #[derive(Subcommand, PartialEq, Clone, Debug)]
 enum Deployments {
@@ -139,6 +476,15 @@ enum Deployments {
     /// Apply deployment
     #[clap(name = "apply", bin_name = "apply")]
     ApplyDeployment(ApplyDeployment),
+    /// Export deployment transactions as unsigned stacks.js transactions
+    #[clap(name = "export", bin_name = "export")]
+    ExportDeployment(ExportDeployment),
+    /// Report the nonces of the project accounts and cancel the stuck ones
+    #[clap(name = "nonces", bin_name = "nonces")]
+    Nonces(DeploymentNonces),
+    /// Execute the simnet deployment plan and the test suite with the interpreter and clarity-wasm and report divergences
+    #[clap(name = "compare-runtimes", bin_name = "compare-runtimes")]
+    CompareRuntimes(CompareRuntimes),
 }
 
 #[derive(Parser, PartialEq, Clone, Debug)]
@@ -157,12 +503,18 @@ struct GenerateProject {
     /// Do not provide developer usage telemetry for this project
     #[clap(long = "disable-telemetry")]
     pub disable_telemetry: bool,
+    /// Generate a contract named after the project, and its test, from a template
+    #[clap(long = "template", value_enum)]
+    pub template: Option<ContractTemplate>,
 }
 
 #[derive(Parser, PartialEq, Clone, Debug)]
 struct NewContract {
     /// Contract's name
     pub name: String,
+    /// Generate the contract and its test from a template
+    #[clap(long = "template", value_enum)]
+    pub template: Option<ContractTemplate>,
     /// Path to Clarinet.toml
     #[clap(long = "manifest-path", short = 'm')]
     pub manifest_path: Option<String>,
@@ -177,15 +529,283 @@ struct RemoveContract {
     pub manifest_path: Option<String>,
 }
 
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct UpgradeContract {
+    /// Name of the contract to upgrade
+    pub name: String,
+    /// Path to the new version of the contract
+    pub source_path: String,
+    /// Name of the new contract (default: <name>-v2)
+    #[clap(long = "new-name")]
+    pub new_name: Option<String>,
+    /// Call performed once the new contract is published, as "<method> <args>..." (prompted if omitted)
+    #[clap(long = "migration-call")]
+    pub migration_calls: Vec<String>,
+    /// Generate the upgrade plan for devnet (default)
+    #[clap(
+        long = "devnet",
+        conflicts_with = "testnet",
+        conflicts_with = "mainnet"
+    )]
+    pub devnet: bool,
+    /// Generate the upgrade plan for testnet
+    #[clap(
+        long = "testnet",
+        conflicts_with = "devnet",
+        conflicts_with = "mainnet"
+    )]
+    pub testnet: bool,
+    /// Generate the upgrade plan for mainnet
+    #[clap(
+        long = "mainnet",
+        conflicts_with = "devnet",
+        conflicts_with = "testnet"
+    )]
+    pub mainnet: bool,
+    /// Path to Clarinet.toml
+    #[clap(long = "manifest-path", short = 'm')]
+    pub manifest_path: Option<String>,
+}
+
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct DiffContracts {
+    /// Git revision to compare the contracts with (ex. "main", "v1.0.0", "HEAD~1")
+    #[clap(long = "from")]
+    pub from: String,
+    /// Path to Clarinet.toml
+    #[clap(long = "manifest-path", short = 'm')]
+    pub manifest_path: Option<String>,
+}
+
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct ContractCosts {
+    /// Name of the contract
+    pub name: String,
+    /// Public or read-only function to evaluate
+    #[clap(long = "function")]
+    pub function: String,
+    /// Path to the arguments spec (lists, buffers and strings sizes), scaffolded if missing
+    #[clap(long = "args-spec")]
+    pub args_spec: String,
+    /// Write the cost curve as csv to this file
+    #[clap(long = "output", short = 'o')]
+    pub output: Option<String>,
+    /// Path to Clarinet.toml
+    #[clap(long = "manifest-path", short = 'm')]
+    pub manifest_path: Option<String>,
+}
+
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct CostsReport {
+    /// Path to the costs report
+    #[clap(default_value = "costs-reports.json")]
+    pub path: String,
+    /// Output the summary as json
+    #[clap(long = "json")]
+    pub json: bool,
+}
+
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct SnapshotContracts {
+    /// Names or identifiers of the contracts (default: all the contracts of the project)
+    pub contracts: Vec<String>,
+    /// Write the snapshot to this file instead of stdout
+    #[clap(long = "output", short = 'o')]
+    pub output: Option<String>,
+    /// Path to Clarinet.toml
+    #[clap(long = "manifest-path", short = 'm')]
+    pub manifest_path: Option<String>,
+    /// If specified, use this deployment file
+    #[clap(long = "deployment-plan-path", short = 'p')]
+    pub deployment_plan_path: Option<String>,
+    /// Execution backend of the contracts, wasm runs them compiled to Wasm (beta)
+    #[clap(long = "runtime", value_enum, default_value = "interpreter")]
+    pub runtime: Runtime,
+}
+
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct RetargetPrincipals {
+    /// Use the [network.principals] mapping of settings/Testnet.toml
+    #[clap(long = "testnet", conflicts_with = "mainnet")]
+    pub testnet: bool,
+    /// Use the [network.principals] mapping of settings/Mainnet.toml
+    #[clap(long = "mainnet", conflicts_with = "testnet")]
+    pub mainnet: bool,
+    /// Report the rewrites without modifying the files
+    #[clap(long = "dry-run")]
+    pub dry_run: bool,
+    /// Path to Clarinet.toml
+    #[clap(long = "manifest-path", short = 'm')]
+    pub manifest_path: Option<String>,
+}
+
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct ContractsGraph {
+    /// Output the graph in the graphviz dot format
+    #[clap(long = "dot")]
+    pub dot: bool,
+    /// Path to Clarinet.toml
+    #[clap(long = "manifest-path", short = 'm')]
+    pub manifest_path: Option<String>,
+}
+
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct FuzzContract {
+    /// Name or identifier of the contract
+    pub contract: String,
+    /// Number of calls
+    #[clap(long = "iterations", short = 'n', default_value = "100")]
+    pub iterations: u32,
+    /// Seed of the generator, to replay a run (default: random)
+    #[clap(long = "seed")]
+    pub seed: Option<u64>,
+    /// Post-condition mode of the calls, in deny mode the calls moving assets are reported
+    #[clap(
+        long = "post-condition-mode",
+        default_value = "allow",
+        value_parser = PostConditionMode::from_str
+    )]
+    pub post_condition_mode: PostConditionMode,
+    /// Path to Clarinet.toml
+    #[clap(long = "manifest-path", short = 'm')]
+    pub manifest_path: Option<String>,
+    /// If specified, use this deployment file
+    #[clap(long = "deployment-plan-path", short = 'p')]
+    pub deployment_plan_path: Option<String>,
+    /// Execution backend of the contracts, wasm runs them compiled to Wasm (beta)
+    #[clap(long = "runtime", value_enum, default_value = "interpreter")]
+    pub runtime: Runtime,
+}
+
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct ExportInterfaces {
+    /// Directory of the generated files, relative to the project root
+    #[clap(long = "output-dir", short = 'o', default_value = "interfaces")]
+    pub output_dir: String,
+    /// Also generate typed call wrappers for @stacks/transactions
+    #[clap(long = "typescript", aliases = &["ts"])]
+    pub typescript: bool,
+    /// Path to Clarinet.toml
+    #[clap(long = "manifest-path", short = 'm')]
+    pub manifest_path: Option<String>,
+}
+
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct DiffSnapshots {
+    /// Path to the previous snapshot
+    pub previous: String,
+    /// Path to the new snapshot
+    pub new: String,
+}
+
 #[derive(Parser, PartialEq, Clone, Debug)]
 struct AddRequirement {
     /// Contract id (ex. "SP2PABAF9FTAJYNFZH93XENAJ8FVY99RRM50D2JG9.nft-trait")
     pub contract_id: String,
+    /// Only add the contract, without the contracts it depends on
+    #[clap(long = "no-transitive")]
+    pub no_transitive: bool,
+    /// Path to Clarinet.toml
+    #[clap(long = "manifest-path", short = 'm')]
+    pub manifest_path: Option<String>,
+}
+
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct NewAccount {
+    /// Account's name
+    pub name: String,
+    /// Number of words of the mnemonic (12 or 24)
+    #[clap(long = "words", default_value = "24")]
+    pub words: usize,
+    /// Derivation path (default: m/44'/5757'/0'/0/0)
+    #[clap(long = "derivation")]
+    pub derivation: Option<String>,
+}
+
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct ImportAccount {
+    /// Account's name
+    pub name: String,
+    /// Import a hex encoded secret key instead of a mnemonic
+    #[clap(long = "secret-key")]
+    pub secret_key: bool,
+    /// Derivation path (default: m/44'/5757'/0'/0/0)
+    #[clap(long = "derivation", conflicts_with = "secret_key")]
+    pub derivation: Option<String>,
+}
+
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct ShowAccount {
+    /// Account's name
+    pub name: String,
+    /// Display the mnemonic or secret key of the account
+    #[clap(long = "reveal")]
+    pub reveal: bool,
+}
+
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct StructuredData {
+    /// Name of the domain
+    #[clap(long = "domain-name")]
+    pub domain_name: String,
+    /// Version of the domain
+    #[clap(long = "domain-version")]
+    pub domain_version: String,
+    /// Chain id of the domain (default: 1 with --mainnet, 2147483648 otherwise)
+    #[clap(long = "chain-id")]
+    pub chain_id: Option<u32>,
+    /// Message, as a Clarity expression (ie: '{ amount: u100, recipient: tx-sender }')
+    #[clap(long = "message")]
+    pub message: String,
+    /// Use the testnet chain id, and the accounts of settings/Testnet.toml when signing
+    #[clap(long = "testnet", conflicts_with = "mainnet")]
+    pub testnet: bool,
+    /// Use the mainnet chain id, and the accounts of settings/Mainnet.toml when signing
+    #[clap(long = "mainnet", conflicts_with = "testnet")]
+    pub mainnet: bool,
+}
+
+impl StructuredData {
+    fn network(&self) -> StacksNetwork {
+        match (self.testnet, self.mainnet) {
+            (true, _) => StacksNetwork::Testnet,
+            (_, true) => StacksNetwork::Mainnet,
+            _ => StacksNetwork::Devnet,
+        }
+    }
+
+    fn chain_id(&self) -> u32 {
+        self.chain_id.unwrap_or(match self.network() {
+            StacksNetwork::Mainnet => CHAIN_ID_MAINNET,
+            _ => CHAIN_ID_TESTNET,
+        })
+    }
+}
+
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct SignStructuredData {
+    #[clap(flatten)]
+    pub data: StructuredData,
+    /// Name of the account signing the message (default: deployer)
+    #[clap(long = "account", default_value = "deployer")]
+    pub account: String,
     /// Path to Clarinet.toml
     #[clap(long = "manifest-path", short = 'm')]
     pub manifest_path: Option<String>,
 }
 
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct VerifyStructuredData {
+    #[clap(flatten)]
+    pub data: StructuredData,
+    /// Hex encoded signature (RSV)
+    #[clap(long = "signature")]
+    pub signature: String,
+    /// Hex encoded public key of the signer
+    #[clap(long = "public-key")]
+    pub public_key: String,
+}
+
 #[derive(Parser, PartialEq, Clone, Debug)]
 struct CheckDeployments {
     /// Path to Clarinet.toml
@@ -193,6 +813,16 @@ struct CheckDeployments {
     pub manifest_path: Option<String>,
 }
 
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct CompareRuntimes {
+    /// Path to Clarinet.toml
+    #[clap(long = "manifest-path", short = 'm')]
+    pub manifest_path: Option<String>,
+    /// Path to the simnet deployment plan (default: deployments/default.simnet-plan.yaml)
+    #[clap(long = "deployment-plan-path", short = 'p')]
+    pub deployment_plan_path: Option<String>,
+}
+
 #[derive(Parser, PartialEq, Clone, Debug)]
 struct GenerateDeployment {
     /// Generate a deployment file for simnet environments (console, tests)
@@ -270,6 +900,14 @@ struct GenerateDeployment {
         long = "manual-cost"
     )]
     pub manual_cost: bool,
+    /// Multiply the estimated costs by this factor
+    #[clap(
+        long = "fee-multiplier",
+        default_value = "1.0",
+        value_parser = parse_fee_multiplier,
+        conflicts_with = "manual_cost"
+    )]
+    pub fee_multiplier: f64,
 }
 
 #[derive(Parser, PartialEq, Clone, Debug)]
@@ -327,54 +965,241 @@ struct ApplyDeployment {
         conflicts_with = "use_on_disk_deployment_plan"
     )]
     pub use_computed_deployment_plan: bool,
+    /// Variables file resolving the {{ name }} placeholders of the deployment plan, can be
+    /// repeated, the last files overriding the values of the first ones
+    #[clap(long = "vars", requires = "deployment_plan_path")]
+    pub vars: Vec<String>,
+    /// Sign the transactions without broadcasting them, and write them to this json file
+    #[clap(long = "dry-run", value_name = "PATH")]
+    pub dry_run: Option<String>,
 }
 
 #[derive(Parser, PartialEq, Clone, Debug)]
-struct Console {
+struct DeploymentNonces {
+    /// Use the accounts of settings/Testnet.toml (default: settings/Devnet.toml)
+    #[clap(long = "testnet", conflicts_with = "mainnet")]
+    pub testnet: bool,
+    /// Use the accounts of settings/Mainnet.toml
+    #[clap(long = "mainnet", conflicts_with = "testnet")]
+    pub mainnet: bool,
+    /// Only report the account with this name or address
+    #[clap(long = "account")]
+    pub account: Option<String>,
+    /// URL of the Stacks API reporting the mempool nonces
+    #[clap(long = "api-url")]
+    pub api_url: Option<String>,
+    /// Broadcast transactions filling the missing nonces that block the mempool
+    #[clap(long = "cancel-stuck")]
+    pub cancel_stuck: bool,
+    /// Fee of the cancel transactions, in µSTX
+    #[clap(long = "fee", default_value = "1000")]
+    pub fee: u64,
     /// Path to Clarinet.toml
     #[clap(long = "manifest-path", short = 'm')]
     pub manifest_path: Option<String>,
-    /// If specified, use this deployment file
-    #[clap(long = "deployment-plan-path", short = 'p')]
-    pub deployment_plan_path: Option<String>,
-    /// Use on disk deployment plan (prevent updates computing)
-    #[clap(
-        long = "use-on-disk-deployment-plan",
-        short = 'd',
-        conflicts_with = "use_computed_deployment_plan"
-    )]
-    pub use_on_disk_deployment_plan: bool,
-    /// Use computed deployment plan (will overwrite on disk version if any update)
-    #[clap(
-        long = "use-computed-deployment-plan",
-        short = 'c',
-        conflicts_with = "use_on_disk_deployment_plan"
-    )]
-    pub use_computed_deployment_plan: bool,
-    /// Allow the Clarity Wasm preview to run in parallel with the Clarity interpreter (beta)
-    #[clap(long = "enable-clarity-wasm")]
-    pub enable_clarity_wasm: bool,
 }
 
 #[derive(Parser, PartialEq, Clone, Debug)]
-struct DevnetStart {
-    /// Path to Clarinet.toml
-    #[clap(long = "manifest-path", short = 'm')]
-    pub manifest_path: Option<String>,
-    /// Display streams of logs instead of terminal UI dashboard
-    #[clap(long = "no-dashboard")]
-    pub no_dashboard: bool,
-    /// If specified, use this deployment file
-    #[clap(long = "deployment-plan-path", short = 'p')]
-    pub deployment_plan_path: Option<String>,
-    /// Use on disk deployment plan (prevent updates computing)
+struct ExportDeployment {
+    /// Export default deployment deployments/default.devnet-plan.yaml
     #[clap(
-        long = "use-on-disk-deployment-plan",
-        short = 'd',
-        conflicts_with = "use_computed_deployment_plan"
-    )]
-    pub use_on_disk_deployment_plan: bool,
-    /// Use computed deployment plan (will overwrite on disk version if any update)
+        long = "devnet",
+        conflicts_with = "deployment_plan_path",
+        conflicts_with = "testnet",
+        conflicts_with = "mainnet"
+    )]
+    pub devnet: bool,
+    /// Export default deployment deployments/default.testnet-plan.yaml
+    #[clap(
+        long = "testnet",
+        conflicts_with = "deployment_plan_path",
+        conflicts_with = "devnet",
+        conflicts_with = "mainnet"
+    )]
+    pub testnet: bool,
+    /// Export default deployment deployments/default.mainnet-plan.yaml
+    #[clap(
+        long = "mainnet",
+        conflicts_with = "deployment_plan_path",
+        conflicts_with = "testnet",
+        conflicts_with = "devnet"
+    )]
+    pub mainnet: bool,
+    /// Path to Clarinet.toml
+    #[clap(long = "manifest-path", short = 'm')]
+    pub manifest_path: Option<String>,
+    /// Export deployment plan specified
+    #[clap(
+        long = "deployment-plan-path",
+        short = 'p',
+        conflicts_with = "devnet",
+        conflicts_with = "testnet",
+        conflicts_with = "mainnet"
+    )]
+    pub deployment_plan_path: Option<String>,
+    /// Write the transactions to this file instead of stdout
+    #[clap(long = "output", short = 'o')]
+    pub output: Option<String>,
+    /// Do not fetch the accounts nonces from the stacks node (nonces start at 0)
+    #[clap(long = "offline")]
+    pub offline: bool,
+}
+
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct Console {
+    /// Path to Clarinet.toml
+    #[clap(long = "manifest-path", short = 'm')]
+    pub manifest_path: Option<String>,
+    /// If specified, use this deployment file
+    #[clap(long = "deployment-plan-path", short = 'p')]
+    pub deployment_plan_path: Option<String>,
+    /// Use on disk deployment plan (prevent updates computing)
+    #[clap(
+        long = "use-on-disk-deployment-plan",
+        short = 'd',
+        conflicts_with = "use_computed_deployment_plan"
+    )]
+    pub use_on_disk_deployment_plan: bool,
+    /// Use computed deployment plan (will overwrite on disk version if any update)
+    #[clap(
+        long = "use-computed-deployment-plan",
+        short = 'c',
+        conflicts_with = "use_on_disk_deployment_plan"
+    )]
+    pub use_computed_deployment_plan: bool,
+    /// Allow the Clarity Wasm preview to run in parallel with the Clarity interpreter (beta)
+    #[clap(long = "enable-clarity-wasm")]
+    pub enable_clarity_wasm: bool,
+    /// Execution backend of the contracts, wasm runs them compiled to Wasm (beta)
+    #[clap(
+        long = "runtime",
+        value_enum,
+        default_value = "interpreter",
+        conflicts_with = "enable_clarity_wasm"
+    )]
+    pub runtime: Runtime,
+}
+
+#[derive(clap::ValueEnum, PartialEq, Clone, Copy, Debug)]
+enum Runtime {
+    Interpreter,
+    Wasm,
+}
+
+impl Runtime {
+    /// Run the contracts of `manifest` with this backend
+    fn apply(&self, manifest: &mut ProjectManifest) {
+        manifest.repl_settings.clarity_wasm_mode = *self == Runtime::Wasm;
+    }
+}
+
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct Explorer {
+    /// Transaction id, address or contract identifier
+    pub resource: String,
+    /// Link to the explorer of devnet, using settings/Devnet.toml
+    #[clap(long = "devnet", conflicts_with = "mainnet")]
+    pub devnet: bool,
+    /// Link to the Mainnet explorer (default: Testnet)
+    #[clap(long = "mainnet", conflicts_with = "devnet")]
+    pub mainnet: bool,
+    /// Path to Clarinet.toml
+    #[clap(long = "manifest-path", short = 'm')]
+    pub manifest_path: Option<String>,
+}
+
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct RunTests {
+    /// Only run the tests whose name contains this string
+    #[clap(long = "filter")]
+    pub filter: Option<String>,
+    /// Write a JUnit XML report of the results to this file
+    #[clap(long = "junit")]
+    pub junit: Option<String>,
+    /// Run the tests from a snapshot saved with `clarinet simnet snapshot`
+    #[clap(long = "snapshot", conflicts_with = "deployment_plan_path")]
+    pub snapshot: Option<String>,
+    /// Path to Clarinet.toml
+    #[clap(long = "manifest-path", short = 'm')]
+    pub manifest_path: Option<String>,
+    /// If specified, use this deployment file
+    #[clap(long = "deployment-plan-path", short = 'p')]
+    pub deployment_plan_path: Option<String>,
+    /// Execution backend of the contracts, wasm runs them compiled to Wasm (beta)
+    #[clap(long = "runtime", value_enum, default_value = "interpreter")]
+    pub runtime: Runtime,
+}
+
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct Query {
+    /// Path to the queries file
+    #[clap(long = "file", short = 'f')]
+    pub file: String,
+    /// Query the Stacks API at this url instead of the session of the project
+    #[clap(long = "api-url")]
+    pub api_url: Option<String>,
+    /// Output the results as json instead of csv
+    #[clap(long = "json")]
+    pub json: bool,
+    /// Write the results to this file instead of stdout
+    #[clap(long = "output", short = 'o')]
+    pub output: Option<String>,
+    /// Path to Clarinet.toml
+    #[clap(long = "manifest-path", short = 'm')]
+    pub manifest_path: Option<String>,
+    /// If specified, use this deployment file
+    #[clap(long = "deployment-plan-path", short = 'p', conflicts_with = "api_url")]
+    pub deployment_plan_path: Option<String>,
+    /// Execution backend of the contracts, wasm runs them compiled to Wasm (beta)
+    #[clap(
+        long = "runtime",
+        value_enum,
+        default_value = "interpreter",
+        conflicts_with = "api_url"
+    )]
+    pub runtime: Runtime,
+}
+
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct ReplayTx {
+    /// Transaction id
+    pub txid: String,
+    /// Stacks API url (default: https://api.hiro.so, or https://api.testnet.hiro.so with --testnet)
+    #[clap(long = "api-url")]
+    pub api_url: Option<String>,
+    /// Replay a Testnet transaction
+    #[clap(long = "testnet")]
+    pub testnet: bool,
+    /// Step through the transaction with the debugger
+    #[clap(long = "debug")]
+    pub debug: bool,
+    /// Output the report as json
+    #[clap(long = "json", conflicts_with = "debug")]
+    pub json: bool,
+    /// Path to Clarinet.toml, the contracts retrieved are stored in the project cache
+    #[clap(long = "manifest-path", short = 'm')]
+    pub manifest_path: Option<String>,
+}
+
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct DevnetStart {
+    /// Path to Clarinet.toml
+    #[clap(long = "manifest-path", short = 'm')]
+    pub manifest_path: Option<String>,
+    /// Display streams of logs instead of terminal UI dashboard
+    #[clap(long = "no-dashboard")]
+    pub no_dashboard: bool,
+    /// If specified, use this deployment file
+    #[clap(long = "deployment-plan-path", short = 'p')]
+    pub deployment_plan_path: Option<String>,
+    /// Use on disk deployment plan (prevent updates computing)
+    #[clap(
+        long = "use-on-disk-deployment-plan",
+        short = 'd',
+        conflicts_with = "use_computed_deployment_plan"
+    )]
+    pub use_on_disk_deployment_plan: bool,
+    /// Use computed deployment plan (will overwrite on disk version if any update)
     #[clap(
         long = "use-computed-deployment-plan",
         short = 'c',
@@ -417,6 +1242,74 @@ struct Check {
     /// Allow the Clarity Wasm preview to run in parallel with the Clarity interpreter (beta)
     #[clap(long = "enable-clarity-wasm")]
     pub enable_clarity_wasm: bool,
+    /// Format of the diagnostics, json for editors and github for GitHub Actions annotations
+    #[clap(
+        long = "output",
+        value_enum,
+        default_value = "human",
+        conflicts_with = "file"
+    )]
+    pub output: CheckOutput,
+}
+
+#[derive(clap::ValueEnum, PartialEq, Clone, Copy, Debug)]
+enum CheckOutput {
+    Human,
+    Json,
+    Github,
+}
+
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct Serve {
+    /// Read requests from stdin and write responses to stdout, one JSON message per line
+    #[clap(long = "stdio")]
+    pub stdio: bool,
+}
+
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct Docs {
+    /// Path to Clarinet.toml
+    #[clap(long = "manifest-path", short = 'm')]
+    pub manifest_path: Option<String>,
+    /// Directory of the generated pages, relative to the project root
+    #[clap(long = "output-dir", short = 'o', default_value = "docs")]
+    pub output_dir: String,
+    /// Format of the generated pages, json for tools consuming the documentation
+    #[clap(long = "format", value_enum, default_value = "markdown")]
+    pub format: DocsFormat,
+}
+
+#[derive(clap::ValueEnum, PartialEq, Clone, Copy, Debug)]
+enum DocsFormat {
+    Markdown,
+    Json,
+}
+
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct RunNotebook {
+    /// Path to the notebook, a markdown file with ```clarity code blocks
+    pub file: String,
+    /// Format of the rendered notebook
+    #[clap(long = "format", value_enum, default_value = "html")]
+    pub format: NotebookFormat,
+    /// Write the rendered notebook to this file instead of stdout
+    #[clap(long = "output", short = 'o')]
+    pub output: Option<String>,
+    /// Path to Clarinet.toml
+    #[clap(long = "manifest-path", short = 'm')]
+    pub manifest_path: Option<String>,
+    /// If specified, use this deployment file
+    #[clap(long = "deployment-plan-path", short = 'p')]
+    pub deployment_plan_path: Option<String>,
+    /// Execution backend of the contracts, wasm runs them compiled to Wasm (beta)
+    #[clap(long = "runtime", value_enum, default_value = "interpreter")]
+    pub runtime: Runtime,
+}
+
+#[derive(clap::ValueEnum, PartialEq, Clone, Copy, Debug)]
+enum NotebookFormat {
+    Html,
+    Markdown,
 }
 
 #[derive(Parser, PartialEq, Clone, Debug)]
@@ -567,6 +1460,7 @@ pub fn main() {
                 project_id,
                 use_current_dir,
                 telemetry_enabled,
+                project_opts.template,
             ) {
                 Ok(changes) => changes,
                 Err(message) => {
@@ -615,7 +1509,7 @@ pub fn main() {
 
                 let default_deployment_path =
                     get_default_deployment_path(&manifest, &network).unwrap();
-                let (mut deployment, _) =
+                let (mut deployment, artifacts) =
                     match generate_default_deployment(&manifest, &network, cmd.no_batch) {
                         Ok(deployment) => deployment,
                         Err(message) => {
@@ -624,6 +1518,13 @@ pub fn main() {
                         }
                     };
 
+                if !display_contract_size_reports(&check_contract_sizes(
+                    &deployment,
+                    &artifacts.asts,
+                )) {
+                    std::process::exit(1);
+                }
+
                 if !cmd.manual_cost
                     && matches!(network, StacksNetwork::Testnet | StacksNetwork::Mainnet)
                 {
@@ -636,7 +1537,7 @@ pub fn main() {
                             std::process::exit(1);
                         }
                     };
-                    match update_deployment_costs(&mut deployment, priority) {
+                    match update_deployment_costs(&mut deployment, priority, cmd.fee_multiplier) {
                         Ok(_) => {}
                         Err(message) => {
                             eprintln!(
@@ -728,7 +1629,10 @@ pub fn main() {
                     }
                     (None, Some(deployment_plan_path)) => {
                         let deployment_path = get_absolute_deployment_path(&manifest, &deployment_plan_path).expect("unable to retrieve deployment");
-                        load_deployment(&manifest, &deployment_path)
+                        match cmd.vars.is_empty() {
+                            false => load_templated_deployment(&manifest, &deployment_path, &cmd.vars),
+                            true => load_deployment(&manifest, &deployment_path),
+                        }
                     }
                     (_, _) => unreachable!()
                 };
@@ -762,11 +1666,24 @@ pub fn main() {
                     }
                 }
 
+                let plugins_context = json!({
+                    "network": format!("{:?}", network).to_lowercase(),
+                    "deployment": deployment.to_specification_file(),
+                });
+                let dry_run_path = cmd.dry_run.clone();
+                let dry_run = dry_run_path.is_some();
+                if !dry_run {
+                    run_plugins_or_exit(
+                        &manifest,
+                        LifecycleHook::PreDeploy,
+                        plugins_context.clone(),
+                    );
+                }
+
                 let (command_tx, command_rx) = std::sync::mpsc::channel();
                 let (event_tx, event_rx) = std::sync::mpsc::channel();
-                let manifest_moved = manifest.clone();
 
-                if manifest.project.telemetry {
+                if manifest.project.telemetry && !dry_run {
                     #[cfg(feature = "telemetry")]
                     telemetry_report_event(DeveloperUsageEvent::ProtocolPublished(
                         DeveloperUsageDigest::new(
@@ -777,27 +1694,30 @@ pub fn main() {
                     ));
                 }
 
-                let transaction_trackers = if cmd.no_dashboard {
+                let explorer_links = get_explorer_links(&manifest, &network);
+                let no_dashboard = cmd.no_dashboard || dry_run;
+                let transaction_trackers = if no_dashboard {
                     vec![]
                 } else {
                     get_initial_transactions_trackers(&deployment)
                 };
-                let network_moved = network.clone();
+                let mut network_manifest =
+                    load_network_manifest_or_exit(&manifest, &network.get_networks());
+                add_keychain_signers_or_exit(&mut network_manifest, &deployment);
+                // transactions posted are recorded, so that an interrupted deployment resumes
+                let mut progress_location = manifest.project.cache_location.clone();
+                let progress_file = format!("deployments/{:?}-progress.json", network);
+                if let Err(message) = progress_location.append_path(&progress_file.to_lowercase()) {
+                    eprintln!("{}", format_err!(message));
+                    process::exit(1);
+                }
+                let step_manifest = manifest.clone();
+                let step_network = plugins_context["network"].clone();
+                let before_step: DeploymentStepHook = Box::new(move |mut context| {
+                    context["network"] = step_network.clone();
+                    run_plugins(&step_manifest, LifecycleHook::PreDeployStep, context)
+                });
                 std::thread::spawn(move || {
-                    let manifest = manifest_moved;
-                    let res = NetworkManifest::from_project_manifest_location(
-                        &manifest.location,
-                        &network_moved.get_networks(),
-                        Some(&manifest.project.cache_location),
-                        None,
-                    );
-                    let network_manifest = match res {
-                        Ok(network_manifest) => network_manifest,
-                        Err(e) => {
-                            let _ = event_tx.send(DeploymentEvent::Interrupted(e));
-                            return;
-                        }
-                    };
                     apply_on_chain_deployment(
                         network_manifest,
                         deployment,
@@ -806,12 +1726,17 @@ pub fn main() {
                         true,
                         None,
                         None,
+                        Some(progress_location),
+                        Some(before_step),
                     );
                 });
 
-                let _ = command_tx.send(DeploymentCommand::Start);
+                let _ = command_tx.send(match dry_run {
+                    true => DeploymentCommand::DryRun,
+                    false => DeploymentCommand::Start,
+                });
 
-                if cmd.no_dashboard {
+                if no_dashboard {
                     loop {
                         let cmd = match event_rx.recv() {
                             Ok(cmd) => cmd,
@@ -828,13 +1753,42 @@ pub fn main() {
                             }
                             DeploymentEvent::TransactionUpdate(update) => {
                                 println!("{} {:?} {}", blue!("➡"), update.status, update.name);
+                                if let (TransactionStatus::Broadcasted(_, txid), Some(links)) =
+                                    (&update.status, &explorer_links)
+                                {
+                                    println!("  {}", links.transaction(txid));
+                                }
+                            }
+                            DeploymentEvent::TransactionsRecorded(transactions) => {
+                                let path = dry_run_path.as_ref().unwrap();
+                                let content = serde_json::to_string_pretty(&transactions)
+                                    .expect("unable to serialize transactions");
+                                if let Err(e) = fs::write(path, content) {
+                                    eprintln!(
+                                        "{}",
+                                        format_err!(format!("unable to write {}: {}", path, e))
+                                    );
+                                    process::exit(1);
+                                }
+                                println!(
+                                    "{} {} recorded in {}, nothing was broadcasted",
+                                    green!("✔"),
+                                    pluralize!(transactions.len(), "transaction"),
+                                    path
+                                );
                             }
+                            DeploymentEvent::DeploymentCompleted if dry_run => break,
                             DeploymentEvent::DeploymentCompleted => {
                                 println!(
                                     "{} Transactions successfully confirmed on {:?}",
                                     green!("✔"),
                                     network
                                 );
+                                run_plugins_or_exit(
+                                    &manifest,
+                                    LifecycleHook::PostDeploy,
+                                    plugins_context,
+                                );
                                 break;
                             }
                         }
@@ -842,17 +1796,88 @@ pub fn main() {
                 } else {
                     let res = deployments::start_ui(&node_url, event_rx, transaction_trackers);
                     match res {
-                        Ok(()) => println!(
-                            "{} Transactions successfully confirmed on {:?}",
-                            green!("✔"),
-                            network
-                        ),
+                        Ok(()) => {
+                            println!(
+                                "{} Transactions successfully confirmed on {:?}",
+                                green!("✔"),
+                                network
+                            );
+                            run_plugins_or_exit(
+                                &manifest,
+                                LifecycleHook::PostDeploy,
+                                plugins_context,
+                            );
+                        }
                         Err(message) => {
                             eprintln!("{} Error publishing transactions: {}", red!("x"), message)
                         }
                     }
                 }
             }
+            Deployments::Nonces(cmd) => deployment_nonces(cmd),
+            Deployments::CompareRuntimes(cmd) => compare_runtimes(cmd),
+            Deployments::ExportDeployment(cmd) => {
+                let manifest = load_manifest_or_exit(cmd.manifest_path);
+
+                let deployment_location = match (
+                    cmd.devnet,
+                    cmd.testnet,
+                    cmd.mainnet,
+                    cmd.deployment_plan_path,
+                ) {
+                    (_, _, _, Some(path)) => get_absolute_deployment_path(&manifest, &path),
+                    (true, _, _, _) => get_default_deployment_path(&manifest, &StacksNetwork::Devnet),
+                    (_, true, _, _) => {
+                        get_default_deployment_path(&manifest, &StacksNetwork::Testnet)
+                    }
+                    (_, _, true, _) => {
+                        get_default_deployment_path(&manifest, &StacksNetwork::Mainnet)
+                    }
+                    _ => Err(format!("{}: a flag `--devnet`, `--testnet`, `--mainnet` or `--deployment-plan-path=path/to/yaml` should be provided.", yellow!("Command usage"))),
+                };
+                let deployment = match deployment_location
+                    .and_then(|location| load_deployment(&manifest, &location))
+                {
+                    Ok(deployment) => deployment,
+                    Err(message) => {
+                        eprintln!("{}", format_err!(message));
+                        process::exit(1);
+                    }
+                };
+
+                let network_manifest =
+                    load_network_manifest_or_exit(&manifest, &deployment.network.get_networks());
+
+                let transactions = match export_unsigned_transactions(
+                    &network_manifest,
+                    &deployment,
+                    !cmd.offline,
+                ) {
+                    Ok(transactions) => transactions,
+                    Err(message) => {
+                        eprintln!("{}", format_err!(message));
+                        process::exit(1);
+                    }
+                };
+                let content = serde_json::to_string_pretty(&transactions)
+                    .expect("unable to serialize transactions");
+
+                match cmd.output {
+                    Some(output) => {
+                        if let Err(e) = fs::write(&output, content) {
+                            eprintln!("{} unable to write file {}: {}", red!("error:"), output, e);
+                            process::exit(1);
+                        }
+                        println!(
+                            "{} {} ({})",
+                            green!("Exported"),
+                            pluralize!(transactions.len(), "transaction"),
+                            output
+                        );
+                    }
+                    None => println!("{}", content),
+                }
+            }
         },
         Command::Chainhooks => {
             let message = "This command is deprecated. Use the chainhooks library instead (https://github.com/hirosystems/chainhook)";
@@ -867,6 +1892,7 @@ pub fn main() {
                     &manifest.location,
                     cmd.name,
                     None,
+                    cmd.template,
                     true,
                 ) {
                     Ok(changes) => changes,
@@ -914,23 +1940,85 @@ pub fn main() {
                     display_post_check_hint();
                 }
             }
+            Contracts::UpgradeContract(cmd) => upgrade_contract(cmd),
+            Contracts::DiffContracts(cmd) => diff_contracts(cmd),
+            Contracts::ContractCosts(cmd) => contract_costs(cmd),
+            Contracts::CostsReport(cmd) => costs_report(cmd),
+            Contracts::SnapshotContracts(cmd) => snapshot_contracts(cmd),
+            Contracts::DiffSnapshots(cmd) => diff_storage_snapshots(cmd),
+            Contracts::RetargetPrincipals(cmd) => retarget_principals(cmd),
+            Contracts::ContractsGraph(cmd) => contracts_graph(cmd),
+            Contracts::FuzzContract(cmd) => fuzz_contract_functions(cmd),
+            Contracts::ExportInterfaces(cmd) => export_interfaces(cmd),
         },
         Command::Requirements(subcommand) => match subcommand {
             Requirements::AddRequirement(cmd) => {
                 let manifest = load_manifest_or_exit(cmd.manifest_path);
 
+                let contracts_ids = if cmd.no_transitive {
+                    vec![cmd.contract_id.clone()]
+                } else {
+                    let contract_id = match QualifiedContractIdentifier::parse(&cmd.contract_id) {
+                        Ok(contract_id) => contract_id,
+                        Err(_) => {
+                            eprintln!(
+                                "{}",
+                                format_err!(format!(
+                                    "malformatted contract_id: {}",
+                                    cmd.contract_id
+                                ))
+                            );
+                            process::exit(1);
+                        }
+                    };
+                    let future = resolve_transitive_requirements(
+                        &contract_id,
+                        &manifest.project.cache_location,
+                        &None,
+                        None,
+                    );
+                    match hiro_system_kit::nestable_block_on(future) {
+                        Ok(contracts_ids) => {
+                            contracts_ids.iter().map(|id| id.to_string()).collect()
+                        }
+                        Err(message) => {
+                            eprintln!("{}", format_err!(message));
+                            process::exit(1);
+                        }
+                    }
+                };
+
+                let existing_requirements =
+                    manifest.project.requirements.clone().unwrap_or_default();
+                let requirements_to_add: Vec<RequirementConfig> = contracts_ids
+                    .into_iter()
+                    .filter(|contract_id| {
+                        !existing_requirements
+                            .iter()
+                            .any(|requirement| &requirement.contract_id == contract_id)
+                    })
+                    .map(|contract_id| RequirementConfig { contract_id })
+                    .collect();
+                if requirements_to_add.is_empty() {
+                    println!("{} already listed in Clarinet.toml", cmd.contract_id);
+                    process::exit(0);
+                }
+
                 let change = TOMLEdition {
                     comment: format!(
-                        "{} with requirement {}",
+                        "{} with {} {}",
                         yellow!("Updated Clarinet.toml"),
-                        green!(format!("{}", cmd.contract_id))
+                        pluralize!(requirements_to_add.len(), "requirement"),
+                        green!(requirements_to_add
+                            .iter()
+                            .map(|requirement| requirement.contract_id.as_str())
+                            .collect::<Vec<_>>()
+                            .join(", "))
                     ),
                     manifest_location: manifest.location.clone(),
                     contracts_to_rm: vec![],
                     contracts_to_add: HashMap::new(),
-                    requirements_to_add: vec![RequirementConfig {
-                        contract_id: cmd.contract_id.clone(),
-                    }],
+                    requirements_to_add,
                 };
                 if !execute_changes(vec![Changes::EditTOML(change)]) {
                     std::process::exit(1);
@@ -940,34 +2028,208 @@ pub fn main() {
                 }
             }
         },
-        Command::Console(cmd) => {
-            // Loop to handle `::reload` command
-            loop {
-                let manifest = load_manifest_or_warn(cmd.manifest_path.clone());
-
-                let mut terminal = match manifest {
-                    Some(ref manifest) => {
-                        let (deployment, _, artifacts) = load_deployment_and_artifacts_or_exit(
-                            manifest,
-                            &cmd.deployment_plan_path,
-                            cmd.use_on_disk_deployment_plan,
-                            cmd.use_computed_deployment_plan,
-                        );
-
-                        if !artifacts.success {
-                            let diags_digest =
-                                DiagnosticsDigest::new(&artifacts.diags, &deployment);
-                            if diags_digest.has_feedbacks() {
-                                println!("{}", diags_digest.message);
-                            }
-                            if diags_digest.errors > 0 {
-                                println!(
-                                    "{} {} detected",
-                                    red!("x"),
-                                    pluralize!(diags_digest.errors, "error")
-                                );
-                            }
-                            std::process::exit(1);
+        Command::Accounts(subcommand) => match subcommand {
+            Accounts::NewAccount(cmd) => {
+                let secret = match generate_mnemonic(cmd.words) {
+                    Ok(mnemonic) => AccountSecret::Mnemonic(mnemonic),
+                    Err(message) => {
+                        eprintln!("{}", format_err!(message));
+                        process::exit(1);
+                    }
+                };
+                create_keychain_account_or_exit(&cmd.name, &secret, cmd.derivation.as_deref());
+                println!(
+                    "{}",
+                    yellow!("Mnemonic (keep it safe, it is the only way to recover this account):")
+                );
+                println!("{}", secret.reveal());
+            }
+            Accounts::ImportAccount(cmd) => {
+                let prompt = match cmd.secret_key {
+                    true => "Secret key:",
+                    false => "Mnemonic:",
+                };
+                let buffer = read_hidden_line(prompt);
+                let secret = match parse_account_secret(&buffer, cmd.secret_key) {
+                    Ok(secret) => secret,
+                    Err(message) => {
+                        eprintln!("{}", format_err!(message));
+                        process::exit(1);
+                    }
+                };
+                create_keychain_account_or_exit(&cmd.name, &secret, cmd.derivation.as_deref());
+            }
+            Accounts::ListAccounts => {
+                let (accounts, warnings) = match KeychainAccount::list() {
+                    Ok(accounts) => accounts,
+                    Err(message) => {
+                        eprintln!("{}", format_err!(message));
+                        process::exit(1);
+                    }
+                };
+                for warning in warnings.iter() {
+                    eprintln!("{} {}", yellow!("!"), warning);
+                }
+                if accounts.is_empty() {
+                    println!("No account found, create one with `clarinet accounts new <name>`");
+                }
+                for account in accounts.iter() {
+                    let testnet_address = account
+                        .addresses
+                        .get("testnet")
+                        .map(|a| a.stx_address.as_str())
+                        .unwrap_or_default();
+                    println!(
+                        "{} ({}) {}",
+                        green!(&account.name),
+                        account.kind,
+                        testnet_address
+                    );
+                }
+            }
+            Accounts::ShowAccount(cmd) => {
+                let account = match KeychainAccount::load(&cmd.name) {
+                    Ok(account) => account,
+                    Err(message) => {
+                        eprintln!("{}", format_err!(message));
+                        process::exit(1);
+                    }
+                };
+                display_keychain_account(&account);
+                if cmd.reveal {
+                    let password = read_keychain_password(false);
+                    match account.decrypt(&password) {
+                        Ok(secret) => println!("{}: {}", secret.kind(), secret.reveal()),
+                        Err(message) => {
+                            eprintln!("{}", format_err!(message));
+                            process::exit(1);
+                        }
+                    }
+                }
+            }
+        },
+        Command::Sip018(subcommand) => match subcommand {
+            Sip018::Hash(cmd) => {
+                let (domain, message) = structured_data_or_exit(&cmd);
+                match sip018::structured_data_hash(&domain, &message) {
+                    Ok(hash) => println!("0x{}", bytes_to_hex(&hash)),
+                    Err(message) => {
+                        eprintln!("{}", format_err!(message));
+                        process::exit(1);
+                    }
+                }
+            }
+            Sip018::Sign(cmd) => {
+                let network = cmd.data.network();
+                let (domain, message) = structured_data_or_exit(&cmd.data);
+                let manifest = load_manifest_or_exit(cmd.manifest_path);
+                let network_manifest =
+                    load_network_manifest_or_exit(&manifest, &network.get_networks());
+                let Some(account) = network_manifest.accounts.get(&cmd.account) else {
+                    eprintln!(
+                        "{}",
+                        format_err!(format!("account '{}' not found", cmd.account))
+                    );
+                    process::exit(1);
+                };
+                let private_key = Secp256k1PrivateKey::from_hex(&account.secret_key_hex())
+                    .expect("unable to decode secret key");
+                let public_key = Secp256k1PublicKey::from_private(&private_key);
+                let hash = sip018::structured_data_hash(&domain, &message);
+                let signature = sip018::sign_structured_data(&domain, &message, &private_key);
+                match (hash, signature) {
+                    (Ok(hash), Ok(signature)) => {
+                        println!("signer: {}", account.stx_address);
+                        println!("public key: {}", public_key.to_hex());
+                        println!("hash: 0x{}", bytes_to_hex(&hash));
+                        println!("signature: 0x{}", bytes_to_hex(&signature));
+                    }
+                    (Err(message), _) | (_, Err(message)) => {
+                        eprintln!("{}", format_err!(message));
+                        process::exit(1);
+                    }
+                }
+            }
+            Sip018::Verify(cmd) => {
+                let (domain, message) = structured_data_or_exit(&cmd.data);
+                let signature = hex_bytes(cmd.signature.trim_start_matches("0x"))
+                    .map_err(|e| format!("unable to decode signature: {}", e));
+                let public_key =
+                    Secp256k1PublicKey::from_hex(cmd.public_key.trim_start_matches("0x"))
+                        .map_err(|e| format!("unable to decode public key: {}", e));
+                let result = signature.and_then(|signature| {
+                    sip018::verify_structured_data(&domain, &message, &signature, &public_key?)
+                });
+                match result {
+                    Ok(true) => println!("{}", green!("Signature is valid")),
+                    Ok(false) => {
+                        eprintln!("{}", format_err!("Signature is invalid"));
+                        process::exit(1);
+                    }
+                    Err(message) => {
+                        eprintln!("{}", format_err!(message));
+                        process::exit(1);
+                    }
+                }
+            }
+        },
+        Command::Explorer(cmd) => {
+            let network = match (cmd.devnet, cmd.mainnet) {
+                (true, _) => StacksNetwork::Devnet,
+                (_, true) => StacksNetwork::Mainnet,
+                _ => StacksNetwork::Testnet,
+            };
+            let links = match network {
+                StacksNetwork::Devnet => {
+                    let manifest = load_manifest_or_exit(cmd.manifest_path);
+                    get_explorer_links(&manifest, &network)
+                }
+                _ => ExplorerLinks::for_network(&network, None),
+            };
+            match links {
+                Some(links) => println!("{}", links.link(&cmd.resource)),
+                None => {
+                    eprintln!("{}", format_err!("unable to read settings/Devnet.toml"));
+                    process::exit(1);
+                }
+            }
+        }
+        Command::Query(cmd) => run_queries(cmd),
+        Command::ReplayTx(cmd) => replay_tx(cmd),
+        Command::Test(cmd) => run_clarity_tests(cmd),
+        Command::Docs(cmd) => generate_docs(cmd),
+        Command::Notebook(cmd) => run_notebook_file(cmd),
+        Command::Console(cmd) => {
+            // Loop to handle `::reload` command
+            loop {
+                let mut manifest = load_manifest_or_warn(cmd.manifest_path.clone());
+                if let Some(ref mut manifest) = manifest {
+                    cmd.runtime.apply(manifest);
+                }
+
+                let mut terminal = match manifest {
+                    Some(ref manifest) => {
+                        let (deployment, _, artifacts) = load_deployment_and_artifacts_or_exit(
+                            manifest,
+                            &cmd.deployment_plan_path,
+                            cmd.use_on_disk_deployment_plan,
+                            cmd.use_computed_deployment_plan,
+                        );
+
+                        if !artifacts.success {
+                            let diags_digest =
+                                DiagnosticsDigest::new(&artifacts.diags, &deployment);
+                            if diags_digest.has_feedbacks() {
+                                println!("{}", diags_digest.message);
+                            }
+                            if diags_digest.errors > 0 {
+                                println!(
+                                    "{} {} detected",
+                                    red!("x"),
+                                    pluralize!(diags_digest.errors, "error")
+                                );
+                            }
+                            std::process::exit(1);
                         }
 
                         if cmd.enable_clarity_wasm {
@@ -988,7 +2250,8 @@ pub fn main() {
                         }
                     }
                     None => {
-                        let settings = repl::SessionSettings::default();
+                        let mut settings = repl::SessionSettings::default();
+                        settings.repl_settings.clarity_wasm_mode = cmd.runtime == Runtime::Wasm;
                         if cmd.enable_clarity_wasm {
                             let mut settings_wasm = repl::SessionSettings::default();
                             settings_wasm.repl_settings.clarity_wasm_mode = true;
@@ -998,6 +2261,38 @@ pub fn main() {
                         }
                     }
                 };
+                if let Some(ref manifest) = manifest {
+                    let working_dir = manifest
+                        .location
+                        .get_project_root_location()
+                        .ok()
+                        .map(|location| PathBuf::from(location.to_string()));
+                    terminal.console_commands = ConsoleCommands::new(
+                        manifest.project.console_commands.clone(),
+                        working_dir,
+                    );
+                    // the transactions are exported for the accounts of settings/Devnet.toml
+                    if let Ok(network_manifest) = NetworkManifest::from_project_manifest_location(
+                        &manifest.location,
+                        &StacksNetwork::Devnet.get_networks(),
+                        Some(&manifest.project.cache_location),
+                        None,
+                    ) {
+                        let accounts = network_manifest.accounts;
+                        let mut nonces = HashMap::new();
+                        terminal.transaction_exporter =
+                            Some(Box::new(move |session: &mut repl::Session, input: &str| {
+                                let export = export_console_transaction(
+                                    session,
+                                    input,
+                                    &accounts,
+                                    &mut nonces,
+                                    &StacksNetwork::Devnet,
+                                )?;
+                                serde_json::to_string_pretty(&export).map_err(|e| e.to_string())
+                            }));
+                    }
+                }
                 let reload = terminal.start();
 
                 // Report telemetry
@@ -1105,7 +2400,12 @@ pub fn main() {
         }
         Command::Check(cmd) => {
             let manifest = load_manifest_or_exit(cmd.manifest_path);
-            let (deployment, _, artifacts) = load_deployment_and_artifacts_or_exit(
+            run_plugins_or_exit(
+                &manifest,
+                LifecycleHook::PreCheck,
+                json!({ "contracts": manifest.contracts.keys().collect::<Vec<_>>() }),
+            );
+            let (deployment, _, mut artifacts) = load_deployment_and_artifacts_or_exit(
                 &manifest,
                 &cmd.deployment_plan_path,
                 cmd.use_on_disk_deployment_plan,
@@ -1125,36 +2425,62 @@ pub fn main() {
             }
 
             let diags_digest = DiagnosticsDigest::new(&artifacts.diags, &deployment);
-            if diags_digest.has_feedbacks() {
-                println!("{}", diags_digest.message);
-            }
+            let human_output = cmd.output == CheckOutput::Human;
+            if human_output {
+                if diags_digest.has_feedbacks() {
+                    println!("{}", diags_digest.message);
+                }
 
-            if diags_digest.warnings > 0 {
-                println!(
-                    "{} {} detected",
-                    yellow!("!"),
-                    pluralize!(diags_digest.warnings, "warning")
-                );
+                if diags_digest.warnings > 0 {
+                    println!(
+                        "{} {} detected",
+                        yellow!("!"),
+                        pluralize!(diags_digest.warnings, "warning")
+                    );
+                }
+                if diags_digest.errors > 0 {
+                    println!(
+                        "{} {} detected",
+                        red!("x"),
+                        pluralize!(diags_digest.errors, "error")
+                    );
+                } else {
+                    println!(
+                        "{} {} checked",
+                        green!("✔"),
+                        pluralize!(diags_digest.contracts_checked, "contract"),
+                    );
+                }
             }
-            if diags_digest.errors > 0 {
-                println!(
-                    "{} {} detected",
-                    red!("x"),
-                    pluralize!(diags_digest.errors, "error")
-                );
-            } else {
-                println!(
-                    "{} {} checked",
-                    green!("✔"),
-                    pluralize!(diags_digest.contracts_checked, "contract"),
-                );
+            // the reports below are written on stderr
+            let contract_sizes_ok =
+                display_contract_size_reports(&check_contract_sizes(&deployment, &artifacts.asts));
+            if artifacts.success {
+                match ErrorCodesRegistry::from_session(&mut artifacts.session) {
+                    Ok(registry) => {
+                        for conflict in registry.conflicts().iter() {
+                            eprintln!("{}", format_warn!(conflict));
+                        }
+                    }
+                    Err(message) => eprintln!("{}", format_warn!(message)),
+                }
             }
-            let exit_code = match artifacts.success {
+            let exit_code = match artifacts.success && contract_sizes_ok {
                 true => 0,
                 false => 1,
             };
+            run_plugins_or_exit(
+                &manifest,
+                LifecycleHook::PostCheck,
+                json!({
+                    "success": artifacts.success,
+                    "contracts_checked": diags_digest.contracts_checked,
+                    "errors": diags_digest.errors,
+                    "warnings": diags_digest.warnings,
+                }),
+            );
 
-            if global_settings.enable_hints.unwrap_or(true) {
+            if human_output && global_settings.enable_hints.unwrap_or(true) {
                 display_post_check_hint();
             }
             if manifest.project.telemetry {
@@ -1163,6 +2489,26 @@ pub fn main() {
                     DeveloperUsageDigest::new(&manifest.project.name, &manifest.project.authors),
                 ));
             }
+            if !human_output {
+                let reports = diagnostics_reports(&artifacts.diags, &deployment);
+                match cmd.output {
+                    CheckOutput::Json => {
+                        let output = json!({
+                            "success": exit_code == 0,
+                            "contracts_checked": diags_digest.contracts_checked,
+                            "errors": diags_digest.errors,
+                            "warnings": diags_digest.warnings,
+                            "diagnostics": reports,
+                        });
+                        println!("{}", serde_json::to_string_pretty(&output).unwrap());
+                    }
+                    _ => {
+                        for report in reports.iter() {
+                            println!("{}", report.to_github_annotation());
+                        }
+                    }
+                }
+            }
             std::process::exit(exit_code);
         }
         Command::Integrate(cmd) => {
@@ -1180,6 +2526,104 @@ pub fn main() {
                 process::exit(1);
             }
         },
+        Command::Hook(subcommand) => match subcommand {
+            Hook::Install(cmd) => {
+                let manifest = load_manifest_or_exit(cmd.manifest_path);
+                match install_git_hooks(&manifest, cmd.force) {
+                    Ok(installed) => {
+                        for path in installed.iter() {
+                            println!("{} {}", green!("Installed"), path);
+                        }
+                    }
+                    Err(message) => {
+                        eprintln!("{}", format_err!(message));
+                        process::exit(1);
+                    }
+                }
+            }
+            Hook::Run(cmd) => run_git_hook(cmd),
+        },
+        Command::Serve(cmd) => {
+            if !cmd.stdio {
+                eprintln!("{}", format_err!("only the --stdio transport is supported"));
+                process::exit(1);
+            }
+            if let Err(e) = super::serve::run_serve() {
+                eprintln!("{}", red!(e));
+                process::exit(1);
+            }
+        }
+        Command::Simnet(subcommand) => match subcommand {
+            Simnet::Serve(cmd) => {
+                let mut manifest = load_manifest_or_exit(cmd.manifest_path);
+                cmd.runtime.apply(&mut manifest);
+                let (deployment, _, artifacts) = load_deployment_and_artifacts_or_exit(
+                    &manifest,
+                    &cmd.deployment_plan_path,
+                    false,
+                    false,
+                );
+                if !artifacts.success {
+                    let diags_digest = DiagnosticsDigest::new(&artifacts.diags, &deployment);
+                    if diags_digest.has_feedbacks() {
+                        println!("{}", diags_digest.message);
+                    }
+                    process::exit(1);
+                }
+                println!(
+                    "{} Stacks API served on http://localhost:{}",
+                    green!("Simnet"),
+                    cmd.port
+                );
+                let api = SimnetApi::new(artifacts.session);
+                if let Err(e) = hiro_system_kit::nestable_block_on(start_simnet_api(api, cmd.port))
+                {
+                    eprintln!("{}", format_err!(e));
+                    process::exit(1);
+                }
+            }
+            Simnet::Snapshot(cmd) => {
+                let mut manifest = load_manifest_or_exit(cmd.manifest_path);
+                cmd.runtime.apply(&mut manifest);
+                let (deployment, _, artifacts) = load_deployment_and_artifacts_or_exit(
+                    &manifest,
+                    &cmd.deployment_plan_path,
+                    false,
+                    false,
+                );
+                if !artifacts.success {
+                    let diags_digest = DiagnosticsDigest::new(&artifacts.diags, &deployment);
+                    if diags_digest.has_feedbacks() {
+                        println!("{}", diags_digest.message);
+                    }
+                    process::exit(1);
+                }
+                let state = artifacts.session.save_state();
+                let content = match state.to_json() {
+                    Ok(content) => content,
+                    Err(message) => {
+                        eprintln!("{}", format_err!(message));
+                        process::exit(1);
+                    }
+                };
+                if let Err(e) = fs::write(&cmd.output, content) {
+                    eprintln!(
+                        "{} unable to write file {}: {}",
+                        red!("error:"),
+                        cmd.output,
+                        e
+                    );
+                    process::exit(1);
+                }
+                println!(
+                    "{} Saved {} and {} to {}",
+                    green!("✔"),
+                    pluralize!(state.contracts.len(), "contract"),
+                    pluralize!(state.interpreter.accounts.len(), "account"),
+                    cmd.output
+                );
+            }
+        },
         Command::Devnet(subcommand) => match subcommand {
             Devnet::Package(cmd) => {
                 let manifest = load_manifest_or_exit(cmd.manifest_path);
@@ -1189,6 +2633,8 @@ pub fn main() {
                 }
             }
             Devnet::DevnetStart(cmd) => devnet_start(cmd, global_settings),
+            Devnet::Btc(subcommand) => devnet_btc(subcommand),
+            Devnet::Load(cmd) => devnet_load(cmd),
         },
     };
 }
@@ -1219,7 +2665,10 @@ fn get_manifest_location_or_warn(path: Option<String>) -> Option<FileLocation> {
 fn load_manifest_or_exit(path: Option<String>) -> ProjectManifest {
     let manifest_location = get_manifest_location_or_exit(path);
     match ProjectManifest::from_location(&manifest_location) {
-        Ok(manifest) => manifest,
+        Ok(manifest) => {
+            print_warnings(&manifest.warnings);
+            manifest
+        }
         Err(message) => {
             eprintln!(
                 "{} syntax errors in Clarinet.toml\n{}",
@@ -1244,51 +2693,303 @@ fn load_manifest_or_warn(path: Option<String>) -> Option<ProjectManifest> {
                 process::exit(1);
             }
         };
+        print_warnings(&manifest.warnings);
         Some(manifest)
     } else {
         None
     }
 }
 
-fn load_deployment_and_artifacts_or_exit(
+fn load_network_manifest_or_exit(
     manifest: &ProjectManifest,
-    deployment_plan_path: &Option<String>,
-    force_on_disk: bool,
-    force_computed: bool,
-) -> (
-    DeploymentSpecification,
-    Option<String>,
-    DeploymentGenerationArtifacts,
+    networks: &(BitcoinNetwork, StacksNetwork),
+) -> NetworkManifest {
+    match NetworkManifest::from_project_manifest_location(
+        &manifest.location,
+        networks,
+        Some(&manifest.project.cache_location),
+        None,
+    ) {
+        Ok(network_manifest) => {
+            print_warnings(&network_manifest.warnings);
+            network_manifest
+        }
+        Err(message) => {
+            eprintln!("{}", format_err!(message));
+            process::exit(1);
+        }
+    }
+}
+
+/// Report the unknown keys of the settings files, they are ignored
+fn print_warnings(warnings: &[String]) {
+    for warning in warnings.iter() {
+        eprintln!("{}", format_warn!(warning));
+    }
+}
+
+fn run_plugins_or_exit(
+    manifest: &ProjectManifest,
+    hook: LifecycleHook,
+    context: serde_json::Value,
 ) {
-    let result = match deployment_plan_path {
-        None => {
-            let res = load_deployment_if_exists(
-                manifest,
-                &StacksNetwork::Simnet,
-                force_on_disk,
-                force_computed,
-            );
-            match res {
-                Some(Ok(deployment)) => {
-                    println!(
-                        "{} using deployments/default.simnet-plan.yaml",
-                        yellow!("note:")
-                    );
-                    let artifacts = setup_session_with_deployment(manifest, &deployment, None);
-                    Ok((deployment, None, artifacts))
+    if let Err(message) = run_plugins(manifest, hook, context) {
+        eprintln!("{}", format_err!(message));
+        process::exit(1);
+    }
+}
+
+fn parse_fee_multiplier(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(multiplier) if multiplier.is_finite() && multiplier > 0.0 => Ok(multiplier),
+        _ => Err(format!(
+            "'{}' is not a valid multiplier (a positive number is expected)",
+            value
+        )),
+    }
+}
+
+/// Unlock the keychain accounts sending transactions of `deployment` that are not
+/// declared in the settings of the network, e.g. the `keychain:<name>` senders of the plan
+fn add_keychain_signers_or_exit(
+    network_manifest: &mut NetworkManifest,
+    deployment: &DeploymentSpecification,
+) {
+    let mut password = None;
+    for batch in deployment.plan.batches.iter() {
+        for transaction in batch.transactions.iter() {
+            let sender = match transaction {
+                TransactionSpecification::ContractCall(tx) => tx.expected_sender.to_address(),
+                TransactionSpecification::ContractPublish(tx) => tx.expected_sender.to_address(),
+                TransactionSpecification::RequirementPublish(tx) => tx.remap_sender.to_address(),
+                TransactionSpecification::StxTransfer(tx) => tx.expected_sender.to_address(),
+                _ => continue,
+            };
+            if network_manifest
+                .accounts
+                .values()
+                .any(|account| account.stx_address == sender)
+            {
+                continue;
+            }
+            let account = match KeychainAccount::find_by_stx_address(&sender, &deployment.network) {
+                Ok(Some(account)) => account,
+                Ok(None) => continue,
+                Err(message) => {
+                    eprintln!("{}", format_err!(message));
+                    process::exit(1);
                 }
-                Some(Err(e)) => Err(format!(
-                    "loading deployments/default.simnet-plan.yaml failed with error: {}",
-                    e
-                )),
-                None => {
-                    match generate_default_deployment(manifest, &StacksNetwork::Simnet, false) {
-                        Ok((deployment, ast_artifacts)) if ast_artifacts.success => {
-                            let mut artifacts = setup_session_with_deployment(
-                                manifest,
-                                &deployment,
-                                Some(&ast_artifacts.asts),
-                            );
+            };
+            let password = password.get_or_insert_with(|| read_keychain_password(false));
+            let config = account.decrypt(password).and_then(|secret| {
+                account.to_account_config(&secret, &account.name, 0, &deployment.network)
+            });
+            match config {
+                Ok(config) => {
+                    network_manifest
+                        .accounts
+                        .insert(account.name.clone(), config);
+                }
+                Err(message) => {
+                    eprintln!("{}", format_err!(message));
+                    process::exit(1);
+                }
+            }
+        }
+    }
+}
+
+/// Read a line from stdin without echoing it when stdin is a terminal
+fn read_hidden_line(prompt: &str) -> String {
+    use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+    use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
+
+    println!("{}", yellow!(prompt));
+    if !atty::is(atty::Stream::Stdin) || enable_raw_mode().is_err() {
+        let mut buffer = String::new();
+        std::io::stdin().read_line(&mut buffer).unwrap();
+        return buffer.trim_end_matches(&['\r', '\n'][..]).to_string();
+    }
+    let mut buffer = String::new();
+    let interrupted = loop {
+        let Ok(Event::Key(key)) = event::read() else {
+            continue;
+        };
+        if key.kind == KeyEventKind::Release {
+            continue;
+        }
+        match key.code {
+            KeyCode::Enter => break false,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => break true,
+            KeyCode::Char(c) => buffer.push(c),
+            KeyCode::Backspace => {
+                buffer.pop();
+            }
+            _ => {}
+        }
+    };
+    let _ = disable_raw_mode();
+    println!();
+    if interrupted {
+        process::exit(130);
+    }
+    buffer
+}
+
+fn read_keychain_password(confirm: bool) -> String {
+    if let Ok(password) = env::var(KEYCHAIN_PASSWORD_ENV) {
+        return password;
+    }
+    let password = read_hidden_line("Keychain password:");
+    if confirm && read_hidden_line("Confirm keychain password:") != password {
+        eprintln!("{}", format_err!("passwords do not match"));
+        process::exit(1);
+    }
+    password
+}
+
+fn create_keychain_account_or_exit(name: &str, secret: &AccountSecret, derivation: Option<&str>) {
+    match KeychainAccount::exists(name) {
+        Ok(false) => {}
+        Ok(true) => {
+            eprintln!(
+                "{}",
+                format_err!(format!("keychain account '{}' already exists", name))
+            );
+            process::exit(1);
+        }
+        Err(message) => {
+            eprintln!("{}", format_err!(message));
+            process::exit(1);
+        }
+    }
+    let password = read_keychain_password(true);
+    let account = match KeychainAccount::new(name, secret, derivation, &password)
+        .and_then(|account| account.save().map(|_| account))
+    {
+        Ok(account) => account,
+        Err(message) => {
+            eprintln!("{}", format_err!(message));
+            process::exit(1);
+        }
+    };
+    println!("{} {}", green!("Created keychain account"), account.name);
+    display_keychain_account(&account);
+    println!(
+        "Reference it from settings/<Network>.toml with `keychain = \"{0}\"`, or from a deployment plan with `expected-sender: keychain:{0}`, unlocked with ${1}",
+        account.name, KEYCHAIN_PASSWORD_ENV
+    );
+}
+
+fn display_keychain_account(account: &KeychainAccount) {
+    println!("name: {}", account.name);
+    println!("kind: {}", account.kind);
+    if account.kind == "mnemonic" {
+        println!("derivation: {}", account.derivation);
+    }
+    for (network, addresses) in account.addresses.iter() {
+        println!(
+            "{}: {} / {}",
+            network, addresses.stx_address, addresses.btc_address
+        );
+    }
+}
+
+/// Build the domain and evaluate the message of a SIP-018 structured data.
+fn structured_data_or_exit(cmd: &StructuredData) -> (Value, Value) {
+    let domain = match sip018::make_domain(&cmd.domain_name, &cmd.domain_version, cmd.chain_id()) {
+        Ok(domain) => domain,
+        Err(message) => {
+            eprintln!("{}", format_err!(message));
+            process::exit(1);
+        }
+    };
+    let mut session = repl::Session::new(repl::SessionSettings::default());
+    let message = match session.eval(cmd.message.clone(), false) {
+        Ok(result) => match result.result {
+            EvaluationResult::Snippet(snippet_result) => snippet_result.result,
+            EvaluationResult::Contract(_) => {
+                eprintln!("{}", format_err!("message must be a Clarity value"));
+                process::exit(1);
+            }
+        },
+        Err(diagnostics) => {
+            let lines: Vec<String> = cmd.message.split('\n').map(|s| s.to_string()).collect();
+            for diagnostic in diagnostics.iter() {
+                for line in output_diagnostic(diagnostic, "message", &lines) {
+                    eprintln!("{}", line);
+                }
+            }
+            process::exit(1);
+        }
+    };
+    (domain, message)
+}
+
+/// Display the contracts close to the maximum transaction length, returns false
+/// if one of them exceeds it.
+fn display_contract_size_reports(reports: &[ContractSizeReport]) -> bool {
+    for report in reports.iter() {
+        let message = format!(
+            "contract {}: publish transaction is {} bytes ({}% of the {} bytes limit)",
+            report.contract_id,
+            report.transaction_len,
+            report.transaction_len * 100 / MAX_TRANSACTION_LEN,
+            MAX_TRANSACTION_LEN
+        );
+        match report.exceeds_limit() {
+            true => eprintln!("{}", format_err!(message)),
+            false => eprintln!("{}", format_warn!(message)),
+        }
+        if !report.largest_definitions.is_empty() {
+            eprintln!("largest definitions:");
+            for (name, len) in report.largest_definitions.iter() {
+                eprintln!("  {} ({} bytes)", name, len);
+            }
+        }
+    }
+    !reports.iter().any(|report| report.exceeds_limit())
+}
+
+fn load_deployment_and_artifacts_or_exit(
+    manifest: &ProjectManifest,
+    deployment_plan_path: &Option<String>,
+    force_on_disk: bool,
+    force_computed: bool,
+) -> (
+    DeploymentSpecification,
+    Option<String>,
+    DeploymentGenerationArtifacts,
+) {
+    let result = match deployment_plan_path {
+        None => {
+            let res = load_deployment_if_exists(
+                manifest,
+                &StacksNetwork::Simnet,
+                force_on_disk,
+                force_computed,
+            );
+            match res {
+                Some(Ok(deployment)) => {
+                    println!(
+                        "{} using deployments/default.simnet-plan.yaml",
+                        yellow!("note:")
+                    );
+                    let artifacts = setup_session_with_deployment(manifest, &deployment, None);
+                    Ok((deployment, None, artifacts))
+                }
+                Some(Err(e)) => Err(format!(
+                    "loading deployments/default.simnet-plan.yaml failed with error: {}",
+                    e
+                )),
+                None => {
+                    match generate_default_deployment(manifest, &StacksNetwork::Simnet, false) {
+                        Ok((deployment, ast_artifacts)) if ast_artifacts.success => {
+                            let mut artifacts = setup_session_with_deployment(
+                                manifest,
+                                &deployment,
+                                Some(&ast_artifacts.asts),
+                            );
                             for (contract_id, mut parser_diags) in ast_artifacts.diags.into_iter() {
                                 // Merge parser's diags with analysis' diags.
                                 if let Some(ref mut diags) = artifacts.diags.remove(&contract_id) {
@@ -1458,6 +3159,35 @@ fn load_deployment_if_exists(
     }
 }
 
+/// Resolve the variables of a plan template, then check the arguments of its contract-calls
+/// against the functions of the project contracts
+fn load_templated_deployment(
+    manifest: &ProjectManifest,
+    deployment_path: &FileLocation,
+    vars_paths: &[String],
+) -> Result<DeploymentSpecification, String> {
+    let cwd = env::current_dir().unwrap();
+    let vars_locations: Vec<FileLocation> = vars_paths
+        .iter()
+        .map(|vars_path| FileLocation::from_path(cwd.join(vars_path)))
+        .collect();
+    let variables = load_variables(&vars_locations).map_err(|e| format!("error: {}", e))?;
+    let deployment = load_deployment_with_variables(manifest, deployment_path, &variables)?;
+
+    let (simnet_deployment, _) =
+        generate_default_deployment(manifest, &StacksNetwork::Simnet, false)?;
+    let artifacts = setup_session_with_deployment(manifest, &simnet_deployment, None);
+    let errors = check_contract_calls_arguments(&deployment, &artifacts.analysis);
+    if !errors.is_empty() {
+        return Err(format!(
+            "error: invalid arguments in {}\n{}",
+            deployment_path,
+            errors.join("\n")
+        ));
+    }
+    Ok(deployment)
+}
+
 fn compare_wasm_artifacts(
     deployment: &DeploymentSpecification,
     artifacts: &DeploymentGenerationArtifacts,
@@ -1469,7 +3199,9 @@ fn compare_wasm_artifacts(
         let wasm_diags = wasm_artifacts.diags.get(contract);
         if diags != wasm_diags {
             print_warning = true;
-            println!("Diagnostics of contract {contract} differs between clarity and clarity-wasm");
+            eprintln!(
+                "Diagnostics of contract {contract} differs between clarity and clarity-wasm"
+            );
             dbg!(diags);
             dbg!(wasm_diags);
         }
@@ -1477,15 +3209,16 @@ fn compare_wasm_artifacts(
         let wasm_value = wasm_artifacts.results_values.get(contract);
         if (diags.is_some() && wasm_diags.is_some()) && (value != wasm_value) {
             print_warning = true;
-            println!(
+            eprintln!(
                 "Evaluation value of contract {contract} differs between clarity and clarity-wasm"
             );
             dbg!(value);
             dbg!(wasm_value);
         };
     }
+    // reported on stderr, stdout may be the machine readable output of the command
     if print_warning {
-        print_clarity_wasm_warning();
+        eprintln!("{}", clarity_wasm_warning());
     }
 }
 
@@ -1615,6 +3348,10 @@ fn execute_changes(changes: Vec<Changes>) -> bool {
                 }
                 for contract_name in options.contracts_to_rm.iter() {
                     config.contracts.remove(contract_name);
+                    config.contracts_dependencies.remove(contract_name);
+                    for dependencies in config.contracts_dependencies.values_mut() {
+                        dependencies.retain(|dependency| dependency != contract_name);
+                    }
                 }
 
                 shared_config = Some(config);
@@ -1786,123 +3523,1907 @@ fn display_deploy_hint() {
     display_hint_footer();
 }
 
-fn devnet_start(cmd: DevnetStart, global_settings: GlobalSettings) {
+fn generate_docs(cmd: Docs) {
     let manifest = load_manifest_or_exit(cmd.manifest_path);
-    println!("Computing deployment plan");
-    let result = match cmd.deployment_plan_path {
-        None => {
-            let res = if let Some(package) = cmd.package {
-                let package_file = match File::open(package) {
-                    Ok(file) => file,
-                    Err(_) => {
-                        eprintln!("{} package file not found", red!("error:"));
-                        std::process::exit(1);
-                    }
-                };
-                let deployment: ConfigurationPackage = serde_json::from_reader(package_file)
-                    .expect("error while reading deployment specification");
-                Some(Ok(deployment.deployment_plan))
-            } else {
-                load_deployment_if_exists(
-                    &manifest,
-                    &StacksNetwork::Devnet,
-                    cmd.use_on_disk_deployment_plan,
-                    cmd.use_computed_deployment_plan,
-                )
+    let (deployment, _) =
+        match generate_default_deployment(&manifest, &StacksNetwork::Simnet, false) {
+            Ok(deployment) => deployment,
+            Err(message) => {
+                eprintln!("{}", format_err!(message));
+                process::exit(1);
+            }
+        };
+    let mut artifacts = setup_session_with_deployment(&manifest, &deployment, None);
+    if !artifacts.success {
+        eprintln!(
+            "{}",
+            format_err!("unable to analyze the contracts, run `clarinet check` for details")
+        );
+        process::exit(1);
+    }
+
+    // contracts are documented in deployment order, requirements are left out
+    let mut contracts = vec![];
+    for batch in deployment.plan.batches.iter() {
+        for transaction in batch.transactions.iter() {
+            let TransactionSpecification::EmulatedContractPublish(tx) = transaction else {
+                continue;
             };
-            match res {
-                Some(Ok(deployment)) => {
-                    println!(
-                        "{} using existing deployments/default.devnet-plan.yaml",
-                        yellow!("note:")
-                    );
-                    // TODO(lgalabru): Think more about the desired DX.
-                    // Compute the latest version, display differences and propose overwrite?
-                    Ok(deployment)
-                }
-                Some(Err(e)) => Err(e),
-                None => {
-                    let default_deployment_path =
-                        get_default_deployment_path(&manifest, &StacksNetwork::Devnet).unwrap();
-                    let (deployment, _) =
-                        match generate_default_deployment(&manifest, &StacksNetwork::Devnet, false)
-                        {
-                            Ok(deployment) => deployment,
-                            Err(message) => {
-                                eprintln!("{}", red!(message));
-                                std::process::exit(1);
-                            }
-                        };
-                    let res = write_deployment(&deployment, &default_deployment_path, true);
-                    if let Err(message) = res {
-                        Err(message)
-                    } else {
-                        println!(
-                            "{} {}",
-                            green!("Generated file"),
-                            default_deployment_path.get_relative_location().unwrap()
-                        );
-                        Ok(deployment)
-                    }
-                }
+            if !manifest.contracts.contains_key(tx.contract_name.as_str()) {
+                continue;
             }
+            let contract_id = QualifiedContractIdentifier::new(
+                tx.emulated_sender.clone(),
+                tx.contract_name.clone(),
+            );
+            let Some(mut analysis) = artifacts.analysis.get(&contract_id).cloned() else {
+                continue;
+            };
+            contracts.push(build_contract_documentation(
+                &mut artifacts.session,
+                &mut analysis,
+                &tx.source,
+            ));
         }
-        Some(deployment_plan_path) => {
-            let deployment_path = get_absolute_deployment_path(&manifest, &deployment_plan_path)
-                .expect("unable to retrieve deployment");
-            load_deployment(&manifest, &deployment_path)
-        }
-    };
+    }
 
-    let deployment = match result {
-        Ok(deployment) => deployment,
-        Err(e) => {
-            eprintln!("{}", format_err!(e));
-            std::process::exit(1);
+    let mut output_dir = manifest.location.get_project_root_location().unwrap();
+    if let Err(message) = output_dir.append_path(&cmd.output_dir) {
+        eprintln!("{}", format_err!(message));
+        process::exit(1);
+    }
+    let mut pages = vec![];
+    match cmd.format {
+        DocsFormat::Markdown => {
+            pages.push((
+                "index.md".to_string(),
+                index_to_markdown(&manifest.project.name, &contracts),
+            ));
+            for contract in contracts.iter() {
+                pages.push((
+                    format!("{}.md", contract.contract_name()),
+                    contract.to_markdown(),
+                ));
+            }
         }
-    };
+        DocsFormat::Json => {
+            let index = index_to_json(&manifest.project.name, &contracts);
+            pages.push((
+                "index.json".to_string(),
+                serde_json::to_string_pretty(&index).unwrap(),
+            ));
+            for contract in contracts.iter() {
+                pages.push((
+                    format!("{}.json", contract.contract_name()),
+                    serde_json::to_string_pretty(&contract.to_json()).unwrap(),
+                ));
+            }
+        }
+    }
+    for (file_name, content) in pages.into_iter() {
+        let mut location = output_dir.clone();
+        location.append_path(&file_name).unwrap();
+        if let Err(message) = location.write_content(content.as_bytes()) {
+            eprintln!("{}", format_err!(message));
+            process::exit(1);
+        }
+    }
+    println!(
+        "{} Documentation of {} generated in {}",
+        green!("✔"),
+        pluralize!(contracts.len(), "contract"),
+        cmd.output_dir
+    );
+}
 
-    let orchestrator = match DevnetOrchestrator::new(manifest, None, None, true, cmd.no_dashboard) {
-        Ok(orchestrator) => orchestrator,
+fn run_notebook_file(cmd: RunNotebook) {
+    let source = match fs::read_to_string(&cmd.file) {
+        Ok(source) => source,
         Err(e) => {
-            eprintln!("{}", format_err!(e));
+            eprintln!("{} unable to read file {}: {}", red!("error:"), cmd.file, e);
             process::exit(1);
         }
     };
-
-    if orchestrator.manifest.project.telemetry {
-        #[cfg(feature = "telemetry")]
-        telemetry_report_event(DeveloperUsageEvent::DevnetExecuted(
-            DeveloperUsageDigest::new(
-                &orchestrator.manifest.project.name,
-                &orchestrator.manifest.project.authors,
-            ),
-        ));
+    let mut manifest = load_manifest_or_exit(cmd.manifest_path);
+    cmd.runtime.apply(&mut manifest);
+    let (deployment, _, mut artifacts) =
+        load_deployment_and_artifacts_or_exit(&manifest, &cmd.deployment_plan_path, false, false);
+    if !artifacts.success {
+        let diags_digest = DiagnosticsDigest::new(&artifacts.diags, &deployment);
+        if diags_digest.has_feedbacks() {
+            println!("{}", diags_digest.message);
+        }
+        process::exit(1);
     }
-    match start(orchestrator, deployment, None, !cmd.no_dashboard) {
-        Err(e) => {
-            eprintln!("{}", format_err!(e));
-            process::exit(1);
+
+    let cells = parse_notebook(&source);
+    let outputs = run_notebook(&mut artifacts.session, &cells);
+    let rendered = match cmd.format {
+        NotebookFormat::Html => {
+            let title = PathBuf::from(&cmd.file)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default();
+            notebook_to_html(&title, &cells, &outputs)
         }
-        Ok(_) => {
-            if global_settings.enable_hints.unwrap_or(true) {
-                display_deploy_hint();
+        NotebookFormat::Markdown => notebook_to_markdown(&cells, &outputs),
+    };
+    let failures = outputs
+        .iter()
+        .flatten()
+        .filter(|output| output.result.is_err())
+        .count();
+
+    match cmd.output {
+        Some(ref output) => {
+            if let Err(e) = fs::write(output, rendered) {
+                eprintln!("{} unable to write file {}: {}", red!("error:"), output, e);
+                process::exit(1);
             }
-            process::exit(0);
+            println!(
+                "{} Executed {} in {}",
+                green!("✔"),
+                pluralize!(outputs.iter().flatten().count(), "cell"),
+                output
+            );
         }
+        None => print!("{}", rendered),
+    }
+    if failures > 0 {
+        eprintln!("{} {} failed", red!("error:"), pluralize!(failures, "cell"));
+        process::exit(1);
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use clap_complete::generate;
-
-    use super::*;
-
-    #[test]
-    fn test_completion_for_shells() {
-        for shell in [
-            Shell::Bash,
+fn export_interfaces(cmd: ExportInterfaces) {
+    let manifest = load_manifest_or_exit(cmd.manifest_path);
+    let (deployment, _) =
+        match generate_default_deployment(&manifest, &StacksNetwork::Simnet, false) {
+            Ok(deployment) => deployment,
+            Err(message) => {
+                eprintln!("{}", format_err!(message));
+                process::exit(1);
+            }
+        };
+    let artifacts = setup_session_with_deployment(&manifest, &deployment, None);
+    if !artifacts.success {
+        eprintln!(
+            "{}",
+            format_err!("unable to analyze the contracts, run `clarinet check` for details")
+        );
+        process::exit(1);
+    }
+
+    let mut output_dir = manifest.location.get_project_root_location().unwrap();
+    if let Err(message) = output_dir.append_path(&cmd.output_dir) {
+        eprintln!("{}", format_err!(message));
+        process::exit(1);
+    }
+    // the testnet and mainnet addresses come from the plans of these networks, when generated
+    let mut network_deployments = vec![];
+    for (name, network) in [
+        ("testnet", StacksNetwork::Testnet),
+        ("mainnet", StacksNetwork::Mainnet),
+    ] {
+        let location = match get_default_deployment_path(&manifest, &network) {
+            Ok(location) if location.exists() => location,
+            _ => continue,
+        };
+        match load_deployment(&manifest, &location) {
+            Ok(network_deployment) => {
+                network_deployments.push((name, publish_order(&network_deployment)))
+            }
+            Err(message) => {
+                eprintln!("{}", format_err!(message));
+                process::exit(1);
+            }
+        }
+    }
+
+    // requirements are left out
+    let mut files = vec![];
+    for contract_id in publish_order(&deployment).iter() {
+        if !manifest.contracts.contains_key(contract_id.name.as_str()) {
+            continue;
+        }
+        let Some(analysis) = artifacts.analysis.get(contract_id) else {
+            continue;
+        };
+        match contract_interface_to_json(analysis) {
+            Ok(json) => files.push((format!("{}.json", contract_id.name), json)),
+            Err(message) => {
+                eprintln!("{}", format_err!(message));
+                process::exit(1);
+            }
+        }
+        if cmd.typescript {
+            let mut addresses = vec![("devnet".to_string(), contract_id.issuer.to_string())];
+            for (network, contracts) in network_deployments.iter() {
+                if let Some(id) = contracts.iter().find(|id| id.name == contract_id.name) {
+                    addresses.push((network.to_string(), id.issuer.to_string()));
+                }
+            }
+            files.push((
+                format!("{}.ts", contract_id.name),
+                contract_typescript_bindings(analysis, &addresses),
+            ));
+        }
+    }
+    for (file_name, content) in files.iter() {
+        let mut location = output_dir.clone();
+        location.append_path(file_name).unwrap();
+        if let Err(message) = location.write_content(content.as_bytes()) {
+            eprintln!("{}", format_err!(message));
+            process::exit(1);
+        }
+    }
+    println!(
+        "{} {} generated in {}",
+        green!("✔"),
+        pluralize!(files.len(), "file"),
+        cmd.output_dir
+    );
+}
+
+fn contract_costs(cmd: ContractCosts) {
+    let manifest = load_manifest_or_exit(cmd.manifest_path);
+    let (deployment, _) =
+        match generate_default_deployment(&manifest, &StacksNetwork::Simnet, false) {
+            Ok(deployment) => deployment,
+            Err(message) => {
+                eprintln!("{}", format_err!(message));
+                process::exit(1);
+            }
+        };
+    let artifacts = setup_session_with_deployment(&manifest, &deployment, None);
+    if !artifacts.success {
+        eprintln!(
+            "{}",
+            format_err!("unable to analyze the contracts, run `clarinet check` for details")
+        );
+        process::exit(1);
+    }
+
+    let Some((contract_id, analysis)) = artifacts
+        .analysis
+        .iter()
+        .find(|(contract_id, _)| contract_id.name.as_str() == cmd.name)
+    else {
+        let message = format!("contract {} not found", cmd.name);
+        eprintln!("{}", format_err!(message));
+        process::exit(1);
+    };
+    let function_type = analysis
+        .public_function_types
+        .iter()
+        .chain(analysis.read_only_function_types.iter())
+        .find(|(name, _)| name.as_str() == cmd.function)
+        .map(|(_, function_type)| function_type);
+    let function = match function_type {
+        Some(FunctionType::Fixed(function)) => function,
+        _ => {
+            let message = format!(
+                "public or read-only function {} not found in {}",
+                cmd.function, cmd.name
+            );
+            eprintln!("{}", format_err!(message));
+            process::exit(1);
+        }
+    };
+
+    let spec_location = FileLocation::from_path(env::current_dir().unwrap().join(&cmd.args_spec));
+    let spec = if spec_location.exists() {
+        match spec_location
+            .read_content()
+            .and_then(|content| ArgumentsSpec::from_yaml(&content))
+        {
+            Ok(spec) => spec,
+            Err(message) => {
+                eprintln!("{}", format_err!(message));
+                process::exit(1);
+            }
+        }
+    } else {
+        let spec = scaffold_arguments_spec(function);
+        if let Err(message) = spec
+            .to_yaml()
+            .and_then(|content| spec_location.write_content(content.as_bytes()))
+        {
+            eprintln!("{}", format_err!(message));
+            process::exit(1);
+        }
+        println!("{} Created {}", green!("✔"), cmd.args_spec);
+        spec
+    };
+    if let Err(errors) = validate_arguments_spec(function, &spec) {
+        for error in errors.iter() {
+            eprintln!("{}", format_err!(error));
+        }
+        process::exit(1);
+    }
+
+    let sender = artifacts.session.get_tx_sender();
+    let curve = compute_cost_curve(
+        &artifacts.session,
+        &contract_id.to_string(),
+        function,
+        &cmd.function,
+        &spec,
+        &sender,
+    );
+    println!("{}", curve.to_table());
+    if let Some(output) = cmd.output {
+        let location = FileLocation::from_path(env::current_dir().unwrap().join(&output));
+        if let Err(message) = location.write_content(curve.to_csv().as_bytes()) {
+            eprintln!("{}", format_err!(message));
+            process::exit(1);
+        }
+        println!("{} Cost curve written to {}", green!("✔"), output);
+    }
+}
+
+fn diff_contracts(cmd: DiffContracts) {
+    let manifest = load_manifest_or_exit(cmd.manifest_path);
+    let project_root = manifest.location.get_project_root_location().unwrap();
+
+    let rev_check = process::Command::new("git")
+        .args(["rev-parse", "--verify", "--quiet"])
+        .arg(format!("{}^{{commit}}", cmd.from))
+        .current_dir(project_root.to_string())
+        .output();
+    if !matches!(rev_check, Ok(ref output) if output.status.success()) {
+        let message = format!("unable to resolve git revision {}", cmd.from);
+        eprintln!("{}", format_err!(message));
+        process::exit(1);
+    }
+
+    let (deployment, _) =
+        match generate_default_deployment(&manifest, &StacksNetwork::Simnet, false) {
+            Ok(deployment) => deployment,
+            Err(message) => {
+                eprintln!("{}", format_err!(message));
+                process::exit(1);
+            }
+        };
+
+    // Same plan, with the sources of the contracts as of the `from` revision
+    let mut previous_deployment = deployment.clone();
+    let mut added_contracts = BTreeSet::new();
+    for batch in previous_deployment.plan.batches.iter_mut() {
+        batch.transactions.retain_mut(|transaction| {
+            let TransactionSpecification::EmulatedContractPublish(tx) = transaction else {
+                return true;
+            };
+            let Ok(relative_path) = tx.location.get_relative_path_from_base(&project_root) else {
+                return true;
+            };
+            let output = process::Command::new("git")
+                .arg("show")
+                .arg(format!("{}:./{}", cmd.from, relative_path))
+                .current_dir(project_root.to_string())
+                .output();
+            match output {
+                Ok(output) if output.status.success() => {
+                    tx.source = String::from_utf8_lossy(&output.stdout).to_string();
+                    true
+                }
+                _ => {
+                    added_contracts.insert(tx.contract_name.to_string());
+                    false
+                }
+            }
+        });
+    }
+
+    let previous_artifacts = setup_session_with_deployment(&manifest, &previous_deployment, None);
+    let artifacts = setup_session_with_deployment(&manifest, &deployment, None);
+    if !artifacts.success {
+        eprintln!(
+            "{}",
+            format_err!("unable to analyze the contracts, run `clarinet check` for details")
+        );
+        process::exit(1);
+    }
+
+    let mut diffs = BTreeMap::new();
+    for (contract_id, analysis) in artifacts.analysis.iter() {
+        let contract_name = contract_id.name.to_string();
+        if added_contracts.contains(&contract_name) {
+            diffs.insert(contract_name, ContractDiff::Added);
+            continue;
+        }
+        match previous_artifacts.analysis.get(contract_id) {
+            Some(previous_analysis) => {
+                let changes = diff_contract_interfaces(previous_analysis, analysis);
+                diffs.insert(contract_name, ContractDiff::Changed(changes));
+            }
+            None => eprintln!(
+                "{} unable to analyze {} as of {}, skipping",
+                yellow!("warning:"),
+                contract_name,
+                cmd.from
+            ),
+        }
+    }
+
+    // Contracts of the manifest as of the `from` revision, no longer in the project
+    let manifest_path = manifest
+        .location
+        .get_relative_path_from_base(&project_root)
+        .unwrap_or_else(|_| "Clarinet.toml".to_string());
+    let previous_manifest = process::Command::new("git")
+        .arg("show")
+        .arg(format!("{}:./{}", cmd.from, manifest_path))
+        .current_dir(project_root.to_string())
+        .output();
+    if let Ok(output) = previous_manifest {
+        let previous_contracts = toml::from_slice::<toml::Value>(&output.stdout)
+            .ok()
+            .and_then(|value| value.get("contracts").and_then(|c| c.as_table()).cloned())
+            .unwrap_or_default();
+        for contract_name in previous_contracts.keys() {
+            if !manifest.contracts.contains_key(contract_name) {
+                diffs.insert(contract_name.clone(), ContractDiff::Removed);
+            }
+        }
+    }
+
+    print!("{}", diffs_to_markdown(&diffs));
+}
+
+fn upgrade_contract(cmd: UpgradeContract) {
+    let manifest = load_manifest_or_exit(cmd.manifest_path);
+    let network = if cmd.testnet {
+        StacksNetwork::Testnet
+    } else if cmd.mainnet {
+        StacksNetwork::Mainnet
+    } else {
+        StacksNetwork::Devnet
+    };
+
+    let contract_config = match manifest.contracts.get(&cmd.name) {
+        Some(contract_config) => contract_config,
+        None => {
+            let message = format!("contract {} not found in Clarinet.toml", cmd.name);
+            eprintln!("{}", format_err!(message));
+            process::exit(1);
+        }
+    };
+    let new_name = cmd.new_name.unwrap_or(format!("{}-v2", cmd.name));
+    let contract_name = match ContractName::try_from(new_name.clone()) {
+        Ok(contract_name) => contract_name,
+        Err(_) => {
+            eprintln!(
+                "{}",
+                format_err!(format!("invalid contract name {}", new_name))
+            );
+            process::exit(1);
+        }
+    };
+    let location = FileLocation::from_path(env::current_dir().unwrap().join(&cmd.source_path));
+    let source = match location.read_content_as_utf8() {
+        Ok(source) => source,
+        Err(message) => {
+            eprintln!("{}", format_err!(message));
+            process::exit(1);
+        }
+    };
+    let mut upgrade = ContractUpgrade {
+        contract_name,
+        source,
+        location,
+        clarity_version: contract_config.clarity_version,
+        migration_calls: vec![],
+    };
+
+    let (deployment, _) = match generate_default_deployment(&manifest, &network, false) {
+        Ok(deployment) => deployment,
+        Err(message) => {
+            eprintln!("{}", format_err!(message));
+            process::exit(1);
+        }
+    };
+    let network_manifest = load_network_manifest_or_exit(&manifest, &network.get_networks());
+    let previous_contract_id = match deployment
+        .contracts
+        .keys()
+        .find(|contract_id| contract_id.name.as_str() == cmd.name)
+    {
+        Some(contract_id) => contract_id,
+        None => {
+            let message = format!(
+                "contract {} not found in {:?} deployment",
+                cmd.name, network
+            );
+            eprintln!("{}", format_err!(message));
+            process::exit(1);
+        }
+    };
+
+    // The upgrade is compared with the version of the contract deployed on the network
+    let deployed_source = match &deployment.stacks_node {
+        Some(node_url) => fetch_deployed_source(node_url, previous_contract_id),
+        None => Err(format!("no stacks node configured for {:?}", network)),
+    };
+    let deployed_source = match deployed_source {
+        Ok(source) => source,
+        Err(message) => {
+            let message = format!(
+                "{}\n{} must be deployed on {:?} before being upgraded",
+                message, previous_contract_id, network
+            );
+            eprintln!("{}", format_err!(message));
+            process::exit(1);
+        }
+    };
+
+    // Deploy the project in a simnet session, with the deployed version of the contract
+    let (mut simnet_deployment, _) =
+        match generate_default_deployment(&manifest, &StacksNetwork::Simnet, false) {
+            Ok(deployment) => deployment,
+            Err(message) => {
+                eprintln!("{}", format_err!(message));
+                process::exit(1);
+            }
+        };
+    use_deployed_source(&mut simnet_deployment, &cmd.name, &deployed_source);
+    let artifacts = setup_session_with_deployment(&manifest, &simnet_deployment, None);
+    if !artifacts.success {
+        eprintln!(
+            "{}",
+            format_err!("unable to deploy the current version of the contracts, run `clarinet check` for details")
+        );
+        process::exit(1);
+    }
+    let (simnet_contract_id, previous_analysis) = match artifacts
+        .analysis
+        .iter()
+        .find(|(contract_id, _)| contract_id.name.as_str() == cmd.name)
+    {
+        Some(entry) => entry,
+        None => {
+            let message = format!("unable to analyze contract {}", cmd.name);
+            eprintln!("{}", format_err!(message));
+            process::exit(1);
+        }
+    };
+    let simnet_deployer = simnet_contract_id.issuer.clone();
+
+    // Publish the new version alone to compare storage layouts
+    let publish_only = build_upgrade_deployment(
+        &simnet_deployment,
+        simnet_contract_id,
+        &upgrade,
+        &simnet_deployer,
+        0,
+    );
+    let simulation = simulate_upgrade_deployment(&mut artifacts.session.clone(), &publish_only);
+    let new_analysis = match simulation.new_contract_analysis {
+        Some(new_analysis) => new_analysis,
+        None => {
+            let reason = match simulation.steps.first().map(|step| &step.result) {
+                Some(Err(reason)) => reason.clone(),
+                _ => String::new(),
+            };
+            let message = format!("unable to deploy {}\n{}", new_name, reason);
+            eprintln!("{}", format_err!(message));
+            process::exit(1);
+        }
+    };
+
+    println!("{}", yellow!("Storage compatibility"));
+    let diffs = check_storage_compatibility(previous_analysis, &new_analysis);
+    if diffs.is_empty() {
+        println!("{} storage layout unchanged", green!("✔"));
+    }
+    for diff in diffs.iter() {
+        match diff.is_breaking() {
+            true => println!("{} {}", red!("x"), diff),
+            false => println!("{} {}", green!("+"), diff),
+        }
+    }
+    if diffs.iter().any(|diff| diff.is_breaking()) {
+        println!(
+            "{} Some data of {} can't be migrated as is. Continue? [y/N]",
+            yellow!("warning:"),
+            cmd.name
+        );
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer).unwrap();
+        if !answer.trim().eq_ignore_ascii_case("y") {
+            eprintln!("{} Upgrade aborted", yellow!("warning:"));
+            process::exit(0);
+        }
+    }
+
+    if cmd.migration_calls.is_empty() {
+        println!(
+            "Migration calls to perform once {} is published, as `<method> <args>...` (empty line to finish):",
+            new_name
+        );
+        loop {
+            let mut buffer = String::new();
+            std::io::stdin().read_line(&mut buffer).unwrap();
+            if buffer.trim().is_empty() {
+                break;
+            }
+            match MigrationCall::parse(&buffer) {
+                Ok(call) => upgrade.migration_calls.push(call),
+                Err(message) => println!("{}", format_err!(message)),
+            }
+        }
+    } else {
+        for call in cmd.migration_calls.iter() {
+            match MigrationCall::parse(call) {
+                Ok(call) => upgrade.migration_calls.push(call),
+                Err(message) => {
+                    eprintln!("{}", format_err!(message));
+                    process::exit(1);
+                }
+            }
+        }
+    }
+
+    // Simulate the upgrade end-to-end before writing the plan
+    println!("{}", yellow!("Simulation"));
+    let simnet_upgrade = build_upgrade_deployment(
+        &simnet_deployment,
+        simnet_contract_id,
+        &upgrade,
+        &simnet_deployer,
+        0,
+    );
+    let mut session = artifacts.session.clone();
+    let simulation = simulate_upgrade_deployment(&mut session, &simnet_upgrade);
+    for step in simulation.steps.iter() {
+        let status = match step.is_success() {
+            true => green!("✔"),
+            false => red!("x"),
+        };
+        match &step.result {
+            Ok(Some(value)) => println!("{} {}: {}", status, step.description, value),
+            Ok(None) => println!("{} {}", status, step.description),
+            Err(message) => println!("{} {}\n{}", status, step.description, message),
+        }
+    }
+    if !simulation.is_success() {
+        eprintln!("{}", format_err!("upgrade simulation failed"));
+        process::exit(1);
+    }
+
+    let upgrade_deployment = build_upgrade_deployment(
+        &deployment,
+        previous_contract_id,
+        &upgrade,
+        &previous_contract_id.issuer,
+        network_manifest.network.deployment_fee_rate,
+    );
+
+    let mut upgrade_deployment_path = manifest.location.get_project_root_location().unwrap();
+    upgrade_deployment_path
+        .append_path(&format!(
+            "deployments/upgrade-{}.{}-plan.yaml",
+            new_name,
+            match network {
+                StacksNetwork::Simnet => "simnet",
+                StacksNetwork::Devnet => "devnet",
+                StacksNetwork::Testnet => "testnet",
+                StacksNetwork::Mainnet => "mainnet",
+            }
+        ))
+        .unwrap();
+    if let Err(message) = write_deployment(&upgrade_deployment, &upgrade_deployment_path, true) {
+        eprintln!("{}", format_err!(message));
+        process::exit(1);
+    }
+    println!(
+        "{} {}",
+        green!("Generated file"),
+        upgrade_deployment_path.get_relative_location().unwrap()
+    );
+}
+
+fn get_explorer_links(
+    manifest: &ProjectManifest,
+    network: &StacksNetwork,
+) -> Option<ExplorerLinks> {
+    let devnet = match network {
+        StacksNetwork::Devnet => NetworkManifest::from_project_manifest_location(
+            &manifest.location,
+            &network.get_networks(),
+            Some(&manifest.project.cache_location),
+            None,
+        )
+        .ok()
+        .and_then(|network_manifest| network_manifest.devnet),
+        _ => None,
+    };
+    ExplorerLinks::for_network(network, devnet.as_ref())
+}
+
+/// Deploy the contracts of the `tests` directory of the project, after the deployment plan
+pub(crate) fn deploy_test_contracts(
+    manifest: &ProjectManifest,
+    session: &mut repl::Session,
+) -> Result<Vec<QualifiedContractIdentifier>, String> {
+    let project_root = manifest.location.get_project_root_location()?;
+    let tests_dir = PathBuf::from(project_root.to_string()).join("tests");
+    let Ok(entries) = fs::read_dir(&tests_dir) else {
+        return Ok(vec![]);
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().map(|ext| ext == "clar").unwrap_or(false))
+        .collect();
+    paths.sort();
+
+    let mut contracts_ids = vec![];
+    for path in paths.into_iter() {
+        let name = path.file_stem().unwrap().to_string_lossy().to_string();
+        let source = fs::read_to_string(&path)
+            .map_err(|e| format!("unable to read {}: {}", path.display(), e))?;
+        let contract = ClarityContract {
+            code_source: ClarityCodeSource::ContractInMemory(source),
+            name: name.clone(),
+            deployer: ContractDeployer::DefaultDeployer,
+            clarity_version: ClarityVersion::default_for_epoch(session.current_epoch),
+            epoch: session.current_epoch,
+        };
+        match session.deploy_contract(&contract, false, None) {
+            Ok(execution) => match execution.result {
+                EvaluationResult::Contract(result) => {
+                    contracts_ids.push(result.contract.analysis.contract_identifier)
+                }
+                EvaluationResult::Snippet(_) => unreachable!(),
+            },
+            Err(diagnostics) => {
+                let messages: Vec<String> = diagnostics.into_iter().map(|d| d.message).collect();
+                return Err(format!(
+                    "unable to deploy tests/{}.clar: {}",
+                    name,
+                    messages.join(", ")
+                ));
+            }
+        }
+    }
+    Ok(contracts_ids)
+}
+
+/// Session restored from a snapshot file, and the contracts of the project it holds
+fn load_snapshot(
+    manifest: &ProjectManifest,
+    path: &str,
+) -> Result<(repl::Session, Vec<QualifiedContractIdentifier>), String> {
+    let content = fs::read(path).map_err(|e| format!("unable to read {}: {}", path, e))?;
+    let state = SessionState::from_json(&content)?;
+    // the snapshot must hold the contracts of the project, as they are now
+    for (name, contract) in manifest.contracts.iter() {
+        let saved = state.contracts.iter().find(|saved| {
+            QualifiedContractIdentifier::parse(&saved.contract_id)
+                .map(|contract_id| contract_id.name.as_str() == name)
+                .unwrap_or(false)
+        });
+        let Some(saved) = saved else {
+            return Err(format!(
+                "contract {} is not in the snapshot {}, run `clarinet simnet snapshot` again",
+                name, path
+            ));
+        };
+        let source = match &contract.code_source {
+            ClarityCodeSource::ContractOnDisk(contract_path) => {
+                fs::read_to_string(contract_path)
+                    .map_err(|e| format!("unable to read {}: {}", contract_path.display(), e))?
+            }
+            ClarityCodeSource::ContractInMemory(code) => code.clone(),
+            ClarityCodeSource::Empty => continue,
+        };
+        if source != saved.code {
+            return Err(format!(
+                "contract {} changed since the snapshot {} was taken, run `clarinet simnet snapshot` again",
+                name, path
+            ));
+        }
+    }
+    let settings = initiate_session_from_manifest(manifest).settings;
+    let session = repl::Session::from_state(settings, &state)?;
+    let contracts_ids = session
+        .contracts
+        .keys()
+        .filter(|contract_id| manifest.contracts.contains_key(contract_id.name.as_str()))
+        .cloned()
+        .collect();
+    Ok((session, contracts_ids))
+}
+
+fn run_clarity_tests(cmd: RunTests) {
+    let mut manifest = load_manifest_or_exit(cmd.manifest_path);
+    cmd.runtime.apply(&mut manifest);
+    let (mut session, mut contracts_ids) = match cmd.snapshot {
+        Some(ref snapshot) => match load_snapshot(&manifest, snapshot) {
+            Ok(restored) => restored,
+            Err(message) => {
+                eprintln!("{}", format_err!(message));
+                process::exit(1);
+            }
+        },
+        None => {
+            let (deployment, _, artifacts) = load_deployment_and_artifacts_or_exit(
+                &manifest,
+                &cmd.deployment_plan_path,
+                false,
+                false,
+            );
+            if !artifacts.success {
+                eprintln!(
+                    "{}",
+                    format_err!(
+                        "unable to analyze the contracts, run `clarinet check` for details"
+                    )
+                );
+                process::exit(1);
+            }
+            let contracts_ids: Vec<QualifiedContractIdentifier> = publish_order(&deployment)
+                .into_iter()
+                .filter(|contract_id| manifest.contracts.contains_key(contract_id.name.as_str()))
+                .collect();
+            (artifacts.session, contracts_ids)
+        }
+    };
+    match deploy_test_contracts(&manifest, &mut session) {
+        Ok(mut test_contracts_ids) => contracts_ids.append(&mut test_contracts_ids),
+        Err(message) => {
+            eprintln!("{}", format_err!(message));
+            process::exit(1);
+        }
+    }
+
+    let mut tests = discover_tests(&session, &contracts_ids);
+    if let Some(ref filter) = cmd.filter {
+        tests.retain(|test| test.name.contains(filter.as_str()));
+    }
+    if tests.is_empty() {
+        println!("{} no test found", yellow!("!"));
+        return;
+    }
+
+    let results = run_tests(&session, tests);
+    for result in results.iter() {
+        let name = format!("{}::{}", result.test.contract_id.name, result.test.name);
+        match &result.outcome {
+            TestOutcome::Passed => println!("{} {}", green!("✔"), name),
+            TestOutcome::Failed(message) => println!("{} {}: {}", red!("x"), name, message),
+        }
+    }
+    let failures = results.iter().filter(|result| !result.passed()).count();
+    println!(
+        "\n{} passed, {} failed",
+        pluralize!(results.len() - failures, "test"),
+        failures
+    );
+
+    if let Some(ref junit) = cmd.junit {
+        if let Err(e) = fs::write(junit, to_junit_xml(&results)) {
+            eprintln!("{} unable to write file {}: {}", red!("error:"), junit, e);
+            process::exit(1);
+        }
+        println!("{} Created {}", green!("✔"), junit);
+    }
+    let tests: Vec<serde_json::Value> = results
+        .iter()
+        .map(|result| {
+            let message = match &result.outcome {
+                TestOutcome::Passed => None,
+                TestOutcome::Failed(message) => Some(message),
+            };
+            json!({
+                "contract_id": result.test.contract_id.to_string(),
+                "name": result.test.name,
+                "passed": result.passed(),
+                "message": message,
+            })
+        })
+        .collect();
+    run_plugins_or_exit(
+        &manifest,
+        LifecycleHook::PostTest,
+        json!({
+            "passed": results.len() - failures,
+            "failed": failures,
+            "tests": tests,
+        }),
+    );
+    if failures > 0 {
+        process::exit(1);
+    }
+}
+
+fn run_queries(cmd: Query) {
+    let location = FileLocation::from_path(env::current_dir().unwrap().join(&cmd.file));
+    let spec = match location
+        .read_content()
+        .and_then(|content| QueriesSpec::from_yaml(&content))
+    {
+        Ok(spec) => spec,
+        Err(message) => {
+            eprintln!("{}", format_err!(message));
+            process::exit(1);
+        }
+    };
+
+    let report = match cmd.api_url {
+        Some(ref api_url) => hiro_system_kit::nestable_block_on(run_queries_on_api(api_url, &spec)),
+        None => {
+            let mut manifest = load_manifest_or_exit(cmd.manifest_path);
+            cmd.runtime.apply(&mut manifest);
+            let (_, _, artifacts) = load_deployment_and_artifacts_or_exit(
+                &manifest,
+                &cmd.deployment_plan_path,
+                false,
+                false,
+            );
+            if !artifacts.success {
+                eprintln!(
+                    "{}",
+                    format_err!(
+                        "unable to analyze the contracts, run `clarinet check` for details"
+                    )
+                );
+                process::exit(1);
+            }
+            run_queries_on_session(&artifacts.session, &spec)
+        }
+    };
+
+    let content = match cmd.json {
+        true => {
+            serde_json::to_string_pretty(&report.to_json()).expect("unable to serialize results")
+        }
+        false => report.to_csv(),
+    };
+    match cmd.output {
+        Some(output) => {
+            if let Err(e) = fs::write(&output, content) {
+                eprintln!("{} unable to write file {}: {}", red!("error:"), output, e);
+                process::exit(1);
+            }
+            println!(
+                "{} {} ({})",
+                green!("Exported"),
+                pluralize!(report.results.len(), "result"),
+                output
+            );
+        }
+        None => println!("{}", content.trim_end()),
+    }
+}
+
+fn snapshot_contracts(cmd: SnapshotContracts) {
+    let mut manifest = load_manifest_or_exit(cmd.manifest_path);
+    cmd.runtime.apply(&mut manifest);
+    let (_, _, mut artifacts) =
+        load_deployment_and_artifacts_or_exit(&manifest, &cmd.deployment_plan_path, false, false);
+    if !artifacts.success {
+        eprintln!(
+            "{}",
+            format_err!("unable to analyze the contracts, run `clarinet check` for details")
+        );
+        process::exit(1);
+    }
+
+    let mut contracts_ids = vec![];
+    for contract in cmd.contracts.iter() {
+        let contract_id = artifacts.session.contracts.keys().find(|contract_id| {
+            contract_id.name.as_str() == *contract || contract_id.to_string() == *contract
+        });
+        match contract_id {
+            Some(contract_id) => contracts_ids.push(contract_id.clone()),
+            None => {
+                let message = format!("contract {} not found", contract);
+                eprintln!("{}", format_err!(message));
+                process::exit(1);
+            }
+        }
+    }
+    let content = match take_snapshot(&mut artifacts.session, &contracts_ids)
+        .and_then(|snapshot| snapshot.to_json())
+    {
+        Ok(content) => content,
+        Err(message) => {
+            eprintln!("{}", format_err!(message));
+            process::exit(1);
+        }
+    };
+    match cmd.output {
+        Some(output) => {
+            if let Err(e) = fs::write(&output, content) {
+                eprintln!("{} unable to write file {}: {}", red!("error:"), output, e);
+                process::exit(1);
+            }
+            println!("{} Created {}", green!("✔"), output);
+        }
+        None => println!("{}", content),
+    }
+}
+
+fn fuzz_contract_functions(cmd: FuzzContract) {
+    let mut manifest = load_manifest_or_exit(cmd.manifest_path);
+    cmd.runtime.apply(&mut manifest);
+    let (deployment, _, artifacts) =
+        load_deployment_and_artifacts_or_exit(&manifest, &cmd.deployment_plan_path, false, false);
+    if !artifacts.success {
+        eprintln!(
+            "{}",
+            format_err!("unable to analyze the contracts, run `clarinet check` for details")
+        );
+        process::exit(1);
+    }
+    let Some(contract_id) = artifacts.session.contracts.keys().find(|contract_id| {
+        contract_id.name.as_str() == cmd.contract || contract_id.to_string() == cmd.contract
+    }) else {
+        let message = format!("contract {} not found", cmd.contract);
+        eprintln!("{}", format_err!(message));
+        process::exit(1);
+    };
+
+    let senders: Vec<PrincipalData> = deployment
+        .genesis
+        .as_ref()
+        .map(|genesis| {
+            genesis
+                .wallets
+                .iter()
+                .map(|wallet| PrincipalData::Standard(wallet.address.clone()))
+                .collect()
+        })
+        .unwrap_or_default();
+    let seed = cmd.seed.unwrap_or_else(|| {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|duration| duration.as_nanos() as u64)
+            .unwrap_or_default()
+    });
+    let report = match fuzz_contract(
+        &artifacts.session,
+        contract_id,
+        &senders,
+        cmd.iterations,
+        seed,
+        &PostConditions::new(cmd.post_condition_mode, vec![]),
+    ) {
+        Ok(report) => report,
+        Err(message) => {
+            eprintln!("{}", format_err!(message));
+            process::exit(1);
+        }
+    };
+
+    for failure in report.failures.iter() {
+        println!("{} {}", red!("x"), failure);
+    }
+    let calls = pluralize!(report.calls, "call");
+    match report.failures.len() {
+        0 => println!("{} no failure in {} (seed {})", green!("✔"), calls, seed),
+        failures => {
+            println!(
+                "{} in {} (seed {})",
+                pluralize!(failures, "failure"),
+                calls,
+                seed
+            );
+            process::exit(1);
+        }
+    }
+}
+
+fn diff_storage_snapshots(cmd: DiffSnapshots) {
+    let read_snapshot = |path: &str| {
+        let location = FileLocation::from_path(env::current_dir().unwrap().join(path));
+        match location
+            .read_content()
+            .and_then(|content| StorageSnapshot::from_json(&content))
+        {
+            Ok(snapshot) => snapshot,
+            Err(message) => {
+                eprintln!("{}", format_err!(message));
+                process::exit(1);
+            }
+        }
+    };
+    let previous = read_snapshot(&cmd.previous);
+    let new = read_snapshot(&cmd.new);
+
+    let diffs = diff_snapshots(&previous, &new);
+    if diffs.is_empty() {
+        println!("{} Snapshots are identical", green!("✔"));
+        return;
+    }
+    for diff in diffs.iter() {
+        println!("{}", diff);
+    }
+    eprintln!(
+        "{}",
+        format_err!(format!(
+            "snapshots differ ({})",
+            pluralize!(diffs.len(), "change")
+        ))
+    );
+    process::exit(1);
+}
+
+fn retarget_principals(cmd: RetargetPrincipals) {
+    let network = match (cmd.testnet, cmd.mainnet) {
+        (true, _) => StacksNetwork::Testnet,
+        (_, true) => StacksNetwork::Mainnet,
+        _ => {
+            eprintln!(
+                "{}",
+                format_err!("a flag `--testnet` or `--mainnet` should be provided")
+            );
+            process::exit(1);
+        }
+    };
+    let manifest = load_manifest_or_exit(cmd.manifest_path);
+    let network_manifest = load_network_manifest_or_exit(&manifest, &network.get_networks());
+    let principals = &network_manifest.network.principals;
+    if principals.is_empty() {
+        let message = format!(
+            "no principals to retarget, add a [network.principals] table to settings/{}.toml",
+            if cmd.mainnet { "Mainnet" } else { "Testnet" }
+        );
+        eprintln!("{}", format_err!(message));
+        process::exit(1);
+    }
+
+    let project_root = match manifest.location.get_project_root_location() {
+        Ok(location) => PathBuf::from(location.to_string()),
+        Err(message) => {
+            eprintln!("{}", format_err!(message));
+            process::exit(1);
+        }
+    };
+    let mut files: Vec<PathBuf> = manifest
+        .contracts
+        .values()
+        .map(|contract| project_root.join(contract.expect_contract_path_as_str()))
+        .collect();
+    match collect_files(&project_root.join("tests"), &["ts", "js", "clar"]) {
+        Ok(mut test_files) => files.append(&mut test_files),
+        Err(message) => {
+            eprintln!("{}", format_err!(message));
+            process::exit(1);
+        }
+    }
+    // the plans of the other networks keep their own principals
+    match get_default_deployment_path(&manifest, &network) {
+        Ok(location) if location.exists() => files.push(PathBuf::from(location.to_string())),
+        Ok(_) => {}
+        Err(message) => {
+            eprintln!("{}", format_err!(message));
+            process::exit(1);
+        }
+    }
+
+    let mut rewrites_count = 0;
+    let mut files_count = 0;
+    for path in files.iter() {
+        let source = match fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!(
+                    "{} unable to read {}: {}",
+                    red!("error:"),
+                    path.display(),
+                    e
+                );
+                process::exit(1);
+            }
+        };
+        let relative_path = path.strip_prefix(&project_root).unwrap_or(path);
+        let (rewritten, rewrites) = rewrite_principals(&source, principals, relative_path);
+        if rewrites.is_empty() {
+            continue;
+        }
+        for rewrite in rewrites.iter() {
+            println!("{}", rewrite);
+        }
+        if !cmd.dry_run {
+            if let Err(e) = fs::write(path, rewritten) {
+                eprintln!(
+                    "{} unable to write {}: {}",
+                    red!("error:"),
+                    path.display(),
+                    e
+                );
+                process::exit(1);
+            }
+        }
+        rewrites_count += rewrites.len();
+        files_count += 1;
+    }
+
+    if cmd.dry_run {
+        println!(
+            "{} {} to rewrite in {}",
+            yellow!("!"),
+            pluralize!(rewrites_count, "principal"),
+            pluralize!(files_count, "file")
+        );
+    } else {
+        println!(
+            "{} {} rewritten in {}",
+            green!("✔"),
+            pluralize!(rewrites_count, "principal"),
+            pluralize!(files_count, "file")
+        );
+    }
+}
+
+fn costs_report(cmd: CostsReport) {
+    let calls = match fs::read_to_string(&cmd.path)
+        .map_err(|e| format!("unable to read {}: {}", cmd.path, e))
+        .and_then(|content| parse_costs_report(&content))
+    {
+        Ok(calls) => calls,
+        Err(message) => {
+            eprintln!("{}", format_err!(message));
+            process::exit(1);
+        }
+    };
+    let functions = summarize_costs(&calls);
+
+    if cmd.json {
+        println!("{}", serde_json::to_string_pretty(&functions).unwrap());
+        return;
+    }
+    println!(
+        "{:<60} {:>6} {:>12} {:>10} {:>12} {:>11} {:>12} {:>8}",
+        "function",
+        "calls",
+        "runtime",
+        "read_count",
+        "read_length",
+        "write_count",
+        "write_length",
+        "% limit"
+    );
+    for function in functions.iter() {
+        let name = format!("{}::{}", function.contract_id, function.method);
+        let percentage = format!("{:.2}%", function.max_percentage);
+        let percentage = format!("{:>8}", percentage);
+        let percentage = match function.max_percentage {
+            p if p >= 100.0 => red!(percentage),
+            p if p >= 50.0 => yellow!(percentage),
+            _ => percentage,
+        };
+        println!(
+            "{:<60} {:>6} {:>12} {:>10} {:>12} {:>11} {:>12} {}",
+            name,
+            function.calls,
+            function.max.runtime,
+            function.max.read_count,
+            function.max.read_length,
+            function.max.write_count,
+            function.max.write_length,
+            percentage
+        );
+    }
+    println!(
+        "\nhighest costs of the {} of each function, compared to the block limits",
+        pluralize!(calls.len(), "call")
+    );
+}
+
+fn contracts_graph(cmd: ContractsGraph) {
+    let manifest = load_manifest_or_exit(cmd.manifest_path);
+    // cycles between contracts are reported here, with the contracts involved
+    let (deployment, artifacts) =
+        match generate_default_deployment(&manifest, &StacksNetwork::Simnet, false) {
+            Ok(deployment) => deployment,
+            Err(message) => {
+                eprintln!("{}", format_err!(message));
+                process::exit(1);
+            }
+        };
+    let contracts = publish_order(&deployment);
+    let dependencies: BTreeMap<_, _> = artifacts
+        .deps
+        .into_iter()
+        .filter(|(contract_id, _)| contracts.contains(contract_id))
+        .collect();
+
+    if cmd.dot {
+        print!("{}", dependencies_to_dot(&dependencies));
+        return;
+    }
+    for (index, contract_id) in contracts.iter().enumerate() {
+        println!("{}. {}", index + 1, contract_id);
+        let Some(contract_dependencies) = dependencies.get(contract_id) else {
+            continue;
+        };
+        for dependency in contract_dependencies.iter() {
+            println!("     -> {}", dependency.contract_id);
+        }
+    }
+}
+
+fn replay_tx(cmd: ReplayTx) {
+    let api_url = cmd.api_url.unwrap_or(match cmd.testnet {
+        true => "https://api.testnet.hiro.so".to_string(),
+        false => "https://api.hiro.so".to_string(),
+    });
+    let cache_location = match get_manifest_location(cmd.manifest_path) {
+        Some(manifest_location) => {
+            load_manifest_or_exit(Some(manifest_location.to_string()))
+                .project
+                .cache_location
+        }
+        None => {
+            let home_dir = dirs::home_dir().expect("unable to retrieve home directory");
+            FileLocation::from_path(home_dir.join(".clarinet").join("cache"))
+        }
+    };
+
+    let tx = match hiro_system_kit::nestable_block_on(fetch_contract_call(&api_url, &cmd.txid)) {
+        Ok(tx) => tx,
+        Err(message) => {
+            eprintln!("{}", format_err!(message));
+            process::exit(1);
+        }
+    };
+    if !cmd.json {
+        println!(
+            "{} {}::{} called by {} at block #{}",
+            yellow!("Replaying"),
+            tx.contract_id,
+            tx.function_name,
+            tx.sender,
+            tx.block_height
+        );
+    }
+
+    let future = setup_replay_session(&api_url, &tx, &cache_location);
+    let (mut session, warnings) = match hiro_system_kit::nestable_block_on(future) {
+        Ok(res) => res,
+        Err(message) => {
+            eprintln!("{}", format_err!(message));
+            process::exit(1);
+        }
+    };
+    if !cmd.json {
+        for warning in warnings.iter() {
+            println!("{}", format_warn!(warning));
+        }
+    }
+
+    if cmd.debug {
+        // Debug on a copy of the session, so that the replay runs against the restored state
+        let mut debug_session = session.clone();
+        let mut output = vec![];
+        debug_session.set_tx_sender(&tx.sender);
+        debug_session.debug(&mut output, &format!("::debug {}", tx.to_snippet()));
+        for line in output.iter() {
+            println!("{}", line);
+        }
+    }
+
+    let report = replay_contract_call(&mut session, &tx);
+    if cmd.json {
+        let cost = report.cost.as_ref().map(|cost| {
+            json!({
+                "runtime": cost.runtime,
+                "read_count": cost.read_count,
+                "read_length": cost.read_length,
+                "write_count": cost.write_count,
+                "write_length": cost.write_length,
+            })
+        });
+        let output = json!({
+            "tx_id": tx.tx_id,
+            "contract_id": tx.contract_id.to_string(),
+            "function_name": tx.function_name,
+            "sender": tx.sender,
+            "block_height": tx.block_height,
+            "local_result": report.result.as_ref().map(value_to_json),
+            "on_chain_result": value_to_json(&tx.result),
+            "on_chain_status": tx.status,
+            "local_cost": cost,
+            "warnings": warnings,
+            "divergences": report.divergences,
+        });
+        println!("{}", serde_json::to_string_pretty(&output).unwrap());
+        return;
+    }
+    if let Some(ref result) = report.result {
+        println!("{} {}", green!("Local result:"), result);
+    }
+    println!(
+        "{} {} ({})",
+        green!("On-chain result:"),
+        tx.result,
+        tx.status
+    );
+    if let Some(ref cost) = report.cost {
+        println!(
+            "{} runtime: {}, read count: {}, read length: {}, write count: {}, write length: {}",
+            green!("Local cost:"),
+            cost.runtime,
+            cost.read_count,
+            cost.read_length,
+            cost.write_count,
+            cost.write_length
+        );
+    }
+    if report.divergences.is_empty() {
+        println!("{}", green!("No divergence from the on-chain execution"));
+    } else {
+        for divergence in report.divergences.iter() {
+            println!("{} {}", yellow!("divergence:"), divergence);
+        }
+    }
+}
+
+fn devnet_bitcoin_rpc_or_exit(
+    manifest_path: Option<String>,
+) -> (NetworkManifest, DevnetBitcoinRpc, String) {
+    let manifest = load_manifest_or_exit(manifest_path);
+    let network_manifest =
+        load_network_manifest_or_exit(&manifest, &StacksNetwork::Devnet.get_networks());
+    let Some(ref devnet) = network_manifest.devnet else {
+        eprintln!("{}", format_err!("unable to load devnet settings"));
+        process::exit(1);
+    };
+    let host = format!("localhost:{}", devnet.bitcoin_node_rpc_port);
+    let miner_btc_address = devnet.miner_btc_address.clone();
+    let rpc = match DevnetBitcoinRpc::new(
+        &host,
+        &devnet.bitcoin_node_username,
+        &devnet.bitcoin_node_password,
+    ) {
+        Ok(rpc) => rpc,
+        Err(message) => {
+            eprintln!("{}", format_err!(message));
+            process::exit(1);
+        }
+    };
+    (network_manifest, rpc, miner_btc_address)
+}
+
+fn run_git_hook(cmd: RunHook) {
+    let hook = match GitHook::from_str(&cmd.hook) {
+        Ok(hook) => hook,
+        Err(message) => {
+            eprintln!("{}", format_err!(message));
+            process::exit(1);
+        }
+    };
+    let manifest = load_manifest_or_exit(cmd.manifest_path);
+    let project_root = manifest.location.get_project_root_location().unwrap();
+
+    for gate in get_git_hook_gates(&manifest, hook).into_iter() {
+        println!("{} {} {}", yellow!("running"), hook, gate);
+        let status = match gate {
+            GitHookGate::Check => process::Command::new(env::current_exe().unwrap())
+                .arg("check")
+                .arg("--manifest-path")
+                .arg(manifest.location.to_string())
+                .status(),
+            GitHookGate::Test => {
+                let mut command =
+                    process::Command::new(if cfg!(windows) { "npm.cmd" } else { "npm" });
+                command.arg("test");
+                // only the tests affected by the uncommitted changes gate commits
+                if hook == GitHook::PreCommit {
+                    command.args(["--", "--changed"]);
+                }
+                command.current_dir(project_root.to_string()).status()
+            }
+        };
+        match status {
+            Ok(status) if status.success() => {}
+            Ok(status) => {
+                let message = format!("{} gate failed during {} ({})", gate, hook, status);
+                eprintln!("{}", format_err!(message));
+                process::exit(1);
+            }
+            Err(e) => {
+                let message = format!("unable to run {} gate ({})", gate, e);
+                eprintln!("{}", format_err!(message));
+                process::exit(1);
+            }
+        }
+    }
+}
+
+fn compare_runtimes(cmd: CompareRuntimes) {
+    let manifest = load_manifest_or_exit(cmd.manifest_path);
+    let (deployment, _, artifacts) =
+        load_deployment_and_artifacts_or_exit(&manifest, &cmd.deployment_plan_path, false, false);
+    if !artifacts.success {
+        eprintln!(
+            "{}",
+            format_err!("unable to analyze the contracts, run `clarinet check` for details")
+        );
+        process::exit(1);
+    }
+
+    // the test suite of the project, `test-` functions of its contracts, is run after the plan
+    let contracts_ids: Vec<QualifiedContractIdentifier> = artifacts
+        .session
+        .contracts
+        .keys()
+        .filter(|contract_id| manifest.contracts.contains_key(contract_id.name.as_str()))
+        .cloned()
+        .collect();
+    let tests = discover_tests(&artifacts.session, &contracts_ids);
+    let run = |clarity_wasm_mode: bool| {
+        let mut manifest = manifest.clone();
+        manifest.repl_settings.clarity_wasm_mode = clarity_wasm_mode;
+        let mut session = initiate_session_from_manifest(&manifest);
+        let mut outcomes = run_deployment_outcomes(&mut session, &deployment);
+        outcomes.extend(run_tests_outcomes(&session, &tests));
+        outcomes
+    };
+    let outcomes = run(false);
+    let wasm_outcomes = run(true);
+
+    let divergences = compare_outcomes(&outcomes, &wasm_outcomes);
+    if divergences.is_empty() {
+        println!(
+            "{} {} and {} executed with the same results, events and costs",
+            green!("✔"),
+            pluralize!(outcomes.len() - tests.len(), "transaction"),
+            pluralize!(tests.len(), "test")
+        );
+        return;
+    }
+    for divergence in divergences.iter() {
+        println!("{}", divergence);
+    }
+    eprintln!(
+        "{}",
+        format_err!(format!(
+            "clarity-wasm diverges from the interpreter ({})",
+            pluralize!(divergences.len(), "difference")
+        ))
+    );
+    process::exit(1);
+}
+
+fn deployment_nonces(cmd: DeploymentNonces) {
+    let network = match (cmd.testnet, cmd.mainnet) {
+        (true, _) => StacksNetwork::Testnet,
+        (_, true) => StacksNetwork::Mainnet,
+        _ => StacksNetwork::Devnet,
+    };
+    let manifest = load_manifest_or_exit(cmd.manifest_path);
+    let network_manifest = load_network_manifest_or_exit(&manifest, &network.get_networks());
+    let api_url = cmd
+        .api_url
+        .unwrap_or(match (&network, &network_manifest.devnet) {
+            (StacksNetwork::Mainnet, _) => "https://api.hiro.so".to_string(),
+            (StacksNetwork::Testnet, _) => "https://api.testnet.hiro.so".to_string(),
+            (_, Some(devnet)) => format!("http://localhost:{}", devnet.stacks_api_port),
+            (_, None) => "http://localhost:3999".to_string(),
+        });
+
+    let mut found = false;
+    for (name, account) in network_manifest.accounts.iter() {
+        if let Some(ref filter) = cmd.account {
+            if filter != name && filter != &account.stx_address {
+                continue;
+            }
+        }
+        found = true;
+        let nonces = match get_address_nonces(&api_url, &account.stx_address) {
+            Ok(nonces) => nonces,
+            Err(message) => {
+                eprintln!("{}", format_err!(message));
+                process::exit(1);
+            }
+        };
+        let stuck = &nonces.detected_missing_nonces;
+        println!(
+            "{} ({}): next nonce {}, {} in mempool, {}",
+            name,
+            account.stx_address,
+            nonces.possible_next_nonce,
+            pluralize!(nonces.detected_mempool_nonces.len(), "transaction"),
+            match stuck.is_empty() {
+                true => green!("no stuck nonce"),
+                false => red!(format!(
+                    "stuck nonces: {}",
+                    stuck
+                        .iter()
+                        .map(|nonce| nonce.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )),
+            }
+        );
+        if !cmd.cancel_stuck {
+            continue;
+        }
+        for nonce in stuck.iter() {
+            match cancel_stuck_nonce(&api_url, account, *nonce, cmd.fee, &network) {
+                Ok(txid) => println!(
+                    "  {} cancel transaction for nonce {}: {}",
+                    blue!("➡"),
+                    nonce,
+                    txid
+                ),
+                Err(message) => eprintln!("  {}", format_err!(message)),
+            }
+        }
+    }
+    if !found {
+        eprintln!(
+            "{}",
+            format_err!(format!("no account found in settings/{:?}.toml", network))
+        );
+        process::exit(1);
+    }
+}
+
+fn devnet_load(cmd: DevnetLoad) {
+    let scenario_location =
+        FileLocation::from_path(env::current_dir().unwrap().join(&cmd.scenario));
+    let scenario = match scenario_location
+        .read_content()
+        .and_then(|content| LoadScenario::from_yaml(&content))
+    {
+        Ok(scenario) => scenario,
+        Err(message) => {
+            eprintln!("{}", format_err!(message));
+            process::exit(1);
+        }
+    };
+    let manifest = load_manifest_or_exit(cmd.manifest_path);
+    let network_manifest =
+        load_network_manifest_or_exit(&manifest, &StacksNetwork::Devnet.get_networks());
+    let Some(ref devnet) = network_manifest.devnet else {
+        eprintln!("{}", format_err!("unable to load devnet settings"));
+        process::exit(1);
+    };
+    let node_url = format!("http://localhost:{}", devnet.stacks_node_rpc_port);
+
+    println!(
+        "{} {} tx/s for {}s, from {} sender(s)",
+        yellow!("submitting"),
+        scenario.rate,
+        scenario.duration,
+        scenario.senders.len()
+    );
+    match run_load_scenario(&scenario, &network_manifest, &node_url) {
+        Ok(report) => println!("{}", report),
+        Err(message) => {
+            eprintln!("{}", format_err!(message));
+            process::exit(1);
+        }
+    }
+}
+
+fn hex_payload_or_exit(payload: &str) -> Vec<u8> {
+    match hex_bytes(payload.trim_start_matches("0x")) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            eprintln!("{}", format_err!(format!("invalid hex payload: {}", e)));
+            process::exit(1);
+        }
+    }
+}
+
+fn devnet_btc_broadcast_or_exit<F>(options: DevnetBtcOptions, outputs: F)
+where
+    F: FnOnce(&AccountConfig) -> Result<Vec<TxOut>, String>,
+{
+    devnet_btc_send_or_exit(options, |rpc, account, sats_per_byte| {
+        let txid = rpc.send_outputs(account, outputs(account)?, sats_per_byte)?;
+        Ok(vec![txid])
+    })
+}
+
+fn devnet_btc_send_or_exit<F>(options: DevnetBtcOptions, send: F)
+where
+    F: FnOnce(&DevnetBitcoinRpc, &AccountConfig, u64) -> Result<Vec<Txid>, String>,
+{
+    let (network_manifest, rpc, miner_btc_address) =
+        devnet_bitcoin_rpc_or_exit(options.manifest_path);
+    let Some(account) = network_manifest.accounts.get(&options.account) else {
+        eprintln!(
+            "{}",
+            format_err!(format!("account '{}' not found", options.account))
+        );
+        process::exit(1);
+    };
+    let txids = match send(&rpc, account, options.sats_per_byte) {
+        Ok(txids) => txids,
+        Err(message) => {
+            eprintln!("{}", format_err!(message));
+            process::exit(1);
+        }
+    };
+    for txid in txids.iter() {
+        println!("{} {}", green!("broadcasted"), txid);
+    }
+    if options.mine {
+        if let Err(message) = rpc.mine_blocks(1, &miner_btc_address) {
+            eprintln!("{}", format_err!(message));
+            process::exit(1);
+        }
+    }
+}
+
+fn devnet_btc(cmd: DevnetBtc) {
+    match cmd {
+        DevnetBtc::Mine(cmd) => {
+            let (_, rpc, miner_btc_address) = devnet_bitcoin_rpc_or_exit(cmd.manifest_path);
+            let recipient = cmd.recipient.unwrap_or(miner_btc_address);
+            match rpc.mine_blocks(cmd.count, &recipient) {
+                Ok(blocks) => {
+                    for block in blocks.iter() {
+                        println!("{} {}", green!("mined"), block);
+                    }
+                }
+                Err(message) => {
+                    eprintln!("{}", format_err!(message));
+                    process::exit(1);
+                }
+            }
+        }
+        DevnetBtc::Send(cmd) => devnet_btc_broadcast_or_exit(cmd.options, |_| {
+            Ok(vec![payment_output(&cmd.recipient, cmd.sats_amount)?])
+        }),
+        DevnetBtc::OpReturn(cmd) => {
+            let payload = hex_payload_or_exit(&cmd.payload);
+            devnet_btc_broadcast_or_exit(cmd.options, |_| Ok(vec![op_return_output(&payload)?]))
+        }
+        DevnetBtc::Pox(cmd) => {
+            devnet_btc_send_or_exit(cmd.options, |rpc, account, sats_per_byte| {
+                let pox_address = cmd.pox_address.as_ref().unwrap_or(&account.btc_address);
+                let (pre_stx_txid, stack_stx_txid) = rpc.send_stack_stx(
+                    account,
+                    cmd.ustx_amount,
+                    cmd.num_cycles,
+                    pox_address,
+                    sats_per_byte,
+                )?;
+                Ok(vec![pre_stx_txid, stack_stx_txid])
+            })
+        }
+        DevnetBtc::Pob(cmd) => {
+            let payload = cmd.payload.as_deref().map(hex_payload_or_exit);
+            devnet_btc_broadcast_or_exit(cmd.options, |_| {
+                let mut outputs = vec![];
+                if let Some(payload) = payload {
+                    outputs.push(op_return_output(&payload)?);
+                }
+                outputs.push(burn_output(cmd.sats_amount)?);
+                Ok(outputs)
+            })
+        }
+    }
+}
+
+fn devnet_start(cmd: DevnetStart, global_settings: GlobalSettings) {
+    let manifest = load_manifest_or_exit(cmd.manifest_path);
+    println!("Computing deployment plan");
+    let result = match cmd.deployment_plan_path {
+        None => {
+            let res = if let Some(package) = cmd.package {
+                let package_file = match File::open(package) {
+                    Ok(file) => file,
+                    Err(_) => {
+                        eprintln!("{} package file not found", red!("error:"));
+                        std::process::exit(1);
+                    }
+                };
+                let deployment: ConfigurationPackage = serde_json::from_reader(package_file)
+                    .expect("error while reading deployment specification");
+                Some(Ok(deployment.deployment_plan))
+            } else {
+                load_deployment_if_exists(
+                    &manifest,
+                    &StacksNetwork::Devnet,
+                    cmd.use_on_disk_deployment_plan,
+                    cmd.use_computed_deployment_plan,
+                )
+            };
+            match res {
+                Some(Ok(deployment)) => {
+                    println!(
+                        "{} using existing deployments/default.devnet-plan.yaml",
+                        yellow!("note:")
+                    );
+                    // TODO(lgalabru): Think more about the desired DX.
+                    // Compute the latest version, display differences and propose overwrite?
+                    Ok(deployment)
+                }
+                Some(Err(e)) => Err(e),
+                None => {
+                    let default_deployment_path =
+                        get_default_deployment_path(&manifest, &StacksNetwork::Devnet).unwrap();
+                    let (deployment, _) =
+                        match generate_default_deployment(&manifest, &StacksNetwork::Devnet, false)
+                        {
+                            Ok(deployment) => deployment,
+                            Err(message) => {
+                                eprintln!("{}", red!(message));
+                                std::process::exit(1);
+                            }
+                        };
+                    let res = write_deployment(&deployment, &default_deployment_path, true);
+                    if let Err(message) = res {
+                        Err(message)
+                    } else {
+                        println!(
+                            "{} {}",
+                            green!("Generated file"),
+                            default_deployment_path.get_relative_location().unwrap()
+                        );
+                        Ok(deployment)
+                    }
+                }
+            }
+        }
+        Some(deployment_plan_path) => {
+            let deployment_path = get_absolute_deployment_path(&manifest, &deployment_plan_path)
+                .expect("unable to retrieve deployment");
+            load_deployment(&manifest, &deployment_path)
+        }
+    };
+
+    let deployment = match result {
+        Ok(deployment) => deployment,
+        Err(e) => {
+            eprintln!("{}", format_err!(e));
+            std::process::exit(1);
+        }
+    };
+
+    let orchestrator = match DevnetOrchestrator::new(manifest, None, None, true, cmd.no_dashboard) {
+        Ok(orchestrator) => orchestrator,
+        Err(e) => {
+            eprintln!("{}", format_err!(e));
+            process::exit(1);
+        }
+    };
+    if let Some(ref network_manifest) = orchestrator.network_config {
+        print_warnings(&network_manifest.warnings);
+    }
+
+    if orchestrator.manifest.project.telemetry {
+        #[cfg(feature = "telemetry")]
+        telemetry_report_event(DeveloperUsageEvent::DevnetExecuted(
+            DeveloperUsageDigest::new(
+                &orchestrator.manifest.project.name,
+                &orchestrator.manifest.project.authors,
+            ),
+        ));
+    }
+    match start(orchestrator, deployment, None, !cmd.no_dashboard) {
+        Err(e) => {
+            eprintln!("{}", format_err!(e));
+            process::exit(1);
+        }
+        Ok(_) => {
+            if global_settings.enable_hints.unwrap_or(true) {
+                display_deploy_hint();
+            }
+            process::exit(0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap_complete::generate;
+
+    use super::*;
+
+    #[test]
+    fn test_completion_for_shells() {
+        for shell in [
+            Shell::Bash,
             Shell::Elvish,
             Shell::Fish,
             Shell::PowerShell,
@@ -1921,6 +5442,135 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_structured_data_chain_id() {
+        let parse = |args: &[&str]| {
+            let mut argv = vec!["clarinet", "sip018", "verify"];
+            argv.extend(args);
+            argv.extend([
+                "--domain-name=app",
+                "--domain-version=1",
+                "--message=u1",
+                "--signature=00",
+                "--public-key=00",
+            ]);
+            match Opts::try_parse_from(argv).unwrap().command {
+                Command::Sip018(Sip018::Verify(cmd)) => cmd.data,
+                _ => unreachable!(),
+            }
+        };
+        assert_eq!(parse(&[]).chain_id(), CHAIN_ID_TESTNET);
+        assert_eq!(parse(&["--testnet"]).chain_id(), CHAIN_ID_TESTNET);
+        assert_eq!(parse(&["--mainnet"]).chain_id(), CHAIN_ID_MAINNET);
+        assert_eq!(parse(&["--mainnet", "--chain-id=3"]).chain_id(), 3);
+    }
+
+    #[test]
+    fn test_runtime_option() {
+        let parse = |args: &[&str]| {
+            let mut argv = vec!["clarinet"];
+            argv.extend(args);
+            Opts::try_parse_from(argv).map(|opts| match opts.command {
+                Command::Console(cmd) => cmd.runtime,
+                Command::Test(cmd) => cmd.runtime,
+                Command::Notebook(cmd) => cmd.runtime,
+                Command::Query(cmd) => cmd.runtime,
+                Command::Simnet(Simnet::Serve(cmd)) => cmd.runtime,
+                Command::Simnet(Simnet::Snapshot(cmd)) => cmd.runtime,
+                Command::Contracts(Contracts::FuzzContract(cmd)) => cmd.runtime,
+                Command::Contracts(Contracts::SnapshotContracts(cmd)) => cmd.runtime,
+                _ => unreachable!(),
+            })
+        };
+        let commands: [&[&str]; 8] = [
+            &["console"],
+            &["test"],
+            &["notebook", "README.md"],
+            &["query", "--file=queries.yaml"],
+            &["simnet", "serve"],
+            &["simnet", "snapshot"],
+            &["contracts", "fuzz", "counter"],
+            &["contracts", "snapshot"],
+        ];
+        for command in commands {
+            assert_eq!(parse(command).unwrap(), Runtime::Interpreter);
+            let mut args = command.to_vec();
+            args.push("--runtime=wasm");
+            assert_eq!(parse(&args).unwrap(), Runtime::Wasm, "{:?}", command);
+        }
+        assert!(parse(&["test", "--runtime=jit"]).is_err());
+        assert!(parse(&["console", "--enable-clarity-wasm", "--runtime=wasm"]).is_err());
+        assert!(parse(&[
+            "query",
+            "--file=queries.yaml",
+            "--api-url=http://localhost:3999",
+            "--runtime=wasm"
+        ])
+        .is_err());
+
+        let manifest_file: ProjectManifestFile =
+            toml::from_str("[project]\nname = \"test\"").unwrap();
+        let location = FileLocation::from_path(PathBuf::from("/tmp/test/Clarinet.toml"));
+        let mut manifest =
+            ProjectManifest::from_project_manifest_file(manifest_file, &location).unwrap();
+        Runtime::Wasm.apply(&mut manifest);
+        assert!(manifest.repl_settings.clarity_wasm_mode);
+        Runtime::Interpreter.apply(&mut manifest);
+        assert!(!manifest.repl_settings.clarity_wasm_mode);
+    }
+
+    #[test]
+    fn test_fee_multiplier() {
+        let parse = |multiplier: &str| {
+            let argv = [
+                "clarinet",
+                "deployments",
+                "generate",
+                "--testnet",
+                "--low-cost",
+                &format!("--fee-multiplier={}", multiplier),
+            ];
+            Opts::try_parse_from(argv).map(|opts| match opts.command {
+                Command::Deployments(Deployments::GenerateDeployment(cmd)) => cmd.fee_multiplier,
+                _ => unreachable!(),
+            })
+        };
+        assert_eq!(parse("1.5").unwrap(), 1.5);
+        assert!(parse("0").is_err());
+        assert!(parse("-1").is_err());
+        assert!(parse("NaN").is_err());
+        assert!(parse("inf").is_err());
+        assert!(parse("fast").is_err());
+    }
+
+    #[test]
+    fn test_sign_then_verify_structured_data() {
+        let data = StructuredData {
+            domain_name: "app".to_string(),
+            domain_version: "1.0.0".to_string(),
+            chain_id: None,
+            message: "{ amount: u100 }".to_string(),
+            testnet: false,
+            mainnet: false,
+        };
+        let (domain, message) = structured_data_or_exit(&data);
+        assert_eq!(
+            domain,
+            sip018::make_domain("app", "1.0.0", CHAIN_ID_TESTNET).unwrap()
+        );
+
+        let private_key = Secp256k1PrivateKey::from_hex(
+            "753b7cc01a1a2e86221266a154af739463fce51219d97e4f856cd7200c3bd2a601",
+        )
+        .unwrap();
+        let public_key = Secp256k1PublicKey::from_private(&private_key);
+        let signature = sip018::sign_structured_data(&domain, &message, &private_key).unwrap();
+        assert_eq!(
+            sip018::verify_structured_data(&domain, &message, &signature, &public_key),
+            Ok(true)
+        );
+    }
+
     #[test]
     fn test_sanitize_project_name() {
         let sanitized = sanitize_project_name("hello_world");
@@ -2,5 +2,7 @@ mod clarinetrc;
 
 pub mod cli;
 pub mod dap;
+pub mod serve;
+pub mod simnet_api;
 #[cfg(feature = "telemetry")]
 mod telemetry;
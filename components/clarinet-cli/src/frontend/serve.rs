@@ -0,0 +1,568 @@
+use super::cli::deploy_test_contracts;
+use crate::deployments::generate_default_deployment;
+use clarinet_deployments::dependency_graph::publish_order;
+use clarinet_deployments::setup_session_with_deployment;
+use clarinet_deployments::test_runner::{discover_tests, run_tests, TestOutcome};
+use clarinet_deployments::types::{DeploymentGenerationArtifacts, DeploymentSpecification};
+use clarinet_files::{get_manifest_location, ProjectManifest, StacksNetwork};
+use clarity_repl::clarity::vm::diagnostic::{Diagnostic, Level};
+use clarity_repl::clarity::vm::{EvaluationResult, SymbolicExpression};
+use clarity_repl::repl::clarity_values::{value_to_json, value_to_string};
+use clarity_repl::repl::Session;
+use clarity_repl::utils::serialize_event;
+use serde_json::Value as JsonValue;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, Write};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const SERVER_ERROR: i64 = -32000;
+
+#[derive(Deserialize)]
+struct Request {
+    jsonrpc: String,
+    /// Missing for notifications, which get no response
+    id: Option<JsonValue>,
+    method: String,
+    #[serde(default)]
+    params: JsonValue,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProjectParams {
+    manifest_path: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TestParams {
+    manifest_path: Option<String>,
+    /// Only run the tests whose name contains this filter
+    filter: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GenerateDeploymentParams {
+    manifest_path: Option<String>,
+    #[serde(default = "default_network")]
+    network: String,
+}
+
+fn default_network() -> String {
+    "simnet".to_string()
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CallFunctionParams {
+    manifest_path: Option<String>,
+    contract: String,
+    method: String,
+    /// Clarity expressions, such as `u1` or `'ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM`
+    #[serde(default)]
+    args: Vec<String>,
+    sender: Option<String>,
+}
+
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl From<String> for RpcError {
+    fn from(message: String) -> Self {
+        RpcError {
+            code: SERVER_ERROR,
+            message,
+        }
+    }
+}
+
+fn invalid_params(e: serde_json::Error) -> RpcError {
+    RpcError {
+        code: INVALID_PARAMS,
+        message: e.to_string(),
+    }
+}
+
+struct ProjectState {
+    manifest: ProjectManifest,
+    deployment: DeploymentSpecification,
+    artifacts: DeploymentGenerationArtifacts,
+}
+
+/// Long-lived JSON-RPC session over stdin/stdout, one message per line.
+/// Projects are kept in memory between requests and only re-analyzed when
+/// their simnet deployment plan changes.
+#[derive(Default)]
+pub struct Server {
+    projects: HashMap<String, ProjectState>,
+}
+
+impl Server {
+    fn load_project(&mut self, manifest_path: Option<String>) -> Result<&ProjectState, String> {
+        let manifest_location = get_manifest_location(manifest_path.clone()).ok_or(format!(
+            "unable to find Clarinet.toml ({:?})",
+            manifest_path
+        ))?;
+        let manifest = ProjectManifest::from_location(&manifest_location)?;
+        let (deployment, ast_artifacts) =
+            generate_default_deployment(&manifest, &StacksNetwork::Simnet, false)?;
+
+        let key = manifest_location.to_string();
+        let up_to_date = matches!(
+            self.projects.get(&key),
+            Some(project) if project.deployment == deployment
+        );
+        if !up_to_date {
+            let artifacts = match ast_artifacts.success {
+                true => {
+                    setup_session_with_deployment(&manifest, &deployment, Some(&ast_artifacts.asts))
+                }
+                false => ast_artifacts,
+            };
+            self.projects.insert(
+                key.clone(),
+                ProjectState {
+                    manifest,
+                    deployment,
+                    artifacts,
+                },
+            );
+        }
+        Ok(self.projects.get(&key).unwrap())
+    }
+
+    fn check(&mut self, params: ProjectParams) -> Result<JsonValue, RpcError> {
+        let project = self.load_project(params.manifest_path)?;
+        let mut diagnostics = vec![];
+        let contracts_diagnostics: BTreeMap<_, _> = project.artifacts.diags.iter().collect();
+        for (contract_id, contract_diagnostics) in contracts_diagnostics.into_iter() {
+            for diagnostic in contract_diagnostics.iter() {
+                diagnostics.push(diagnostic_to_json(&contract_id.to_string(), diagnostic));
+            }
+        }
+        Ok(json!({
+            "success": project.artifacts.success,
+            "diagnostics": diagnostics,
+        }))
+    }
+
+    fn test(&mut self, params: TestParams) -> Result<JsonValue, RpcError> {
+        let project = self.load_project(params.manifest_path)?;
+        if !project.artifacts.success {
+            return Err("unable to analyze the contracts, run `check` for details"
+                .to_string()
+                .into());
+        }
+        let mut session = project.artifacts.session.clone();
+        let mut contracts_ids: Vec<_> = publish_order(&project.deployment)
+            .into_iter()
+            .filter(|contract_id| {
+                project
+                    .manifest
+                    .contracts
+                    .contains_key(contract_id.name.as_str())
+            })
+            .collect();
+        contracts_ids.append(&mut deploy_test_contracts(&project.manifest, &mut session)?);
+
+        let mut tests = discover_tests(&session, &contracts_ids);
+        if let Some(ref filter) = params.filter {
+            tests.retain(|test| test.name.contains(filter.as_str()));
+        }
+        let results = run_tests(&session, tests);
+        let failed = results.iter().filter(|result| !result.passed()).count();
+        let tests: Vec<JsonValue> = results
+            .iter()
+            .map(|result| {
+                let message = match &result.outcome {
+                    TestOutcome::Passed => None,
+                    TestOutcome::Failed(message) => Some(message.clone()),
+                };
+                json!({
+                    "contract": result.test.contract_id.to_string(),
+                    "name": result.test.name,
+                    "passed": result.passed(),
+                    "message": message,
+                })
+            })
+            .collect();
+        Ok(json!({
+            "passed": results.len() - failed,
+            "failed": failed,
+            "tests": tests,
+        }))
+    }
+
+    fn generate_deployment(
+        &mut self,
+        params: GenerateDeploymentParams,
+    ) -> Result<JsonValue, RpcError> {
+        let network = match params.network.as_str() {
+            "simnet" => StacksNetwork::Simnet,
+            "devnet" => StacksNetwork::Devnet,
+            "testnet" => StacksNetwork::Testnet,
+            "mainnet" => StacksNetwork::Mainnet,
+            network => {
+                return Err(RpcError {
+                    code: INVALID_PARAMS,
+                    message: format!("unknown network {}", network),
+                })
+            }
+        };
+        let manifest_location = get_manifest_location(params.manifest_path)
+            .ok_or("unable to find Clarinet.toml".to_string())?;
+        let manifest = ProjectManifest::from_location(&manifest_location)?;
+        let (deployment, _) = generate_default_deployment(&manifest, &network, false)?;
+        let content = deployment.to_file_content()?;
+        Ok(json!({
+            "plan": String::from_utf8_lossy(&content),
+        }))
+    }
+
+    fn call_function(&mut self, params: CallFunctionParams) -> Result<JsonValue, RpcError> {
+        let project = self.load_project(params.manifest_path)?;
+        if !project.artifacts.success {
+            return Err("unable to analyze the contracts, run `check` for details"
+                .to_string()
+                .into());
+        }
+        // each call is simulated against the freshly deployed contracts
+        let mut session = project.artifacts.session.clone();
+        let sender = params.sender.unwrap_or(session.get_tx_sender());
+
+        let mut args = vec![];
+        for arg in params.args.iter() {
+            args.push(eval_arg(&mut session, arg)?);
+        }
+        let execution = session
+            .call_contract_fn(
+                &params.contract,
+                &params.method,
+                &args,
+                &sender,
+                false,
+                true,
+            )
+            .map_err(|diagnostics| {
+                let messages: Vec<String> = diagnostics.into_iter().map(|d| d.message).collect();
+                messages.join("\n")
+            })?;
+
+        let result = match execution.result {
+            EvaluationResult::Snippet(result) => result.result,
+            EvaluationResult::Contract(_) => unreachable!(),
+        };
+        let cost = execution.cost.map(|cost| {
+            json!({
+                "runtime": cost.total.runtime,
+                "readCount": cost.total.read_count,
+                "readLength": cost.total.read_length,
+                "writeCount": cost.total.write_count,
+                "writeLength": cost.total.write_length,
+            })
+        });
+        Ok(json!({
+            "result": value_to_json(&result),
+            "repr": value_to_string(&result),
+            "events": execution.events.iter().map(serialize_event).collect::<Vec<_>>(),
+            "cost": cost,
+        }))
+    }
+
+    /// Handle a message, returns the response, if any, and whether the session should end.
+    /// Notifications (requests without an id) are processed but never answered.
+    pub fn handle_message(&mut self, message: &str) -> (Option<JsonValue>, bool) {
+        let request: Request = match serde_json::from_str(message) {
+            Ok(request) => request,
+            Err(e) => {
+                return (
+                    Some(error_response(JsonValue::Null, PARSE_ERROR, e.to_string())),
+                    false,
+                )
+            }
+        };
+        let id = request.id;
+        if request.jsonrpc != "2.0" {
+            let message = "only jsonrpc 2.0 is supported".to_string();
+            let response = id.map(|id| error_response(id, INVALID_REQUEST, message));
+            return (response, false);
+        }
+
+        let params = match request.params {
+            JsonValue::Null => json!({}),
+            params => params,
+        };
+        let result = match request.method.as_str() {
+            "check" => serde_json::from_value(params)
+                .map_err(invalid_params)
+                .and_then(|params| self.check(params)),
+            "test" => serde_json::from_value(params)
+                .map_err(invalid_params)
+                .and_then(|params| self.test(params)),
+            "generateDeployment" => serde_json::from_value(params)
+                .map_err(invalid_params)
+                .and_then(|params| self.generate_deployment(params)),
+            "callFunction" => serde_json::from_value(params)
+                .map_err(invalid_params)
+                .and_then(|params| self.call_function(params)),
+            "shutdown" => {
+                let response = id.map(|id| json!({ "jsonrpc": "2.0", "id": id, "result": null }));
+                return (response, true);
+            }
+            method => Err(RpcError {
+                code: METHOD_NOT_FOUND,
+                message: format!("unknown method {}", method),
+            }),
+        };
+
+        let response = id.map(|id| match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(e) => error_response(id, e.code, e.message),
+        });
+        (response, false)
+    }
+}
+
+fn error_response(id: JsonValue, code: i64, message: String) -> JsonValue {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
+
+fn eval_arg(session: &mut Session, arg: &str) -> Result<SymbolicExpression, String> {
+    let execution = session
+        .eval(arg.to_string(), false)
+        .map_err(|diagnostics| {
+            let messages: Vec<String> = diagnostics.into_iter().map(|d| d.message).collect();
+            format!("invalid argument {}: {}", arg, messages.join("\n"))
+        })?;
+    match execution.result {
+        EvaluationResult::Snippet(result) => Ok(SymbolicExpression::atom_value(result.result)),
+        EvaluationResult::Contract(_) => Err(format!("invalid argument {}", arg)),
+    }
+}
+
+fn diagnostic_to_json(contract_id: &str, diagnostic: &Diagnostic) -> JsonValue {
+    let level = match diagnostic.level {
+        Level::Note => "note",
+        Level::Warning => "warning",
+        Level::Error => "error",
+    };
+    let span = diagnostic.spans.first();
+    json!({
+        "contract": contract_id,
+        "level": level,
+        "message": diagnostic.message,
+        "line": span.map(|s| s.start_line),
+        "column": span.map(|s| s.start_column),
+    })
+}
+
+pub fn run_serve() -> Result<(), String> {
+    let mut server = Server::default();
+    let stdin = std::io::stdin();
+    let mut stdout = std::io::stdout();
+    for line in stdin.lock().lines() {
+        let line = line.map_err(|e| format!("unable to read stdin: {}", e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (response, shutdown) = server.handle_message(&line);
+        if let Some(response) = response {
+            writeln!(stdout, "{}", response)
+                .map_err(|e| format!("unable to write stdout: {}", e))?;
+            stdout
+                .flush()
+                .map_err(|e| format!("unable to write stdout: {}", e))?;
+        }
+        if shutdown {
+            break;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    /// Project in a temporary directory, removed when it is dropped
+    fn counter_project() -> TempDir {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("contracts")).unwrap();
+        fs::create_dir_all(root.join("settings")).unwrap();
+        fs::write(
+            root.join("Clarinet.toml"),
+            [
+                "[project]",
+                "name = 'counter'",
+                "telemetry = false",
+                "requirements = []",
+                "",
+                "[contracts.counter]",
+                "path = 'contracts/counter.clar'",
+                "clarity_version = 2",
+                "epoch = 2.5",
+            ]
+            .join("\n"),
+        )
+        .unwrap();
+        fs::write(
+            root.join("contracts/counter.clar"),
+            [
+                "(define-data-var count uint u0)",
+                "(define-public (increment (step uint))",
+                "  (begin",
+                "    (print { step: step })",
+                "    (ok (var-set count (+ (var-get count) step)))))",
+                "(define-read-only (get-count) (var-get count))",
+                "(define-public (test-increment)",
+                "  (begin (unwrap-panic (increment u2)) (asserts! (is-eq (get-count) u2) (err u1)) (ok true)))",
+                "(define-public (test-broken) (begin (asserts! false (err u2)) (ok true)))",
+            ]
+            .join("\n"),
+        )
+        .unwrap();
+        fs::write(
+            root.join("settings/Devnet.toml"),
+            [
+                "[network]",
+                "name = 'devnet'",
+                "",
+                "[accounts.deployer]",
+                "mnemonic = 'twice kind fence tip hidden tilt action fragile skin nothing glory cousin green tomorrow spring wrist shed math olympic multiply hip blue scout claw'",
+                "balance = 100_000_000_000_000",
+            ]
+            .join("\n"),
+        )
+        .unwrap();
+        temp_dir
+    }
+
+    fn request(id: u64, method: &str, params: JsonValue) -> String {
+        json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }).to_string()
+    }
+
+    #[test]
+    fn test_handle_invalid_messages() {
+        let mut server = Server::default();
+
+        let (response, shutdown) = server.handle_message("{");
+        assert_eq!(response.unwrap()["error"]["code"], PARSE_ERROR);
+        assert!(!shutdown);
+
+        let (response, _) =
+            server.handle_message(r#"{"jsonrpc":"2.0","id":1,"method":"deploy","params":{}}"#);
+        let response = response.unwrap();
+        assert_eq!(response["id"], 1);
+        assert_eq!(response["error"]["code"], METHOD_NOT_FOUND);
+
+        let (response, _) = server
+            .handle_message(r#"{"jsonrpc":"2.0","id":2,"method":"callFunction","params":{}}"#);
+        assert_eq!(response.unwrap()["error"]["code"], INVALID_PARAMS);
+
+        // notifications are never answered, even on errors
+        let (response, _) = server.handle_message(r#"{"jsonrpc":"2.0","method":"deploy"}"#);
+        assert!(response.is_none());
+
+        let (response, shutdown) =
+            server.handle_message(r#"{"jsonrpc":"2.0","id":3,"method":"shutdown"}"#);
+        assert_eq!(response.unwrap()["result"], JsonValue::Null);
+        assert!(shutdown);
+    }
+
+    #[test]
+    fn test_handle_project_requests() {
+        let root = counter_project();
+        let manifest_path = root
+            .path()
+            .join("Clarinet.toml")
+            .to_string_lossy()
+            .to_string();
+        let mut server = Server::default();
+
+        let (response, _) = server.handle_message(&request(
+            1,
+            "check",
+            json!({ "manifestPath": manifest_path }),
+        ));
+        let response = response.unwrap();
+        assert_eq!(response["id"], 1);
+        assert_eq!(response["result"]["success"], true);
+
+        let (response, _) = server.handle_message(&request(
+            2,
+            "callFunction",
+            json!({
+                "manifestPath": manifest_path,
+                "contract": "counter",
+                "method": "increment",
+                "args": ["u3"],
+            }),
+        ));
+        let result = &response.unwrap()["result"];
+        assert_eq!(result["repr"], "(ok true)");
+        assert_eq!(result["events"].as_array().unwrap().len(), 1);
+        assert!(result["cost"]["runtime"].as_u64().unwrap() > 0);
+
+        // calls are simulated, the next one starts from the deployed contracts again
+        let (response, _) = server.handle_message(&request(
+            3,
+            "callFunction",
+            json!({ "manifestPath": manifest_path, "contract": "counter", "method": "get-count" }),
+        ));
+        assert_eq!(response.unwrap()["result"]["repr"], "u0");
+
+        let (response, _) = server.handle_message(&request(
+            4,
+            "test",
+            json!({ "manifestPath": manifest_path }),
+        ));
+        let result = &response.unwrap()["result"];
+        assert_eq!(result["passed"], 1);
+        assert_eq!(result["failed"], 1);
+        let tests = result["tests"].as_array().unwrap();
+        let broken = tests.iter().find(|t| t["name"] == "test-broken").unwrap();
+        assert_eq!(broken["passed"], false);
+        assert!(broken["message"].is_string());
+
+        let (response, _) = server.handle_message(&request(
+            5,
+            "test",
+            json!({ "manifestPath": manifest_path, "filter": "increment" }),
+        ));
+        let result = &response.unwrap()["result"];
+        assert_eq!(result["passed"], 1);
+        assert_eq!(result["failed"], 0);
+
+        let (response, _) = server.handle_message(&request(
+            6,
+            "generateDeployment",
+            json!({ "manifestPath": manifest_path }),
+        ));
+        let plan = response.unwrap()["result"]["plan"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        assert!(plan.contains("contract-name: counter"));
+
+        let notification = json!({
+            "jsonrpc": "2.0",
+            "method": "check",
+            "params": { "manifestPath": manifest_path },
+        });
+        let (response, _) = server.handle_message(&notification.to_string());
+        assert!(response.is_none());
+    }
+}
@@ -0,0 +1,578 @@
+use clarity_repl::clarity::codec::StacksMessageCodec;
+use clarity_repl::clarity::util::hash::hex_bytes;
+use clarity_repl::clarity::vm::diagnostic::Diagnostic;
+use clarity_repl::clarity::vm::types::{QualifiedContractIdentifier, Value};
+use clarity_repl::clarity::vm::{EvaluationResult, ExecutionResult, SymbolicExpression};
+use clarity_repl::clarity::ClarityVersion;
+use clarity_repl::repl::clarity_values::{to_raw_value, value_to_json, value_to_string};
+use clarity_repl::repl::{ClarityCodeSource, ClarityContract, ContractDeployer, Session};
+use clarity_repl::utils::serialize_event;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use serde_json::Value as JsonValue;
+use stacks_codec::codec::{StacksTransaction, TransactionPayload};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+#[derive(Deserialize)]
+struct ReadOnlyCallRequest {
+    sender: String,
+    #[serde(default)]
+    arguments: Vec<String>,
+}
+
+type ApiResult = Result<JsonValue, (StatusCode, JsonValue)>;
+
+/// Subset of the Stacks Blockchain API backed by a simnet session, for developing
+/// frontends against instant and deterministic chain state. Each broadcasted
+/// transaction is executed right away and closes its block, transactions failing at
+/// runtime are recorded as aborted with their `vm_error`. Signatures, fees and
+/// post-conditions are not checked. Along with the hex encoded results, responses carry
+/// the `value_to_json` representation of the values, in a `value` field.
+pub struct SimnetApi {
+    session: Session,
+    nonces: HashMap<String, u64>,
+    transactions: HashMap<String, JsonValue>,
+}
+
+impl SimnetApi {
+    pub fn new(session: Session) -> Self {
+        SimnetApi {
+            session,
+            nonces: HashMap::new(),
+            transactions: HashMap::new(),
+        }
+    }
+
+    pub fn handle(&mut self, method: &Method, path: &str, body: &[u8]) -> ApiResult {
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+        match (method, segments.as_slice()) {
+            (&Method::GET, ["v2", "info"]) => Ok(self.get_info()),
+            (&Method::GET, ["v2", "accounts", principal]) => Ok(self.get_account(principal)),
+            (&Method::POST, ["v2", "contracts", "call-read", address, contract, function]) => {
+                self.call_read_only(address, contract, function, body)
+            }
+            (&Method::POST, ["v2", "transactions"]) => self.broadcast(body),
+            (&Method::GET, ["extended", "v1", "tx", txid]) => {
+                let txid = txid.trim_start_matches("0x");
+                self.transactions.get(txid).cloned().ok_or((
+                    StatusCode::NOT_FOUND,
+                    json!({ "error": format!("could not find transaction by ID {}", txid) }),
+                ))
+            }
+            _ => Err((
+                StatusCode::NOT_FOUND,
+                json!({ "error": format!("no route for {} {}", method, path) }),
+            )),
+        }
+    }
+
+    fn get_info(&mut self) -> JsonValue {
+        json!({
+            "network_id": 2147483648u32,
+            "stacks_tip_height": self.session.interpreter.get_block_height(),
+            "burn_block_height": self.session.interpreter.get_burn_block_height(),
+            "server_version": format!("clarinet-simnet/{}", env!("CARGO_PKG_VERSION")),
+        })
+    }
+
+    fn get_account(&self, principal: &str) -> JsonValue {
+        let balance = self
+            .session
+            .interpreter
+            .get_balance_for_account(principal, "STX");
+        json!({
+            "balance": format!("0x{:032x}", balance),
+            "locked": format!("0x{:032x}", 0),
+            "unlock_height": 0,
+            "nonce": self.nonces.get(principal).copied().unwrap_or(0),
+        })
+    }
+
+    fn call_read_only(
+        &mut self,
+        address: &str,
+        contract: &str,
+        function: &str,
+        body: &[u8],
+    ) -> ApiResult {
+        let request: ReadOnlyCallRequest = serde_json::from_slice(body).map_err(bad_request)?;
+        let contract_id = QualifiedContractIdentifier::parse(&format!("{}.{}", address, contract))
+            .map_err(bad_request)?;
+        let is_read_only = self
+            .session
+            .contracts
+            .get(&contract_id)
+            .map(|contract| {
+                contract
+                    .analysis
+                    .read_only_function_types
+                    .contains_key(function)
+            })
+            .ok_or((
+                StatusCode::NOT_FOUND,
+                json!({ "error": format!("contract {} not found", contract_id) }),
+            ))?;
+        if !is_read_only {
+            return Ok(json!({
+                "okay": false,
+                "cause": format!("{} is not a read-only function", function),
+            }));
+        }
+
+        let mut args = vec![];
+        for argument in request.arguments.iter() {
+            args.push(SymbolicExpression::atom_value(decode_value(argument)?));
+        }
+        // read-only calls never alter the chain state
+        let mut session = self.session.clone();
+        let execution = session.call_contract_fn(
+            &contract_id.to_string(),
+            function,
+            &args,
+            &request.sender,
+            false,
+            false,
+        );
+        Ok(match execution {
+            Ok(execution) => {
+                let result = execution_value(&execution);
+                json!({
+                    "okay": true,
+                    "result": to_raw_value(&result),
+                    "value": value_to_json(&result),
+                })
+            }
+            Err(diagnostics) => json!({
+                "okay": false,
+                "cause": diagnostics_message(diagnostics),
+            }),
+        })
+    }
+
+    fn broadcast(&mut self, body: &[u8]) -> ApiResult {
+        let transaction = StacksTransaction::consensus_deserialize(&mut &body[..])
+            .map_err(|e| rejected("Deserialization", e.to_string()))?;
+        let txid = transaction.txid().to_string();
+        let sender = transaction.origin_address().to_string();
+        let nonce = transaction.get_origin_nonce();
+        let expected_nonce = self.nonces.get(&sender).copied().unwrap_or(0);
+        if nonce != expected_nonce {
+            return Err(rejected(
+                "BadNonce",
+                format!("expected nonce {}, got {}", expected_nonce, nonce),
+            ));
+        }
+
+        let (tx_type, execution) = match &transaction.payload {
+            TransactionPayload::TokenTransfer(recipient, amount, _) => {
+                let initial_tx_sender = self.session.get_tx_sender();
+                self.session.set_tx_sender(&sender);
+                let execution = self.session.stx_transfer(*amount, &recipient.to_string());
+                self.session.set_tx_sender(&initial_tx_sender);
+                ("token_transfer", execution)
+            }
+            TransactionPayload::ContractCall(call) => {
+                let args: Vec<SymbolicExpression> = call
+                    .function_args
+                    .iter()
+                    .map(|arg| SymbolicExpression::atom_value(arg.clone()))
+                    .collect();
+                let execution = self.session.call_contract_fn(
+                    &format!("{}.{}", call.address, call.contract_name),
+                    &call.function_name,
+                    &args,
+                    &sender,
+                    false,
+                    false,
+                );
+                ("contract_call", execution)
+            }
+            TransactionPayload::SmartContract(contract, clarity_version) => {
+                let epoch = self.session.current_epoch;
+                let contract = ClarityContract {
+                    code_source: ClarityCodeSource::ContractInMemory(
+                        contract.code_body.to_string(),
+                    ),
+                    name: contract.name.to_string(),
+                    deployer: ContractDeployer::Address(sender.clone()),
+                    clarity_version: clarity_version
+                        .unwrap_or(ClarityVersion::default_for_epoch(epoch)),
+                    epoch,
+                };
+                (
+                    "smart_contract",
+                    self.session.deploy_contract(&contract, false, None),
+                )
+            }
+            payload => {
+                return Err(rejected(
+                    "UnsupportedPayload",
+                    format!("{} transactions are not supported", payload.name()),
+                ))
+            }
+        };
+        // like on a node, a transaction failing at runtime is mined as aborted
+        let (result, events, vm_error) = match execution {
+            Ok(execution) => {
+                let events: Vec<JsonValue> = execution.events.iter().map(serialize_event).collect();
+                (execution_value(&execution), events, None)
+            }
+            Err(diagnostics) => (
+                Value::err_none(),
+                vec![],
+                Some(diagnostics_message(diagnostics)),
+            ),
+        };
+        let tx_status = match result {
+            Value::Response(ref response) if !response.committed => "abort_by_response",
+            _ => "success",
+        };
+        let block_height = self.session.interpreter.get_block_height();
+        self.session.advance_chain_tip(1);
+        self.nonces.insert(sender.clone(), nonce + 1);
+        self.transactions.insert(
+            txid.clone(),
+            json!({
+                "tx_id": format!("0x{}", txid),
+                "tx_type": tx_type,
+                "tx_status": tx_status,
+                "sender_address": sender,
+                "nonce": nonce,
+                "block_height": block_height,
+                "tx_result": {
+                    "hex": to_raw_value(&result),
+                    "repr": value_to_string(&result),
+                    "value": value_to_json(&result),
+                },
+                "vm_error": vm_error,
+                "events": events,
+            }),
+        );
+        Ok(json!(txid))
+    }
+}
+
+/// Serve the API on `localhost:<port>` until the process is interrupted
+pub async fn start_simnet_api(api: SimnetApi, port: u16) -> Result<(), String> {
+    let addr = SocketAddr::from(([127, 0, 0, 1], port));
+    let api = Arc::new(Mutex::new(api));
+    let make_service = make_service_fn(move |_conn| {
+        let api = api.clone();
+        async move { Ok::<_, Infallible>(service_fn(move |req| handle_request(req, api.clone()))) }
+    });
+
+    Server::try_bind(&addr)
+        .map_err(|e| format!("unable to start simnet API on {}: {}", addr, e))?
+        .serve(make_service)
+        .await
+        .map_err(|e| format!("simnet API terminated: {}", e))
+}
+
+async fn handle_request(
+    req: Request<Body>,
+    api: Arc<Mutex<SimnetApi>>,
+) -> Result<Response<Body>, Infallible> {
+    let method = req.method().clone();
+    let path = req.uri().path().to_string();
+    // frontends are served from another origin
+    if method == Method::OPTIONS {
+        let response = with_cors_headers(Response::builder())
+            .status(StatusCode::NO_CONTENT)
+            .body(Body::empty())
+            .expect("unable to build response");
+        return Ok(response);
+    }
+    let body = match hyper::body::to_bytes(req.into_body()).await {
+        Ok(body) => body,
+        Err(e) => {
+            return Ok(json_response(
+                StatusCode::BAD_REQUEST,
+                json!({ "error": e.to_string() }),
+            ))
+        }
+    };
+
+    let result = match api.lock() {
+        Ok(mut api) => api.handle(&method, &path, &body),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            json!({ "error": e.to_string() }),
+        )),
+    };
+    let response = match result {
+        Ok(value) => json_response(StatusCode::OK, value),
+        Err((status, value)) => json_response(status, value),
+    };
+    Ok(response)
+}
+
+fn json_response(status: StatusCode, value: JsonValue) -> Response<Body> {
+    with_cors_headers(Response::builder())
+        .status(status)
+        .header("Content-Type", "application/json")
+        .body(Body::from(value.to_string()))
+        .expect("unable to build response")
+}
+
+fn with_cors_headers(builder: hyper::http::response::Builder) -> hyper::http::response::Builder {
+    builder
+        .header("Access-Control-Allow-Origin", "*")
+        .header("Access-Control-Allow-Methods", "GET, POST, OPTIONS")
+        .header("Access-Control-Allow-Headers", "*")
+}
+
+fn bad_request<E: std::fmt::Display>(e: E) -> (StatusCode, JsonValue) {
+    (StatusCode::BAD_REQUEST, json!({ "error": e.to_string() }))
+}
+
+/// Same body as the one of the stacks node when a transaction is refused by the mempool
+fn rejected(reason: &str, message: String) -> (StatusCode, JsonValue) {
+    (
+        StatusCode::BAD_REQUEST,
+        json!({
+            "error": "transaction rejected",
+            "reason": reason,
+            "reason_data": { "message": message },
+        }),
+    )
+}
+
+fn decode_value(hex: &str) -> Result<Value, (StatusCode, JsonValue)> {
+    let bytes = hex_bytes(hex.trim_start_matches("0x"))
+        .map_err(|e| bad_request(format!("invalid argument {}: {}", hex, e)))?;
+    Value::consensus_deserialize(&mut &bytes[..])
+        .map_err(|e| bad_request(format!("invalid argument {}: {}", hex, e)))
+}
+
+fn execution_value(execution: &ExecutionResult) -> Value {
+    match &execution.result {
+        EvaluationResult::Snippet(result) => result.result.clone(),
+        EvaluationResult::Contract(contract) => contract.result.clone().unwrap_or(Value::none()),
+    }
+}
+
+fn diagnostics_message(diagnostics: Vec<Diagnostic>) -> String {
+    let messages: Vec<String> = diagnostics.into_iter().map(|d| d.message).collect();
+    messages.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clarinet_deployments::onchain::{
+        encode_contract_call, encode_contract_publish, encode_stx_transfer,
+    };
+    use clarinet_files::{
+        compute_addresses, AccountConfig, StacksNetwork, DEFAULT_DERIVATION_PATH,
+    };
+    use clarity_repl::clarity::vm::types::PrincipalData;
+    use clarity_repl::clarity::vm::{ClarityName, ContractName};
+    use clarity_repl::clarity::StacksEpochId;
+    use clarity_repl::repl::post_conditions::PostConditions;
+    use clarity_repl::repl::SessionSettings;
+    use stacks_codec::codec::TransactionAnchorMode;
+
+    const DEPLOYER_MNEMONIC: &str = "twice kind fence tip hidden tilt action fragile skin nothing glory cousin green tomorrow spring wrist shed math olympic multiply hip blue scout claw";
+    const WALLET: &str = "ST2CY5V39NHDPWSXMW9QDT3HC3GD6Q6XX4CFRK9AG";
+
+    fn deployer_account() -> AccountConfig {
+        let (stx_address, btc_address, _) = compute_addresses(
+            DEPLOYER_MNEMONIC,
+            DEFAULT_DERIVATION_PATH,
+            &StacksNetwork::Devnet.get_networks(),
+        );
+        AccountConfig {
+            label: "deployer".to_string(),
+            mnemonic: DEPLOYER_MNEMONIC.to_string(),
+            derivation: DEFAULT_DERIVATION_PATH.to_string(),
+            secret_key: None,
+            balance: 1_000_000,
+            stx_address,
+            btc_address,
+            is_mainnet: false,
+        }
+    }
+
+    fn simnet_api(deployer: &AccountConfig) -> SimnetApi {
+        let mut session = Session::new(SessionSettings::default());
+        session.update_epoch(StacksEpochId::Epoch25);
+        session
+            .interpreter
+            .mint_stx_balance(
+                PrincipalData::parse(&deployer.stx_address).unwrap(),
+                deployer.balance,
+            )
+            .unwrap();
+        SimnetApi::new(session)
+    }
+
+    fn broadcast(api: &mut SimnetApi, transaction: StacksTransaction) -> String {
+        let mut body = vec![];
+        transaction.consensus_serialize(&mut body).unwrap();
+        let txid = api
+            .handle(&Method::POST, "/v2/transactions", &body)
+            .unwrap();
+        txid.as_str().unwrap().to_string()
+    }
+
+    fn get_transaction(api: &mut SimnetApi, txid: &str) -> JsonValue {
+        api.handle(&Method::GET, &format!("/extended/v1/tx/0x{}", txid), &[])
+            .unwrap()
+    }
+
+    #[test]
+    fn test_simnet_api_routes() {
+        let mut api = SimnetApi::new(Session::new(SessionSettings::default()));
+        let deployer = "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM";
+
+        let account = api
+            .handle(&Method::GET, &format!("/v2/accounts/{}", deployer), &[])
+            .unwrap();
+        assert_eq!(account["nonce"], 0);
+        assert_eq!(account["balance"], format!("0x{}", "0".repeat(32)));
+
+        let (status, _) = api
+            .handle(&Method::GET, "/extended/v1/tx/0x1234", &[])
+            .unwrap_err();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, body) = api
+            .handle(&Method::POST, "/v2/transactions", &[0, 1, 2])
+            .unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["reason"], "Deserialization");
+
+        let (status, _) = api.handle(&Method::DELETE, "/v2/info", &[]).unwrap_err();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn test_simnet_api_transactions() {
+        let deployer = deployer_account();
+        let mut api = simnet_api(&deployer);
+        let network = StacksNetwork::Devnet;
+        let anchor_mode = TransactionAnchorMode::Any;
+        let no_post_conditions = PostConditions::default();
+
+        let source = [
+            "(define-data-var count uint u0)",
+            "(define-public (increment) (ok (var-set count (+ (var-get count) u1))))",
+            "(define-public (explode) (begin (unwrap-panic (if true none (some u1))) (ok true)))",
+            "(define-read-only (get-count (offset uint)) (+ (var-get count) offset))",
+        ]
+        .join("\n");
+        let contract_name = ContractName::try_from("counter".to_string()).unwrap();
+        let publish = encode_contract_publish(
+            &contract_name,
+            &source,
+            None,
+            &deployer,
+            0,
+            0,
+            anchor_mode,
+            &network,
+        )
+        .unwrap();
+        let txid = broadcast(&mut api, publish);
+        assert_eq!(get_transaction(&mut api, &txid)["tx_status"], "success");
+
+        let transfer = encode_stx_transfer(
+            PrincipalData::parse(WALLET).unwrap(),
+            100,
+            [0u8; 34],
+            &deployer,
+            1,
+            0,
+            anchor_mode,
+            &network,
+        )
+        .unwrap();
+        let txid = broadcast(&mut api, transfer);
+        assert_eq!(
+            get_transaction(&mut api, &txid)["tx_type"],
+            "token_transfer"
+        );
+        let wallet = api
+            .handle(&Method::GET, &format!("/v2/accounts/{}", WALLET), &[])
+            .unwrap();
+        assert_eq!(wallet["balance"], format!("0x{:032x}", 100));
+
+        let contract_id =
+            QualifiedContractIdentifier::parse(&format!("{}.counter", deployer.stx_address))
+                .unwrap();
+        let call = |function: &str, nonce: u64| {
+            encode_contract_call(
+                &contract_id,
+                ClarityName::try_from(function.to_string()).unwrap(),
+                vec![],
+                &no_post_conditions,
+                &deployer,
+                nonce,
+                0,
+                anchor_mode,
+                &network,
+            )
+            .unwrap()
+        };
+        let txid = broadcast(&mut api, call("increment", 2));
+        let transaction = get_transaction(&mut api, &txid);
+        assert_eq!(transaction["tx_status"], "success");
+        assert_eq!(transaction["tx_result"]["repr"], "(ok true)");
+
+        // a runtime error is mined as an aborted transaction, and consumes the nonce
+        let txid = broadcast(&mut api, call("explode", 3));
+        let transaction = get_transaction(&mut api, &txid);
+        assert_eq!(transaction["tx_status"], "abort_by_response");
+        assert_eq!(transaction["tx_result"]["repr"], "(err none)");
+        assert!(transaction["vm_error"].is_string());
+        let account = api
+            .handle(
+                &Method::GET,
+                &format!("/v2/accounts/{}", deployer.stx_address),
+                &[],
+            )
+            .unwrap();
+        assert_eq!(account["nonce"], 4);
+
+        let mut stale = vec![];
+        call("increment", 2)
+            .consensus_serialize(&mut stale)
+            .unwrap();
+        let (status, body) = api
+            .handle(&Method::POST, "/v2/transactions", &stale)
+            .unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["reason"], "BadNonce");
+
+        let read_only = api
+            .handle(
+                &Method::POST,
+                &format!(
+                    "/v2/contracts/call-read/{}/counter/get-count",
+                    deployer.stx_address
+                ),
+                json!({ "sender": WALLET, "arguments": [to_raw_value(&Value::UInt(10))] })
+                    .to_string()
+                    .as_bytes(),
+            )
+            .unwrap();
+        assert_eq!(read_only["okay"], true);
+        assert_eq!(read_only["result"], to_raw_value(&Value::UInt(11)));
+        assert_eq!(read_only["value"], value_to_json(&Value::UInt(11)));
+
+        let not_read_only = api
+            .handle(
+                &Method::POST,
+                &format!(
+                    "/v2/contracts/call-read/{}/counter/increment",
+                    deployer.stx_address
+                ),
+                json!({ "sender": WALLET }).to_string().as_bytes(),
+            )
+            .unwrap();
+        assert_eq!(not_read_only["okay"], false);
+    }
+}
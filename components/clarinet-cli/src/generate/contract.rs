@@ -1,5 +1,6 @@
 use super::changes::{Changes, FileCreation, FileDeletion, TOMLEdition};
-use clarinet_files::FileLocation;
+use super::templates::ContractTemplate;
+use clarinet_files::{FileLocation, RequirementConfig};
 use clarity_repl::repl::{
     ClarityCodeSource, ClarityContract, ContractDeployer, DEFAULT_CLARITY_VERSION, DEFAULT_EPOCH,
 };
@@ -74,10 +75,34 @@ impl GetChangesForRmContract {
     }
 }
 
+const DEFAULT_TEST: &str = r#"
+import { describe, expect, it } from "vitest";
+
+const accounts = simnet.getAccounts();
+const address1 = accounts.get("wallet_1")!;
+
+/*
+  The test below is an example. To learn more, read the testing documentation here:
+  https://docs.hiro.so/stacks/clarinet-js-sdk
+*/
+
+describe("example tests", () => {
+  it("ensures simnet is well initalised", () => {
+    expect(simnet.blockHeight).toBeDefined();
+  });
+
+  // it("shows an example", () => {
+  //   const { result } = simnet.callReadOnlyFn("counter", "get-counter", [], address1);
+  //   expect(result).toBeUint(0);
+  // });
+});
+"#;
+
 pub struct GetChangesForNewContract {
     manifest_location: FileLocation,
     contract_name: String,
     source: Option<String>,
+    template: Option<ContractTemplate>,
     changes: Vec<Changes>,
 }
 
@@ -86,11 +111,13 @@ impl GetChangesForNewContract {
         manifest_location: FileLocation,
         contract_name: String,
         source: Option<String>,
+        template: Option<ContractTemplate>,
     ) -> Self {
         Self {
             manifest_location,
             contract_name: contract_name.replace('.', "_"),
             source,
+            template,
             changes: vec![],
         }
     }
@@ -107,6 +134,8 @@ impl GetChangesForNewContract {
     fn create_template_contract(&mut self) -> Result<(), String> {
         let content = if let Some(ref source) = self.source {
             source.to_string()
+        } else if let Some(template) = self.template {
+            template.source(&self.contract_name)
         } else {
             format!(
                 r#"
@@ -160,29 +189,10 @@ impl GetChangesForNewContract {
     }
 
     fn create_template_test(&mut self) -> Result<(), String> {
-        let content = r#"
-import { describe, expect, it } from "vitest";
-
-const accounts = simnet.getAccounts();
-const address1 = accounts.get("wallet_1")!;
-
-/*
-  The test below is an example. To learn more, read the testing documentation here:
-  https://docs.hiro.so/stacks/clarinet-js-sdk
-*/
-
-describe("example tests", () => {
-  it("ensures simnet is well initalised", () => {
-    expect(simnet.blockHeight).toBeDefined();
-  });
-
-  // it("shows an example", () => {
-  //   const { result } = simnet.callReadOnlyFn("counter", "get-counter", [], address1);
-  //   expect(result).toBeUint(0);
-  // });
-});
-"#
-        .into();
+        let content = match self.template {
+            Some(template) => template.test(&self.contract_name),
+            None => DEFAULT_TEST.into(),
+        };
 
         let name = format!("{}.test.ts", self.contract_name);
         let mut new_file = self.manifest_location.get_project_root_location().unwrap();
@@ -226,7 +236,13 @@ describe("example tests", () => {
             manifest_location,
             contracts_to_rm: vec![],
             contracts_to_add,
-            requirements_to_add: vec![],
+            requirements_to_add: self
+                .template
+                .map(|template| template.requirements())
+                .unwrap_or_default()
+                .into_iter()
+                .map(|contract_id| RequirementConfig { contract_id })
+                .collect(),
         };
         self.changes.push(Changes::EditTOML(change));
     }
@@ -1,11 +1,13 @@
 pub mod changes;
 mod contract;
 mod project;
+mod templates;
 
 pub use changes::Changes;
 use clarinet_files::FileLocation;
 use contract::GetChangesForNewContract;
 use project::GetChangesForNewProject;
+pub use templates::ContractTemplate;
 
 use self::contract::GetChangesForRmContract;
 
@@ -14,12 +16,14 @@ pub fn get_changes_for_new_project(
     project_name: String,
     use_current_dir: bool,
     telemetry_enabled: bool,
+    template: Option<ContractTemplate>,
 ) -> Result<Vec<Changes>, String> {
     let mut command = GetChangesForNewProject::new(
         project_path,
         project_name,
         use_current_dir,
         telemetry_enabled,
+        template,
     );
     command.run()
 }
@@ -28,10 +32,11 @@ pub fn get_changes_for_new_contract(
     manifest_location: &FileLocation,
     contract_name: String,
     source: Option<String>,
+    template: Option<ContractTemplate>,
     include_test: bool,
 ) -> Result<Vec<Changes>, String> {
     let mut command =
-        GetChangesForNewContract::new(manifest_location.clone(), contract_name, source);
+        GetChangesForNewContract::new(manifest_location.clone(), contract_name, source, template);
     command.run(include_test)
 }
 
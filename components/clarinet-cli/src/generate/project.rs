@@ -1,3 +1,4 @@
+use clarinet_files::FileLocation;
 use clarinet_files::{
     DEFAULT_BITCOIN_EXPLORER_IMAGE, DEFAULT_BITCOIN_NODE_IMAGE, DEFAULT_DERIVATION_PATH,
     DEFAULT_EPOCH_2_0, DEFAULT_EPOCH_2_05, DEFAULT_EPOCH_2_1, DEFAULT_EPOCH_2_2, DEFAULT_EPOCH_2_3,
@@ -9,6 +10,8 @@ use clarinet_files::{
 };
 
 use super::changes::{Changes, DirectoryCreation, FileCreation};
+use super::contract::GetChangesForNewContract;
+use super::templates::ContractTemplate;
 
 pub struct GetChangesForNewProject {
     project_path: String,
@@ -16,6 +19,7 @@ pub struct GetChangesForNewProject {
     use_current_dir: bool,
     changes: Vec<Changes>,
     telemetry_enabled: bool,
+    template: Option<ContractTemplate>,
 }
 
 impl GetChangesForNewProject {
@@ -24,6 +28,7 @@ impl GetChangesForNewProject {
         project_name: String,
         use_current_dir: bool,
         telemetry_enabled: bool,
+        template: Option<ContractTemplate>,
     ) -> Self {
         let project_path = if use_current_dir {
             project_path.clone()
@@ -37,6 +42,7 @@ impl GetChangesForNewProject {
             use_current_dir,
             changes: vec![],
             telemetry_enabled,
+            template,
         }
     }
 
@@ -57,9 +63,26 @@ impl GetChangesForNewProject {
         self.create_gitignore();
         self.create_gitattributes();
         self.create_nodejs_files();
+        if let Some(template) = self.template {
+            self.create_template_contract(template)?;
+        }
         Ok(self.changes.clone())
     }
 
+    /// Contract named after the project, generated from `template` with its test
+    fn create_template_contract(&mut self, template: ContractTemplate) -> Result<(), String> {
+        let manifest_location =
+            FileLocation::from_path_string(&format!("{}/Clarinet.toml", self.project_path))?;
+        let mut command = GetChangesForNewContract::new(
+            manifest_location,
+            self.project_name.clone(),
+            None,
+            Some(template),
+        );
+        self.changes.extend(command.run(true)?);
+        Ok(())
+    }
+
     fn create_root_directory(&mut self) {
         let change = DirectoryCreation {
             comment: format!("{} {}", green!("Created directory"), self.project_name),
@@ -182,6 +205,12 @@ check_checker = {{ trusted_sender = false, trusted_caller = false, callee_filter
 # warning, if it gets checked inside. This check will also propagate up to the
 # caller.
 # More informations: https://www.hiro.so/blog/new-safety-checks-in-clarinet
+
+# Network settings can be overridden per network, along with the contracts deployers:
+# [network.testnet]
+# stacks_node_rpc_address = "https://api.testnet.hiro.so"
+# deployment_cost_limit = 1000000
+# deployers = {{ counter = "deployer" }}
 "#,
             self.project_name, self.telemetry_enabled
         );
@@ -194,10 +223,14 @@ check_checker = {{ trusted_sender = false, trusted_caller = false, callee_filter
         let content = r#"[network]
 name = "testnet"
 stacks_node_rpc_address = "https://api.testnet.hiro.so"
+# nodes to fail over to when the one above is not answering:
+# stacks_node_rpc_fallback_addresses = ["http://localhost:20443"]
 deployment_fee_rate = 10
 
 [accounts.deployer]
 mnemonic = "<YOUR PRIVATE TESTNET MNEMONIC HERE>"
+# values can be read from environment variables, keeping secrets out of the repository:
+# mnemonic = "${TESTNET_DEPLOYER_MNEMONIC}"
 "#
         .into();
         let name = "settings/Testnet.toml".into();
@@ -209,10 +242,14 @@ mnemonic = "<YOUR PRIVATE TESTNET MNEMONIC HERE>"
         let content = r#"[network]
 name = "mainnet"
 stacks_node_rpc_address = "https://api.hiro.so"
+# nodes to fail over to when the one above is not answering:
+# stacks_node_rpc_fallback_addresses = ["http://localhost:20443"]
 deployment_fee_rate = 10
 
 [accounts.deployer]
 mnemonic = "<YOUR PRIVATE MAINNET MNEMONIC HERE>"
+# values can be read from environment variables, keeping secrets out of the repository:
+# mnemonic = "${MAINNET_DEPLOYER_MNEMONIC}"
 "#
         .into();
         let name = "settings/Mainnet.toml".into();
@@ -311,6 +348,9 @@ disable_stacks_api = false
 # stacker_mnemonic = "{default_stacks_stacker_mnemonic}"
 # stacker_derivation_path = "{default_derivation_path}"
 # orchestrator_port = 20445
+# orchestrator_control_port = 20446
+# faucet_account = "faucet"
+# orchestrator_metrics_port = 20447
 # bitcoin_node_p2p_port = 18444
 # bitcoin_node_rpc_port = 18443
 # bitcoin_node_username = "devnet"
@@ -379,6 +419,12 @@ wallet = "wallet_3"
 slots = 2
 btc_address = "mvZtbibDAAA3WLpY7zXXFqRa3T4XSknBX7"
 
+# Mint project tokens to accounts, once the contracts are deployed
+# [[devnet.genesis_mints]]
+# contract = "my-token"
+# recipient = "wallet_1"
+# amount = 1_000_000
+
 "#,
             default_derivation_path = DEFAULT_DERIVATION_PATH,
             default_bitcoin_node_image = DEFAULT_BITCOIN_NODE_IMAGE,
@@ -543,3 +589,47 @@ export default defineConfig({
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_project_with_template() {
+        let project_path = std::env::temp_dir().to_string_lossy().to_string();
+        let mut command = GetChangesForNewProject::new(
+            project_path.clone(),
+            "my-nft".to_string(),
+            false,
+            false,
+            Some(ContractTemplate::Sip009Nft),
+        );
+        let changes = command.run().unwrap();
+        let files: Vec<&str> = changes
+            .iter()
+            .filter_map(|change| match change {
+                Changes::AddFile(file) => Some(file.path.as_str()),
+                _ => None,
+            })
+            .collect();
+        let project_path = format!("{}/my-nft", project_path);
+        assert!(files.contains(&format!("{}/contracts/my-nft.clar", project_path).as_str()));
+        assert!(files.contains(&format!("{}/tests/my-nft.test.ts", project_path).as_str()));
+
+        // the contract is added to the manifest once it is created
+        let (position, edition) = changes
+            .iter()
+            .enumerate()
+            .find_map(|(position, change)| match change {
+                Changes::EditTOML(edition) => Some((position, edition)),
+                _ => None,
+            })
+            .unwrap();
+        assert!(edition.contracts_to_add.contains_key("my-nft"));
+        assert_eq!(edition.requirements_to_add.len(), 1);
+        let manifest_position = changes.iter().position(|change| {
+            matches!(change, Changes::AddFile(file) if file.path.ends_with("/Clarinet.toml"))
+        });
+        assert!(manifest_position.unwrap() < position);
+    }
+}
@@ -0,0 +1,365 @@
+/// Contracts implementing common patterns, generated with a test exercising them
+#[derive(PartialEq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum ContractTemplate {
+    Counter,
+    Sip009Nft,
+    Sip010Ft,
+}
+
+const SIP009_TRAIT: &str = "SP2PABAF9FTAJYNFZH93XENAJ8FVY99RRM50D2JG9.nft-trait";
+const SIP010_TRAIT: &str = "SP3FBR2AGK5H9QBDH3EEN6DF8EK8JY7RX8QJ5SVTE.sip-010-trait-ft-standard";
+
+impl ContractTemplate {
+    /// Mainnet contracts the template depends on, to be added as requirements
+    pub fn requirements(&self) -> Vec<String> {
+        match self {
+            ContractTemplate::Counter => vec![],
+            ContractTemplate::Sip009Nft => vec![SIP009_TRAIT.to_string()],
+            ContractTemplate::Sip010Ft => vec![SIP010_TRAIT.to_string()],
+        }
+    }
+
+    pub fn source(&self, name: &str) -> String {
+        match self {
+            ContractTemplate::Counter => format!(
+                r#"
+;; title: {name}
+;; summary: a counter that anyone can increment or decrement
+
+(define-constant ERR_UNDERFLOW (err u100))
+
+(define-data-var counter uint u0)
+
+(define-public (increment)
+  (ok (var-set counter (+ (var-get counter) u1)))
+)
+
+(define-public (decrement)
+  (let ((current (var-get counter)))
+    (asserts! (> current u0) ERR_UNDERFLOW)
+    (ok (var-set counter (- current u1)))
+  )
+)
+
+(define-read-only (get-counter)
+  (var-get counter)
+)
+"#
+            ),
+            ContractTemplate::Sip009Nft => format!(
+                r#"
+;; title: {name}
+;; summary: a SIP-009 non fungible token, minted by the deployer
+
+(impl-trait '{SIP009_TRAIT}.nft-trait)
+
+(define-constant CONTRACT_OWNER tx-sender)
+(define-constant ERR_OWNER_ONLY (err u100))
+(define-constant ERR_NOT_TOKEN_OWNER (err u101))
+
+(define-non-fungible-token {name} uint)
+
+(define-data-var last-token-id uint u0)
+
+(define-read-only (get-last-token-id)
+  (ok (var-get last-token-id))
+)
+
+(define-read-only (get-token-uri (token-id uint))
+  (ok none)
+)
+
+(define-read-only (get-owner (token-id uint))
+  (ok (nft-get-owner? {name} token-id))
+)
+
+(define-public (transfer (token-id uint) (sender principal) (recipient principal))
+  (begin
+    (asserts! (is-eq tx-sender sender) ERR_NOT_TOKEN_OWNER)
+    (nft-transfer? {name} token-id sender recipient)
+  )
+)
+
+(define-public (mint (recipient principal))
+  (let ((token-id (+ (var-get last-token-id) u1)))
+    (asserts! (is-eq tx-sender CONTRACT_OWNER) ERR_OWNER_ONLY)
+    (try! (nft-mint? {name} token-id recipient))
+    (var-set last-token-id token-id)
+    (ok token-id)
+  )
+)
+"#
+            ),
+            ContractTemplate::Sip010Ft => {
+                let token_name: String = name.chars().take(32).collect();
+                let symbol = token_name.to_uppercase();
+                format!(
+                    r#"
+;; title: {name}
+;; summary: a SIP-010 fungible token, minted by the deployer
+
+(impl-trait '{SIP010_TRAIT}.sip-010-trait)
+
+(define-constant CONTRACT_OWNER tx-sender)
+(define-constant ERR_OWNER_ONLY (err u100))
+(define-constant ERR_NOT_TOKEN_OWNER (err u101))
+
+(define-fungible-token {name})
+
+(define-public (transfer (amount uint) (sender principal) (recipient principal) (memo (optional (buff 34))))
+  (begin
+    (asserts! (is-eq tx-sender sender) ERR_NOT_TOKEN_OWNER)
+    (try! (ft-transfer? {name} amount sender recipient))
+    (match memo to-print (print to-print) 0x)
+    (ok true)
+  )
+)
+
+(define-read-only (get-name)
+  (ok "{token_name}")
+)
+
+(define-read-only (get-symbol)
+  (ok "{symbol}")
+)
+
+(define-read-only (get-decimals)
+  (ok u6)
+)
+
+(define-read-only (get-balance (who principal))
+  (ok (ft-get-balance {name} who))
+)
+
+(define-read-only (get-total-supply)
+  (ok (ft-get-supply {name}))
+)
+
+(define-read-only (get-token-uri)
+  (ok none)
+)
+
+(define-public (mint (amount uint) (recipient principal))
+  (begin
+    (asserts! (is-eq tx-sender CONTRACT_OWNER) ERR_OWNER_ONLY)
+    (ft-mint? {name} amount recipient)
+  )
+)
+"#
+                )
+            }
+        }
+    }
+
+    pub fn test(&self, name: &str) -> String {
+        let tests = match self {
+            ContractTemplate::Counter => format!(
+                r#"  it("increments the counter", () => {{
+    const {{ result }} = simnet.callPublicFn("{name}", "increment", [], address1);
+    expect(result).toBeOk(Cl.bool(true));
+
+    const counter = simnet.callReadOnlyFn("{name}", "get-counter", [], address1);
+    expect(counter.result).toBeUint(1);
+  }});
+
+  it("does not decrement below zero", () => {{
+    const {{ result }} = simnet.callPublicFn("{name}", "decrement", [], address1);
+    expect(result).toBeErr(Cl.uint(100));
+  }});"#
+            ),
+            ContractTemplate::Sip009Nft => format!(
+                r#"  it("mints tokens to the recipient", () => {{
+    const {{ result }} = simnet.callPublicFn("{name}", "mint", [Cl.principal(address1)], deployer);
+    expect(result).toBeOk(Cl.uint(1));
+
+    const owner = simnet.callReadOnlyFn("{name}", "get-owner", [Cl.uint(1)], deployer);
+    expect(owner.result).toBeOk(Cl.some(Cl.principal(address1)));
+  }});
+
+  it("only lets the deployer mint", () => {{
+    const {{ result }} = simnet.callPublicFn("{name}", "mint", [Cl.principal(address1)], address1);
+    expect(result).toBeErr(Cl.uint(100));
+  }});"#
+            ),
+            ContractTemplate::Sip010Ft => format!(
+                r#"  it("transfers tokens", () => {{
+    simnet.callPublicFn("{name}", "mint", [Cl.uint(1000), Cl.principal(address1)], deployer);
+
+    const {{ result }} = simnet.callPublicFn(
+      "{name}",
+      "transfer",
+      [Cl.uint(100), Cl.principal(address1), Cl.principal(address2), Cl.none()],
+      address1,
+    );
+    expect(result).toBeOk(Cl.bool(true));
+
+    const balance = simnet.callReadOnlyFn("{name}", "get-balance", [Cl.principal(address2)], address1);
+    expect(balance.result).toBeOk(Cl.uint(100));
+  }});
+
+  it("only lets the deployer mint", () => {{
+    const {{ result }} = simnet.callPublicFn("{name}", "mint", [Cl.uint(1), Cl.principal(address1)], address1);
+    expect(result).toBeErr(Cl.uint(100));
+  }});"#
+            ),
+        };
+        format!(
+            r#"
+import {{ Cl }} from "@stacks/transactions";
+import {{ describe, expect, it }} from "vitest";
+
+const accounts = simnet.getAccounts();
+const deployer = accounts.get("deployer")!;
+const address1 = accounts.get("wallet_1")!;
+const address2 = accounts.get("wallet_2")!;
+
+describe("{name}", () => {{
+{tests}
+}});
+"#
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clarity_repl::clarity::vm::EvaluationResult;
+    use clarity_repl::clarity::{ClarityVersion, StacksEpochId};
+    use clarity_repl::repl::clarity_values::value_to_string;
+    use clarity_repl::repl::{
+        ClarityCodeSource, ClarityContract, ContractDeployer, Session, SessionSettings,
+    };
+
+    const NFT_TRAIT: &str = r#"
+(define-trait nft-trait
+  (
+    (get-last-token-id () (response uint uint))
+    (get-token-uri (uint) (response (optional (string-ascii 256)) uint))
+    (get-owner (uint) (response (optional principal) uint))
+    (transfer (uint principal principal) (response bool uint))
+  )
+)"#;
+
+    const FT_TRAIT: &str = r#"
+(define-trait sip-010-trait
+  (
+    (transfer (uint principal principal (optional (buff 34))) (response bool uint))
+    (get-name () (response (string-ascii 32) uint))
+    (get-symbol () (response (string-ascii 32) uint))
+    (get-decimals () (response uint uint))
+    (get-balance (principal) (response uint uint))
+    (get-total-supply () (response uint uint))
+    (get-token-uri () (response (optional (string-utf8 256)) uint))
+  )
+)"#;
+
+    fn deploy(session: &mut Session, deployer: ContractDeployer, name: &str, source: String) {
+        let contract = ClarityContract {
+            code_source: ClarityCodeSource::ContractInMemory(source),
+            name: name.to_string(),
+            deployer,
+            clarity_version: ClarityVersion::Clarity2,
+            epoch: StacksEpochId::Epoch25,
+        };
+        if let Err(diagnostics) = session.deploy_contract(&contract, false, None) {
+            panic!("unable to deploy {}: {:?}", name, diagnostics);
+        }
+    }
+
+    /// Session with the SIP traits deployed at their mainnet addresses
+    fn session() -> Session {
+        let mut session = Session::new(SessionSettings::default());
+        session.update_epoch(StacksEpochId::Epoch25);
+        for (requirement, source) in [(SIP009_TRAIT, NFT_TRAIT), (SIP010_TRAIT, FT_TRAIT)] {
+            let (address, name) = requirement.split_once('.').unwrap();
+            let deployer = ContractDeployer::Address(address.to_string());
+            deploy(&mut session, deployer, name, source.to_string());
+        }
+        session
+    }
+
+    fn deploy_template(session: &mut Session, template: ContractTemplate, name: &str) {
+        let deployer = ContractDeployer::DefaultDeployer;
+        deploy(session, deployer, name, template.source(name));
+    }
+
+    fn eval(session: &mut Session, snippet: &str) -> String {
+        match session.eval(snippet.to_string(), false) {
+            Ok(execution) => match execution.result {
+                EvaluationResult::Snippet(result) => value_to_string(&result.result),
+                EvaluationResult::Contract(_) => unreachable!(),
+            },
+            Err(diagnostics) => panic!("unable to evaluate {}: {:?}", snippet, diagnostics),
+        }
+    }
+
+    #[test]
+    fn test_counter_template() {
+        let mut session = session();
+        deploy_template(&mut session, ContractTemplate::Counter, "counter");
+
+        assert_eq!(
+            eval(&mut session, "(contract-call? .counter decrement)"),
+            "(err u100)"
+        );
+        assert_eq!(
+            eval(&mut session, "(contract-call? .counter increment)"),
+            "(ok true)"
+        );
+        assert_eq!(
+            eval(&mut session, "(contract-call? .counter get-counter)"),
+            "u1"
+        );
+    }
+
+    #[test]
+    fn test_sip009_nft_template() {
+        let mut session = session();
+        deploy_template(&mut session, ContractTemplate::Sip009Nft, "my-nft");
+
+        let mint = "(contract-call? .my-nft mint tx-sender)";
+        assert_eq!(eval(&mut session, mint), "(ok u1)");
+        assert_eq!(
+            eval(&mut session, "(contract-call? .my-nft get-last-token-id)"),
+            "(ok u1)"
+        );
+    }
+
+    #[test]
+    fn test_sip010_ft_template() {
+        let mut session = session();
+        deploy_template(&mut session, ContractTemplate::Sip010Ft, "my-token");
+
+        let mint = "(contract-call? .my-token mint u1000 tx-sender)";
+        assert_eq!(eval(&mut session, mint), "(ok true)");
+        assert_eq!(
+            eval(&mut session, "(contract-call? .my-token get-symbol)"),
+            "(ok \"MY-TOKEN\")"
+        );
+        assert_eq!(
+            eval(&mut session, "(contract-call? .my-token get-total-supply)"),
+            "(ok u1000)"
+        );
+    }
+
+    #[test]
+    fn test_template_tests() {
+        for template in [
+            ContractTemplate::Counter,
+            ContractTemplate::Sip009Nft,
+            ContractTemplate::Sip010Ft,
+        ] {
+            let test = template.test("my-contract");
+            assert!(test.contains("describe(\"my-contract\", () => {\n"));
+            assert!(test.contains("simnet.callPublicFn(\"my-contract\""));
+            assert!(!test.contains("{{") && !test.contains("}}"));
+        }
+        assert_eq!(
+            ContractTemplate::Sip009Nft.requirements(),
+            vec![SIP009_TRAIT.to_string()]
+        );
+        assert!(ContractTemplate::Counter.requirements().is_empty());
+    }
+}
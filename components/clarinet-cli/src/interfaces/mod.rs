@@ -0,0 +1,329 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use clarity_repl::clarity::vm::analysis::ContractAnalysis;
+use clarity_repl::clarity::vm::types::{
+    FunctionType, SequenceSubtype, StringSubtype, TypeSignature,
+};
+
+/// Keywords and strict mode reserved words, `arguments` and `eval` can't be bound either
+const RESERVED_WORDS: [&str; 48] = [
+    "arguments",
+    "await",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "enum",
+    "eval",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "function",
+    "if",
+    "implements",
+    "import",
+    "in",
+    "instanceof",
+    "interface",
+    "let",
+    "new",
+    "null",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "static",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "var",
+    "void",
+    "while",
+    "with",
+    "yield",
+];
+
+/// Interface of the contract (functions, variables, maps and tokens), as saved on-chain
+pub fn contract_interface_to_json(analysis: &ContractAnalysis) -> Result<String, String> {
+    let Some(ref interface) = analysis.contract_interface else {
+        return Err(format!(
+            "interface of {} not available",
+            analysis.contract_identifier
+        ));
+    };
+    serde_json::to_string_pretty(interface)
+        .map_err(|e| format!("unable to serialize interface: {}", e))
+}
+
+/// `token-id` becomes `tokenId`
+fn to_camel_case(name: &str) -> String {
+    let mut identifier = String::new();
+    let mut upper = false;
+    for c in name.chars() {
+        match c {
+            c if !c.is_alphanumeric() && c != '_' => upper = !identifier.is_empty(),
+            c if upper => {
+                identifier.extend(c.to_uppercase());
+                upper = false;
+            }
+            c => identifier.push(c),
+        }
+    }
+    if RESERVED_WORDS.contains(&identifier.as_str()) {
+        identifier.push('_');
+    }
+    identifier
+}
+
+/// `token-id` becomes `TokenId`
+fn to_pascal_case(name: &str) -> String {
+    let identifier = to_camel_case(name);
+    let mut chars = identifier.trim_end_matches('_').chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => identifier,
+    }
+}
+
+/// `base`, suffixed with a counter when already taken (`getA`, `getA_2`)
+fn unique_identifier(base: String, used: &mut BTreeSet<String>) -> String {
+    let mut identifier = base.clone();
+    let mut counter = 1;
+    while used.contains(&identifier) {
+        counter += 1;
+        identifier = format!("{}_{}", base, counter);
+    }
+    used.insert(identifier.clone());
+    identifier
+}
+
+/// Type of the `@stacks/transactions` values matching `signature`
+fn clarity_value_type(signature: &TypeSignature, imports: &mut BTreeSet<&'static str>) -> String {
+    let (import, generic) = match signature {
+        TypeSignature::IntType => ("IntCV", None),
+        TypeSignature::UIntType => ("UIntCV", None),
+        TypeSignature::BoolType => ("BooleanCV", None),
+        TypeSignature::PrincipalType => ("PrincipalCV", None),
+        TypeSignature::CallableType(_)
+        | TypeSignature::ListUnionType(_)
+        | TypeSignature::TraitReferenceType(_) => ("ContractPrincipalCV", None),
+        TypeSignature::SequenceType(SequenceSubtype::BufferType(_)) => ("BufferCV", None),
+        TypeSignature::SequenceType(SequenceSubtype::StringType(StringSubtype::ASCII(_))) => {
+            ("StringAsciiCV", None)
+        }
+        TypeSignature::SequenceType(SequenceSubtype::StringType(StringSubtype::UTF8(_))) => {
+            ("StringUtf8CV", None)
+        }
+        TypeSignature::SequenceType(SequenceSubtype::ListType(list)) => (
+            "ListCV",
+            Some(clarity_value_type(list.get_list_item_type(), imports)),
+        ),
+        TypeSignature::OptionalType(inner) => {
+            ("OptionalCV", Some(clarity_value_type(inner, imports)))
+        }
+        TypeSignature::ResponseType(types) => {
+            imports.insert("ResponseOkCV");
+            imports.insert("ResponseErrorCV");
+            return format!(
+                "ResponseOkCV<{}> | ResponseErrorCV<{}>",
+                clarity_value_type(&types.0, imports),
+                clarity_value_type(&types.1, imports)
+            );
+        }
+        TypeSignature::TupleType(tuple) => {
+            let fields: Vec<String> = tuple
+                .get_type_map()
+                .iter()
+                .map(|(name, field)| {
+                    format!("\"{}\": {}", name, clarity_value_type(field, imports))
+                })
+                .collect();
+            ("TupleCV", Some(format!("{{ {} }}", fields.join("; "))))
+        }
+        TypeSignature::NoType => return "never".to_string(),
+        _ => ("ClarityValue", None),
+    };
+    imports.insert(import);
+    match generic {
+        Some(generic) => format!("{}<{}>", import, generic),
+        None => import.to_string(),
+    }
+}
+
+/// Typed wrappers of the public and read-only functions of the contract, returning the
+/// options of `makeContractCall` / `fetchCallReadOnlyFunction` from `@stacks/transactions`.
+/// `addresses` are the deployers of the contract by network, the wrappers target the first
+/// one unless told otherwise. Names colliding once converted to camel case are suffixed
+/// (`get-a` and `get-a?` give `getA` and `getA_2`).
+pub fn contract_typescript_bindings(
+    analysis: &ContractAnalysis,
+    addresses: &[(String, String)],
+) -> String {
+    let contract_id = &analysis.contract_identifier;
+    let default_network = addresses
+        .first()
+        .map(|(network, _)| network.as_str())
+        .unwrap_or("devnet");
+    let mut imports = BTreeSet::new();
+    let mut functions = vec![];
+    let mut identifiers =
+        BTreeSet::from(["contractAddresses".to_string(), "contractName".to_string()]);
+    let mut types = BTreeSet::from(["Network".to_string()]);
+    for (access, function_types) in [
+        ("public", &analysis.public_function_types),
+        ("read-only", &analysis.read_only_function_types),
+    ] {
+        for (name, function_type) in function_types.iter() {
+            let FunctionType::Fixed(function) = function_type else {
+                continue;
+            };
+            let mut arg_names = BTreeSet::new();
+            let args: Vec<(String, String)> = function
+                .args
+                .iter()
+                .map(|arg| {
+                    (
+                        unique_identifier(to_camel_case(&arg.name), &mut arg_names),
+                        clarity_value_type(&arg.signature, &mut imports),
+                    )
+                })
+                .collect();
+            let identifier = unique_identifier(to_camel_case(name), &mut identifiers);
+            let result_name =
+                unique_identifier(format!("{}Result", to_pascal_case(&identifier)), &mut types);
+            let result_type = clarity_value_type(&function.returns, &mut imports);
+            let network_param = format!("network: Network = \"{}\"", default_network);
+            let (params, function_args) = match args.is_empty() {
+                true => (network_param, String::new()),
+                false => (
+                    format!(
+                        "args: {{ {} }}, {}",
+                        args.iter()
+                            .map(|(name, cv_type)| format!("{}: {}", name, cv_type))
+                            .collect::<Vec<_>>()
+                            .join("; "),
+                        network_param
+                    ),
+                    args.iter()
+                        .map(|(name, _)| format!("args.{}", name))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                ),
+            };
+            functions.push(format!(
+                r#"
+/** `{name}` ({access}), returns `{returns}` */
+export type {result_name} = {result_type};
+
+export function {identifier}({params}) {{
+  return {{
+    contractAddress: contractAddresses[network],
+    contractName,
+    functionName: "{name}",
+    functionArgs: [{function_args}],
+  }};
+}}
+"#,
+                returns = function.returns,
+            ));
+        }
+    }
+
+    let mut bindings =
+        String::from("// generated by `clarinet contracts interfaces`, do not edit\n");
+    if !imports.is_empty() {
+        let imports: Vec<&str> = imports.into_iter().collect();
+        bindings.push_str(&format!(
+            "import type {{ {} }} from \"@stacks/transactions\";\n\n",
+            imports.join(", ")
+        ));
+    }
+    bindings.push_str("export const contractAddresses = {\n");
+    for (network, address) in addresses.iter() {
+        bindings.push_str(&format!("  {}: \"{}\",\n", network, address));
+    }
+    bindings.push_str("};\nexport type Network = keyof typeof contractAddresses;\n");
+    bindings.push_str(&format!(
+        "export const contractName = \"{}\";\n",
+        contract_id.name
+    ));
+    for function in functions.into_iter() {
+        bindings.push_str(&function);
+    }
+    bindings
+}
+
+#[cfg(test)]
+mod tests {
+    use clarity_repl::clarity::vm::EvaluationResult;
+    use clarity_repl::clarity::StacksEpochId;
+    use clarity_repl::repl::{Session, SessionSettings};
+
+    use super::*;
+
+    #[test]
+    fn test_contract_typescript_bindings() {
+        let mut session = Session::new(SessionSettings::default());
+        session.update_epoch(StacksEpochId::Epoch25);
+        let snippet = [
+            "(define-map owners uint principal)",
+            "(define-public (transfer (token-id uint) (recipient principal))",
+            "  (ok (map-set owners token-id recipient)))",
+            "(define-read-only (get-owner (token-id uint)) (map-get? owners token-id))",
+            "(define-read-only (get-owner? (token-id uint)) (is-some (map-get? owners token-id)))",
+            "(define-read-only (contract-name) \"tokens\")",
+            "(define-read-only (static) u1)",
+        ]
+        .join("\n");
+        let analysis = match session.eval(snippet, false).unwrap().result {
+            EvaluationResult::Contract(evaluation) => evaluation.contract.analysis,
+            _ => unreachable!(),
+        };
+
+        let addresses = [
+            ("devnet", analysis.contract_identifier.issuer.to_string()),
+            (
+                "testnet",
+                "ST2CY5V39NHDPWSXMW9QDT3HC3GD6Q6XX4CFRK9AG".to_string(),
+            ),
+        ]
+        .map(|(network, address)| (network.to_string(), address));
+        let bindings = contract_typescript_bindings(&analysis, &addresses);
+        assert!(bindings.contains(
+            "import type { BooleanCV, OptionalCV, PrincipalCV, ResponseErrorCV, ResponseOkCV, StringAsciiCV, UIntCV } from \"@stacks/transactions\";"
+        ));
+        assert!(bindings.contains("  testnet: \"ST2CY5V39NHDPWSXMW9QDT3HC3GD6Q6XX4CFRK9AG\",\n"));
+        assert!(bindings.contains(
+            "export function transfer(args: { tokenId: UIntCV; recipient: PrincipalCV }, network: Network = \"devnet\")"
+        ));
+        assert!(bindings.contains("contractAddress: contractAddresses[network],"));
+        assert!(bindings.contains("functionArgs: [args.tokenId, args.recipient],"));
+        assert!(bindings.contains("export type GetOwnerResult = OptionalCV<PrincipalCV>;"));
+        // names colliding once in camel case
+        assert!(bindings.contains("export type GetOwner_2Result = BooleanCV;"));
+        assert!(bindings.contains("export function getOwner_2(args: { tokenId: UIntCV }"));
+        assert!(bindings.contains("export function contractName_2(network: Network = \"devnet\")"));
+        // strict mode reserved words
+        assert!(bindings.contains("export function static_(network: Network = \"devnet\")"));
+        assert!(bindings.contains(
+            "export type TransferResult = ResponseOkCV<BooleanCV> | ResponseErrorCV<never>;"
+        ));
+
+        let json = contract_interface_to_json(&analysis).unwrap();
+        assert!(json.contains("\"name\": \"get-owner\""));
+    }
+}
@@ -14,7 +14,10 @@ extern crate lazy_static;
 pub extern crate clarity_repl;
 
 pub mod deployments;
+pub mod docs;
 pub mod generate;
+pub mod interfaces;
+pub mod notebook;
 
 pub mod devnet;
 #[cfg(feature = "cli")]
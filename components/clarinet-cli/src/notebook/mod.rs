@@ -0,0 +1,353 @@
+use clarity_repl::clarity::costs::ExecutionCost;
+use clarity_repl::clarity::vm::diagnostic::Diagnostic;
+use clarity_repl::clarity::vm::{EvaluationResult, ExecutionResult};
+use clarity_repl::clarity::ClarityVersion;
+use clarity_repl::repl::clarity_values::value_to_string;
+use clarity_repl::repl::{ClarityCodeSource, ClarityContract, ContractDeployer, Session};
+use clarity_repl::utils::serialize_event;
+
+const CLARITY_FENCES: [&str; 2] = ["clarity", "clar"];
+
+/// Cell of a notebook: markdown text, or a fenced ```clarity code block. A code block
+/// named in its info string (` ```clarity counter`) is deployed as a contract of that name.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NotebookCell {
+    Markdown(String),
+    Clarity {
+        contract_name: Option<String>,
+        code: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CellOutput {
+    /// Value returned by the cell, or the error messages
+    pub result: Result<String, String>,
+    pub events: Vec<serde_json::Value>,
+    pub cost: Option<ExecutionCost>,
+}
+
+/// Cells of a markdown notebook, in order
+pub fn parse_notebook(source: &str) -> Vec<NotebookCell> {
+    let mut cells = vec![];
+    let mut markdown: Vec<&str> = vec![];
+    let mut code: Option<(Option<String>, Vec<&str>)> = None;
+    for line in source.lines() {
+        match code {
+            Some((ref contract_name, ref mut lines)) => {
+                if line.trim_end() == "```" {
+                    cells.push(NotebookCell::Clarity {
+                        contract_name: contract_name.clone(),
+                        code: lines.join("\n"),
+                    });
+                    code = None;
+                } else {
+                    lines.push(line);
+                }
+            }
+            None => {
+                let mut info = line
+                    .strip_prefix("```")
+                    .unwrap_or_default()
+                    .split_whitespace();
+                match info.next() {
+                    Some(language) if CLARITY_FENCES.contains(&language) => {
+                        let text = markdown.join("\n");
+                        if !text.trim().is_empty() {
+                            cells.push(NotebookCell::Markdown(text.trim().to_string()));
+                        }
+                        markdown.clear();
+                        code = Some((info.next().map(|name| name.to_string()), vec![]));
+                    }
+                    _ => markdown.push(line),
+                }
+            }
+        }
+    }
+    // an unterminated code block is kept as markdown
+    if let Some((_, lines)) = code {
+        markdown.push("```clarity");
+        markdown.extend(lines);
+    }
+    let text = markdown.join("\n");
+    if !text.trim().is_empty() {
+        cells.push(NotebookCell::Markdown(text.trim().to_string()));
+    }
+    cells
+}
+
+fn cell_output(execution: Result<ExecutionResult, Vec<Diagnostic>>) -> CellOutput {
+    match execution {
+        Ok(execution) => {
+            let result = match execution.result {
+                EvaluationResult::Snippet(result) => value_to_string(&result.result),
+                EvaluationResult::Contract(result) => {
+                    format!("{} deployed", result.contract.analysis.contract_identifier)
+                }
+            };
+            CellOutput {
+                result: Ok(result),
+                events: execution.events.iter().map(serialize_event).collect(),
+                cost: execution.cost.map(|cost| cost.total),
+            }
+        }
+        Err(diagnostics) => CellOutput {
+            result: Err(diagnostics
+                .into_iter()
+                .map(|d| d.message)
+                .collect::<Vec<_>>()
+                .join("\n")),
+            events: vec![],
+            cost: None,
+        },
+    }
+}
+
+/// Execute the code cells one after the other in `session`, each cell sees the changes
+/// made by the previous ones. Markdown cells have no output.
+pub fn run_notebook(session: &mut Session, cells: &[NotebookCell]) -> Vec<Option<CellOutput>> {
+    let mut outputs = vec![];
+    for cell in cells.iter() {
+        let NotebookCell::Clarity {
+            contract_name,
+            code,
+        } = cell
+        else {
+            outputs.push(None);
+            continue;
+        };
+        let execution = match contract_name {
+            Some(name) => {
+                let contract = ClarityContract {
+                    code_source: ClarityCodeSource::ContractInMemory(code.clone()),
+                    name: name.clone(),
+                    deployer: ContractDeployer::DefaultDeployer,
+                    clarity_version: ClarityVersion::default_for_epoch(session.current_epoch),
+                    epoch: session.current_epoch,
+                };
+                session.deploy_contract(&contract, true, None)
+            }
+            None => session.eval(code.clone(), true),
+        };
+        outputs.push(Some(cell_output(execution)));
+    }
+    outputs
+}
+
+fn cost_to_string(cost: &ExecutionCost) -> String {
+    format!(
+        "runtime {}, read count {}, read length {}, write count {}, write length {}",
+        cost.runtime, cost.read_count, cost.read_length, cost.write_count, cost.write_length
+    )
+}
+
+pub fn notebook_to_markdown(cells: &[NotebookCell], outputs: &[Option<CellOutput>]) -> String {
+    let mut output = vec![];
+    for (cell, cell_output) in cells.iter().zip(outputs.iter()) {
+        match cell {
+            NotebookCell::Markdown(text) => output.push(format!("{}\n", text)),
+            NotebookCell::Clarity {
+                contract_name,
+                code,
+            } => {
+                let info = match contract_name {
+                    Some(name) => format!("clarity {}", name),
+                    None => "clarity".to_string(),
+                };
+                output.push(format!("```{}\n{}\n```\n", info, code));
+            }
+        }
+        let Some(cell_output) = cell_output else {
+            continue;
+        };
+        match &cell_output.result {
+            Ok(result) => output.push(format!("```\n{}\n```\n", result)),
+            Err(message) => output.push(format!("Error:\n\n```\n{}\n```\n", message)),
+        }
+        if !cell_output.events.is_empty() {
+            output.push("Events:\n".to_string());
+            for event in cell_output.events.iter() {
+                output.push(format!("- `{}`", event));
+            }
+            output.push(String::new());
+        }
+        if let Some(cost) = &cell_output.cost {
+            output.push(format!("Cost: {}\n", cost_to_string(cost)));
+        }
+    }
+    output.join("\n")
+}
+
+fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Headings and paragraphs of a markdown cell, the rest of the syntax is kept as text
+fn markdown_to_html(text: &str) -> String {
+    let mut html = vec![];
+    for block in text.split("\n\n") {
+        let block = block.trim();
+        if block.is_empty() {
+            continue;
+        }
+        let level = block.chars().take_while(|c| *c == '#').count();
+        match level {
+            1..=6 if block[level..].starts_with(' ') && !block.contains('\n') => {
+                html.push(format!(
+                    "<h{0}>{1}</h{0}>",
+                    level,
+                    escape_html(block[level..].trim())
+                ))
+            }
+            _ => html.push(format!("<p>{}</p>", escape_html(block))),
+        }
+    }
+    html.join("\n")
+}
+
+pub fn notebook_to_html(
+    title: &str,
+    cells: &[NotebookCell],
+    outputs: &[Option<CellOutput>],
+) -> String {
+    let mut body = vec![];
+    for (cell, cell_output) in cells.iter().zip(outputs.iter()) {
+        match cell {
+            NotebookCell::Markdown(text) => body.push(markdown_to_html(text)),
+            NotebookCell::Clarity { code, .. } => body.push(format!(
+                "<pre class=\"cell\"><code class=\"language-clarity\">{}</code></pre>",
+                escape_html(code)
+            )),
+        }
+        let Some(cell_output) = cell_output else {
+            continue;
+        };
+        match &cell_output.result {
+            Ok(result) => body.push(format!(
+                "<pre class=\"output\">{}</pre>",
+                escape_html(result)
+            )),
+            Err(message) => body.push(format!(
+                "<pre class=\"output error\">{}</pre>",
+                escape_html(message)
+            )),
+        }
+        if !cell_output.events.is_empty() {
+            let events: Vec<String> = cell_output
+                .events
+                .iter()
+                .map(|event| format!("<li><code>{}</code></li>", escape_html(&event.to_string())))
+                .collect();
+            body.push(format!(
+                "<ul class=\"events\">\n{}\n</ul>",
+                events.join("\n")
+            ));
+        }
+        if let Some(cost) = &cell_output.cost {
+            body.push(format!(
+                "<p class=\"cost\">Cost: {}</p>",
+                escape_html(&cost_to_string(cost))
+            ));
+        }
+    }
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>
+body {{ font-family: sans-serif; max-width: 800px; margin: auto; }}
+pre {{ padding: 8px; background: #f5f5f5; overflow-x: auto; }}
+pre.output {{ border-left: 3px solid #5546ff; }}
+pre.error {{ border-left-color: #d32f2f; }}
+.cost {{ color: #666; font-size: small; }}
+</style>
+</head>
+<body>
+{body}
+</body>
+</html>
+"#,
+        title = escape_html(title),
+        body = body.join("\n"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clarity_repl::clarity::StacksEpochId;
+    use clarity_repl::repl::SessionSettings;
+
+    #[test]
+    fn test_run_notebook() {
+        let source = [
+            "# Counter",
+            "",
+            "Deploy the counter:",
+            "",
+            "```clarity counter",
+            "(define-data-var count uint u0)",
+            "(define-public (increment)",
+            "  (begin",
+            "    (print { action: \"increment\" })",
+            "    (ok (var-set count (+ (var-get count) u1)))))",
+            "(define-read-only (get-count) (var-get count))",
+            "```",
+            "",
+            "```clarity",
+            "(contract-call? .counter increment)",
+            "```",
+            "",
+            "```clarity",
+            "(contract-call? .counter get-count)",
+            "```",
+            "",
+            "```clarity",
+            "(contract-call? .counter decrement)",
+            "```",
+        ]
+        .join("\n");
+        let cells = parse_notebook(&source);
+        assert_eq!(cells.len(), 5);
+        assert_eq!(
+            cells[0],
+            NotebookCell::Markdown("# Counter\n\nDeploy the counter:".to_string())
+        );
+        assert!(matches!(
+            &cells[1],
+            NotebookCell::Clarity { contract_name: Some(name), .. } if name == "counter"
+        ));
+
+        let mut session = Session::new(SessionSettings::default());
+        session.update_epoch(StacksEpochId::Epoch25);
+        let outputs = run_notebook(&mut session, &cells);
+        assert!(outputs[0].is_none());
+        let deployment = outputs[1].as_ref().unwrap();
+        assert!(deployment
+            .result
+            .as_ref()
+            .unwrap()
+            .ends_with(".counter deployed"));
+        let increment = outputs[2].as_ref().unwrap();
+        assert_eq!(increment.result, Ok("(ok true)".to_string()));
+        assert_eq!(increment.events.len(), 1);
+        assert!(increment.cost.is_some());
+        let get_count = outputs[3].as_ref().unwrap();
+        assert_eq!(get_count.result, Ok("u1".to_string()));
+        assert!(outputs[4].as_ref().unwrap().result.is_err());
+
+        let markdown = notebook_to_markdown(&cells, &outputs);
+        assert!(markdown.contains("```clarity counter\n(define-data-var count uint u0)"));
+        assert!(markdown.contains("```\nu1\n```"));
+        let html = notebook_to_html("counter", &cells, &outputs);
+        assert!(html.contains("<h1>Counter</h1>"));
+        assert!(html.contains("<pre class=\"output\">u1</pre>"));
+        assert!(html.contains("<pre class=\"output error\">"));
+    }
+}
@@ -0,0 +1,169 @@
+use clarity_repl::clarity::vm::ast::ContractAST;
+use clarity_repl::clarity::vm::types::QualifiedContractIdentifier;
+use clarity_repl::clarity::vm::SymbolicExpression;
+use std::collections::BTreeMap;
+
+use crate::types::{DeploymentSpecification, EpochSpec, TransactionSpecification};
+
+/// Maximum length of a transaction accepted by stacks nodes.
+pub const MAX_TRANSACTION_LEN: usize = 1024 * 1024;
+/// Contracts are reported once their publish transaction reaches 80% of the limit.
+const WARNING_THRESHOLD: usize = MAX_TRANSACTION_LEN / 10 * 8;
+/// Length of a single-sig transaction without post-conditions, payload excluded:
+/// version, chain id, auth (type, hash mode, signer, nonce, fee, key encoding, signature),
+/// anchor mode, post-condition mode and post-conditions count.
+const TRANSACTION_OVERHEAD_LEN: usize = 1 + 4 + (1 + 1 + 20 + 8 + 8 + 1 + 65) + 1 + 1 + 4;
+const LARGEST_DEFINITIONS_COUNT: usize = 5;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContractSizeReport {
+    pub contract_id: QualifiedContractIdentifier,
+    pub transaction_len: usize,
+    /// Top-level definitions, sorted by decreasing source length.
+    pub largest_definitions: Vec<(String, usize)>,
+}
+
+impl ContractSizeReport {
+    pub fn exceeds_limit(&self) -> bool {
+        self.transaction_len > MAX_TRANSACTION_LEN
+    }
+}
+
+/// Length of the serialized contract-publish transaction of a contract.
+pub fn estimate_contract_publish_len(contract_name: &str, source: &str, versioned: bool) -> usize {
+    // payload type, (clarity version), contract name (len prefixed), code body (len prefixed)
+    let payload_len = 1 + usize::from(versioned) + (1 + contract_name.len()) + (4 + source.len());
+    TRANSACTION_OVERHEAD_LEN + payload_len
+}
+
+fn definition_name(expr: &SymbolicExpression) -> Option<String> {
+    let list = expr.match_list()?;
+    let kind = list.first()?.match_atom()?;
+    let name = match list.get(1)?.match_atom() {
+        Some(name) => name,
+        None => list.get(1)?.match_list()?.first()?.match_atom()?,
+    };
+    Some(format!("({} {})", kind, name))
+}
+
+fn largest_definitions(source: &str, ast: &ContractAST) -> Vec<(String, usize)> {
+    let mut line_offsets = vec![0];
+    for (i, c) in source.char_indices() {
+        if c == '\n' {
+            line_offsets.push(i + 1);
+        }
+    }
+    let offset = |line: u32, column: u32| {
+        line_offsets
+            .get(line.saturating_sub(1) as usize)
+            .map(|start| start + column.saturating_sub(1) as usize)
+    };
+
+    let mut definitions = vec![];
+    for expr in ast.expressions.iter() {
+        let (Some(name), Some(start), Some(end)) = (
+            definition_name(expr),
+            offset(expr.span.start_line, expr.span.start_column),
+            offset(expr.span.end_line, expr.span.end_column),
+        ) else {
+            continue;
+        };
+        definitions.push((name, end.saturating_sub(start) + 1));
+    }
+    definitions.sort_by(|a, b| b.1.cmp(&a.1));
+    definitions.truncate(LARGEST_DEFINITIONS_COUNT);
+    definitions
+}
+
+/// Report the contracts of a deployment plan whose publish transaction is close to,
+/// or over, the maximum transaction length, along with their largest definitions.
+pub fn check_contract_sizes(
+    deployment: &DeploymentSpecification,
+    asts: &BTreeMap<QualifiedContractIdentifier, ContractAST>,
+) -> Vec<ContractSizeReport> {
+    let mut reports = vec![];
+    for batch in deployment.plan.batches.iter() {
+        for transaction in batch.transactions.iter() {
+            let (contract_id, source) = match transaction {
+                TransactionSpecification::ContractPublish(tx) => (
+                    QualifiedContractIdentifier::new(
+                        tx.expected_sender.clone(),
+                        tx.contract_name.clone(),
+                    ),
+                    &tx.source,
+                ),
+                TransactionSpecification::EmulatedContractPublish(tx) => (
+                    QualifiedContractIdentifier::new(
+                        tx.emulated_sender.clone(),
+                        tx.contract_name.clone(),
+                    ),
+                    &tx.source,
+                ),
+                _ => continue,
+            };
+            let versioned = !matches!(batch.epoch, Some(epoch) if epoch < EpochSpec::Epoch2_1);
+            let transaction_len =
+                estimate_contract_publish_len(&contract_id.name, source, versioned);
+            if transaction_len < WARNING_THRESHOLD {
+                continue;
+            }
+            let largest_definitions = asts
+                .get(&contract_id)
+                .map(|ast| largest_definitions(source, ast))
+                .unwrap_or_default();
+            reports.push(ContractSizeReport {
+                contract_id,
+                transaction_len,
+                largest_definitions,
+            });
+        }
+    }
+    reports
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clarity_repl::repl::{
+        ClarityCodeSource, ClarityContract, ContractDeployer, Session, SessionSettings,
+        DEFAULT_CLARITY_VERSION, DEFAULT_EPOCH,
+    };
+
+    #[test]
+    fn test_largest_definitions() {
+        let source = [
+            "(define-constant ERR_UNAUTHORIZED (err u1))",
+            "(define-data-var counter uint u0)",
+            "(define-public (increment)\n  (ok (var-set counter (+ (var-get counter) u1))))",
+        ]
+        .join("\n");
+        let contract = ClarityContract {
+            code_source: ClarityCodeSource::ContractInMemory(source.clone()),
+            name: "counter".into(),
+            deployer: ContractDeployer::DefaultDeployer,
+            clarity_version: DEFAULT_CLARITY_VERSION,
+            epoch: DEFAULT_EPOCH,
+        };
+        let mut session = Session::new(SessionSettings::default());
+        let (ast, _, success) = session.interpreter.build_ast(&contract);
+        assert!(success);
+
+        assert_eq!(
+            largest_definitions(&source, &ast),
+            vec![
+                ("(define-public increment)".to_string(), 77),
+                ("(define-constant ERR_UNAUTHORIZED)".to_string(), 43),
+                ("(define-data-var counter)".to_string(), 33),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_estimate_contract_publish_len() {
+        let source = "(define-read-only (get-one) u1)";
+        assert_eq!(
+            estimate_contract_publish_len("one", source, true),
+            TRANSACTION_OVERHEAD_LEN + 1 + 1 + 4 + 4 + source.len()
+        );
+    }
+}
@@ -0,0 +1,452 @@
+use clarity_repl::clarity::costs::ExecutionCost;
+use clarity_repl::clarity::vm::types::{
+    FixedFunction, SequenceSubtype, StringSubtype, TypeSignature,
+};
+use clarity_repl::clarity::vm::{EvaluationResult, SymbolicExpression};
+use clarity_repl::repl::interpreter::BLOCK_LIMIT_MAINNET;
+use clarity_repl::repl::Session;
+
+/// How an argument of the analyzed function is generated, for each point of the grid
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ArgumentSpec {
+    /// Fixed argument, as a Clarity expression
+    Value(String),
+    /// List made of `item` repeated for each size
+    List {
+        item: String,
+        sizes: Vec<u32>,
+    },
+    Buff {
+        sizes: Vec<u32>,
+    },
+    StringAscii {
+        sizes: Vec<u32>,
+    },
+    StringUtf8 {
+        sizes: Vec<u32>,
+    },
+}
+
+impl ArgumentSpec {
+    fn sizes(&self) -> Option<&Vec<u32>> {
+        match self {
+            ArgumentSpec::Value(_) => None,
+            ArgumentSpec::List { sizes, .. }
+            | ArgumentSpec::Buff { sizes }
+            | ArgumentSpec::StringAscii { sizes }
+            | ArgumentSpec::StringUtf8 { sizes } => Some(sizes),
+        }
+    }
+
+    fn expression(&self, size: u32) -> String {
+        let size = size as usize;
+        match self {
+            ArgumentSpec::Value(value) => value.clone(),
+            ArgumentSpec::List { item, .. } => {
+                let items = format!(" {}", item).repeat(size);
+                format!("(list{})", items)
+            }
+            ArgumentSpec::Buff { .. } => format!("0x{}", "00".repeat(size)),
+            ArgumentSpec::StringAscii { .. } => format!("\"{}\"", "a".repeat(size)),
+            ArgumentSpec::StringUtf8 { .. } => format!("u\"{}\"", "a".repeat(size)),
+        }
+    }
+}
+
+/// Arguments file of `clarinet contracts costs`, one spec per argument of the function
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ArgumentsSpec {
+    pub args: Vec<ArgumentSpec>,
+}
+
+impl ArgumentsSpec {
+    pub fn from_yaml(content: &[u8]) -> Result<ArgumentsSpec, String> {
+        serde_yaml::from_slice(content)
+            .map_err(|e| format!("unable to parse arguments spec: {}", e))
+    }
+
+    pub fn to_yaml(&self) -> Result<String, String> {
+        serde_yaml::to_string(self)
+            .map_err(|e| format!("unable to serialize arguments spec: {}", e))
+    }
+}
+
+fn sizes_grid(max_len: u32) -> Vec<u32> {
+    let mut sizes = vec![1, max_len / 4, max_len / 2, max_len / 4 * 3, max_len];
+    sizes.retain(|size| *size > 0);
+    sizes.dedup();
+    sizes
+}
+
+fn default_value(signature: &TypeSignature) -> String {
+    match signature {
+        TypeSignature::IntType => "1".to_string(),
+        TypeSignature::UIntType => "u1".to_string(),
+        TypeSignature::BoolType => "true".to_string(),
+        TypeSignature::PrincipalType => "tx-sender".to_string(),
+        TypeSignature::OptionalType(_) => "none".to_string(),
+        TypeSignature::SequenceType(SequenceSubtype::BufferType(_)) => "0x00".to_string(),
+        TypeSignature::SequenceType(SequenceSubtype::StringType(StringSubtype::ASCII(_))) => {
+            "\"a\"".to_string()
+        }
+        TypeSignature::SequenceType(SequenceSubtype::StringType(StringSubtype::UTF8(_))) => {
+            "u\"a\"".to_string()
+        }
+        // left for the author to fill
+        signature => signature.to_string(),
+    }
+}
+
+/// Spec covering up to the declared maximum length of each sequence argument of `function`
+pub fn scaffold_arguments_spec(function: &FixedFunction) -> ArgumentsSpec {
+    let args = function
+        .args
+        .iter()
+        .map(|arg| match &arg.signature {
+            TypeSignature::SequenceType(SequenceSubtype::ListType(list)) => ArgumentSpec::List {
+                item: default_value(list.get_list_item_type()),
+                sizes: sizes_grid(list.get_max_len()),
+            },
+            TypeSignature::SequenceType(SequenceSubtype::BufferType(len)) => ArgumentSpec::Buff {
+                sizes: sizes_grid(len.into()),
+            },
+            TypeSignature::SequenceType(SequenceSubtype::StringType(StringSubtype::ASCII(len))) => {
+                ArgumentSpec::StringAscii {
+                    sizes: sizes_grid(len.into()),
+                }
+            }
+            TypeSignature::SequenceType(SequenceSubtype::StringType(StringSubtype::UTF8(len))) => {
+                ArgumentSpec::StringUtf8 {
+                    sizes: sizes_grid(len.into()),
+                }
+            }
+            signature => ArgumentSpec::Value(default_value(signature)),
+        })
+        .collect();
+    ArgumentsSpec { args }
+}
+
+/// Check that each spec matches the type of the argument it generates, and that the
+/// sizes fit in the declared maximum lengths.
+pub fn validate_arguments_spec(
+    function: &FixedFunction,
+    spec: &ArgumentsSpec,
+) -> Result<(), Vec<String>> {
+    let mut errors = vec![];
+    if function.args.len() != spec.args.len() {
+        errors.push(format!(
+            "expected {} arguments, got {}",
+            function.args.len(),
+            spec.args.len()
+        ));
+        return Err(errors);
+    }
+    for (arg, arg_spec) in function.args.iter().zip(spec.args.iter()) {
+        let max_len: Option<u32> = match (&arg.signature, arg_spec) {
+            (_, ArgumentSpec::Value(_)) => None,
+            (
+                TypeSignature::SequenceType(SequenceSubtype::ListType(list)),
+                ArgumentSpec::List { .. },
+            ) => Some(list.get_max_len()),
+            (
+                TypeSignature::SequenceType(SequenceSubtype::BufferType(len)),
+                ArgumentSpec::Buff { .. },
+            ) => Some(len.into()),
+            (
+                TypeSignature::SequenceType(SequenceSubtype::StringType(StringSubtype::ASCII(len))),
+                ArgumentSpec::StringAscii { .. },
+            ) => Some(len.into()),
+            (
+                TypeSignature::SequenceType(SequenceSubtype::StringType(StringSubtype::UTF8(len))),
+                ArgumentSpec::StringUtf8 { .. },
+            ) => Some(len.into()),
+            (signature, _) => {
+                errors.push(format!(
+                    "argument {}: spec does not match type {}",
+                    arg.name, signature
+                ));
+                continue;
+            }
+        };
+        let (Some(max_len), Some(sizes)) = (max_len, arg_spec.sizes()) else {
+            continue;
+        };
+        if sizes.is_empty() {
+            errors.push(format!("argument {}: no sizes", arg.name));
+        }
+        for size in sizes.iter().filter(|size| **size > max_len) {
+            errors.push(format!(
+                "argument {}: size {} exceeds the maximum length {}",
+                arg.name, size, max_len
+            ));
+        }
+    }
+    match errors.is_empty() {
+        true => Ok(()),
+        false => Err(errors),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CostPoint {
+    /// Size of each sized argument
+    pub sizes: Vec<u32>,
+    pub cost: Result<ExecutionCost, String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CostCurve {
+    /// Names of the sized arguments
+    pub arguments: Vec<String>,
+    pub points: Vec<CostPoint>,
+}
+
+/// Highest share of the mainnet block limit used by `cost`, across all dimensions, in percent
+pub fn block_limit_share(cost: &ExecutionCost) -> f64 {
+    [
+        (cost.runtime, BLOCK_LIMIT_MAINNET.runtime),
+        (cost.read_count, BLOCK_LIMIT_MAINNET.read_count),
+        (cost.read_length, BLOCK_LIMIT_MAINNET.read_length),
+        (cost.write_count, BLOCK_LIMIT_MAINNET.write_count),
+        (cost.write_length, BLOCK_LIMIT_MAINNET.write_length),
+    ]
+    .iter()
+    .map(|(used, limit)| *used as f64 * 100.0 / *limit as f64)
+    .fold(0.0, f64::max)
+}
+
+fn eval_argument(session: &mut Session, expression: String) -> Result<SymbolicExpression, String> {
+    let execution = session.eval(expression, false).map_err(|diagnostics| {
+        let messages: Vec<String> = diagnostics.into_iter().map(|d| d.message).collect();
+        messages.join("\n")
+    })?;
+    match execution.result {
+        EvaluationResult::Snippet(result) => Ok(SymbolicExpression::atom_value(result.result)),
+        _ => unreachable!("Contract result from snippet"),
+    }
+}
+
+/// Call `function` for each combination of the sizes of the spec. Each call is
+/// performed on a copy of `session`, so that the points do not depend on each other.
+pub fn compute_cost_curve(
+    session: &Session,
+    contract_id: &str,
+    function: &FixedFunction,
+    function_name: &str,
+    spec: &ArgumentsSpec,
+    sender: &str,
+) -> CostCurve {
+    let mut arguments = vec![];
+    let mut grid: Vec<Vec<u32>> = vec![vec![]];
+    for (arg, arg_spec) in function.args.iter().zip(spec.args.iter()) {
+        let Some(sizes) = arg_spec.sizes() else {
+            continue;
+        };
+        arguments.push(arg.name.to_string());
+        grid = grid
+            .into_iter()
+            .flat_map(|point| {
+                sizes.iter().map(move |size| {
+                    let mut point = point.clone();
+                    point.push(*size);
+                    point
+                })
+            })
+            .collect();
+    }
+
+    let mut points = vec![];
+    for sizes in grid.into_iter() {
+        let mut call_session = session.clone();
+        let mut remaining_sizes = sizes.iter();
+        let mut args = vec![];
+        let mut cost = Ok(ExecutionCost::zero());
+        for arg_spec in spec.args.iter() {
+            let size = match arg_spec.sizes() {
+                Some(_) => *remaining_sizes.next().unwrap(),
+                None => 0,
+            };
+            match eval_argument(&mut call_session, arg_spec.expression(size)) {
+                Ok(arg) => args.push(arg),
+                Err(e) => cost = Err(e),
+            }
+        }
+        if cost.is_ok() {
+            cost = call_session
+                .call_contract_fn(contract_id, function_name, &args, sender, false, true)
+                .map_err(|diagnostics| {
+                    let messages: Vec<String> =
+                        diagnostics.into_iter().map(|d| d.message).collect();
+                    messages.join("\n")
+                })
+                .map(|execution| {
+                    execution
+                        .cost
+                        .map(|cost| cost.total)
+                        .unwrap_or_else(ExecutionCost::zero)
+                });
+        }
+        points.push(CostPoint { sizes, cost });
+    }
+    CostCurve { arguments, points }
+}
+
+impl CostCurve {
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.points
+            .iter()
+            .map(|point| {
+                let mut row: Vec<String> = point.sizes.iter().map(|s| s.to_string()).collect();
+                match &point.cost {
+                    Ok(cost) => row.extend([
+                        cost.runtime.to_string(),
+                        cost.read_count.to_string(),
+                        cost.read_length.to_string(),
+                        cost.write_count.to_string(),
+                        cost.write_length.to_string(),
+                        format!("{:.2}", block_limit_share(cost)),
+                    ]),
+                    Err(e) => row.push(format!("error: {}", e.replace('\n', " "))),
+                }
+                row
+            })
+            .collect()
+    }
+
+    fn headers(&self) -> Vec<String> {
+        let mut headers = self.arguments.clone();
+        headers.extend(
+            [
+                "runtime",
+                "read_count",
+                "read_length",
+                "write_count",
+                "write_length",
+                "block_limit_pct",
+            ]
+            .map(String::from),
+        );
+        headers
+    }
+
+    /// Data of the curve, meant for plotting tools
+    pub fn to_csv(&self) -> String {
+        let mut output = vec![self.headers().join(",")];
+        for row in self.rows() {
+            output.push(row.join(","));
+        }
+        output.push(String::new());
+        output.join("\n")
+    }
+
+    pub fn to_table(&self) -> String {
+        let headers = self.headers();
+        let rows = self.rows();
+        let mut widths: Vec<usize> = headers.iter().map(|h| h.len()).collect();
+        for row in rows.iter() {
+            for (i, cell) in row.iter().enumerate().take(widths.len()) {
+                widths[i] = widths[i].max(cell.len());
+            }
+        }
+        let format_row = |row: &[String]| {
+            row.iter()
+                .enumerate()
+                .map(|(i, cell)| match widths.get(i) {
+                    Some(width) => format!("{:>width$}", cell, width = width),
+                    None => cell.clone(),
+                })
+                .collect::<Vec<_>>()
+                .join("  ")
+        };
+        let mut output = vec![format_row(&headers)];
+        for row in rows.iter() {
+            output.push(format_row(row));
+        }
+        output.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clarity_repl::clarity::vm::types::FunctionType;
+    use clarity_repl::clarity::StacksEpochId;
+    use clarity_repl::repl::SessionSettings;
+
+    #[test]
+    fn test_cost_curve() {
+        let mut session = Session::new(SessionSettings::default());
+        session.update_epoch(StacksEpochId::Epoch25);
+        let snippet = [
+            "(define-read-only (sum (items (list 100 uint)) (memo (buff 34)) (offset uint))",
+            "  (fold + items offset))",
+        ]
+        .join("\n");
+        let analysis = match session.eval(snippet, false).unwrap().result {
+            EvaluationResult::Contract(evaluation) => evaluation.contract.analysis,
+            _ => unreachable!(),
+        };
+        let contract_id = analysis.contract_identifier.to_string();
+        let Some((_, FunctionType::Fixed(function))) = analysis
+            .read_only_function_types
+            .iter()
+            .find(|(name, _)| name.as_str() == "sum")
+        else {
+            unreachable!()
+        };
+
+        let spec = scaffold_arguments_spec(function);
+        assert_eq!(
+            spec.args,
+            vec![
+                ArgumentSpec::List {
+                    item: "u1".to_string(),
+                    sizes: vec![1, 25, 50, 75, 100],
+                },
+                ArgumentSpec::Buff {
+                    sizes: vec![1, 8, 17, 24, 34],
+                },
+                ArgumentSpec::Value("u1".to_string()),
+            ]
+        );
+        assert!(validate_arguments_spec(function, &spec).is_ok());
+
+        let invalid = ArgumentsSpec {
+            args: vec![
+                ArgumentSpec::List {
+                    item: "u1".to_string(),
+                    sizes: vec![200],
+                },
+                ArgumentSpec::StringAscii { sizes: vec![1] },
+                ArgumentSpec::Value("u1".to_string()),
+            ],
+        };
+        assert_eq!(
+            validate_arguments_spec(function, &invalid)
+                .unwrap_err()
+                .len(),
+            2
+        );
+
+        let spec = ArgumentsSpec {
+            args: vec![
+                ArgumentSpec::List {
+                    item: "u1".to_string(),
+                    sizes: vec![1, 10, 100],
+                },
+                ArgumentSpec::Buff { sizes: vec![1, 34] },
+                ArgumentSpec::Value("u0".to_string()),
+            ],
+        };
+        let sender = session.get_tx_sender();
+        let curve = compute_cost_curve(&session, &contract_id, function, "sum", &spec, &sender);
+        assert_eq!(curve.arguments, vec!["items", "memo"]);
+        assert_eq!(curve.points.len(), 6);
+        assert_eq!(curve.points[5].sizes, vec![100, 34]);
+        let runtime = |point: &CostPoint| point.cost.as_ref().unwrap().runtime;
+        assert!(runtime(&curve.points[0]) < runtime(&curve.points[2]));
+        assert!(runtime(&curve.points[2]) < runtime(&curve.points[4]));
+        assert!(curve.to_csv().starts_with("items,memo,runtime,"));
+    }
+}
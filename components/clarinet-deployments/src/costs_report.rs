@@ -0,0 +1,143 @@
+use std::collections::BTreeMap;
+
+use clarity_repl::clarity::costs::ExecutionCost;
+
+/// Cost of a call recorded by the sdk (`vitest run -- --costs`)
+#[derive(Deserialize, Debug, Clone)]
+pub struct RecordedCallCost {
+    pub contract_id: String,
+    pub method: String,
+    pub cost_result: RecordedCost,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RecordedCost {
+    pub total: ExecutionCost,
+    pub limit: ExecutionCost,
+}
+
+/// Highest costs of the calls of a function, compared to the block limits
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct FunctionCosts {
+    pub contract_id: String,
+    pub method: String,
+    pub calls: usize,
+    pub max: ExecutionCost,
+    pub limit: ExecutionCost,
+    /// Percentage of the most consumed dimension of the block limit
+    pub max_percentage: f64,
+}
+
+fn percentage(value: u64, limit: u64) -> f64 {
+    match limit {
+        0 => 0.0,
+        limit => value as f64 * 100.0 / limit as f64,
+    }
+}
+
+impl FunctionCosts {
+    fn update_percentage(&mut self) {
+        let (max, limit) = (&self.max, &self.limit);
+        self.max_percentage = [
+            percentage(max.runtime, limit.runtime),
+            percentage(max.read_count, limit.read_count),
+            percentage(max.read_length, limit.read_length),
+            percentage(max.write_count, limit.write_count),
+            percentage(max.write_length, limit.write_length),
+        ]
+        .into_iter()
+        .fold(0.0, f64::max);
+    }
+}
+
+/// Parse a costs report file, the reports of each test file can be nested in their own list
+pub fn parse_costs_report(content: &str) -> Result<Vec<RecordedCallCost>, String> {
+    let value: serde_json::Value = serde_json::from_str(content)
+        .map_err(|e| format!("unable to parse costs report: {}", e))?;
+    let mut entries = vec![];
+    let serde_json::Value::Array(items) = value else {
+        return Err("unable to parse costs report: expected a list".to_string());
+    };
+    for item in items {
+        let nested = match item {
+            serde_json::Value::Array(nested) => nested,
+            item => vec![item],
+        };
+        for entry in nested {
+            entries.push(
+                serde_json::from_value(entry)
+                    .map_err(|e| format!("unable to parse costs report: {}", e))?,
+            );
+        }
+    }
+    Ok(entries)
+}
+
+/// Group the recorded calls by function, keeping the highest value of each cost dimension
+pub fn summarize_costs(calls: &[RecordedCallCost]) -> Vec<FunctionCosts> {
+    let mut functions: BTreeMap<(&str, &str), FunctionCosts> = BTreeMap::new();
+    for call in calls.iter() {
+        let total = &call.cost_result.total;
+        let function = functions
+            .entry((call.contract_id.as_str(), call.method.as_str()))
+            .or_insert_with(|| FunctionCosts {
+                contract_id: call.contract_id.clone(),
+                method: call.method.clone(),
+                calls: 0,
+                max: ExecutionCost::zero(),
+                limit: call.cost_result.limit.clone(),
+                max_percentage: 0.0,
+            });
+        function.calls += 1;
+        function.max.runtime = function.max.runtime.max(total.runtime);
+        function.max.read_count = function.max.read_count.max(total.read_count);
+        function.max.read_length = function.max.read_length.max(total.read_length);
+        function.max.write_count = function.max.write_count.max(total.write_count);
+        function.max.write_length = function.max.write_length.max(total.write_length);
+        function.update_percentage();
+    }
+    functions.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summarize_costs() {
+        let call = |method: &str, runtime: u64, write_count: u64| {
+            serde_json::json!({
+                "test_name": "tests/counter.test.ts__increments",
+                "contract_id": "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.counter",
+                "method": method,
+                "args": [],
+                "cost_result": {
+                    "total": { "runtime": runtime, "read_count": 1, "read_length": 10, "write_count": write_count, "write_length": 10 },
+                    "limit": { "runtime": 1000, "read_count": 10, "read_length": 1000, "write_count": 10, "write_length": 1000 },
+                    "memory": 0,
+                    "memory_limit": 0
+                }
+            })
+        };
+        let content = serde_json::json!([
+            [call("increment", 100, 1), call("increment", 200, 5)],
+            [call("get-count", 50, 0)]
+        ])
+        .to_string();
+
+        let calls = parse_costs_report(&content).unwrap();
+        assert_eq!(calls.len(), 3);
+
+        let functions = summarize_costs(&calls);
+        assert_eq!(
+            functions
+                .iter()
+                .map(|f| (f.method.as_str(), f.calls))
+                .collect::<Vec<_>>(),
+            vec![("get-count", 1), ("increment", 2)]
+        );
+        assert_eq!(functions[1].max.runtime, 200);
+        assert_eq!(functions[1].max.write_count, 5);
+        assert_eq!(functions[1].max_percentage, 50.0);
+    }
+}
@@ -0,0 +1,188 @@
+use std::collections::BTreeMap;
+
+use clarity_repl::analysis::ast_dependency_detector::DependencySet;
+use clarity_repl::clarity::vm::types::QualifiedContractIdentifier;
+
+use crate::types::{DeploymentSpecification, TransactionSpecification};
+
+/// Contracts published by a deployment plan, in the order of publication. Plans generated
+/// by `generate_default_deployment` are topologically sorted, dependencies coming first.
+pub fn publish_order(deployment: &DeploymentSpecification) -> Vec<QualifiedContractIdentifier> {
+    let mut contracts = vec![];
+    for batch in deployment.plan.batches.iter() {
+        for transaction in batch.transactions.iter() {
+            let contract_id = match transaction {
+                TransactionSpecification::EmulatedContractPublish(tx) => {
+                    QualifiedContractIdentifier::new(
+                        tx.emulated_sender.clone(),
+                        tx.contract_name.clone(),
+                    )
+                }
+                TransactionSpecification::ContractPublish(tx) => QualifiedContractIdentifier::new(
+                    tx.expected_sender.clone(),
+                    tx.contract_name.clone(),
+                ),
+                TransactionSpecification::RequirementPublish(tx) => tx.contract_id.clone(),
+                _ => continue,
+            };
+            contracts.push(contract_id);
+        }
+    }
+    contracts
+}
+
+/// Graphviz rendering of the dependencies of the contracts. Dependencies that are not
+/// required before the publication of a contract (dynamic calls) are dashed.
+pub fn dependencies_to_dot(
+    dependencies: &BTreeMap<QualifiedContractIdentifier, DependencySet>,
+) -> String {
+    let mut dot = String::from("digraph dependencies {\n");
+    for (contract_id, contract_dependencies) in dependencies.iter() {
+        dot.push_str(&format!("  \"{}\";\n", contract_id));
+        for dependency in contract_dependencies.iter() {
+            let style = match dependency.required_before_publish {
+                true => "",
+                false => " [style=dashed]",
+            };
+            dot.push_str(&format!(
+                "  \"{}\" -> \"{}\"{};\n",
+                contract_id, dependency.contract_id, style
+            ));
+        }
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{
+        ContractCallSpecification, ContractPublishSpecification,
+        EmulatedContractPublishSpecification, RequirementPublishSpecification,
+        TransactionPlanSpecification, TransactionsBatchSpecification,
+    };
+    use clarinet_files::{FileLocation, StacksNetwork};
+    use clarity_repl::clarity::ClarityVersion;
+    use clarity_repl::repl::post_conditions::PostConditionMode;
+
+    #[test]
+    fn test_publish_order() {
+        let deployer =
+            QualifiedContractIdentifier::parse("ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.token")
+                .unwrap()
+                .issuer;
+        let location = FileLocation::from_path_string("/project/contracts/token.clar").unwrap();
+        let nft_trait = QualifiedContractIdentifier::parse(
+            "SP2PABAF9FTAJYNFZH93XENAJ8FVY99RRM50D2JG9.nft-trait",
+        )
+        .unwrap();
+        let publish = |name: &str| {
+            TransactionSpecification::ContractPublish(ContractPublishSpecification {
+                contract_name: name.into(),
+                expected_sender: deployer.clone(),
+                location: location.clone(),
+                source: "(define-read-only (get-name) none)".to_string(),
+                clarity_version: ClarityVersion::Clarity2,
+                cost: 1000,
+                anchor_block_only: true,
+            })
+        };
+        let requirement =
+            TransactionSpecification::RequirementPublish(RequirementPublishSpecification {
+                contract_id: nft_trait.clone(),
+                remap_sender: deployer.clone(),
+                remap_principals: BTreeMap::new(),
+                source: "(define-trait nft-trait ())".to_string(),
+                clarity_version: ClarityVersion::Clarity1,
+                cost: 1000,
+                location: location.clone(),
+            });
+        let emulated = TransactionSpecification::EmulatedContractPublish(
+            EmulatedContractPublishSpecification {
+                contract_name: "helper".into(),
+                emulated_sender: deployer.clone(),
+                source: "(define-read-only (get-name) none)".to_string(),
+                clarity_version: ClarityVersion::Clarity2,
+                location: location.clone(),
+            },
+        );
+        let call = TransactionSpecification::ContractCall(ContractCallSpecification {
+            contract_id: QualifiedContractIdentifier::new(deployer.clone(), "token".into()),
+            expected_sender: deployer.clone(),
+            method: "get-name".into(),
+            parameters: vec![],
+            cost: 1000,
+            anchor_block_only: true,
+            post_condition_mode: PostConditionMode::Allow,
+            post_conditions: vec![],
+        });
+        let deployment = DeploymentSpecification {
+            id: 0,
+            name: "devnet".to_string(),
+            network: StacksNetwork::Devnet,
+            stacks_node: None,
+            bitcoin_node: None,
+            genesis: None,
+            plan: TransactionPlanSpecification {
+                batches: vec![
+                    TransactionsBatchSpecification {
+                        id: 0,
+                        transactions: vec![requirement],
+                        epoch: None,
+                    },
+                    TransactionsBatchSpecification {
+                        id: 1,
+                        transactions: vec![publish("token"), call, publish("market")],
+                        epoch: None,
+                    },
+                    TransactionsBatchSpecification {
+                        id: 2,
+                        transactions: vec![emulated, publish("auction")],
+                        epoch: None,
+                    },
+                ],
+            },
+            contracts: BTreeMap::new(),
+        };
+
+        // the order of the plan is kept, from the first batch to the last, calls are skipped
+        let contract = |name: &str| QualifiedContractIdentifier::new(deployer.clone(), name.into());
+        assert_eq!(
+            publish_order(&deployment),
+            vec![
+                nft_trait,
+                contract("token"),
+                contract("market"),
+                contract("helper"),
+                contract("auction"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dependencies_to_dot() {
+        let token =
+            QualifiedContractIdentifier::parse("ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.token")
+                .unwrap();
+        let market =
+            QualifiedContractIdentifier::parse("ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.market")
+                .unwrap();
+        let mut market_dependencies = DependencySet::new();
+        market_dependencies.add_dependency(token.clone(), true);
+        let dependencies =
+            BTreeMap::from([(token, DependencySet::new()), (market, market_dependencies)]);
+
+        assert_eq!(
+            dependencies_to_dot(&dependencies),
+            [
+                "digraph dependencies {",
+                "  \"ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.market\";",
+                "  \"ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.market\" -> \"ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.token\";",
+                "  \"ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.token\";",
+                "}\n",
+            ]
+            .join("\n")
+        );
+    }
+}
@@ -4,6 +4,7 @@ use clarinet_files::{FileLocation, StacksNetwork};
 use clarity_repl::clarity::{
     vm::types::QualifiedContractIdentifier, ClarityName, ClarityVersion, ContractName,
 };
+use clarity_repl::repl::post_conditions::PostConditionMode;
 
 use crate::types::*;
 
@@ -31,6 +32,8 @@ fn get_test_txs() -> (TransactionSpecification, TransactionSpecification) {
             emulated_sender: tx_sender.clone(),
             method: ClarityName::try_from("test".to_string()).unwrap(),
             parameters: vec![],
+            post_condition_mode: PostConditionMode::Allow,
+            post_conditions: vec![],
         });
 
     (contract_publish_tx, contract_call_txs)
@@ -3,6 +3,7 @@ use std::collections::HashMap;
 use clarity_repl::{
     clarity::{
         diagnostic::{Diagnostic, Level},
+        representations::Span,
         vm::types::QualifiedContractIdentifier,
     },
     repl::diagnostic::output_code,
@@ -108,3 +109,193 @@ impl DiagnosticsDigest {
         self.errors > 0 || self.warnings > 0
     }
 }
+
+/// Diagnostic of a contract of the project, in a stable schema for editors and CI
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DiagnosticReport {
+    pub contract_id: String,
+    pub file: String,
+    pub line: u32,
+    pub column: u32,
+    pub end_line: u32,
+    pub end_column: u32,
+    pub severity: &'static str,
+    pub message: String,
+    pub suggestion: Option<String>,
+}
+
+impl DiagnosticReport {
+    /// Workflow command creating an annotation in GitHub Actions
+    pub fn to_github_annotation(&self) -> String {
+        let command = match self.severity {
+            "error" => "error",
+            "warning" => "warning",
+            _ => "notice",
+        };
+        let mut message = self.message.clone();
+        if let Some(ref suggestion) = self.suggestion {
+            message = format!("{}\n{}", message, suggestion);
+        }
+        format!(
+            "::{} file={},line={},col={},endLine={},endColumn={}::{}",
+            command,
+            self.file,
+            self.line,
+            self.column,
+            self.end_line,
+            self.end_column,
+            message
+                .replace('%', "%25")
+                .replace('\r', "%0D")
+                .replace('\n', "%0A")
+        )
+    }
+}
+
+/// Diagnostics of the contracts of the project, sorted by file and position
+pub fn diagnostics_reports(
+    contracts_diags: &HashMap<QualifiedContractIdentifier, Vec<Diagnostic>>,
+    deployment: &DeploymentSpecification,
+) -> Vec<DiagnosticReport> {
+    let mut reports = vec![];
+    for (contract_id, diags) in contracts_diags.iter() {
+        let Some((_, contract_location)) = deployment.contracts.get(contract_id) else {
+            continue;
+        };
+        let file = match contract_location.get_relative_location() {
+            Ok(contract_path) => contract_path,
+            _ => contract_location.to_string(),
+        };
+        for diagnostic in diags {
+            let span = diagnostic.spans.first().cloned().unwrap_or_else(Span::zero);
+            reports.push(DiagnosticReport {
+                contract_id: contract_id.to_string(),
+                file: file.clone(),
+                line: span.start_line,
+                column: span.start_column,
+                end_line: span.end_line,
+                end_column: span.end_column,
+                severity: match diagnostic.level {
+                    Level::Error => "error",
+                    Level::Warning => "warning",
+                    Level::Note => "note",
+                },
+                message: diagnostic.message.clone(),
+                suggestion: diagnostic.suggestion.clone(),
+            });
+        }
+    }
+    reports.sort_by(|a, b| (&a.file, a.line, a.column).cmp(&(&b.file, b.line, b.column)));
+    reports
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{TransactionPlanSpecification, TransactionsBatchSpecification};
+    use clarinet_files::{FileLocation, StacksNetwork};
+    use clarity_repl::clarity::StacksEpochId;
+    use std::collections::BTreeMap;
+
+    fn diagnostic(level: Level, message: &str, line: u32, column: u32) -> Diagnostic {
+        Diagnostic {
+            level,
+            message: message.to_string(),
+            spans: vec![Span {
+                start_line: line,
+                start_column: column,
+                end_line: line,
+                end_column: column + 4,
+            }],
+            suggestion: None,
+        }
+    }
+
+    #[test]
+    fn test_diagnostics_reports() {
+        let counter =
+            QualifiedContractIdentifier::parse("ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.counter")
+                .unwrap();
+        let requirement = QualifiedContractIdentifier::parse(
+            "SP2C2YFP12AJZB4MABJBAJ55XECVS7E4PMMZ89YZR.requirement",
+        )
+        .unwrap();
+        let mut contracts = BTreeMap::new();
+        contracts.insert(
+            counter.clone(),
+            (
+                "(define-data-var count uint u0)".to_string(),
+                FileLocation::from_path_string("/project/contracts/counter.clar").unwrap(),
+            ),
+        );
+        let deployment = DeploymentSpecification {
+            id: 0,
+            name: "check".to_string(),
+            network: StacksNetwork::Simnet,
+            stacks_node: None,
+            bitcoin_node: None,
+            genesis: None,
+            plan: TransactionPlanSpecification {
+                batches: vec![TransactionsBatchSpecification {
+                    id: 0,
+                    transactions: vec![],
+                    epoch: Some(StacksEpochId::Epoch25.into()),
+                }],
+            },
+            contracts,
+        };
+
+        let mut diags = HashMap::new();
+        diags.insert(
+            counter,
+            vec![
+                diagnostic(Level::Warning, "unchecked data", 7, 3),
+                diagnostic(Level::Error, "use of unresolved function", 2, 9),
+                Diagnostic {
+                    level: Level::Note,
+                    message: "no span".to_string(),
+                    spans: vec![],
+                    suggestion: Some("fix it".to_string()),
+                },
+            ],
+        );
+        // requirements are not part of the project
+        diags.insert(
+            requirement,
+            vec![diagnostic(Level::Error, "in requirement", 1, 1)],
+        );
+
+        let reports = diagnostics_reports(&diags, &deployment);
+        assert_eq!(reports.len(), 3);
+        assert!(reports
+            .iter()
+            .all(|report| report.file == "/project/contracts/counter.clar"));
+        assert_eq!(reports[0].severity, "note");
+        assert_eq!((reports[0].line, reports[0].column), (0, 0));
+        assert_eq!(reports[0].suggestion, Some("fix it".to_string()));
+        assert_eq!(reports[1].severity, "error");
+        assert_eq!((reports[1].line, reports[1].column), (2, 9));
+        assert_eq!(reports[1].end_column, 13);
+        assert_eq!(reports[2].severity, "warning");
+        assert_eq!(reports[2].message, "unchecked data");
+    }
+
+    #[test]
+    fn test_github_annotation() {
+        let report = DiagnosticReport {
+            contract_id: "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.counter".to_string(),
+            file: "contracts/counter.clar".to_string(),
+            line: 3,
+            column: 5,
+            end_line: 3,
+            end_column: 12,
+            severity: "warning",
+            message: "use of potentially unchecked data".to_string(),
+            suggestion: Some("add an assert (100%)".to_string()),
+        };
+        assert_eq!(
+            report.to_github_annotation(),
+            "::warning file=contracts/counter.clar,line=3,col=5,endLine=3,endColumn=12::use of potentially unchecked data%0Aadd an assert (100%25)"
+        );
+    }
+}
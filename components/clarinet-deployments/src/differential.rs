@@ -0,0 +1,379 @@
+use std::fmt;
+
+use clarity_repl::clarity::costs::ExecutionCost;
+use clarity_repl::clarity::vm::diagnostic::Diagnostic;
+use clarity_repl::clarity::vm::{EvaluationResult, ExecutionResult};
+use clarity_repl::clarity::StacksEpochId;
+use clarity_repl::repl::{Session, DEFAULT_EPOCH};
+use clarity_repl::utils::serialize_event;
+
+use crate::test_runner::ClarityTest;
+use crate::types::{DeploymentSpecification, TransactionSpecification};
+use crate::{
+    handle_emulated_contract_call, handle_emulated_contract_publish, handle_stx_transfer,
+    update_session_with_genesis,
+};
+
+/// Observable effects of a transaction of a simnet deployment plan
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransactionOutcome {
+    pub description: String,
+    pub result: Result<String, String>,
+    pub events: Vec<String>,
+    pub cost: Option<ExecutionCost>,
+}
+
+impl TransactionOutcome {
+    fn new(description: String, execution: Result<ExecutionResult, Vec<Diagnostic>>) -> Self {
+        match execution {
+            Ok(execution) => {
+                let result = match execution.result {
+                    EvaluationResult::Contract(contract_result) => match contract_result.result {
+                        Some(value) => value.to_string(),
+                        None => "published".to_string(),
+                    },
+                    EvaluationResult::Snippet(snippet_result) => snippet_result.result.to_string(),
+                };
+                TransactionOutcome {
+                    description,
+                    result: Ok(result),
+                    events: execution
+                        .events
+                        .iter()
+                        .map(|event| serialize_event(event).to_string())
+                        .collect(),
+                    cost: execution.cost.map(|cost| cost.total),
+                }
+            }
+            Err(diagnostics) => TransactionOutcome {
+                description,
+                result: Err(diagnostics
+                    .iter()
+                    .map(|d| d.message.clone())
+                    .collect::<Vec<_>>()
+                    .join("\n")),
+                events: vec![],
+                cost: None,
+            },
+        }
+    }
+}
+
+/// Execute the emulated transactions of a deployment plan, with costs tracking.
+/// `session` must be a new session, initiated from the manifest of the project.
+pub fn run_deployment_outcomes(
+    session: &mut Session,
+    deployment: &DeploymentSpecification,
+) -> Vec<TransactionOutcome> {
+    update_session_with_genesis(session, deployment);
+
+    let mut outcomes = vec![];
+    for batch in deployment.plan.batches.iter() {
+        let epoch: StacksEpochId = match batch.epoch {
+            Some(epoch) => epoch.into(),
+            None => DEFAULT_EPOCH,
+        };
+        session.advance_chain_tip(1);
+        session.update_epoch(epoch);
+
+        for transaction in batch.transactions.iter() {
+            let outcome = match transaction {
+                TransactionSpecification::EmulatedContractPublish(tx) => TransactionOutcome::new(
+                    format!("publish {}.{}", tx.emulated_sender, tx.contract_name),
+                    handle_emulated_contract_publish(session, tx, None, epoch, true),
+                ),
+                TransactionSpecification::EmulatedContractCall(tx) => TransactionOutcome::new(
+                    format!("call {}::{}", tx.contract_id, tx.method),
+                    handle_emulated_contract_call(session, tx, true),
+                ),
+                TransactionSpecification::StxTransfer(tx) => TransactionOutcome::new(
+                    format!(
+                        "transfer {} uSTX from {} to {}",
+                        tx.mstx_amount, tx.expected_sender, tx.recipient
+                    ),
+                    handle_stx_transfer(session, tx),
+                ),
+                // not admitted in simnet deployment plans
+                TransactionSpecification::RequirementPublish(_)
+                | TransactionSpecification::BtcTransfer(_)
+                | TransactionSpecification::ContractCall(_)
+                | TransactionSpecification::ContractPublish(_) => continue,
+            };
+            outcomes.push(outcome);
+        }
+    }
+    outcomes
+}
+
+/// Run each test of the test suite, with costs tracking, in its own copy of `session`
+/// like `test_runner::run_tests` does. `session` is a session the deployment plan was
+/// executed in.
+pub fn run_tests_outcomes(session: &Session, tests: &[ClarityTest]) -> Vec<TransactionOutcome> {
+    let mut outcomes = vec![];
+    for test in tests.iter() {
+        let mut test_session = session.clone();
+        let execution = test_session.call_contract_fn(
+            &test.contract_id.to_string(),
+            &test.name,
+            &[],
+            &test.contract_id.issuer.to_string(),
+            false,
+            true,
+        );
+        outcomes.push(TransactionOutcome::new(
+            format!("test {}::{}", test.contract_id.name, test.name),
+            execution,
+        ));
+    }
+    outcomes
+}
+
+/// Effect of a transaction that differs between two runtimes
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutcomeDivergence {
+    pub description: String,
+    pub aspect: &'static str,
+    pub reference: String,
+    pub candidate: String,
+}
+
+impl fmt::Display for OutcomeDivergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} differs\n  reference: {}\n  candidate: {}",
+            self.description, self.aspect, self.reference, self.candidate
+        )
+    }
+}
+
+fn format_result(result: &Result<String, String>) -> String {
+    match result {
+        Ok(value) => value.clone(),
+        Err(message) => format!("error: {}", message),
+    }
+}
+
+fn format_cost(cost: &Option<ExecutionCost>) -> String {
+    match cost {
+        Some(cost) => format!(
+            "runtime {}, read_count {}, read_length {}, write_count {}, write_length {}",
+            cost.runtime, cost.read_count, cost.read_length, cost.write_count, cost.write_length
+        ),
+        None => "none".to_string(),
+    }
+}
+
+/// Compare the outcomes of the same deployment plan executed by two runtimes
+pub fn compare_outcomes(
+    reference: &[TransactionOutcome],
+    candidate: &[TransactionOutcome],
+) -> Vec<OutcomeDivergence> {
+    let mut divergences = vec![];
+    for (reference, candidate) in reference.iter().zip(candidate.iter()) {
+        let aspects = [
+            (
+                "result",
+                format_result(&reference.result),
+                format_result(&candidate.result),
+            ),
+            (
+                "events",
+                reference.events.join(", "),
+                candidate.events.join(", "),
+            ),
+            (
+                "cost",
+                format_cost(&reference.cost),
+                format_cost(&candidate.cost),
+            ),
+        ];
+        for (aspect, reference_value, candidate_value) in aspects {
+            if reference_value != candidate_value {
+                divergences.push(OutcomeDivergence {
+                    description: reference.description.clone(),
+                    aspect,
+                    reference: reference_value,
+                    candidate: candidate_value,
+                });
+            }
+        }
+    }
+    if reference.len() != candidate.len() {
+        divergences.push(OutcomeDivergence {
+            description: "deployment".to_string(),
+            aspect: "transactions count",
+            reference: reference.len().to_string(),
+            candidate: candidate.len().to_string(),
+        });
+    }
+    divergences
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_runner::discover_tests;
+    use crate::types::{
+        EmulatedContractCallSpecification, EmulatedContractPublishSpecification,
+        GenesisSpecification, StxTransferSpecification, TransactionPlanSpecification,
+        TransactionsBatchSpecification, WalletSpecification,
+    };
+    use clarinet_files::{FileLocation, StacksNetwork};
+    use clarity_repl::clarity::vm::types::{PrincipalData, QualifiedContractIdentifier};
+    use clarity_repl::clarity::ClarityVersion;
+    use clarity_repl::repl::post_conditions::PostConditionMode;
+    use clarity_repl::repl::SessionSettings;
+    use std::collections::BTreeMap;
+
+    fn outcome(result: Result<&str, &str>, runtime: u64) -> TransactionOutcome {
+        TransactionOutcome {
+            description: "call ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.counter::increment"
+                .to_string(),
+            result: result.map(String::from).map_err(String::from),
+            events: vec![],
+            cost: Some(ExecutionCost {
+                runtime,
+                ..ExecutionCost::zero()
+            }),
+        }
+    }
+
+    fn counter_deployment() -> DeploymentSpecification {
+        let deployer =
+            PrincipalData::parse_standard_principal("ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM")
+                .unwrap();
+        let wallet = PrincipalData::parse("ST1SJ3DTE5DN7X54YDH5D64R3BCB6A2AG2ZQ8YPD5").unwrap();
+        let source = [
+            "(define-data-var count uint u0)",
+            "(define-public (increment)",
+            "  (begin",
+            "    (print { action: \"increment\" })",
+            "    (ok (var-set count (+ (var-get count) u1)))))",
+            "(define-public (test-increment)",
+            "  (begin",
+            "    (try! (increment))",
+            "    (asserts! (is-eq (var-get count) u2) (err u1))",
+            "    (ok true)))",
+        ]
+        .join("\n");
+        let counter = QualifiedContractIdentifier::new(deployer.clone(), "counter".into());
+        let location = FileLocation::from_path_string("/project/contracts/counter.clar").unwrap();
+        let mut contracts = BTreeMap::new();
+        contracts.insert(counter.clone(), (source.clone(), location.clone()));
+        let transactions = vec![
+            TransactionSpecification::EmulatedContractPublish(
+                EmulatedContractPublishSpecification {
+                    contract_name: "counter".into(),
+                    emulated_sender: deployer.clone(),
+                    source,
+                    clarity_version: ClarityVersion::Clarity2,
+                    location,
+                },
+            ),
+            TransactionSpecification::EmulatedContractCall(EmulatedContractCallSpecification {
+                contract_id: counter,
+                emulated_sender: deployer.clone(),
+                method: "increment".into(),
+                parameters: vec![],
+                post_condition_mode: PostConditionMode::Allow,
+                post_conditions: vec![],
+            }),
+            TransactionSpecification::StxTransfer(StxTransferSpecification {
+                expected_sender: deployer.clone(),
+                recipient: wallet,
+                mstx_amount: 1000,
+                memo: [0; 34],
+                cost: 0,
+                anchor_block_only: true,
+            }),
+        ];
+        DeploymentSpecification {
+            id: 0,
+            name: "simnet".to_string(),
+            network: StacksNetwork::Simnet,
+            stacks_node: None,
+            bitcoin_node: None,
+            genesis: Some(GenesisSpecification {
+                wallets: vec![WalletSpecification {
+                    name: "deployer".to_string(),
+                    address: deployer,
+                    balance: 100_000_000,
+                }],
+                contracts: vec![],
+            }),
+            plan: TransactionPlanSpecification {
+                batches: vec![TransactionsBatchSpecification {
+                    id: 0,
+                    transactions,
+                    epoch: Some(StacksEpochId::Epoch25.into()),
+                }],
+            },
+            contracts,
+        }
+    }
+
+    #[test]
+    fn test_run_deployment_and_tests_outcomes() {
+        let deployment = counter_deployment();
+        let mut session = Session::new(SessionSettings::default());
+        let outcomes = run_deployment_outcomes(&mut session, &deployment);
+        assert_eq!(
+            outcomes
+                .iter()
+                .map(|outcome| outcome.description.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "publish ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.counter",
+                "call ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.counter::increment",
+                "transfer 1000 uSTX from ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM to ST1SJ3DTE5DN7X54YDH5D64R3BCB6A2AG2ZQ8YPD5",
+            ]
+        );
+        assert_eq!(outcomes[1].result, Ok("(ok true)".to_string()));
+        assert_eq!(outcomes[1].events.len(), 1);
+        assert!(outcomes[1].cost.is_some());
+        assert_eq!(outcomes[2].result, Ok("(ok true)".to_string()));
+        assert_eq!(outcomes[2].events.len(), 1);
+
+        let contracts_ids: Vec<QualifiedContractIdentifier> =
+            deployment.contracts.keys().cloned().collect();
+        let tests = discover_tests(&session, &contracts_ids);
+        let tests_outcomes = run_tests_outcomes(&session, &tests);
+        assert_eq!(tests_outcomes.len(), 1);
+        assert_eq!(
+            tests_outcomes[0].description,
+            "test counter::test-increment"
+        );
+        assert_eq!(tests_outcomes[0].result, Ok("(ok true)".to_string()));
+        assert!(tests_outcomes[0].cost.is_some());
+
+        // the same plan and tests, executed again, do not diverge
+        let mut other_session = Session::new(SessionSettings::default());
+        let other_outcomes = run_deployment_outcomes(&mut other_session, &deployment);
+        assert!(compare_outcomes(&outcomes, &other_outcomes).is_empty());
+        let other_tests_outcomes = run_tests_outcomes(&other_session, &tests);
+        assert!(compare_outcomes(&tests_outcomes, &other_tests_outcomes).is_empty());
+    }
+
+    #[test]
+    fn test_compare_outcomes() {
+        let reference = vec![outcome(Ok("(ok u1)"), 1000)];
+        assert!(compare_outcomes(&reference, &reference).is_empty());
+
+        let candidate = vec![outcome(Ok("(ok u1)"), 1200)];
+        let divergences = compare_outcomes(&reference, &candidate);
+        assert_eq!(divergences.len(), 1);
+        assert_eq!(divergences[0].aspect, "cost");
+
+        let candidate = vec![outcome(Err("runtime error"), 1000), outcome(Ok("true"), 1)];
+        let divergences = compare_outcomes(&reference, &candidate);
+        assert_eq!(
+            divergences
+                .iter()
+                .map(|divergence| divergence.aspect)
+                .collect::<Vec<_>>(),
+            vec!["result", "transactions count"]
+        );
+        assert_eq!(divergences[0].candidate, "error: runtime error");
+    }
+}
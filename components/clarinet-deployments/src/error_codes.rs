@@ -0,0 +1,173 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use clarity_repl::clarity::vm::types::{QualifiedContractIdentifier, ResponseData};
+use clarity_repl::clarity::vm::Value;
+use clarity_repl::repl::session::BOOT_CONTRACTS_DATA;
+use clarity_repl::repl::Session;
+
+/// Error constants defined by a contract, such as `(define-constant ERR_UNAUTHORIZED (err u401))`
+pub fn get_defined_errors(
+    session: &mut Session,
+    contract_id: &QualifiedContractIdentifier,
+) -> Result<BTreeMap<String, Value>, String> {
+    let constants = session.interpreter.get_contract_constants(contract_id)?;
+    Ok(constants
+        .into_iter()
+        .filter(|(_, value)| {
+            matches!(
+                value,
+                Value::Response(ResponseData {
+                    committed: false,
+                    ..
+                })
+            )
+        })
+        .collect())
+}
+
+/// Error constants of every contract of a project
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ErrorCodesRegistry {
+    pub contracts: BTreeMap<QualifiedContractIdentifier, BTreeMap<String, Value>>,
+}
+
+/// An error code given different names across contracts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorCodeConflict {
+    pub code: String,
+    /// Contracts and names of the constants defining the code
+    pub definitions: Vec<(QualifiedContractIdentifier, String)>,
+}
+
+impl fmt::Display for ErrorCodeConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let definitions: Vec<String> = self
+            .definitions
+            .iter()
+            .map(|(contract_id, name)| format!("{} in {}", name, contract_id.name))
+            .collect();
+        write!(
+            f,
+            "error code {} has different meanings: {}",
+            self.code,
+            definitions.join(", ")
+        )
+    }
+}
+
+/// `ERR-NOT-FOUND` and `ERR_NOT_FOUND` have the same meaning
+fn normalize_error_name(name: &str) -> String {
+    name.to_uppercase().replace('-', "_")
+}
+
+impl ErrorCodesRegistry {
+    /// Collect the error constants of the contracts of the session, boot contracts excluded
+    pub fn from_session(session: &mut Session) -> Result<Self, String> {
+        let contracts_ids: Vec<QualifiedContractIdentifier> = session
+            .contracts
+            .keys()
+            .filter(|contract_id| !BOOT_CONTRACTS_DATA.contains_key(contract_id))
+            .cloned()
+            .collect();
+        let mut contracts = BTreeMap::new();
+        for contract_id in contracts_ids.into_iter() {
+            let errors = get_defined_errors(session, &contract_id)?;
+            if !errors.is_empty() {
+                contracts.insert(contract_id, errors);
+            }
+        }
+        Ok(ErrorCodesRegistry { contracts })
+    }
+
+    /// Contracts defining an error named `name`, with its value
+    pub fn lookup(&self, name: &str) -> Vec<(&QualifiedContractIdentifier, &Value)> {
+        let name = normalize_error_name(name);
+        self.contracts
+            .iter()
+            .flat_map(|(contract_id, errors)| {
+                errors
+                    .iter()
+                    .filter(|(error_name, _)| normalize_error_name(error_name) == name)
+                    .map(move |(_, value)| (contract_id, value))
+            })
+            .collect()
+    }
+
+    /// Error codes defined under several names across the project
+    pub fn conflicts(&self) -> Vec<ErrorCodeConflict> {
+        let mut codes: BTreeMap<String, Vec<(QualifiedContractIdentifier, String)>> =
+            BTreeMap::new();
+        for (contract_id, errors) in self.contracts.iter() {
+            for (name, value) in errors.iter() {
+                codes
+                    .entry(value.to_string())
+                    .or_default()
+                    .push((contract_id.clone(), name.clone()));
+            }
+        }
+        codes
+            .into_iter()
+            .filter(|(_, definitions)| {
+                let names: BTreeSet<String> = definitions
+                    .iter()
+                    .map(|(_, name)| normalize_error_name(name))
+                    .collect();
+                names.len() > 1
+            })
+            .map(|(code, definitions)| ErrorCodeConflict { code, definitions })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clarity_repl::clarity::vm::EvaluationResult;
+    use clarity_repl::clarity::StacksEpochId;
+    use clarity_repl::repl::SessionSettings;
+
+    fn deploy(session: &mut Session, snippet: &str) -> QualifiedContractIdentifier {
+        match session.eval(snippet.to_string(), false).unwrap().result {
+            EvaluationResult::Contract(evaluation) => {
+                evaluation.contract.analysis.contract_identifier
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_error_codes_registry() {
+        let mut session = Session::new(SessionSettings::default());
+        session.update_epoch(StacksEpochId::Epoch25);
+        let token = deploy(
+            &mut session,
+            "(define-constant ERR_UNAUTHORIZED (err u401))\n(define-constant ERR_NOT_FOUND (err u404))\n(define-constant MAX_SUPPLY u1000)",
+        );
+        let market = deploy(
+            &mut session,
+            "(define-constant ERR-UNAUTHORIZED (err u401))\n(define-constant ERR_SOLD_OUT (err u404))",
+        );
+
+        let registry = ErrorCodesRegistry::from_session(&mut session).unwrap();
+        assert_eq!(registry.contracts[&token].len(), 2);
+        assert_eq!(
+            registry.lookup("ERR_UNAUTHORIZED"),
+            vec![
+                (&token, &Value::error(Value::UInt(401)).unwrap()),
+                (&market, &Value::error(Value::UInt(401)).unwrap()),
+            ]
+        );
+
+        let conflicts = registry.conflicts();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].code, "(err u404)");
+        assert_eq!(
+            conflicts[0].definitions,
+            vec![
+                (token, "ERR_NOT_FOUND".to_string()),
+                (market, "ERR_SOLD_OUT".to_string()),
+            ]
+        );
+    }
+}
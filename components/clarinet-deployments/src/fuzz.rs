@@ -0,0 +1,564 @@
+use std::{fmt, mem};
+
+use clarity_repl::clarity::vm::types::{
+    CharType, FunctionType, PrincipalData, QualifiedContractIdentifier, SequenceData,
+    SequenceSubtype, StringSubtype, TupleData, TypeSignature,
+};
+use clarity_repl::clarity::vm::{EvaluationResult, SymbolicExpression, Value};
+use clarity_repl::repl::post_conditions::{PostConditionMode, PostConditions};
+use clarity_repl::repl::Session;
+
+use crate::test_runner::TEST_FUNCTIONS_PREFIX;
+
+pub const INVARIANT_FUNCTIONS_PREFIX: &str = "invariant-";
+/// Generated sequences are kept short, whatever their declared maximum length
+const MAX_SEQUENCE_LEN: u32 = 32;
+const MAX_SHRINK_STEPS: u32 = 1000;
+const POST_CONDITION_FAILURE: &str = "Post-condition check failure: ";
+const UTF8_CHARS: [char; 6] = ['a', 'Z', '0', 'é', '€', '🦊'];
+
+/// Deterministic generator (splitmix64), a seed always replays the same calls
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Random number in `0..n`
+    pub fn below(&mut self, n: u64) -> u64 {
+        match n {
+            0 => 0,
+            n => self.next_u64() % n,
+        }
+    }
+
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len() as u64) as usize]
+    }
+}
+
+fn sequence_len(rng: &mut Rng, max_len: u32) -> usize {
+    let max_len = max_len.min(MAX_SEQUENCE_LEN);
+    match rng.below(8) {
+        0 => max_len as usize,
+        _ => rng.below(max_len as u64 + 1) as usize,
+    }
+}
+
+/// Random value of type `signature`, biased towards edge cases (zero, bounds, empty and
+/// full sequences). Principals are taken from `principals`. Returns `None` for the types
+/// that can not be generated, such as traits.
+pub fn random_value(
+    rng: &mut Rng,
+    signature: &TypeSignature,
+    principals: &[PrincipalData],
+) -> Option<Value> {
+    let value = match signature {
+        TypeSignature::IntType => Value::Int(match rng.below(4) {
+            0 => *rng.pick(&[0, 1, -1, i128::MAX, i128::MIN]),
+            1 => rng.below(200) as i128 - 100,
+            _ => rng.next_u64() as i64 as i128,
+        }),
+        TypeSignature::UIntType => Value::UInt(match rng.below(4) {
+            0 => *rng.pick(&[0, 1, u128::MAX]),
+            1 => rng.below(100) as u128,
+            _ => rng.next_u64() as u128,
+        }),
+        TypeSignature::BoolType => Value::Bool(rng.below(2) == 0),
+        TypeSignature::PrincipalType if !principals.is_empty() => {
+            Value::Principal(rng.pick(principals).clone())
+        }
+        TypeSignature::OptionalType(inner) => match rng.below(4) {
+            0 => Value::none(),
+            _ => Value::some(random_value(rng, inner, principals)?).ok()?,
+        },
+        TypeSignature::ResponseType(types) => match rng.below(2) {
+            0 => Value::okay(random_value(rng, &types.0, principals)?).ok()?,
+            _ => Value::error(random_value(rng, &types.1, principals)?).ok()?,
+        },
+        TypeSignature::TupleType(tuple) => {
+            let mut fields = vec![];
+            for (name, field_type) in tuple.get_type_map().iter() {
+                fields.push((name.clone(), random_value(rng, field_type, principals)?));
+            }
+            Value::Tuple(TupleData::from_data(fields).ok()?)
+        }
+        TypeSignature::SequenceType(SequenceSubtype::BufferType(len)) => {
+            let len = sequence_len(rng, len.into());
+            Value::buff_from((0..len).map(|_| rng.below(256) as u8).collect()).ok()?
+        }
+        TypeSignature::SequenceType(SequenceSubtype::StringType(StringSubtype::ASCII(len))) => {
+            let len = sequence_len(rng, len.into());
+            let bytes = (0..len).map(|_| 0x20 + rng.below(0x5f) as u8).collect();
+            Value::string_ascii_from_bytes(bytes).ok()?
+        }
+        TypeSignature::SequenceType(SequenceSubtype::StringType(StringSubtype::UTF8(len))) => {
+            let len = sequence_len(rng, len.into());
+            let string: String = (0..len).map(|_| *rng.pick(&UTF8_CHARS)).collect();
+            Value::string_utf8_from_bytes(string.into_bytes()).ok()?
+        }
+        TypeSignature::SequenceType(SequenceSubtype::ListType(list)) => {
+            let len = sequence_len(rng, list.get_max_len());
+            let mut items = vec![];
+            for _ in 0..len {
+                items.push(random_value(rng, list.get_list_item_type(), principals)?);
+            }
+            Value::cons_list_unsanitized(items).ok()?
+        }
+        _ => return None,
+    };
+    Some(value)
+}
+
+/// Simpler values of the same type than `value`, tried in order to minimize a failing input.
+/// Numbers get closer to zero by halving steps, so that the smallest failing number is found.
+pub fn shrink_value(value: &Value) -> Vec<Value> {
+    match value {
+        Value::Int(i) if *i != 0 => {
+            let mut candidates = vec![Value::Int(0)];
+            let mut step = i / 2;
+            while step != 0 {
+                candidates.push(Value::Int(i - step));
+                step /= 2;
+            }
+            candidates
+        }
+        Value::UInt(u) if *u != 0 => {
+            let mut candidates = vec![Value::UInt(0)];
+            let mut step = u / 2;
+            while step != 0 {
+                candidates.push(Value::UInt(u - step));
+                step /= 2;
+            }
+            candidates
+        }
+        Value::Bool(true) => vec![Value::Bool(false)],
+        Value::Optional(optional) => match &optional.data {
+            Some(inner) => {
+                let mut candidates = vec![Value::none()];
+                candidates.extend(
+                    shrink_value(inner)
+                        .into_iter()
+                        .filter_map(|inner| Value::some(inner).ok()),
+                );
+                candidates
+            }
+            None => vec![],
+        },
+        Value::Response(response) => shrink_value(&response.data)
+            .into_iter()
+            .filter_map(|data| match response.committed {
+                true => Value::okay(data).ok(),
+                false => Value::error(data).ok(),
+            })
+            .collect(),
+        Value::Tuple(tuple) => {
+            let mut candidates = vec![];
+            for (name, field) in tuple.data_map.iter() {
+                for field in shrink_value(field) {
+                    let mut fields = tuple.data_map.clone();
+                    fields.insert(name.clone(), field);
+                    if let Ok(tuple) = TupleData::from_data(fields.into_iter().collect()) {
+                        candidates.push(Value::Tuple(tuple));
+                    }
+                }
+            }
+            candidates
+        }
+        Value::Sequence(sequence) => {
+            let len = sequence.len();
+            if len == 0 {
+                return vec![];
+            }
+            let mut lens = vec![0, len / 2, len - 1];
+            lens.dedup();
+            lens.into_iter()
+                .filter_map(|len| truncate_sequence(sequence, len))
+                .collect()
+        }
+        _ => vec![],
+    }
+}
+
+fn truncate_sequence(sequence: &SequenceData, len: usize) -> Option<Value> {
+    match sequence {
+        SequenceData::Buffer(buff) => Value::buff_from(buff.data[..len].to_vec()).ok(),
+        SequenceData::String(CharType::ASCII(string)) => {
+            Value::string_ascii_from_bytes(string.data[..len].to_vec()).ok()
+        }
+        SequenceData::String(CharType::UTF8(string)) => {
+            Value::string_utf8_from_bytes(string.data[..len].concat()).ok()
+        }
+        SequenceData::List(list) => Value::cons_list_unsanitized(list.data[..len].to_vec()).ok(),
+    }
+}
+
+/// Whether `random_value` can generate values of type `signature`
+fn is_generable(signature: &TypeSignature) -> bool {
+    match signature {
+        TypeSignature::IntType
+        | TypeSignature::UIntType
+        | TypeSignature::BoolType
+        | TypeSignature::PrincipalType
+        | TypeSignature::SequenceType(SequenceSubtype::BufferType(_))
+        | TypeSignature::SequenceType(SequenceSubtype::StringType(_)) => true,
+        TypeSignature::OptionalType(inner) => is_generable(inner),
+        TypeSignature::ResponseType(types) => is_generable(&types.0) && is_generable(&types.1),
+        TypeSignature::TupleType(tuple) => tuple.get_type_map().values().all(is_generable),
+        TypeSignature::SequenceType(SequenceSubtype::ListType(list)) => {
+            is_generable(list.get_list_item_type())
+        }
+        _ => false,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FailureKind {
+    RuntimeError(String),
+    /// The assets moved by the call do not satisfy the post-conditions
+    PostConditionViolated(String),
+    /// The invariant function returned false (or not a bool) after the call
+    InvariantViolated(String),
+}
+
+/// Call of a public function making the contract fail, with shrunk arguments
+#[derive(Debug, Clone, PartialEq)]
+pub struct FuzzFailure {
+    pub function: String,
+    pub sender: PrincipalData,
+    pub args: Vec<Value>,
+    pub kind: FailureKind,
+}
+
+impl fmt::Display for FuzzFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}", self.function)?;
+        for arg in self.args.iter() {
+            write!(f, " {}", arg)?;
+        }
+        write!(f, ") sent by {}: ", self.sender)?;
+        match &self.kind {
+            FailureKind::RuntimeError(message) => write!(f, "runtime error: {}", message),
+            FailureKind::PostConditionViolated(message) => {
+                write!(f, "post-condition violated: {}", message)
+            }
+            FailureKind::InvariantViolated(invariant) => write!(f, "{} violated", invariant),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FuzzReport {
+    pub calls: u32,
+    /// First failure of each kind found for each function
+    pub failures: Vec<FuzzFailure>,
+}
+
+struct FuzzedContract<'a> {
+    contract_id: String,
+    invariants: &'a [String],
+    post_conditions: &'a PostConditions,
+}
+
+impl FuzzedContract<'_> {
+    /// Call `function` on `session`. The calls returning a failure kind other than
+    /// `InvariantViolated` are rolled back by the VM, the others are committed.
+    fn execute(
+        &self,
+        session: &mut Session,
+        function: &str,
+        sender: &PrincipalData,
+        args: &[Value],
+    ) -> Option<FailureKind> {
+        let sender = sender.to_string();
+        let args: Vec<SymbolicExpression> = args
+            .iter()
+            .map(|arg| SymbolicExpression::atom_value(arg.clone()))
+            .collect();
+        if self.post_conditions.mode == PostConditionMode::Deny
+            || !self.post_conditions.conditions.is_empty()
+        {
+            session
+                .interpreter
+                .set_post_conditions(self.post_conditions.clone());
+        }
+        let diagnostics =
+            session.call_contract_fn(&self.contract_id, function, &args, &sender, false, false);
+        let Err(diagnostics) = diagnostics else {
+            return None;
+        };
+        let messages: Vec<String> = diagnostics.into_iter().map(|d| d.message).collect();
+        let message = messages.join(", ");
+        Some(match message.split_once(POST_CONDITION_FAILURE) {
+            Some((_, violation)) => FailureKind::PostConditionViolated(violation.to_string()),
+            None => FailureKind::RuntimeError(message),
+        })
+    }
+
+    /// Call `function` on `session`, then check the invariants
+    fn call(
+        &self,
+        session: &mut Session,
+        function: &str,
+        sender: &PrincipalData,
+        args: &[Value],
+    ) -> Option<FailureKind> {
+        if let Some(kind) = self.execute(session, function, sender, args) {
+            return Some(kind);
+        }
+        let sender = sender.to_string();
+        for invariant in self.invariants.iter() {
+            let holds = match session.call_contract_fn(
+                &self.contract_id,
+                invariant,
+                &[],
+                &sender,
+                false,
+                false,
+            ) {
+                Ok(execution) => match execution.result {
+                    EvaluationResult::Snippet(result) => result.result == Value::Bool(true),
+                    EvaluationResult::Contract(_) => false,
+                },
+                Err(_) => false,
+            };
+            if !holds {
+                return Some(FailureKind::InvariantViolated(invariant.clone()));
+            }
+        }
+        None
+    }
+
+    /// Simplify the arguments of `failure` one at a time, as long as the call keeps failing
+    /// the same way. `session` is the state before the failing call, it is only copied again
+    /// when a candidate call gets committed.
+    fn shrink(&self, session: &Session, mut failure: FuzzFailure) -> FuzzFailure {
+        let mut scratch = session.clone();
+        let mut steps = 0;
+        'shrinking: loop {
+            for index in 0..failure.args.len() {
+                for candidate in shrink_value(&failure.args[index]) {
+                    steps += 1;
+                    if steps > MAX_SHRINK_STEPS {
+                        break 'shrinking;
+                    }
+                    let mut args = failure.args.clone();
+                    args[index] = candidate;
+                    let kind = self.call(&mut scratch, &failure.function, &failure.sender, &args);
+                    if matches!(kind, None | Some(FailureKind::InvariantViolated(_))) {
+                        scratch = session.clone();
+                    }
+                    if let Some(kind) = kind {
+                        if mem::discriminant(&kind) == mem::discriminant(&failure.kind) {
+                            failure.args = args;
+                            failure.kind = kind;
+                            continue 'shrinking;
+                        }
+                    }
+                }
+            }
+            break;
+        }
+        failure
+    }
+}
+
+/// Call random public functions of the contract with random arguments and senders, for
+/// `iterations` calls. The state changes of the successful calls are kept, so that the
+/// argument-less read-only `invariant-` functions of the contract are checked against
+/// sequences of calls. Each call is made with `post_conditions`, in deny mode the calls
+/// moving assets are reported. The calls returning an err response are not failures.
+pub fn fuzz_contract(
+    session: &Session,
+    contract_id: &QualifiedContractIdentifier,
+    senders: &[PrincipalData],
+    iterations: u32,
+    seed: u64,
+    post_conditions: &PostConditions,
+) -> Result<FuzzReport, String> {
+    let analysis = match session.contracts.get(contract_id) {
+        Some(contract) => &contract.analysis,
+        None => return Err(format!("contract {} not found", contract_id)),
+    };
+    if senders.is_empty() {
+        return Err("no sender to call the functions with".to_string());
+    }
+    let mut principals = senders.to_vec();
+    principals.push(PrincipalData::Contract(contract_id.clone()));
+
+    let mut functions = vec![];
+    for (name, function_type) in analysis.public_function_types.iter() {
+        let FunctionType::Fixed(function) = function_type else {
+            continue;
+        };
+        if name.starts_with(TEST_FUNCTIONS_PREFIX)
+            || !function.args.iter().all(|arg| is_generable(&arg.signature))
+        {
+            continue;
+        }
+        let signatures: Vec<TypeSignature> = function
+            .args
+            .iter()
+            .map(|arg| arg.signature.clone())
+            .collect();
+        functions.push((name.to_string(), signatures));
+    }
+    if functions.is_empty() {
+        return Err(format!(
+            "no public function of {} can be fuzzed",
+            contract_id
+        ));
+    }
+    let invariants: Vec<String> = analysis
+        .read_only_function_types
+        .iter()
+        .filter(|(name, function_type)| {
+            name.starts_with(INVARIANT_FUNCTIONS_PREFIX)
+                && matches!(function_type, FunctionType::Fixed(function) if function.args.is_empty())
+        })
+        .map(|(name, _)| name.to_string())
+        .collect();
+    let contract = FuzzedContract {
+        contract_id: contract_id.to_string(),
+        invariants: &invariants,
+        post_conditions,
+    };
+
+    let mut rng = Rng::new(seed);
+    // The changes of a call violating an invariant are dropped by replaying the calls
+    // committed since the checkpoint, rather than copying the session before each call.
+    let mut checkpoint = session.clone();
+    let mut committed: Vec<(String, PrincipalData, Vec<Value>)> = vec![];
+    let mut session = session.clone();
+    let mut report = FuzzReport {
+        calls: 0,
+        failures: vec![],
+    };
+    for _ in 0..iterations {
+        let (function, signatures) = rng.pick(&functions);
+        let sender = rng.pick(senders).clone();
+        let Some(args) = signatures
+            .iter()
+            .map(|signature| random_value(&mut rng, signature, &principals))
+            .collect::<Option<Vec<Value>>>()
+        else {
+            continue;
+        };
+        report.calls += 1;
+        let Some(kind) = contract.call(&mut session, function, &sender, &args) else {
+            committed.push((function.clone(), sender, args));
+            continue;
+        };
+        if let FailureKind::InvariantViolated(_) = kind {
+            for (function, sender, args) in committed.drain(..) {
+                contract.execute(&mut checkpoint, &function, &sender, &args);
+            }
+            session = checkpoint.clone();
+        }
+        let known = report.failures.iter().any(|failure| {
+            &failure.function == function
+                && mem::discriminant(&failure.kind) == mem::discriminant(&kind)
+        });
+        if !known {
+            let failure = FuzzFailure {
+                function: function.clone(),
+                sender,
+                args,
+                kind,
+            };
+            report.failures.push(contract.shrink(&session, failure));
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clarity_repl::clarity::StacksEpochId;
+    use clarity_repl::repl::SessionSettings;
+
+    #[test]
+    fn test_fuzz_contract() {
+        let mut session = Session::new(SessionSettings::default());
+        session.update_epoch(StacksEpochId::Epoch25);
+        let snippet = [
+            "(define-fungible-token token)",
+            "(define-data-var last uint u0)",
+            "(define-public (deposit (amount uint))",
+            "  (ok (var-set last amount)))",
+            "(define-public (burn (amount uint))",
+            "  (begin",
+            "    (try! (ft-mint? token amount tx-sender))",
+            "    (ft-burn? token amount tx-sender)))",
+            "(define-read-only (invariant-capped) (<= (var-get last) u1000))",
+        ]
+        .join("\n");
+        let contract_id = match session.eval(snippet, false).unwrap().result {
+            EvaluationResult::Contract(evaluation) => {
+                evaluation.contract.analysis.contract_identifier
+            }
+            _ => unreachable!(),
+        };
+        let senders =
+            vec![PrincipalData::parse("ST1SJ3DTE5DN7X54YDH5D64R3BCB6A2AG2ZQ8YPD5").unwrap()];
+
+        let allow = PostConditions::default();
+        let report = fuzz_contract(&session, &contract_id, &senders, 100, 0, &allow).unwrap();
+        assert_eq!(report.calls, 100);
+        let failure = report
+            .failures
+            .iter()
+            .find(|failure| {
+                failure.kind == FailureKind::InvariantViolated("invariant-capped".to_string())
+            })
+            .unwrap();
+        // shrunk to the smallest deposit exceeding the cap
+        assert_eq!(failure.args, vec![Value::UInt(1001)]);
+        assert!(!report
+            .failures
+            .iter()
+            .any(|failure| matches!(failure.kind, FailureKind::PostConditionViolated(_))));
+
+        // in deny mode, burning tokens without a post-condition is reported
+        let deny = PostConditions::new(PostConditionMode::Deny, vec![]);
+        let report = fuzz_contract(&session, &contract_id, &senders, 100, 0, &deny).unwrap();
+        let failure = report
+            .failures
+            .iter()
+            .find(|failure| matches!(failure.kind, FailureKind::PostConditionViolated(_)))
+            .unwrap();
+        assert_eq!(failure.function, "burn");
+        assert_eq!(failure.args, vec![Value::UInt(1)]);
+
+        let mut rng = Rng::new(0);
+        let value = random_value(
+            &mut rng,
+            &TypeSignature::new_option(TypeSignature::UIntType).unwrap(),
+            &senders,
+        );
+        assert!(value.is_some());
+        assert_eq!(
+            shrink_value(&Value::UInt(10)),
+            vec![
+                Value::UInt(0),
+                Value::UInt(5),
+                Value::UInt(8),
+                Value::UInt(9)
+            ]
+        );
+        assert_eq!(
+            shrink_value(&Value::Int(-4)),
+            vec![Value::Int(0), Value::Int(-2), Value::Int(-3)]
+        );
+    }
+}
@@ -0,0 +1,408 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use clarity_repl::clarity::vm::analysis::ContractAnalysis;
+use clarity_repl::clarity::vm::types::FunctionType;
+use clarity_repl::clarity::vm::{ClarityName, SymbolicExpression};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InterfaceChange {
+    FunctionAdded {
+        signature: String,
+    },
+    FunctionRemoved {
+        signature: String,
+    },
+    SignatureChanged {
+        previous: String,
+        new: String,
+    },
+    ErrorCodesChanged {
+        function: String,
+        added: Vec<String>,
+        removed: Vec<String>,
+    },
+    ErrorConstantChanged {
+        name: String,
+        previous: Option<String>,
+        new: Option<String>,
+    },
+}
+
+impl InterfaceChange {
+    /// Removing a function, changing its signature, or changing the value of an
+    /// error constant can break callers.
+    pub fn is_breaking(&self) -> bool {
+        match self {
+            InterfaceChange::FunctionAdded { .. } | InterfaceChange::ErrorCodesChanged { .. } => {
+                false
+            }
+            InterfaceChange::ErrorConstantChanged { previous, .. } => previous.is_some(),
+            _ => true,
+        }
+    }
+}
+
+impl fmt::Display for InterfaceChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InterfaceChange::FunctionAdded { signature } => write!(f, "added `{}`", signature),
+            InterfaceChange::FunctionRemoved { signature } => {
+                write!(f, "removed `{}`", signature)
+            }
+            InterfaceChange::SignatureChanged { previous, new } => {
+                write!(f, "changed `{}` to `{}`", previous, new)
+            }
+            InterfaceChange::ErrorCodesChanged {
+                function,
+                added,
+                removed,
+            } => {
+                write!(f, "error codes of `{}`:", function)?;
+                if !added.is_empty() {
+                    write!(f, " added {}", added.join(", "))?;
+                }
+                if !removed.is_empty() {
+                    write!(f, " removed {}", removed.join(", "))?;
+                }
+                Ok(())
+            }
+            InterfaceChange::ErrorConstantChanged {
+                name,
+                previous,
+                new,
+            } => match (previous, new) {
+                (None, Some(new)) => write!(f, "added error `{}` ({})", name, new),
+                (Some(previous), None) => write!(f, "removed error `{}` ({})", name, previous),
+                (Some(previous), Some(new)) => {
+                    write!(f, "changed error `{}` from {} to {}", name, previous, new)
+                }
+                (None, None) => write!(f, "error `{}`", name),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContractDiff {
+    Added,
+    /// Contract no longer in the project, always breaking
+    Removed,
+    Changed(Vec<InterfaceChange>),
+}
+
+fn function_signature(access: &str, name: &ClarityName, function_type: &FunctionType) -> String {
+    match function_type {
+        FunctionType::Fixed(function) => {
+            let args = function
+                .args
+                .iter()
+                .map(|arg| format!(" ({} {})", arg.name, arg.signature))
+                .collect::<String>();
+            format!("({} ({}{}) {})", access, name, args, function.returns)
+        }
+        _ => format!("({} ({}))", access, name),
+    }
+}
+
+pub fn functions_signatures(analysis: &ContractAnalysis) -> BTreeMap<String, String> {
+    let mut signatures = BTreeMap::new();
+    for (access, functions) in [
+        ("define-public", &analysis.public_function_types),
+        ("define-read-only", &analysis.read_only_function_types),
+    ] {
+        for (name, function_type) in functions.iter() {
+            signatures.insert(
+                name.to_string(),
+                function_signature(access, name, function_type),
+            );
+        }
+    }
+    signatures
+}
+
+/// Constants defined as `(err <value>)`, mapped to their value
+pub fn error_constants(analysis: &ContractAnalysis) -> BTreeMap<String, String> {
+    let mut constants = BTreeMap::new();
+    for expr in analysis.expressions.iter() {
+        let Some([define, name, value]) = expr.match_list() else {
+            continue;
+        };
+        if define.match_atom().map(|a| a.as_str()) != Some("define-constant") {
+            continue;
+        }
+        if let (Some(name), Some(code)) = (name.match_atom(), error_code(value)) {
+            constants.insert(name.to_string(), code);
+        }
+    }
+    constants
+}
+
+fn error_code(expr: &SymbolicExpression) -> Option<String> {
+    match expr.match_list() {
+        Some([function, code]) if function.match_atom().map(|a| a.as_str()) == Some("err") => {
+            code.match_literal_value().map(|value| value.to_string())
+        }
+        _ => None,
+    }
+}
+
+fn collect_error_codes(
+    expr: &SymbolicExpression,
+    constants: &BTreeMap<String, String>,
+    private_functions: &BTreeMap<String, &SymbolicExpression>,
+    visited: &mut BTreeSet<String>,
+    codes: &mut BTreeSet<String>,
+) {
+    if let Some(code) = error_code(expr) {
+        codes.insert(code);
+    } else if let Some(atom) = expr.match_atom() {
+        if let Some(code) = constants.get(atom.as_str()) {
+            codes.insert(code.clone());
+        }
+    } else if let Some(list) = expr.match_list() {
+        // errors returned by the private functions called are returned as well
+        if let Some(name) = list.first().and_then(|f| f.match_atom()) {
+            if let Some(body) = private_functions.get(name.as_str()) {
+                if visited.insert(name.to_string()) {
+                    collect_error_codes(body, constants, private_functions, visited, codes);
+                }
+            }
+        }
+        for expr in list.iter() {
+            collect_error_codes(expr, constants, private_functions, visited, codes);
+        }
+    }
+}
+
+/// Functions defined with one of `defines`, with their body
+fn defined_functions<'a>(
+    analysis: &'a ContractAnalysis,
+    defines: &[&str],
+) -> BTreeMap<String, &'a SymbolicExpression> {
+    let mut functions = BTreeMap::new();
+    for expr in analysis.expressions.iter() {
+        let Some([define, signature, body]) = expr.match_list() else {
+            continue;
+        };
+        if !define
+            .match_atom()
+            .map(|a| defines.contains(&a.as_str()))
+            .unwrap_or(false)
+        {
+            continue;
+        }
+        let Some(name) = signature
+            .match_list()
+            .and_then(|s| s.first())
+            .and_then(|n| n.match_atom())
+        else {
+            continue;
+        };
+        functions.insert(name.to_string(), body);
+    }
+    functions
+}
+
+/// Error codes each public and read-only function can return, inline, through constants
+/// or through the private functions it calls
+pub fn functions_error_codes(analysis: &ContractAnalysis) -> BTreeMap<String, BTreeSet<String>> {
+    let constants = error_constants(analysis);
+    let private_functions = defined_functions(analysis, &["define-private"]);
+    let mut functions = BTreeMap::new();
+    for (name, body) in defined_functions(analysis, &["define-public", "define-read-only"]) {
+        let mut codes = BTreeSet::new();
+        let mut visited = BTreeSet::new();
+        collect_error_codes(
+            body,
+            &constants,
+            &private_functions,
+            &mut visited,
+            &mut codes,
+        );
+        functions.insert(name, codes);
+    }
+    functions
+}
+
+/// Compare the public interface of two versions of a contract: public and read-only
+/// functions signatures, the errors they return, and the error constants.
+pub fn diff_contract_interfaces(
+    previous: &ContractAnalysis,
+    new: &ContractAnalysis,
+) -> Vec<InterfaceChange> {
+    let mut changes = vec![];
+
+    let previous_signatures = functions_signatures(previous);
+    let new_signatures = functions_signatures(new);
+    for (name, signature) in previous_signatures.iter() {
+        match new_signatures.get(name) {
+            None => changes.push(InterfaceChange::FunctionRemoved {
+                signature: signature.clone(),
+            }),
+            Some(new_signature) if new_signature != signature => {
+                changes.push(InterfaceChange::SignatureChanged {
+                    previous: signature.clone(),
+                    new: new_signature.clone(),
+                })
+            }
+            Some(_) => {}
+        }
+    }
+    for (name, signature) in new_signatures.iter() {
+        if !previous_signatures.contains_key(name) {
+            changes.push(InterfaceChange::FunctionAdded {
+                signature: signature.clone(),
+            });
+        }
+    }
+
+    let previous_codes = functions_error_codes(previous);
+    for (function, codes) in functions_error_codes(new).iter() {
+        let Some(previous_codes) = previous_codes.get(function) else {
+            continue;
+        };
+        let added: Vec<String> = codes.difference(previous_codes).cloned().collect();
+        let removed: Vec<String> = previous_codes.difference(codes).cloned().collect();
+        if !added.is_empty() || !removed.is_empty() {
+            changes.push(InterfaceChange::ErrorCodesChanged {
+                function: function.clone(),
+                added,
+                removed,
+            });
+        }
+    }
+
+    let previous_constants = error_constants(previous);
+    let new_constants = error_constants(new);
+    let names: BTreeSet<&String> = previous_constants
+        .keys()
+        .chain(new_constants.keys())
+        .collect();
+    for name in names {
+        let previous = previous_constants.get(name);
+        let new = new_constants.get(name);
+        if previous != new {
+            changes.push(InterfaceChange::ErrorConstantChanged {
+                name: name.clone(),
+                previous: previous.cloned(),
+                new: new.cloned(),
+            });
+        }
+    }
+
+    changes
+}
+
+/// Format the diffs of a project's contracts as a markdown list, meant for changelogs.
+pub fn diffs_to_markdown(diffs: &BTreeMap<String, ContractDiff>) -> String {
+    let mut output = vec![];
+    for (contract_name, diff) in diffs.iter() {
+        match diff {
+            ContractDiff::Added => output.push(format!("- `{}`: new contract", contract_name)),
+            ContractDiff::Removed => output.push(format!(
+                "- `{}`: **breaking:** contract removed",
+                contract_name
+            )),
+            ContractDiff::Changed(changes) if changes.is_empty() => {}
+            ContractDiff::Changed(changes) => {
+                output.push(format!("- `{}`", contract_name));
+                for change in changes.iter() {
+                    let prefix = match change.is_breaking() {
+                        true => "**breaking:** ",
+                        false => "",
+                    };
+                    output.push(format!("  - {}{}", prefix, change));
+                }
+            }
+        }
+    }
+    if output.is_empty() {
+        output.push("No interface changes".to_string());
+    }
+    output.push(String::new());
+    output.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clarity_repl::clarity::vm::EvaluationResult;
+    use clarity_repl::clarity::StacksEpochId;
+    use clarity_repl::repl::{Session, SessionSettings};
+
+    fn analysis(session: &mut Session, snippet: &[&str]) -> ContractAnalysis {
+        match session.eval(snippet.join("\n"), false).unwrap().result {
+            EvaluationResult::Contract(evaluation) => evaluation.contract.analysis,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_diff_contract_interfaces() {
+        let mut session = Session::new(SessionSettings::default());
+        session.update_epoch(StacksEpochId::Epoch25);
+        let previous = analysis(
+            &mut session,
+            &[
+                "(define-constant ERR-UNAUTHORIZED (err u100))",
+                "(define-public (transfer (amount uint))",
+                "  (if (> amount u0) (ok true) ERR-UNAUTHORIZED))",
+                "(define-read-only (get-count) u1)",
+                "(define-read-only (get-owner) tx-sender)",
+            ],
+        );
+        let new = analysis(
+            &mut session,
+            &[
+                "(define-constant ERR-UNAUTHORIZED (err u101))",
+                "(define-private (check-amount (amount uint))",
+                "  (if (> amount u0) (ok true) (err u103)))",
+                "(define-public (transfer (amount uint) (memo (buff 34)))",
+                "  (if (> amount u0) (check-amount amount) (err u102)))",
+                "(define-read-only (get-count) u1)",
+                "(define-read-only (get-version) u2)",
+            ],
+        );
+
+        let changes = diff_contract_interfaces(&previous, &new);
+        assert_eq!(
+            changes,
+            vec![
+                InterfaceChange::FunctionRemoved {
+                    signature: "(define-read-only (get-owner) principal)".to_string(),
+                },
+                InterfaceChange::SignatureChanged {
+                    previous: "(define-public (transfer (amount uint)) (response bool uint))"
+                        .to_string(),
+                    new: "(define-public (transfer (amount uint) (memo (buff 34))) (response bool uint))"
+                        .to_string(),
+                },
+                InterfaceChange::FunctionAdded {
+                    signature: "(define-read-only (get-version) uint)".to_string(),
+                },
+                InterfaceChange::ErrorCodesChanged {
+                    function: "transfer".to_string(),
+                    added: vec!["u102".to_string(), "u103".to_string()],
+                    removed: vec!["u100".to_string()],
+                },
+                InterfaceChange::ErrorConstantChanged {
+                    name: "ERR-UNAUTHORIZED".to_string(),
+                    previous: Some("u100".to_string()),
+                    new: Some("u101".to_string()),
+                },
+            ]
+        );
+        assert!(!changes[2].is_breaking());
+        assert!(changes[4].is_breaking());
+
+        let mut diffs = BTreeMap::new();
+        diffs.insert("token".to_string(), ContractDiff::Changed(changes));
+        diffs.insert("vault".to_string(), ContractDiff::Added);
+        diffs.insert("wallet".to_string(), ContractDiff::Removed);
+        let markdown = diffs_to_markdown(&diffs);
+        assert!(markdown.starts_with("- `token`\n  - **breaking:** removed"));
+        assert!(markdown.contains("- `vault`: new contract"));
+        assert!(markdown.contains("- `wallet`: **breaking:** contract removed"));
+    }
+}
@@ -8,11 +8,25 @@ extern crate serde;
 #[macro_use]
 extern crate serde_derive;
 
+pub mod contract_size;
+pub mod cost_curve;
+pub mod costs_report;
+pub mod dependency_graph;
 pub mod diagnostic_digest;
+pub mod differential;
+pub mod error_codes;
+pub mod fuzz;
+pub mod interface_diff;
 #[cfg(feature = "onchain")]
 pub mod onchain;
+pub mod query;
+pub mod replay;
 pub mod requirements;
+pub mod snapshot;
+pub mod test_runner;
 pub mod types;
+pub mod upgrade;
+pub mod variables;
 
 #[cfg(test)]
 mod deployment_plan_test;
@@ -22,20 +36,24 @@ use self::types::{
     TransactionPlanSpecification, TransactionsBatchSpecification, WalletSpecification,
 };
 use clarinet_files::StacksNetwork;
+use clarinet_files::{AccountConfig, GenesisMint, NetworkManifest, ProjectManifest};
 use clarinet_files::{FileAccessor, FileLocation};
-use clarinet_files::{NetworkManifest, ProjectManifest};
 use clarity_repl::analysis::ast_dependency_detector::{ASTDependencyDetector, DependencySet};
 use clarity_repl::clarity::vm::ast::ContractAST;
-use clarity_repl::clarity::vm::diagnostic::Diagnostic;
+use clarity_repl::clarity::vm::diagnostic::{Diagnostic, Level};
 use clarity_repl::clarity::vm::types::PrincipalData;
 use clarity_repl::clarity::vm::types::QualifiedContractIdentifier;
-use clarity_repl::clarity::vm::ContractName;
+use clarity_repl::clarity::vm::types::StandardPrincipalData;
 use clarity_repl::clarity::vm::EvaluationResult;
 use clarity_repl::clarity::vm::ExecutionResult;
+use clarity_repl::clarity::vm::{ClarityName, ContractName};
+use clarity_repl::repl::post_conditions::{PostCondition, PostConditionMode, PostConditions};
 use clarity_repl::repl::session::BOOT_CONTRACTS_DATA;
 use clarity_repl::repl::Session;
 use clarity_repl::repl::SessionSettings;
 use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use types::ContractCallSpecification;
+use types::PostConditionSpecification;
 use types::TransactionSpecification;
 use types::{ContractPublishSpecification, EpochSpec};
 use types::{DeploymentGenerationArtifacts, StxTransferSpecification};
@@ -118,12 +136,11 @@ fn update_session_with_genesis_accounts(
     }
 }
 
-pub fn update_session_with_deployment_plan(
+/// Fund the genesis accounts of the deployment and publish the boot contracts
+pub(crate) fn update_session_with_genesis(
     session: &mut Session,
     deployment: &DeploymentSpecification,
-    contracts_asts: Option<&BTreeMap<QualifiedContractIdentifier, ContractAST>>,
-    forced_min_epoch: Option<StacksEpochId>,
-) -> UpdateSessionExecutionResult {
+) -> ExecutionResultMap {
     update_session_with_genesis_accounts(session, deployment);
 
     let boot_contracts_data = BOOT_CONTRACTS_DATA.clone();
@@ -135,6 +152,16 @@ pub fn update_session_with_deployment_plan(
             .run(&boot_contract, Some(&ast), false, None);
         boot_contracts.insert(contract_id, result);
     }
+    boot_contracts
+}
+
+pub fn update_session_with_deployment_plan(
+    session: &mut Session,
+    deployment: &DeploymentSpecification,
+    contracts_asts: Option<&BTreeMap<QualifiedContractIdentifier, ContractAST>>,
+    forced_min_epoch: Option<StacksEpochId>,
+) -> UpdateSessionExecutionResult {
+    let boot_contracts = update_session_with_genesis(session, deployment);
 
     let mut contracts = BTreeMap::new();
     for batch in deployment.plan.batches.iter() {
@@ -159,14 +186,15 @@ pub fn update_session_with_deployment_plan(
                         tx.contract_name.clone(),
                     );
                     let contract_ast = contracts_asts.as_ref().and_then(|m| m.get(&contract_id));
-                    let result = handle_emulated_contract_publish(session, tx, contract_ast, epoch);
+                    let result =
+                        handle_emulated_contract_publish(session, tx, contract_ast, epoch, false);
                     contracts.insert(contract_id, result);
                 }
                 TransactionSpecification::EmulatedContractCall(tx) => {
-                    let _ = handle_emulated_contract_call(session, tx);
+                    let _ = handle_emulated_contract_call(session, tx, false);
                 }
                 TransactionSpecification::StxTransfer(tx) => {
-                    handle_stx_transfer(session, tx);
+                    let _ = handle_stx_transfer(session, tx);
                 }
             }
         }
@@ -177,20 +205,25 @@ pub fn update_session_with_deployment_plan(
     }
 }
 
-fn handle_stx_transfer(session: &mut Session, tx: &StxTransferSpecification) {
+pub(crate) fn handle_stx_transfer(
+    session: &mut Session,
+    tx: &StxTransferSpecification,
+) -> Result<ExecutionResult, Vec<Diagnostic>> {
     let default_tx_sender = session.get_tx_sender();
     session.set_tx_sender(&tx.expected_sender.to_string());
 
-    let _ = session.stx_transfer(tx.mstx_amount, &tx.recipient.to_string());
+    let result = session.stx_transfer(tx.mstx_amount, &tx.recipient.to_string());
 
     session.set_tx_sender(&default_tx_sender);
+    result
 }
 
-fn handle_emulated_contract_publish(
+pub(crate) fn handle_emulated_contract_publish(
     session: &mut Session,
     tx: &EmulatedContractPublishSpecification,
     contract_ast: Option<&ContractAST>,
     epoch: StacksEpochId,
+    track_costs: bool,
 ) -> Result<ExecutionResult, Vec<Diagnostic>> {
     let default_tx_sender = session.get_tx_sender();
     session.set_tx_sender(&tx.emulated_sender.to_string());
@@ -203,7 +236,7 @@ fn handle_emulated_contract_publish(
         epoch,
     };
 
-    let result = session.deploy_contract(&contract, false, contract_ast);
+    let result = session.deploy_contract(&contract, track_costs, contract_ast);
 
     session.set_tx_sender(&default_tx_sender);
     result
@@ -219,10 +252,57 @@ fn eval_clarity_string(session: &mut Session, snippet: &str) -> SymbolicExpressi
     SymbolicExpression::atom_value(value)
 }
 
-fn handle_emulated_contract_call(
+/// Evaluate the post-conditions of a contract call, so that they can be enforced by the session
+pub fn build_post_conditions(
+    session: &mut Session,
+    mode: PostConditionMode,
+    specs: &[PostConditionSpecification],
+) -> Result<PostConditions, String> {
+    let mut conditions = vec![];
+    for spec in specs.iter() {
+        let condition = match spec {
+            PostConditionSpecification::Stx(principal, code, amount) => {
+                PostCondition::Stx(principal.clone(), *code, (*amount).into())
+            }
+            PostConditionSpecification::Fungible(principal, asset, code, amount) => {
+                PostCondition::Fungible(principal.clone(), asset.clone(), *code, (*amount).into())
+            }
+            PostConditionSpecification::NonFungible(principal, asset, asset_id, code) => {
+                let value = match session.eval(asset_id.to_string(), false) {
+                    Ok(ExecutionResult {
+                        result: EvaluationResult::Snippet(result),
+                        ..
+                    }) => result.result,
+                    _ => return Err(format!("unable to evaluate asset-id '{}'", asset_id)),
+                };
+                PostCondition::NonFungible(principal.clone(), asset.clone(), value, *code)
+            }
+        };
+        conditions.push(condition);
+    }
+    Ok(PostConditions::new(mode, conditions))
+}
+
+pub(crate) fn handle_emulated_contract_call(
     session: &mut Session,
     tx: &EmulatedContractCallSpecification,
+    track_costs: bool,
 ) -> Result<ExecutionResult, Vec<Diagnostic>> {
+    if tx.post_condition_mode == PostConditionMode::Deny || !tx.post_conditions.is_empty() {
+        let post_conditions =
+            build_post_conditions(session, tx.post_condition_mode, &tx.post_conditions).map_err(
+                |message| {
+                    vec![Diagnostic {
+                        level: Level::Error,
+                        message,
+                        spans: vec![],
+                        suggestion: None,
+                    }]
+                },
+            )?;
+        session.interpreter.set_post_conditions(post_conditions);
+    }
+
     let default_tx_sender = session.get_tx_sender();
     session.set_tx_sender(&tx.emulated_sender.to_string());
 
@@ -237,10 +317,10 @@ fn handle_emulated_contract_call(
         &params,
         &tx.emulated_sender.to_string(),
         true,
-        false,
+        track_costs,
     );
     if let Err(errors) = &result {
-        println!("error: {:?}", errors.first().unwrap().message);
+        eprintln!("error: {:?}", errors.first().unwrap().message);
     }
 
     session.set_tx_sender(&default_tx_sender);
@@ -499,7 +579,7 @@ pub async fn generate_default_deployment(
             match dependencies {
                 Ok(inferable_dependencies) => {
                     if inferable_dependencies.len() > 1 {
-                        println!("warning: inferable_dependencies contains more than one entry");
+                        eprintln!("warning: inferable_dependencies contains more than one entry");
                     }
                     // We submitted a HashMap with one contract, so we have at most one result in the `inferable_dependencies` map.
                     // We will extract and keep the associated data (source, ast, deps).
@@ -575,6 +655,7 @@ pub async fn generate_default_deployment(
 
     let mut contracts = HashMap::new();
     let mut contracts_sources = HashMap::new();
+    let mut contracts_ids = HashMap::new();
 
     let base_location = manifest.location.clone().get_parent_location()?;
 
@@ -621,7 +702,10 @@ pub async fn generate_default_deployment(
             Err(_) => return Err(format!("unable to use {} as a valid contract name", name)),
         };
 
-        let deployer = match &contract_config.deployer {
+        let deployer = match &manifest
+            .contract_deployer(name, network)
+            .unwrap_or(contract_config.deployer.clone())
+        {
             ContractDeployer::DefaultDeployer => default_deployer,
             ContractDeployer::LabeledDeployer(deployer) => {
                 let deployer = match network_manifest.accounts.get(deployer) {
@@ -656,6 +740,7 @@ pub async fn generate_default_deployment(
             .clone();
 
         let contract_id = QualifiedContractIdentifier::new(sender.clone(), contract_name.clone());
+        contracts_ids.insert(name.clone(), contract_id.clone());
 
         let epoch = match forced_min_epoch {
             Some(min_epoch) => std::cmp::max(min_epoch, contract_config.epoch),
@@ -684,12 +769,21 @@ pub async fn generate_default_deployment(
                 },
             )
         } else {
+            let cost =
+                deployment_fee_rate.saturating_mul(source.as_bytes().len().try_into().unwrap());
+            if let Some(cost_limit) = network_manifest.network.deployment_cost_limit {
+                if cost > cost_limit {
+                    return Err(format!(
+                        "publishing contract {} costs {}µSTX, above the deployment_cost_limit of {}µSTX",
+                        name, cost, cost_limit
+                    ));
+                }
+            }
             TransactionSpecification::ContractPublish(ContractPublishSpecification {
                 contract_name,
                 expected_sender: sender,
                 location: contract_location,
-                cost: deployment_fee_rate
-                    .saturating_mul(source.as_bytes().len().try_into().unwrap()),
+                cost,
                 source,
                 anchor_block_only: true,
                 clarity_version: contract_config.clarity_version,
@@ -734,6 +828,23 @@ pub async fn generate_default_deployment(
 
     dependencies.extend(requirements_deps);
 
+    // Dependencies that can't be detected in the sources, listed with `depends_on`
+    for (name, depends_on) in manifest.contracts_dependencies.iter() {
+        let Some(dependencies) = contracts_ids
+            .get(name)
+            .and_then(|contract_id| dependencies.get_mut(contract_id))
+        else {
+            continue;
+        };
+        for dependency in depends_on.iter() {
+            if let Some(dependency_id) = contracts_ids.get(dependency) {
+                dependencies.add_dependency(dependency_id.clone(), true);
+            }
+        }
+    }
+
+    check_dependencies_epochs(&dependencies, &contract_epochs)?;
+
     let ordered_contracts_ids =
         match ASTDependencyDetector::order_contracts(&dependencies, &contract_epochs) {
             Ok(ordered_contracts_ids) => ordered_contracts_ids,
@@ -786,6 +897,27 @@ pub async fn generate_default_deployment(
         }
     }
 
+    // tokens pre-minted on devnet, once all the contracts are published
+    let genesis_mints = match (network, &network_manifest.devnet) {
+        (StacksNetwork::Devnet, Some(devnet)) => get_genesis_mints_transactions(
+            &devnet.genesis_mints,
+            &network_manifest.accounts,
+            &default_deployer_address,
+            &contracts_map,
+            deployment_fee_rate,
+        )?,
+        _ => vec![],
+    };
+    let last_epoch = batches.last().and_then(|batch| batch.epoch);
+    for txs in genesis_mints.chunks(tx_chain_limit) {
+        batches.push(TransactionsBatchSpecification {
+            id: batch_count,
+            transactions: txs.to_vec(),
+            epoch: last_epoch,
+        });
+        batch_count += 1;
+    }
+
     let mut wallets = vec![];
     if matches!(network, StacksNetwork::Simnet) {
         for (name, account) in network_manifest.accounts.into_iter() {
@@ -843,6 +975,91 @@ pub async fn generate_default_deployment(
     Ok((deployment, artifacts))
 }
 
+/// Contracts are deployed in the epoch they pin in the manifest, so they can only
+/// depend on contracts pinned to the same epoch or an earlier one.
+fn check_dependencies_epochs(
+    dependencies: &BTreeMap<QualifiedContractIdentifier, DependencySet>,
+    contract_epochs: &HashMap<QualifiedContractIdentifier, StacksEpochId>,
+) -> Result<(), String> {
+    for (contract_id, contract_dependencies) in dependencies.iter() {
+        let Some(epoch) = contract_epochs.get(contract_id) else {
+            continue;
+        };
+        for dependency in contract_dependencies.iter() {
+            match contract_epochs.get(&dependency.contract_id) {
+                Some(dependency_epoch) if dependency_epoch > epoch => {
+                    return Err(format!(
+                        "contract {} (epoch {}) depends on {}, deployed in epoch {}: set the epoch of {} to {} or later",
+                        contract_id.name,
+                        epoch,
+                        dependency.contract_id.name,
+                        dependency_epoch,
+                        contract_id.name,
+                        dependency_epoch
+                    ));
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Contract calls minting the genesis tokens of the devnet accounts
+fn get_genesis_mints_transactions(
+    genesis_mints: &[GenesisMint],
+    accounts: &BTreeMap<String, AccountConfig>,
+    deployer: &StandardPrincipalData,
+    contracts: &BTreeMap<QualifiedContractIdentifier, (String, FileLocation)>,
+    deployment_fee_rate: u64,
+) -> Result<Vec<TransactionSpecification>, String> {
+    let get_account = |wallet: &str| -> Result<&AccountConfig, String> {
+        accounts
+            .get(wallet)
+            .ok_or(format!("unable to retrieve account {}", wallet))
+    };
+
+    let mut transactions = vec![];
+    for mint in genesis_mints.iter() {
+        let contract_id = match mint.contract.contains('.') {
+            true => QualifiedContractIdentifier::parse(&mint.contract)
+                .map_err(|e| format!("invalid contract id {}: {}", mint.contract, e))?,
+            false => {
+                let contract_name = ContractName::try_from(mint.contract.clone())
+                    .map_err(|e| format!("invalid contract name {}: {}", mint.contract, e))?;
+                QualifiedContractIdentifier::new(deployer.clone(), contract_name)
+            }
+        };
+        if !contracts.contains_key(&contract_id) {
+            return Err(format!("genesis mint contract {} not found", contract_id));
+        }
+        let function = mint.function.clone().unwrap_or("mint".to_string());
+        let method = ClarityName::try_from(function.clone())
+            .map_err(|e| format!("invalid function name {}: {}", function, e))?;
+        let sender = get_account(mint.sender.as_deref().unwrap_or("deployer"))?;
+        let sender = PrincipalData::parse_standard_principal(&sender.stx_address)
+            .map_err(|_| format!("unable to parse address {}", sender.stx_address))?;
+        let recipient = get_account(&mint.recipient)?;
+
+        for parameters in mint.get_calls_parameters(&recipient.stx_address) {
+            transactions.push(TransactionSpecification::ContractCall(
+                ContractCallSpecification {
+                    contract_id: contract_id.clone(),
+                    expected_sender: sender.clone(),
+                    method: method.clone(),
+                    parameters,
+                    // rough size of a contract-call transaction
+                    cost: deployment_fee_rate.saturating_mul(1000),
+                    anchor_block_only: true,
+                    post_condition_mode: PostConditionMode::Allow,
+                    post_conditions: vec![],
+                },
+            ));
+        }
+    }
+    Ok(transactions)
+}
+
 fn add_transaction_to_epoch(
     transactions: &mut BTreeMap<EpochSpec, Vec<TransactionSpecification>>,
     transaction: TransactionSpecification,
@@ -873,6 +1090,35 @@ pub fn get_default_deployment_path(
     Ok(deployment_path)
 }
 
+/// Load a plan template, with its `{{ name }}` placeholders resolved from `variables`
+pub fn load_deployment_with_variables(
+    manifest: &ProjectManifest,
+    deployment_plan_location: &FileLocation,
+    variables: &BTreeMap<String, serde_yaml::Value>,
+) -> Result<DeploymentSpecification, String> {
+    let project_root_location = manifest.location.get_project_root_location()?;
+    let content = deployment_plan_location.read_content()?;
+    let template: serde_yaml::Value = serde_yaml::from_slice(&content).map_err(|msg| {
+        format!(
+            "error: {} syntax incorrect\n{}",
+            deployment_plan_location, msg
+        )
+    })?;
+    let plan = variables::substitute_variables(template, variables).map_err(|e| {
+        format!(
+            "error: unable to resolve {}\n{}",
+            deployment_plan_location, e
+        )
+    })?;
+    let content = serde_yaml::to_vec(&plan).map_err(|e| e.to_string())?;
+    DeploymentSpecification::from_config_content(&content, &project_root_location).map_err(|msg| {
+        format!(
+            "error: {} syntax incorrect\n{}",
+            deployment_plan_location, msg
+        )
+    })
+}
+
 pub fn load_deployment(
     manifest: &ProjectManifest,
     deployment_plan_location: &FileLocation,
@@ -896,6 +1142,7 @@ pub fn load_deployment(
 #[cfg(test)]
 mod tests {
     use clarity::vm::{types::TupleData, ClarityName, ClarityVersion, Value};
+    use clarity_repl::repl::post_conditions::FungibleConditionCode;
     use clarity_repl::repl::{clarity_values::to_raw_value, SessionSettings};
 
     use super::*;
@@ -916,7 +1163,95 @@ mod tests {
             location: FileLocation::from_path_string("/contracts/contract_1.clar").unwrap(),
         };
 
-        handle_emulated_contract_publish(session, &emulated_publish_spec, None, epoch)
+        handle_emulated_contract_publish(session, &emulated_publish_spec, None, epoch, false)
+    }
+
+    #[test]
+    fn test_genesis_mints_transactions() {
+        let account = |label: &str, stx_address: &str| AccountConfig {
+            label: label.to_string(),
+            mnemonic: String::new(),
+            derivation: String::new(),
+            secret_key: None,
+            balance: 0,
+            stx_address: stx_address.to_string(),
+            btc_address: String::new(),
+            is_mainnet: false,
+        };
+        let wallet_1 = "ST1SJ3DTE5DN7X54YDH5D64R3BCB6A2AG2ZQ8YPD5";
+        let mut accounts = BTreeMap::new();
+        accounts.insert("deployer".to_string(), account("deployer", DEPLOYER));
+        accounts.insert("wallet_1".to_string(), account("wallet_1", wallet_1));
+
+        let deployer = PrincipalData::parse_standard_principal(DEPLOYER).unwrap();
+        let token_id = QualifiedContractIdentifier::new(deployer.clone(), "token".into());
+        let mut contracts = BTreeMap::new();
+        contracts.insert(
+            token_id.clone(),
+            (
+                String::new(),
+                FileLocation::from_path_string("/contracts/token.clar").unwrap(),
+            ),
+        );
+
+        let mint = GenesisMint {
+            contract: "token".to_string(),
+            function: None,
+            sender: None,
+            recipient: "wallet_1".to_string(),
+            amount: Some(1000),
+            token_ids: Some(vec![1, 2]),
+            count: None,
+        };
+        let transactions =
+            get_genesis_mints_transactions(&[mint.clone()], &accounts, &deployer, &contracts, 10)
+                .unwrap();
+        assert_eq!(transactions.len(), 3);
+        let TransactionSpecification::ContractCall(call) = &transactions[2] else {
+            panic!("expected a contract call");
+        };
+        assert_eq!(call.contract_id, token_id);
+        assert_eq!(call.expected_sender, deployer);
+        assert_eq!(call.method.as_str(), "mint");
+        assert_eq!(
+            call.parameters,
+            vec!["u2".to_string(), format!("'{}", wallet_1)]
+        );
+
+        // contracts of other deployers are referenced by their fully qualified id
+        let nft_id = QualifiedContractIdentifier::parse(&format!("{}.nft", wallet_1)).unwrap();
+        contracts.insert(
+            nft_id.clone(),
+            (
+                String::new(),
+                FileLocation::from_path_string("/contracts/nft.clar").unwrap(),
+            ),
+        );
+        let nft_mint = GenesisMint {
+            contract: nft_id.to_string(),
+            sender: Some("wallet_1".to_string()),
+            amount: None,
+            token_ids: None,
+            count: Some(1),
+            ..mint.clone()
+        };
+        let transactions =
+            get_genesis_mints_transactions(&[nft_mint], &accounts, &deployer, &contracts, 10)
+                .unwrap();
+        let TransactionSpecification::ContractCall(call) = &transactions[0] else {
+            panic!("expected a contract call");
+        };
+        assert_eq!(call.contract_id, nft_id);
+        assert_eq!(call.parameters, vec![format!("'{}", wallet_1)]);
+
+        let unknown = GenesisMint {
+            contract: "nft".to_string(),
+            ..mint
+        };
+        assert!(
+            get_genesis_mints_transactions(&[unknown], &accounts, &deployer, &contracts, 10)
+                .is_err()
+        );
     }
 
     #[test]
@@ -981,8 +1316,10 @@ mod tests {
             emulated_sender: PrincipalData::parse_standard_principal(DEPLOYER).unwrap(),
             method: ClarityName::from("add"),
             parameters: vec!["1".to_string()],
+            post_condition_mode: PostConditionMode::Allow,
+            post_conditions: vec![],
         };
-        let result = handle_emulated_contract_call(&mut session, &contract_call_spec);
+        let result = handle_emulated_contract_call(&mut session, &contract_call_spec, false);
         assert!(result.is_ok());
 
         let var_x = session.interpreter.get_data_var(&contract_id, "x");
@@ -1015,8 +1352,10 @@ mod tests {
             emulated_sender: PrincipalData::parse_standard_principal(DEPLOYER).unwrap(),
             method: ClarityName::from("set-sum"),
             parameters: vec!["2".to_string(), "(list 20 20)".to_string()],
+            post_condition_mode: PostConditionMode::Allow,
+            post_conditions: vec![],
         };
-        let result = handle_emulated_contract_call(&mut session, &contract_call_spec);
+        let result = handle_emulated_contract_call(&mut session, &contract_call_spec, false);
         assert!(result.is_ok());
 
         let var_x = session.interpreter.get_data_var(&contract_id, "sum");
@@ -1049,8 +1388,10 @@ mod tests {
             emulated_sender: PrincipalData::parse_standard_principal(DEPLOYER).unwrap(),
             method: ClarityName::from("set-data"),
             parameters: vec!["{ a: 2 }".to_string(), "{ b: u3 }".to_string()],
+            post_condition_mode: PostConditionMode::Allow,
+            post_conditions: vec![],
         };
-        let result = handle_emulated_contract_call(&mut session, &contract_call_spec);
+        let result = handle_emulated_contract_call(&mut session, &contract_call_spec, false);
         assert!(result.is_ok());
 
         let data = session.interpreter.get_data_var(&contract_id, "data");
@@ -1072,6 +1413,55 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_handle_emulated_contract_call_with_post_conditions() {
+        let mut session = Session::new(SessionSettings::default());
+        let epoch = StacksEpochId::Epoch25;
+        session.update_epoch(epoch);
+
+        let deployer = PrincipalData::parse_standard_principal(DEPLOYER).unwrap();
+        let _ = session
+            .interpreter
+            .mint_stx_balance(PrincipalData::Standard(deployer.clone()), 1000000);
+
+        let snippet = [
+            "(define-public (pay (amount uint))",
+            "  (stx-transfer? amount tx-sender 'ST2CY5V39NHDPWSXMW9QDT3HC3GD6Q6XX4CFRK9AG)",
+            ")",
+        ]
+        .join("\n");
+        let result = deploy_contract(&mut session, "contract_1", &snippet, epoch);
+        assert!(result.is_ok());
+
+        let contract_call_spec = |amount: &str| EmulatedContractCallSpecification {
+            contract_id: QualifiedContractIdentifier::new(
+                deployer.clone(),
+                ContractName::from("contract_1"),
+            ),
+            emulated_sender: deployer.clone(),
+            method: ClarityName::from("pay"),
+            parameters: vec![amount.to_string()],
+            post_condition_mode: PostConditionMode::Deny,
+            post_conditions: vec![PostConditionSpecification::Stx(
+                PrincipalData::Standard(deployer.clone()),
+                FungibleConditionCode::SentLe,
+                1000,
+            )],
+        };
+
+        let result =
+            handle_emulated_contract_call(&mut session, &contract_call_spec("u1000"), false);
+        assert!(result.is_ok());
+
+        let result =
+            handle_emulated_contract_call(&mut session, &contract_call_spec("u1001"), false);
+        assert!(result.is_err());
+        assert_eq!(
+            session.interpreter.get_balance_for_account(DEPLOYER, "STX"),
+            1000000 - 1000
+        );
+    }
+
     #[test]
     fn test_stx_transfer() {
         let mut session = Session::new(SessionSettings::default());
@@ -1103,4 +1493,24 @@ mod tests {
         assert_eq!(*stx_maps.get(sender).unwrap(), 999000);
         assert_eq!(*stx_maps.get(receiver).unwrap(), 1000);
     }
+
+    #[test]
+    fn test_check_dependencies_epochs() {
+        let token = QualifiedContractIdentifier::parse(&format!("{}.token", DEPLOYER)).unwrap();
+        let pool = QualifiedContractIdentifier::parse(&format!("{}.pool", DEPLOYER)).unwrap();
+        let mut pool_dependencies = DependencySet::new();
+        pool_dependencies.add_dependency(token.clone(), true);
+        let mut dependencies = BTreeMap::new();
+        dependencies.insert(token.clone(), DependencySet::new());
+        dependencies.insert(pool.clone(), pool_dependencies);
+
+        let mut contract_epochs = HashMap::new();
+        contract_epochs.insert(token.clone(), StacksEpochId::Epoch21);
+        contract_epochs.insert(pool.clone(), StacksEpochId::Epoch25);
+        assert!(check_dependencies_epochs(&dependencies, &contract_epochs).is_ok());
+
+        contract_epochs.insert(pool, StacksEpochId::Epoch2_05);
+        let error = check_dependencies_epochs(&dependencies, &contract_epochs).unwrap_err();
+        assert!(error.starts_with("contract pool (epoch 2.05) depends on token"));
+    }
 }
@@ -131,12 +131,12 @@ pub fn sign_transaction(
     }
 }
 
-pub fn send_transaction_spec(
-    bitcoin_rpc: &Client,
+/// Build and sign the transaction of `tx_spec`, spending the UTXOs of the sender
+pub fn sign_transaction_spec(
     bitcoin_wallet_rpc: &Client,
     tx_spec: &BtcTransferSpecification,
     signer: &SecretKey,
-) -> Result<bitcoincore_rpc::bitcoin::Txid, String> {
+) -> Transaction {
     // In this v1, we're assuming that the bitcoin node is indexing sender's UTXOs.
     let sender_address =
         Address::from_str(&tx_spec.expected_sender).expect("Unable to parse address");
@@ -153,6 +153,16 @@ pub fn send_transaction_spec(
 
     let (mut transaction, selected_utxos) = build_transaction_spec(tx_spec, &mut utxos);
     sign_transaction(&mut transaction, selected_utxos, signer);
+    transaction
+}
+
+pub fn send_transaction_spec(
+    bitcoin_rpc: &Client,
+    bitcoin_wallet_rpc: &Client,
+    tx_spec: &BtcTransferSpecification,
+    signer: &SecretKey,
+) -> Result<bitcoincore_rpc::bitcoin::Txid, String> {
+    let transaction = sign_transaction_spec(bitcoin_wallet_rpc, tx_spec, signer);
 
     println!("-> Transaction\n{:?}", transaction);
 
@@ -0,0 +1,540 @@
+use std::str::FromStr;
+
+use bitcoin::absolute::LockTime;
+use bitcoin::blockdata::opcodes;
+use bitcoin::blockdata::script::Builder;
+use bitcoin::consensus::encode;
+use bitcoin::hashes::Hash;
+use bitcoin::psbt::Psbt;
+use bitcoin::script::PushBytes;
+use bitcoin::sighash::SighashCache;
+use bitcoin::transaction::Version;
+use bitcoin::{
+    Amount, Network, OutPoint, PrivateKey, PubkeyHash, ScriptBuf, Sequence, Transaction, TxIn,
+    Witness,
+};
+pub use bitcoin::{TxOut, Txid};
+use bitcoincore_rpc::bitcoin::secp256k1::{Message, PublicKey, Secp256k1};
+use bitcoincore_rpc::bitcoin::{Address, BlockHash};
+use bitcoincore_rpc::{Auth, Client, RpcApi};
+use clarinet_files::AccountConfig;
+
+use super::get_btc_keypair;
+
+/// Magic bytes prefixing Stacks burnchain operations on regtest.
+pub const DEVNET_MAGIC_BYTES: [u8; 2] = [b'i', b'd'];
+/// Largest payload relayed by bitcoind nodes in an OP_RETURN output.
+pub const MAX_OP_RETURN_LEN: usize = 80;
+/// Smallest amount accepted in a P2PKH output.
+pub const DUST_AMOUNT: u64 = 546;
+/// Size used for estimating the fees of the devnet transactions.
+const TYPICAL_TX_SIZE: u64 = 600;
+const PRE_STX_OPCODE: u8 = b'p';
+const STACK_STX_OPCODE: u8 = b'x';
+
+/// Bitcoin RPC clients for a devnet bitcoin node: the node itself, and the
+/// watch-only wallet tracking the devnet accounts UTXOs.
+pub struct DevnetBitcoinRpc {
+    rpc: Client,
+    wallet_rpc: Client,
+}
+
+impl DevnetBitcoinRpc {
+    pub fn new(host: &str, username: &str, password: &str) -> Result<Self, String> {
+        let auth = Auth::UserPass(username.to_string(), password.to_string());
+        let rpc = Client::new(&format!("http://{}", host), auth.clone())
+            .map_err(|e| format!("unable to create bitcoin rpc client: {}", e))?;
+        let wallet_rpc = Client::new(&format!("http://{}/wallet/", host), auth)
+            .map_err(|e| format!("unable to create bitcoin rpc client: {}", e))?;
+        Ok(DevnetBitcoinRpc { rpc, wallet_rpc })
+    }
+
+    pub fn mine_blocks(&self, count: u64, recipient: &str) -> Result<Vec<BlockHash>, String> {
+        let address = parse_address(recipient)?;
+        self.rpc
+            .generate_to_address(count, &address)
+            .map_err(|e| format!("unable to generate blocks: {}", e))
+    }
+
+    /// Fund, sign and broadcast a transaction paying `outputs` from the P2PKH
+    /// address of `sender`, sending the change back to it.
+    pub fn send_outputs(
+        &self,
+        sender: &AccountConfig,
+        outputs: Vec<TxOut>,
+        sats_per_byte: u64,
+    ) -> Result<Txid, String> {
+        let mut psbt = self.build_psbt(sender, outputs, sats_per_byte)?;
+        sign_psbt(&mut psbt, sender)?;
+        self.broadcast_psbt(psbt)
+    }
+
+    /// Unsigned PSBT paying `outputs` from the UTXOs of the P2PKH address of `sender`,
+    /// sending the change back to it. The previous transactions are attached to the inputs.
+    pub fn build_psbt(
+        &self,
+        sender: &AccountConfig,
+        outputs: Vec<TxOut>,
+        sats_per_byte: u64,
+    ) -> Result<Psbt, String> {
+        let sender_address = parse_address(&sender.btc_address)?;
+        let utxos = self
+            .wallet_rpc
+            .list_unspent(None, None, Some(&[&sender_address]), None, None)
+            .map_err(|e| format!("unable to retrieve UTXOs: {}", e))?;
+        let utxos = utxos
+            .into_iter()
+            .map(|utxo| {
+                let txout = TxOut {
+                    value: utxo.amount,
+                    script_pubkey: ScriptBuf::from(utxo.script_pub_key.into_bytes()),
+                };
+                (OutPoint::new(utxo.txid, utxo.vout), txout)
+            })
+            .collect();
+
+        let transaction = build_transaction(&sender.btc_address, utxos, outputs, sats_per_byte)?;
+        let mut psbt = Psbt::from_unsigned_tx(transaction)
+            .map_err(|e| format!("unable to create PSBT: {}", e))?;
+        for (input, psbt_input) in psbt.unsigned_tx.input.iter().zip(psbt.inputs.iter_mut()) {
+            let previous_transaction = self
+                .rpc
+                .get_raw_transaction(&input.previous_output.txid, None)
+                .map_err(|e| format!("unable to retrieve transaction: {}", e))?;
+            psbt_input.non_witness_utxo = Some(previous_transaction);
+        }
+        Ok(psbt)
+    }
+
+    /// Unsigned PSBT of a `stack-stx` operation spending the `pre-stx` operation `pre_stx_txid`
+    /// sent to `stacker`, which can still be in the mempool.
+    pub fn build_stack_stx_psbt(
+        &self,
+        stacker: &AccountConfig,
+        pre_stx_txid: &Txid,
+        ustx_amount: u128,
+        num_cycles: u8,
+        pox_address: &str,
+        sats_per_byte: u64,
+    ) -> Result<Psbt, String> {
+        let pre_stx = self
+            .rpc
+            .get_raw_transaction(pre_stx_txid, None)
+            .map_err(|e| format!("unable to retrieve transaction: {}", e))?;
+        stack_stx_psbt(
+            &stacker.btc_address,
+            pre_stx,
+            ustx_amount,
+            num_cycles,
+            pox_address,
+            sats_per_byte,
+        )
+    }
+
+    /// Broadcast a `stack-stx` operation locking the STX of `stacker`, preceded by the
+    /// `pre-stx` operation it spends. Returns the txids of both transactions.
+    pub fn send_stack_stx(
+        &self,
+        stacker: &AccountConfig,
+        ustx_amount: u128,
+        num_cycles: u8,
+        pox_address: &str,
+        sats_per_byte: u64,
+    ) -> Result<(Txid, Txid), String> {
+        let outputs = pre_stx_outputs(&stacker.btc_address, stack_stx_funding(sats_per_byte))?;
+        let mut psbt = self.build_psbt(stacker, outputs, sats_per_byte)?;
+        sign_psbt(&mut psbt, stacker)?;
+        let pre_stx = extract_signed_transaction(psbt)?;
+        let pre_stx_txid = self.broadcast_transaction(&pre_stx)?;
+
+        let mut psbt = stack_stx_psbt(
+            &stacker.btc_address,
+            pre_stx,
+            ustx_amount,
+            num_cycles,
+            pox_address,
+            sats_per_byte,
+        )?;
+        sign_psbt(&mut psbt, stacker)?;
+        let stack_stx_txid = self.broadcast_psbt(psbt)?;
+        Ok((pre_stx_txid, stack_stx_txid))
+    }
+
+    /// Broadcast a PSBT whose inputs are all finalized
+    pub fn broadcast_psbt(&self, psbt: Psbt) -> Result<Txid, String> {
+        let transaction = extract_signed_transaction(psbt)?;
+        self.broadcast_transaction(&transaction)
+    }
+
+    fn broadcast_transaction(&self, transaction: &Transaction) -> Result<Txid, String> {
+        self.rpc
+            .send_raw_transaction(&encode::serialize(transaction))
+            .map_err(|e| format!("unable to broadcast transaction: {}", e))
+    }
+}
+
+fn extract_signed_transaction(psbt: Psbt) -> Result<Transaction, String> {
+    if let Some(index) = psbt
+        .inputs
+        .iter()
+        .position(|input| input.final_script_sig.is_none())
+    {
+        return Err(format!("input {} of the PSBT is not signed", index));
+    }
+    psbt.extract_tx()
+        .map_err(|e| format!("unable to extract transaction: {}", e))
+}
+
+/// Regtest WIF of the bitcoin key of `account`, for importing it in a wallet.
+pub fn get_account_bitcoin_wif(account: &AccountConfig) -> String {
+    let (secret_key, _) = get_btc_keypair(account);
+    PrivateKey::new(secret_key, Network::Regtest).to_wif()
+}
+
+/// Sign and finalize the inputs of `psbt` spending from the P2PKH address of `signer`,
+/// other inputs are left untouched. Returns the number of inputs signed.
+pub fn sign_psbt(psbt: &mut Psbt, signer: &AccountConfig) -> Result<usize, String> {
+    let (secret_key, _) = get_btc_keypair(signer);
+    let secp = Secp256k1::new();
+    let public_key = PublicKey::from_secret_key(&secp, &secret_key);
+    let script_pubkey = parse_address(&signer.btc_address)?.script_pubkey();
+
+    let mut signed = 0;
+    for i in 0..psbt.inputs.len() {
+        let vout = psbt.unsigned_tx.input[i].previous_output.vout as usize;
+        let spends_from_signer = psbt.inputs[i]
+            .non_witness_utxo
+            .as_ref()
+            .and_then(|previous_transaction| previous_transaction.output.get(vout))
+            .map(|spent| spent.script_pubkey == script_pubkey)
+            .ok_or(format!("previous transaction of input {} is missing", i))?;
+        if !spends_from_signer {
+            continue;
+        }
+
+        let sig_hash_all = 0x01;
+        let sig_hash = SighashCache::new(&psbt.unsigned_tx)
+            .legacy_signature_hash(i, &script_pubkey, sig_hash_all)
+            .map_err(|e| format!("unable to compute signature hash: {}", e))?;
+        let message = Message::from_digest_slice(&sig_hash[..])
+            .map_err(|e| format!("unable to create message: {}", e))?;
+        let signature = secp.sign_ecdsa(&message, &secret_key).serialize_der();
+        let sig_slice = [&*signature, &[sig_hash_all as u8][..]].concat();
+        psbt.inputs[i].final_script_sig = Some(
+            Builder::new()
+                .push_slice(<&PushBytes>::try_from(sig_slice.as_slice()).unwrap())
+                .push_slice(public_key.serialize())
+                .into_script(),
+        );
+        signed += 1;
+    }
+    Ok(signed)
+}
+
+pub fn psbt_to_base64(psbt: &Psbt) -> String {
+    use base64::{engine::general_purpose::STANDARD as b64, Engine as _};
+    b64.encode(psbt.serialize())
+}
+
+pub fn psbt_from_base64(encoded: &str) -> Result<Psbt, String> {
+    use base64::{engine::general_purpose::STANDARD as b64, Engine as _};
+    let bytes = b64
+        .decode(encoded)
+        .map_err(|e| format!("invalid PSBT encoding: {}", e))?;
+    Psbt::deserialize(&bytes).map_err(|e| format!("invalid PSBT: {}", e))
+}
+
+fn parse_address(address: &str) -> Result<Address, String> {
+    Address::from_str(address)
+        .map(|address| address.assume_checked())
+        .map_err(|e| format!("invalid bitcoin address {}: {}", address, e))
+}
+
+fn p2pkh_script(pubkey_hash: &[u8]) -> Result<ScriptBuf, String> {
+    let pubkey_hash = PubkeyHash::from_slice(pubkey_hash).map_err(|e| e.to_string())?;
+    Ok(Builder::new()
+        .push_opcode(opcodes::all::OP_DUP)
+        .push_opcode(opcodes::all::OP_HASH160)
+        .push_slice(pubkey_hash)
+        .push_opcode(opcodes::all::OP_EQUALVERIFY)
+        .push_opcode(opcodes::all::OP_CHECKSIG)
+        .into_script())
+}
+
+pub fn payment_output(recipient: &str, sats_amount: u64) -> Result<TxOut, String> {
+    Ok(TxOut {
+        value: Amount::from_sat(sats_amount),
+        script_pubkey: parse_address(recipient)?.script_pubkey(),
+    })
+}
+
+pub fn op_return_output(payload: &[u8]) -> Result<TxOut, String> {
+    if payload.len() > MAX_OP_RETURN_LEN {
+        return Err(format!(
+            "OP_RETURN payload too large: {} bytes (max {})",
+            payload.len(),
+            MAX_OP_RETURN_LEN
+        ));
+    }
+    let payload = <&PushBytes>::try_from(payload).map_err(|e| e.to_string())?;
+    Ok(TxOut {
+        value: Amount::ZERO,
+        script_pubkey: Builder::new()
+            .push_opcode(opcodes::all::OP_RETURN)
+            .push_slice(payload)
+            .into_script(),
+    })
+}
+
+/// Output sending `sats_amount` to the burn address (P2PKH of a zeroed hash160).
+pub fn burn_output(sats_amount: u64) -> Result<TxOut, String> {
+    Ok(TxOut {
+        value: Amount::from_sat(sats_amount),
+        script_pubkey: p2pkh_script(&[0u8; 20])?,
+    })
+}
+
+/// Outputs of a `pre-stx` burnchain operation, the second output sending `sats_amount`
+/// to `stacker`. stacks-node only accepts a `stack-stx` operation whose first input
+/// spends this output, and locks the STX of `stacker`.
+pub fn pre_stx_outputs(stacker: &str, sats_amount: u64) -> Result<Vec<TxOut>, String> {
+    let mut payload = DEVNET_MAGIC_BYTES.to_vec();
+    payload.push(PRE_STX_OPCODE);
+    Ok(vec![
+        op_return_output(&payload)?,
+        payment_output(stacker, sats_amount)?,
+    ])
+}
+
+/// Amount to send in a `pre-stx` output for funding the `stack-stx` operation spending it.
+pub fn stack_stx_funding(sats_per_byte: u64) -> u64 {
+    DUST_AMOUNT + sats_per_byte * TYPICAL_TX_SIZE
+}
+
+/// Payload of a `stack-stx` burnchain operation:
+/// `magic (2) | opcode (1) | uSTX to lock (16) | number of cycles (1)`.
+pub fn stack_stx_payload(ustx_amount: u128, num_cycles: u8) -> Vec<u8> {
+    let mut payload = DEVNET_MAGIC_BYTES.to_vec();
+    payload.push(STACK_STX_OPCODE);
+    payload.extend(ustx_amount.to_be_bytes());
+    payload.push(num_cycles);
+    payload
+}
+
+/// Outputs of a `stack-stx` burnchain operation, the second output being the
+/// PoX reward address. These outputs alone are ignored by stacks-node: the transaction
+/// must spend a `pre-stx` output first, see `stack_stx_psbt`.
+pub fn stack_stx_outputs(
+    ustx_amount: u128,
+    num_cycles: u8,
+    pox_address: &str,
+) -> Result<Vec<TxOut>, String> {
+    Ok(vec![
+        op_return_output(&stack_stx_payload(ustx_amount, num_cycles))?,
+        payment_output(pox_address, DUST_AMOUNT)?,
+    ])
+}
+
+/// Unsigned PSBT of a `stack-stx` operation whose only input is the second output of the
+/// `pre_stx` transaction, sent to `stacker`.
+pub fn stack_stx_psbt(
+    stacker: &str,
+    pre_stx: Transaction,
+    ustx_amount: u128,
+    num_cycles: u8,
+    pox_address: &str,
+    sats_per_byte: u64,
+) -> Result<Psbt, String> {
+    let stacker_script = parse_address(stacker)?.script_pubkey();
+    let funding = match pre_stx.output.get(1) {
+        Some(output) if output.script_pubkey == stacker_script => output.clone(),
+        _ => {
+            return Err(format!(
+                "transaction {} is not a pre-stx operation sent to {}",
+                pre_stx.txid(),
+                stacker
+            ))
+        }
+    };
+    let utxos = vec![(OutPoint::new(pre_stx.txid(), 1), funding)];
+    let outputs = stack_stx_outputs(ustx_amount, num_cycles, pox_address)?;
+    let transaction = build_transaction(stacker, utxos, outputs, sats_per_byte)?;
+    let mut psbt =
+        Psbt::from_unsigned_tx(transaction).map_err(|e| format!("unable to create PSBT: {}", e))?;
+    psbt.inputs[0].non_witness_utxo = Some(pre_stx);
+    Ok(psbt)
+}
+
+/// Build an unsigned transaction spending the largest UTXOs first to cover the outputs
+/// and the fees, with a change output sent back to the P2PKH `sender`.
+pub fn build_transaction(
+    sender: &str,
+    mut utxos: Vec<(OutPoint, TxOut)>,
+    outputs: Vec<TxOut>,
+    sats_per_byte: u64,
+) -> Result<Transaction, String> {
+    let sender_script = parse_address(sender)?.script_pubkey();
+    if !sender_script.is_p2pkh() {
+        return Err(format!("sender {} is not a P2PKH address", sender));
+    }
+
+    let tx_fee = sats_per_byte * TYPICAL_TX_SIZE;
+    let total_required = outputs.iter().map(|o| o.value.to_sat()).sum::<u64>() + tx_fee;
+
+    utxos.sort_by(|a, b| b.1.value.cmp(&a.1.value));
+    let mut inputs = vec![];
+    let mut cumulated_amount = 0;
+    for (outpoint, txout) in utxos.into_iter() {
+        if cumulated_amount >= total_required {
+            break;
+        }
+        cumulated_amount += txout.value.to_sat();
+        inputs.push(TxIn {
+            previous_output: outpoint,
+            script_sig: ScriptBuf::default(),
+            sequence: Sequence(0xFFFFFFFD), // allow RBF
+            witness: Witness::new(),
+        });
+    }
+    if cumulated_amount < total_required {
+        return Err(format!(
+            "insufficient funds: {} sats available, {} sats required",
+            cumulated_amount, total_required
+        ));
+    }
+
+    let mut output = outputs;
+    let change = cumulated_amount - total_required;
+    if change >= DUST_AMOUNT {
+        output.push(TxOut {
+            value: Amount::from_sat(change),
+            script_pubkey: sender_script,
+        });
+    }
+
+    Ok(Transaction {
+        version: Version::ONE,
+        lock_time: LockTime::ZERO,
+        input: inputs,
+        output,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SENDER: &str = "mqVnk6NPRdhntvfm4hh9vvjiRkFDUuSYsH";
+
+    #[test]
+    fn test_stack_stx_payload() {
+        let payload = stack_stx_payload(100_000_000_000, 6);
+        assert_eq!(payload.len(), 20);
+        assert_eq!(&payload[..3], b"idx");
+        assert_eq!(&payload[3..19], &100_000_000_000u128.to_be_bytes());
+        assert_eq!(payload[19], 6);
+
+        let outputs = stack_stx_outputs(100_000_000_000, 6, SENDER).unwrap();
+        assert!(outputs[0].script_pubkey.is_op_return());
+        assert_eq!(outputs[1].value.to_sat(), DUST_AMOUNT);
+    }
+
+    #[test]
+    fn test_stack_stx_psbt() {
+        let pre_stx = Transaction {
+            version: Version::ONE,
+            lock_time: LockTime::ZERO,
+            input: vec![],
+            output: pre_stx_outputs(SENDER, stack_stx_funding(10)).unwrap(),
+        };
+        assert_eq!(
+            pre_stx.output[0].script_pubkey,
+            op_return_output(b"idp").unwrap().script_pubkey
+        );
+
+        let psbt = stack_stx_psbt(SENDER, pre_stx.clone(), 100_000_000_000, 6, SENDER, 10).unwrap();
+        let transaction = &psbt.unsigned_tx;
+        assert_eq!(transaction.input.len(), 1);
+        assert_eq!(
+            transaction.input[0].previous_output,
+            OutPoint::new(pre_stx.txid(), 1)
+        );
+        assert_eq!(
+            transaction.output,
+            stack_stx_outputs(100_000_000_000, 6, SENDER).unwrap()
+        );
+        assert_eq!(psbt.inputs[0].non_witness_utxo, Some(pre_stx.clone()));
+
+        let stacker = "mr1iPkD9N3RJZZxXRk7xF9d36gffa6exNC";
+        assert!(stack_stx_psbt(stacker, pre_stx, 100_000_000_000, 6, SENDER, 10).is_err());
+    }
+
+    #[test]
+    fn test_op_return_output() {
+        assert!(op_return_output(&[0u8; 80]).is_ok());
+        assert!(op_return_output(&[0u8; 81]).is_err());
+    }
+
+    #[test]
+    fn test_build_transaction() {
+        let utxo = |vout: u32, sats: u64| {
+            let txout = TxOut {
+                value: Amount::from_sat(sats),
+                script_pubkey: parse_address(SENDER).unwrap().script_pubkey(),
+            };
+            (OutPoint::new(Txid::all_zeros(), vout), txout)
+        };
+        let utxos = vec![utxo(0, 5_000), utxo(1, 50_000), utxo(2, 20_000)];
+        let outputs = vec![burn_output(40_000).unwrap()];
+
+        let transaction = build_transaction(SENDER, utxos.clone(), outputs.clone(), 10).unwrap();
+        assert_eq!(transaction.input.len(), 1);
+        assert_eq!(transaction.input[0].previous_output.vout, 1);
+        assert_eq!(transaction.output.len(), 2);
+        assert_eq!(
+            transaction.output[1].value.to_sat(),
+            50_000 - 40_000 - 6_000
+        );
+
+        assert!(build_transaction(SENDER, utxos, outputs, 100).is_err());
+    }
+
+    #[test]
+    fn test_sign_psbt() {
+        let (stx_address, btc_address, _) = clarinet_files::compute_addresses(
+            clarinet_files::DEFAULT_SUBNET_MNEMONIC,
+            clarinet_files::DEFAULT_DERIVATION_PATH,
+            &clarinet_files::StacksNetwork::Devnet.get_networks(),
+        );
+        let signer = AccountConfig {
+            label: "deployer".to_string(),
+            mnemonic: clarinet_files::DEFAULT_SUBNET_MNEMONIC.to_string(),
+            derivation: clarinet_files::DEFAULT_DERIVATION_PATH.to_string(),
+            secret_key: None,
+            balance: 0,
+            stx_address,
+            btc_address: btc_address.clone(),
+            is_mainnet: false,
+        };
+        let previous_transaction = Transaction {
+            version: Version::ONE,
+            lock_time: LockTime::ZERO,
+            input: vec![],
+            output: vec![payment_output(&btc_address, 50_000).unwrap()],
+        };
+        let utxos = vec![(
+            OutPoint::new(previous_transaction.txid(), 0),
+            previous_transaction.output[0].clone(),
+        )];
+        let outputs = vec![op_return_output(b"id").unwrap()];
+        let transaction = build_transaction(&btc_address, utxos, outputs, 10).unwrap();
+        let mut psbt = Psbt::from_unsigned_tx(transaction).unwrap();
+        assert!(sign_psbt(&mut psbt, &signer).is_err());
+
+        psbt.inputs[0].non_witness_utxo = Some(previous_transaction);
+        let mut psbt = psbt_from_base64(&psbt_to_base64(&psbt)).unwrap();
+        assert_eq!(sign_psbt(&mut psbt, &signer).unwrap(), 1);
+        let transaction = psbt.extract_tx().unwrap();
+        assert!(!transaction.input[0].script_sig.is_empty());
+    }
+}
@@ -0,0 +1,251 @@
+use clarity_repl::clarity::codec::StacksMessageCodec;
+use clarity_repl::clarity::util::hash::bytes_to_hex;
+use stacks_codec::codec::StacksTransaction;
+use stacks_rpc_client::rpc_client::{Contract, NodeInfo, RpcError};
+use stacks_rpc_client::StacksRpc;
+
+/// Transaction signed during a dry run, instead of being broadcasted
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedTransaction {
+    pub txid: String,
+    /// Node the transaction would have been sent to
+    pub node: String,
+    pub tx_hex: String,
+}
+
+/// Status of a node of the broadcaster, as reported by `/v2/info`
+#[derive(Debug)]
+pub struct NodeHealth {
+    pub url: String,
+    pub status: Result<NodeInfo, String>,
+}
+
+/// Stacks nodes of a network, requests are sent to the active node and fail over to
+/// the next healthy one when it stops answering. In dry-run mode, transactions are
+/// recorded instead of being posted.
+pub struct Broadcaster {
+    endpoints: Vec<StacksRpc>,
+    active: usize,
+    recorded: Option<Vec<RecordedTransaction>>,
+}
+
+impl Broadcaster {
+    /// `urls` are tried in order, duplicates are ignored
+    pub fn new(urls: &[String]) -> Self {
+        let mut endpoints: Vec<StacksRpc> = vec![];
+        for url in urls.iter() {
+            let url = url.trim_end_matches('/');
+            if !endpoints.iter().any(|endpoint| endpoint.url == url) {
+                endpoints.push(StacksRpc::new(url));
+            }
+        }
+        assert!(!endpoints.is_empty(), "no stacks node to broadcast to");
+        Broadcaster {
+            endpoints,
+            active: 0,
+            recorded: None,
+        }
+    }
+
+    /// Record the transactions instead of posting them, read requests still reach the nodes
+    pub fn start_recording(&mut self) {
+        self.recorded.get_or_insert_with(Vec::new);
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.recorded.is_some()
+    }
+
+    pub fn recorded_transactions(&self) -> &[RecordedTransaction] {
+        self.recorded.as_deref().unwrap_or_default()
+    }
+
+    pub fn rpc(&self) -> &StacksRpc {
+        &self.endpoints[self.active]
+    }
+
+    pub fn url(&self) -> &str {
+        &self.rpc().url
+    }
+
+    /// Query `/v2/info` on every node
+    pub fn health_check(&self) -> Vec<NodeHealth> {
+        self.endpoints
+            .iter()
+            .map(|endpoint| NodeHealth {
+                url: endpoint.url.clone(),
+                status: endpoint.get_info().map_err(|e| e.to_string()),
+            })
+            .collect()
+    }
+
+    /// Make the first healthy node, starting from the active one, the active node
+    pub fn select_healthy_node(&mut self) -> Result<NodeInfo, String> {
+        let mut errors = vec![];
+        for offset in 0..self.endpoints.len() {
+            let index = (self.active + offset) % self.endpoints.len();
+            match self.endpoints[index].get_info() {
+                Ok(info) => {
+                    self.active = index;
+                    return Ok(info);
+                }
+                Err(e) => errors.push(format!("{} ({})", self.endpoints[index].url, e)),
+            }
+        }
+        Err(format!(
+            "none of the stacks nodes is answering: {}",
+            errors.join(", ")
+        ))
+    }
+
+    /// Switch to the next node answering `/v2/info`, returns false if none does
+    pub fn failover(&mut self) -> bool {
+        for offset in 1..self.endpoints.len() {
+            let index = (self.active + offset) % self.endpoints.len();
+            if self.endpoints[index].get_info().is_ok() {
+                self.active = index;
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Send `request` to the active node, and again to the next healthy node if the active
+    /// one is down. An error returned by a healthy node is not retried on the other nodes.
+    fn with_failover<T>(
+        &mut self,
+        request: impl Fn(&StacksRpc) -> Result<T, RpcError>,
+    ) -> Result<T, String> {
+        let e = match request(self.rpc()) {
+            Ok(res) => return Ok(res),
+            Err(e) => e,
+        };
+        if self.rpc().get_info().is_ok() || !self.failover() {
+            return Err(e.to_string());
+        }
+        request(self.rpc()).map_err(|e| e.to_string())
+    }
+
+    pub fn get_info(&mut self) -> Result<NodeInfo, String> {
+        match self.rpc().get_info() {
+            Ok(info) => Ok(info),
+            Err(e) => match self.failover() {
+                true => self.rpc().get_info().map_err(|e| e.to_string()),
+                false => Err(e.to_string()),
+            },
+        }
+    }
+
+    pub fn get_nonce(&mut self, address: &str) -> Result<u64, String> {
+        self.with_failover(|rpc| rpc.get_nonce(address))
+    }
+
+    pub fn get_contract_source(
+        &mut self,
+        principal: &str,
+        contract_name: &str,
+    ) -> Result<Contract, String> {
+        self.with_failover(|rpc| rpc.get_contract_source(principal, contract_name))
+    }
+
+    /// Record a transaction sent to another node during a dry run, e.g. a bitcoin transfer
+    pub fn record(&mut self, transaction: RecordedTransaction) {
+        if let Some(ref mut recorded) = self.recorded {
+            recorded.push(transaction);
+        }
+    }
+
+    /// Post `transaction`, returns its txid. A transaction rejected by a healthy node
+    /// is not retried on the other nodes.
+    pub fn post_transaction(&mut self, transaction: &StacksTransaction) -> Result<String, String> {
+        if let Some(ref mut recorded) = self.recorded {
+            let txid = transaction.txid().to_string();
+            recorded.push(RecordedTransaction {
+                txid: txid.clone(),
+                node: self.endpoints[self.active].url.clone(),
+                tx_hex: bytes_to_hex(&transaction.serialize_to_vec()),
+            });
+            return Ok(txid);
+        }
+        self.with_failover(|rpc| rpc.post_transaction(transaction).map(|res| res.txid))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::onchain::encode_stx_transfer;
+    use clarinet_files::{AccountConfig, StacksNetwork};
+    use clarity_repl::clarity::vm::types::PrincipalData;
+    use stacks_codec::codec::TransactionAnchorMode;
+
+    #[test]
+    fn test_broadcaster_endpoints() {
+        let mut broadcaster = Broadcaster::new(&[
+            "http://127.0.0.1:1/".to_string(),
+            "http://127.0.0.1:2".to_string(),
+            "http://127.0.0.1:1".to_string(),
+        ]);
+        assert_eq!(broadcaster.endpoints.len(), 2);
+        assert_eq!(broadcaster.url(), "http://127.0.0.1:1");
+
+        // none of the nodes is reachable
+        assert!(!broadcaster.failover());
+        assert!(broadcaster.get_info().is_err());
+        assert!(broadcaster
+            .get_nonce("ST000000000000000000002AMW42H")
+            .is_err());
+        assert_eq!(broadcaster.url(), "http://127.0.0.1:1");
+
+        let health = broadcaster.health_check();
+        assert_eq!(health.len(), 2);
+        assert!(health.iter().all(|node| node.status.is_err()));
+        let error = broadcaster.select_healthy_node().unwrap_err();
+        assert!(error.contains("http://127.0.0.1:1") && error.contains("http://127.0.0.1:2"));
+    }
+
+    #[test]
+    fn test_dry_run_recorder() {
+        let (stx_address, btc_address, _) = clarinet_files::compute_addresses(
+            clarinet_files::DEFAULT_SUBNET_MNEMONIC,
+            clarinet_files::DEFAULT_DERIVATION_PATH,
+            &StacksNetwork::Testnet.get_networks(),
+        );
+        let account = AccountConfig {
+            label: "deployer".to_string(),
+            mnemonic: clarinet_files::DEFAULT_SUBNET_MNEMONIC.to_string(),
+            derivation: clarinet_files::DEFAULT_DERIVATION_PATH.to_string(),
+            secret_key: None,
+            balance: 0,
+            stx_address: stx_address.clone(),
+            btc_address,
+            is_mainnet: false,
+        };
+        let transaction = encode_stx_transfer(
+            PrincipalData::parse(&stx_address).unwrap(),
+            1000,
+            [0; 34],
+            &account,
+            0,
+            200,
+            TransactionAnchorMode::Any,
+            &StacksNetwork::Testnet,
+        )
+        .unwrap();
+
+        // the node is not reachable, but nothing is sent to it
+        let mut broadcaster = Broadcaster::new(&["http://127.0.0.1:1".to_string()]);
+        assert!(!broadcaster.is_dry_run());
+        broadcaster.start_recording();
+        let txid = broadcaster.post_transaction(&transaction).unwrap();
+        assert_eq!(txid, transaction.txid().to_string());
+
+        let recorded = broadcaster.recorded_transactions();
+        assert_eq!(recorded.len(), 1);
+        assert_eq!(recorded[0].node, "http://127.0.0.1:1");
+        assert_eq!(
+            recorded[0].tx_hex,
+            bytes_to_hex(&transaction.serialize_to_vec())
+        );
+    }
+}
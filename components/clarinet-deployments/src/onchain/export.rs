@@ -0,0 +1,775 @@
+use clarinet_files::{AccountConfig, NetworkManifest, StacksNetwork};
+use clarity_repl::clarity::chainstate::StacksAddress;
+use clarity_repl::clarity::codec::StacksMessageCodec;
+use clarity_repl::clarity::util::hash::bytes_to_hex;
+use clarity_repl::clarity::vm::types::{QualifiedContractIdentifier, Value};
+use clarity_repl::clarity::{ClarityName, ClarityVersion, ContractName, EvaluationResult};
+use clarity_repl::repl::post_conditions::{PostConditionMode, PostConditions};
+use clarity_repl::repl::{Session, SessionSettings};
+use stacks_codec::codec::{
+    StacksString, TokenTransferMemo, TransactionAnchorMode, TransactionContractCall,
+    TransactionPayload, TransactionSmartContract,
+};
+use stacks_rpc_client::StacksRpc;
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+
+use super::nonces::NonceTracker;
+use super::{build_unsigned_transaction, encode_post_conditions, get_keypair};
+use crate::build_post_conditions;
+use crate::types::{DeploymentSpecification, EpochSpec, TransactionSpecification};
+
+/// Unsigned transaction, described with the field names used by the
+/// `makeUnsigned*` builders of @stacks/transactions, along with its raw
+/// serialization, so that an external signer can take over.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnsignedTransactionExport {
+    pub name: String,
+    pub tx_type: String,
+    pub network: String,
+    pub sender_address: String,
+    pub public_key: String,
+    pub nonce: u64,
+    pub fee: u64,
+    pub anchor_mode: String,
+    pub post_condition_mode: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contract_address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contract_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function_args: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code_body: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clarity_version: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recipient: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+    pub tx_hex: String,
+}
+
+fn anchor_mode_to_string(anchor_block_only: bool) -> (TransactionAnchorMode, String) {
+    match anchor_block_only {
+        true => (TransactionAnchorMode::OnChainOnly, "onChainOnly".into()),
+        false => (TransactionAnchorMode::Any, "any".into()),
+    }
+}
+
+fn clarity_version_to_u8(clarity_version: &ClarityVersion) -> u8 {
+    match clarity_version {
+        ClarityVersion::Clarity1 => 1,
+        ClarityVersion::Clarity2 => 2,
+        ClarityVersion::Clarity3 => 3,
+    }
+}
+
+fn network_name(network: &StacksNetwork) -> String {
+    format!("{:?}", network).to_lowercase()
+}
+
+/// Unsigned call of `contract_id::function_name` sent by `account`
+#[allow(clippy::too_many_arguments)]
+pub fn export_contract_call(
+    contract_id: &QualifiedContractIdentifier,
+    function_name: &ClarityName,
+    function_args: Vec<Value>,
+    post_conditions: &PostConditions,
+    account: &AccountConfig,
+    nonce: u64,
+    fee: u64,
+    anchor_block_only: bool,
+    network: &StacksNetwork,
+) -> Result<UnsignedTransactionExport, String> {
+    let (_, public_key) = get_keypair(account);
+    let mut encoded_args = vec![];
+    for value in function_args.iter() {
+        let hex = value
+            .serialize_to_hex()
+            .map_err(|e| format!("unable to serialize argument: {:?}", e))?;
+        encoded_args.push(format!("0x{}", hex));
+    }
+
+    let (anchor_mode, anchor_mode_name) = anchor_mode_to_string(anchor_block_only);
+    let payload = TransactionPayload::ContractCall(TransactionContractCall {
+        contract_name: contract_id.name.clone(),
+        address: StacksAddress::from(contract_id.issuer.clone()),
+        function_name: function_name.clone(),
+        function_args,
+    });
+    let mut unsigned_tx =
+        build_unsigned_transaction(&public_key, payload, nonce, fee, anchor_mode, network);
+    (unsigned_tx.post_condition_mode, unsigned_tx.post_conditions) =
+        encode_post_conditions(post_conditions)?;
+
+    Ok(UnsignedTransactionExport {
+        name: format!("Call {}::{}", contract_id, function_name),
+        tx_type: "contract_call".into(),
+        network: network_name(network),
+        sender_address: account.stx_address.clone(),
+        public_key: bytes_to_hex(&public_key.serialize_compressed()),
+        nonce,
+        fee,
+        anchor_mode: anchor_mode_name,
+        post_condition_mode: match post_conditions.mode {
+            PostConditionMode::Allow => "allow".into(),
+            PostConditionMode::Deny => "deny".into(),
+        },
+        contract_address: Some(contract_id.issuer.to_address()),
+        contract_name: Some(contract_id.name.to_string()),
+        function_name: Some(function_name.to_string()),
+        function_args: Some(encoded_args),
+        code_body: None,
+        clarity_version: None,
+        recipient: None,
+        amount: None,
+        memo: None,
+        tx_hex: bytes_to_hex(&unsigned_tx.serialize_to_vec()),
+    })
+}
+
+/// Unsigned publish of the contract `contract_name` sent by `account`. The Clarity version
+/// is only encoded from epoch 2.1.
+#[allow(clippy::too_many_arguments)]
+pub fn export_contract_publish(
+    contract_name: &ContractName,
+    source: &str,
+    clarity_version: Option<ClarityVersion>,
+    account: &AccountConfig,
+    nonce: u64,
+    fee: u64,
+    anchor_block_only: bool,
+    network: &StacksNetwork,
+) -> Result<UnsignedTransactionExport, String> {
+    let (_, public_key) = get_keypair(account);
+    let (anchor_mode, anchor_mode_name) = anchor_mode_to_string(anchor_block_only);
+    let payload = TransactionPayload::SmartContract(
+        TransactionSmartContract {
+            name: contract_name.clone(),
+            code_body: StacksString::from_str(source)
+                .map_err(|e| format!("unable to encode contract {}: {}", contract_name, e))?,
+        },
+        clarity_version,
+    );
+    let unsigned_tx =
+        build_unsigned_transaction(&public_key, payload, nonce, fee, anchor_mode, network);
+
+    Ok(UnsignedTransactionExport {
+        name: format!("Publish {}.{}", account.stx_address, contract_name),
+        tx_type: "smart_contract".into(),
+        network: network_name(network),
+        sender_address: account.stx_address.clone(),
+        public_key: bytes_to_hex(&public_key.serialize_compressed()),
+        nonce,
+        fee,
+        anchor_mode: anchor_mode_name,
+        post_condition_mode: "allow".into(),
+        contract_address: None,
+        contract_name: Some(contract_name.to_string()),
+        function_name: None,
+        function_args: None,
+        code_body: Some(source.to_string()),
+        clarity_version: clarity_version.as_ref().map(clarity_version_to_u8),
+        recipient: None,
+        amount: None,
+        memo: None,
+        tx_hex: bytes_to_hex(&unsigned_tx.serialize_to_vec()),
+    })
+}
+
+/// Encode the contract calls, contract publishes and STX transfers of a deployment
+/// plan as unsigned transactions. Nonces are tracked per sender across the plan,
+/// starting from the values reported by the stacks node when `fetch_initial_nonces`
+/// is set, and from 0 otherwise.
+pub fn export_unsigned_transactions(
+    network_manifest: &NetworkManifest,
+    deployment: &DeploymentSpecification,
+    fetch_initial_nonces: bool,
+) -> Result<Vec<UnsignedTransactionExport>, String> {
+    let network = &deployment.network;
+
+    let stacks_rpc = match (fetch_initial_nonces, &deployment.stacks_node) {
+        (true, Some(url)) => Some(StacksRpc::new(url)),
+        (true, None) => return Err("unable to get stacks node rcp address".to_string()),
+        (false, _) => None,
+    };
+
+    let mut accounts_lookup: BTreeMap<String, &AccountConfig> = BTreeMap::new();
+    for (_, account) in network_manifest.accounts.iter() {
+        accounts_lookup.insert(account.stx_address.clone(), account);
+    }
+    let get_account = |address: &str| {
+        accounts_lookup.get(address).copied().ok_or_else(|| {
+            format!(
+                "account {} not found in settings/{:?}.toml",
+                address, network
+            )
+        })
+    };
+
+    let default_epoch = match network_manifest.devnet {
+        Some(_) => EpochSpec::Epoch2_1,
+        None => EpochSpec::Epoch2_05,
+    };
+
+    let mut session = Session::new(SessionSettings::default());
+    let nonces = NonceTracker::new();
+    let mut exports = vec![];
+
+    for batch_spec in deployment.plan.batches.iter() {
+        let epoch = batch_spec.epoch.unwrap_or(default_epoch);
+        for transaction in batch_spec.transactions.iter() {
+            let export = match transaction {
+                TransactionSpecification::ContractCall(tx) => {
+                    let sender_address = tx.expected_sender.to_address();
+                    let account = get_account(&sender_address)?;
+                    let nonce = nonces.reserve(&sender_address, stacks_rpc.as_ref())?;
+
+                    let mut function_args = vec![];
+                    for value in tx.parameters.iter() {
+                        let execution = session.eval(value.to_string(), false).map_err(|_| {
+                            format!(
+                                "unable to process contract-call {}::{}: argument {} invalid",
+                                tx.contract_id, tx.method, value
+                            )
+                        })?;
+                        match execution.result {
+                            EvaluationResult::Snippet(result) => function_args.push(result.result),
+                            _ => unreachable!("Contract result from snippet"),
+                        };
+                    }
+                    let post_conditions = build_post_conditions(
+                        &mut session,
+                        tx.post_condition_mode,
+                        &tx.post_conditions,
+                    )
+                    .map_err(|e| {
+                        format!(
+                            "unable to process contract-call {}::{}: {}",
+                            tx.contract_id, tx.method, e
+                        )
+                    })?;
+                    export_contract_call(
+                        &tx.contract_id,
+                        &tx.method,
+                        function_args,
+                        &post_conditions,
+                        account,
+                        nonce,
+                        tx.cost,
+                        tx.anchor_block_only,
+                        network,
+                    )?
+                }
+                TransactionSpecification::ContractPublish(tx) => {
+                    let sender_address = tx.expected_sender.to_address();
+                    let account = get_account(&sender_address)?;
+                    let nonce = nonces.reserve(&sender_address, stacks_rpc.as_ref())?;
+                    let clarity_version = if epoch >= EpochSpec::Epoch2_1 {
+                        Some(tx.clarity_version)
+                    } else {
+                        None
+                    };
+                    export_contract_publish(
+                        &tx.contract_name,
+                        &tx.source,
+                        clarity_version,
+                        account,
+                        nonce,
+                        tx.cost,
+                        tx.anchor_block_only,
+                        network,
+                    )?
+                }
+                TransactionSpecification::StxTransfer(tx) => {
+                    let sender_address = tx.expected_sender.to_address();
+                    let account = get_account(&sender_address)?;
+                    let (_, public_key) = get_keypair(account);
+                    let nonce = nonces.reserve(&sender_address, stacks_rpc.as_ref())?;
+
+                    let (anchor_mode, anchor_mode_name) =
+                        anchor_mode_to_string(tx.anchor_block_only);
+                    let payload = TransactionPayload::TokenTransfer(
+                        tx.recipient.clone(),
+                        tx.mstx_amount,
+                        TokenTransferMemo(tx.memo),
+                    );
+                    let unsigned_tx = build_unsigned_transaction(
+                        &public_key,
+                        payload,
+                        nonce,
+                        tx.cost,
+                        anchor_mode,
+                        network,
+                    );
+                    let memo_len = tx.memo.iter().rposition(|b| *b != 0).map_or(0, |i| i + 1);
+
+                    UnsignedTransactionExport {
+                        name: format!("STX transfer to {}", tx.recipient),
+                        tx_type: "token_transfer".into(),
+                        network: network_name(network),
+                        sender_address,
+                        public_key: bytes_to_hex(&public_key.serialize_compressed()),
+                        nonce,
+                        fee: tx.cost,
+                        anchor_mode: anchor_mode_name,
+                        post_condition_mode: "allow".into(),
+                        contract_address: None,
+                        contract_name: None,
+                        function_name: None,
+                        function_args: None,
+                        code_body: None,
+                        clarity_version: None,
+                        recipient: Some(tx.recipient.to_string()),
+                        amount: Some(tx.mstx_amount),
+                        memo: Some(String::from_utf8_lossy(&tx.memo[..memo_len]).to_string()),
+                        tx_hex: bytes_to_hex(&unsigned_tx.serialize_to_vec()),
+                    }
+                }
+                TransactionSpecification::RequirementPublish(_)
+                | TransactionSpecification::BtcTransfer(_)
+                | TransactionSpecification::EmulatedContractPublish(_)
+                | TransactionSpecification::EmulatedContractCall(_) => continue,
+            };
+            exports.push(export);
+        }
+    }
+
+    Ok(exports)
+}
+
+/// Top-level expressions of `source`, separated by whitespace
+fn split_expressions(source: &str) -> Result<Vec<&str>, String> {
+    let mut expressions = vec![];
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut start = None;
+    for (i, c) in source.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            c if c.is_whitespace() && depth == 0 => {
+                if let Some(begin) = start.take() {
+                    expressions.push(&source[begin..i]);
+                }
+                continue;
+            }
+            '(' | '{' => depth += 1,
+            ')' | '}' => {
+                depth -= 1;
+                if depth < 0 {
+                    return Err(format!("unexpected '{}'", c));
+                }
+            }
+            '"' => in_string = true,
+            _ => {}
+        }
+        if start.is_none() {
+            start = Some(i);
+        }
+    }
+    if depth != 0 || in_string {
+        return Err("unterminated expression".to_string());
+    }
+    if let Some(begin) = start {
+        expressions.push(&source[begin..]);
+    }
+    Ok(expressions)
+}
+
+/// Contract id written as in a Clarity expression, `.name` being deployed by `tx_sender`
+fn parse_contract_id(
+    contract: &str,
+    tx_sender: &str,
+) -> Result<QualifiedContractIdentifier, String> {
+    let contract = contract.trim_start_matches('\'');
+    let contract_id = match contract.strip_prefix('.') {
+        Some(name) => format!("{}.{}", tx_sender, name),
+        None => contract.to_string(),
+    };
+    QualifiedContractIdentifier::parse(&contract_id)
+        .map_err(|e| format!("invalid contract {}: {}", contract, e))
+}
+
+/// Unsigned transaction for the console command `::export_tx <input>`, sent by the
+/// tx-sender of `session`. `input` is either a `(contract-call? <contract> <function> <args>)`
+/// expression, its arguments being evaluated in the session, or a contract of the session to
+/// publish. The nonce of the sender is taken from `nonces`, and incremented.
+pub fn export_console_transaction(
+    session: &mut Session,
+    input: &str,
+    accounts: &BTreeMap<String, AccountConfig>,
+    nonces: &mut HashMap<String, u64>,
+    network: &StacksNetwork,
+) -> Result<UnsignedTransactionExport, String> {
+    let tx_sender = session.get_tx_sender();
+    let account = accounts
+        .values()
+        .find(|account| account.stx_address == tx_sender)
+        .ok_or(format!(
+            "tx-sender {} is not an account of settings/{:?}.toml",
+            tx_sender, network
+        ))?;
+    let nonce = nonces.get(&tx_sender).copied().unwrap_or(0);
+
+    let input = input.trim();
+    let export = match input
+        .strip_prefix('(')
+        .and_then(|input| input.strip_suffix(')'))
+    {
+        Some(expression) => {
+            let parts = split_expressions(expression)?;
+            let [keyword, contract, function, args @ ..] = parts.as_slice() else {
+                return Err("expected (contract-call? <contract> <function> <args>)".to_string());
+            };
+            if *keyword != "contract-call?" {
+                return Err(format!(
+                    "unable to export {}, expected contract-call?",
+                    keyword
+                ));
+            }
+            let contract_id = parse_contract_id(contract, &tx_sender)?;
+            let function_name = ClarityName::try_from(function.to_string())
+                .map_err(|e| format!("invalid function {}: {}", function, e))?;
+            let mut function_args = vec![];
+            for arg in args.iter() {
+                let execution = session
+                    .eval(arg.to_string(), false)
+                    .map_err(|diagnostics| {
+                        let messages: Vec<String> =
+                            diagnostics.into_iter().map(|d| d.message).collect();
+                        format!("invalid argument {}: {}", arg, messages.join("\n"))
+                    })?;
+                match execution.result {
+                    EvaluationResult::Snippet(result) => function_args.push(result.result),
+                    _ => unreachable!("Contract result from snippet"),
+                };
+            }
+            export_contract_call(
+                &contract_id,
+                &function_name,
+                function_args,
+                &PostConditions::default(),
+                account,
+                nonce,
+                0,
+                false,
+                network,
+            )?
+        }
+        None => {
+            let contract_id = parse_contract_id(&format!(".{}", input), &tx_sender)
+                .or_else(|_| parse_contract_id(input, &tx_sender))?;
+            let contract = session
+                .contracts
+                .get(&contract_id)
+                .ok_or(format!("contract {} not found", contract_id))?;
+            export_contract_publish(
+                &contract_id.name,
+                &contract.code,
+                Some(contract.analysis.clarity_version),
+                account,
+                nonce,
+                0,
+                false,
+                network,
+            )?
+        }
+    };
+    nonces.insert(tx_sender, nonce + 1);
+    Ok(export)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{
+        ContractCallSpecification, ContractPublishSpecification, EmulatedContractCallSpecification,
+        StxTransferSpecification, TransactionPlanSpecification, TransactionsBatchSpecification,
+    };
+    use clarinet_files::{compute_addresses, FileLocation, DEFAULT_DERIVATION_PATH};
+    use clarity_repl::clarity::vm::types::PrincipalData;
+    use clarity_repl::clarity::StacksEpochId;
+    use clarity_repl::repl::{ClarityCodeSource, ClarityContract, ContractDeployer};
+    use stacks_codec::codec::StacksTransaction;
+
+    const DEPLOYER_MNEMONIC: &str = "twice kind fence tip hidden tilt action fragile skin nothing glory cousin green tomorrow spring wrist shed math olympic multiply hip blue scout claw";
+
+    fn deployer_accounts() -> BTreeMap<String, AccountConfig> {
+        let (stx_address, btc_address, _) = compute_addresses(
+            DEPLOYER_MNEMONIC,
+            DEFAULT_DERIVATION_PATH,
+            &StacksNetwork::Devnet.get_networks(),
+        );
+        let account = AccountConfig {
+            label: "deployer".to_string(),
+            mnemonic: DEPLOYER_MNEMONIC.to_string(),
+            derivation: DEFAULT_DERIVATION_PATH.to_string(),
+            secret_key: None,
+            balance: 0,
+            stx_address,
+            btc_address,
+            is_mainnet: false,
+        };
+        BTreeMap::from([("deployer".to_string(), account)])
+    }
+
+    fn decode(export: &UnsignedTransactionExport) -> StacksTransaction {
+        let bytes = clarity_repl::clarity::util::hash::hex_bytes(&export.tx_hex).unwrap();
+        StacksTransaction::consensus_deserialize(&mut &bytes[..]).unwrap()
+    }
+
+    #[test]
+    fn test_export_unsigned_transactions() {
+        let accounts = deployer_accounts();
+        let network_manifest: NetworkManifest = serde_json::from_value(serde_json::json!({
+            "network": {
+                "name": "testnet",
+                "deployment_fee_rate": 10,
+                "sats_per_bytes": 10,
+            },
+            "accounts": accounts.values().collect::<Vec<_>>(),
+        }))
+        .unwrap();
+        let deployer =
+            PrincipalData::parse_standard_principal(&accounts["deployer"].stx_address).unwrap();
+        let wallet = PrincipalData::parse("ST1SJ3DTE5DN7X54YDH5D64R3BCB6A2AG2ZQ8YPD5").unwrap();
+        let counter = QualifiedContractIdentifier::new(deployer.clone(), "counter".into());
+        let source = "(define-public (add (n uint)) (ok n))".to_string();
+        let location = FileLocation::from_path_string("/project/contracts/counter.clar").unwrap();
+        let mut memo = [0; 34];
+        memo[..2].copy_from_slice(b"hi");
+
+        let publish = TransactionSpecification::ContractPublish(ContractPublishSpecification {
+            contract_name: "counter".into(),
+            expected_sender: deployer.clone(),
+            location,
+            source: source.clone(),
+            clarity_version: ClarityVersion::Clarity2,
+            cost: 2000,
+            anchor_block_only: true,
+        });
+        let call = TransactionSpecification::ContractCall(ContractCallSpecification {
+            contract_id: counter.clone(),
+            expected_sender: deployer.clone(),
+            method: "add".into(),
+            parameters: vec!["u1".to_string()],
+            cost: 1000,
+            anchor_block_only: false,
+            post_condition_mode: PostConditionMode::Allow,
+            post_conditions: vec![],
+        });
+        let emulated_call =
+            TransactionSpecification::EmulatedContractCall(EmulatedContractCallSpecification {
+                contract_id: counter,
+                emulated_sender: deployer.clone(),
+                method: "add".into(),
+                parameters: vec!["u2".to_string()],
+                post_condition_mode: PostConditionMode::Allow,
+                post_conditions: vec![],
+            });
+        let transfer = TransactionSpecification::StxTransfer(StxTransferSpecification {
+            expected_sender: deployer.clone(),
+            recipient: wallet.clone(),
+            mstx_amount: 1000,
+            memo,
+            cost: 300,
+            anchor_block_only: true,
+        });
+        let mut deployment = DeploymentSpecification {
+            id: 0,
+            name: "testnet".to_string(),
+            network: StacksNetwork::Testnet,
+            stacks_node: None,
+            bitcoin_node: None,
+            genesis: None,
+            plan: TransactionPlanSpecification {
+                batches: vec![
+                    TransactionsBatchSpecification {
+                        id: 0,
+                        transactions: vec![publish.clone()],
+                        epoch: Some(EpochSpec::Epoch2_05),
+                    },
+                    TransactionsBatchSpecification {
+                        id: 1,
+                        transactions: vec![call, emulated_call, publish, transfer],
+                        epoch: Some(EpochSpec::Epoch2_1),
+                    },
+                ],
+            },
+            contracts: BTreeMap::new(),
+        };
+
+        let exports = export_unsigned_transactions(&network_manifest, &deployment, false).unwrap();
+        // the emulated call is skipped, the nonces of the sender follow the plan
+        assert_eq!(
+            exports
+                .iter()
+                .map(|export| (export.tx_type.as_str(), export.nonce, export.fee))
+                .collect::<Vec<_>>(),
+            vec![
+                ("smart_contract", 0, 2000),
+                ("contract_call", 1, 1000),
+                ("smart_contract", 2, 2000),
+                ("token_transfer", 3, 300),
+            ]
+        );
+        for export in exports.iter() {
+            assert_eq!(export.network, "testnet");
+            let transaction = decode(export);
+            assert_eq!(transaction.get_origin_nonce(), export.nonce);
+            assert_eq!(transaction.get_tx_fee(), export.fee);
+        }
+
+        // the clarity version is only encoded from epoch 2.1
+        assert_eq!(exports[0].clarity_version, None);
+        assert_eq!(exports[0].anchor_mode, "onChainOnly");
+        let TransactionPayload::SmartContract(contract, None) = decode(&exports[0]).payload else {
+            panic!("expected a contract publish without clarity version");
+        };
+        assert_eq!(contract.code_body.to_string(), source);
+        assert_eq!(exports[2].clarity_version, Some(2));
+        assert!(matches!(
+            decode(&exports[2]).payload,
+            TransactionPayload::SmartContract(_, Some(ClarityVersion::Clarity2))
+        ));
+
+        assert_eq!(exports[1].anchor_mode, "any");
+        let TransactionPayload::ContractCall(payload) = decode(&exports[1]).payload else {
+            panic!("expected a contract call");
+        };
+        assert_eq!(payload.function_name.as_str(), "add");
+        assert_eq!(payload.function_args, vec![Value::UInt(1)]);
+
+        assert_eq!(exports[3].memo, Some("hi".to_string()));
+        let TransactionPayload::TokenTransfer(recipient, amount, transfer_memo) =
+            decode(&exports[3]).payload
+        else {
+            panic!("expected a token transfer");
+        };
+        assert_eq!((recipient, amount, transfer_memo.0), (wallet, 1000, memo));
+
+        // the senders must be accounts of the network manifest
+        let TransactionSpecification::StxTransfer(ref mut transfer) =
+            deployment.plan.batches[1].transactions[3]
+        else {
+            unreachable!()
+        };
+        transfer.expected_sender =
+            PrincipalData::parse_standard_principal("ST2CY5V39NHDPWSXMW9QDT3HC3GD6Q6XX4CFRK9AG")
+                .unwrap();
+        assert!(export_unsigned_transactions(&network_manifest, &deployment, false).is_err());
+        // the initial nonces can't be fetched without a stacks node
+        assert!(export_unsigned_transactions(&network_manifest, &deployment, true).is_err());
+    }
+
+    #[test]
+    fn test_split_expressions() {
+        assert_eq!(
+            split_expressions("contract-call? .counter add (list u1 u2) { a: \"x y)\" } 'ST1")
+                .unwrap(),
+            vec![
+                "contract-call?",
+                ".counter",
+                "add",
+                "(list u1 u2)",
+                "{ a: \"x y)\" }",
+                "'ST1"
+            ]
+        );
+        assert!(split_expressions("add (list u1").is_err());
+        assert!(split_expressions("add u1)").is_err());
+    }
+
+    #[test]
+    fn test_export_console_transaction() {
+        let accounts = deployer_accounts();
+        let deployer = accounts["deployer"].stx_address.clone();
+        let mut session = Session::new(SessionSettings::default());
+        session.update_epoch(StacksEpochId::Epoch25);
+        session.set_tx_sender(&deployer);
+        let contract = ClarityContract {
+            code_source: ClarityCodeSource::ContractInMemory(
+                "(define-public (add (values (list 10 uint))) (ok (fold + values u0)))".to_string(),
+            ),
+            name: "counter".to_string(),
+            deployer: ContractDeployer::Address(deployer.clone()),
+            clarity_version: ClarityVersion::Clarity2,
+            epoch: StacksEpochId::Epoch25,
+        };
+        session.deploy_contract(&contract, false, None).unwrap();
+        let mut nonces = HashMap::new();
+        let network = StacksNetwork::Devnet;
+
+        let publish =
+            export_console_transaction(&mut session, "counter", &accounts, &mut nonces, &network)
+                .unwrap();
+        assert_eq!(publish.tx_type, "smart_contract");
+        assert_eq!(publish.sender_address, deployer);
+        assert_eq!(publish.clarity_version, Some(2));
+        assert_eq!(publish.nonce, 0);
+        assert!(matches!(
+            decode(&publish).payload,
+            TransactionPayload::SmartContract(_, Some(ClarityVersion::Clarity2))
+        ));
+
+        let call = export_console_transaction(
+            &mut session,
+            "(contract-call? .counter add (list u1 u2))",
+            &accounts,
+            &mut nonces,
+            &network,
+        )
+        .unwrap();
+        assert_eq!(call.tx_type, "contract_call");
+        assert_eq!(call.nonce, 1);
+        assert_eq!(call.contract_address, Some(deployer.clone()));
+        assert_eq!(call.function_name, Some("add".to_string()));
+        let TransactionPayload::ContractCall(payload) = decode(&call).payload else {
+            panic!("expected a contract call");
+        };
+        assert_eq!(
+            payload.function_args,
+            vec![Value::cons_list_unsanitized(vec![Value::UInt(1), Value::UInt(2)]).unwrap()]
+        );
+        assert_eq!(
+            call.function_args,
+            Some(vec![format!(
+                "0x{}",
+                payload.function_args[0].serialize_to_hex().unwrap()
+            )])
+        );
+
+        assert!(export_console_transaction(
+            &mut session,
+            "(stx-transfer? u1 tx-sender 'ST1)",
+            &accounts,
+            &mut nonces,
+            &network,
+        )
+        .is_err());
+        session.set_tx_sender("ST2CY5V39NHDPWSXMW9QDT3HC3GD6Q6XX4CFRK9AG");
+        assert!(export_console_transaction(
+            &mut session,
+            "counter",
+            &accounts,
+            &mut nonces,
+            &network
+        )
+        .is_err());
+    }
+}
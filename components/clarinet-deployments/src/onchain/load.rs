@@ -0,0 +1,465 @@
+use clarinet_files::{AccountConfig, NetworkManifest, StacksNetwork};
+use clarity_repl::clarity::vm::types::{PrincipalData, QualifiedContractIdentifier};
+use clarity_repl::clarity::vm::{ClarityName, ContractName, Value};
+use clarity_repl::clarity::EvaluationResult;
+use clarity_repl::repl::post_conditions::PostConditions;
+use clarity_repl::repl::{Session, SessionSettings};
+use serde_json::Value as JsonValue;
+use stacks_codec::codec::TransactionAnchorMode;
+use stacks_rpc_client::StacksRpc;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use super::encode_contract_call;
+use super::nonces::NonceTracker;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+fn default_fee() -> u64 {
+    1000
+}
+
+fn default_inclusion_timeout() -> u64 {
+    120
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+/// Load scenario, as described in a yaml file:
+///
+/// ```yaml
+/// duration: 60
+/// rate: 5
+/// senders: [wallet_1, wallet_2]
+/// calls:
+///   - contract: counter
+///     method: increment
+///     weight: 3
+///   - contract: counter
+///     method: add
+///     args: [u10]
+/// ```
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub struct LoadScenario {
+    /// Length of the run, in seconds
+    pub duration: u64,
+    /// Transactions submitted per second, across all senders
+    pub rate: f64,
+    /// Fee of each transaction, in µSTX
+    #[serde(default = "default_fee")]
+    pub fee: u64,
+    /// Time given to the pending transactions to be included once the run is
+    /// over, in seconds
+    #[serde(default = "default_inclusion_timeout")]
+    pub inclusion_timeout: u64,
+    /// Names of the devnet accounts signing the transactions
+    pub senders: Vec<String>,
+    pub calls: Vec<ScenarioCall>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub struct ScenarioCall {
+    /// Contract name, deployed by the deployer account, or contract identifier
+    pub contract: String,
+    pub method: String,
+    /// Clarity expressions, such as `u1` or `"hello"`
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Share of this call in the transactions mix, relative to the other calls
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+}
+
+impl LoadScenario {
+    pub fn from_yaml(content: &[u8]) -> Result<LoadScenario, String> {
+        let scenario: LoadScenario = serde_yaml::from_slice(content)
+            .map_err(|e| format!("unable to parse scenario: {}", e))?;
+        // the interval between two submissions, 1 / rate, must be a valid duration
+        let rate = scenario.rate;
+        if !(rate > 0.0 && rate.is_finite() && (1.0 / rate).is_finite()) {
+            return Err(format!(
+                "scenario rate must be a number greater than 0, found {}",
+                scenario.rate
+            ));
+        }
+        if scenario.senders.is_empty() {
+            return Err("scenario must have at least one sender".to_string());
+        }
+        if scenario.calls.iter().all(|call| call.weight == 0) {
+            return Err("scenario must have at least one call with a weight".to_string());
+        }
+        Ok(scenario)
+    }
+}
+
+/// Smooth weighted round-robin: calls are interleaved according to their weights,
+/// so that the mix is respected over any window of the run.
+pub struct CallSchedule {
+    weights: Vec<i64>,
+    current: Vec<i64>,
+    total: i64,
+}
+
+impl CallSchedule {
+    pub fn new(calls: &[ScenarioCall]) -> CallSchedule {
+        let weights: Vec<i64> = calls.iter().map(|call| call.weight as i64).collect();
+        CallSchedule {
+            total: weights.iter().sum(),
+            current: vec![0; weights.len()],
+            weights,
+        }
+    }
+}
+
+impl Iterator for CallSchedule {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let mut selected = None;
+        for (index, weight) in self.weights.iter().enumerate() {
+            self.current[index] += weight;
+            match selected {
+                Some(selected) if self.current[selected] >= self.current[index] => {}
+                _ if *weight > 0 => selected = Some(index),
+                _ => {}
+            }
+        }
+        let selected = selected?;
+        self.current[selected] -= self.total;
+        Some(selected)
+    }
+}
+
+struct LoadCall {
+    contract_id: QualifiedContractIdentifier,
+    method: ClarityName,
+    args: Vec<Value>,
+}
+
+struct Submission {
+    sender: usize,
+    nonce: u64,
+    submitted_at: Instant,
+    inclusion_latency: Option<Duration>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LoadReport {
+    pub submitted: usize,
+    pub included: usize,
+    /// Transactions rejected by the node, by reason
+    pub rejected: BTreeMap<String, usize>,
+    /// Sorted inclusion latencies of the included transactions
+    pub latencies: Vec<Duration>,
+    pub elapsed: Duration,
+    pub blocks: u64,
+}
+
+impl LoadReport {
+    pub fn pending(&self) -> usize {
+        self.submitted - self.included
+    }
+
+    pub fn latency_percentile(&self, percentile: usize) -> Option<Duration> {
+        if self.latencies.is_empty() {
+            return None;
+        }
+        let index = (self.latencies.len() * percentile).div_ceil(100);
+        Some(self.latencies[index.saturating_sub(1)])
+    }
+}
+
+fn format_latency(latency: Option<Duration>) -> String {
+    match latency {
+        Some(latency) => format!("{:.1}s", latency.as_secs_f64()),
+        None => "-".to_string(),
+    }
+}
+
+impl fmt::Display for LoadReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let elapsed = self.elapsed.as_secs_f64().max(1.0);
+        writeln!(
+            f,
+            "submitted: {} ({:.2} tx/s)",
+            self.submitted,
+            self.submitted as f64 / elapsed
+        )?;
+        writeln!(
+            f,
+            "included: {} ({:.2} tx/s, {} blocks)",
+            self.included,
+            self.included as f64 / elapsed,
+            self.blocks
+        )?;
+        writeln!(f, "pending: {}", self.pending())?;
+        writeln!(
+            f,
+            "inclusion latency: p50 {}, p90 {}, max {}",
+            format_latency(self.latency_percentile(50)),
+            format_latency(self.latency_percentile(90)),
+            format_latency(self.latencies.last().cloned()),
+        )?;
+        let rejected: usize = self.rejected.values().sum();
+        write!(f, "rejected: {}", rejected)?;
+        for (reason, count) in self.rejected.iter() {
+            write!(f, "\n  {}: {}", reason, count)?;
+        }
+        Ok(())
+    }
+}
+
+/// Mempool rejections come as a json payload, only its reason is kept
+fn rejection_reason(message: &str) -> String {
+    serde_json::from_str::<JsonValue>(message)
+        .ok()
+        .and_then(|payload| payload["reason"].as_str().map(|r| r.to_string()))
+        .unwrap_or(message.to_string())
+}
+
+fn resolve_calls(
+    scenario: &LoadScenario,
+    network_manifest: &NetworkManifest,
+) -> Result<Vec<LoadCall>, String> {
+    let deployer = network_manifest
+        .accounts
+        .get("deployer")
+        .ok_or("unable to retrieve default deployer account".to_string())?;
+    let deployer_address = PrincipalData::parse_standard_principal(&deployer.stx_address)
+        .map_err(|_| format!("unable to parse address {}", deployer.stx_address))?;
+
+    let mut session = Session::new(SessionSettings::default());
+    let mut calls = vec![];
+    for call in scenario.calls.iter() {
+        let contract_id = match call.contract.contains('.') {
+            true => QualifiedContractIdentifier::parse(&call.contract)
+                .map_err(|_| format!("unable to parse contract identifier {}", call.contract))?,
+            false => QualifiedContractIdentifier::new(
+                deployer_address.clone(),
+                ContractName::try_from(call.contract.clone())
+                    .map_err(|_| format!("unable to parse contract name {}", call.contract))?,
+            ),
+        };
+        let method = ClarityName::try_from(call.method.clone())
+            .map_err(|_| format!("unable to parse method {}", call.method))?;
+
+        let mut args = vec![];
+        for arg in call.args.iter() {
+            let execution = session.eval(arg.to_string(), false).map_err(|_| {
+                format!(
+                    "unable to process contract-call {}::{}: argument {} invalid",
+                    contract_id, method, arg
+                )
+            })?;
+            match execution.result {
+                EvaluationResult::Snippet(result) => args.push(result.result),
+                _ => unreachable!("Contract result from snippet"),
+            };
+        }
+        calls.push(LoadCall {
+            contract_id,
+            method,
+            args,
+        });
+    }
+    Ok(calls)
+}
+
+fn poll_inclusions(
+    stacks_rpc: &StacksRpc,
+    nonces: &NonceTracker,
+    senders: &[&AccountConfig],
+    submissions: &mut [Submission],
+) -> Result<(), String> {
+    for (index, sender) in senders.iter().enumerate() {
+        let address = &sender.stx_address;
+        nonces.refresh(address, stacks_rpc)?;
+        for submission in submissions.iter_mut() {
+            if submission.sender == index
+                && nonces.is_included(address, submission.nonce)
+                && submission.inclusion_latency.is_none()
+            {
+                submission.inclusion_latency = Some(submission.submitted_at.elapsed());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Submit the contract-calls of `scenario` to the node at `node_url` at the requested
+/// rate, then wait for their inclusion. A transaction is considered included once
+/// the nonce of its sender moves past it.
+pub fn run_load_scenario(
+    scenario: &LoadScenario,
+    network_manifest: &NetworkManifest,
+    node_url: &str,
+) -> Result<LoadReport, String> {
+    let stacks_rpc = StacksRpc::new(node_url);
+    let calls = resolve_calls(scenario, network_manifest)?;
+
+    let nonces = NonceTracker::new();
+    let mut senders = vec![];
+    for name in scenario.senders.iter() {
+        let account = network_manifest
+            .accounts
+            .get(name)
+            .ok_or(format!("unable to retrieve account {}", name))?;
+        nonces.refresh(&account.stx_address, &stacks_rpc)?;
+        senders.push(account);
+    }
+
+    let start_height = stacks_rpc
+        .get_info()
+        .map_err(|e| format!("unable to reach stacks node: {}", e))?
+        .stacks_tip_height;
+
+    let mut report = LoadReport::default();
+    let mut submissions = vec![];
+    let mut schedule = CallSchedule::new(&scenario.calls);
+    let interval = Duration::from_secs_f64(1.0 / scenario.rate);
+    let start = Instant::now();
+    let end = start + Duration::from_secs(scenario.duration);
+    let mut next_submission = start;
+    let mut last_poll = start;
+    let mut sender_index = 0;
+
+    while next_submission < end {
+        let now = Instant::now();
+        if now < next_submission {
+            sleep(next_submission - now);
+        }
+        next_submission += interval;
+
+        let call = &calls[schedule.next().unwrap()];
+        let sender = senders[sender_index];
+        let nonce = nonces.reserve(&sender.stx_address, Some(&stacks_rpc))?;
+        let transaction = encode_contract_call(
+            &call.contract_id,
+            call.method.clone(),
+            call.args.clone(),
+            &PostConditions::default(),
+            sender,
+            nonce,
+            scenario.fee,
+            TransactionAnchorMode::Any,
+            &StacksNetwork::Devnet,
+        )?;
+        match stacks_rpc.post_transaction(&transaction) {
+            Ok(_) => {
+                report.submitted += 1;
+                submissions.push(Submission {
+                    sender: sender_index,
+                    nonce,
+                    submitted_at: Instant::now(),
+                    inclusion_latency: None,
+                });
+            }
+            Err(e) => {
+                nonces.release(&sender.stx_address, nonce);
+                *report
+                    .rejected
+                    .entry(rejection_reason(&e.to_string()))
+                    .or_insert(0) += 1;
+            }
+        }
+        sender_index = (sender_index + 1) % senders.len();
+
+        if last_poll.elapsed() >= POLL_INTERVAL {
+            poll_inclusions(&stacks_rpc, &nonces, &senders, &mut submissions)?;
+            last_poll = Instant::now();
+        }
+    }
+
+    let deadline = Instant::now() + Duration::from_secs(scenario.inclusion_timeout);
+    loop {
+        poll_inclusions(&stacks_rpc, &nonces, &senders, &mut submissions)?;
+        let pending = submissions
+            .iter()
+            .any(|submission| submission.inclusion_latency.is_none());
+        if !pending || Instant::now() >= deadline {
+            break;
+        }
+        sleep(POLL_INTERVAL);
+    }
+
+    let end_height = stacks_rpc
+        .get_info()
+        .map_err(|e| format!("unable to reach stacks node: {}", e))?
+        .stacks_tip_height;
+
+    report.latencies = submissions
+        .iter()
+        .filter_map(|submission| submission.inclusion_latency)
+        .collect();
+    report.latencies.sort();
+    report.included = report.latencies.len();
+    report.elapsed = start.elapsed();
+    report.blocks = end_height.saturating_sub(start_height);
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scenario_call_schedule() {
+        let scenario = LoadScenario::from_yaml(
+            br#"
+duration: 10
+rate: 2.5
+senders: [wallet_1]
+calls:
+  - contract: counter
+    method: increment
+    weight: 3
+  - contract: counter
+    method: add
+    args: [u10]
+"#,
+        )
+        .unwrap();
+        assert_eq!(scenario.fee, 1000);
+        assert_eq!(scenario.calls[1].weight, 1);
+        assert_eq!(scenario.calls[1].args, vec!["u10".to_string()]);
+
+        let schedule: Vec<usize> = CallSchedule::new(&scenario.calls).take(8).collect();
+        assert_eq!(schedule, vec![0, 0, 1, 0, 0, 0, 1, 0]);
+
+        assert!(LoadScenario::from_yaml(b"duration: 10\nrate: 0\nsenders: []\ncalls: []").is_err());
+        for rate in ["0", "-1", ".nan", ".inf", "1e-320"] {
+            let content = format!(
+                "duration: 10\nrate: {}\nsenders: [wallet_1]\ncalls: [{{ contract: counter, method: increment }}]",
+                rate
+            );
+            let err = LoadScenario::from_yaml(content.as_bytes()).unwrap_err();
+            assert!(err.starts_with("scenario rate must be a number greater than 0"));
+        }
+    }
+
+    #[test]
+    fn test_report_latencies() {
+        let report = LoadReport {
+            submitted: 4,
+            included: 3,
+            latencies: vec![
+                Duration::from_secs(2),
+                Duration::from_secs(4),
+                Duration::from_secs(9),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(report.pending(), 1);
+        assert_eq!(report.latency_percentile(50), Some(Duration::from_secs(4)));
+        assert_eq!(report.latency_percentile(90), Some(Duration::from_secs(9)));
+        assert!(report
+            .to_string()
+            .contains("inclusion latency: p50 4.0s, p90 9.0s, max 9.0s"));
+    }
+}
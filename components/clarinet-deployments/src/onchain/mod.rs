@@ -1,17 +1,21 @@
 use bitcoincore_rpc::{Auth, Client};
 use clarinet_files::StacksNetwork;
-use clarinet_files::{AccountConfig, NetworkManifest};
-use clarinet_utils::get_bip39_seed_from_mnemonic;
+use clarinet_files::{AccountConfig, FileLocation, NetworkManifest};
 use clarity_repl::clarity::chainstate::StacksAddress;
 use clarity_repl::clarity::codec::StacksMessageCodec;
+use clarity_repl::clarity::util::hash::{bytes_to_hex, hex_bytes};
 use clarity_repl::clarity::util::secp256k1::{
     MessageSignature, Secp256k1PrivateKey, Secp256k1PublicKey,
 };
 use clarity_repl::clarity::vm::types::{
-    PrincipalData, QualifiedContractIdentifier, StandardPrincipalData,
+    AssetIdentifier, PrincipalData, QualifiedContractIdentifier, StandardPrincipalData,
 };
 use clarity_repl::clarity::vm::{ClarityName, Value};
 use clarity_repl::clarity::{ClarityVersion, ContractName, EvaluationResult};
+use clarity_repl::repl::post_conditions::{
+    FungibleConditionCode, NonFungibleConditionCode, PostCondition, PostConditionMode,
+    PostConditions,
+};
 use clarity_repl::repl::session::{
     BOOT_MAINNET_ADDRESS, BOOT_TESTNET_ADDRESS, V1_BOOT_CONTRACTS, V2_BOOT_CONTRACTS,
     V3_BOOT_CONTRACTS,
@@ -19,17 +23,18 @@ use clarity_repl::repl::session::{
 use clarity_repl::repl::{Session, SessionSettings};
 use reqwest::Url;
 use stacks_codec::codec::{
-    SinglesigHashMode, SinglesigSpendingCondition, StacksString, StacksTransactionSigner,
-    TokenTransferMemo, TransactionAuth, TransactionContractCall, TransactionPayload,
-    TransactionPostConditionMode, TransactionPublicKeyEncoding, TransactionSmartContract,
-    TransactionSpendingCondition, TransactionVersion,
+    AssetInfo, FungibleConditionCode as TransactionFungibleConditionCode, NonfungibleConditionCode,
+    PostConditionPrincipal, SinglesigHashMode, SinglesigSpendingCondition, StacksString,
+    StacksTransactionSigner, TokenTransferMemo, TransactionAuth, TransactionContractCall,
+    TransactionPayload, TransactionPostCondition, TransactionPostConditionMode,
+    TransactionPublicKeyEncoding, TransactionSmartContract, TransactionSpendingCondition,
+    TransactionVersion,
 };
 use stacks_codec::codec::{StacksTransaction, TransactionAnchorMode};
 use stacks_rpc_client::StacksRpc;
 use std::collections::{BTreeMap, HashSet, VecDeque};
 use std::str::FromStr;
 use std::sync::mpsc::{Receiver, Sender};
-use tiny_hderive::bip32::ExtendedPrivKey;
 
 use clarity_repl::clarity::address::{
     AddressHashMode, C32_ADDRESS_VERSION_MAINNET_SINGLESIG, C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
@@ -37,8 +42,25 @@ use clarity_repl::clarity::address::{
 use libsecp256k1::{PublicKey, SecretKey};
 
 mod bitcoin_deployment;
-
+pub mod bitcoin_devnet;
+pub mod broadcaster;
+pub mod export;
+pub mod load;
+pub mod nonces;
+pub mod progress;
+
+use crate::build_post_conditions;
 use crate::types::{DeploymentSpecification, EpochSpec, TransactionSpecification};
+use broadcaster::{Broadcaster, RecordedTransaction};
+use nonces::NonceTracker;
+use progress::{BroadcastedTransaction, DeploymentProgress};
+
+/// Bytes of the secret key of `account`, derived from its mnemonic or imported as is
+fn get_secret_key_bytes(account: &AccountConfig) -> Vec<u8> {
+    let mut bytes = hex_bytes(&account.secret_key_hex()).expect("invalid secret key");
+    bytes.truncate(32);
+    bytes
+}
 
 fn get_btc_keypair(
     account: &AccountConfig,
@@ -47,27 +69,18 @@ fn get_btc_keypair(
     bitcoincore_rpc::bitcoin::secp256k1::PublicKey,
 ) {
     use bitcoincore_rpc::bitcoin::secp256k1::{PublicKey, Secp256k1, SecretKey};
-    let bip39_seed = match get_bip39_seed_from_mnemonic(&account.mnemonic, "") {
-        Ok(bip39_seed) => bip39_seed,
-        Err(_) => panic!(),
-    };
     let secp = Secp256k1::new();
-    let ext = ExtendedPrivKey::derive(&bip39_seed[..], account.derivation.as_str()).unwrap();
-    let secret_key = SecretKey::from_slice(&ext.secret()).unwrap();
+    let secret_key = SecretKey::from_slice(&get_secret_key_bytes(account)).unwrap();
     let public_key = PublicKey::from_secret_key(&secp, &secret_key);
     (secret_key, public_key)
 }
 
-fn get_keypair(account: &AccountConfig) -> (ExtendedPrivKey, Secp256k1PrivateKey, PublicKey) {
-    let bip39_seed = match get_bip39_seed_from_mnemonic(&account.mnemonic, "") {
-        Ok(bip39_seed) => bip39_seed,
-        Err(_) => panic!(),
-    };
-    let ext = ExtendedPrivKey::derive(&bip39_seed[..], account.derivation.as_str()).unwrap();
-    let wrapped_secret_key = Secp256k1PrivateKey::from_slice(&ext.secret()).unwrap();
-    let secret_key = SecretKey::parse_slice(&ext.secret()).unwrap();
+fn get_keypair(account: &AccountConfig) -> (Secp256k1PrivateKey, PublicKey) {
+    let secret = get_secret_key_bytes(account);
+    let wrapped_secret_key = Secp256k1PrivateKey::from_slice(&secret).unwrap();
+    let secret_key = SecretKey::parse_slice(&secret).unwrap();
     let public_key = PublicKey::from_secret_key(&secret_key);
-    (ext, wrapped_secret_key, public_key)
+    (wrapped_secret_key, public_key)
 }
 
 fn get_stacks_address(public_key: &PublicKey, network: &StacksNetwork) -> StacksAddress {
@@ -86,16 +99,15 @@ fn get_stacks_address(public_key: &PublicKey, network: &StacksNetwork) -> Stacks
     .unwrap()
 }
 
-fn sign_transaction_payload(
-    account: &AccountConfig,
+fn build_unsigned_transaction(
+    public_key: &PublicKey,
     payload: TransactionPayload,
     nonce: u64,
     tx_fee: u64,
     anchor_mode: TransactionAnchorMode,
     network: &StacksNetwork,
-) -> Result<StacksTransaction, String> {
-    let (_, secret_key, public_key) = get_keypair(account);
-    let signer_addr = get_stacks_address(&public_key, network);
+) -> StacksTransaction {
+    let signer_addr = get_stacks_address(public_key, network);
 
     let spending_condition = TransactionSpendingCondition::Singlesig(SinglesigSpendingCondition {
         signer: signer_addr.bytes,
@@ -107,7 +119,7 @@ fn sign_transaction_payload(
     });
 
     let auth = TransactionAuth::Standard(spending_condition);
-    let unsigned_tx = StacksTransaction {
+    StacksTransaction {
         version: match network {
             StacksNetwork::Mainnet => TransactionVersion::Mainnet,
             _ => TransactionVersion::Testnet,
@@ -121,23 +133,121 @@ fn sign_transaction_payload(
         post_condition_mode: TransactionPostConditionMode::Allow,
         post_conditions: vec![],
         payload,
-    };
+    }
+}
+
+fn sign_transaction_payload(
+    account: &AccountConfig,
+    payload: TransactionPayload,
+    nonce: u64,
+    tx_fee: u64,
+    anchor_mode: TransactionAnchorMode,
+    network: &StacksNetwork,
+) -> Result<StacksTransaction, String> {
+    let (_, public_key) = get_keypair(account);
+    let unsigned_tx =
+        build_unsigned_transaction(&public_key, payload, nonce, tx_fee, anchor_mode, network);
+    sign_transaction(account, &unsigned_tx)
+}
+
+fn sign_transaction(
+    account: &AccountConfig,
+    unsigned_tx: &StacksTransaction,
+) -> Result<StacksTransaction, String> {
+    let (secret_key, _) = get_keypair(account);
 
     let mut unsigned_tx_bytes = vec![];
     unsigned_tx
         .consensus_serialize(&mut unsigned_tx_bytes)
         .expect("FATAL: invalid transaction");
 
-    let mut tx_signer = StacksTransactionSigner::new(&unsigned_tx);
+    let mut tx_signer = StacksTransactionSigner::new(unsigned_tx);
     tx_signer.sign_origin(&secret_key).unwrap();
     let signed_tx = tx_signer.get_tx().unwrap();
     Ok(signed_tx)
 }
 
+fn encode_post_condition_principal(principal: &PrincipalData) -> PostConditionPrincipal {
+    match principal {
+        PrincipalData::Standard(principal) => {
+            PostConditionPrincipal::Standard(StacksAddress::from(principal.clone()))
+        }
+        PrincipalData::Contract(contract_id) => PostConditionPrincipal::Contract(
+            StacksAddress::from(contract_id.issuer.clone()),
+            contract_id.name.clone(),
+        ),
+    }
+}
+
+fn encode_asset_info(asset: &AssetIdentifier) -> AssetInfo {
+    AssetInfo {
+        contract_address: StacksAddress::from(asset.contract_identifier.issuer.clone()),
+        contract_name: asset.contract_identifier.name.clone(),
+        asset_name: asset.asset_name.clone(),
+    }
+}
+
+fn encode_fungible_condition_code(
+    code: &FungibleConditionCode,
+) -> TransactionFungibleConditionCode {
+    match code {
+        FungibleConditionCode::SentEq => TransactionFungibleConditionCode::SentEq,
+        FungibleConditionCode::SentGt => TransactionFungibleConditionCode::SentGt,
+        FungibleConditionCode::SentGe => TransactionFungibleConditionCode::SentGe,
+        FungibleConditionCode::SentLt => TransactionFungibleConditionCode::SentLt,
+        FungibleConditionCode::SentLe => TransactionFungibleConditionCode::SentLe,
+    }
+}
+
+/// Convert the post-conditions enforced in simnet to the ones included in transactions.
+pub fn encode_post_conditions(
+    post_conditions: &PostConditions,
+) -> Result<(TransactionPostConditionMode, Vec<TransactionPostCondition>), String> {
+    let mode = match post_conditions.mode {
+        PostConditionMode::Allow => TransactionPostConditionMode::Allow,
+        PostConditionMode::Deny => TransactionPostConditionMode::Deny,
+    };
+    let encode_amount = |amount: &u128| {
+        u64::try_from(*amount).map_err(|_| format!("post-condition amount {} too large", amount))
+    };
+    let mut conditions = vec![];
+    for condition in post_conditions.conditions.iter() {
+        let condition = match condition {
+            PostCondition::Stx(principal, code, amount) => TransactionPostCondition::STX(
+                encode_post_condition_principal(principal),
+                encode_fungible_condition_code(code),
+                encode_amount(amount)?,
+            ),
+            PostCondition::Fungible(principal, asset, code, amount) => {
+                TransactionPostCondition::Fungible(
+                    encode_post_condition_principal(principal),
+                    encode_asset_info(asset),
+                    encode_fungible_condition_code(code),
+                    encode_amount(amount)?,
+                )
+            }
+            PostCondition::NonFungible(principal, asset, value, code) => {
+                TransactionPostCondition::Nonfungible(
+                    encode_post_condition_principal(principal),
+                    encode_asset_info(asset),
+                    value.clone(),
+                    match code {
+                        NonFungibleConditionCode::Sent => NonfungibleConditionCode::Sent,
+                        NonFungibleConditionCode::NotSent => NonfungibleConditionCode::NotSent,
+                    },
+                )
+            }
+        };
+        conditions.push(condition);
+    }
+    Ok((mode, conditions))
+}
+
 pub fn encode_contract_call(
     contract_id: &QualifiedContractIdentifier,
     function_name: ClarityName,
     function_args: Vec<Value>,
+    post_conditions: &PostConditions,
     account: &AccountConfig,
     nonce: u64,
     tx_fee: u64,
@@ -150,14 +260,18 @@ pub fn encode_contract_call(
         function_name: function_name.clone(),
         function_args: function_args.clone(),
     };
-    sign_transaction_payload(
-        account,
+    let (_, public_key) = get_keypair(account);
+    let mut unsigned_tx = build_unsigned_transaction(
+        &public_key,
         TransactionPayload::ContractCall(payload),
         nonce,
         tx_fee,
         anchor_mode,
         network,
-    )
+    );
+    (unsigned_tx.post_condition_mode, unsigned_tx.post_conditions) =
+        encode_post_conditions(post_conditions)?;
+    sign_transaction(account, &unsigned_tx)
 }
 
 pub fn encode_stx_transfer(
@@ -226,17 +340,33 @@ pub enum TransactionCheck {
 #[derive(Clone, Debug)]
 pub enum DeploymentEvent {
     TransactionUpdate(TransactionTracker),
+    /// Transactions signed during a dry run, sent before `DeploymentCompleted`
+    TransactionsRecorded(Vec<RecordedTransaction>),
     Interrupted(String),
     DeploymentCompleted,
 }
 
+/// Called with the details of each batch of the plan before it is broadcasted, an error
+/// interrupts the deployment
+pub type DeploymentStepHook = Box<dyn Fn(serde_json::Value) -> Result<(), String> + Send>;
+
 pub enum DeploymentCommand {
     Start,
+    /// Sign the transactions and record them instead of broadcasting them
+    DryRun,
+}
+
+/// `fee` multiplied by `fee_multiplier`, rounded up
+pub fn apply_fee_multiplier(fee: u64, fee_multiplier: f64) -> u64 {
+    (fee as f64 * fee_multiplier).ceil() as u64
 }
 
+/// Set the costs of the transactions of `deployment` to the fees estimated by the stacks node,
+/// multiplied by `fee_multiplier`
 pub fn update_deployment_costs(
     deployment: &mut DeploymentSpecification,
     priority: usize,
+    fee_multiplier: f64,
 ) -> Result<(), String> {
     let stacks_node_url = deployment
         .stacks_node
@@ -257,7 +387,7 @@ pub fn update_deployment_costs(
 
                     match stacks_rpc.estimate_transaction_fee(&transaction_payload, priority) {
                         Ok(fee) => {
-                            tx.cost = fee;
+                            tx.cost = apply_fee_multiplier(fee, fee_multiplier);
                         }
                         Err(e) => {
                             println!("unable to estimate fee for transaction: {}", e);
@@ -288,7 +418,7 @@ pub fn update_deployment_costs(
 
                     match stacks_rpc.estimate_transaction_fee(&transaction_payload, priority) {
                         Ok(fee) => {
-                            tx.cost = fee;
+                            tx.cost = apply_fee_multiplier(fee, fee_multiplier);
                         }
                         Err(e) => {
                             println!("unable to estimate fee for transaction: {}", e);
@@ -307,7 +437,7 @@ pub fn update_deployment_costs(
 
                     match stacks_rpc.estimate_transaction_fee(&transaction_payload, priority) {
                         Ok(fee) => {
-                            tx.cost = fee;
+                            tx.cost = apply_fee_multiplier(fee, fee_multiplier);
                         }
                         Err(e) => {
                             println!("unable to estimate fee for transaction: {}", e);
@@ -325,6 +455,113 @@ pub fn update_deployment_costs(
     Ok(())
 }
 
+/// Encoded transaction of the plan, with what is needed to record its broadcast
+struct PlannedTransaction {
+    position: usize,
+    sender: String,
+    nonce: u64,
+    tracker: TransactionTracker,
+}
+
+fn transaction_sender(transaction: &TransactionSpecification) -> Option<&StandardPrincipalData> {
+    match transaction {
+        TransactionSpecification::StxTransfer(tx) => Some(&tx.expected_sender),
+        TransactionSpecification::ContractCall(tx) => Some(&tx.expected_sender),
+        TransactionSpecification::ContractPublish(tx) => Some(&tx.expected_sender),
+        TransactionSpecification::RequirementPublish(tx) => Some(&tx.remap_sender),
+        TransactionSpecification::BtcTransfer(_)
+        | TransactionSpecification::EmulatedContractPublish(_)
+        | TransactionSpecification::EmulatedContractCall(_) => None,
+    }
+}
+
+fn transaction_name(transaction: &TransactionSpecification) -> String {
+    match transaction {
+        TransactionSpecification::StxTransfer(tx) => format!(
+            "STX transfer ({}µSTX from {} to {})",
+            tx.mstx_amount, tx.expected_sender, tx.recipient,
+        ),
+        TransactionSpecification::ContractCall(tx) => format!(
+            "Call ({} {} {})",
+            tx.contract_id,
+            tx.method,
+            tx.parameters.join(" ")
+        ),
+        TransactionSpecification::ContractPublish(tx) => {
+            format!("Publish {}.{}", tx.expected_sender, tx.contract_name)
+        }
+        TransactionSpecification::RequirementPublish(tx) => {
+            format!("Publish {}.{}", tx.remap_sender, tx.contract_id.name)
+        }
+        TransactionSpecification::BtcTransfer(tx) => format!(
+            "BTC transfer ({} from {} to {})",
+            tx.sats_amount, tx.expected_sender, tx.recipient
+        ),
+        TransactionSpecification::EmulatedContractPublish(tx) => {
+            format!(
+                "Emulated publish {}.{}",
+                tx.emulated_sender, tx.contract_name
+            )
+        }
+        TransactionSpecification::EmulatedContractCall(tx) => {
+            format!("Emulated call {}::{}", tx.contract_id, tx.method)
+        }
+    }
+}
+
+/// Status of a transaction posted by a previous run of the deployment: confirmed once the
+/// account nonce `confirmed` moved past its nonce, still pending otherwise
+fn resume_status(
+    previous: &BroadcastedTransaction,
+    sender: &StandardPrincipalData,
+    confirmed: u64,
+    nonces: &NonceTracker,
+) -> TransactionStatus {
+    if confirmed > previous.nonce {
+        return TransactionStatus::Confirmed;
+    }
+    nonces.mark_pending(&previous.sender, previous.nonce, confirmed);
+    TransactionStatus::Broadcasted(
+        TransactionCheck::NonceCheck(sender.clone(), previous.nonce),
+        previous.txid.clone(),
+    )
+}
+
+/// Account and next nonce of the sender `address` of a transaction
+fn reserve_sender<'a>(
+    address: &str,
+    accounts: &BTreeMap<String, &'a AccountConfig>,
+    nonces: &NonceTracker,
+    broadcaster: &mut Broadcaster,
+) -> Result<(&'a AccountConfig, u64), String> {
+    let account = accounts
+        .get(address)
+        .ok_or_else(|| format!("account {} not found in the network settings", address))?;
+    let nonce = nonces.reserve_with(address, |address| broadcaster.get_nonce(address))?;
+    Ok((account, nonce))
+}
+
+/// Wait for the command starting the deployment, false if the channel is broken
+fn receive_deployment_command(
+    deployment_command_rx: &Receiver<DeploymentCommand>,
+    deployment_event_tx: &Sender<DeploymentEvent>,
+    broadcaster: &mut Broadcaster,
+) -> bool {
+    match deployment_command_rx.recv() {
+        Ok(DeploymentCommand::Start) => true,
+        Ok(DeploymentCommand::DryRun) => {
+            broadcaster.start_recording();
+            true
+        }
+        Err(_) => {
+            let _ = deployment_event_tx.send(DeploymentEvent::Interrupted(
+                "deployment aborted - broken channel".to_string(),
+            ));
+            false
+        }
+    }
+}
+
 pub fn apply_on_chain_deployment(
     network_manifest: NetworkManifest,
     deployment: DeploymentSpecification,
@@ -333,6 +570,8 @@ pub fn apply_on_chain_deployment(
     fetch_initial_nonces: bool,
     override_bitcoin_rpc_url: Option<String>,
     override_stacks_rpc_url: Option<String>,
+    progress_location: Option<FileLocation>,
+    before_step: Option<DeploymentStepHook>,
 ) {
     let networks = deployment.network.get_networks();
     let delay_between_checks: u64 = if matches!(networks.1, StacksNetwork::Devnet) {
@@ -345,13 +584,13 @@ pub fn apply_on_chain_deployment(
 
     let mut batches = VecDeque::new();
     let network = deployment.network.clone();
-    let mut accounts_cached_nonces: BTreeMap<String, u64> = BTreeMap::new();
+    let nonces = NonceTracker::new();
     let mut stx_accounts_lookup: BTreeMap<String, &AccountConfig> = BTreeMap::new();
     let mut btc_accounts_lookup: BTreeMap<String, &AccountConfig> = BTreeMap::new();
     let mut default_epoch = EpochSpec::Epoch2_05;
     if !fetch_initial_nonces {
         for (_, account) in network_manifest.accounts.iter() {
-            accounts_cached_nonces.insert(account.stx_address.clone(), 0);
+            nonces.set_next_nonce(&account.stx_address, 0);
         }
         if network_manifest.devnet.is_some() {
             default_epoch = EpochSpec::Epoch2_1;
@@ -371,7 +610,22 @@ pub fn apply_on_chain_deployment(
             .expect("unable to get stacks node rcp address")
     };
 
-    let stacks_rpc = StacksRpc::new(&stacks_node_url);
+    let mut stacks_node_urls = vec![stacks_node_url];
+    stacks_node_urls.extend(
+        network_manifest
+            .network
+            .stacks_node_rpc_fallback_addresses
+            .iter()
+            .cloned(),
+    );
+    let mut broadcaster = Broadcaster::new(&stacks_node_urls);
+    // Phase 1 queries the nodes for nonces and published contracts
+    if fetch_initial_nonces {
+        if let Err(e) = broadcaster.select_healthy_node() {
+            let _ = deployment_event_tx.send(DeploymentEvent::Interrupted(e));
+            return;
+        }
+    }
 
     let bitcoin_node_url = if let Some(url) = override_bitcoin_rpc_url {
         url
@@ -381,8 +635,37 @@ pub fn apply_on_chain_deployment(
             .expect("unable to get bitcoin node rcp address")
     };
 
+    let mut progress = match progress_location {
+        Some(ref location) => match DeploymentProgress::load(location, &deployment.name) {
+            Ok(progress) => progress,
+            Err(e) => {
+                let _ = deployment_event_tx.send(DeploymentEvent::Interrupted(e));
+                return;
+            }
+        },
+        None => DeploymentProgress::new(&deployment.name),
+    };
+
+    // BTC transfers are sent while encoding, so a dry run has to be known beforehand.
+    // Otherwise the plan is encoded while waiting for the command.
+    let sends_btc_transfers = deployment.plan.batches.iter().any(|batch| {
+        batch
+            .transactions
+            .iter()
+            .any(|tx| matches!(tx, TransactionSpecification::BtcTransfer(_)))
+    });
+    if sends_btc_transfers
+        && !receive_deployment_command(
+            &deployment_command_rx,
+            &deployment_event_tx,
+            &mut broadcaster,
+        )
+    {
+        return;
+    }
+
     // Phase 1: we traverse the deployment plan and encode all the transactions,
-    // keeping the order.
+    // keeping the order. Transactions posted by a previous run are not encoded again.
     // Using a session to encode + coerce/check (todo) contract calls arguments.
     let mut session = Session::new(SessionSettings::default());
     let mut index = 0;
@@ -407,20 +690,76 @@ pub fn apply_on_chain_deployment(
         ));
     }
 
+    let mut position = 0;
     for batch_spec in deployment.plan.batches.iter() {
         let epoch = batch_spec.epoch.unwrap_or(default_epoch);
         let mut batch = Vec::new();
         for transaction in batch_spec.transactions.iter() {
-            let tracker = match transaction {
+            let transaction_spec = transaction;
+            let current_position = position;
+            position += 1;
+
+            if let TransactionSpecification::RequirementPublish(tx) = transaction {
+                // the contracts published after a requirement refer to its new address
+                let old_contract_id = tx.contract_id.to_string();
+                let new_contract_id = QualifiedContractIdentifier::new(
+                    tx.remap_sender.clone(),
+                    tx.contract_id.name.clone(),
+                )
+                .to_string();
+                contracts_ids_to_remap.insert((old_contract_id, new_contract_id));
+            }
+
+            let previous = transaction_sender(transaction).and_then(|sender| {
+                progress
+                    .broadcasted(current_position, &sender.to_address())
+                    .map(|previous| (sender, previous.clone()))
+            });
+            if let Some((sender, previous)) = previous {
+                let confirmed = match broadcaster.get_nonce(&previous.sender) {
+                    Ok(confirmed) => confirmed,
+                    Err(e) => {
+                        let _ = deployment_event_tx.send(DeploymentEvent::Interrupted(format!(
+                            "unable to retrieve nonce for {}: {}",
+                            previous.sender, e
+                        )));
+                        return;
+                    }
+                };
+                let tracker = TransactionTracker {
+                    index,
+                    name: transaction_name(transaction),
+                    status: resume_status(&previous, sender, confirmed, &nonces),
+                };
+                let _ =
+                    deployment_event_tx.send(DeploymentEvent::TransactionUpdate(tracker.clone()));
+                index += 1;
+                if matches!(tracker.status, TransactionStatus::Broadcasted(..)) {
+                    batch.push(PlannedTransaction {
+                        position: current_position,
+                        sender: previous.sender,
+                        nonce: previous.nonce,
+                        tracker,
+                    });
+                }
+                continue;
+            }
+
+            let (tracker, sender, nonce) = match transaction {
                 TransactionSpecification::StxTransfer(tx) => {
                     let issuer_address = tx.expected_sender.to_address();
-                    let nonce = match accounts_cached_nonces.get(&issuer_address) {
-                        Some(cached_nonce) => *cached_nonce,
-                        None => stacks_rpc
-                            .get_nonce(&issuer_address)
-                            .expect("Unable to retrieve account"),
+                    let (account, nonce) = match reserve_sender(
+                        &issuer_address,
+                        &stx_accounts_lookup,
+                        &nonces,
+                        &mut broadcaster,
+                    ) {
+                        Ok(res) => res,
+                        Err(e) => {
+                            let _ = deployment_event_tx.send(DeploymentEvent::Interrupted(e));
+                            return;
+                        }
                     };
-                    let account = stx_accounts_lookup.get(&issuer_address).unwrap();
 
                     let anchor_mode = match tx.anchor_block_only {
                         true => TransactionAnchorMode::OnChainOnly,
@@ -446,17 +785,13 @@ pub fn apply_on_chain_deployment(
                         }
                     };
 
-                    accounts_cached_nonces.insert(issuer_address.clone(), nonce + 1);
-                    let name = format!(
-                        "STX transfer ({}µSTX from {} to {})",
-                        tx.mstx_amount, issuer_address, tx.recipient,
-                    );
                     let check = TransactionCheck::NonceCheck(tx.expected_sender.clone(), nonce);
-                    TransactionTracker {
+                    let tracker = TransactionTracker {
                         index,
-                        name: name.clone(),
+                        name: transaction_name(transaction_spec),
                         status: TransactionStatus::Encoded(transaction, check),
-                    }
+                    };
+                    (tracker, issuer_address, nonce)
                 }
                 TransactionSpecification::BtcTransfer(tx) => {
                     let url = Url::parse(&bitcoin_node_url).expect("Url malformatted");
@@ -483,8 +818,29 @@ pub fn apply_on_chain_deployment(
                     let bitcoin_node_wallet_rpc =
                         Client::new(&bitcoin_node_wallet_rpc_url, auth).unwrap();
 
-                    let account = btc_accounts_lookup.get(&tx.expected_sender).unwrap();
+                    let Some(account) = btc_accounts_lookup.get(&tx.expected_sender) else {
+                        let _ = deployment_event_tx.send(DeploymentEvent::Interrupted(format!(
+                            "account {} not found in the network settings",
+                            tx.expected_sender
+                        )));
+                        return;
+                    };
                     let (secret_key, _public_key) = get_btc_keypair(account);
+                    if broadcaster.is_dry_run() {
+                        let transaction = bitcoin_deployment::sign_transaction_spec(
+                            &bitcoin_node_wallet_rpc,
+                            tx,
+                            &secret_key,
+                        );
+                        broadcaster.record(RecordedTransaction {
+                            txid: transaction.txid().to_string(),
+                            node: bitcoin_node_url.clone(),
+                            tx_hex: bytes_to_hex(&bitcoin::consensus::encode::serialize(
+                                &transaction,
+                            )),
+                        });
+                        continue;
+                    }
                     let _ = bitcoin_deployment::send_transaction_spec(
                         &bitcoin_rpc,
                         &bitcoin_node_wallet_rpc,
@@ -495,13 +851,18 @@ pub fn apply_on_chain_deployment(
                 }
                 TransactionSpecification::ContractCall(tx) => {
                     let issuer_address = tx.expected_sender.to_address();
-                    let nonce = match accounts_cached_nonces.get(&issuer_address) {
-                        Some(cached_nonce) => *cached_nonce,
-                        None => stacks_rpc
-                            .get_nonce(&issuer_address)
-                            .expect("Unable to retrieve account"),
+                    let (account, nonce) = match reserve_sender(
+                        &issuer_address,
+                        &stx_accounts_lookup,
+                        &nonces,
+                        &mut broadcaster,
+                    ) {
+                        Ok(res) => res,
+                        Err(e) => {
+                            let _ = deployment_event_tx.send(DeploymentEvent::Interrupted(e));
+                            return;
+                        }
                     };
-                    let account = stx_accounts_lookup.get(&issuer_address).unwrap();
 
                     let mut function_args = vec![];
                     for value in tx.parameters.iter() {
@@ -523,6 +884,22 @@ pub fn apply_on_chain_deployment(
                         };
                     }
 
+                    let post_conditions = match build_post_conditions(
+                        &mut session,
+                        tx.post_condition_mode,
+                        &tx.post_conditions,
+                    ) {
+                        Ok(post_conditions) => post_conditions,
+                        Err(e) => {
+                            let _ =
+                                deployment_event_tx.send(DeploymentEvent::Interrupted(format!(
+                                    "unable to process contract-call {}::{}: {}",
+                                    tx.contract_id, tx.method, e
+                                )));
+                            return;
+                        }
+                    };
+
                     let anchor_mode = match tx.anchor_block_only {
                         true => TransactionAnchorMode::OnChainOnly,
                         false => TransactionAnchorMode::Any,
@@ -532,6 +909,7 @@ pub fn apply_on_chain_deployment(
                         &tx.contract_id,
                         tx.method.clone(),
                         function_args,
+                        &post_conditions,
                         account,
                         nonce,
                         tx.cost,
@@ -549,30 +927,53 @@ pub fn apply_on_chain_deployment(
                         }
                     };
 
-                    accounts_cached_nonces.insert(issuer_address.clone(), nonce + 1);
-                    let name = format!(
-                        "Call ({} {} {})",
-                        tx.contract_id,
-                        tx.method,
-                        tx.parameters.join(" ")
-                    );
                     let check = TransactionCheck::NonceCheck(tx.expected_sender.clone(), nonce);
-                    TransactionTracker {
+                    let tracker = TransactionTracker {
                         index,
-                        name: name.clone(),
+                        name: transaction_name(transaction_spec),
                         status: TransactionStatus::Encoded(transaction, check),
-                    }
+                    };
+                    (tracker, issuer_address, nonce)
                 }
                 TransactionSpecification::ContractPublish(tx) => {
+                    let name = transaction_name(transaction_spec);
+
+                    // Resuming an interrupted deployment: skip the contracts already published
+                    if matches!(
+                        deployment.network,
+                        StacksNetwork::Testnet | StacksNetwork::Mainnet
+                    ) {
+                        let res = broadcaster.get_contract_source(
+                            &tx.expected_sender.to_address(),
+                            &tx.contract_name.to_string(),
+                        );
+                        if let Ok(_contract) = res {
+                            let tracker = TransactionTracker {
+                                index,
+                                name,
+                                status: TransactionStatus::Confirmed,
+                            };
+                            let _ = deployment_event_tx
+                                .send(DeploymentEvent::TransactionUpdate(tracker));
+                            index += 1;
+                            continue;
+                        }
+                    }
+
                     // Retrieve nonce for issuer
                     let issuer_address = tx.expected_sender.to_address();
-                    let nonce = match accounts_cached_nonces.get(&issuer_address) {
-                        Some(cached_nonce) => *cached_nonce,
-                        None => stacks_rpc
-                            .get_nonce(&issuer_address)
-                            .expect("Unable to retrieve account"),
+                    let (account, nonce) = match reserve_sender(
+                        &issuer_address,
+                        &stx_accounts_lookup,
+                        &nonces,
+                        &mut broadcaster,
+                    ) {
+                        Ok(res) => res,
+                        Err(e) => {
+                            let _ = deployment_event_tx.send(DeploymentEvent::Interrupted(e));
+                            return;
+                        }
                     };
-                    let account = stx_accounts_lookup.get(&issuer_address).unwrap();
                     let source = if matches!(
                         deployment.network,
                         StacksNetwork::Devnet | StacksNetwork::Testnet
@@ -629,33 +1030,25 @@ pub fn apply_on_chain_deployment(
                         }
                     };
 
-                    accounts_cached_nonces.insert(issuer_address.clone(), nonce + 1);
-                    let name = format!("Publish {}.{}", tx.expected_sender, tx.contract_name);
                     let check = TransactionCheck::ContractPublish(
                         tx.expected_sender.clone(),
                         tx.contract_name.clone(),
                     );
-                    TransactionTracker {
+                    let tracker = TransactionTracker {
                         index,
-                        name: name.clone(),
+                        name,
                         status: TransactionStatus::Encoded(transaction, check),
-                    }
+                    };
+                    (tracker, issuer_address, nonce)
                 }
                 TransactionSpecification::RequirementPublish(tx) => {
                     if matches!(deployment.network, StacksNetwork::Mainnet) {
                         panic!("Deployment specification malformed - requirements publish not supported on mainnet");
                     }
-                    let old_contract_id = tx.contract_id.to_string();
-                    let new_contract_id = QualifiedContractIdentifier::new(
-                        tx.remap_sender.clone(),
-                        tx.contract_id.name.clone(),
-                    )
-                    .to_string();
-                    contracts_ids_to_remap.insert((old_contract_id, new_contract_id));
 
                     // Testnet handling: don't re-deploy previously deployed contracts
                     if matches!(deployment.network, StacksNetwork::Testnet) {
-                        let res = stacks_rpc.get_contract_source(
+                        let res = broadcaster.get_contract_source(
                             &tx.remap_sender.to_address(),
                             &tx.contract_id.name.to_string(),
                         );
@@ -666,13 +1059,18 @@ pub fn apply_on_chain_deployment(
 
                     // Retrieve nonce for issuer
                     let issuer_address = tx.remap_sender.to_address();
-                    let nonce = match accounts_cached_nonces.get(&issuer_address) {
-                        Some(cached_nonce) => *cached_nonce,
-                        None => stacks_rpc
-                            .get_nonce(&issuer_address)
-                            .expect("Unable to retrieve account"),
+                    let (account, nonce) = match reserve_sender(
+                        &issuer_address,
+                        &stx_accounts_lookup,
+                        &nonces,
+                        &mut broadcaster,
+                    ) {
+                        Ok(res) => res,
+                        Err(e) => {
+                            let _ = deployment_event_tx.send(DeploymentEvent::Interrupted(e));
+                            return;
+                        }
                     };
-                    let account = stx_accounts_lookup.get(&issuer_address).unwrap();
 
                     // Remapping principals - This is happening
                     let mut source = tx.source.clone();
@@ -717,46 +1115,50 @@ pub fn apply_on_chain_deployment(
                         }
                     };
 
-                    accounts_cached_nonces.insert(issuer_address.clone(), nonce + 1);
-                    let name = format!("Publish {}.{}", tx.remap_sender, tx.contract_id.name);
                     let check = TransactionCheck::ContractPublish(
                         tx.remap_sender.clone(),
                         tx.contract_id.name.clone(),
                     );
-                    TransactionTracker {
+                    let tracker = TransactionTracker {
                         index,
-                        name: name.clone(),
+                        name: transaction_name(transaction_spec),
                         status: TransactionStatus::Encoded(transaction, check),
-                    }
+                    };
+                    (tracker, issuer_address, nonce)
                 }
                 TransactionSpecification::EmulatedContractPublish(_)
                 | TransactionSpecification::EmulatedContractCall(_) => continue,
             };
 
-            batch.push(tracker.clone());
-            let _ = deployment_event_tx.send(DeploymentEvent::TransactionUpdate(tracker));
+            let _ = deployment_event_tx.send(DeploymentEvent::TransactionUpdate(tracker.clone()));
+            batch.push(PlannedTransaction {
+                position: current_position,
+                sender,
+                nonce,
+                tracker,
+            });
             index += 1;
         }
 
         batches.push_back((epoch, batch));
     }
 
-    let _cmd = match deployment_command_rx.recv() {
-        Ok(cmd) => cmd,
-        Err(_) => {
-            let _ = deployment_event_tx.send(DeploymentEvent::Interrupted(
-                "deployment aborted - broken channel".to_string(),
-            ));
-            return;
-        }
-    };
+    if !sends_btc_transfers
+        && !receive_deployment_command(
+            &deployment_command_rx,
+            &deployment_event_tx,
+            &mut broadcaster,
+        )
+    {
+        return;
+    }
 
     // Phase 2: we submit all the transactions previously encoded,
     // and wait for their inclusion in a block before moving to the next batch.
     let mut current_block_height = 0;
     let mut current_bitcoin_block_height = 0;
-    for (epoch, batch) in batches.into_iter() {
-        if network == StacksNetwork::Devnet {
+    for (step, (epoch, batch)) in batches.into_iter().enumerate() {
+        if network == StacksNetwork::Devnet && !broadcaster.is_dry_run() {
             // Devnet only: ensure we've reached the appropriate epoch for this batch
             let after_bitcoin_block = match epoch {
                 EpochSpec::Epoch2_0 => network_manifest.devnet.as_ref().unwrap().epoch_2_0,
@@ -772,7 +1174,7 @@ pub fn apply_on_chain_deployment(
                 current_bitcoin_block_height > after_bitcoin_block;
 
             while !epoch_transition_successful {
-                let (bitcoin_block_tip, stacks_block_tip) = match stacks_rpc.get_info() {
+                let (bitcoin_block_tip, stacks_block_tip) = match broadcaster.get_info() {
                     Ok(info) => {
                         if info.stacks_tip_height == 0 {
                             // Always loop if we have not yet seen the genesis block.
@@ -815,19 +1217,52 @@ pub fn apply_on_chain_deployment(
             }
         }
 
+        if let (Some(before_step), false) = (&before_step, broadcaster.is_dry_run()) {
+            let transactions: Vec<&str> = batch
+                .iter()
+                .map(|planned| planned.tracker.name.as_str())
+                .collect();
+            let context = serde_json::json!({
+                "step": step,
+                "epoch": epoch,
+                "transactions": transactions,
+            });
+            if let Err(message) = before_step(context) {
+                let _ = deployment_event_tx.send(DeploymentEvent::Interrupted(message));
+                return;
+            }
+        }
+
         let mut ongoing_batch = BTreeMap::new();
-        for mut tracker in batch.into_iter() {
+        for planned in batch.into_iter() {
+            let mut tracker = planned.tracker;
+            if let TransactionStatus::Broadcasted(_, ref txid) = tracker.status {
+                // posted by a previous run, only waiting for its confirmation
+                let txid = txid.clone();
+                ongoing_batch.insert(txid, tracker);
+                continue;
+            }
             let (transaction, check) = match tracker.status {
                 TransactionStatus::Encoded(transaction, check) => (transaction, check),
                 _ => unreachable!(),
             };
-            match stacks_rpc.post_transaction(&transaction) {
-                Ok(res) => {
-                    tracker.status = TransactionStatus::Broadcasted(check, res.txid.clone());
+            match broadcaster.post_transaction(&transaction) {
+                Ok(txid) => {
+                    if let (Some(location), false) = (&progress_location, broadcaster.is_dry_run())
+                    {
+                        progress.record(planned.position, &txid, &planned.sender, planned.nonce);
+                        if let Err(e) = progress.save(location) {
+                            let _ = deployment_event_tx.send(DeploymentEvent::Interrupted(e));
+                            return;
+                        }
+                    }
+                    tracker.status = TransactionStatus::Broadcasted(check, txid.clone());
 
                     let _ = deployment_event_tx
                         .send(DeploymentEvent::TransactionUpdate(tracker.clone()));
-                    ongoing_batch.insert(res.txid, tracker);
+                    if !broadcaster.is_dry_run() {
+                        ongoing_batch.insert(txid, tracker);
+                    }
                 }
                 Err(e) => {
                     let message = format!("unable to post transaction\n{}", e);
@@ -840,11 +1275,15 @@ pub fn apply_on_chain_deployment(
                 }
             };
         }
+        // Nothing was broadcasted during a dry run, no need to wait for the batch
+        if ongoing_batch.is_empty() {
+            continue;
+        }
         let mut last_stacks_chain_check_at_height = 0;
         let mut last_bitcoin_chain_check_at_height = 0;
 
         loop {
-            let (bitcoin_tip_height, stacks_tip_height) = match stacks_rpc.get_info() {
+            let (bitcoin_tip_height, stacks_tip_height) = match broadcaster.get_info() {
                 Ok(info) => (info.burn_block_height, info.stacks_tip_height),
                 _ => {
                     std::thread::sleep(std::time::Duration::from_secs(delay_between_checks));
@@ -866,7 +1305,7 @@ pub fn apply_on_chain_deployment(
                         TransactionCheck::ContractPublish(deployer, contract_name) => {
                             let deployer_address = deployer.to_address();
                             let res =
-                                stacks_rpc.get_contract_source(&deployer_address, contract_name);
+                                broadcaster.get_contract_source(&deployer_address, contract_name);
                             match res {
                                 Ok(_contract) => {
                                     tracker.status = TransactionStatus::Confirmed;
@@ -881,7 +1320,7 @@ pub fn apply_on_chain_deployment(
                         }
                         TransactionCheck::NonceCheck(tx_sender, expected_nonce) => {
                             let tx_sender_address = tx_sender.to_address();
-                            let res = stacks_rpc.get_nonce(&tx_sender_address);
+                            let res = broadcaster.get_nonce(&tx_sender_address);
                             if let Ok(current_nonce) = res {
                                 if current_nonce.gt(expected_nonce) {
                                     tracker.status = TransactionStatus::Confirmed;
@@ -930,6 +1369,14 @@ pub fn apply_on_chain_deployment(
         }
     }
 
+    if broadcaster.is_dry_run() {
+        let _ = deployment_event_tx.send(DeploymentEvent::TransactionsRecorded(
+            broadcaster.recorded_transactions().to_vec(),
+        ));
+    } else if let Some(location) = progress_location {
+        // the deployment is complete, applying it again starts from scratch
+        let _ = std::fs::remove_file(location.to_string());
+    }
     let _ = deployment_event_tx.send(DeploymentEvent::DeploymentCompleted);
 }
 
@@ -999,3 +1446,45 @@ pub fn get_initial_transactions_trackers(
     }
     trackers
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_fee_multiplier() {
+        assert_eq!(apply_fee_multiplier(1000, 1.0), 1000);
+        assert_eq!(apply_fee_multiplier(1000, 1.5), 1500);
+        assert_eq!(apply_fee_multiplier(3, 0.5), 2);
+        assert_eq!(apply_fee_multiplier(1000, 0.0), 0);
+    }
+
+    #[test]
+    fn test_resume_status() {
+        let sender = "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM";
+        let principal = PrincipalData::parse_standard_principal(sender).unwrap();
+        let previous = BroadcastedTransaction {
+            txid: "0x01".to_string(),
+            sender: sender.to_string(),
+            nonce: 4,
+        };
+        let nonces = NonceTracker::new();
+
+        // the node moved past the nonce of the transaction
+        let status = resume_status(&previous, &principal, 5, &nonces);
+        assert!(matches!(status, TransactionStatus::Confirmed));
+        assert_eq!(nonces.reserve(sender, None), Ok(0));
+
+        // still in the mempool: the next transactions of the sender come after it
+        let nonces = NonceTracker::new();
+        let status = resume_status(&previous, &principal, 3, &nonces);
+        match status {
+            TransactionStatus::Broadcasted(TransactionCheck::NonceCheck(_, nonce), txid) => {
+                assert_eq!(nonce, 4);
+                assert_eq!(txid, "0x01");
+            }
+            _ => panic!("expected a broadcasted transaction"),
+        }
+        assert_eq!(nonces.reserve(sender, None), Ok(5));
+    }
+}
@@ -0,0 +1,247 @@
+use clarinet_files::{AccountConfig, StacksNetwork};
+use clarity_repl::clarity::vm::types::PrincipalData;
+use stacks_codec::codec::{StacksTransaction, TransactionAnchorMode};
+use stacks_rpc_client::rpc_client::AddressNonces;
+use stacks_rpc_client::StacksRpc;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::encode_stx_transfer;
+
+const CANCEL_MEMO: &[u8] = b"clarinet: cancel stuck nonce";
+
+#[derive(Debug, Clone, Default)]
+struct AccountNonces {
+    /// Nonce of the next transaction to be included
+    confirmed: u64,
+    /// Nonce of the next transaction to be sent
+    next: u64,
+    /// Nonces sent and not included yet, with the time they were reserved
+    pending: BTreeMap<u64, Instant>,
+}
+
+/// Nonces of the accounts sending transactions. Clones share the same state, so that
+/// concurrent operations sending from the same account never reuse a nonce.
+#[derive(Debug, Clone, Default)]
+pub struct NonceTracker {
+    accounts: Arc<Mutex<BTreeMap<String, AccountNonces>>>,
+}
+
+impl NonceTracker {
+    pub fn new() -> Self {
+        NonceTracker::default()
+    }
+
+    /// Start tracking `address` from `nonce`, without querying the node
+    pub fn set_next_nonce(&self, address: &str, nonce: u64) {
+        let mut accounts = self.accounts.lock().unwrap();
+        let account = accounts.entry(address.to_string()).or_default();
+        account.confirmed = nonce;
+        account.next = nonce;
+        account.pending.clear();
+    }
+
+    /// Reserve the next nonce of `address`. Accounts that are not tracked yet start
+    /// from the nonce reported by `stacks_rpc`, or from 0 without node.
+    pub fn reserve(&self, address: &str, stacks_rpc: Option<&StacksRpc>) -> Result<u64, String> {
+        self.reserve_with(address, |address| match stacks_rpc {
+            Some(stacks_rpc) => stacks_rpc.get_nonce(address).map_err(|e| e.to_string()),
+            None => Ok(0),
+        })
+    }
+
+    /// Reserve the next nonce of `address`, accounts that are not tracked yet start
+    /// from the nonce returned by `fetch_nonce`
+    pub fn reserve_with(
+        &self,
+        address: &str,
+        fetch_nonce: impl FnOnce(&str) -> Result<u64, String>,
+    ) -> Result<u64, String> {
+        let mut accounts = self.accounts.lock().unwrap();
+        if !accounts.contains_key(address) {
+            let nonce = fetch_nonce(address)
+                .map_err(|e| format!("unable to retrieve nonce for {}: {}", address, e))?;
+            accounts.insert(
+                address.to_string(),
+                AccountNonces {
+                    confirmed: nonce,
+                    next: nonce,
+                    pending: BTreeMap::new(),
+                },
+            );
+        }
+        let account = accounts.get_mut(address).unwrap();
+        let nonce = account.next;
+        account.next += 1;
+        account.pending.insert(nonce, Instant::now());
+        Ok(nonce)
+    }
+
+    /// Track `nonce` of `address` as pending, for a transaction sent by a previous run of
+    /// the deployment. Accounts that are not tracked yet start from `confirmed`.
+    pub fn mark_pending(&self, address: &str, nonce: u64, confirmed: u64) {
+        let mut accounts = self.accounts.lock().unwrap();
+        let account = accounts
+            .entry(address.to_string())
+            .or_insert_with(|| AccountNonces {
+                confirmed,
+                next: confirmed,
+                pending: BTreeMap::new(),
+            });
+        account.next = account.next.max(nonce + 1);
+        account.pending.insert(nonce, Instant::now());
+    }
+
+    /// Give back a nonce whose transaction was rejected. Only the last reserved nonce
+    /// can be reused, an earlier one leaves a gap that `--cancel-stuck` fills.
+    pub fn release(&self, address: &str, nonce: u64) {
+        let mut accounts = self.accounts.lock().unwrap();
+        if let Some(account) = accounts.get_mut(address) {
+            account.pending.remove(&nonce);
+            if account.next == nonce + 1 {
+                account.next = nonce;
+            }
+        }
+    }
+
+    /// Update the confirmed nonce of `address` from the node, returns it
+    pub fn refresh(&self, address: &str, stacks_rpc: &StacksRpc) -> Result<u64, String> {
+        let confirmed = stacks_rpc
+            .get_nonce(address)
+            .map_err(|e| format!("unable to retrieve nonce for {}: {}", address, e))?;
+        self.confirm(address, confirmed);
+        Ok(confirmed)
+    }
+
+    /// Transactions of `address` with a nonce lower than `confirmed` are included
+    pub fn confirm(&self, address: &str, confirmed: u64) {
+        let mut accounts = self.accounts.lock().unwrap();
+        let account = accounts.entry(address.to_string()).or_default();
+        account.confirmed = account.confirmed.max(confirmed);
+        account.next = account.next.max(confirmed);
+        account.pending.retain(|nonce, _| *nonce >= confirmed);
+    }
+
+    pub fn is_included(&self, address: &str, nonce: u64) -> bool {
+        let accounts = self.accounts.lock().unwrap();
+        accounts
+            .get(address)
+            .map(|account| nonce < account.confirmed)
+            .unwrap_or(false)
+    }
+
+    /// Pending nonces of `address` reserved more than `timeout` ago
+    pub fn stuck_nonces(&self, address: &str, timeout: Duration) -> Vec<u64> {
+        let accounts = self.accounts.lock().unwrap();
+        let Some(account) = accounts.get(address) else {
+            return vec![];
+        };
+        account
+            .pending
+            .iter()
+            .filter(|(_, reserved_at)| reserved_at.elapsed() >= timeout)
+            .map(|(nonce, _)| *nonce)
+            .collect()
+    }
+}
+
+/// Burn address of the network, used as recipient of the cancel transactions
+fn get_burn_address(network: &StacksNetwork) -> &'static str {
+    match network {
+        StacksNetwork::Mainnet => "SP000000000000000000002Q6VF78",
+        _ => "ST000000000000000000002AMW42H",
+    }
+}
+
+/// Transaction occupying `nonce` with a transfer of 1µSTX to the burn address, so that
+/// the transactions sent after it can be mined. Replacing a transaction pending in the
+/// mempool requires a higher `tx_fee` than the one it paid.
+pub fn encode_cancel_transaction(
+    account: &AccountConfig,
+    nonce: u64,
+    tx_fee: u64,
+    network: &StacksNetwork,
+) -> Result<StacksTransaction, String> {
+    let recipient = PrincipalData::parse(get_burn_address(network))
+        .map_err(|e| format!("unable to parse burn address ({})", e))?;
+    let mut memo = [0u8; 34];
+    memo[..CANCEL_MEMO.len()].copy_from_slice(CANCEL_MEMO);
+    encode_stx_transfer(
+        recipient,
+        1,
+        memo,
+        account,
+        nonce,
+        tx_fee,
+        TransactionAnchorMode::Any,
+        network,
+    )
+}
+
+/// Nonces of `address` reported by the Stacks API at `api_url`, including the missing
+/// nonces that block the mempool transactions sent after them
+pub fn get_address_nonces(api_url: &str, address: &str) -> Result<AddressNonces, String> {
+    StacksRpc::new(api_url)
+        .get_address_nonces(address)
+        .map_err(|e| format!("unable to retrieve nonces of {}: {}", address, e))
+}
+
+/// Broadcast a cancel transaction for the stuck `nonce` of `account`, returns its txid
+pub fn cancel_stuck_nonce(
+    api_url: &str,
+    account: &AccountConfig,
+    nonce: u64,
+    tx_fee: u64,
+    network: &StacksNetwork,
+) -> Result<String, String> {
+    let transaction = encode_cancel_transaction(account, nonce, tx_fee, network)?;
+    StacksRpc::new(api_url)
+        .post_transaction(&transaction)
+        .map(|res| res.txid)
+        .map_err(|e| format!("unable to cancel nonce {}: {}", nonce, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nonce_tracker() {
+        let address = "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM";
+        let tracker = NonceTracker::new();
+        tracker.set_next_nonce(address, 3);
+
+        let shared = tracker.clone();
+        assert_eq!(tracker.reserve(address, None), Ok(3));
+        assert_eq!(shared.reserve(address, None), Ok(4));
+        assert_eq!(tracker.reserve(address, None), Ok(5));
+
+        // the last nonce can be reused, earlier ones would leave a gap
+        tracker.release(address, 5);
+        tracker.release(address, 3);
+        assert_eq!(shared.reserve(address, None), Ok(5));
+
+        tracker.confirm(address, 5);
+        assert!(tracker.is_included(address, 4));
+        assert!(!tracker.is_included(address, 5));
+        assert_eq!(tracker.stuck_nonces(address, Duration::ZERO), vec![5]);
+
+        let other = "ST2CY5V39NHDPWSXMW9QDT3HC3GD6Q6XX4CFRK9AG";
+        assert_eq!(tracker.reserve(other, None), Ok(0));
+
+        // the node is only queried for accounts that are not tracked yet
+        let third = "ST2JHG361ZXG51QTKY2NQCVBPPRRE2KZB1HR05NNC";
+        assert_eq!(tracker.reserve_with(third, |_| Ok(7)), Ok(7));
+        assert_eq!(
+            tracker.reserve_with(third, |_| Err("unreachable".to_string())),
+            Ok(8)
+        );
+        let error = tracker
+            .reserve_with("ST3NBRSFKX28FQ2ZJ1MAKX58HKHSDGNV5N7R21XCP", |_| {
+                Err("node down".to_string())
+            })
+            .unwrap_err();
+        assert!(error.contains("node down"));
+    }
+}
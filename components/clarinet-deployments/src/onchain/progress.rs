@@ -0,0 +1,96 @@
+use clarinet_files::FileLocation;
+use std::collections::BTreeMap;
+
+/// Transaction of a deployment plan posted to a node
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BroadcastedTransaction {
+    pub txid: String,
+    pub sender: String,
+    pub nonce: u64,
+}
+
+/// Transactions posted while applying a deployment plan, by position in the plan, so that
+/// an interrupted deployment resumes where it left off instead of sending them again
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DeploymentProgress {
+    /// Name of the deployment plan
+    pub deployment: String,
+    pub transactions: BTreeMap<usize, BroadcastedTransaction>,
+}
+
+impl DeploymentProgress {
+    pub fn new(deployment: &str) -> Self {
+        DeploymentProgress {
+            deployment: deployment.to_string(),
+            transactions: BTreeMap::new(),
+        }
+    }
+
+    /// Progress of the deployment `deployment` saved at `location`. A missing file, or the
+    /// progress of another deployment, starts from scratch.
+    pub fn load(location: &FileLocation, deployment: &str) -> Result<Self, String> {
+        if !location.exists() {
+            return Ok(DeploymentProgress::new(deployment));
+        }
+        let content = location.read_content()?;
+        let progress: DeploymentProgress = serde_json::from_slice(&content)
+            .map_err(|e| format!("unable to parse {}: {}", location, e))?;
+        match progress.deployment == deployment {
+            true => Ok(progress),
+            false => Ok(DeploymentProgress::new(deployment)),
+        }
+    }
+
+    pub fn save(&self, location: &FileLocation) -> Result<(), String> {
+        let content = serde_json::to_vec_pretty(self).map_err(|e| e.to_string())?;
+        location.write_content(&content)
+    }
+
+    pub fn record(&mut self, position: usize, txid: &str, sender: &str, nonce: u64) {
+        self.transactions.insert(
+            position,
+            BroadcastedTransaction {
+                txid: txid.to_string(),
+                sender: sender.to_string(),
+                nonce,
+            },
+        );
+    }
+
+    /// Transaction posted at `position` by `sender` in a previous run
+    pub fn broadcasted(&self, position: usize, sender: &str) -> Option<&BroadcastedTransaction> {
+        self.transactions
+            .get(&position)
+            .filter(|transaction| transaction.sender == sender)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deployment_progress() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let location = FileLocation::from_path(temp_dir.path().join("progress.json"));
+        let sender = "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM";
+
+        let mut progress = DeploymentProgress::load(&location, "plan").unwrap();
+        assert!(progress.transactions.is_empty());
+        progress.record(0, "0x01", sender, 4);
+        progress.record(2, "0x02", sender, 5);
+        progress.save(&location).unwrap();
+
+        let resumed = DeploymentProgress::load(&location, "plan").unwrap();
+        assert_eq!(resumed, progress);
+        assert_eq!(resumed.broadcasted(2, sender).unwrap().nonce, 5);
+        assert!(resumed.broadcasted(1, sender).is_none());
+        assert!(resumed
+            .broadcasted(0, "ST2CY5V39NHDPWSXMW9QDT3HC3GD6Q6XX4CFRK9AG")
+            .is_none());
+
+        // the progress of another plan is ignored
+        let other = DeploymentProgress::load(&location, "other plan").unwrap();
+        assert!(other.transactions.is_empty());
+    }
+}
@@ -0,0 +1,418 @@
+use clarity_repl::clarity::codec::StacksMessageCodec;
+use clarity_repl::clarity::util::hash::bytes_to_hex;
+use clarity_repl::clarity::vm::types::QualifiedContractIdentifier;
+use clarity_repl::clarity::vm::{ClarityName, EvaluationResult, SymbolicExpression, Value};
+use clarity_repl::repl::{Session, SessionSettings};
+use serde_json::json;
+
+use crate::replay::{fetch_json, parse_value, BlockResponse};
+
+fn default_step() -> u32 {
+    1
+}
+
+/// Read-only calls of `clarinet query`, as described in a yaml file:
+///
+/// ```yaml
+/// sender: ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM
+/// blocks:
+///   from: 100
+///   to: 200
+///   step: 50
+/// queries:
+///   - name: supply
+///     contract: token
+///     function: get-total-supply
+///   - contract: ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.token
+///     function: get-balance
+///     args: ["'ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM"]
+/// ```
+///
+/// Without `blocks`, the queries are executed once, at the chain tip.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct QueriesSpec {
+    pub sender: Option<String>,
+    pub blocks: Option<BlockRange>,
+    pub queries: Vec<QuerySpec>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BlockRange {
+    pub from: u32,
+    pub to: u32,
+    #[serde(default = "default_step")]
+    pub step: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct QuerySpec {
+    pub name: Option<String>,
+    /// Contract name, deployed by the project, or contract identifier
+    pub contract: String,
+    pub function: String,
+    /// Arguments, as Clarity expressions
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+impl QuerySpec {
+    fn label(&self) -> String {
+        self.name
+            .clone()
+            .unwrap_or(format!("{}::{}", self.contract, self.function))
+    }
+}
+
+impl QueriesSpec {
+    pub fn from_yaml(content: &[u8]) -> Result<QueriesSpec, String> {
+        let spec: QueriesSpec = serde_yaml::from_slice(content)
+            .map_err(|e| format!("unable to parse queries file: {}", e))?;
+        if let Some(ref blocks) = spec.blocks {
+            if blocks.from > blocks.to || blocks.step == 0 {
+                return Err("blocks: `from` must not exceed `to`, `step` must be positive".into());
+            }
+        }
+        Ok(spec)
+    }
+
+    /// Heights the queries are executed at, `None` being the chain tip
+    fn heights(&self) -> Vec<Option<u32>> {
+        match self.blocks {
+            Some(ref blocks) => (blocks.from..=blocks.to)
+                .step_by(blocks.step as usize)
+                .map(Some)
+                .collect(),
+            None => vec![None],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryResult {
+    pub name: String,
+    pub block_height: Option<u32>,
+    /// Clarity representation of the returned value, or the reason of the failure
+    pub result: Result<String, String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueryReport {
+    pub results: Vec<QueryResult>,
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+impl QueryReport {
+    pub fn to_csv(&self) -> String {
+        let mut output = vec!["name,block_height,result,error".to_string()];
+        for result in self.results.iter() {
+            let (value, error) = match &result.result {
+                Ok(value) => (value.as_str(), ""),
+                Err(error) => ("", error.as_str()),
+            };
+            let block_height = result
+                .block_height
+                .map(|height| height.to_string())
+                .unwrap_or_default();
+            output.push(
+                [result.name.as_str(), block_height.as_str(), value, error]
+                    .map(csv_field)
+                    .join(","),
+            );
+        }
+        output.push(String::new());
+        output.join("\n")
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let results: Vec<serde_json::Value> = self
+            .results
+            .iter()
+            .map(|result| match &result.result {
+                Ok(value) => json!({
+                    "name": result.name,
+                    "block_height": result.block_height,
+                    "result": value,
+                }),
+                Err(error) => json!({
+                    "name": result.name,
+                    "block_height": result.block_height,
+                    "error": error,
+                }),
+            })
+            .collect();
+        json!(results)
+    }
+}
+
+fn eval_argument(session: &mut Session, expression: &str) -> Result<Value, String> {
+    let execution = session
+        .eval(expression.to_string(), false)
+        .map_err(|_| format!("argument {} is not a valid Clarity value", expression))?;
+    match execution.result {
+        EvaluationResult::Snippet(result) => Ok(result.result),
+        _ => unreachable!("Contract result from snippet"),
+    }
+}
+
+/// Contracts of the project can be referred to by name
+fn resolve_contract_id(
+    session: &Session,
+    contract: &str,
+) -> Result<QualifiedContractIdentifier, String> {
+    if contract.contains('.') {
+        return QualifiedContractIdentifier::parse(contract)
+            .map_err(|_| format!("unable to parse contract identifier {}", contract));
+    }
+    session
+        .contracts
+        .keys()
+        .find(|contract_id| contract_id.name.as_str() == contract)
+        .cloned()
+        .ok_or(format!("contract {} not found", contract))
+}
+
+fn run_query_on_session(
+    session: &mut Session,
+    query: &QuerySpec,
+    sender: &str,
+) -> Result<String, String> {
+    let contract_id = resolve_contract_id(session, &query.contract)?;
+    let is_read_only = match (
+        session.contracts.get(&contract_id),
+        ClarityName::try_from(query.function.clone()),
+    ) {
+        (Some(contract), Ok(function)) => contract
+            .analysis
+            .read_only_function_types
+            .contains_key(&function),
+        _ => false,
+    };
+    if !is_read_only {
+        return Err(format!(
+            "{} is not a read-only function of {}",
+            query.function, contract_id
+        ));
+    }
+
+    let mut args = vec![];
+    for arg in query.args.iter() {
+        args.push(SymbolicExpression::atom_value(eval_argument(session, arg)?));
+    }
+    let execution = session
+        .call_contract_fn(
+            &contract_id.to_string(),
+            &query.function,
+            &args,
+            sender,
+            false,
+            false,
+        )
+        .map_err(|diagnostics| {
+            let messages: Vec<String> = diagnostics.into_iter().map(|d| d.message).collect();
+            messages.join(" ")
+        })?;
+    match execution.result {
+        EvaluationResult::Snippet(result) => Ok(result.result.to_string()),
+        _ => unreachable!("Contract result from contract call"),
+    }
+}
+
+/// Execute the queries on a copy of `session`. The chain tip of the copy is advanced to
+/// each height of the range, heights that precede the chain tip of `session` fail.
+pub fn run_queries_on_session(session: &Session, spec: &QueriesSpec) -> QueryReport {
+    let mut session = session.clone();
+    let sender = spec.sender.clone().unwrap_or(session.get_tx_sender());
+    let mut report = QueryReport::default();
+    for height in spec.heights() {
+        let current_height = session.interpreter.get_block_height();
+        let block_height = match height {
+            Some(height) if height < current_height => {
+                for query in spec.queries.iter() {
+                    report.results.push(QueryResult {
+                        name: query.label(),
+                        block_height: Some(height),
+                        result: Err(format!(
+                            "block height {} precedes the chain tip of the session ({})",
+                            height, current_height
+                        )),
+                    });
+                }
+                continue;
+            }
+            Some(height) => {
+                if height > current_height {
+                    session.advance_chain_tip(height - current_height);
+                }
+                height
+            }
+            None => current_height,
+        };
+        for query in spec.queries.iter() {
+            report.results.push(QueryResult {
+                name: query.label(),
+                block_height: Some(block_height),
+                result: run_query_on_session(&mut session, query, &sender),
+            });
+        }
+    }
+    report
+}
+
+#[derive(Deserialize, Debug)]
+struct CallReadOnlyResponse {
+    okay: bool,
+    result: Option<String>,
+    cause: Option<String>,
+}
+
+async fn run_query_on_api(
+    api_url: &str,
+    query: &QuerySpec,
+    sender: &Option<String>,
+    tip: &Option<String>,
+    session: &mut Session,
+) -> Result<String, String> {
+    let contract_id = QualifiedContractIdentifier::parse(&query.contract).map_err(|_| {
+        format!(
+            "contract {} must be a contract identifier when querying an api",
+            query.contract
+        )
+    })?;
+    let mut arguments = vec![];
+    for arg in query.args.iter() {
+        let value = eval_argument(session, arg)?;
+        let bytes = value
+            .serialize_to_vec()
+            .map_err(|e| format!("unable to serialize argument {}: {}", arg, e))?;
+        arguments.push(bytes_to_hex(&bytes));
+    }
+    let sender = sender.clone().unwrap_or(contract_id.issuer.to_address());
+
+    let mut request_url = format!(
+        "{}/v2/contracts/call-read/{}/{}/{}",
+        api_url, contract_id.issuer, contract_id.name, query.function
+    );
+    if let Some(tip) = tip {
+        request_url = format!("{}?tip={}", request_url, tip.trim_start_matches("0x"));
+    }
+    let response = reqwest::Client::new()
+        .post(&request_url)
+        .json(&json!({ "sender": sender, "arguments": arguments }))
+        .send()
+        .await
+        .map_err(|e| format!("unable to fetch {}: {}", request_url, e))?;
+    if !response.status().is_success() {
+        return Err(format!(
+            "unable to fetch {}: status {}",
+            request_url,
+            response.status()
+        ));
+    }
+    let response: CallReadOnlyResponse = response
+        .json()
+        .await
+        .map_err(|e| format!("unable to parse response of {}: {}", request_url, e))?;
+    match (response.okay, response.result) {
+        (true, Some(result)) => Ok(parse_value(&result)?.to_string()),
+        _ => Err(response.cause.unwrap_or("call failed".to_string())),
+    }
+}
+
+/// Execute the queries with the read-only endpoint of the Stacks API at `api_url`.
+/// Each height of the range is queried at the state of its index block.
+pub async fn run_queries_on_api(api_url: &str, spec: &QueriesSpec) -> QueryReport {
+    // used to evaluate the arguments
+    let mut session = Session::new(SessionSettings::default());
+    let mut report = QueryReport::default();
+    for height in spec.heights() {
+        let tip = match height {
+            Some(height) => fetch_json::<BlockResponse>(format!(
+                "{}/extended/v1/block/by_height/{}",
+                api_url, height
+            ))
+            .await
+            .map(|block| Some(block.index_block_hash)),
+            None => Ok(None),
+        };
+        for query in spec.queries.iter() {
+            let result = match tip {
+                Ok(ref tip) => {
+                    run_query_on_api(api_url, query, &spec.sender, tip, &mut session).await
+                }
+                Err(ref e) => Err(e.clone()),
+            };
+            report.results.push(QueryResult {
+                name: query.label(),
+                block_height: height,
+                result,
+            });
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clarity_repl::clarity::StacksEpochId;
+
+    #[test]
+    fn test_run_queries_on_session() {
+        let mut session = Session::new(SessionSettings::default());
+        session.update_epoch(StacksEpochId::Epoch25);
+        let snippet = [
+            "(define-read-only (get-height) block-height)",
+            "(define-read-only (get-label (a uint)) (if (> a u0) \"a, b\" \"none\"))",
+            "(define-public (set) (ok true))",
+        ]
+        .join("\n");
+        let contract_id = match session.eval(snippet, false).unwrap().result {
+            EvaluationResult::Contract(evaluation) => {
+                evaluation.contract.analysis.contract_identifier
+            }
+            _ => unreachable!(),
+        };
+
+        let spec = QueriesSpec::from_yaml(
+            format!(
+                r#"
+blocks:
+  from: 10
+  to: 20
+  step: 10
+queries:
+  - name: height
+    contract: {}
+    function: get-height
+  - contract: {}
+    function: get-label
+    args: [u1]
+  - contract: {}
+    function: set
+"#,
+                contract_id, contract_id.name, contract_id.name
+            )
+            .as_bytes(),
+        )
+        .unwrap();
+
+        let report = run_queries_on_session(&session, &spec);
+        assert_eq!(report.results.len(), 6);
+        assert_eq!(report.results[0].result, Ok("u10".to_string()));
+        assert_eq!(report.results[3].result, Ok("u20".to_string()));
+        assert!(report.results[5].result.is_err());
+
+        let csv = report.to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "name,block_height,result,error");
+        assert_eq!(lines[1], "height,10,u10,");
+        assert_eq!(lines[2], "contract-0::get-label,10,\"\"\"a, b\"\"\",");
+    }
+}
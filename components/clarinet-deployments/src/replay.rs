@@ -0,0 +1,939 @@
+use clarinet_files::FileLocation;
+use clarity_repl::analysis::ast_dependency_detector::ASTDependencyDetector;
+use clarity_repl::clarity::chainstate::StacksAddress;
+use clarity_repl::clarity::codec::StacksMessageCodec;
+use clarity_repl::clarity::util::hash::hex_bytes;
+use clarity_repl::clarity::vm::ast::ContractAST;
+use clarity_repl::clarity::vm::contexts::{Environment, LocalContext};
+use clarity_repl::clarity::vm::costs::ExecutionCost;
+use clarity_repl::clarity::vm::errors::Error;
+use clarity_repl::clarity::vm::types::{PrincipalData, QualifiedContractIdentifier};
+use clarity_repl::clarity::vm::{
+    ClarityVersion, EvalHook, EvaluationResult, ExecutionResult, SymbolicExpression, Value,
+};
+use clarity_repl::clarity::Address;
+use clarity_repl::repl::session::BOOT_CONTRACTS_DATA;
+use clarity_repl::repl::{
+    ClarityCodeSource, ClarityContract, ContractDeployer, Session, SessionSettings,
+};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::requirements::{
+    epoch_for_height, resolve_transitive_requirements, retrieve_contract_from,
+};
+
+/// Executions of the transaction used to discover the state it reads, each one can reveal
+/// entries whose keys depend on the entries restored by the previous one.
+const MAX_STATE_DISCOVERY_ROUNDS: usize = 10;
+
+#[derive(Deserialize, Debug)]
+struct TransactionResponse {
+    tx_id: String,
+    tx_type: String,
+    tx_status: String,
+    sender_address: String,
+    block_height: u32,
+    burn_block_height: u32,
+    tx_result: TransactionResultResponse,
+    contract_call: Option<ContractCallResponse>,
+    execution_cost_read_count: u64,
+    execution_cost_read_length: u64,
+    execution_cost_runtime: u64,
+    execution_cost_write_count: u64,
+    execution_cost_write_length: u64,
+}
+
+#[derive(Deserialize, Debug)]
+struct TransactionResultResponse {
+    hex: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct ContractCallResponse {
+    contract_id: String,
+    function_name: String,
+    #[serde(default)]
+    function_args: Vec<FunctionArgResponse>,
+}
+
+#[derive(Deserialize, Debug)]
+struct FunctionArgResponse {
+    hex: String,
+    repr: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct BlockResponse {
+    pub index_block_hash: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct DataVarResponse {
+    data: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct AccountResponse {
+    balance: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct MapEntryResponse {
+    data: String,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct BalancesResponse {
+    #[serde(default)]
+    fungible_tokens: HashMap<String, FungibleTokenBalanceResponse>,
+}
+
+#[derive(Deserialize, Debug)]
+struct FungibleTokenBalanceResponse {
+    balance: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct NftHistoryResponse {
+    results: Vec<NftHistoryEventResponse>,
+}
+
+#[derive(Deserialize, Debug)]
+struct NftHistoryEventResponse {
+    recipient: Option<String>,
+    tx: NftHistoryTransactionResponse,
+}
+
+#[derive(Deserialize, Debug)]
+struct NftHistoryTransactionResponse {
+    block_height: u32,
+}
+
+/// A contract call, as executed on-chain.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OnChainContractCall {
+    pub tx_id: String,
+    pub sender: String,
+    pub contract_id: QualifiedContractIdentifier,
+    pub function_name: String,
+    pub function_args: Vec<Value>,
+    /// Clarity representation of the arguments, as returned by the API
+    pub function_args_repr: Vec<String>,
+    pub block_height: u32,
+    pub burn_block_height: u32,
+    /// Index block hash of the parent block, the state the transaction was executed against
+    pub parent_index_block_hash: String,
+    pub status: String,
+    pub result: Value,
+    pub cost: ExecutionCost,
+}
+
+impl OnChainContractCall {
+    pub fn is_mainnet(&self) -> bool {
+        StacksAddress::from_string(&self.sender)
+            .map(|address| address.is_mainnet())
+            .unwrap_or(true)
+    }
+
+    /// `contract-call?` expression of the transaction, used to step through it with the debugger.
+    pub fn to_snippet(&self) -> String {
+        let mut snippet = format!(
+            "(contract-call? '{} {}",
+            self.contract_id, self.function_name
+        );
+        for arg in self.function_args_repr.iter() {
+            snippet.push(' ');
+            snippet.push_str(arg);
+        }
+        snippet.push(')');
+        snippet
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayReport {
+    pub result: Option<Value>,
+    pub cost: Option<ExecutionCost>,
+    pub divergences: Vec<String>,
+}
+
+pub(crate) fn parse_value(hex: &str) -> Result<Value, String> {
+    let bytes = hex_bytes(hex.trim_start_matches("0x"))
+        .map_err(|e| format!("unable to decode value {}: {}", hex, e))?;
+    Value::consensus_deserialize(&mut &bytes[..])
+        .map_err(|e| format!("unable to parse value {}: {}", hex, e))
+}
+
+pub(crate) async fn fetch_json<T: serde::de::DeserializeOwned>(
+    request_url: String,
+) -> Result<T, String> {
+    let response = reqwest::get(&request_url)
+        .await
+        .map_err(|e| format!("unable to fetch {}: {}", request_url, e))?;
+    if !response.status().is_success() {
+        return Err(format!(
+            "unable to fetch {}: status {}",
+            request_url,
+            response.status()
+        ));
+    }
+    response
+        .json()
+        .await
+        .map_err(|e| format!("unable to parse response of {}: {}", request_url, e))
+}
+
+/// Retrieve a contract call transaction, and the index block hash of its parent block.
+pub async fn fetch_contract_call(api_url: &str, txid: &str) -> Result<OnChainContractCall, String> {
+    let txid = format!("0x{}", txid.trim_start_matches("0x"));
+    let tx: TransactionResponse =
+        fetch_json(format!("{}/extended/v1/tx/{}", api_url, txid)).await?;
+    let Some(contract_call) = tx.contract_call else {
+        return Err(format!(
+            "transaction {} is a {}, only contract calls can be replayed",
+            tx.tx_id, tx.tx_type
+        ));
+    };
+    if tx.block_height == 0 {
+        return Err(format!("transaction {} is not anchored yet", tx.tx_id));
+    }
+
+    let contract_id = QualifiedContractIdentifier::parse(&contract_call.contract_id)
+        .map_err(|e| format!("invalid contract id {}: {}", contract_call.contract_id, e))?;
+    let mut function_args = vec![];
+    let mut function_args_repr = vec![];
+    for arg in contract_call.function_args.into_iter() {
+        function_args.push(parse_value(&arg.hex)?);
+        function_args_repr.push(arg.repr);
+    }
+    let parent_block: BlockResponse = fetch_json(format!(
+        "{}/extended/v1/block/by_height/{}",
+        api_url,
+        tx.block_height - 1
+    ))
+    .await?;
+
+    Ok(OnChainContractCall {
+        tx_id: tx.tx_id,
+        sender: tx.sender_address,
+        contract_id,
+        function_name: contract_call.function_name,
+        function_args,
+        function_args_repr,
+        block_height: tx.block_height,
+        burn_block_height: tx.burn_block_height,
+        parent_index_block_hash: parent_block
+            .index_block_hash
+            .trim_start_matches("0x")
+            .to_string(),
+        status: tx.tx_status,
+        result: parse_value(&tx.tx_result.hex)?,
+        cost: ExecutionCost {
+            write_length: tx.execution_cost_write_length,
+            write_count: tx.execution_cost_write_count,
+            read_length: tx.execution_cost_read_length,
+            read_count: tx.execution_cost_read_count,
+            runtime: tx.execution_cost_runtime,
+        },
+    })
+}
+
+async fn post_json<T: serde::de::DeserializeOwned>(
+    request_url: String,
+    body: &serde_json::Value,
+) -> Result<T, String> {
+    let response = reqwest::Client::new()
+        .post(&request_url)
+        .json(body)
+        .send()
+        .await
+        .map_err(|e| format!("unable to fetch {}: {}", request_url, e))?;
+    if !response.status().is_success() {
+        return Err(format!(
+            "unable to fetch {}: status {}",
+            request_url,
+            response.status()
+        ));
+    }
+    response
+        .json()
+        .await
+        .map_err(|e| format!("unable to parse response of {}: {}", request_url, e))
+}
+
+/// Part of the state of a contract read or written by a transaction
+#[derive(Debug, Clone, PartialEq)]
+pub enum StateAccess {
+    MapEntry {
+        contract_id: QualifiedContractIdentifier,
+        map_name: String,
+        key: Value,
+    },
+    FungibleTokenBalance {
+        contract_id: QualifiedContractIdentifier,
+        token_name: String,
+        owner: PrincipalData,
+    },
+    NonFungibleTokenOwner {
+        contract_id: QualifiedContractIdentifier,
+        asset_name: String,
+        asset: Value,
+    },
+}
+
+enum PendingAccess {
+    MapKey(String),
+    TokenOwner(String),
+    Asset(String),
+}
+
+/// Records the map keys, token owners and assets evaluated as arguments of the map and
+/// token functions.
+#[derive(Default)]
+struct StateAccessRecorder {
+    pending: HashMap<(QualifiedContractIdentifier, u64), PendingAccess>,
+    accesses: Vec<StateAccess>,
+}
+
+impl EvalHook for StateAccessRecorder {
+    fn will_begin_eval(
+        &mut self,
+        env: &mut Environment,
+        _context: &LocalContext,
+        expr: &SymbolicExpression,
+    ) {
+        let Some((function, args)) = expr.match_list().and_then(|list| list.split_first()) else {
+            return;
+        };
+        let (Some(function), Some(name)) = (
+            function.match_atom(),
+            args.first().and_then(|arg| arg.match_atom()),
+        ) else {
+            return;
+        };
+        let name = name.to_string();
+        let accessed = match function.as_str() {
+            "map-get?" | "map-set" | "map-insert" | "map-delete" => {
+                vec![(1, PendingAccess::MapKey(name))]
+            }
+            "ft-get-balance" => vec![(1, PendingAccess::TokenOwner(name))],
+            "ft-transfer?" => vec![
+                (2, PendingAccess::TokenOwner(name.clone())),
+                (3, PendingAccess::TokenOwner(name)),
+            ],
+            "ft-mint?" | "ft-burn?" => vec![(2, PendingAccess::TokenOwner(name))],
+            "nft-get-owner?" | "nft-transfer?" | "nft-mint?" | "nft-burn?" => {
+                vec![(1, PendingAccess::Asset(name))]
+            }
+            _ => return,
+        };
+        let contract_id = &env.contract_context.contract_identifier;
+        for (position, access) in accessed.into_iter() {
+            if let Some(arg) = args.get(position) {
+                self.pending.insert((contract_id.clone(), arg.id), access);
+            }
+        }
+    }
+
+    fn did_finish_eval(
+        &mut self,
+        env: &mut Environment,
+        _context: &LocalContext,
+        expr: &SymbolicExpression,
+        res: &Result<Value, Error>,
+    ) {
+        let contract_id = env.contract_context.contract_identifier.clone();
+        let Some(access) = self.pending.remove(&(contract_id.clone(), expr.id)) else {
+            return;
+        };
+        let Ok(value) = res else {
+            return;
+        };
+        let access = match (access, value) {
+            (PendingAccess::MapKey(map_name), key) => StateAccess::MapEntry {
+                contract_id,
+                map_name,
+                key: key.clone(),
+            },
+            (PendingAccess::TokenOwner(token_name), Value::Principal(owner)) => {
+                StateAccess::FungibleTokenBalance {
+                    contract_id,
+                    token_name,
+                    owner: owner.clone(),
+                }
+            }
+            (PendingAccess::Asset(asset_name), asset) => StateAccess::NonFungibleTokenOwner {
+                contract_id,
+                asset_name,
+                asset: asset.clone(),
+            },
+            _ => return,
+        };
+        if !self.accesses.contains(&access) {
+            self.accesses.push(access);
+        }
+    }
+
+    fn did_complete(&mut self, _result: Result<&mut ExecutionResult, String>) {}
+}
+
+/// Execute the transaction in `session`, and return the state it accessed. The state of
+/// the session is left untouched.
+pub fn record_state_accesses(
+    session: &mut Session,
+    tx: &OnChainContractCall,
+) -> Result<Vec<StateAccess>, String> {
+    let sender = PrincipalData::parse_standard_principal(&tx.sender)
+        .map_err(|e| format!("invalid sender {}: {}", tx.sender, e))?;
+    let args: Vec<SymbolicExpression> = tx
+        .function_args
+        .iter()
+        .map(|arg| SymbolicExpression::atom_value(arg.clone()))
+        .collect();
+    let state = session.interpreter.save_state();
+    let mut recorder = StateAccessRecorder::default();
+    session.interpreter.set_tx_sender(sender);
+    // failures are reported by the replay itself
+    let _ = session.interpreter.call_contract_fn(
+        &tx.contract_id,
+        &tx.function_name,
+        &args,
+        session.current_epoch,
+        ClarityVersion::default_for_epoch(session.current_epoch),
+        false,
+        false,
+        vec![&mut recorder],
+    );
+    session.interpreter.restore_state(&state)?;
+    Ok(recorder.accesses)
+}
+
+/// Restore `access` as it was at the parent block of the transaction. The balances fetched
+/// are cached by owner, in `balances`.
+async fn restore_state_access(
+    api_url: &str,
+    tx: &OnChainContractCall,
+    session: &mut Session,
+    access: &StateAccess,
+    balances: &mut HashMap<String, BalancesResponse>,
+) -> Result<(), String> {
+    match access {
+        StateAccess::MapEntry {
+            contract_id,
+            map_name,
+            key,
+        } => {
+            let key_hex = key
+                .serialize_to_hex()
+                .map_err(|e| format!("unable to serialize {}: {}", key, e))?;
+            let request_url = format!(
+                "{}/v2/map_entry/{}/{}/{}?proof=0&tip={}",
+                api_url, contract_id.issuer, contract_id.name, map_name, tx.parent_index_block_hash
+            );
+            let response: MapEntryResponse =
+                post_json(request_url, &serde_json::json!(format!("0x{}", key_hex))).await?;
+            match parse_value(&response.data)? {
+                Value::Optional(entry) => match entry.data {
+                    Some(value) => session.interpreter.set_map_entry(
+                        contract_id,
+                        map_name,
+                        key.clone(),
+                        *value,
+                    ),
+                    None => Ok(()),
+                },
+                value => Err(format!("unexpected map entry {}", value)),
+            }
+        }
+        StateAccess::FungibleTokenBalance {
+            contract_id,
+            token_name,
+            owner,
+        } => {
+            let owner_balances = match balances.entry(owner.to_string()) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    let request_url = format!(
+                        "{}/extended/v1/address/{}/balances?until_block={}",
+                        api_url,
+                        owner,
+                        tx.block_height - 1
+                    );
+                    entry.insert(fetch_json(request_url).await?)
+                }
+            };
+            let asset_identifier = format!("{}::{}", contract_id, token_name);
+            let Some(balance) = owner_balances.fungible_tokens.get(&asset_identifier) else {
+                return Ok(());
+            };
+            let balance = balance.balance.parse::<u128>().map_err(|e| {
+                format!(
+                    "unable to parse {} balance of {}: {}",
+                    asset_identifier, owner, e
+                )
+            })?;
+            session
+                .interpreter
+                .set_ft_balance(contract_id, token_name, owner, balance)
+        }
+        StateAccess::NonFungibleTokenOwner {
+            contract_id,
+            asset_name,
+            asset,
+        } => {
+            let asset_hex = asset
+                .serialize_to_hex()
+                .map_err(|e| format!("unable to serialize {}: {}", asset, e))?;
+            let request_url = format!(
+                "{}/extended/v1/tokens/nft/history?asset_identifier={}::{}&value=0x{}&tx_metadata=true&limit=50",
+                api_url, contract_id, asset_name, asset_hex
+            );
+            let history: NftHistoryResponse = fetch_json(request_url).await?;
+            // events are sorted from the most recent one
+            let owner = history
+                .results
+                .into_iter()
+                .find(|event| event.tx.block_height < tx.block_height)
+                .and_then(|event| event.recipient);
+            let Some(owner) = owner else {
+                return Ok(());
+            };
+            let owner = PrincipalData::parse(&owner)
+                .map_err(|e| format!("invalid owner {}: {}", owner, e))?;
+            session
+                .interpreter
+                .set_nft_owner(contract_id, asset_name, asset, &owner)
+        }
+    }
+}
+
+/// Move the chain tip of the session to the block of the transaction. Returns a warning
+/// when the local chain is already past one of its heights.
+fn jump_to_transaction_block(
+    session: &mut Session,
+    tx: &OnChainContractCall,
+) -> Result<Option<String>, String> {
+    let block_height = session.interpreter.get_block_height();
+    if tx.block_height <= block_height {
+        return Ok(Some(format!(
+            "block-height is {} locally, {} on-chain",
+            block_height, tx.block_height
+        )));
+    }
+    let burn_block_height = std::cmp::max(
+        tx.burn_block_height,
+        session.interpreter.get_burn_block_height(),
+    );
+    session
+        .interpreter
+        .jump_to_chain_tip(burn_block_height, tx.block_height)?;
+    match burn_block_height == tx.burn_block_height {
+        true => Ok(None),
+        false => Ok(Some(format!(
+            "burn-block-height is {} locally, {} on-chain",
+            burn_block_height, tx.burn_block_height
+        ))),
+    }
+}
+
+fn data_vars_names(ast: &ContractAST) -> Vec<String> {
+    ast.expressions
+        .iter()
+        .filter_map(|expr| {
+            let list = expr.match_list()?;
+            if list.first()?.match_atom()?.as_str() != "define-data-var" {
+                return None;
+            }
+            Some(list.get(1)?.match_atom()?.to_string())
+        })
+        .collect()
+}
+
+/// Deploy the called contract and its dependencies in a new session, then restore the
+/// data vars of these contracts, the STX balance of the sender, and the map entries and
+/// token balances accessed by the transaction, as they were at the parent block. The chain
+/// tip is moved to the block of the transaction. Returns the session along with warnings
+/// about the state that could not be restored.
+pub async fn setup_replay_session(
+    api_url: &str,
+    tx: &OnChainContractCall,
+    cache_location: &FileLocation,
+) -> Result<(Session, Vec<String>), String> {
+    let mut warnings = vec![];
+    let mut session = Session::new(SessionSettings::default());
+    for (_, (boot_contract, ast)) in BOOT_CONTRACTS_DATA.clone() {
+        let _ = session
+            .interpreter
+            .run(&boot_contract, Some(&ast), false, None);
+    }
+
+    let contracts_ids =
+        resolve_transitive_requirements(&tx.contract_id, cache_location, &None, Some(api_url))
+            .await?;
+    let mut contracts = BTreeMap::new();
+    let mut contracts_asts = BTreeMap::new();
+    let mut contract_epochs = HashMap::new();
+    for contract_id in contracts_ids.into_iter() {
+        let (source, epoch, clarity_version, _) =
+            retrieve_contract_from(&contract_id, cache_location, &None, Some(api_url)).await?;
+        let contract = ClarityContract {
+            code_source: ClarityCodeSource::ContractInMemory(source),
+            name: contract_id.name.to_string(),
+            deployer: ContractDeployer::ContractIdentifier(contract_id.clone()),
+            clarity_version,
+            epoch,
+        };
+        let (ast, _, _) = session.interpreter.build_ast(&contract);
+        contracts_asts.insert(contract_id.clone(), (clarity_version, ast));
+        contract_epochs.insert(contract_id.clone(), epoch);
+        contracts.insert(contract_id, contract);
+    }
+
+    let boot_contracts_data = BOOT_CONTRACTS_DATA
+        .iter()
+        .map(|(id, (contract, ast))| (id.clone(), (contract.clarity_version, ast.clone())))
+        .collect();
+    let dependencies =
+        ASTDependencyDetector::detect_dependencies(&contracts_asts, &boot_contracts_data)
+            .map_err(|_| format!("unable to resolve dependencies of {}", tx.contract_id))?;
+    let ordered_contracts_ids =
+        ASTDependencyDetector::order_contracts(&dependencies, &contract_epochs)
+            .map_err(|e| format!("unable to order contracts: {}", e.err))?;
+
+    for contract_id in ordered_contracts_ids.into_iter() {
+        let Some(contract) = contracts.get(contract_id) else {
+            continue;
+        };
+        let ast = contracts_asts.get(contract_id).map(|(_, ast)| ast);
+        session.update_epoch(contract.epoch);
+        if let Err(diagnostics) = session.deploy_contract(contract, false, ast) {
+            let messages: Vec<String> = diagnostics.into_iter().map(|d| d.message).collect();
+            return Err(format!(
+                "unable to deploy {}: {}",
+                contract_id,
+                messages.join(", ")
+            ));
+        }
+
+        for var_name in ast.map(data_vars_names).unwrap_or_default().iter() {
+            let request_url = format!(
+                "{}/v2/data_var/{}/{}/{}?proof=0&tip={}",
+                api_url, contract_id.issuer, contract_id.name, var_name, tx.parent_index_block_hash
+            );
+            let value = match fetch_json::<DataVarResponse>(request_url).await {
+                Ok(response) => parse_value(&response.data)?,
+                Err(message) => {
+                    warnings.push(message);
+                    continue;
+                }
+            };
+            if let Err(message) = session
+                .interpreter
+                .set_data_var(contract_id, var_name, value)
+            {
+                warnings.push(message);
+            }
+        }
+    }
+
+    let request_url = format!(
+        "{}/v2/accounts/{}?proof=0&tip={}",
+        api_url, tx.sender, tx.parent_index_block_hash
+    );
+    let account: AccountResponse = fetch_json(request_url).await?;
+    let balance = u128::from_str_radix(account.balance.trim_start_matches("0x"), 16)
+        .map_err(|e| format!("unable to parse balance of {}: {}", tx.sender, e))?;
+    let sender = PrincipalData::parse(&tx.sender)
+        .map_err(|e| format!("invalid sender {}: {}", tx.sender, e))?;
+    session
+        .interpreter
+        .mint_stx_balance(sender, u64::try_from(balance).unwrap_or(u64::MAX))?;
+
+    session.update_epoch(epoch_for_height(tx.is_mainnet(), tx.block_height));
+    if let Some(warning) = jump_to_transaction_block(&mut session, tx)? {
+        warnings.push(warning);
+    }
+
+    let mut restored = vec![];
+    let mut balances = HashMap::new();
+    let mut complete = false;
+    for _ in 0..MAX_STATE_DISCOVERY_ROUNDS {
+        let accesses: Vec<StateAccess> = record_state_accesses(&mut session, tx)?
+            .into_iter()
+            .filter(|access| !restored.contains(access))
+            .collect();
+        if accesses.is_empty() {
+            complete = true;
+            break;
+        }
+        for access in accesses.into_iter() {
+            if let Err(message) =
+                restore_state_access(api_url, tx, &mut session, &access, &mut balances).await
+            {
+                warnings.push(message);
+            }
+            restored.push(access);
+        }
+    }
+    if !complete {
+        warnings.push(format!(
+            "the state accessed by the transaction may not be fully restored after {} executions",
+            MAX_STATE_DISCOVERY_ROUNDS
+        ));
+    }
+    if restored
+        .iter()
+        .any(|access| matches!(access, StateAccess::FungibleTokenBalance { .. }))
+    {
+        warnings.push("token supplies are not restored from the parent block".to_string());
+    }
+    Ok((session, warnings))
+}
+
+fn cost_divergences(local: &ExecutionCost, on_chain: &ExecutionCost) -> Vec<String> {
+    [
+        ("runtime", local.runtime, on_chain.runtime),
+        ("read_count", local.read_count, on_chain.read_count),
+        ("read_length", local.read_length, on_chain.read_length),
+        ("write_count", local.write_count, on_chain.write_count),
+        ("write_length", local.write_length, on_chain.write_length),
+    ]
+    .into_iter()
+    .filter(|(_, local, on_chain)| local != on_chain)
+    .map(|(dimension, local, on_chain)| {
+        format!(
+            "cost {}: {} locally, {} on-chain",
+            dimension, local, on_chain
+        )
+    })
+    .collect()
+}
+
+/// Execute the contract call in the session, and compare the outcome with the on-chain one.
+pub fn replay_contract_call(session: &mut Session, tx: &OnChainContractCall) -> ReplayReport {
+    let args: Vec<SymbolicExpression> = tx
+        .function_args
+        .iter()
+        .map(|arg| SymbolicExpression::atom_value(arg.clone()))
+        .collect();
+    let execution = session.call_contract_fn(
+        &tx.contract_id.to_string(),
+        &tx.function_name,
+        &args,
+        &tx.sender,
+        false,
+        true,
+    );
+
+    let mut divergences = vec![];
+    let execution = match execution {
+        Ok(execution) => execution,
+        Err(diagnostics) => {
+            let messages: Vec<String> = diagnostics.into_iter().map(|d| d.message).collect();
+            divergences.push(format!(
+                "status: runtime error locally ({}), {} on-chain",
+                messages.join(", "),
+                tx.status
+            ));
+            return ReplayReport {
+                result: None,
+                cost: None,
+                divergences,
+            };
+        }
+    };
+
+    let result = match execution.result {
+        EvaluationResult::Snippet(snippet_result) => snippet_result.result,
+        EvaluationResult::Contract(_) => unreachable!(),
+    };
+    let local_status = match result {
+        Value::Response(ref response) if !response.committed => "abort_by_response",
+        _ => "success",
+    };
+    if tx.status == "abort_by_post_condition" {
+        divergences.push("status: post-conditions are not checked locally".to_string());
+    } else if local_status != tx.status {
+        divergences.push(format!(
+            "status: {} locally, {} on-chain",
+            local_status, tx.status
+        ));
+    }
+    if result != tx.result {
+        divergences.push(format!(
+            "result: {} locally, {} on-chain",
+            result, tx.result
+        ));
+    }
+    let cost = execution.cost.map(|cost| cost.total);
+    if let Some(ref cost) = cost {
+        divergences.append(&mut cost_divergences(cost, &tx.cost));
+    }
+
+    ReplayReport {
+        result: Some(result),
+        cost,
+        divergences,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clarity_repl::repl::{DEFAULT_CLARITY_VERSION, DEFAULT_EPOCH};
+
+    const SENDER: &str = "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM";
+
+    fn contract_call(result: Value, status: &str) -> OnChainContractCall {
+        OnChainContractCall {
+            tx_id: "0x00".to_string(),
+            sender: SENDER.to_string(),
+            contract_id: QualifiedContractIdentifier::parse(&format!("{}.counter", SENDER))
+                .unwrap(),
+            function_name: "increment".to_string(),
+            function_args: vec![Value::UInt(2)],
+            function_args_repr: vec!["u2".to_string()],
+            block_height: 100,
+            burn_block_height: 100,
+            parent_index_block_hash: "00".to_string(),
+            status: status.to_string(),
+            result,
+            cost: ExecutionCost::zero(),
+        }
+    }
+
+    fn setup_session() -> Session {
+        let mut session = Session::new(SessionSettings::default());
+        session.update_epoch(DEFAULT_EPOCH);
+        let contract = ClarityContract {
+            code_source: ClarityCodeSource::ContractInMemory(
+                "(define-data-var counter uint u0)
+                (define-public (increment (step uint))
+                    (begin (var-set counter (+ (var-get counter) step)) (ok (var-get counter))))"
+                    .to_string(),
+            ),
+            name: "counter".to_string(),
+            deployer: ContractDeployer::Address(SENDER.to_string()),
+            clarity_version: DEFAULT_CLARITY_VERSION,
+            epoch: DEFAULT_EPOCH,
+        };
+        session.deploy_contract(&contract, false, None).unwrap();
+        session
+    }
+
+    #[test]
+    fn test_replay_contract_call() {
+        let mut session = setup_session();
+        let tx = contract_call(Value::okay(Value::UInt(12)).unwrap(), "success");
+        session
+            .interpreter
+            .set_data_var(&tx.contract_id, "counter", Value::UInt(10))
+            .unwrap();
+
+        let report = replay_contract_call(&mut session, &tx);
+        assert_eq!(report.result, Some(Value::okay(Value::UInt(12)).unwrap()));
+        assert!(report
+            .divergences
+            .iter()
+            .all(|divergence| divergence.starts_with("cost")));
+    }
+
+    #[test]
+    fn test_replay_contract_call_divergence() {
+        let mut session = setup_session();
+        let tx = contract_call(Value::okay(Value::UInt(12)).unwrap(), "success");
+
+        let report = replay_contract_call(&mut session, &tx);
+        assert_eq!(report.result, Some(Value::okay(Value::UInt(2)).unwrap()));
+        assert!(report
+            .divergences
+            .contains(&"result: (ok u2) locally, (ok u12) on-chain".to_string()));
+    }
+
+    #[test]
+    fn test_record_state_accesses() {
+        let mut session = setup_session();
+        let contract = ClarityContract {
+            code_source: ClarityCodeSource::ContractInMemory(
+                "(define-map claims principal uint)
+                (define-fungible-token gold)
+                (define-non-fungible-token badge uint)
+                (define-public (claim (id uint))
+                    (begin
+                        (map-set claims tx-sender (+ (default-to u0 (map-get? claims tx-sender)) u1))
+                        (try! (ft-mint? gold u10 tx-sender))
+                        (nft-mint? badge id tx-sender)))"
+                    .to_string(),
+            ),
+            name: "claims".to_string(),
+            deployer: ContractDeployer::Address(SENDER.to_string()),
+            clarity_version: DEFAULT_CLARITY_VERSION,
+            epoch: DEFAULT_EPOCH,
+        };
+        session.deploy_contract(&contract, false, None).unwrap();
+        let contract_id =
+            QualifiedContractIdentifier::parse(&format!("{}.claims", SENDER)).unwrap();
+        let tx = OnChainContractCall {
+            contract_id: contract_id.clone(),
+            function_name: "claim".to_string(),
+            function_args_repr: vec!["u2".to_string()],
+            ..contract_call(Value::okay_true(), "success")
+        };
+
+        let accesses = record_state_accesses(&mut session, &tx).unwrap();
+        let sender = PrincipalData::parse(SENDER).unwrap();
+        assert_eq!(
+            accesses,
+            vec![
+                StateAccess::MapEntry {
+                    contract_id: contract_id.clone(),
+                    map_name: "claims".to_string(),
+                    key: Value::Principal(sender.clone()),
+                },
+                StateAccess::FungibleTokenBalance {
+                    contract_id: contract_id.clone(),
+                    token_name: "gold".to_string(),
+                    owner: sender.clone(),
+                },
+                StateAccess::NonFungibleTokenOwner {
+                    contract_id: contract_id.clone(),
+                    asset_name: "badge".to_string(),
+                    asset: Value::UInt(2),
+                },
+            ]
+        );
+        // the execution is rolled back
+        assert_eq!(
+            session
+                .interpreter
+                .get_map_entry(&contract_id, "claims", &Value::Principal(sender)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_jump_to_transaction_block() {
+        let mut session = setup_session();
+        let tx = OnChainContractCall {
+            block_height: 150_000,
+            burn_block_height: 840_000,
+            ..contract_call(Value::none(), "success")
+        };
+        assert_eq!(jump_to_transaction_block(&mut session, &tx), Ok(None));
+        assert_eq!(session.interpreter.get_block_height(), 150_000);
+        assert_eq!(session.interpreter.get_burn_block_height(), 840_000);
+        assert!(jump_to_transaction_block(&mut session, &tx)
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn test_to_snippet() {
+        let tx = contract_call(Value::none(), "success");
+        assert_eq!(
+            tx.to_snippet(),
+            format!("(contract-call? '{} increment u2)", tx.contract_id)
+        );
+    }
+}
@@ -1,12 +1,17 @@
 use clarinet_files::{FileAccessor, FileLocation};
 use clarity_repl::{
+    analysis::ast_dependency_detector::ASTDependencyDetector,
     clarity::{
         chainstate::StacksAddress, vm::types::QualifiedContractIdentifier, Address, ClarityVersion,
         StacksEpochId,
     },
-    repl::{DEFAULT_CLARITY_VERSION, DEFAULT_EPOCH},
+    repl::{
+        session::BOOT_CONTRACTS_DATA, ClarityCodeSource, ClarityContract, ContractDeployer,
+        Session, SessionSettings, DEFAULT_CLARITY_VERSION, DEFAULT_EPOCH,
+    },
 };
 use reqwest;
+use std::collections::{BTreeMap, VecDeque};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContractMetadata {
@@ -27,6 +32,17 @@ pub async fn retrieve_contract(
     contract_id: &QualifiedContractIdentifier,
     cache_location: &FileLocation,
     file_accessor: &Option<&dyn FileAccessor>,
+) -> Result<(String, StacksEpochId, ClarityVersion, FileLocation), String> {
+    retrieve_contract_from(contract_id, cache_location, file_accessor, None).await
+}
+
+/// Same as `retrieve_contract`, a contract missing from the cache is fetched from `api_url`
+/// when set, instead of the default Stacks APIs.
+pub async fn retrieve_contract_from(
+    contract_id: &QualifiedContractIdentifier,
+    cache_location: &FileLocation,
+    file_accessor: &Option<&dyn FileAccessor>,
+    api_url: Option<&str>,
 ) -> Result<(String, StacksEpochId, ClarityVersion, FileLocation), String> {
     let contract_deployer = contract_id.issuer.to_address();
     let contract_name = contract_id.name.to_string();
@@ -63,20 +79,28 @@ pub async fn retrieve_contract(
     let is_mainnet = StacksAddress::from_string(&contract_deployer)
         .unwrap()
         .is_mainnet();
-    let stacks_node_addr = if is_mainnet {
-        "https://api.hiro.so".to_string()
-    } else {
-        "https://api.testnet.hiro.so".to_string()
+    let mut errors = vec![];
+    let mut fetched = None;
+    let api_urls = match api_url {
+        Some(api_url) => vec![api_url.to_string()],
+        None => requirements_api_urls(is_mainnet),
     };
-
-    let request_url = format!(
-        "{host}/v2/contracts/source/{addr}/{name}?proof=0",
-        host = stacks_node_addr,
-        addr = contract_deployer,
-        name = contract_name
-    );
-
-    let contract = fetch_contract(request_url).await?;
+    for stacks_node_addr in api_urls {
+        let request_url = format!(
+            "{host}/v2/contracts/source/{addr}/{name}?proof=0",
+            host = stacks_node_addr.trim_end_matches('/'),
+            addr = contract_deployer,
+            name = contract_name
+        );
+        match fetch_contract(request_url).await {
+            Ok(contract) => {
+                fetched = Some(contract);
+                break;
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+    let contract = fetched.ok_or_else(|| errors.join(", "))?;
     let epoch = epoch_for_height(is_mainnet, contract.publish_height);
     let clarity_version = match contract.clarity_version {
         Some(1) => ClarityVersion::Clarity1,
@@ -121,6 +145,82 @@ pub async fn retrieve_contract(
     Ok((contract.source, epoch, clarity_version, contract_location))
 }
 
+/// Retrieve `contract_id` and the contracts it references, transitively (boot contracts
+/// excluded). The contracts are returned in the order they were discovered, starting
+/// with `contract_id`. The contracts missing from the cache are fetched from `api_url` when set.
+pub async fn resolve_transitive_requirements(
+    contract_id: &QualifiedContractIdentifier,
+    cache_location: &FileLocation,
+    file_accessor: &Option<&dyn FileAccessor>,
+    api_url: Option<&str>,
+) -> Result<Vec<QualifiedContractIdentifier>, String> {
+    let mut session = Session::new(SessionSettings::default());
+    let mut contracts_data = BTreeMap::new();
+    for (id, (contract, ast)) in BOOT_CONTRACTS_DATA.clone() {
+        contracts_data.insert(id, (contract.clarity_version, ast));
+    }
+
+    let mut resolved = vec![];
+    let mut queue = VecDeque::from([contract_id.clone()]);
+    while let Some(contract_id) = queue.pop_front() {
+        if resolved.contains(&contract_id) {
+            continue;
+        }
+        let (clarity_version, ast) = match contracts_data.remove(&contract_id) {
+            Some(data) => data,
+            None => {
+                let (source, epoch, clarity_version, _) =
+                    retrieve_contract_from(&contract_id, cache_location, file_accessor, api_url)
+                        .await?;
+                let contract = ClarityContract {
+                    code_source: ClarityCodeSource::ContractInMemory(source),
+                    name: contract_id.name.to_string(),
+                    deployer: ContractDeployer::ContractIdentifier(contract_id.clone()),
+                    clarity_version,
+                    epoch,
+                };
+                let (ast, _, _) = session.interpreter.build_ast(&contract);
+                (clarity_version, ast)
+            }
+        };
+
+        let mut contract_data = BTreeMap::new();
+        contract_data.insert(contract_id.clone(), (clarity_version, ast));
+        let dependencies =
+            ASTDependencyDetector::detect_dependencies(&contract_data, &contracts_data)
+                .map(|mut dependencies| dependencies.remove(&contract_id).unwrap_or_default());
+        let data = contract_data
+            .remove(&contract_id)
+            .expect("unable to retrieve ast");
+        contracts_data.insert(contract_id.clone(), data);
+
+        match dependencies {
+            Ok(dependencies) => {
+                for dependency in dependencies.iter() {
+                    queue.push_back(dependency.contract_id.clone());
+                }
+                resolved.push(contract_id);
+            }
+            Err((_, non_inferable_dependencies)) => {
+                if non_inferable_dependencies
+                    .iter()
+                    .all(|id| contracts_data.contains_key(id))
+                {
+                    return Err(format!("unable to resolve dependencies of {}", contract_id));
+                }
+                // Retrieve the unknown contracts first, then retry
+                queue.push_front(contract_id);
+                for non_inferable_contract_id in non_inferable_dependencies.into_iter() {
+                    queue.push_front(non_inferable_contract_id);
+                }
+            }
+        }
+    }
+
+    resolved.retain(|contract_id| !BOOT_CONTRACTS_DATA.contains_key(contract_id));
+    Ok(resolved)
+}
+
 pub const MAINNET_20_START_HEIGHT: u32 = 1;
 pub const MAINNET_2_05_START_HEIGHT: u32 = 40_607;
 pub const MAINNET_21_START_HEIGHT: u32 = 99_113;
@@ -139,7 +239,7 @@ pub const TESTNET_24_START_HEIGHT: u32 = 5;
 pub const TESTNET_25_START_HEIGHT: u32 = 6;
 pub const TESTNET_30_START_HEIGHT: u32 = 45_560;
 
-fn epoch_for_height(is_mainnet: bool, height: u32) -> StacksEpochId {
+pub(crate) fn epoch_for_height(is_mainnet: bool, height: u32) -> StacksEpochId {
     if is_mainnet {
         epoch_for_mainnet_height(height)
     } else {
@@ -187,6 +287,35 @@ fn epoch_for_testnet_height(height: u32) -> StacksEpochId {
     }
 }
 
+const MAINNET_API_URLS_ENV: &str = "CLARINET_MAINNET_API_URLS";
+const TESTNET_API_URLS_ENV: &str = "CLARINET_TESTNET_API_URLS";
+
+/// Stacks APIs the requirements are fetched from, tried in order until one answers. The
+/// defaults are replaced by the comma separated urls of `CLARINET_MAINNET_API_URLS` or
+/// `CLARINET_TESTNET_API_URLS`, so that the console and deployments keep working when an
+/// API is down.
+fn requirements_api_urls(is_mainnet: bool) -> Vec<String> {
+    let (env_var, defaults) = match is_mainnet {
+        true => (
+            MAINNET_API_URLS_ENV,
+            vec!["https://api.hiro.so", "https://api.mainnet.hiro.so"],
+        ),
+        false => (TESTNET_API_URLS_ENV, vec!["https://api.testnet.hiro.so"]),
+    };
+    parse_api_urls(std::env::var(env_var).ok().as_deref())
+        .unwrap_or_else(|| defaults.into_iter().map(String::from).collect())
+}
+
+fn parse_api_urls(value: Option<&str>) -> Option<Vec<String>> {
+    let urls: Vec<String> = value?
+        .split(',')
+        .map(|url| url.trim())
+        .filter(|url| !url.is_empty())
+        .map(String::from)
+        .collect();
+    (!urls.is_empty()).then_some(urls)
+}
+
 #[allow(dead_code)]
 #[derive(Deserialize, Debug, Default, Clone)]
 struct Contract {
@@ -207,3 +336,66 @@ async fn fetch_contract(request_url: String) -> Result<Contract, String> {
 
     Ok(contract)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_api_urls() {
+        assert_eq!(parse_api_urls(None), None);
+        assert_eq!(parse_api_urls(Some(" , ")), None);
+        assert_eq!(
+            parse_api_urls(Some("https://node-a.example, https://node-b.example/")),
+            Some(vec![
+                "https://node-a.example".to_string(),
+                "https://node-b.example/".to_string()
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resolve_transitive_requirements() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let cache_location = FileLocation::from_path(temp_dir.path().to_path_buf());
+        let deployer = "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM";
+        let call = |name: &str| format!("(contract-call? '{}.{} run)", deployer, name);
+        // `a` requires `b` and `c`, and `b` has its own requirement on `c`
+        let contracts = [
+            (
+                "a",
+                format!(
+                    "(define-public (run) (begin (try! {}) {}))",
+                    call("b"),
+                    call("c")
+                ),
+            ),
+            ("b", format!("(define-public (run) {})", call("c"))),
+            ("c", "(define-public (run) (ok true))".to_string()),
+        ];
+        let metadata = serde_json::to_string(&ContractMetadata {
+            epoch: StacksEpochId::Epoch25,
+            clarity_version: ClarityVersion::Clarity2,
+        })
+        .unwrap();
+        for (name, source) in contracts.iter() {
+            let mut location = cache_location.clone();
+            location
+                .append_path(&format!("requirements/{}.{}.clar", deployer, name))
+                .unwrap();
+            location.write_content(source.as_bytes()).unwrap();
+            let mut location = cache_location.clone();
+            location
+                .append_path(&format!("requirements/{}.{}.json", deployer, name))
+                .unwrap();
+            location.write_content(metadata.as_bytes()).unwrap();
+        }
+
+        let contract_id = QualifiedContractIdentifier::parse(&format!("{}.a", deployer)).unwrap();
+        let resolved = resolve_transitive_requirements(&contract_id, &cache_location, &None, None)
+            .await
+            .unwrap();
+        let names: Vec<String> = resolved.iter().map(|id| id.name.to_string()).collect();
+        assert_eq!(names, ["a", "b", "c"]);
+    }
+}
@@ -0,0 +1,280 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use clarity_repl::clarity::vm::types::QualifiedContractIdentifier;
+use clarity_repl::repl::session::BOOT_CONTRACTS_DATA;
+use clarity_repl::repl::Session;
+
+use crate::replay::parse_value;
+use crate::upgrade::StorageKind;
+
+/// Data vars and map entries of a contract, as Clarity values
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ContractStorage {
+    #[serde(default)]
+    pub data_vars: BTreeMap<String, String>,
+    #[serde(default)]
+    pub maps: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+/// Storage of a set of contracts at a given block height
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct StorageSnapshot {
+    pub block_height: u32,
+    pub contracts: BTreeMap<String, ContractStorage>,
+}
+
+impl StorageSnapshot {
+    pub fn from_json(content: &[u8]) -> Result<Self, String> {
+        serde_json::from_slice(content).map_err(|e| format!("unable to parse snapshot: {}", e))
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| format!("unable to serialize snapshot: {}", e))
+    }
+}
+
+/// Snapshot the storage of `contracts`, or of every contract of the session but the
+/// boot contracts if empty.
+pub fn take_snapshot(
+    session: &mut Session,
+    contracts: &[QualifiedContractIdentifier],
+) -> Result<StorageSnapshot, String> {
+    let contracts_ids: Vec<QualifiedContractIdentifier> = match contracts.is_empty() {
+        true => session
+            .contracts
+            .keys()
+            .filter(|contract_id| !BOOT_CONTRACTS_DATA.contains_key(contract_id))
+            .cloned()
+            .collect(),
+        false => contracts.to_vec(),
+    };
+
+    let mut snapshot = StorageSnapshot {
+        block_height: session.interpreter.get_block_height(),
+        contracts: BTreeMap::new(),
+    };
+    for contract_id in contracts_ids.iter() {
+        let analysis = match session.contracts.get(contract_id) {
+            Some(contract) => &contract.analysis,
+            None => return Err(format!("contract {} not found", contract_id)),
+        };
+        let vars_names: Vec<String> = analysis
+            .persisted_variable_types
+            .keys()
+            .map(|name| name.to_string())
+            .collect();
+        let maps_names: Vec<String> = analysis
+            .map_types
+            .keys()
+            .map(|name| name.to_string())
+            .collect();
+
+        let mut storage = ContractStorage::default();
+        for var_name in vars_names {
+            if let Some(value_hex) = session.interpreter.get_data_var(contract_id, &var_name) {
+                storage
+                    .data_vars
+                    .insert(var_name, parse_value(&value_hex)?.to_string());
+            }
+        }
+        for map_name in maps_names {
+            let mut entries = BTreeMap::new();
+            for (key_hex, value_hex) in session.interpreter.get_map_entries(contract_id, &map_name)
+            {
+                // entries are stored as optionals, `(some value)`
+                let value = parse_value(&value_hex)?
+                    .expect_optional()
+                    .map_err(|e| e.to_string())?
+                    .ok_or(format!("unexpected empty entry in map {}", map_name))?;
+                entries.insert(parse_value(&key_hex)?.to_string(), value.to_string());
+            }
+            storage.maps.insert(map_name, entries);
+        }
+        snapshot.contracts.insert(contract_id.to_string(), storage);
+    }
+    Ok(snapshot)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnapshotChange {
+    Added(String),
+    Removed(String),
+    Changed { previous: String, new: String },
+}
+
+/// Difference between two snapshots, on a data var, a map entry or a whole contract
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotDiff {
+    pub contract: String,
+    pub location: Option<(StorageKind, String)>,
+    pub change: SnapshotChange,
+}
+
+impl fmt::Display for SnapshotDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some((kind, name)) = &self.location else {
+            return match &self.change {
+                SnapshotChange::Added(_) => write!(f, "contract {} added", self.contract),
+                _ => write!(f, "contract {} removed", self.contract),
+            };
+        };
+        write!(f, "{} {} {}", self.contract, kind, name)?;
+        match &self.change {
+            SnapshotChange::Added(value) => write!(f, " added: {}", value),
+            SnapshotChange::Removed(value) => write!(f, " removed: {}", value),
+            SnapshotChange::Changed { previous, new } => {
+                write!(f, " changed from {} to {}", previous, new)
+            }
+        }
+    }
+}
+
+fn diff_values(
+    contract: &str,
+    location: impl Fn(&str) -> (StorageKind, String),
+    previous: &BTreeMap<String, String>,
+    new: &BTreeMap<String, String>,
+    diffs: &mut Vec<SnapshotDiff>,
+) {
+    for (key, previous_value) in previous.iter() {
+        let change = match new.get(key) {
+            None => SnapshotChange::Removed(previous_value.clone()),
+            Some(new_value) if new_value != previous_value => SnapshotChange::Changed {
+                previous: previous_value.clone(),
+                new: new_value.clone(),
+            },
+            Some(_) => continue,
+        };
+        diffs.push(SnapshotDiff {
+            contract: contract.to_string(),
+            location: Some(location(key)),
+            change,
+        });
+    }
+    for (key, new_value) in new.iter().filter(|(key, _)| !previous.contains_key(*key)) {
+        diffs.push(SnapshotDiff {
+            contract: contract.to_string(),
+            location: Some(location(key)),
+            change: SnapshotChange::Added(new_value.clone()),
+        });
+    }
+}
+
+/// Compare the storage of two snapshots, the block heights are ignored.
+pub fn diff_snapshots(previous: &StorageSnapshot, new: &StorageSnapshot) -> Vec<SnapshotDiff> {
+    let mut diffs = vec![];
+    for (contract, previous_storage) in previous.contracts.iter() {
+        let Some(new_storage) = new.contracts.get(contract) else {
+            diffs.push(SnapshotDiff {
+                contract: contract.clone(),
+                location: None,
+                change: SnapshotChange::Removed(String::new()),
+            });
+            continue;
+        };
+        diff_values(
+            contract,
+            |name| (StorageKind::DataVar, name.to_string()),
+            &previous_storage.data_vars,
+            &new_storage.data_vars,
+            &mut diffs,
+        );
+        let empty = BTreeMap::new();
+        let maps_names = previous_storage.maps.keys().chain(
+            new_storage
+                .maps
+                .keys()
+                .filter(|name| !previous_storage.maps.contains_key(*name)),
+        );
+        for map_name in maps_names {
+            diff_values(
+                contract,
+                |key| (StorageKind::Map, format!("{}[{}]", map_name, key)),
+                previous_storage.maps.get(map_name).unwrap_or(&empty),
+                new_storage.maps.get(map_name).unwrap_or(&empty),
+                &mut diffs,
+            );
+        }
+    }
+    for contract in new
+        .contracts
+        .keys()
+        .filter(|contract| !previous.contracts.contains_key(*contract))
+    {
+        diffs.push(SnapshotDiff {
+            contract: contract.clone(),
+            location: None,
+            change: SnapshotChange::Added(String::new()),
+        });
+    }
+    diffs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clarity_repl::clarity::vm::{EvaluationResult, SymbolicExpression, Value};
+    use clarity_repl::clarity::StacksEpochId;
+    use clarity_repl::repl::SessionSettings;
+
+    #[test]
+    fn test_snapshot_and_diff() {
+        let mut session = Session::new(SessionSettings::default());
+        session.update_epoch(StacksEpochId::Epoch25);
+        let snippet = [
+            "(define-data-var counter uint u1)",
+            "(define-map scores uint uint)",
+            "(map-insert scores u1 u10)",
+            "(define-public (bump (id uint))",
+            "  (begin",
+            "    (var-set counter (+ (var-get counter) u1))",
+            "    (map-delete scores u1)",
+            "    (ok (map-insert scores id u20))))",
+        ]
+        .join("\n");
+        let contract_id = match session.eval(snippet, false).unwrap().result {
+            EvaluationResult::Contract(evaluation) => {
+                evaluation.contract.analysis.contract_identifier
+            }
+            _ => unreachable!(),
+        };
+
+        let before = take_snapshot(&mut session, &[contract_id.clone()]).unwrap();
+        let storage = &before.contracts[&contract_id.to_string()];
+        assert_eq!(storage.data_vars["counter"], "u1");
+        assert_eq!(storage.maps["scores"].len(), 1);
+        assert_eq!(
+            StorageSnapshot::from_json(before.to_json().unwrap().as_bytes()),
+            Ok(before.clone())
+        );
+
+        let sender = session.get_tx_sender();
+        session
+            .call_contract_fn(
+                &contract_id.to_string(),
+                "bump",
+                &[SymbolicExpression::atom_value(Value::UInt(2))],
+                &sender,
+                false,
+                false,
+            )
+            .unwrap();
+        let after = take_snapshot(&mut session, &[]).unwrap();
+        assert!(diff_snapshots(&before, &before).is_empty());
+
+        let diffs: Vec<String> = diff_snapshots(&before, &after)
+            .iter()
+            .map(|diff| diff.to_string())
+            .collect();
+        assert_eq!(
+            diffs,
+            vec![
+                format!("{} data-var counter changed from u1 to u2", contract_id),
+                format!("{} map scores[u1] removed: u10", contract_id),
+                format!("{} map scores[u2] added: u20", contract_id),
+            ]
+        );
+    }
+}
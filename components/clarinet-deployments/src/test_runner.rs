@@ -0,0 +1,263 @@
+use std::time::{Duration, Instant};
+
+use clarity_repl::clarity::vm::diagnostic::Diagnostic;
+use clarity_repl::clarity::vm::types::{FunctionType, QualifiedContractIdentifier};
+use clarity_repl::clarity::vm::{EvaluationResult, ExecutionResult, Value};
+use clarity_repl::repl::Session;
+
+pub const TEST_FUNCTIONS_PREFIX: &str = "test-";
+const EXPECT_FAILURE_ANNOTATION: &str = "@expect-failure";
+
+/// Public function of a contract, prefixed with `test-` and without arguments
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClarityTest {
+    pub contract_id: QualifiedContractIdentifier,
+    pub name: String,
+    /// Annotated with `;; @expect-failure`, the test passes if it returns an err or aborts
+    pub expect_failure: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TestOutcome {
+    Passed,
+    Failed(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct TestResult {
+    pub test: ClarityTest,
+    pub outcome: TestOutcome,
+    pub duration: Duration,
+}
+
+impl TestResult {
+    pub fn passed(&self) -> bool {
+        self.outcome == TestOutcome::Passed
+    }
+}
+
+/// Whether the comments preceding the definition of `function` hold the expect-failure annotation
+fn is_expected_to_fail(source: &str, function: &str) -> bool {
+    let definition = format!("(define-public ({}", function);
+    let lines: Vec<&str> = source.lines().map(|line| line.trim()).collect();
+    let Some(index) = lines.iter().position(|line| {
+        line.strip_prefix(&definition)
+            .map(|rest| rest.is_empty() || rest.starts_with([' ', ')']))
+            .unwrap_or(false)
+    }) else {
+        return false;
+    };
+    lines[..index]
+        .iter()
+        .rev()
+        .take_while(|line| line.starts_with(";;"))
+        .any(|line| line.contains(EXPECT_FAILURE_ANNOTATION))
+}
+
+/// Tests defined by `contracts`, in the order of the contracts
+pub fn discover_tests(
+    session: &Session,
+    contracts: &[QualifiedContractIdentifier],
+) -> Vec<ClarityTest> {
+    let mut tests = vec![];
+    for contract_id in contracts.iter() {
+        let Some(contract) = session.contracts.get(contract_id) else {
+            continue;
+        };
+        for (name, function_type) in contract.analysis.public_function_types.iter() {
+            if !name.starts_with(TEST_FUNCTIONS_PREFIX) {
+                continue;
+            }
+            match function_type {
+                FunctionType::Fixed(function) if function.args.is_empty() => {}
+                _ => continue,
+            }
+            tests.push(ClarityTest {
+                contract_id: contract_id.clone(),
+                name: name.to_string(),
+                expect_failure: is_expected_to_fail(&contract.code, name),
+            });
+        }
+    }
+    tests
+}
+
+fn test_outcome(
+    execution: Result<ExecutionResult, Vec<Diagnostic>>,
+    expect_failure: bool,
+) -> TestOutcome {
+    let failure = match execution {
+        Ok(execution) => match execution.result {
+            EvaluationResult::Snippet(snippet_result) => match snippet_result.result {
+                Value::Response(response) if response.committed => None,
+                value => Some(format!("returned {}", value)),
+            },
+            EvaluationResult::Contract(_) => unreachable!(),
+        },
+        Err(diagnostics) => Some(
+            diagnostics
+                .into_iter()
+                .map(|d| d.message)
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+    };
+    match (failure, expect_failure) {
+        (None, false) | (Some(_), true) => TestOutcome::Passed,
+        (None, true) => TestOutcome::Failed("expected a failure, returned (ok ...)".to_string()),
+        (Some(message), false) => TestOutcome::Failed(message),
+    }
+}
+
+/// Run each test, sent by the deployer of its contract, in its own copy of `session`:
+/// the changes made by a test are not seen by the other tests.
+pub fn run_tests(session: &Session, tests: Vec<ClarityTest>) -> Vec<TestResult> {
+    let mut results = vec![];
+    for test in tests.into_iter() {
+        let mut test_session = session.clone();
+        let start = Instant::now();
+        let execution = test_session.call_contract_fn(
+            &test.contract_id.to_string(),
+            &test.name,
+            &[],
+            &test.contract_id.issuer.to_string(),
+            false,
+            false,
+        );
+        let outcome = test_outcome(execution, test.expect_failure);
+        results.push(TestResult {
+            test,
+            outcome,
+            duration: start.elapsed(),
+        });
+    }
+    results
+}
+
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// JUnit XML report of the results, with a test suite per contract
+pub fn to_junit_xml(results: &[TestResult]) -> String {
+    let mut suites: Vec<(&QualifiedContractIdentifier, Vec<&TestResult>)> = vec![];
+    for result in results.iter() {
+        match suites.last_mut() {
+            Some((contract_id, suite)) if *contract_id == &result.test.contract_id => {
+                suite.push(result)
+            }
+            _ => suites.push((&result.test.contract_id, vec![result])),
+        }
+    }
+
+    let failures = |results: &[&TestResult]| results.iter().filter(|r| !r.passed()).count();
+    let time = |results: &[&TestResult]| {
+        results
+            .iter()
+            .map(|r| r.duration.as_secs_f64())
+            .sum::<f64>()
+    };
+    let all: Vec<&TestResult> = results.iter().collect();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites name=\"clarinet\" tests=\"{}\" failures=\"{}\" time=\"{:.3}\">\n",
+        all.len(),
+        failures(&all),
+        time(&all)
+    ));
+    for (contract_id, suite) in suites.iter() {
+        let contract_id = escape_xml(&contract_id.to_string());
+        xml.push_str(&format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" time=\"{:.3}\">\n",
+            contract_id,
+            suite.len(),
+            failures(suite),
+            time(suite)
+        ));
+        for result in suite.iter() {
+            let testcase = format!(
+                "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\"",
+                escape_xml(&result.test.name),
+                contract_id,
+                result.duration.as_secs_f64()
+            );
+            match &result.outcome {
+                TestOutcome::Passed => xml.push_str(&format!("{} />\n", testcase)),
+                TestOutcome::Failed(message) => xml.push_str(&format!(
+                    "{}>\n      <failure message=\"{}\" />\n    </testcase>\n",
+                    testcase,
+                    escape_xml(message)
+                )),
+            }
+        }
+        xml.push_str("  </testsuite>\n");
+    }
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clarity_repl::clarity::StacksEpochId;
+    use clarity_repl::repl::SessionSettings;
+
+    #[test]
+    fn test_run_tests() {
+        let mut session = Session::new(SessionSettings::default());
+        session.update_epoch(StacksEpochId::Epoch25);
+        let snippet = [
+            "(define-data-var counter uint u0)",
+            "(define-public (increment) (ok (var-set counter (+ (var-get counter) u1))))",
+            "(define-public (test-increment)",
+            "  (begin",
+            "    (try! (increment))",
+            "    (asserts! (is-eq (var-get counter) u1) (err u1))",
+            "    (ok true)))",
+            ";; counters can not go below zero",
+            ";; @expect-failure",
+            "(define-public (test-underflow)",
+            "  (ok (var-set counter (- (var-get counter) u1))))",
+            "(define-public (test-noop) (begin (asserts! false (err u2)) (ok true)))",
+            "(define-public (test-with-args (amount uint)) (ok amount))",
+        ]
+        .join("\n");
+        let contract_id = match session.eval(snippet, false).unwrap().result {
+            EvaluationResult::Contract(evaluation) => {
+                evaluation.contract.analysis.contract_identifier
+            }
+            _ => unreachable!(),
+        };
+
+        let tests = discover_tests(&session, &[contract_id]);
+        assert_eq!(
+            tests
+                .iter()
+                .map(|t| (t.name.as_str(), t.expect_failure))
+                .collect::<Vec<_>>(),
+            vec![
+                ("test-increment", false),
+                ("test-noop", false),
+                ("test-underflow", true)
+            ]
+        );
+
+        // the counter incremented by the first test is not seen by the last one
+        let results = run_tests(&session, tests);
+        assert_eq!(results[0].outcome, TestOutcome::Passed);
+        assert_eq!(
+            results[1].outcome,
+            TestOutcome::Failed("returned (err u2)".to_string())
+        );
+        assert_eq!(results[2].outcome, TestOutcome::Passed);
+
+        let xml = to_junit_xml(&results);
+        assert!(xml.contains("tests=\"3\" failures=\"1\""));
+        assert!(xml.contains("<failure message=\"returned (err u2)\" />"));
+    }
+}
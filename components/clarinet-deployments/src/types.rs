@@ -5,16 +5,20 @@ use clarity_repl::clarity::vm::analysis::ContractAnalysis;
 use clarity_repl::clarity::vm::ast::ContractAST;
 use clarity_repl::clarity::vm::diagnostic::Diagnostic;
 use clarity_repl::clarity::vm::types::{
-    PrincipalData, QualifiedContractIdentifier, StandardPrincipalData,
+    AssetIdentifier, PrincipalData, QualifiedContractIdentifier, StandardPrincipalData,
 };
 
 use clarity_repl::analysis::ast_dependency_detector::DependencySet;
 use clarity_repl::clarity::{ClarityName, ClarityVersion, ContractName, StacksEpochId, Value};
+use clarity_repl::repl::post_conditions::{
+    FungibleConditionCode, NonFungibleConditionCode, PostConditionMode,
+};
 use clarity_repl::repl::{Session, DEFAULT_CLARITY_VERSION};
 use serde::{Deserialize, Serialize};
 use serde_yaml;
 use std::collections::BTreeMap;
 use std::collections::HashMap;
+use std::str::FromStr;
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Copy, Eq, PartialOrd, Ord)]
 pub enum EpochSpec {
@@ -155,6 +159,38 @@ pub struct ContractCallSpecificationFile {
     pub cost: u64,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub anchor_block_only: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_condition_mode: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_conditions: Vec<PostConditionSpecificationFile>,
+}
+
+/// Post-condition attached to a contract call:
+///
+/// ```yaml
+/// post-condition-mode: deny
+/// post-conditions:
+///   - principal: ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM
+///     asset: stx
+///     condition: sent-le
+///     amount: 1000000
+///   - principal: ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM
+///     asset: ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.nft::ticket
+///     asset-id: u1
+///     condition: sent
+/// ```
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct PostConditionSpecificationFile {
+    pub principal: String,
+    /// `stx`, or the identifier of a token, ie `<contract-id>::<token-name>`
+    pub asset: String,
+    pub condition: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount: Option<u64>,
+    /// Clarity expression of the non-fungible token id
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asset_id: Option<String>,
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
@@ -199,6 +235,10 @@ pub struct EmulatedContractCallSpecificationFile {
     pub emulated_sender: String,
     pub method: String,
     pub parameters: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_condition_mode: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_conditions: Vec<PostConditionSpecificationFile>,
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
@@ -397,6 +437,8 @@ pub struct ContractCallSpecification {
     pub parameters: Vec<String>,
     pub cost: u64,
     pub anchor_block_only: bool,
+    pub post_condition_mode: PostConditionMode,
+    pub post_conditions: Vec<PostConditionSpecification>,
 }
 
 impl ContractCallSpecification {
@@ -441,10 +483,137 @@ impl ContractCallSpecification {
             parameters: specs.parameters.clone(),
             cost: specs.cost,
             anchor_block_only: specs.anchor_block_only.unwrap_or(true),
+            post_condition_mode: parse_post_condition_mode(&specs.post_condition_mode)?,
+            post_conditions: specs
+                .post_conditions
+                .iter()
+                .map(PostConditionSpecification::from_specifications)
+                .collect::<Result<_, _>>()?,
         })
     }
 }
 
+fn parse_post_condition_mode(mode: &Option<String>) -> Result<PostConditionMode, String> {
+    match mode {
+        Some(mode) => PostConditionMode::from_str(mode),
+        None => Ok(PostConditionMode::Allow),
+    }
+}
+
+fn post_condition_mode_to_file(mode: &PostConditionMode) -> Option<String> {
+    match mode {
+        PostConditionMode::Allow => None,
+        PostConditionMode::Deny => Some("deny".to_string()),
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub enum PostConditionSpecification {
+    Stx(PrincipalData, FungibleConditionCode, u64),
+    Fungible(PrincipalData, AssetIdentifier, FungibleConditionCode, u64),
+    /// The token id is a Clarity expression, evaluated like the call parameters
+    NonFungible(
+        PrincipalData,
+        AssetIdentifier,
+        String,
+        NonFungibleConditionCode,
+    ),
+}
+
+impl PostConditionSpecification {
+    pub fn from_specifications(
+        specs: &PostConditionSpecificationFile,
+    ) -> Result<PostConditionSpecification, String> {
+        let principal = match PrincipalData::parse(&specs.principal) {
+            Ok(res) => res,
+            Err(_) => {
+                return Err(format!(
+                    "unable to parse post-condition principal '{}'",
+                    specs.principal
+                ))
+            }
+        };
+
+        if specs.asset.eq_ignore_ascii_case("stx") {
+            let condition = FungibleConditionCode::from_str(&specs.condition)?;
+            let Some(amount) = specs.amount else {
+                return Err("stx post-conditions require an amount".to_string());
+            };
+            return Ok(PostConditionSpecification::Stx(
+                principal, condition, amount,
+            ));
+        }
+
+        let asset = match specs.asset.split_once("::").and_then(|(contract_id, name)| {
+            let contract_identifier = QualifiedContractIdentifier::parse(contract_id).ok()?;
+            let asset_name = ClarityName::try_from(name.to_string()).ok()?;
+            Some(AssetIdentifier {
+                contract_identifier,
+                asset_name,
+            })
+        }) {
+            Some(asset) => asset,
+            None => {
+                return Err(format!(
+                    "unable to parse post-condition asset '{}' (expected 'stx' or '<contract-id>::<token-name>')",
+                    specs.asset
+                ))
+            }
+        };
+
+        match (&specs.asset_id, specs.amount) {
+            (Some(asset_id), None) => Ok(PostConditionSpecification::NonFungible(
+                principal,
+                asset,
+                asset_id.clone(),
+                NonFungibleConditionCode::from_str(&specs.condition)?,
+            )),
+            (None, Some(amount)) => Ok(PostConditionSpecification::Fungible(
+                principal,
+                asset,
+                FungibleConditionCode::from_str(&specs.condition)?,
+                amount,
+            )),
+            _ => Err(format!(
+                "post-condition on '{}' requires either an amount (fungible token) or an asset-id (non-fungible token)",
+                specs.asset
+            )),
+        }
+    }
+
+    pub fn to_specification_file(&self) -> PostConditionSpecificationFile {
+        match self {
+            PostConditionSpecification::Stx(principal, condition, amount) => {
+                PostConditionSpecificationFile {
+                    principal: principal.to_string(),
+                    asset: "stx".to_string(),
+                    condition: condition.to_string(),
+                    amount: Some(*amount),
+                    asset_id: None,
+                }
+            }
+            PostConditionSpecification::Fungible(principal, asset, condition, amount) => {
+                PostConditionSpecificationFile {
+                    principal: principal.to_string(),
+                    asset: format!("{}::{}", asset.contract_identifier, asset.asset_name),
+                    condition: condition.to_string(),
+                    amount: Some(*amount),
+                    asset_id: None,
+                }
+            }
+            PostConditionSpecification::NonFungible(principal, asset, asset_id, condition) => {
+                PostConditionSpecificationFile {
+                    principal: principal.to_string(),
+                    asset: format!("{}::{}", asset.contract_identifier, asset.asset_name),
+                    condition: condition.to_string(),
+                    amount: None,
+                    asset_id: Some(asset_id.clone()),
+                }
+            }
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
 pub struct ContractPublishSpecification {
     pub contract_name: ContractName,
@@ -781,6 +950,8 @@ pub struct EmulatedContractCallSpecification {
     pub emulated_sender: StandardPrincipalData,
     pub method: ClarityName,
     pub parameters: Vec<String>,
+    pub post_condition_mode: PostConditionMode,
+    pub post_conditions: Vec<PostConditionSpecification>,
 }
 
 impl EmulatedContractCallSpecification {
@@ -823,6 +994,12 @@ impl EmulatedContractCallSpecification {
             emulated_sender,
             method,
             parameters: specs.parameters.clone(),
+            post_condition_mode: parse_post_condition_mode(&specs.post_condition_mode)?,
+            post_conditions: specs
+                .post_conditions
+                .iter()
+                .map(PostConditionSpecification::from_specifications)
+                .collect::<Result<_, _>>()?,
         })
     }
 }
@@ -1005,9 +1182,16 @@ impl DeploymentSpecification {
         project_root_location: &FileLocation,
     ) -> Result<DeploymentSpecification, String> {
         let spec_file_content = deployment_location.read_content()?;
+        DeploymentSpecification::from_config_content(&spec_file_content, project_root_location)
+    }
 
-        let specification_file: DeploymentSpecificationFile =
-            match serde_yaml::from_slice(&spec_file_content[..]) {
+    pub fn from_config_content(
+        spec_file_content: &[u8],
+        project_root_location: &FileLocation,
+    ) -> Result<DeploymentSpecification, String> {
+        #[allow(unused_mut)]
+        let mut specification_file: DeploymentSpecificationFile =
+            match serde_yaml::from_slice(spec_file_content) {
                 Ok(res) => res,
                 Err(msg) => return Err(format!("unable to read file {}", msg)),
             };
@@ -1025,6 +1209,9 @@ impl DeploymentSpecification {
             }
         };
 
+        #[cfg(feature = "cli")]
+        specification_file.resolve_keychain_senders(&network)?;
+
         let deployment_spec = DeploymentSpecification::from_specifications(
             &specification_file,
             &network,
@@ -1272,6 +1459,46 @@ impl DeploymentSpecificationFile {
         serde_yaml::from_str(spec_file_content)
             .map_err(|msg| format!("unable to read file {}", msg))
     }
+
+    /// Replace the `keychain:<name>` senders of the plan by the addresses of the
+    /// keychain accounts on `network`
+    #[cfg(feature = "cli")]
+    pub fn resolve_keychain_senders(&mut self, network: &StacksNetwork) -> Result<(), String> {
+        use clarinet_files::keychain::resolve_keychain_sender;
+
+        let Some(ref mut plan) = self.plan else {
+            return Ok(());
+        };
+        for batch in plan.batches.iter_mut() {
+            for transaction in batch.transactions.iter_mut() {
+                let (sender, is_bitcoin) = match transaction {
+                    TransactionSpecificationFile::ContractCall(tx) => {
+                        (&mut tx.expected_sender, false)
+                    }
+                    TransactionSpecificationFile::ContractPublish(tx) => {
+                        (&mut tx.expected_sender, false)
+                    }
+                    TransactionSpecificationFile::EmulatedContractCall(tx) => {
+                        (&mut tx.emulated_sender, false)
+                    }
+                    TransactionSpecificationFile::EmulatedContractPublish(tx) => {
+                        (&mut tx.emulated_sender, false)
+                    }
+                    TransactionSpecificationFile::RequirementPublish(tx) => {
+                        (&mut tx.remap_sender, false)
+                    }
+                    TransactionSpecificationFile::StxTransfer(tx) => {
+                        (&mut tx.expected_sender, false)
+                    }
+                    TransactionSpecificationFile::BtcTransfer(tx) => {
+                        (&mut tx.expected_sender, true)
+                    }
+                };
+                *sender = resolve_keychain_sender(sender, network, is_bitcoin)?;
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
@@ -1382,6 +1609,14 @@ impl TransactionPlanSpecification {
                             parameters: tx.parameters.clone(),
                             cost: tx.cost,
                             anchor_block_only: Some(tx.anchor_block_only),
+                            post_condition_mode: post_condition_mode_to_file(
+                                &tx.post_condition_mode,
+                            ),
+                            post_conditions: tx
+                                .post_conditions
+                                .iter()
+                                .map(|pc| pc.to_specification_file())
+                                .collect(),
                         })
                     }
                     TransactionSpecification::ContractPublish(tx) => {
@@ -1409,6 +1644,14 @@ impl TransactionPlanSpecification {
                                 emulated_sender: tx.emulated_sender.to_address(),
                                 method: tx.method.to_string(),
                                 parameters: tx.parameters.clone(),
+                                post_condition_mode: post_condition_mode_to_file(
+                                    &tx.post_condition_mode,
+                                ),
+                                post_conditions: tx
+                                    .post_conditions
+                                    .iter()
+                                    .map(|pc| pc.to_specification_file())
+                                    .collect(),
                             },
                         )
                     }
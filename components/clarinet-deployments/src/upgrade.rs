@@ -0,0 +1,671 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use clarinet_files::{FileLocation, StacksNetwork};
+use clarity_repl::clarity::vm::analysis::ContractAnalysis;
+use clarity_repl::clarity::vm::types::{
+    QualifiedContractIdentifier, ResponseData, StandardPrincipalData,
+};
+use clarity_repl::clarity::vm::{ClarityName, ContractName, EvaluationResult, Value};
+use clarity_repl::clarity::ClarityVersion;
+use clarity_repl::repl::post_conditions::PostConditionMode;
+use clarity_repl::repl::Session;
+
+use crate::types::{
+    ContractCallSpecification, ContractPublishSpecification, DeploymentSpecification,
+    EmulatedContractCallSpecification, EmulatedContractPublishSpecification, EpochSpec,
+    TransactionPlanSpecification, TransactionSpecification, TransactionsBatchSpecification,
+};
+use crate::{handle_emulated_contract_call, handle_emulated_contract_publish};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageKind {
+    DataVar,
+    Map,
+    FungibleToken,
+    NonFungibleToken,
+}
+
+impl fmt::Display for StorageKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageKind::DataVar => write!(f, "data-var"),
+            StorageKind::Map => write!(f, "map"),
+            StorageKind::FungibleToken => write!(f, "fungible-token"),
+            StorageKind::NonFungibleToken => write!(f, "non-fungible-token"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StorageChange {
+    Added,
+    Removed,
+    TypeChanged { previous: String, new: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageDiff {
+    pub kind: StorageKind,
+    pub name: String,
+    pub change: StorageChange,
+}
+
+impl StorageDiff {
+    /// Removed or retyped storage can't be migrated as is to the new contract.
+    pub fn is_breaking(&self) -> bool {
+        !matches!(self.change, StorageChange::Added)
+    }
+}
+
+impl fmt::Display for StorageDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.change {
+            StorageChange::Added => write!(f, "{} {} added", self.kind, self.name),
+            StorageChange::Removed => write!(f, "{} {} removed", self.kind, self.name),
+            StorageChange::TypeChanged { previous, new } => write!(
+                f,
+                "{} {} type changed from {} to {}",
+                self.kind, self.name, previous, new
+            ),
+        }
+    }
+}
+
+fn diff_storage(
+    kind: StorageKind,
+    previous: BTreeMap<String, String>,
+    new: BTreeMap<String, String>,
+    diffs: &mut Vec<StorageDiff>,
+) {
+    for (name, previous_type) in previous.iter() {
+        let change = match new.get(name) {
+            None => StorageChange::Removed,
+            Some(new_type) if new_type != previous_type => StorageChange::TypeChanged {
+                previous: previous_type.clone(),
+                new: new_type.clone(),
+            },
+            Some(_) => continue,
+        };
+        diffs.push(StorageDiff {
+            kind,
+            name: name.clone(),
+            change,
+        });
+    }
+    for name in new.keys().filter(|name| !previous.contains_key(*name)) {
+        diffs.push(StorageDiff {
+            kind,
+            name: name.clone(),
+            change: StorageChange::Added,
+        });
+    }
+}
+
+/// Compare the data vars, maps and tokens declared by two versions of a contract.
+pub fn check_storage_compatibility(
+    previous: &ContractAnalysis,
+    new: &ContractAnalysis,
+) -> Vec<StorageDiff> {
+    let data_vars = |analysis: &ContractAnalysis| -> BTreeMap<String, String> {
+        analysis
+            .persisted_variable_types
+            .iter()
+            .map(|(name, var_type)| (name.to_string(), var_type.to_string()))
+            .collect()
+    };
+    let maps = |analysis: &ContractAnalysis| -> BTreeMap<String, String> {
+        analysis
+            .map_types
+            .iter()
+            .map(|(name, (key, value))| (name.to_string(), format!("{{ {}: {} }}", key, value)))
+            .collect()
+    };
+    let fts = |analysis: &ContractAnalysis| -> BTreeMap<String, String> {
+        analysis
+            .fungible_tokens
+            .iter()
+            .map(|name| (name.to_string(), String::new()))
+            .collect()
+    };
+    let nfts = |analysis: &ContractAnalysis| -> BTreeMap<String, String> {
+        analysis
+            .non_fungible_tokens
+            .iter()
+            .map(|(name, asset_type)| (name.to_string(), asset_type.to_string()))
+            .collect()
+    };
+
+    let mut diffs = vec![];
+    diff_storage(
+        StorageKind::DataVar,
+        data_vars(previous),
+        data_vars(new),
+        &mut diffs,
+    );
+    diff_storage(StorageKind::Map, maps(previous), maps(new), &mut diffs);
+    diff_storage(
+        StorageKind::FungibleToken,
+        fts(previous),
+        fts(new),
+        &mut diffs,
+    );
+    diff_storage(
+        StorageKind::NonFungibleToken,
+        nfts(previous),
+        nfts(new),
+        &mut diffs,
+    );
+    diffs
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MigrationCall {
+    pub method: ClarityName,
+    pub parameters: Vec<String>,
+}
+
+impl MigrationCall {
+    /// Parse a call written as `<method> <arg>...`, arguments being Clarity
+    /// expressions separated by spaces outside of parentheses, braces and strings.
+    pub fn parse(input: &str) -> Result<MigrationCall, String> {
+        let input = input.trim();
+        let (method, args) = input.split_once(char::is_whitespace).unwrap_or((input, ""));
+        let method = ClarityName::try_from(method.to_string())
+            .map_err(|_| format!("invalid function name '{}'", method))?;
+
+        let mut parameters = vec![];
+        let mut current = String::new();
+        let mut depth = 0;
+        let mut in_string = false;
+        for c in args.trim().chars() {
+            match c {
+                '"' => in_string = !in_string,
+                '(' | '{' if !in_string => depth += 1,
+                ')' | '}' if !in_string => depth -= 1,
+                c if c.is_whitespace() && depth == 0 && !in_string => {
+                    if !current.is_empty() {
+                        parameters.push(std::mem::take(&mut current));
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+            current.push(c);
+        }
+        if depth != 0 || in_string {
+            return Err(format!("unbalanced arguments in '{}'", input));
+        }
+        if !current.is_empty() {
+            parameters.push(current);
+        }
+        Ok(MigrationCall { method, parameters })
+    }
+}
+
+/// A new version of a contract, and the calls migrating data to it once published.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContractUpgrade {
+    pub contract_name: ContractName,
+    pub source: String,
+    pub location: FileLocation,
+    pub clarity_version: ClarityVersion,
+    pub migration_calls: Vec<MigrationCall>,
+}
+
+/// Build a plan publishing the new contract in a first batch, then performing the
+/// migration calls in a second one. Network settings are inherited from `base`.
+pub fn build_upgrade_deployment(
+    base: &DeploymentSpecification,
+    previous_contract_id: &QualifiedContractIdentifier,
+    upgrade: &ContractUpgrade,
+    deployer: &StandardPrincipalData,
+    deployment_fee_rate: u64,
+) -> DeploymentSpecification {
+    let emulated = matches!(base.network, StacksNetwork::Simnet);
+    let contract_id =
+        QualifiedContractIdentifier::new(deployer.clone(), upgrade.contract_name.clone());
+    let epoch: Option<EpochSpec> = base.plan.batches.iter().filter_map(|b| b.epoch).max();
+
+    let publish = if emulated {
+        TransactionSpecification::EmulatedContractPublish(EmulatedContractPublishSpecification {
+            contract_name: upgrade.contract_name.clone(),
+            emulated_sender: deployer.clone(),
+            source: upgrade.source.clone(),
+            location: upgrade.location.clone(),
+            clarity_version: upgrade.clarity_version,
+        })
+    } else {
+        TransactionSpecification::ContractPublish(ContractPublishSpecification {
+            contract_name: upgrade.contract_name.clone(),
+            expected_sender: deployer.clone(),
+            location: upgrade.location.clone(),
+            cost: deployment_fee_rate
+                .saturating_mul(upgrade.source.as_bytes().len().try_into().unwrap()),
+            source: upgrade.source.clone(),
+            anchor_block_only: true,
+            clarity_version: upgrade.clarity_version,
+        })
+    };
+    let mut batches = vec![TransactionsBatchSpecification {
+        id: 0,
+        transactions: vec![publish],
+        epoch,
+    }];
+
+    let calls: Vec<TransactionSpecification> = upgrade
+        .migration_calls
+        .iter()
+        .map(|call| {
+            if emulated {
+                TransactionSpecification::EmulatedContractCall(EmulatedContractCallSpecification {
+                    contract_id: contract_id.clone(),
+                    emulated_sender: deployer.clone(),
+                    method: call.method.clone(),
+                    parameters: call.parameters.clone(),
+                    post_condition_mode: PostConditionMode::Allow,
+                    post_conditions: vec![],
+                })
+            } else {
+                TransactionSpecification::ContractCall(ContractCallSpecification {
+                    contract_id: contract_id.clone(),
+                    expected_sender: deployer.clone(),
+                    method: call.method.clone(),
+                    parameters: call.parameters.clone(),
+                    // rough size of a contract-call transaction
+                    cost: deployment_fee_rate.saturating_mul(1000),
+                    anchor_block_only: true,
+                    post_condition_mode: PostConditionMode::Allow,
+                    post_conditions: vec![],
+                })
+            }
+        })
+        .collect();
+    if !calls.is_empty() {
+        batches.push(TransactionsBatchSpecification {
+            id: 1,
+            transactions: calls,
+            epoch,
+        });
+    }
+
+    let mut contracts = BTreeMap::new();
+    contracts.insert(
+        contract_id,
+        (upgrade.source.clone(), upgrade.location.clone()),
+    );
+
+    DeploymentSpecification {
+        id: 0,
+        name: format!(
+            "Upgrade of {} to {}",
+            previous_contract_id.name, upgrade.contract_name
+        ),
+        network: base.network.clone(),
+        stacks_node: base.stacks_node.clone(),
+        bitcoin_node: base.bitcoin_node.clone(),
+        genesis: base.genesis.clone(),
+        plan: TransactionPlanSpecification { batches },
+        contracts,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulationStep {
+    pub description: String,
+    pub result: Result<Option<Value>, String>,
+}
+
+impl SimulationStep {
+    /// A step fails if the transaction is rejected or if a call returns an `err` response.
+    pub fn is_success(&self) -> bool {
+        !matches!(
+            self.result,
+            Err(_)
+                | Ok(Some(Value::Response(ResponseData {
+                    committed: false,
+                    ..
+                })))
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct UpgradeSimulation {
+    pub new_contract_analysis: Option<ContractAnalysis>,
+    pub steps: Vec<SimulationStep>,
+}
+
+impl UpgradeSimulation {
+    pub fn is_success(&self) -> bool {
+        self.steps.iter().all(|step| step.is_success())
+    }
+}
+
+/// Execute an emulated upgrade plan (see `build_upgrade_deployment`) in a session where
+/// the previous version of the contract is deployed, stopping at the first failure.
+pub fn simulate_upgrade_deployment(
+    session: &mut Session,
+    deployment: &DeploymentSpecification,
+) -> UpgradeSimulation {
+    let mut simulation = UpgradeSimulation {
+        new_contract_analysis: None,
+        steps: vec![],
+    };
+    for batch in deployment.plan.batches.iter() {
+        session.advance_chain_tip(1);
+        let epoch = match batch.epoch {
+            Some(epoch) => epoch.into(),
+            None => session.current_epoch,
+        };
+        session.update_epoch(epoch);
+
+        for transaction in batch.transactions.iter() {
+            let step = match transaction {
+                TransactionSpecification::EmulatedContractPublish(tx) => {
+                    let result = handle_emulated_contract_publish(session, tx, None, epoch, false);
+                    let result = match result {
+                        Ok(execution) => match execution.result {
+                            EvaluationResult::Contract(contract_result) => {
+                                simulation.new_contract_analysis =
+                                    Some(contract_result.contract.analysis);
+                                Ok(contract_result.result)
+                            }
+                            EvaluationResult::Snippet(_) => Ok(None),
+                        },
+                        Err(diagnostics) => Err(diagnostics
+                            .iter()
+                            .map(|d| d.message.clone())
+                            .collect::<Vec<_>>()
+                            .join("\n")),
+                    };
+                    SimulationStep {
+                        description: format!("publish {}", tx.contract_name),
+                        result,
+                    }
+                }
+                TransactionSpecification::EmulatedContractCall(tx) => {
+                    let result = match handle_emulated_contract_call(session, tx, false) {
+                        Ok(execution) => match execution.result {
+                            EvaluationResult::Snippet(snippet_result) => {
+                                Ok(Some(snippet_result.result))
+                            }
+                            EvaluationResult::Contract(_) => Ok(None),
+                        },
+                        Err(diagnostics) => Err(diagnostics
+                            .iter()
+                            .map(|d| d.message.clone())
+                            .collect::<Vec<_>>()
+                            .join("\n")),
+                    };
+                    SimulationStep {
+                        description: format!("call {}::{}", tx.contract_id, tx.method),
+                        result,
+                    }
+                }
+                _ => SimulationStep {
+                    description: "unsupported transaction".to_string(),
+                    result: Err("only emulated transactions can be simulated".to_string()),
+                },
+            };
+            let success = step.is_success();
+            simulation.steps.push(step);
+            if !success {
+                return simulation;
+            }
+        }
+    }
+    simulation
+}
+
+/// Source of `contract_id` as deployed on the node at `node_url`
+#[cfg(feature = "onchain")]
+pub fn fetch_deployed_source(
+    node_url: &str,
+    contract_id: &QualifiedContractIdentifier,
+) -> Result<String, String> {
+    let stacks_rpc = stacks_rpc_client::StacksRpc::new(node_url);
+    stacks_rpc
+        .get_contract_source(&contract_id.issuer.to_address(), contract_id.name.as_str())
+        .map(|contract| contract.source)
+        .map_err(|e| format!("unable to fetch {} from {}: {}", contract_id, node_url, e))
+}
+
+/// Replace the source of the contract `contract_name` in the simnet `deployment`, so that
+/// the upgrade is compared with the version deployed on a network rather than the local one.
+pub fn use_deployed_source(
+    deployment: &mut DeploymentSpecification,
+    contract_name: &str,
+    source: &str,
+) {
+    for batch in deployment.plan.batches.iter_mut() {
+        for transaction in batch.transactions.iter_mut() {
+            match transaction {
+                TransactionSpecification::EmulatedContractPublish(tx)
+                    if tx.contract_name.as_str() == contract_name =>
+                {
+                    tx.source = source.to_string();
+                }
+                _ => {}
+            }
+        }
+    }
+    for (contract_id, (contract_source, _)) in deployment.contracts.iter_mut() {
+        if contract_id.name.as_str() == contract_name {
+            *contract_source = source.to_string();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clarity_repl::clarity::vm::types::PrincipalData;
+    use clarity_repl::clarity::StacksEpochId;
+    use clarity_repl::repl::SessionSettings;
+
+    static DEPLOYER: &str = "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM";
+
+    fn base_deployment(network: StacksNetwork) -> DeploymentSpecification {
+        DeploymentSpecification {
+            id: 0,
+            name: "base".to_string(),
+            network,
+            stacks_node: None,
+            bitcoin_node: None,
+            genesis: None,
+            plan: TransactionPlanSpecification {
+                batches: vec![TransactionsBatchSpecification {
+                    id: 0,
+                    transactions: vec![],
+                    epoch: Some(StacksEpochId::Epoch25.into()),
+                }],
+            },
+            contracts: BTreeMap::new(),
+        }
+    }
+
+    fn upgrade(source: &str, migration_calls: Vec<MigrationCall>) -> ContractUpgrade {
+        ContractUpgrade {
+            contract_name: ContractName::from("counter-v2"),
+            source: source.to_string(),
+            location: FileLocation::from_path_string("/contracts/counter-v2.clar").unwrap(),
+            clarity_version: ClarityVersion::Clarity2,
+            migration_calls,
+        }
+    }
+
+    fn simulate(
+        session: &mut Session,
+        source: &str,
+        calls: Vec<MigrationCall>,
+    ) -> UpgradeSimulation {
+        let deployer = PrincipalData::parse_standard_principal(DEPLOYER).unwrap();
+        let previous_id =
+            QualifiedContractIdentifier::new(deployer.clone(), ContractName::from("counter"));
+        let deployment = build_upgrade_deployment(
+            &base_deployment(StacksNetwork::Simnet),
+            &previous_id,
+            &upgrade(source, calls),
+            &deployer,
+            10,
+        );
+        simulate_upgrade_deployment(session, &deployment)
+    }
+
+    #[test]
+    fn test_migration_call_parse() {
+        let call = MigrationCall::parse("migrate u1 { a: (list 1 2) } \"a b\"").unwrap();
+        assert_eq!(call.method.as_str(), "migrate");
+        assert_eq!(call.parameters, vec!["u1", "{ a: (list 1 2) }", "\"a b\""]);
+
+        assert!(MigrationCall::parse("migrate (list 1").is_err());
+    }
+
+    #[test]
+    fn test_check_storage_compatibility() {
+        let mut session = Session::new(SessionSettings::default());
+        session.update_epoch(StacksEpochId::Epoch25);
+        let v1 = [
+            "(define-data-var count uint u0)",
+            "(define-map balances principal uint)",
+            "(define-fungible-token token)",
+        ]
+        .join("\n");
+        let v2 = [
+            "(define-data-var count int 0)",
+            "(define-data-var owner principal tx-sender)",
+            "(define-fungible-token token)",
+        ]
+        .join("\n");
+        let analysis = |session: &mut Session, snippet: String| match session
+            .eval(snippet, false)
+            .unwrap()
+            .result
+        {
+            EvaluationResult::Contract(evaluation) => evaluation.contract.analysis,
+            _ => unreachable!(),
+        };
+        let previous = analysis(&mut session, v1);
+        let new = analysis(&mut session, v2);
+
+        let diffs = check_storage_compatibility(&previous, &new);
+        assert_eq!(diffs.len(), 3);
+        assert_eq!(
+            diffs[0],
+            StorageDiff {
+                kind: StorageKind::DataVar,
+                name: "count".to_string(),
+                change: StorageChange::TypeChanged {
+                    previous: "uint".to_string(),
+                    new: "int".to_string(),
+                },
+            }
+        );
+        assert_eq!(diffs[1].change, StorageChange::Added);
+        assert_eq!(diffs[2].kind, StorageKind::Map);
+        assert_eq!(diffs[2].change, StorageChange::Removed);
+        assert!(!diffs[1].is_breaking());
+        assert!(diffs[2].is_breaking());
+    }
+
+    #[test]
+    fn test_build_upgrade_deployment() {
+        let deployer = PrincipalData::parse_standard_principal(DEPLOYER).unwrap();
+        let previous_id =
+            QualifiedContractIdentifier::new(deployer.clone(), ContractName::from("counter"));
+        let calls = vec![MigrationCall::parse("migrate u1").unwrap()];
+        let deployment = build_upgrade_deployment(
+            &base_deployment(StacksNetwork::Testnet),
+            &previous_id,
+            &upgrade("(define-public (migrate (n uint)) (ok n))", calls),
+            &deployer,
+            10,
+        );
+
+        assert_eq!(deployment.plan.batches.len(), 2);
+        match &deployment.plan.batches[0].transactions[0] {
+            TransactionSpecification::ContractPublish(tx) => {
+                assert_eq!(tx.contract_name.as_str(), "counter-v2");
+                assert_eq!(tx.cost, 10 * 41);
+            }
+            _ => panic!("expected a contract-publish"),
+        }
+        match &deployment.plan.batches[1].transactions[0] {
+            TransactionSpecification::ContractCall(tx) => {
+                assert_eq!(
+                    tx.contract_id.to_string(),
+                    format!("{}.counter-v2", DEPLOYER)
+                );
+                assert_eq!(tx.parameters, vec!["u1"]);
+            }
+            _ => panic!("expected a contract-call"),
+        }
+        assert!(deployment.to_file_content().is_ok());
+    }
+
+    #[test]
+    fn test_use_deployed_source() {
+        let deployer = PrincipalData::parse_standard_principal(DEPLOYER).unwrap();
+        let contract_id =
+            QualifiedContractIdentifier::new(deployer.clone(), ContractName::from("counter"));
+        let location = FileLocation::from_path_string("/contracts/counter.clar").unwrap();
+        let mut deployment = base_deployment(StacksNetwork::Simnet);
+        deployment.plan.batches[0].transactions.push(
+            TransactionSpecification::EmulatedContractPublish(
+                EmulatedContractPublishSpecification {
+                    contract_name: contract_id.name.clone(),
+                    emulated_sender: deployer,
+                    source: "(define-data-var count uint u0)".to_string(),
+                    clarity_version: ClarityVersion::Clarity2,
+                    location: location.clone(),
+                },
+            ),
+        );
+        deployment.contracts.insert(
+            contract_id.clone(),
+            ("(define-data-var count uint u0)".to_string(), location),
+        );
+
+        let deployed = "(define-data-var count int 0)";
+        use_deployed_source(&mut deployment, "counter", deployed);
+        match &deployment.plan.batches[0].transactions[0] {
+            TransactionSpecification::EmulatedContractPublish(tx) => {
+                assert_eq!(tx.source, deployed)
+            }
+            _ => panic!("expected an emulated-contract-publish"),
+        }
+        assert_eq!(deployment.contracts[&contract_id].0, deployed);
+    }
+
+    #[test]
+    fn test_simulate_upgrade_deployment() {
+        let mut session = Session::new(SessionSettings::default());
+        let source = [
+            "(define-data-var count uint u0)",
+            "(define-public (migrate (n uint))",
+            "  (if (> n u0) (ok (var-set count n)) (err u1)))",
+        ]
+        .join("\n");
+
+        let simulation = simulate(
+            &mut session.clone(),
+            &source,
+            vec![MigrationCall::parse("migrate u3").unwrap()],
+        );
+        assert!(simulation.is_success());
+        assert_eq!(simulation.steps.len(), 2);
+        assert!(simulation.new_contract_analysis.is_some());
+
+        let simulation = simulate(
+            &mut session,
+            &source,
+            vec![
+                MigrationCall::parse("migrate u0").unwrap(),
+                MigrationCall::parse("migrate u3").unwrap(),
+            ],
+        );
+        assert!(!simulation.is_success());
+        assert_eq!(simulation.steps.len(), 2);
+    }
+}
@@ -0,0 +1,340 @@
+use clarinet_files::FileLocation;
+use clarity_repl::clarity::vm::analysis::ContractAnalysis;
+use clarity_repl::clarity::vm::types::{FunctionType, QualifiedContractIdentifier};
+use clarity_repl::clarity::vm::EvaluationResult;
+use clarity_repl::repl::{Session, SessionSettings};
+use serde_yaml::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::types::{DeploymentSpecification, TransactionSpecification};
+
+/// Variables of a plan template, from yaml files mapping names to scalar values:
+///
+/// ```yaml
+/// deployer: ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM
+/// fee_rate: 10
+/// ```
+///
+/// A variable defined in several files takes the value of the last one.
+pub fn load_variables(locations: &[FileLocation]) -> Result<BTreeMap<String, Value>, String> {
+    let mut variables = BTreeMap::new();
+    for location in locations.iter() {
+        let content = location.read_content()?;
+        let values: BTreeMap<String, Value> = serde_yaml::from_slice(&content)
+            .map_err(|e| format!("unable to parse variables file {}: {}", location, e))?;
+        for (name, value) in values.into_iter() {
+            if !matches!(value, Value::String(_) | Value::Number(_) | Value::Bool(_)) {
+                return Err(format!(
+                    "variable {} ({}) must be a string, a number or a bool",
+                    name, location
+                ));
+            }
+            variables.insert(name, value);
+        }
+    }
+    Ok(variables)
+}
+
+fn scalar_to_string(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.clone(),
+        Value::Number(value) => value.to_string(),
+        Value::Bool(value) => value.to_string(),
+        _ => String::new(),
+    }
+}
+
+/// Name of a `{{ name }}` placeholder left unquoted in the template, which yaml reads as a
+/// flow mapping holding a single `{ name: null }` key
+fn unquoted_placeholder(value: &Value) -> Option<String> {
+    let Value::Mapping(outer) = value else {
+        return None;
+    };
+    let (key, key_value) = outer.iter().next().filter(|_| outer.len() == 1)?;
+    let Value::Mapping(inner) = key else {
+        return None;
+    };
+    let (name, name_value) = inner.iter().next().filter(|_| inner.len() == 1)?;
+    match (name, key_value, name_value) {
+        (Value::String(name), Value::Null, Value::Null) => Some(name.trim().to_string()),
+        _ => None,
+    }
+}
+
+fn substitute_string(
+    content: &str,
+    variables: &BTreeMap<String, Value>,
+    undefined: &mut BTreeSet<String>,
+) -> String {
+    let mut output = String::with_capacity(content.len());
+    let mut remaining = content;
+    while let Some(start) = remaining.find("{{") {
+        let Some(len) = remaining[start..].find("}}") else {
+            break;
+        };
+        output.push_str(&remaining[..start]);
+        let name = remaining[start + 2..start + len].trim();
+        match variables.get(name) {
+            Some(value) => output.push_str(&scalar_to_string(value)),
+            None => {
+                undefined.insert(name.to_string());
+            }
+        }
+        remaining = &remaining[start + len + 2..];
+    }
+    output.push_str(remaining);
+    output
+}
+
+fn substitute_value(
+    value: Value,
+    variables: &BTreeMap<String, Value>,
+    undefined: &mut BTreeSet<String>,
+) -> Value {
+    if let Some(name) = unquoted_placeholder(&value) {
+        return match variables.get(&name) {
+            Some(value) => value.clone(),
+            None => {
+                undefined.insert(name);
+                Value::Null
+            }
+        };
+    }
+    match value {
+        Value::String(content) => Value::String(substitute_string(&content, variables, undefined)),
+        Value::Sequence(values) => Value::Sequence(
+            values
+                .into_iter()
+                .map(|value| substitute_value(value, variables, undefined))
+                .collect(),
+        ),
+        Value::Mapping(entries) => Value::Mapping(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key, substitute_value(value, variables, undefined)))
+                .collect(),
+        ),
+        value => value,
+    }
+}
+
+/// Replace the `{{ name }}` placeholders of a parsed plan template. The values are inserted
+/// in the parsed document, never re-read as yaml. An unquoted placeholder (`cost: {{ fee }}`)
+/// takes the type of the variable, a placeholder inside a string (`- "u{{ amount }}"`) is
+/// replaced by the text of the variable.
+pub fn substitute_variables(
+    template: Value,
+    variables: &BTreeMap<String, Value>,
+) -> Result<Value, String> {
+    let mut undefined = BTreeSet::new();
+    let value = substitute_value(template, variables, &mut undefined);
+    if !undefined.is_empty() {
+        let names: Vec<String> = undefined.into_iter().collect();
+        return Err(format!("undefined variables: {}", names.join(", ")));
+    }
+    Ok(value)
+}
+
+/// Check that the arguments of the contract-calls of a plan are Clarity values admitted by
+/// the signature of the called function. Functions are looked up in `analyses`, by contract
+/// identifier first, then by contract name since the same project is deployed by a different
+/// deployer on each network.
+pub fn check_contract_calls_arguments(
+    deployment: &DeploymentSpecification,
+    analyses: &HashMap<QualifiedContractIdentifier, ContractAnalysis>,
+) -> Vec<String> {
+    let mut session = Session::new(SessionSettings::default());
+    let mut errors = vec![];
+    for batch in deployment.plan.batches.iter() {
+        for transaction in batch.transactions.iter() {
+            let TransactionSpecification::ContractCall(tx) = transaction else {
+                continue;
+            };
+            let analysis = analyses.get(&tx.contract_id).or_else(|| {
+                analyses
+                    .iter()
+                    .find(|(contract_id, _)| contract_id.name == tx.contract_id.name)
+                    .map(|(_, analysis)| analysis)
+            });
+            let function_type = analysis.and_then(|analysis| {
+                analysis
+                    .public_function_types
+                    .get(&tx.method)
+                    .map(|function_type| (analysis.epoch, function_type))
+            });
+            if let Some((_, FunctionType::Fixed(function))) = function_type {
+                if function.args.len() != tx.parameters.len() {
+                    errors.push(format!(
+                        "contract-call {}::{}: expected {} arguments, got {}",
+                        tx.contract_id,
+                        tx.method,
+                        function.args.len(),
+                        tx.parameters.len()
+                    ));
+                    continue;
+                }
+            }
+
+            for (index, parameter) in tx.parameters.iter().enumerate() {
+                let value = match session.eval(parameter.to_string(), false) {
+                    Ok(execution) => match execution.result {
+                        EvaluationResult::Snippet(result) => result.result,
+                        _ => unreachable!("Contract result from snippet"),
+                    },
+                    Err(_) => {
+                        errors.push(format!(
+                            "contract-call {}::{}: argument {} is not a valid Clarity value",
+                            tx.contract_id, tx.method, parameter
+                        ));
+                        continue;
+                    }
+                };
+                let Some((epoch, FunctionType::Fixed(function))) = function_type else {
+                    continue;
+                };
+                let arg = &function.args[index];
+                if !arg.signature.admits(&epoch, &value).unwrap_or(false) {
+                    errors.push(format!(
+                        "contract-call {}::{}: argument {} expects {}, got {}",
+                        tx.contract_id, tx.method, arg.name, arg.signature, parameter
+                    ));
+                }
+            }
+        }
+    }
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clarinet_files::StacksNetwork;
+    use clarity_repl::clarity::StacksEpochId;
+    use clarity_repl::repl::{ClarityCodeSource, ClarityContract, ContractDeployer};
+
+    const DEPLOYER: &str = "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM";
+
+    fn variables() -> BTreeMap<String, Value> {
+        BTreeMap::from([
+            ("deployer".to_string(), Value::String(DEPLOYER.to_string())),
+            ("fee_rate".to_string(), Value::Number(10.into())),
+            (
+                "memo".to_string(),
+                Value::String("a: b # not a comment".to_string()),
+            ),
+        ])
+    }
+
+    fn substitute(template: &str) -> Result<Value, String> {
+        substitute_variables(serde_yaml::from_str(template).unwrap(), &variables())
+    }
+
+    #[test]
+    fn test_substitute_variables() {
+        let template =
+            "expected-sender: {{ deployer }}\ncost: {{fee_rate}}\nmemo: \"{{ memo }}\"\n";
+        let value = substitute(template).unwrap();
+        assert_eq!(
+            value["expected-sender"],
+            Value::String(DEPLOYER.to_string())
+        );
+        // unquoted placeholders keep the type of the variable
+        assert_eq!(value["cost"], Value::Number(10.into()));
+        // values are never parsed as yaml
+        assert_eq!(
+            value["memo"],
+            Value::String("a: b # not a comment".to_string())
+        );
+
+        let value =
+            substitute("parameters:\n  - \"u{{ fee_rate }}\"\n  - \"'{{ deployer }}\"").unwrap();
+        assert_eq!(
+            value["parameters"],
+            serde_yaml::from_str::<Value>(&format!("[u10, \"'{}\"]", DEPLOYER)).unwrap()
+        );
+
+        let template =
+            "parameters:\n  - \"{{ amount }}\"\n  - {{ recipient }}\ncost: {{ fee_rate }}";
+        assert_eq!(
+            substitute(template).unwrap_err(),
+            "undefined variables: amount, recipient"
+        );
+    }
+
+    #[test]
+    fn test_load_variables() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("base.yaml");
+        let testnet = dir.path().join("testnet.yaml");
+        let invalid = dir.path().join("invalid.yaml");
+        std::fs::write(&base, "deployer: ST1\nfee_rate: 10\n").unwrap();
+        std::fs::write(&testnet, "fee_rate: 20\nenabled: true\n").unwrap();
+        std::fs::write(&invalid, "recipients: [ST1, ST2]\n").unwrap();
+
+        let variables = load_variables(&[
+            FileLocation::from_path(base.clone()),
+            FileLocation::from_path(testnet),
+        ])
+        .unwrap();
+        assert_eq!(variables["deployer"], Value::String("ST1".to_string()));
+        assert_eq!(variables["fee_rate"], Value::Number(20.into()));
+        assert_eq!(variables["enabled"], Value::Bool(true));
+
+        assert!(load_variables(&[
+            FileLocation::from_path(base),
+            FileLocation::from_path(invalid)
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_check_contract_calls_arguments() {
+        let mut session = Session::new(SessionSettings::default());
+        session.update_epoch(StacksEpochId::Epoch25);
+        let contract = ClarityContract {
+            code_source: ClarityCodeSource::ContractInMemory(
+                "(define-public (mint (amount uint) (recipient principal)) (ok true))".to_string(),
+            ),
+            name: "token".to_string(),
+            deployer: ContractDeployer::Address(DEPLOYER.to_string()),
+            clarity_version: clarity_repl::clarity::ClarityVersion::Clarity2,
+            epoch: StacksEpochId::Epoch25,
+        };
+        session.deploy_contract(&contract, false, None).unwrap();
+        let analyses: HashMap<_, _> = session
+            .contracts
+            .iter()
+            .map(|(contract_id, contract)| (contract_id.clone(), contract.analysis.clone()))
+            .collect();
+
+        // the same contract, deployed by another deployer on testnet
+        let testnet_deployer = "ST2CY5V39NHDPWSXMW9QDT3HC3GD6Q6XX4CFRK9AG";
+        let plan = |parameters: &[&str]| {
+            let template = format!(
+                "---\nid: 0\nname: Testnet deployment\nnetwork: testnet\nplan:\n  batches:\n    - id: 0\n      transactions:\n        - contract-call:\n            contract-id: {0}.token\n            expected-sender: {0}\n            method: mint\n            parameters: [{1}]\n            cost: 1000\n",
+                testnet_deployer,
+                parameters.join(", ")
+            );
+            let root = FileLocation::from_path(std::env::temp_dir());
+            DeploymentSpecification::from_config_content(template.as_bytes(), &root).unwrap()
+        };
+
+        let deployment = plan(&["u100", &format!("\"'{}\"", DEPLOYER)]);
+        assert_eq!(deployment.network, StacksNetwork::Testnet);
+        assert!(check_contract_calls_arguments(&deployment, &analyses).is_empty());
+
+        let errors = check_contract_calls_arguments(&plan(&["u100"]), &analyses);
+        assert_eq!(
+            errors,
+            vec![format!(
+                "contract-call {}.token::mint: expected 2 arguments, got 1",
+                testnet_deployer
+            )]
+        );
+
+        let errors = check_contract_calls_arguments(&plan(&["100", "(unknown"]), &analyses);
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].contains("argument amount expects uint, got 100"));
+        assert!(errors[1].contains("argument (unknown is not a valid Clarity value"));
+    }
+}
@@ -0,0 +1,97 @@
+use clarity::vm::types::PrincipalData;
+
+use crate::{DevnetConfig, StacksNetwork};
+
+pub const HIRO_EXPLORER_URL: &str = "https://explorer.hiro.so";
+
+/// Links to the explorer pages of transactions, addresses and contracts of a network:
+/// the explorer container of devnet, or the Hiro explorer for testnet and mainnet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExplorerLinks {
+    base_url: String,
+    chain: &'static str,
+}
+
+impl ExplorerLinks {
+    /// Simnet has no explorer, devnet links require the settings of Devnet.toml
+    pub fn for_network(network: &StacksNetwork, devnet: Option<&DevnetConfig>) -> Option<Self> {
+        let (base_url, chain) = match network {
+            StacksNetwork::Simnet => return None,
+            StacksNetwork::Devnet => (
+                format!("http://localhost:{}", devnet?.stacks_explorer_port),
+                "testnet",
+            ),
+            StacksNetwork::Testnet => (HIRO_EXPLORER_URL.to_string(), "testnet"),
+            StacksNetwork::Mainnet => (HIRO_EXPLORER_URL.to_string(), "mainnet"),
+        };
+        Some(ExplorerLinks { base_url, chain })
+    }
+
+    pub fn transaction(&self, txid: &str) -> String {
+        let txid = txid.trim_start_matches("0x");
+        format!("{}/txid/0x{}?chain={}", self.base_url, txid, self.chain)
+    }
+
+    pub fn address(&self, address: &str) -> String {
+        format!("{}/address/{}?chain={}", self.base_url, address, self.chain)
+    }
+
+    /// Contracts pages are indexed by contract identifier
+    pub fn contract(&self, contract_id: &str) -> String {
+        format!(
+            "{}/txid/{}?chain={}",
+            self.base_url, contract_id, self.chain
+        )
+    }
+
+    /// Link to a contract identifier, a principal or a txid
+    pub fn link(&self, resource: &str) -> String {
+        match PrincipalData::parse(resource) {
+            Ok(PrincipalData::Contract(_)) => self.contract(resource),
+            Ok(PrincipalData::Standard(_)) => self.address(resource),
+            Err(_) => self.transaction(resource),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_explorer_links() {
+        assert_eq!(
+            ExplorerLinks::for_network(&StacksNetwork::Simnet, None),
+            None
+        );
+        assert_eq!(
+            ExplorerLinks::for_network(&StacksNetwork::Devnet, None),
+            None
+        );
+
+        let links = ExplorerLinks::for_network(&StacksNetwork::Testnet, None).unwrap();
+        assert_eq!(
+            links.link("e3dc0b2a"),
+            "https://explorer.hiro.so/txid/0xe3dc0b2a?chain=testnet"
+        );
+        assert_eq!(links.link("0xe3dc0b2a"), links.link("e3dc0b2a"));
+        assert_eq!(
+            links.link("ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM"),
+            "https://explorer.hiro.so/address/ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM?chain=testnet"
+        );
+        assert_eq!(
+            links.link("ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.counter"),
+            "https://explorer.hiro.so/txid/ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.counter?chain=testnet"
+        );
+
+        // not a valid address (wrong checksum)
+        assert!(links
+            .link("ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGN")
+            .starts_with("https://explorer.hiro.so/txid/0x"));
+
+        let links = ExplorerLinks::for_network(&StacksNetwork::Mainnet, None).unwrap();
+        assert!(links
+            .address("SP000000000000000000002Q6VF78")
+            .ends_with("?chain=mainnet"));
+    }
+}
@@ -0,0 +1,147 @@
+use crate::plugins::named_enum;
+use std::str::FromStr;
+
+/// Marker identifying the hooks installed by clarinet, so that they can be
+/// overwritten by a later install while hooks written by hand are preserved.
+pub const GIT_HOOK_MARKER: &str = "# installed by `clarinet hook install`";
+
+named_enum! {
+    /// Git hooks running the gates declared in the `[project.git_hooks]` section
+    /// of Clarinet.toml:
+    ///
+    /// ```toml
+    /// [project.git_hooks]
+    /// pre-commit = ["check"]
+    /// pre-push = ["check", "test"]
+    /// ```
+    pub enum GitHook("git hook") {
+        PreCommit = "pre-commit",
+        PrePush = "pre-push",
+    }
+}
+
+impl GitHook {
+    /// Gates used when the manifest does not declare any
+    pub fn default_gates(&self) -> Vec<GitHookGate> {
+        match self {
+            GitHook::PreCommit => vec![GitHookGate::Check],
+            GitHook::PrePush => vec![GitHookGate::Check, GitHookGate::Test],
+        }
+    }
+
+    /// Shell script invoking `clarinet hook run` for this hook, with the clarinet
+    /// executable at `clarinet_path`
+    pub fn script(&self, clarinet_path: &str, manifest_path: &str) -> String {
+        format!(
+            "#!/bin/sh\n{}\nexec {} hook run {} --manifest-path {}\n",
+            GIT_HOOK_MARKER,
+            shell_words::quote(clarinet_path),
+            self.as_str(),
+            shell_words::quote(manifest_path)
+        )
+    }
+}
+
+named_enum! {
+    /// Checks a git hook can run:
+    /// - `check`: analysis of the contracts, including the lints enabled in `[repl.analysis]`
+    /// - `test`: the project tests, limited to the files affected by the changes on pre-commit
+    pub enum GitHookGate("git hook gate") {
+        Check = "check",
+        Test = "test",
+    }
+}
+
+/// Gates configured for `hook`, in the order they are declared
+pub fn get_git_hook_gates(manifest: &crate::ProjectManifest, hook: GitHook) -> Vec<GitHookGate> {
+    match manifest.project.git_hooks.get(hook.as_str()) {
+        Some(gates) => gates
+            .iter()
+            .filter_map(|gate| GitHookGate::from_str(gate).ok())
+            .collect(),
+        None if manifest.project.git_hooks.is_empty() => hook.default_gates(),
+        None => vec![],
+    }
+}
+
+/// Write the git hooks of the project in the hooks directory of its repository.
+/// Hooks that were not installed by clarinet are only replaced with `force`.
+#[cfg(feature = "cli")]
+pub fn install_git_hooks(
+    manifest: &crate::ProjectManifest,
+    force: bool,
+) -> Result<Vec<String>, String> {
+    use std::path::PathBuf;
+    use std::process::Command;
+
+    let project_root = manifest.location.get_project_root_location()?;
+    let output = Command::new("git")
+        .args(["rev-parse", "--git-path", "hooks"])
+        .current_dir(project_root.to_string())
+        .output()
+        .map_err(|e| format!("unable to run git ({})", e))?;
+    if !output.status.success() {
+        return Err("project is not in a git repository".to_string());
+    }
+    // the path is relative to the project root, unless the hooks are outside of it
+    let hooks_dir = PathBuf::from(project_root.to_string())
+        .join(String::from_utf8_lossy(&output.stdout).trim());
+    std::fs::create_dir_all(&hooks_dir)
+        .map_err(|e| format!("unable to create {} ({})", hooks_dir.display(), e))?;
+
+    let clarinet_path = std::env::current_exe()
+        .map_err(|e| format!("unable to locate the clarinet executable ({})", e))?;
+    let mut installed = vec![];
+    for hook in GitHook::all() {
+        if get_git_hook_gates(manifest, hook).is_empty() {
+            continue;
+        }
+        let path = hooks_dir.join(hook.as_str());
+        if let Ok(existing) = std::fs::read_to_string(&path) {
+            if !existing.contains(GIT_HOOK_MARKER) && !force {
+                return Err(format!(
+                    "{} already exists, use --force to replace it",
+                    path.display()
+                ));
+            }
+        }
+        let script = hook.script(
+            &clarinet_path.display().to_string(),
+            &manifest.location.to_string(),
+        );
+        std::fs::write(&path, script)
+            .map_err(|e| format!("unable to write {} ({})", path.display(), e))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))
+                .map_err(|e| format!("unable to make {} executable ({})", path.display(), e))?;
+        }
+        installed.push(path.display().to_string());
+    }
+    Ok(installed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_git_hook_names() {
+        for hook in GitHook::all() {
+            assert_eq!(GitHook::from_str(hook.as_str()), Ok(hook));
+        }
+        for gate in GitHookGate::all() {
+            assert_eq!(GitHookGate::from_str(gate.as_str()), Ok(gate));
+        }
+        assert!(GitHookGate::from_str("format").is_err());
+
+        let script =
+            GitHook::PrePush.script("/opt/clarinet/bin/clarinet", "/my project/Clarinet.toml");
+        assert!(script.starts_with("#!/bin/sh\n"));
+        assert!(script.contains(GIT_HOOK_MARKER));
+        assert!(script.ends_with(
+            "exec /opt/clarinet/bin/clarinet hook run pre-push --manifest-path '/my project/Clarinet.toml'\n"
+        ));
+    }
+}
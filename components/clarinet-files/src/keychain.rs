@@ -0,0 +1,493 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
+
+use bip39::Mnemonic;
+use clarity::util::hash::{bytes_to_hex, hex_bytes};
+use libsecp256k1::SecretKey;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::pbkdf2;
+use ring::rand::{SecureRandom, SystemRandom};
+
+use crate::network_manifest::{
+    compute_addresses, compute_addresses_from_secret_key, parse_mnemonic_words,
+};
+use crate::{AccountConfig, BitcoinNetwork, StacksNetwork, DEFAULT_DERIVATION_PATH};
+
+/// Environment variable read for unlocking keychain accounts referenced in settings files.
+pub const KEYCHAIN_PASSWORD_ENV: &str = "CLARINET_KEYCHAIN_PASSWORD";
+
+const PBKDF2_ITERATIONS: u32 = 100_000;
+const SALT_LEN: usize = 16;
+const KEY_LEN: usize = 32;
+
+/// Secret of a keychain account, never written on disk unencrypted.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case", tag = "type", content = "value")]
+pub enum AccountSecret {
+    Mnemonic(String),
+    SecretKey(String),
+}
+
+impl AccountSecret {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AccountSecret::Mnemonic(_) => "mnemonic",
+            AccountSecret::SecretKey(_) => "secret_key",
+        }
+    }
+
+    pub fn reveal(&self) -> &str {
+        match self {
+            AccountSecret::Mnemonic(value) | AccountSecret::SecretKey(value) => value,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AccountAddresses {
+    pub stx_address: String,
+    pub btc_address: String,
+}
+
+/// Named account stored in `~/.clarinet/keychain/<name>.json`.
+/// The addresses derived for each network are kept in clear, so that accounts
+/// can be listed without a password, while the secret is encrypted with AES-256-GCM,
+/// using a key derived from the password with PBKDF2-HMAC-SHA256.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct KeychainAccount {
+    pub name: String,
+    pub kind: String,
+    pub derivation: String,
+    pub addresses: BTreeMap<String, AccountAddresses>,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+pub fn keychain_dir() -> Result<PathBuf, String> {
+    let home_dir = dirs::home_dir().ok_or("unable to locate home directory")?;
+    Ok(home_dir.join(".clarinet").join("keychain"))
+}
+
+fn account_path(name: &str) -> Result<PathBuf, String> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!(
+            "invalid account name '{}' (expected letters, digits, '-' or '_')",
+            name
+        ));
+    }
+    Ok(keychain_dir()?.join(format!("{}.json", name)))
+}
+
+/// Write `content` to a file only readable by its owner, the keychain directory is
+/// restricted the same way
+fn write_private_file(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    // the mode only applies to new files
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+    file.write_all(content)
+}
+
+fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(dir)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+    }
+    Ok(())
+}
+
+fn derive_encryption_key(password: &str, salt: &[u8]) -> Result<LessSafeKey, String> {
+    let mut key = [0u8; KEY_LEN];
+    pbkdf2::derive(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        NonZeroU32::new(PBKDF2_ITERATIONS).unwrap(),
+        salt,
+        password.as_bytes(),
+        &mut key,
+    );
+    let key = UnboundKey::new(&AES_256_GCM, &key).map_err(|_| "unable to derive key")?;
+    Ok(LessSafeKey::new(key))
+}
+
+/// Generate a new english mnemonic of 12 or 24 words.
+pub fn generate_mnemonic(words: usize) -> Result<String, String> {
+    let mut entropy = match words {
+        12 => vec![0u8; 16],
+        24 => vec![0u8; 32],
+        _ => return Err(format!("unsupported mnemonic length {} (12 or 24)", words)),
+    };
+    SystemRandom::new()
+        .fill(&mut entropy)
+        .map_err(|_| "unable to generate entropy")?;
+    let mnemonic = Mnemonic::from_entropy(&entropy).map_err(|e| e.to_string())?;
+    Ok(mnemonic.to_string())
+}
+
+/// Parse a mnemonic, or a 32 bytes hex encoded secret key (optionally suffixed with
+/// the `01` compression flag used by Stacks).
+pub fn parse_account_secret(input: &str, is_secret_key: bool) -> Result<AccountSecret, String> {
+    let input = input.trim();
+    if !is_secret_key {
+        let mnemonic =
+            parse_mnemonic_words(input).map_err(|e| format!("invalid mnemonic: {}", e))?;
+        return Ok(AccountSecret::Mnemonic(mnemonic.to_string()));
+    }
+    let bytes = hex_bytes(input.trim_start_matches("0x"))
+        .map_err(|_| "invalid secret key: expected hex encoding".to_string())?;
+    let bytes = match bytes.len() {
+        32 => bytes,
+        33 if bytes[32] == 1 => bytes[..32].to_vec(),
+        _ => return Err("invalid secret key: expected 32 bytes".to_string()),
+    };
+    SecretKey::parse_slice(&bytes).map_err(|e| format!("invalid secret key: {:?}", e))?;
+    Ok(AccountSecret::SecretKey(bytes_to_hex(&bytes)))
+}
+
+fn compute_account_addresses(
+    secret: &AccountSecret,
+    derivation: &str,
+) -> Result<BTreeMap<String, AccountAddresses>, String> {
+    let networks = [
+        ("mainnet", (BitcoinNetwork::Mainnet, StacksNetwork::Mainnet)),
+        ("testnet", (BitcoinNetwork::Testnet, StacksNetwork::Testnet)),
+        ("devnet", (BitcoinNetwork::Regtest, StacksNetwork::Devnet)),
+    ];
+    let mut addresses = BTreeMap::new();
+    for (name, networks) in networks.iter() {
+        let (stx_address, btc_address, _) = match secret {
+            AccountSecret::Mnemonic(mnemonic) => compute_addresses(mnemonic, derivation, networks),
+            AccountSecret::SecretKey(secret_key) => {
+                let bytes = hex_bytes(secret_key).map_err(|_| "invalid secret key")?;
+                let secret_key = SecretKey::parse_slice(&bytes)
+                    .map_err(|e| format!("invalid secret key: {:?}", e))?;
+                compute_addresses_from_secret_key(&secret_key, networks)
+            }
+        };
+        addresses.insert(
+            name.to_string(),
+            AccountAddresses {
+                stx_address,
+                btc_address,
+            },
+        );
+    }
+    Ok(addresses)
+}
+
+impl KeychainAccount {
+    pub fn new(
+        name: &str,
+        secret: &AccountSecret,
+        derivation: Option<&str>,
+        password: &str,
+    ) -> Result<KeychainAccount, String> {
+        account_path(name)?;
+        let derivation = derivation.unwrap_or(DEFAULT_DERIVATION_PATH).to_string();
+        let addresses = compute_account_addresses(secret, &derivation)?;
+
+        let rng = SystemRandom::new();
+        let mut salt = [0u8; SALT_LEN];
+        let mut nonce = [0u8; NONCE_LEN];
+        rng.fill(&mut salt)
+            .and_then(|_| rng.fill(&mut nonce))
+            .map_err(|_| "unable to generate entropy")?;
+
+        let key = derive_encryption_key(password, &salt)?;
+        let mut ciphertext = serde_json::to_vec(secret).map_err(|e| e.to_string())?;
+        key.seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(name.as_bytes()),
+            &mut ciphertext,
+        )
+        .map_err(|_| "unable to encrypt account")?;
+
+        Ok(KeychainAccount {
+            name: name.to_string(),
+            kind: secret.kind().to_string(),
+            derivation,
+            addresses,
+            salt: bytes_to_hex(&salt),
+            nonce: bytes_to_hex(&nonce),
+            ciphertext: bytes_to_hex(&ciphertext),
+        })
+    }
+
+    pub fn decrypt(&self, password: &str) -> Result<AccountSecret, String> {
+        let corrupted = || format!("keychain account '{}' is corrupted", self.name);
+        let salt = hex_bytes(&self.salt).map_err(|_| corrupted())?;
+        let nonce: [u8; NONCE_LEN] = hex_bytes(&self.nonce)
+            .ok()
+            .and_then(|nonce| nonce.try_into().ok())
+            .ok_or_else(corrupted)?;
+        let mut ciphertext = hex_bytes(&self.ciphertext).map_err(|_| corrupted())?;
+
+        let key = derive_encryption_key(password, &salt)?;
+        let plaintext = key
+            .open_in_place(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(self.name.as_bytes()),
+                &mut ciphertext,
+            )
+            .map_err(|_| format!("invalid password for keychain account '{}'", self.name))?;
+        serde_json::from_slice(plaintext).map_err(|_| corrupted())
+    }
+
+    pub fn exists(name: &str) -> Result<bool, String> {
+        Ok(account_path(name)?.exists())
+    }
+
+    pub fn load(name: &str) -> Result<KeychainAccount, String> {
+        let path = account_path(name)?;
+        let content = fs::read_to_string(&path)
+            .map_err(|_| format!("keychain account '{}' not found", name))?;
+        serde_json::from_str(&content)
+            .map_err(|e| format!("unable to parse {}: {}", path.display(), e))
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        self.save_in(&keychain_dir()?)
+    }
+
+    fn save_in(&self, dir: &Path) -> Result<(), String> {
+        account_path(&self.name)?;
+        create_private_dir(dir)
+            .map_err(|e| format!("unable to create {}: {}", dir.display(), e))?;
+        let path = dir.join(format!("{}.json", self.name));
+        let content = serde_json::to_vec_pretty(self).map_err(|e| e.to_string())?;
+        write_private_file(&path, &content)
+            .map_err(|e| format!("unable to write {}: {}", path.display(), e))
+    }
+
+    /// Accounts of the keychain, sorted by name, and the files that could not be loaded
+    pub fn list() -> Result<(Vec<KeychainAccount>, Vec<String>), String> {
+        Self::list_in(&keychain_dir()?)
+    }
+
+    fn list_in(dir: &Path) -> Result<(Vec<KeychainAccount>, Vec<String>), String> {
+        if !dir.exists() {
+            return Ok((vec![], vec![]));
+        }
+        let entries =
+            fs::read_dir(dir).map_err(|e| format!("unable to read {}: {}", dir.display(), e))?;
+        let mut accounts = vec![];
+        let mut warnings = vec![];
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            let account = fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|content| {
+                    serde_json::from_str::<KeychainAccount>(&content).map_err(|e| e.to_string())
+                });
+            match account {
+                Ok(account) => accounts.push(account),
+                Err(e) => warnings.push(format!("skipping {}: {}", path.display(), e)),
+            }
+        }
+        accounts.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok((accounts, warnings))
+    }
+
+    /// Account of the keychain whose address on `network` is `stx_address`
+    pub fn find_by_stx_address(
+        stx_address: &str,
+        network: &StacksNetwork,
+    ) -> Result<Option<KeychainAccount>, String> {
+        let (accounts, _) = Self::list()?;
+        Ok(accounts.into_iter().find(|account| {
+            account
+                .addresses
+                .get(network_key(network))
+                .map(|addresses| addresses.stx_address == stx_address)
+                .unwrap_or(false)
+        }))
+    }
+
+    pub fn addresses_on(&self, network: &StacksNetwork) -> Result<&AccountAddresses, String> {
+        self.addresses.get(network_key(network)).ok_or_else(|| {
+            format!(
+                "keychain account '{}' has no address for {:?}",
+                self.name, network
+            )
+        })
+    }
+
+    /// Account usable in settings files and deployment plans, `balance` only matters on devnet
+    pub fn to_account_config(
+        &self,
+        secret: &AccountSecret,
+        label: &str,
+        balance: u64,
+        network: &StacksNetwork,
+    ) -> Result<AccountConfig, String> {
+        let addresses = self.addresses_on(network)?;
+        let (mnemonic, derivation, secret_key) = match secret {
+            AccountSecret::Mnemonic(mnemonic) => (mnemonic.clone(), self.derivation.clone(), None),
+            AccountSecret::SecretKey(secret_key) => {
+                (String::new(), String::new(), Some(secret_key.clone()))
+            }
+        };
+        Ok(AccountConfig {
+            label: label.to_string(),
+            mnemonic,
+            derivation,
+            secret_key,
+            balance,
+            stx_address: addresses.stx_address.clone(),
+            btc_address: addresses.btc_address.clone(),
+            is_mainnet: matches!(network, StacksNetwork::Mainnet),
+        })
+    }
+}
+
+fn network_key(network: &StacksNetwork) -> &'static str {
+    match network {
+        StacksNetwork::Mainnet => "mainnet",
+        StacksNetwork::Testnet => "testnet",
+        StacksNetwork::Devnet | StacksNetwork::Simnet => "devnet",
+    }
+}
+
+/// Unlock the keychain account `name`, referenced in a settings file with
+/// `keychain = "<name>"` or in a deployment plan with `keychain:<name>`. The password is
+/// read from the `CLARINET_KEYCHAIN_PASSWORD` environment variable.
+pub fn resolve_keychain_account(
+    name: &str,
+    label: &str,
+    balance: u64,
+    network: &StacksNetwork,
+) -> Result<AccountConfig, String> {
+    let account = KeychainAccount::load(name)?;
+    let password = std::env::var(KEYCHAIN_PASSWORD_ENV).map_err(|_| {
+        format!(
+            "{} must be set to unlock keychain account '{}'",
+            KEYCHAIN_PASSWORD_ENV, name
+        )
+    })?;
+    let secret = account.decrypt(&password)?;
+    account.to_account_config(&secret, label, balance, network)
+}
+
+/// Address of the sender of a deployment plan transaction: `keychain:<name>` is replaced
+/// by the address of the keychain account on `network`, other senders are kept as is.
+pub fn resolve_keychain_sender(
+    sender: &str,
+    network: &StacksNetwork,
+    is_bitcoin: bool,
+) -> Result<String, String> {
+    let Some(name) = sender.strip_prefix("keychain:") else {
+        return Ok(sender.to_string());
+    };
+    let account = KeychainAccount::load(name)?;
+    let addresses = account.addresses_on(network)?;
+    Ok(match is_bitcoin {
+        true => addresses.btc_address.clone(),
+        false => addresses.stx_address.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use unicode_normalization::UnicodeNormalization;
+
+    #[test]
+    fn test_keychain_account_encryption() {
+        let mnemonic = generate_mnemonic(24).unwrap();
+        let secret = parse_account_secret(&mnemonic, false).unwrap();
+        let account = KeychainAccount::new("alice", &secret, None, "p4ssw0rd").unwrap();
+
+        assert_eq!(account.kind, "mnemonic");
+        assert!(!account
+            .ciphertext
+            .contains(&bytes_to_hex(mnemonic.as_bytes())));
+        assert_eq!(account.decrypt("p4ssw0rd"), Ok(secret));
+        assert!(account.decrypt("password").is_err());
+
+        let mut renamed = account.clone();
+        renamed.name = "bob".to_string();
+        assert!(renamed.decrypt("p4ssw0rd").is_err());
+    }
+
+    #[test]
+    fn test_parse_account_secret() {
+        let key = "7287ba251d44a4d3fd9276c88ce34c5c52a038955511cccaf77e61068649c178";
+        assert_eq!(
+            parse_account_secret(&format!("{}01", key), true),
+            Ok(AccountSecret::SecretKey(key.to_string()))
+        );
+        assert!(parse_account_secret("00", true).is_err());
+        assert!(parse_account_secret("not a valid mnemonic", false).is_err());
+
+        let mnemonic = Mnemonic::from_entropy_in(bip39::Language::Spanish, &[1u8; 32]).unwrap();
+        let composed = mnemonic.to_string().nfc().collect::<String>();
+        assert_eq!(
+            parse_account_secret(&composed, false),
+            Ok(AccountSecret::Mnemonic(mnemonic.to_string()))
+        );
+    }
+
+    #[test]
+    fn test_keychain_files() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path().join("keychain");
+        let secret = parse_account_secret(&generate_mnemonic(12).unwrap(), false).unwrap();
+        let account = KeychainAccount::new("alice", &secret, None, "p4ssw0rd").unwrap();
+        account.save_in(&dir).unwrap();
+        fs::write(dir.join("corrupted.json"), "{").unwrap();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let metadata = fs::metadata(dir.join("alice.json")).unwrap();
+            assert_eq!(metadata.permissions().mode() & 0o777, 0o600);
+        }
+
+        let (accounts, warnings) = KeychainAccount::list_in(&dir).unwrap();
+        assert_eq!(accounts.len(), 1);
+        assert_eq!(accounts[0].name, "alice");
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("corrupted.json"));
+    }
+
+    #[test]
+    fn test_secret_key_account_config() {
+        let key = "7287ba251d44a4d3fd9276c88ce34c5c52a038955511cccaf77e61068649c178";
+        let secret = parse_account_secret(key, true).unwrap();
+        let account = KeychainAccount::new("bob", &secret, None, "p4ssw0rd").unwrap();
+        let config = account
+            .to_account_config(&secret, "deployer", 0, &StacksNetwork::Testnet)
+            .unwrap();
+        assert_eq!(config.secret_key_hex(), format!("{}01", key));
+        assert_eq!(config.stx_address, account.addresses["testnet"].stx_address);
+        assert!(!config.is_mainnet);
+
+        let sender = "ST1SJ3DTE5DN7X54YDH5D64R3BCB6A2AG2ZQ8YPD5";
+        assert_eq!(
+            resolve_keychain_sender(sender, &StacksNetwork::Testnet, false),
+            Ok(sender.to_string())
+        );
+    }
+}
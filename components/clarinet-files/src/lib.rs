@@ -6,7 +6,14 @@ extern crate serde_derive;
 pub extern crate bip39;
 pub extern crate url;
 
+pub mod explorer;
+pub mod git_hooks;
+#[cfg(feature = "cli")]
+pub mod keychain;
 mod network_manifest;
+pub mod plugins;
+#[cfg(feature = "cli")]
+pub mod principals;
 mod project_manifest;
 
 pub use network_manifest::{BitcoinNetwork, StacksNetwork};
@@ -17,8 +24,8 @@ mod wasm_fs_accessor;
 pub use wasm_fs_accessor::WASMFileSystemAccessor;
 
 pub use network_manifest::{
-    compute_addresses, AccountConfig, DevnetConfig, DevnetConfigFile, NetworkManifest,
-    NetworkManifestFile, PoxStackingOrder, DEFAULT_BITCOIN_EXPLORER_IMAGE,
+    compute_addresses, AccountConfig, DevnetConfig, DevnetConfigFile, GenesisMint, NetworkManifest,
+    NetworkManifestFile, NetworkOverrides, PoxStackingOrder, DEFAULT_BITCOIN_EXPLORER_IMAGE,
     DEFAULT_BITCOIN_NODE_IMAGE, DEFAULT_DERIVATION_PATH, DEFAULT_DOCKER_PLATFORM,
     DEFAULT_EPOCH_2_0, DEFAULT_EPOCH_2_05, DEFAULT_EPOCH_2_1, DEFAULT_EPOCH_2_2, DEFAULT_EPOCH_2_3,
     DEFAULT_EPOCH_2_4, DEFAULT_EPOCH_2_5, DEFAULT_EPOCH_3_0, DEFAULT_FAUCET_MNEMONIC,
@@ -410,3 +417,227 @@ pub fn get_manifest_location(path: Option<String>) -> Option<FileLocation> {
         }
     }
 }
+
+/// Parse the settings file at `location`, reporting the position of the errors
+/// along with a hint on how to fix the most common ones.
+/// `${NAME}` in string values is replaced with the environment variable `NAME`, except
+/// on wasm where there is no environment to read from.
+pub fn parse_toml_file<T: serde::de::DeserializeOwned>(
+    content: &[u8],
+    location: &FileLocation,
+) -> Result<T, String> {
+    // values are only rebuilt when needed, deserializing a toml::Value loses the errors positions
+    if cfg!(target_family = "wasm") || !content.windows(2).any(|w| w == b"${") {
+        return toml::from_slice(content).map_err(|e| format_toml_error(e, location));
+    }
+    let mut value: toml::Value =
+        toml::from_slice(content).map_err(|e| format_toml_error(e, location))?;
+    interpolate_env_vars(&mut value).map_err(|e| format!("{}: {}", location, e))?;
+    value.try_into().map_err(|e| format_toml_error(e, location))
+}
+
+fn format_toml_error(e: toml::de::Error, location: &FileLocation) -> String {
+    let mut message = e.to_string();
+    if let Some((line, col)) = e.line_col() {
+        let position = format!(" at line {} column {}", line + 1, col + 1);
+        if let Some(stripped) = message.strip_suffix(&position) {
+            message = stripped.to_string();
+        }
+        message = format!("{}:{}:{}: {}", location, line + 1, col + 1, message);
+    } else {
+        message = format!("{}: {}", location, message);
+    }
+    match toml_error_hint(&message) {
+        Some(hint) => format!("{}\nhint: {}", message, hint),
+        None => message,
+    }
+}
+
+fn interpolate_env_vars(value: &mut toml::Value) -> Result<(), String> {
+    match value {
+        toml::Value::String(string) => *string = interpolate_string(string)?,
+        toml::Value::Array(values) => {
+            for value in values.iter_mut() {
+                interpolate_env_vars(value)?;
+            }
+        }
+        toml::Value::Table(table) => {
+            for (_, value) in table.iter_mut() {
+                interpolate_env_vars(value)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn interpolate_string(source: &str) -> Result<String, String> {
+    let mut result = String::with_capacity(source.len());
+    let mut rest = source;
+    while let Some(start) = rest.find("${") {
+        result.push_str(&rest[..start]);
+        let Some(len) = rest[start..].find('}') else {
+            return Err(format!("unterminated variable in \"{}\"", source));
+        };
+        let name = &rest[start + 2..start + len];
+        let value =
+            std::env::var(name).map_err(|_| format!("environment variable {} is not set", name))?;
+        result.push_str(&value);
+        rest = &rest[start + len + 1..];
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+/// Keys of the table `section` of `value` (each table of it if it is an array of tables)
+/// that are not fields of `T`. They are ignored when the file is parsed, so a typo goes
+/// unnoticed unless they are reported as warnings.
+pub fn toml_unknown_keys<T: serde::de::DeserializeOwned>(
+    value: &toml::Value,
+    section: &str,
+    location: &FileLocation,
+) -> Vec<String> {
+    let Some(fields) = struct_fields::<T>() else {
+        return vec![];
+    };
+    let mut tables = vec![];
+    let section_value = section
+        .split('.')
+        .try_fold(value, |value, key| value.get(key));
+    match section_value {
+        Some(toml::Value::Table(table)) => tables.push(table),
+        Some(toml::Value::Array(values)) => {
+            tables.extend(values.iter().filter_map(|value| value.as_table()))
+        }
+        _ => {}
+    }
+    let mut warnings = vec![];
+    for table in tables {
+        for key in table.keys() {
+            if !fields.contains(&key.as_str()) {
+                warnings.push(format!(
+                    "{}: unknown key `{}` in [{}] is ignored",
+                    location, key, section
+                ));
+            }
+        }
+    }
+    warnings
+}
+
+/// Fields of the struct `T`, as listed by its derived `Deserialize` implementation.
+/// `None` for the other types, and for the structs with flattened fields.
+fn struct_fields<T: serde::de::DeserializeOwned>() -> Option<&'static [&'static str]> {
+    use serde::de::{Error, Visitor};
+
+    struct FieldsDeserializer(Option<&'static [&'static str]>);
+
+    impl<'de> serde::Deserializer<'de> for &mut FieldsDeserializer {
+        type Error = serde::de::value::Error;
+
+        fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+            Err(Error::custom("not a struct"))
+        }
+
+        fn deserialize_struct<V: Visitor<'de>>(
+            self,
+            _name: &'static str,
+            fields: &'static [&'static str],
+            _visitor: V,
+        ) -> Result<V::Value, Self::Error> {
+            self.0 = Some(fields);
+            Err(Error::custom("fields collected"))
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes
+            byte_buf option unit unit_struct newtype_struct seq tuple tuple_struct map enum
+            identifier ignored_any
+        }
+    }
+
+    let mut deserializer = FieldsDeserializer(None);
+    let _ = T::deserialize(&mut deserializer);
+    deserializer.0
+}
+
+fn toml_error_hint(message: &str) -> Option<&'static str> {
+    if message.contains("missing field") {
+        Some("this field is required")
+    } else if message.contains("invalid type") {
+        Some("check the type of the value, strings must be quoted")
+    } else if message.contains("duplicate key") {
+        Some("a key can only be defined once in a table")
+    } else if message.contains("expected an equals") {
+        Some("keys and values are separated with `=`")
+    } else if message.contains("unterminated string") {
+        Some("a closing quote is missing")
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct Settings {
+        name: String,
+        port: Option<u16>,
+    }
+
+    #[test]
+    fn test_parse_toml_file() {
+        let location = FileLocation::from_path(PathBuf::from("/tmp/settings/Devnet.toml"));
+
+        let settings: Settings = parse_toml_file(b"name = \"devnet\"", &location).unwrap();
+        assert_eq!(settings.name, "devnet");
+
+        let error = parse_toml_file::<Settings>(b"name = \"devnet\"\nport = \"20443\"", &location)
+            .unwrap_err();
+        assert!(error.starts_with("/tmp/settings/Devnet.toml"));
+        assert!(error.ends_with("hint: check the type of the value, strings must be quoted"));
+
+        let error = parse_toml_file::<Settings>(b"port = 20443", &location).unwrap_err();
+        assert!(error.contains("missing field `name`"));
+        assert!(error.ends_with("hint: this field is required"));
+    }
+
+    #[test]
+    fn test_toml_unknown_keys() {
+        let location = FileLocation::from_path(PathBuf::from("/tmp/settings/Devnet.toml"));
+        let value: toml::Value = toml::from_str(
+            "[devnet]\nname = \"devnet\"\nprot = 20443\n\n[[mints]]\nname = \"a\"\n\n[[mints]]\nnmae = \"b\"",
+        )
+        .unwrap();
+        assert_eq!(
+            toml_unknown_keys::<Settings>(&value, "devnet", &location),
+            vec!["/tmp/settings/Devnet.toml: unknown key `prot` in [devnet] is ignored"]
+        );
+        assert_eq!(
+            toml_unknown_keys::<Settings>(&value, "mints", &location),
+            vec!["/tmp/settings/Devnet.toml: unknown key `nmae` in [mints] is ignored"]
+        );
+        assert!(toml_unknown_keys::<Settings>(&value, "network", &location).is_empty());
+    }
+
+    #[test]
+    fn test_parse_toml_file_with_env_vars() {
+        let location = FileLocation::from_path(PathBuf::from("/tmp/settings/Testnet.toml"));
+        std::env::set_var("CLARINET_TEST_NETWORK_NAME", "testnet");
+
+        let settings: Settings =
+            parse_toml_file(b"name = \"${CLARINET_TEST_NETWORK_NAME}-1\"", &location).unwrap();
+        assert_eq!(settings.name, "testnet-1");
+
+        let error =
+            parse_toml_file::<Settings>(b"name = \"${CLARINET_TEST_UNSET_VAR}\"", &location)
+                .unwrap_err();
+        assert_eq!(
+            error,
+            "/tmp/settings/Testnet.toml: environment variable CLARINET_TEST_UNSET_VAR is not set"
+        );
+    }
+}
@@ -1,17 +1,18 @@
 use std::collections::BTreeMap;
 
-use super::{FileAccessor, FileLocation};
-use bip39::{Language, Mnemonic};
+use super::{parse_toml_file, toml_unknown_keys, FileAccessor, FileLocation};
+use bip39::Mnemonic;
 use clarinet_utils::get_bip39_seed_from_mnemonic;
 use clarity::address::AddressHashMode;
 use clarity::types::chainstate::{StacksAddress, StacksPrivateKey};
 use clarity::util::{hash::bytes_to_hex, secp256k1::Secp256k1PublicKey};
-use clarity::vm::types::QualifiedContractIdentifier;
+use clarity::vm::types::{PrincipalData, QualifiedContractIdentifier};
 use lazy_static::lazy_static;
 use libsecp256k1::{PublicKey, SecretKey};
 use serde::Serialize;
 use tiny_hderive::bip32::ExtendedPrivKey;
 use toml::value::Value;
+use unicode_normalization::UnicodeNormalization;
 
 pub const DEFAULT_DERIVATION_PATH: &str = "m/44'/5757'/0'/0/0";
 
@@ -103,9 +104,73 @@ pub struct NetworkConfigFile {
     name: String,
     node_rpc_address: Option<String>,
     stacks_node_rpc_address: Option<String>,
+    stacks_node_rpc_fallback_addresses: Option<Vec<String>>,
     bitcoin_node_rpc_address: Option<String>,
     deployment_fee_rate: Option<u64>,
+    deployment_cost_limit: Option<u64>,
     sats_per_bytes: Option<u64>,
+    principals: Option<BTreeMap<String, String>>,
+}
+
+/// Settings of a network overridden by a `[network.<name>]` table of Clarinet.toml
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct NetworkOverrides {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stacks_node_rpc_address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bitcoin_node_rpc_address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deployment_fee_rate: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deployment_cost_limit: Option<u64>,
+    /// Accounts deploying the contracts on this network, by contract name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub deployers: BTreeMap<String, String>,
+}
+
+impl NetworkOverrides {
+    /// Name of the table overriding the settings of `network`, simnet uses the devnet settings
+    pub fn table_name(network: &StacksNetwork) -> &'static str {
+        match network {
+            StacksNetwork::Simnet | StacksNetwork::Devnet => "devnet",
+            StacksNetwork::Testnet => "testnet",
+            StacksNetwork::Mainnet => "mainnet",
+        }
+    }
+
+    pub fn apply(&self, network: &mut NetworkConfig) {
+        if let Some(ref url) = self.stacks_node_rpc_address {
+            network.stacks_node_rpc_address = Some(url.clone());
+        }
+        if let Some(ref url) = self.bitcoin_node_rpc_address {
+            network.bitcoin_node_rpc_address = Some(url.clone());
+        }
+        if let Some(fee_rate) = self.deployment_fee_rate {
+            network.deployment_fee_rate = fee_rate;
+        }
+        if let Some(cost_limit) = self.deployment_cost_limit {
+            network.deployment_cost_limit = Some(cost_limit);
+        }
+    }
+}
+
+/// The `[network.<name>]` tables of Clarinet.toml, the rest of the file is ignored
+#[derive(Deserialize, Debug, Default)]
+struct ProjectNetworksFile {
+    network: Option<BTreeMap<String, NetworkOverrides>>,
+}
+
+impl ProjectNetworksFile {
+    fn overrides(
+        content: &[u8],
+        location: &FileLocation,
+        network: &StacksNetwork,
+    ) -> Result<Option<NetworkOverrides>, String> {
+        let file: ProjectNetworksFile = parse_toml_file(content, location)?;
+        Ok(file
+            .network
+            .and_then(|mut networks| networks.remove(NetworkOverrides::table_name(network))))
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Default)]
@@ -114,6 +179,8 @@ pub struct DevnetConfigFile {
     pub network_id: Option<u16>,
     pub orchestrator_port: Option<u16>,
     pub orchestrator_control_port: Option<u16>,
+    pub faucet_account: Option<String>,
+    pub orchestrator_metrics_port: Option<u16>,
     pub bitcoin_node_p2p_port: Option<u16>,
     pub bitcoin_node_rpc_port: Option<u16>,
     pub stacks_node_p2p_port: Option<u16>,
@@ -152,6 +219,7 @@ pub struct DevnetConfigFile {
     pub stacks_api_postgres_database: Option<String>,
     pub subnet_api_postgres_database: Option<String>,
     pub pox_stacking_orders: Option<Vec<PoxStackingOrder>>,
+    pub genesis_mints: Option<Vec<GenesisMint>>,
     pub execute_script: Option<Vec<ExecuteScript>>,
     pub bitcoin_node_image_url: Option<String>,
     pub bitcoin_explorer_image_url: Option<String>,
@@ -224,6 +292,9 @@ pub struct NetworkManifest {
     pub accounts: BTreeMap<String, AccountConfig>,
     #[serde(rename = "devnet_settings")]
     pub devnet: Option<DevnetConfig>,
+    /// Unknown keys of the `[network]` and `[devnet]` tables
+    #[serde(skip)]
+    pub warnings: Vec<String>,
 }
 
 pub mod accounts_serde {
@@ -264,9 +335,18 @@ pub mod accounts_serde {
 pub struct NetworkConfig {
     name: String,
     pub stacks_node_rpc_address: Option<String>,
+    /// Stacks nodes used when `stacks_node_rpc_address` is not answering
+    #[serde(default)]
+    pub stacks_node_rpc_fallback_addresses: Vec<String>,
     pub bitcoin_node_rpc_address: Option<String>,
     pub deployment_fee_rate: u64,
+    /// Maximum fee of a contract publish transaction, in µSTX
+    #[serde(default)]
+    pub deployment_cost_limit: Option<u64>,
     pub sats_per_bytes: u64,
+    /// Principals of the project to replace with the ones of this network, when retargeting
+    #[serde(default)]
+    pub principals: BTreeMap<String, String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -275,6 +355,10 @@ pub struct DevnetConfig {
     pub network_id: Option<u16>,
     pub orchestrator_ingestion_port: u16,
     pub orchestrator_control_port: u16,
+    /// Label of the account funding the transfers of the control API faucet
+    pub faucet_account: String,
+    /// Port of the Prometheus metrics endpoint of the event observer, disabled if not set
+    pub orchestrator_metrics_port: Option<u16>,
     pub bitcoin_node_p2p_port: u16,
     pub bitcoin_node_rpc_port: u16,
     pub bitcoin_node_username: String,
@@ -318,6 +402,7 @@ pub struct DevnetConfig {
     pub stacks_api_postgres_database: String,
     pub subnet_api_postgres_database: String,
     pub pox_stacking_orders: Vec<PoxStackingOrder>,
+    pub genesis_mints: Vec<GenesisMint>,
     pub execute_script: Vec<ExecuteScript>,
     pub bitcoin_node_image_url: String,
     pub stacks_node_image_url: String,
@@ -364,6 +449,41 @@ pub struct DevnetConfig {
     pub docker_platform: String,
 }
 
+/// Tokens of a project contract minted to an account when devnet starts, by calling
+/// `(mint u<amount> '<recipient>)`, `(mint u<token-id> '<recipient>)` for each of
+/// `token_ids`, or `(mint '<recipient>)` `count` times.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GenesisMint {
+    /// Name of a contract published by the deployer, or fully qualified contract id
+    pub contract: String,
+    /// Name of the minting function (default: mint)
+    pub function: Option<String>,
+    /// Account calling the minting function (default: deployer)
+    pub sender: Option<String>,
+    pub recipient: String,
+    pub amount: Option<u64>,
+    pub token_ids: Option<Vec<u64>>,
+    pub count: Option<u32>,
+}
+
+impl GenesisMint {
+    /// Arguments of each call to the minting function, `recipient` being the address of the recipient
+    pub fn get_calls_parameters(&self, recipient: &str) -> Vec<Vec<String>> {
+        let recipient = format!("'{}", recipient);
+        let mut calls = vec![];
+        if let Some(amount) = self.amount {
+            calls.push(vec![format!("u{}", amount), recipient.clone()]);
+        }
+        for token_id in self.token_ids.iter().flatten() {
+            calls.push(vec![format!("u{}", token_id), recipient.clone()]);
+        }
+        for _ in 0..self.count.unwrap_or(0) {
+            calls.push(vec![recipient.clone()]);
+        }
+        calls
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct PoxStackingOrder {
     pub start_at_cycle: u32,
@@ -379,12 +499,28 @@ pub struct AccountConfig {
     pub label: String,
     pub mnemonic: String,
     pub derivation: String,
+    /// Hex encoded secret key of the accounts imported from a key instead of a mnemonic
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret_key: Option<String>,
     pub balance: u64,
     pub stx_address: String,
     pub btc_address: String,
     pub is_mainnet: bool,
 }
 
+impl AccountConfig {
+    /// Secret key of the account, with the `01` compression suffix expected by Stacks
+    pub fn secret_key_hex(&self) -> String {
+        match self.secret_key {
+            Some(ref secret_key) => format!("{}01", secret_key),
+            None => {
+                let networks = (BitcoinNetwork::Testnet, StacksNetwork::Testnet);
+                compute_addresses(&self.mnemonic, &self.derivation, &networks).2
+            }
+        }
+    }
+}
+
 impl NetworkManifest {
     pub fn from_project_manifest_location(
         project_manifest_location: &FileLocation,
@@ -394,12 +530,21 @@ impl NetworkManifest {
     ) -> Result<NetworkManifest, String> {
         let network_manifest_location =
             project_manifest_location.get_network_manifest_location(&networks.1)?;
-        NetworkManifest::from_location(
+        let mut network_manifest = NetworkManifest::from_location(
             &network_manifest_location,
             networks,
             cache_location,
             devnet_override,
-        )
+        )?;
+        if project_manifest_location.exists() {
+            let content = project_manifest_location.read_content()?;
+            if let Some(overrides) =
+                ProjectNetworksFile::overrides(&content, project_manifest_location, &networks.1)?
+            {
+                overrides.apply(&mut network_manifest.network);
+            }
+        }
+        Ok(network_manifest)
     }
 
     pub async fn from_project_manifest_location_using_file_accessor(
@@ -414,13 +559,22 @@ impl NetworkManifest {
             .await?;
 
         let mut network_manifest_file: NetworkManifestFile =
-            toml::from_slice(content.as_bytes()).unwrap();
-        NetworkManifest::from_network_manifest_file(
+            parse_toml_file(content.as_bytes(), &network_manifest_location)?;
+        let mut network_manifest = NetworkManifest::from_network_manifest_file(
             &mut network_manifest_file,
             networks,
             None,
             None,
-        )
+        )?;
+        network_manifest.warnings =
+            NetworkManifest::unknown_keys(content.as_bytes(), &network_manifest_location);
+        let content = file_accessor.read_file(location.to_string()).await?;
+        if let Some(overrides) =
+            ProjectNetworksFile::overrides(content.as_bytes(), location, &networks.1)?
+        {
+            overrides.apply(&mut network_manifest.network);
+        }
+        Ok(network_manifest)
     }
 
     pub fn from_location(
@@ -431,13 +585,34 @@ impl NetworkManifest {
     ) -> Result<NetworkManifest, String> {
         let network_manifest_file_content = location.read_content()?;
         let mut network_manifest_file: NetworkManifestFile =
-            toml::from_slice(&network_manifest_file_content[..]).unwrap();
-        NetworkManifest::from_network_manifest_file(
+            parse_toml_file(&network_manifest_file_content[..], location)?;
+        let mut network_manifest = NetworkManifest::from_network_manifest_file(
             &mut network_manifest_file,
             networks,
             cache_location,
             devnet_override,
-        )
+        )?;
+        network_manifest.warnings =
+            NetworkManifest::unknown_keys(&network_manifest_file_content, location);
+        Ok(network_manifest)
+    }
+
+    /// Keys of the `[network]`, `[devnet]` and `[[devnet.genesis_mints]]` tables of `content`
+    /// that are ignored
+    fn unknown_keys(content: &[u8], location: &FileLocation) -> Vec<String> {
+        let Ok(value) = toml::from_slice::<toml::Value>(content) else {
+            return vec![];
+        };
+        let mut warnings = toml_unknown_keys::<NetworkConfigFile>(&value, "network", location);
+        warnings.extend(toml_unknown_keys::<DevnetConfigFile>(
+            &value, "devnet", location,
+        ));
+        warnings.extend(toml_unknown_keys::<GenesisMint>(
+            &value,
+            "devnet.genesis_mints",
+            location,
+        ));
+        warnings
     }
 
     pub fn from_network_manifest_file(
@@ -456,6 +631,11 @@ impl NetworkManifest {
         let network = NetworkConfig {
             name: network_manifest_file.network.name.clone(),
             stacks_node_rpc_address,
+            stacks_node_rpc_fallback_addresses: network_manifest_file
+                .network
+                .stacks_node_rpc_fallback_addresses
+                .clone()
+                .unwrap_or_default(),
             bitcoin_node_rpc_address: network_manifest_file
                 .network
                 .bitcoin_node_rpc_address
@@ -464,12 +644,36 @@ impl NetworkManifest {
                 .network
                 .deployment_fee_rate
                 .unwrap_or(10),
+            deployment_cost_limit: network_manifest_file.network.deployment_cost_limit,
             sats_per_bytes: network_manifest_file.network.sats_per_bytes.unwrap_or(10),
+            principals: network_manifest_file
+                .network
+                .principals
+                .clone()
+                .unwrap_or_default(),
         };
 
         let mut accounts = BTreeMap::new();
         let is_mainnet = matches!(networks.1, StacksNetwork::Mainnet);
 
+        for (from, to) in network.principals.iter() {
+            for principal in [from, to] {
+                if PrincipalData::parse_standard_principal(principal).is_err() {
+                    return Err(format!(
+                        "network.principals: {} is not a valid standard principal",
+                        principal
+                    ));
+                }
+            }
+            let is_mainnet_address = to.starts_with("SP") || to.starts_with("SM");
+            if is_mainnet_address != is_mainnet {
+                return Err(format!(
+                    "network.principals: {} is not an address of {}",
+                    to, network.name
+                ));
+            }
+        }
+
         if let Some(Value::Table(entries)) = &network_manifest_file.accounts {
             for (account_name, account_settings) in entries.iter() {
                 if let Value::Table(account_settings) = account_settings {
@@ -478,18 +682,25 @@ impl NetworkManifest {
                         _ => 0,
                     };
 
+                    if let Some(Value::String(name)) = account_settings.get("keychain") {
+                        let account =
+                            resolve_keychain_account(name, account_name, balance, &networks.1)
+                                .map_err(|e| {
+                                    format!(
+                                        "account {} (located in ./settings/{:?}.toml): {}",
+                                        account_name, networks.1, e
+                                    )
+                                })?;
+                        accounts.insert(account_name.to_string(), account);
+                        continue;
+                    }
+
                     let mnemonic = match account_settings.get("mnemonic") {
-                        Some(Value::String(words)) => {
-                            match Mnemonic::parse_in_normalized(Language::English, words) {
-                                Ok(result) => result.to_string(),
-                                Err(e) => {
-                                    return Err(format!(
-                                        "mnemonic (located in ./settings/{:?}.toml) for deploying address is invalid: {}",
-                                        networks.1 , e
-                                    ));
-                                }
-                            }
-                        }
+                        Some(Value::String(words)) => parse_mnemonic(
+                            words,
+                            &format!("accounts.{}.mnemonic", account_name),
+                            &networks.1,
+                        )?,
                         _ => {
                             let entropy = &[
                                 0x33, 0xE4, 0x6B, 0xB1, 0x3A, 0x74, 0x6E, 0xA4, 0x1C, 0xDD, 0xE4,
@@ -513,6 +724,7 @@ impl NetworkManifest {
                             label: account_name.to_string(),
                             mnemonic: mnemonic.to_string(),
                             derivation,
+                            secret_key: None,
                             balance,
                             stx_address,
                             btc_address,
@@ -539,6 +751,14 @@ impl NetworkManifest {
                     devnet_config.orchestrator_control_port = Some(val);
                 }
 
+                if let Some(ref val) = devnet_override.faucet_account {
+                    devnet_config.faucet_account = Some(val.clone());
+                }
+
+                if let Some(val) = devnet_override.orchestrator_metrics_port {
+                    devnet_config.orchestrator_metrics_port = Some(val);
+                }
+
                 if let Some(val) = devnet_override.bitcoin_node_p2p_port {
                     devnet_config.bitcoin_node_p2p_port = Some(val);
                 }
@@ -627,6 +847,10 @@ impl NetworkManifest {
                     devnet_config.pox_stacking_orders = Some(val.clone());
                 }
 
+                if let Some(ref val) = devnet_override.genesis_mints {
+                    devnet_config.genesis_mints = Some(val.clone());
+                }
+
                 if let Some(ref val) = devnet_override.execute_script {
                     devnet_config.execute_script = Some(val.clone());
                 }
@@ -771,10 +995,10 @@ impl NetworkManifest {
                 }
             };
 
-            let miner_mnemonic = devnet_config
-                .miner_mnemonic
-                .take()
-                .unwrap_or(DEFAULT_STACKS_MINER_MNEMONIC.to_string());
+            let miner_mnemonic = match devnet_config.miner_mnemonic.take() {
+                Some(words) => parse_mnemonic(&words, "devnet.miner_mnemonic", &networks.1)?,
+                None => DEFAULT_STACKS_MINER_MNEMONIC.to_string(),
+            };
             let miner_derivation_path = devnet_config
                 .miner_derivation_path
                 .take()
@@ -782,10 +1006,10 @@ impl NetworkManifest {
             let (miner_stx_address, miner_btc_address, miner_secret_key_hex) =
                 compute_addresses(&miner_mnemonic, &miner_derivation_path, networks);
 
-            let faucet_mnemonic = devnet_config
-                .faucet_mnemonic
-                .take()
-                .unwrap_or(DEFAULT_FAUCET_MNEMONIC.to_string());
+            let faucet_mnemonic = match devnet_config.faucet_mnemonic.take() {
+                Some(words) => parse_mnemonic(&words, "devnet.faucet_mnemonic", &networks.1)?,
+                None => DEFAULT_FAUCET_MNEMONIC.to_string(),
+            };
             let faucet_derivation_path = devnet_config
                 .faucet_derivation_path
                 .take()
@@ -793,10 +1017,12 @@ impl NetworkManifest {
             let (faucet_stx_address, faucet_btc_address, faucet_secret_key_hex) =
                 compute_addresses(&faucet_mnemonic, &faucet_derivation_path, networks);
 
-            let subnet_leader_mnemonic = devnet_config
-                .subnet_leader_mnemonic
-                .take()
-                .unwrap_or(DEFAULT_SUBNET_MNEMONIC.to_string());
+            let subnet_leader_mnemonic = match devnet_config.subnet_leader_mnemonic.take() {
+                Some(words) => {
+                    parse_mnemonic(&words, "devnet.subnet_leader_mnemonic", &networks.1)?
+                }
+                None => DEFAULT_SUBNET_MNEMONIC.to_string(),
+            };
             let subnet_leader_derivation_path = devnet_config
                 .subnet_leader_derivation_path
                 .take()
@@ -845,10 +1071,10 @@ impl NetworkManifest {
                 ));
             }
 
-            let stacker_mnemonic = devnet_config
-                .stacker_mnemonic
-                .take()
-                .unwrap_or(DEFAULT_STACKER_MNEMONIC.to_string());
+            let stacker_mnemonic = match devnet_config.stacker_mnemonic.take() {
+                Some(words) => parse_mnemonic(&words, "devnet.stacker_mnemonic", &networks.1)?,
+                None => DEFAULT_STACKER_MNEMONIC.to_string(),
+            };
             let stacker_derivation_path = devnet_config
                 .stacker_derivation_path
                 .take()
@@ -862,6 +1088,7 @@ impl NetworkManifest {
                     label: "stacker".to_string(),
                     mnemonic: stacker_mnemonic.clone(),
                     derivation: stacker_derivation_path.clone(),
+                    secret_key: None,
                     balance: 100_000_000_000_000,
                     stx_address,
                     btc_address,
@@ -910,11 +1137,32 @@ impl NetworkManifest {
                 }
             }
 
+            let genesis_mints = devnet_config.genesis_mints.take().unwrap_or_default();
+            for (i, mint) in genesis_mints.iter().enumerate() {
+                let sender = mint.sender.as_deref().unwrap_or("deployer");
+                for wallet_name in [sender, mint.recipient.as_str()] {
+                    if !accounts.contains_key(wallet_name) {
+                        return Err(format!("Account data was not provided for the wallet ({}) listed in genesis mint {}.", wallet_name, i + 1));
+                    }
+                }
+                if mint.amount.is_none() && mint.token_ids.is_none() && mint.count.is_none() {
+                    return Err(format!(
+                        "genesis mint {} must specify an amount, token_ids or a count",
+                        i + 1
+                    ));
+                }
+            }
+
             let config = DevnetConfig {
                 name: devnet_config.name.take().unwrap_or("devnet".into()),
                 network_id: devnet_config.network_id,
                 orchestrator_ingestion_port: devnet_config.orchestrator_port.unwrap_or(20445),
                 orchestrator_control_port: devnet_config.orchestrator_control_port.unwrap_or(20446),
+                faucet_account: devnet_config
+                    .faucet_account
+                    .take()
+                    .unwrap_or("faucet".into()),
+                orchestrator_metrics_port: devnet_config.orchestrator_metrics_port,
                 bitcoin_node_p2p_port: devnet_config.bitcoin_node_p2p_port.unwrap_or(18444),
                 bitcoin_node_rpc_port: devnet_config.bitcoin_node_rpc_port.unwrap_or(18443),
                 bitcoin_node_username: devnet_config
@@ -1017,6 +1265,7 @@ impl NetworkManifest {
                     .take()
                     .unwrap_or(DEFAULT_BITCOIN_EXPLORER_IMAGE.to_string()),
                 pox_stacking_orders: stacking_orders,
+                genesis_mints,
                 disable_bitcoin_explorer: devnet_config.disable_bitcoin_explorer.unwrap_or(false),
                 disable_stacks_api: devnet_config.disable_stacks_api.unwrap_or(false),
                 disable_postgres: devnet_config.disable_postgres.unwrap_or(false),
@@ -1104,12 +1353,55 @@ impl NetworkManifest {
             network,
             accounts,
             devnet,
+            warnings: vec![],
         };
 
         Ok(config)
     }
 }
 
+/// Parse a BIP39 mnemonic, detecting its wordlist across the supported languages,
+/// and report the settings entry `entry` when the words or the checksum are invalid.
+fn parse_mnemonic(words: &str, entry: &str, network: &StacksNetwork) -> Result<String, String> {
+    parse_mnemonic_words(words)
+        .map(|mnemonic| mnemonic.to_string())
+        .map_err(|e| {
+            format!(
+                "{} (located in ./settings/{:?}.toml) is not a valid mnemonic: {}",
+                entry, network, e
+            )
+        })
+}
+
+/// Parse a BIP39 mnemonic in any of the supported languages. The words are NFKD normalized
+/// first, as required by BIP39 for the wordlist lookup and the seed derivation.
+pub(crate) fn parse_mnemonic_words(words: &str) -> Result<Mnemonic, bip39::Error> {
+    Mnemonic::parse_normalized(&words.nfkd().collect::<String>())
+}
+
+#[cfg(feature = "cli")]
+fn resolve_keychain_account(
+    name: &str,
+    label: &str,
+    balance: u64,
+    network: &StacksNetwork,
+) -> Result<AccountConfig, String> {
+    crate::keychain::resolve_keychain_account(name, label, balance, network)
+}
+
+#[cfg(not(feature = "cli"))]
+fn resolve_keychain_account(
+    name: &str,
+    _label: &str,
+    _balance: u64,
+    _network: &StacksNetwork,
+) -> Result<AccountConfig, String> {
+    Err(format!(
+        "keychain account '{}' can only be resolved with clarinet CLI",
+        name
+    ))
+}
+
 pub fn compute_addresses(
     mnemonic: &str,
     derivation_path: &str,
@@ -1124,12 +1416,19 @@ pub fn compute_addresses(
 
     let secret_key = SecretKey::parse_slice(&ext.secret()).unwrap();
 
+    compute_addresses_from_secret_key(&secret_key, networks)
+}
+
+pub fn compute_addresses_from_secret_key(
+    secret_key: &SecretKey,
+    networks: &(BitcoinNetwork, StacksNetwork),
+) -> (String, String, String) {
     // Enforce a 33 bytes secret key format, expected by Stacks
     let mut secret_key_bytes = secret_key.serialize().to_vec();
     secret_key_bytes.push(1);
     let miner_secret_key_hex = bytes_to_hex(&secret_key_bytes);
 
-    let public_key = PublicKey::from_secret_key(&secret_key);
+    let public_key = PublicKey::from_secret_key(secret_key);
     let pub_key = Secp256k1PublicKey::from_slice(&public_key.serialize_compressed()).unwrap();
     let version = if matches!(networks.1, StacksNetwork::Mainnet) {
         clarity::address::C32_ADDRESS_VERSION_MAINNET_SINGLESIG
@@ -1191,3 +1490,116 @@ pub fn is_in_reward_phase(
 fn compute_btc_address(_public_key: &PublicKey, _network: &BitcoinNetwork) -> String {
     "__not_implemented__".to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mnemonic() {
+        let network = StacksNetwork::Devnet;
+        assert_eq!(
+            parse_mnemonic(
+                DEFAULT_FAUCET_MNEMONIC,
+                "accounts.faucet.mnemonic",
+                &network
+            ),
+            Ok(DEFAULT_FAUCET_MNEMONIC.to_string())
+        );
+
+        // valid words, invalid checksum (the last word should be "about")
+        let words = ["abandon"; 12].join(" ");
+        let err = parse_mnemonic(&words, "accounts.faucet.mnemonic", &network).unwrap_err();
+        assert!(err.starts_with("accounts.faucet.mnemonic (located in ./settings/Devnet.toml)"));
+
+        assert!(
+            parse_mnemonic("shadow private easily", "devnet.miner_mnemonic", &network).is_err()
+        );
+        assert!(parse_mnemonic(
+            "clarinet is not a mnemonic",
+            "devnet.miner_mnemonic",
+            &network
+        )
+        .is_err());
+
+        // a spanish mnemonic, with its accented letters composed (NFC) as typed on most keyboards
+        let mnemonic = Mnemonic::from_entropy_in(bip39::Language::Spanish, &[0u8; 16]).unwrap();
+        let words = mnemonic.to_string();
+        let composed = words.nfc().collect::<String>();
+        assert_ne!(composed, words);
+        assert_eq!(
+            parse_mnemonic(&composed, "accounts.faucet.mnemonic", &network),
+            Ok(words)
+        );
+    }
+
+    #[test]
+    fn test_network_overrides() {
+        let location = FileLocation::from_path(std::path::PathBuf::from("/tmp/Clarinet.toml"));
+        let content = [
+            "[project]",
+            "name = \"test\"",
+            "",
+            "[network.testnet]",
+            "stacks_node_rpc_address = \"https://testnet.example.com\"",
+            "deployment_fee_rate = 20",
+            "deployment_cost_limit = 1000",
+        ]
+        .join("\n");
+        let overrides =
+            ProjectNetworksFile::overrides(content.as_bytes(), &location, &StacksNetwork::Testnet)
+                .unwrap()
+                .unwrap();
+        let mut network = NetworkConfig {
+            name: "testnet".to_string(),
+            stacks_node_rpc_address: Some("https://api.testnet.hiro.so".to_string()),
+            stacks_node_rpc_fallback_addresses: vec![],
+            bitcoin_node_rpc_address: None,
+            deployment_fee_rate: 10,
+            deployment_cost_limit: None,
+            sats_per_bytes: 10,
+            principals: BTreeMap::new(),
+        };
+        overrides.apply(&mut network);
+        assert_eq!(
+            network.stacks_node_rpc_address.as_deref(),
+            Some("https://testnet.example.com")
+        );
+        assert_eq!(network.deployment_fee_rate, 20);
+        assert_eq!(network.deployment_cost_limit, Some(1000));
+        assert_eq!(network.sats_per_bytes, 10);
+
+        let overrides =
+            ProjectNetworksFile::overrides(content.as_bytes(), &location, &StacksNetwork::Mainnet)
+                .unwrap();
+        assert!(overrides.is_none());
+    }
+
+    #[test]
+    fn test_unknown_keys() {
+        let location =
+            FileLocation::from_path(std::path::PathBuf::from("/tmp/settings/Devnet.toml"));
+        let content = [
+            "[network]",
+            "name = \"devnet\"",
+            "deployment_fee_rate = 10",
+            "",
+            "[devnet]",
+            "disable_stacks_api = true",
+            "disable_stacks_explroer = true",
+            "",
+            "[[devnet.genesis_mints]]",
+            "contract = \"token\"",
+            "recipient = \"wallet_1\"",
+            "amout = 100",
+        ]
+        .join("\n");
+        assert_eq!(
+            NetworkManifest::unknown_keys(content.as_bytes(), &location),
+            vec![
+                "/tmp/settings/Devnet.toml: unknown key `disable_stacks_explroer` in [devnet] is ignored",
+                "/tmp/settings/Devnet.toml: unknown key `amout` in [devnet.genesis_mints] is ignored",
+            ]
+        );
+    }
+}
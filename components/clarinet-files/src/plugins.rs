@@ -0,0 +1,299 @@
+/// Largest message a WebAssembly plugin can print with `clarinet.log`.
+pub const MAX_PLUGIN_LOG_LEN: usize = 64 * 1024;
+
+/// Enum of the names used in Clarinet.toml, `$kind` being used in the error of `from_str`.
+/// Provides `all`, `all_names`, `as_str`, `Display` and `FromStr`.
+macro_rules! named_enum {
+    (
+        $(#[$outer:meta])*
+        pub enum $Name:ident($kind:literal) {
+            $($(#[$inner:meta])* $Variant:ident = $name:literal,)+
+        }
+    ) => {
+        $(#[$outer])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum $Name {
+            $($(#[$inner])* $Variant,)+
+        }
+
+        impl $Name {
+            pub fn all() -> Vec<$Name> {
+                vec![$($Name::$Variant,)+]
+            }
+
+            pub fn all_names() -> Vec<&'static str> {
+                vec![$($name,)+]
+            }
+
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    $($Name::$Variant => $name,)+
+                }
+            }
+        }
+
+        impl std::fmt::Display for $Name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "{}", self.as_str())
+            }
+        }
+
+        impl std::str::FromStr for $Name {
+            type Err = String;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                $Name::all()
+                    .into_iter()
+                    .find(|variant| variant.as_str() == s)
+                    .ok_or(format!("unknown {} '{}'", $kind, s))
+            }
+        }
+    };
+}
+pub(crate) use named_enum;
+
+named_enum! {
+    /// Lifecycle events that can trigger the plugins declared in the
+    /// `[project.plugins]` section of Clarinet.toml:
+    ///
+    /// ```toml
+    /// [project.plugins]
+    /// pre-check = ["./scripts/contract-size.sh"]
+    /// post-test = ["./plugins/coverage-gate.wasm"]
+    /// pre-deploy-step = ["./scripts/confirm.sh --batch-size 25"]
+    /// devnet-ready = ["node ./scripts/notify.js"]
+    /// ```
+    ///
+    /// Commands are not run by a shell, variables like `$NETWORK` are passed as is: the
+    /// network and the deployment details are part of the JSON document read on stdin.
+    pub enum LifecycleHook("lifecycle hook") {
+        PreCheck = "pre-check",
+        PostCheck = "post-check",
+        PostTest = "post-test",
+        PreDeploy = "pre-deploy",
+        /// Before each batch of transactions of a deployment plan
+        PreDeployStep = "pre-deploy-step",
+        PostDeploy = "post-deploy",
+        DevnetReady = "devnet-ready",
+    }
+}
+
+/// Program and arguments of a plugin command, split like a POSIX shell would
+pub fn parse_plugin_command(command: &str) -> Result<Vec<String>, String> {
+    shell_words::split(command)
+        .map_err(|e| format!("unable to parse plugin command `{}` ({})", command, e))
+}
+
+/// Run the plugins registered for `hook`, in the order they are declared.
+/// Each plugin is spawned from the project root and receives a JSON document
+/// on stdin, containing the hook name, the project details and `context`.
+/// Commands are split with the quoting rules of a POSIX shell, without running a shell.
+/// A plugin whose program is a `.wasm` file is run in-process, see `run_wasm_plugin`.
+/// The first plugin failing interrupts the sequence.
+#[cfg(feature = "cli")]
+pub fn run_plugins(
+    manifest: &crate::ProjectManifest,
+    hook: LifecycleHook,
+    context: serde_json::Value,
+) -> Result<(), String> {
+    use serde_json::json;
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    let Some(commands) = manifest.project.plugins.get(hook.as_str()) else {
+        return Ok(());
+    };
+
+    let project_root = manifest.location.get_project_root_location()?;
+    let input = json!({
+        "hook": hook.as_str(),
+        "project": {
+            "name": manifest.project.name,
+            "manifest_path": manifest.location.to_string(),
+        },
+        "context": context,
+    });
+    let input = serde_json::to_vec(&input).map_err(|e| e.to_string())?;
+
+    for command in commands.iter() {
+        let parts = parse_plugin_command(command)?;
+        let Some((program, args)) = parts.split_first() else {
+            continue;
+        };
+
+        if program.ends_with(".wasm") {
+            let mut module_location = project_root.clone();
+            module_location.append_path(program)?;
+            let path = std::path::PathBuf::from(module_location.to_string());
+            run_wasm_plugin(&path, &input)
+                .map_err(|e| format!("plugin `{}` failed during {} ({})", command, hook, e))?;
+            continue;
+        }
+
+        let mut child = Command::new(program)
+            .args(args)
+            .current_dir(project_root.to_string())
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("unable to spawn plugin `{}` ({})", command, e))?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            // A plugin may exit without reading its input, ignore broken pipes.
+            let _ = stdin.write_all(&input);
+        }
+
+        let status = child
+            .wait()
+            .map_err(|e| format!("unable to run plugin `{}` ({})", command, e))?;
+        if !status.success() {
+            return Err(format!(
+                "plugin `{}` failed during {} ({})",
+                command, hook, status
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Run the WebAssembly plugin at `path` with the JSON document `input`. The module exports
+/// its `memory`, an `alloc(len: i32) -> i32` function returning the offset where `len`
+/// bytes of input can be written, and a `run(ptr: i32, len: i32) -> i32` entry point
+/// returning 0 on success. It can import `clarinet.log(ptr: i32, len: i32)` to print
+/// messages of up to `MAX_PLUGIN_LOG_LEN` bytes, invalid messages abort the plugin.
+#[cfg(feature = "cli")]
+pub fn run_wasm_plugin(path: &std::path::Path, input: &[u8]) -> Result<(), String> {
+    use wasmtime::{Caller, Engine, Extern, Linker, Module, Store};
+
+    let engine = Engine::default();
+    let module = Module::from_file(&engine, path)
+        .map_err(|e| format!("unable to load {}: {}", path.display(), e))?;
+    let mut store = Store::new(&engine, ());
+    let mut linker = Linker::new(&engine);
+    linker
+        .func_wrap(
+            "clarinet",
+            "log",
+            |mut caller: Caller<'_, ()>, ptr: i32, len: i32| -> wasmtime::Result<()> {
+                let (Ok(ptr), Ok(len)) = (usize::try_from(ptr), usize::try_from(len)) else {
+                    return Err(wasmtime::Error::msg("invalid log message location"));
+                };
+                if len > MAX_PLUGIN_LOG_LEN {
+                    return Err(wasmtime::Error::msg(format!(
+                        "log message too large: {} bytes (max {})",
+                        len, MAX_PLUGIN_LOG_LEN
+                    )));
+                }
+                let Some(Extern::Memory(memory)) = caller.get_export("memory") else {
+                    return Ok(());
+                };
+                let mut message = vec![0u8; len];
+                memory.read(&caller, ptr, &mut message)?;
+                println!("{}", String::from_utf8_lossy(&message));
+                Ok(())
+            },
+        )
+        .map_err(|e| e.to_string())?;
+    let instance = linker
+        .instantiate(&mut store, &module)
+        .map_err(|e| format!("unable to instantiate {}: {}", path.display(), e))?;
+
+    let memory = instance
+        .get_memory(&mut store, "memory")
+        .ok_or("the module does not export its memory")?;
+    let alloc = instance
+        .get_typed_func::<i32, i32>(&mut store, "alloc")
+        .map_err(|e| format!("invalid export alloc: {}", e))?;
+    let run = instance
+        .get_typed_func::<(i32, i32), i32>(&mut store, "run")
+        .map_err(|e| format!("invalid export run: {}", e))?;
+
+    let len = i32::try_from(input.len()).map_err(|e| e.to_string())?;
+    let ptr = alloc
+        .call(&mut store, len)
+        .map_err(|e| format!("alloc failed: {}", e))?;
+    memory
+        .write(&mut store, ptr as usize, input)
+        .map_err(|e| format!("unable to write the input: {}", e))?;
+    match run.call(&mut store, (ptr, len)) {
+        Ok(0) => Ok(()),
+        Ok(status) => Err(format!("exit status: {}", status)),
+        Err(e) => Err(format!("run failed: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_lifecycle_hook_names() {
+        for hook in LifecycleHook::all() {
+            assert_eq!(LifecycleHook::from_str(hook.as_str()), Ok(hook));
+        }
+        assert_eq!(
+            LifecycleHook::from_str("pre-deploy-step"),
+            Ok(LifecycleHook::PreDeployStep)
+        );
+        assert!(LifecycleHook::from_str("post-deploy-step").is_err());
+    }
+
+    #[test]
+    fn test_parse_plugin_command() {
+        assert_eq!(
+            parse_plugin_command(r#"node "./scripts/notify bot.js" --title 'build ok'"#),
+            Ok(vec![
+                "node".to_string(),
+                "./scripts/notify bot.js".to_string(),
+                "--title".to_string(),
+                "build ok".to_string(),
+            ])
+        );
+        assert_eq!(parse_plugin_command(""), Ok(vec![]));
+        assert!(parse_plugin_command("node \"unterminated").is_err());
+    }
+
+    #[cfg(feature = "cli")]
+    #[test]
+    fn test_run_wasm_plugin() {
+        // accepts a json object as input
+        let module = r#"(module
+            (import "clarinet" "log" (func $log (param i32 i32)))
+            (memory (export "memory") 1)
+            (data (i32.const 0) "checked")
+            (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+            (func (export "run") (param $ptr i32) (param $len i32) (result i32)
+                (call $log (i32.const 0) (i32.const 7))
+                (if (result i32) (i32.eq (i32.load8_u (local.get $ptr)) (i32.const 123))
+                    (then (i32.const 0))
+                    (else (i32.const 1)))))"#;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("plugin.wat");
+        std::fs::write(&path, module).unwrap();
+
+        assert_eq!(run_wasm_plugin(&path, br#"{"hook":"post-test"}"#), Ok(()));
+        assert_eq!(
+            run_wasm_plugin(&path, b"[]"),
+            Err("exit status: 1".to_string())
+        );
+        std::fs::remove_file(&path).unwrap();
+        assert!(run_wasm_plugin(&path, b"{}").is_err());
+
+        // logs a message with a negative length, then one too large
+        for len in ["-1", "65537"] {
+            let module = format!(
+                r#"(module
+                    (import "clarinet" "log" (func $log (param i32 i32)))
+                    (memory (export "memory") 2)
+                    (func (export "alloc") (param i32) (result i32) (i32.const 0))
+                    (func (export "run") (param i32 i32) (result i32)
+                        (call $log (i32.const 0) (i32.const {}))
+                        (i32.const 0)))"#,
+                len
+            );
+            std::fs::write(&path, module).unwrap();
+            let result = run_wasm_plugin(&path, b"{}");
+            assert!(matches!(result, Err(ref e) if e.starts_with("run failed")));
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A principal replaced in a file of the project
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrincipalRewrite {
+    pub path: PathBuf,
+    pub line: usize,
+    pub from: String,
+    pub to: String,
+}
+
+impl fmt::Display for PrincipalRewrite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {} -> {}",
+            self.path.display(),
+            self.line,
+            self.from,
+            self.to
+        )
+    }
+}
+
+/// Replace the principals of `source` found in `principals`. Only whole addresses are
+/// replaced, contract principals (`<address>.<contract>`) are retargeted with their address.
+pub fn rewrite_principals(
+    source: &str,
+    principals: &BTreeMap<String, String>,
+    path: &Path,
+) -> (String, Vec<PrincipalRewrite>) {
+    let mut rewritten = String::with_capacity(source.len());
+    let mut rewrites = vec![];
+    for (index, line) in source.split_inclusive('\n').enumerate() {
+        let mut rest = line;
+        while let Some(c) = rest.chars().next() {
+            let word_len = rest
+                .find(|c: char| !c.is_ascii_alphanumeric())
+                .unwrap_or(rest.len());
+            if word_len == 0 {
+                rewritten.push(c);
+                rest = &rest[c.len_utf8()..];
+                continue;
+            }
+            let (word, tail) = rest.split_at(word_len);
+            match principals.get(word) {
+                Some(target) => {
+                    rewritten.push_str(target);
+                    rewrites.push(PrincipalRewrite {
+                        path: path.to_path_buf(),
+                        line: index + 1,
+                        from: word.to_string(),
+                        to: target.clone(),
+                    });
+                }
+                None => rewritten.push_str(word),
+            }
+            rest = tail;
+        }
+    }
+    (rewritten, rewrites)
+}
+
+/// Files of `dir` and of its sub directories with one of the `extensions`,
+/// `node_modules` directories excluded.
+pub fn collect_files(dir: &Path, extensions: &[&str]) -> Result<Vec<PathBuf>, String> {
+    let mut files = vec![];
+    if !dir.is_dir() {
+        return Ok(files);
+    }
+    let entries =
+        fs::read_dir(dir).map_err(|e| format!("unable to read {}: {}", dir.display(), e))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            if path.file_name().and_then(|n| n.to_str()) != Some("node_modules") {
+                files.append(&mut collect_files(&path, extensions)?);
+            }
+        } else if path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| extensions.contains(&e))
+            .unwrap_or(false)
+        {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewrite_principals() {
+        let principals = BTreeMap::from([(
+            "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM".to_string(),
+            "SP2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKNRV9EJ7".to_string(),
+        )]);
+        let source = [
+            "(define-constant OWNER 'ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM)",
+            "(define-constant OTHER 'ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGMX)",
+            "(contract-call? 'ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.token transfer)",
+        ]
+        .join("\n");
+        let path = PathBuf::from("contracts/market.clar");
+
+        let (rewritten, rewrites) = rewrite_principals(&source, &principals, &path);
+        assert_eq!(
+            rewritten,
+            [
+                "(define-constant OWNER 'SP2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKNRV9EJ7)",
+                "(define-constant OTHER 'ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGMX)",
+                "(contract-call? 'SP2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKNRV9EJ7.token transfer)",
+            ]
+            .join("\n")
+        );
+        assert_eq!(
+            rewrites.iter().map(|r| r.line).collect::<Vec<_>>(),
+            vec![1, 3]
+        );
+        assert_eq!(
+            rewrites[0].to_string(),
+            "contracts/market.clar:1: ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM -> SP2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKNRV9EJ7"
+        );
+    }
+}
@@ -1,4 +1,6 @@
-use crate::FileAccessor;
+use crate::git_hooks::{GitHook, GitHookGate};
+use crate::plugins::LifecycleHook;
+use crate::{parse_toml_file, toml_unknown_keys, FileAccessor, NetworkOverrides, StacksNetwork};
 
 use super::FileLocation;
 use clarity::types::StacksEpochId;
@@ -17,6 +19,7 @@ pub const INVALID_CLARITY_VERSION: &str =
     "clarity_version field invalid (value supported: 1, 2, 3)";
 const INVALID_EPOCH: &str =
     "epoch field invalid (value supported: 2.0, 2.05, 2.1, 2.2, 2.3, 2.4, 3.0)";
+const INVALID_DEPENDS_ON: &str = "depends_on field invalid (expected a list of contract names)";
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct ClarityContractMetadata {
@@ -31,6 +34,7 @@ pub struct ProjectManifestFile {
     project: ProjectConfigFile,
     contracts: Option<TomlValue>,
     repl: Option<repl::SettingsFile>,
+    network: Option<BTreeMap<String, NetworkOverrides>>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -41,6 +45,9 @@ pub struct ProjectConfigFile {
     telemetry: Option<bool>,
     requirements: Option<TomlValue>,
     boot_contracts: Option<Vec<String>>,
+    plugins: Option<BTreeMap<String, Vec<String>>>,
+    git_hooks: Option<BTreeMap<String, Vec<String>>>,
+    console_commands: Option<BTreeMap<String, String>>,
 
     // The fields below have been moved into repl above, but are kept here for
     // backwards compatibility.
@@ -48,19 +55,65 @@ pub struct ProjectConfigFile {
     cache_dir: Option<String>,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Debug, Clone)]
 pub struct ProjectManifest {
     pub project: ProjectConfig,
-    #[serde(serialize_with = "toml::ser::tables_last")]
     #[serde(deserialize_with = "contracts_deserializer")]
     pub contracts: BTreeMap<String, ClarityContract>,
     #[serde(rename = "repl")]
     pub repl_settings: repl::Settings,
-    #[serde(skip_serializing)]
     #[serde(default = "default_location")]
     pub location: FileLocation,
-    #[serde(skip_serializing, skip_deserializing)]
+    #[serde(skip_deserializing)]
     pub contracts_settings: HashMap<FileLocation, ClarityContractMetadata>,
+    /// Dependencies listed with `depends_on`, in addition to the ones detected in the sources
+    #[serde(skip_deserializing)]
+    pub contracts_dependencies: BTreeMap<String, Vec<String>>,
+    /// `[network.<name>]` tables, by network name
+    #[serde(skip_deserializing)]
+    pub network_overrides: BTreeMap<String, NetworkOverrides>,
+    /// Unknown keys of the `[project]` and `[network.<name>]` tables
+    #[serde(skip_deserializing)]
+    pub warnings: Vec<String>,
+}
+
+/// Contract of the `[contracts]` table, with the dependencies listed in `depends_on`
+#[derive(Serialize)]
+struct ContractConfigFile<'a> {
+    #[serde(flatten)]
+    contract: &'a ClarityContract,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    depends_on: Option<&'a Vec<String>>,
+}
+
+impl Serialize for ProjectManifest {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let contracts: BTreeMap<&String, ContractConfigFile> = self
+            .contracts
+            .iter()
+            .map(|(name, contract)| {
+                let depends_on = self.contracts_dependencies.get(name);
+                (
+                    name,
+                    ContractConfigFile {
+                        contract,
+                        depends_on,
+                    },
+                )
+            })
+            .collect();
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("project", &self.project)?;
+        map.serialize_entry("contracts", &contracts)?;
+        map.serialize_entry("repl", &self.repl_settings)?;
+        if !self.network_overrides.is_empty() {
+            map.serialize_entry("network", &self.network_overrides)?;
+        }
+        map.end()
+    }
 }
 
 fn default_location() -> FileLocation {
@@ -140,6 +193,12 @@ pub struct ProjectConfig {
     pub cache_location: FileLocation,
     #[serde(skip_deserializing)]
     pub boot_contracts: Vec<String>,
+    #[serde(default)]
+    pub plugins: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    pub git_hooks: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    pub console_commands: BTreeMap<String, String>,
 }
 
 fn cache_location_deserializer<'de, D>(des: D) -> Result<FileLocation, D::Error>
@@ -170,6 +229,15 @@ impl Serialize for ProjectConfig {
         if self.requirements.is_some() {
             map.serialize_entry("requirements", &self.requirements)?;
         }
+        if !self.plugins.is_empty() {
+            map.serialize_entry("plugins", &self.plugins)?;
+        }
+        if !self.git_hooks.is_empty() {
+            map.serialize_entry("git_hooks", &self.git_hooks)?;
+        }
+        if !self.console_commands.is_empty() {
+            map.serialize_entry("console_commands", &self.console_commands)?;
+        }
         map.end()
     }
 }
@@ -186,27 +254,40 @@ impl ProjectManifest {
     ) -> Result<ProjectManifest, String> {
         let content = file_accessor.read_file(location.to_string()).await?;
 
-        let project_manifest_file: ProjectManifestFile = match toml::from_slice(content.as_bytes())
-        {
-            Ok(s) => s,
-            Err(e) => {
-                return Err(format!("Clarinet.toml file malformatted {:?}", e));
-            }
-        };
-        ProjectManifest::from_project_manifest_file(project_manifest_file, location)
+        let project_manifest_file: ProjectManifestFile =
+            parse_toml_file(content.as_bytes(), location)?;
+        let mut manifest =
+            ProjectManifest::from_project_manifest_file(project_manifest_file, location)?;
+        manifest.warnings = ProjectManifest::unknown_keys(content.as_bytes(), location);
+        Ok(manifest)
     }
 
     pub fn from_location(location: &FileLocation) -> Result<ProjectManifest, String> {
         let project_manifest_file_content = location.read_content()?;
         let project_manifest_file: ProjectManifestFile =
-            match toml::from_slice(&project_manifest_file_content[..]) {
-                Ok(s) => s,
-                Err(e) => {
-                    return Err(format!("Clarinet.toml file malformatted {:?}", e));
-                }
-            };
+            parse_toml_file(&project_manifest_file_content[..], location)?;
+
+        let mut manifest =
+            ProjectManifest::from_project_manifest_file(project_manifest_file, location)?;
+        manifest.warnings = ProjectManifest::unknown_keys(&project_manifest_file_content, location);
+        Ok(manifest)
+    }
 
-        ProjectManifest::from_project_manifest_file(project_manifest_file, location)
+    /// Keys of the `[project]` and `[network.<name>]` tables of `content` that are ignored
+    fn unknown_keys(content: &[u8], location: &FileLocation) -> Vec<String> {
+        let Ok(value) = toml::from_slice::<TomlValue>(content) else {
+            return vec![];
+        };
+        let mut warnings = toml_unknown_keys::<ProjectConfigFile>(&value, "project", location);
+        if let Some(TomlValue::Table(networks)) = value.get("network") {
+            for network in networks.keys() {
+                let section = format!("network.{}", network);
+                warnings.extend(toml_unknown_keys::<NetworkOverrides>(
+                    &value, &section, location,
+                ));
+            }
+        }
+        warnings
     }
 
     pub fn from_project_manifest_file(
@@ -224,6 +305,55 @@ impl ProjectManifest {
             repl_settings.analysis.set_passes(passes);
         }
 
+        let plugins = project_manifest_file.project.plugins.unwrap_or_default();
+        for hook in plugins.keys() {
+            if LifecycleHook::from_str(hook).is_err() {
+                return Err(format!(
+                    "unknown plugin hook '{}' (value supported: {})",
+                    hook,
+                    LifecycleHook::all_names().join(", ")
+                ));
+            }
+        }
+
+        let git_hooks = project_manifest_file.project.git_hooks.unwrap_or_default();
+        for (hook, gates) in git_hooks.iter() {
+            if GitHook::from_str(hook).is_err() {
+                return Err(format!(
+                    "unknown git hook '{}' (value supported: {})",
+                    hook,
+                    GitHook::all_names().join(", ")
+                ));
+            }
+            for gate in gates.iter() {
+                if GitHookGate::from_str(gate).is_err() {
+                    return Err(format!(
+                        "unknown {} gate '{}' (value supported: {})",
+                        hook,
+                        gate,
+                        GitHookGate::all_names().join(", ")
+                    ));
+                }
+            }
+        }
+
+        let console_commands = project_manifest_file
+            .project
+            .console_commands
+            .unwrap_or_default();
+        for name in console_commands.keys() {
+            if name.is_empty()
+                || !name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            {
+                return Err(format!(
+                    "invalid console command name '{}' (letters, digits, '-' and '_' only)",
+                    name
+                ));
+            }
+        }
+
         let project_name = project_manifest_file.project.name;
         let project_root_location = manifest_location.get_parent_location()?;
         let cache_location = match project_manifest_file.project.cache_dir {
@@ -258,6 +388,9 @@ impl ProjectManifest {
                 "cost-voting".to_string(),
                 "bns".to_string(),
             ],
+            plugins,
+            git_hooks,
+            console_commands,
         };
 
         let mut config = ProjectManifest {
@@ -266,9 +399,13 @@ impl ProjectManifest {
             repl_settings,
             location: manifest_location.clone(),
             contracts_settings: HashMap::new(),
+            contracts_dependencies: BTreeMap::new(),
+            network_overrides: BTreeMap::new(),
+            warnings: vec![],
         };
         let mut config_contracts = BTreeMap::new();
         let mut contracts_settings = HashMap::new();
+        let mut contracts_dependencies = BTreeMap::new();
         let mut config_requirements: Vec<RequirementConfig> = Vec::new();
 
         if let Some(TomlValue::Array(requirements)) = project_manifest_file.project.requirements {
@@ -322,6 +459,22 @@ impl ProjectManifest {
                         parsed_clarity_version.as_deref(),
                     )?;
 
+                    if let Some(depends_on) = contract_settings.get("depends_on") {
+                        let TomlValue::Array(depends_on) = depends_on else {
+                            return Err(INVALID_DEPENDS_ON.into());
+                        };
+                        let mut dependencies = vec![];
+                        for dependency in depends_on.iter() {
+                            match dependency {
+                                TomlValue::String(dependency) => {
+                                    dependencies.push(dependency.clone())
+                                }
+                                _ => return Err(INVALID_DEPENDS_ON.into()),
+                            }
+                        }
+                        contracts_dependencies.insert(contract_name.to_string(), dependencies);
+                    }
+
                     config_contracts.insert(
                         contract_name.to_string(),
                         ClarityContract {
@@ -347,11 +500,62 @@ impl ProjectManifest {
                 }
             }
         };
+        for (contract_name, dependencies) in contracts_dependencies.iter() {
+            for dependency in dependencies.iter() {
+                if dependency == contract_name || !config_contracts.contains_key(dependency) {
+                    return Err(format!(
+                        "contract {} can not depend on {} (depends_on must list other contracts of the project)",
+                        contract_name, dependency
+                    ));
+                }
+            }
+        }
+        let network_overrides = project_manifest_file.network.unwrap_or_default();
+        for (network, overrides) in network_overrides.iter() {
+            if !["devnet", "testnet", "mainnet"].contains(&network.as_str()) {
+                return Err(format!(
+                    "unknown network '{}' (value supported: devnet, testnet, mainnet)",
+                    network
+                ));
+            }
+            if let Some(contract_name) = overrides
+                .deployers
+                .keys()
+                .find(|name| !config_contracts.contains_key(*name))
+            {
+                return Err(format!(
+                    "network.{}.deployers: {} is not a contract of the project",
+                    network, contract_name
+                ));
+            }
+        }
         config.contracts = config_contracts;
         config.contracts_settings = contracts_settings;
+        config.contracts_dependencies = contracts_dependencies;
         config.project.requirements = Some(config_requirements);
+        config.network_overrides = network_overrides;
         Ok(config)
     }
+
+    /// Deployer of the contract `contract_name` on `network`, the one of its
+    /// `[network.<name>.deployers]` table takes precedence over the `[contracts]` one
+    pub fn contract_deployer(
+        &self,
+        contract_name: &str,
+        network: &StacksNetwork,
+    ) -> Option<ContractDeployer> {
+        let overridden = self
+            .network_overrides
+            .get(NetworkOverrides::table_name(network))
+            .and_then(|overrides| overrides.deployers.get(contract_name));
+        match overridden {
+            Some(label) => Some(ContractDeployer::LabeledDeployer(label.clone())),
+            None => self
+                .contracts
+                .get(contract_name)
+                .map(|contract| contract.deployer.clone()),
+        }
+    }
 }
 
 fn get_epoch_and_clarity_version(
@@ -443,3 +647,158 @@ fn test_get_epoch_and_clarity_version() {
     let result = get_epoch_and_clarity_version(Some("2.1"), Some("2"));
     assert_eq!(result, Ok((Epoch21, Clarity2)));
 }
+
+#[test]
+fn test_contracts_dependencies() {
+    let manifest = |contracts: &str| {
+        let content = format!("[project]\nname = \"test\"\n\n{}", contracts);
+        let manifest_file: ProjectManifestFile = toml::from_str(&content).unwrap();
+        let location = FileLocation::from_path(PathBuf::from("/tmp/test/Clarinet.toml"));
+        ProjectManifest::from_project_manifest_file(manifest_file, &location)
+    };
+
+    let result = manifest(
+        "[contracts.token]\npath = \"contracts/token.clar\"\n\n[contracts.market]\npath = \"contracts/market.clar\"\nepoch = 2.1\ndepends_on = [\"token\"]",
+    )
+    .unwrap();
+    assert_eq!(
+        result.contracts_dependencies,
+        BTreeMap::from([("market".to_string(), vec!["token".to_string()])])
+    );
+    assert_eq!(result.contracts["market"].epoch, StacksEpochId::Epoch21);
+
+    let result =
+        manifest("[contracts.market]\npath = \"contracts/market.clar\"\ndepends_on = [\"token\"]");
+    assert!(result.is_err());
+
+    let result =
+        manifest("[contracts.market]\npath = \"contracts/market.clar\"\ndepends_on = \"token\"");
+    assert_eq!(result.unwrap_err(), INVALID_DEPENDS_ON);
+}
+
+#[test]
+fn test_network_overrides() {
+    let manifest = |network: &str| {
+        let content = format!(
+            "[project]\nname = \"test\"\n\n[contracts.token]\npath = \"contracts/token.clar\"\n\n{}",
+            network
+        );
+        let manifest_file: ProjectManifestFile = toml::from_str(&content).unwrap();
+        let location = FileLocation::from_path(PathBuf::from("/tmp/test/Clarinet.toml"));
+        ProjectManifest::from_project_manifest_file(manifest_file, &location)
+    };
+
+    let result = manifest(
+        "[network.testnet]\nstacks_node_rpc_address = \"https://testnet.example.com\"\ndeployment_cost_limit = 1000000\ndeployers = { token = \"token_deployer\" }",
+    )
+    .unwrap();
+    let overrides = &result.network_overrides["testnet"];
+    assert_eq!(
+        overrides.stacks_node_rpc_address.as_deref(),
+        Some("https://testnet.example.com")
+    );
+    assert_eq!(overrides.deployment_cost_limit, Some(1000000));
+    assert_eq!(
+        result.contract_deployer("token", &StacksNetwork::Testnet),
+        Some(ContractDeployer::LabeledDeployer(
+            "token_deployer".to_string()
+        ))
+    );
+    assert_eq!(
+        result.contract_deployer("token", &StacksNetwork::Mainnet),
+        Some(ContractDeployer::DefaultDeployer)
+    );
+
+    let result = manifest("[network.testnet]\ndeployers = { market = \"deployer\" }");
+    assert_eq!(
+        result.unwrap_err(),
+        "network.testnet.deployers: market is not a contract of the project"
+    );
+
+    let result = manifest("[network.regtest]\ndeployment_fee_rate = 1");
+    assert!(result.unwrap_err().starts_with("unknown network 'regtest'"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(content: &str) -> ProjectManifest {
+        let location = FileLocation::from_path_string("/project/Clarinet.toml").unwrap();
+        let file: ProjectManifestFile = toml::from_str(content).unwrap();
+        ProjectManifest::from_project_manifest_file(file, &location).unwrap()
+    }
+
+    /// Manifest written back the way `clarinet contracts new` and `rm` do
+    fn write(manifest: &ProjectManifest) -> String {
+        let value = toml::Value::try_from(manifest).unwrap();
+        toml::ser::to_string_pretty(&value).unwrap()
+    }
+
+    #[test]
+    fn test_manifest_round_trip() {
+        let manifest = load(
+            r#"
+[project]
+name = "counter"
+
+[contracts.counter]
+path = "contracts/counter.clar"
+epoch = 2.5
+depends_on = ["traits"]
+
+[contracts.traits]
+path = "contracts/traits.clar"
+
+[network.testnet]
+stacks_node_rpc_address = "https://testnet.example.com"
+
+[network.testnet.deployers]
+counter = "deployer_2"
+"#,
+        );
+
+        let reloaded = load(&write(&manifest));
+        assert_eq!(
+            reloaded.contracts_dependencies,
+            manifest.contracts_dependencies
+        );
+        assert_eq!(
+            reloaded.contracts_dependencies.get("counter"),
+            Some(&vec!["traits".to_string()])
+        );
+        assert_eq!(
+            reloaded.contracts.keys().collect::<Vec<_>>(),
+            vec!["counter", "traits"]
+        );
+        assert_eq!(reloaded.contracts["counter"].epoch, StacksEpochId::Epoch25);
+    }
+
+    #[test]
+    fn test_unknown_keys() {
+        let location = FileLocation::from_path_string("/project/Clarinet.toml").unwrap();
+        let content = r#"
+[project]
+name = "counter"
+telemetrie = false
+
+[project.plugins]
+post-test = ["./plugins/coverage-gate.wasm"]
+
+[network.testnet]
+stacks_node_rpc_adress = "https://testnet.example.com"
+"#;
+        assert_eq!(
+            ProjectManifest::unknown_keys(content.as_bytes(), &location),
+            vec![
+                "/project/Clarinet.toml: unknown key `telemetrie` in [project] is ignored",
+                "/project/Clarinet.toml: unknown key `stacks_node_rpc_adress` in [network.testnet] is ignored",
+            ]
+        );
+
+        // the keys written back by clarinet are known
+        let manifest = load(content);
+        let content = write(&manifest);
+        assert!(ProjectManifest::unknown_keys(content.as_bytes(), &location).is_empty());
+    }
+}
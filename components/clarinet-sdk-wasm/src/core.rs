@@ -1,10 +1,12 @@
 use clarinet_deployments::diagnostic_digest::DiagnosticsDigest;
+use clarinet_deployments::error_codes::{get_defined_errors, ErrorCodesRegistry};
+use clarinet_deployments::snapshot::take_snapshot;
 use clarinet_deployments::types::{
     DeploymentSpecification, DeploymentSpecificationFile, EmulatedContractPublishSpecification,
-    TransactionSpecification,
+    PostConditionSpecification, PostConditionSpecificationFile, TransactionSpecification,
 };
 use clarinet_deployments::{
-    generate_default_deployment, initiate_session_from_manifest,
+    build_post_conditions, generate_default_deployment, initiate_session_from_manifest,
     update_session_with_deployment_plan,
 };
 use clarinet_files::StacksNetwork;
@@ -13,6 +15,7 @@ use clarity_repl::clarity::analysis::contract_interface_builder::{
     ContractInterface, ContractInterfaceFunction, ContractInterfaceFunctionAccess,
 };
 use clarity_repl::clarity::chainstate::StacksAddress;
+use clarity_repl::clarity::util::secp256k1::Secp256k1PrivateKey;
 use clarity_repl::clarity::vm::types::{
     PrincipalData, QualifiedContractIdentifier, StandardPrincipalData,
 };
@@ -20,10 +23,12 @@ use clarity_repl::clarity::{
     Address, ClarityVersion, EvaluationResult, ExecutionResult, StacksEpochId, SymbolicExpression,
 };
 use clarity_repl::repl::clarity_values::{uint8_to_string, uint8_to_value};
+use clarity_repl::repl::post_conditions::PostConditionMode;
 use clarity_repl::repl::session::{CostsReport, BOOT_CONTRACTS_DATA};
+use clarity_repl::repl::sip018;
 use clarity_repl::repl::{
     clarity_values, ClarityCodeSource, ClarityContract, ContractDeployer, Session, SessionSettings,
-    DEFAULT_CLARITY_VERSION, DEFAULT_EPOCH,
+    SessionState, DEFAULT_CLARITY_VERSION, DEFAULT_EPOCH,
 };
 use gloo_utils::format::JsValueSerdeExt;
 use js_sys::Function as JsFunction;
@@ -31,6 +36,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::json;
 use serde_wasm_bindgen::to_value as encode_to_js;
 use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
 use std::{panic, path::PathBuf};
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsValue;
@@ -44,6 +50,10 @@ extern "C" {
     pub type AssetsMap;
     #[wasm_bindgen(typescript_type = "Map<string, string>")]
     pub type Accounts;
+    #[wasm_bindgen(typescript_type = "Map<string, string>")]
+    pub type DefinedErrors;
+    #[wasm_bindgen(typescript_type = "Map<string, Map<string, string>>")]
+    pub type ErrorsRegistry;
     #[wasm_bindgen(typescript_type = "EpochString")]
     pub type EpochString;
     #[wasm_bindgen(typescript_type = "ClarityVersionString")]
@@ -201,6 +211,16 @@ impl TransferSTXArgs {
     }
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PostConditionArgs {
+    principal: String,
+    asset: String,
+    condition: String,
+    amount: Option<u64>,
+    asset_id: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 #[wasm_bindgen]
@@ -251,6 +271,64 @@ pub fn execution_result_to_transaction_res(execution: &ExecutionResult) -> Trans
     }
 }
 
+/// Move all the batches of a deployment to `epoch` and write all its contracts in
+/// `clarity_version`. Without a Clarity version, the contracts written in a version more recent
+/// than the default one of their epoch are downgraded
+fn override_deployment_versions(
+    deployment: &mut DeploymentSpecification,
+    epoch: Option<StacksEpochId>,
+    clarity_version: Option<ClarityVersion>,
+) -> Result<(), String> {
+    for batch in deployment.plan.batches.iter_mut() {
+        if let Some(epoch) = epoch {
+            batch.epoch = Some(epoch.into());
+        }
+        let batch_epoch = batch
+            .epoch
+            .map(StacksEpochId::from)
+            .unwrap_or(DEFAULT_EPOCH);
+        let default_version = ClarityVersion::default_for_epoch(batch_epoch);
+        for transaction in batch.transactions.iter_mut() {
+            if let TransactionSpecification::EmulatedContractPublish(tx) = transaction {
+                match clarity_version {
+                    Some(version) if version > default_version => {
+                        return Err(format!("{version} can not be used with {batch_epoch}"));
+                    }
+                    Some(version) => tx.clarity_version = version,
+                    None if tx.clarity_version > default_version => {
+                        tx.clarity_version = default_version
+                    }
+                    None => {}
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn parse_epoch(epoch: &str) -> Option<StacksEpochId> {
+    match epoch {
+        "2.0" => Some(StacksEpochId::Epoch20),
+        "2.05" => Some(StacksEpochId::Epoch2_05),
+        "2.1" => Some(StacksEpochId::Epoch21),
+        "2.2" => Some(StacksEpochId::Epoch22),
+        "2.3" => Some(StacksEpochId::Epoch23),
+        "2.4" => Some(StacksEpochId::Epoch24),
+        "2.5" => Some(StacksEpochId::Epoch25),
+        "3.0" => Some(StacksEpochId::Epoch30),
+        _ => None,
+    }
+}
+
+fn parse_clarity_version(clarity_version: &str) -> Option<ClarityVersion> {
+    match clarity_version {
+        "Clarity1" => Some(ClarityVersion::Clarity1),
+        "Clarity2" => Some(ClarityVersion::Clarity2),
+        "Clarity3" => Some(ClarityVersion::Clarity3),
+        _ => None,
+    }
+}
+
 #[derive(Clone)]
 struct ProjectCache {
     accounts: HashMap<String, String>,
@@ -282,11 +360,16 @@ impl SDKOptions {
 pub struct SDK {
     #[wasm_bindgen(getter_with_clone)]
     pub deployer: String,
-    cache: HashMap<FileLocation, ProjectCache>,
+    /// Sessions by manifest, epoch and Clarity version (as passed to `initSession`)
+    cache: HashMap<(FileLocation, Option<StacksEpochId>, Option<String>), ProjectCache>,
     accounts: HashMap<String, String>,
     contracts_locations: HashMap<QualifiedContractIdentifier, FileLocation>,
     contracts_interfaces: HashMap<QualifiedContractIdentifier, ContractInterface>,
     session: Option<Session>,
+    /// Sessions saved by `snapshot`, by snapshot id
+    snapshots: BTreeMap<u32, Session>,
+    next_snapshot_id: u32,
+    fs_request: JsFunction,
     file_accessor: Box<dyn FileAccessor>,
     options: SDKOptions,
     current_test_name: String,
@@ -299,7 +382,7 @@ impl SDK {
     pub fn new(fs_request: JsFunction, options: Option<SDKOptions>) -> Self {
         panic::set_hook(Box::new(console_error_panic_hook::hook));
 
-        let fs = Box::new(WASMFileSystemAccessor::new(fs_request));
+        let fs = Box::new(WASMFileSystemAccessor::new(fs_request.clone()));
 
         let track_coverage = options.as_ref().map_or(false, |o| o.track_coverage);
         let track_costs = options.as_ref().map_or(false, |o| o.track_costs);
@@ -311,6 +394,9 @@ impl SDK {
             contracts_interfaces: HashMap::new(),
             contracts_locations: HashMap::new(),
             session: None,
+            snapshots: BTreeMap::new(),
+            next_snapshot_id: 0,
+            fs_request,
             file_accessor: fs,
             options: SDKOptions {
                 track_coverage,
@@ -352,24 +438,53 @@ impl SDK {
     pub async fn init_empty_session(&mut self) -> Result<(), String> {
         let session = Session::new(SessionSettings::default());
         self.session = Some(session);
+        self.snapshots.clear();
         Ok(())
     }
 
+    /// Deploy the contracts of the project in a new session. If an `epoch` or a
+    /// `clarity_version` is specified, all the contracts are deployed with it instead of the
+    /// ones set in the manifest
     #[wasm_bindgen(js_name=initSession)]
-    pub async fn init_session(&mut self, cwd: String, manifest_path: String) -> Result<(), String> {
+    pub async fn init_session(
+        &mut self,
+        cwd: String,
+        manifest_path: String,
+        epoch: Option<EpochString>,
+        clarity_version: Option<ClarityVersionString>,
+    ) -> Result<(), String> {
         let cwd_path = PathBuf::from(cwd);
         let cwd_root = FileLocation::FileSystem { path: cwd_path };
         let manifest_location = FileLocation::try_parse(&manifest_path, Some(&cwd_root))
             .ok_or("Failed to parse manifest location")?;
+        let epoch = match epoch.and_then(|epoch| epoch.as_string()) {
+            Some(epoch) => Some(parse_epoch(&epoch).ok_or(format!("Invalid epoch {epoch}"))?),
+            None => None,
+        };
+        let clarity_version_name = clarity_version.and_then(|version| version.as_string());
+        let clarity_version = match clarity_version_name {
+            Some(ref version) => Some(
+                parse_clarity_version(version)
+                    .ok_or(format!("Invalid clarity version {version}"))?,
+            ),
+            None => None,
+        };
+        let cache_key = (manifest_location.clone(), epoch, clarity_version_name);
 
         let ProjectCache {
             session,
             contracts_interfaces,
             contracts_locations,
             accounts,
-        } = match self.cache.get(&manifest_location) {
+        } = match self.cache.get(&cache_key) {
             Some(cache) => cache.clone(),
-            None => self.setup_session(&manifest_location).await?,
+            None => {
+                let cache = self
+                    .setup_session(&manifest_location, epoch, clarity_version)
+                    .await?;
+                self.cache.insert(cache_key, cache.clone());
+                cache
+            }
         };
 
         self.deployer = session.interpreter.get_tx_sender().to_string();
@@ -378,6 +493,7 @@ impl SDK {
         self.contracts_locations = contracts_locations;
         self.accounts = accounts;
         self.session = Some(session);
+        self.snapshots.clear();
 
         Ok(())
     }
@@ -385,6 +501,8 @@ impl SDK {
     async fn setup_session(
         &mut self,
         manifest_location: &FileLocation,
+        epoch: Option<StacksEpochId>,
+        clarity_version: Option<ClarityVersion>,
     ) -> Result<ProjectCache, String> {
         let manifest =
             ProjectManifest::from_file_accessor(manifest_location, &*self.file_accessor).await?;
@@ -450,6 +568,15 @@ impl SDK {
                 .await?;
         }
 
+        // the plan written on disk is left untouched by the versions override
+        let contracts_asts = match (epoch, clarity_version) {
+            (None, None) => Some(&artifacts.asts),
+            _ => {
+                override_deployment_versions(&mut deployment, epoch, clarity_version)?;
+                None
+            }
+        };
+
         let mut session = initiate_session_from_manifest(&manifest);
         if self.options.track_coverage {
             session.enable_coverage();
@@ -457,7 +584,7 @@ impl SDK {
         let executed_contracts = update_session_with_deployment_plan(
             &mut session,
             &deployment,
-            Some(&artifacts.asts),
+            contracts_asts,
             Some(DEFAULT_EPOCH),
         );
 
@@ -504,14 +631,12 @@ impl SDK {
             contracts_locations.insert(contract_id.clone(), location.clone());
         }
 
-        let cache = ProjectCache {
+        Ok(ProjectCache {
             accounts,
             contracts_interfaces,
             contracts_locations,
             session,
-        };
-        self.cache.insert(manifest_location.clone(), cache.clone());
-        Ok(cache)
+        })
     }
 
     #[wasm_bindgen(js_name=clearCache)]
@@ -605,16 +730,9 @@ impl SDK {
     #[wasm_bindgen(js_name=setEpoch)]
     pub fn set_epoch(&mut self, epoch: EpochString) {
         let epoch = epoch.as_string().unwrap_or("2.4".into());
-        let epoch = match epoch.as_str() {
-            "2.0" => StacksEpochId::Epoch20,
-            "2.05" => StacksEpochId::Epoch2_05,
-            "2.1" => StacksEpochId::Epoch21,
-            "2.2" => StacksEpochId::Epoch22,
-            "2.3" => StacksEpochId::Epoch23,
-            "2.4" => StacksEpochId::Epoch24,
-            "2.5" => StacksEpochId::Epoch25,
-            "3.0" => StacksEpochId::Epoch30,
-            _ => {
+        let epoch = match parse_epoch(&epoch) {
+            Some(epoch) => epoch,
+            None => {
                 log!("Invalid epoch {epoch}. Using default epoch");
                 DEFAULT_EPOCH
             }
@@ -694,6 +812,196 @@ impl SDK {
             .ok_or("value not found".into())
     }
 
+    /// Error constants defined by a contract, such as `(define-constant ERR_UNAUTHORIZED (err u401))`
+    #[wasm_bindgen(js_name=getDefinedErrors)]
+    pub fn get_defined_errors(&mut self, contract: &str) -> Result<DefinedErrors, String> {
+        let contract_id = self.desugar_contract_id(contract)?;
+        let session = self.get_session_mut();
+        let mut errors = BTreeMap::new();
+        for (name, value) in get_defined_errors(session, &contract_id)?.into_iter() {
+            let value_hex = value.serialize_to_hex().map_err(|e| e.to_string())?;
+            errors.insert(name, format!("0x{value_hex}"));
+        }
+        Ok(encode_to_js(&errors)
+            .map_err(|e| e.to_string())?
+            .unchecked_into::<DefinedErrors>())
+    }
+
+    /// Error constants of every contract of the project, by contract identifier
+    #[wasm_bindgen(js_name=getErrorsRegistry)]
+    pub fn get_errors_registry(&mut self) -> Result<ErrorsRegistry, String> {
+        let session = self.get_session_mut();
+        let registry = ErrorCodesRegistry::from_session(session)?;
+        let mut contracts = BTreeMap::new();
+        for (contract_id, errors) in registry.contracts.into_iter() {
+            let mut encoded_errors = BTreeMap::new();
+            for (name, value) in errors.into_iter() {
+                let value_hex = value.serialize_to_hex().map_err(|e| e.to_string())?;
+                encoded_errors.insert(name, format!("0x{value_hex}"));
+            }
+            contracts.insert(contract_id.to_string(), encoded_errors);
+        }
+        Ok(encode_to_js(&contracts)
+            .map_err(|e| e.to_string())?
+            .unchecked_into::<ErrorsRegistry>())
+    }
+
+    /// Data vars and map entries of the contracts of the project, as a JSON storage snapshot
+    #[wasm_bindgen(js_name=getStorageSnapshot)]
+    pub fn get_storage_snapshot(&mut self) -> Result<String, String> {
+        let session = self.get_session_mut();
+        take_snapshot(session, &[]).and_then(|snapshot| snapshot.to_json())
+    }
+
+    /// Save the state of the session, returns the id of the snapshot to pass to `revertTo`.
+    /// Several snapshots can be taken at the same block height.
+    #[wasm_bindgen(js_name=snapshot)]
+    pub fn snapshot(&mut self) -> u32 {
+        let snapshot = self.get_session().clone();
+        let snapshot_id = self.next_snapshot_id;
+        self.next_snapshot_id += 1;
+        self.snapshots.insert(snapshot_id, snapshot);
+        snapshot_id
+    }
+
+    /// Restore the session saved by the snapshot `snapshot_id`, the snapshots taken after it
+    /// are discarded
+    #[wasm_bindgen(js_name=revertTo)]
+    pub fn revert_to(&mut self, snapshot_id: u32) -> Result<(), String> {
+        let session = self
+            .snapshots
+            .get(&snapshot_id)
+            .ok_or(format!("no snapshot with id {snapshot_id}"))?
+            .clone();
+        self.snapshots.split_off(&(snapshot_id + 1));
+        self.session = Some(session);
+        Ok(())
+    }
+
+    /// State of the simulated chain as JSON, it can be written to a file and restored with
+    /// `fromState`, to skip the deployment of the contracts
+    #[wasm_bindgen(js_name=saveState)]
+    pub fn save_state(&self) -> Result<String, String> {
+        self.get_session().save_state().to_json()
+    }
+
+    /// Replace the session with the state saved by `saveState`. The accounts and the
+    /// contracts locations of the project set by `initSession` are kept.
+    #[wasm_bindgen(js_name=fromState)]
+    pub fn from_state(&mut self, state: String) -> Result<(), String> {
+        let state = SessionState::from_json(state.as_bytes())?;
+        let settings = match self.session {
+            Some(ref session) => session.settings.clone(),
+            None => SessionSettings::default(),
+        };
+        let session = Session::from_state(settings, &state)?;
+        for (contract_id, contract) in session.contracts.iter() {
+            if BOOT_CONTRACTS_DATA.contains_key(contract_id) {
+                continue;
+            }
+            if let Some(ref contract_interface) = contract.analysis.contract_interface {
+                self.contracts_interfaces
+                    .insert(contract_id.clone(), contract_interface.clone());
+            }
+        }
+        self.deployer = session.interpreter.get_tx_sender().to_string();
+        self.session = Some(session);
+        self.snapshots.clear();
+        Ok(())
+    }
+
+    /// New simnet in the same state, isolated: the changes made to one are not seen by the other
+    #[wasm_bindgen(js_name=fork)]
+    pub fn fork(&self) -> SDK {
+        let options = SDKOptions::new(self.options.track_costs, self.options.track_coverage);
+        let mut fork = SDK::new(self.fs_request.clone(), Some(options));
+        fork.deployer = self.deployer.clone();
+        fork.accounts = self.accounts.clone();
+        fork.contracts_locations = self.contracts_locations.clone();
+        fork.contracts_interfaces = self.contracts_interfaces.clone();
+        fork.session = self.session.clone();
+        fork.current_test_name = self.current_test_name.clone();
+        fork
+    }
+
+    /// Assets balances changes since the snapshot `snapshot_id`
+    #[wasm_bindgen(js_name=getAssetsMapDelta)]
+    pub fn get_assets_map_delta(&self, snapshot_id: u32) -> Result<AssetsMap, String> {
+        let snapshot = self
+            .snapshots
+            .get(&snapshot_id)
+            .ok_or(format!("no snapshot with id {snapshot_id}"))?;
+        let previous_assets_maps = snapshot.get_assets_maps();
+        let assets_maps = self.get_session().get_assets_maps();
+
+        let mut delta: BTreeMap<String, BTreeMap<String, i128>> = BTreeMap::new();
+        let empty = BTreeMap::new();
+        for asset in previous_assets_maps.keys().chain(assets_maps.keys()) {
+            let previous_balances = previous_assets_maps.get(asset).unwrap_or(&empty);
+            let balances = assets_maps.get(asset).unwrap_or(&empty);
+            for account in previous_balances.keys().chain(balances.keys()) {
+                let previous_balance = *previous_balances.get(account).unwrap_or(&0) as i128;
+                let balance = *balances.get(account).unwrap_or(&0) as i128;
+                if balance != previous_balance {
+                    delta
+                        .entry(asset.clone())
+                        .or_default()
+                        .insert(account.clone(), balance - previous_balance);
+                }
+            }
+        }
+        Ok(encode_to_js(&delta)
+            .map_err(|e| e.to_string())?
+            .unchecked_into::<AssetsMap>())
+    }
+
+    /// Post-conditions enforced on the next contract call, which is rolled back if they are not met.
+    /// They only apply to the next transaction: an STX transfer or a deployment discards them.
+    #[wasm_bindgen(js_name=setPostConditions)]
+    pub fn set_post_conditions(
+        &mut self,
+        post_conditions: js_sys::Array,
+        mode: &str,
+    ) -> Result<(), String> {
+        let mode = PostConditionMode::from_str(mode)?;
+        let args: Vec<PostConditionArgs> = post_conditions
+            .into_serde()
+            .map_err(|e| format!("Failed to parse post-conditions: {:}", e))?;
+        let specs = args
+            .into_iter()
+            .map(|arg| {
+                PostConditionSpecification::from_specifications(&PostConditionSpecificationFile {
+                    principal: arg.principal,
+                    asset: arg.asset,
+                    condition: arg.condition,
+                    amount: arg.amount,
+                    asset_id: arg.asset_id,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let session = self.get_session_mut();
+        let post_conditions = build_post_conditions(session, mode, &specs)?;
+        session.interpreter.set_post_conditions(post_conditions);
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name=signStructuredData)]
+    pub fn sign_structured_data(
+        &self,
+        domain: Vec<u8>,
+        message: Vec<u8>,
+        private_key: &str,
+    ) -> Result<Vec<u8>, String> {
+        let private_key = Secp256k1PrivateKey::from_hex(private_key.trim_start_matches("0x"))
+            .map_err(|e| format!("invalid private key: {}", e))?;
+        sip018::sign_structured_data(
+            &uint8_to_value(&domain),
+            &uint8_to_value(&message),
+            &private_key,
+        )
+    }
+
     fn get_function_interface(
         &self,
         contract: &str,
@@ -890,24 +1198,34 @@ impl SDK {
         Ok(execution_result_to_transaction_res(&execution))
     }
 
+    /// Run the transaction `run`, then discard the post-conditions whether it used them or not
+    fn run_transaction<F>(&mut self, run: F) -> Result<TransactionRes, String>
+    where
+        F: FnOnce(&mut Self) -> Result<TransactionRes, String>,
+    {
+        let result = run(self);
+        self.get_session_mut().interpreter.clear_post_conditions();
+        result
+    }
+
     #[wasm_bindgen(js_name=deployContract)]
     pub fn deploy_contract(&mut self, args: &DeployContractArgs) -> Result<TransactionRes, String> {
-        self.inner_deploy_contract(args, true)
+        self.run_transaction(|sdk| sdk.inner_deploy_contract(args, true))
     }
 
     #[wasm_bindgen(js_name = "transferSTX")]
     pub fn transfer_stx(&mut self, args: &TransferSTXArgs) -> Result<TransactionRes, String> {
-        self.inner_transfer_stx(args, true)
+        self.run_transaction(|sdk| sdk.inner_transfer_stx(args, true))
     }
 
     #[wasm_bindgen(js_name = "callPublicFn")]
     pub fn call_public_fn(&mut self, args: &CallFnArgs) -> Result<TransactionRes, String> {
-        self.inner_call_public_fn(args, true)
+        self.run_transaction(|sdk| sdk.inner_call_public_fn(args, true))
     }
 
     #[wasm_bindgen(js_name = "callPrivateFn")]
     pub fn call_private_fn(&mut self, args: &CallFnArgs) -> Result<TransactionRes, String> {
-        self.inner_call_private_fn(args, true)
+        self.run_transaction(|sdk| sdk.inner_call_private_fn(args, true))
     }
 
     #[wasm_bindgen(js_name=mineBlock)]
@@ -925,13 +1243,15 @@ impl SDK {
 
         for tx in txs {
             let result = if let Some(call_public) = tx.call_public_fn {
-                self.inner_call_public_fn(&CallFnArgs::from_json_args(call_public), false)
+                let args = CallFnArgs::from_json_args(call_public);
+                self.run_transaction(|sdk| sdk.inner_call_public_fn(&args, false))
             } else if let Some(call_private) = tx.call_private_fn {
-                self.inner_call_private_fn(&CallFnArgs::from_json_args(call_private), false)
+                let args = CallFnArgs::from_json_args(call_private);
+                self.run_transaction(|sdk| sdk.inner_call_private_fn(&args, false))
             } else if let Some(transfer_stx) = tx.transfer_stx {
-                self.inner_transfer_stx(&transfer_stx, false)
+                self.run_transaction(|sdk| sdk.inner_transfer_stx(&transfer_stx, false))
             } else if let Some(deploy_contract) = tx.deploy_contract {
-                self.inner_deploy_contract(&deploy_contract, false)
+                self.run_transaction(|sdk| sdk.inner_deploy_contract(&deploy_contract, false))
             } else {
                 return Err("Invalid tx arguments".into());
             }?;
@@ -61,3 +61,21 @@ async fn it_can_call_a_private_function() {
     let expected = format!("0x{}", ClarityValue::UInt(2).serialize_to_hex().unwrap());
     assert_eq!(tx.result, expected);
 }
+
+#[wasm_bindgen_test]
+async fn it_can_revert_to_a_snapshot() {
+    let mut sdk = init_sdk().await;
+    let block_height = sdk.block_height();
+    let snapshot_id = sdk.snapshot();
+    let _ = deploy_basic_contract(&mut sdk);
+    sdk.mine_empty_blocks(Some(3));
+    assert!(sdk.block_height() > block_height);
+    let later_snapshot_id = sdk.snapshot();
+
+    sdk.revert_to(snapshot_id).unwrap();
+    assert_eq!(sdk.block_height(), block_height);
+    assert!(sdk
+        .get_contract_source("ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.basic-contract")
+        .is_none());
+    assert!(sdk.revert_to(later_snapshot_id).is_err());
+}
@@ -13,10 +13,13 @@ import {
   type CallFn,
   type DeployContract,
   type GetDataVar,
+  type GetDefinedErrors,
+  type GetErrorsRegistry,
   type GetMapEntry,
   type MineBlock,
   type ParsedTransactionResult,
   type Execute,
+  type SignStructuredData,
   type TransferSTX,
 } from "../../common/src/sdkProxyHelpers.js";
 
@@ -41,7 +44,15 @@ export type Simnet = {
                 ? GetDataVar
                 : K extends "getMapEntry"
                   ? GetMapEntry
-                  : SDK[K];
+                  : K extends "getDefinedErrors"
+                    ? GetDefinedErrors
+                    : K extends "getErrorsRegistry"
+                      ? GetErrorsRegistry
+                      : K extends "signStructuredData"
+                        ? SignStructuredData
+                        : K extends "fork"
+                          ? () => Simnet
+                          : SDK[K];
 };
 
 function parseTxResponse(response: TransactionRes): ParsedTransactionResult {
@@ -149,6 +160,44 @@ export function getSessionProxy() {
         return getMapEntry;
       }
 
+      if (prop === "getDefinedErrors") {
+        const getDefinedErrors: GetDefinedErrors = (contract) => {
+          const response: Map<string, string> = session.getDefinedErrors(contract);
+          return new Map(
+            [...response.entries()].map(([name, value]) => [name, Cl.deserialize(value)]),
+          );
+        };
+        return getDefinedErrors;
+      }
+
+      if (prop === "getErrorsRegistry") {
+        const getErrorsRegistry: GetErrorsRegistry = () => {
+          const response: Map<string, Map<string, string>> = session.getErrorsRegistry();
+          return new Map(
+            [...response.entries()].map(([contract, errors]) => [
+              contract,
+              new Map([...errors.entries()].map(([name, value]) => [name, Cl.deserialize(value)])),
+            ]),
+          );
+        };
+        return getErrorsRegistry;
+      }
+
+      if (prop === "signStructuredData") {
+        const signStructuredData: SignStructuredData = (domain, message, privateKey) => {
+          return session.signStructuredData(
+            Cl.serialize(domain),
+            Cl.serialize(message),
+            privateKey,
+          );
+        };
+        return signStructuredData;
+      }
+
+      if (prop === "fork") {
+        return () => new Proxy(session.fork(), getSessionProxy()) as unknown as Simnet;
+      }
+
       return Reflect.get(session, prop, receiver);
     },
   };
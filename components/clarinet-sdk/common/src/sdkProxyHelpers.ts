@@ -117,3 +117,10 @@ export type MineBlock = (txs: Array<Tx>) => ParsedTransactionResult[];
 export type Execute = (snippet: string) => ParsedTransactionResult;
 export type GetDataVar = (contract: string, dataVar: string) => ClarityValue;
 export type GetMapEntry = (contract: string, mapName: string, mapKey: ClarityValue) => ClarityValue;
+export type GetDefinedErrors = (contract: string) => Map<string, ClarityValue>;
+export type GetErrorsRegistry = () => Map<string, Map<string, ClarityValue>>;
+export type SignStructuredData = (
+  domain: ClarityValue,
+  message: ClarityValue,
+  privateKey: string,
+) => Uint8Array;
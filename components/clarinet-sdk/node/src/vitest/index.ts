@@ -4,6 +4,10 @@ import url from "node:url";
 import yargs from "yargs";
 import { hideBin } from "yargs/helpers";
 
+import { getMatrixCellName, parseClarinetVitestsMatrix } from "./matrix.js";
+
+export { parseClarinetVitestsMatrix, type MatrixCell } from "./matrix.js";
+
 export function getClarinetVitestsArgv() {
   const argv = hideBin(process.argv);
   const topLevel = yargs(argv).argv;
@@ -15,6 +19,21 @@ export function getClarinetVitestsArgv() {
       type: "string",
       default: "./Clarinet.toml",
     })
+    .option("epoch", {
+      description: "Deploy the contracts in this epoch, instead of the ones set in the manifest",
+      type: "string",
+    })
+    .option("clarity-version", {
+      description: "Deploy the contracts in this Clarity version (1, 2 or 3)",
+      type: "string",
+      coerce: (version?: string) => version && `Clarity${version}`,
+    })
+    .option("matrix", {
+      description:
+        "Epochs and Clarity versions to run the tests in with getClarinetVitestsMatrix, e.g. epochs=2.05,2.1 clarity=1,2",
+      type: "string",
+      array: true,
+    })
     .option("init-before-each", {
       description: "Reinitialize the Clarinet state before each test",
       type: "boolean",
@@ -39,9 +58,39 @@ export function getClarinetVitestsArgv() {
       alias: "costs-file",
       type: "string",
       default: "costs-reports.json",
+    })
+    .option("failure-artifacts", {
+      description: "Write the calls, storage and costs of the failing tests in this directory",
+      type: "string",
     }).argv;
 }
 
+/*
+  Returns one vitest workspace project per epoch and Clarity version of the `--matrix` option, so
+  that the results of each combination are reported separately. To be used in `vitest.workspace.js`:
+    export default defineWorkspace(getClarinetVitestsMatrix("./vitest.config.js"));
+    - vitest run -- --matrix epochs=2.05,2.1,2.4
+    - vitest run -- --matrix epochs=2.4,3.0 clarity=2,3
+*/
+export function getClarinetVitestsMatrix(configFile: string) {
+  const { matrix, ...options } = getClarinetVitestsArgv() as Awaited<
+    ReturnType<typeof getClarinetVitestsArgv>
+  >;
+  if (!matrix || matrix.length === 0) {
+    return [configFile];
+  }
+
+  return parseClarinetVitestsMatrix(matrix.map(String)).map((cell) => ({
+    extends: configFile,
+    test: {
+      name: getMatrixCellName(cell),
+      environmentOptions: {
+        clarinet: { ...options, ...cell },
+      },
+    },
+  }));
+}
+
 // ensure vitest helpers can be imported even in workspace setup
 // import.meta.resolve return an url like "file:///absolute/path/to/clarinet-sdk/dist/esm/index.js"
 const sdkURL = import.meta.resolve("@hirosystems/clarinet-sdk");
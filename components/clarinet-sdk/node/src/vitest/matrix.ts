@@ -0,0 +1,64 @@
+import type { ClarityVersionString, EpochString } from "@hirosystems/clarinet-sdk-wasm";
+
+export type MatrixCell = {
+  epoch?: EpochString;
+  clarityVersion?: ClarityVersionString;
+};
+
+const epochs: EpochString[] = ["2.0", "2.05", "2.1", "2.2", "2.3", "2.4", "2.5", "3.0"];
+const clarityVersions: ClarityVersionString[] = ["Clarity1", "Clarity2", "Clarity3"];
+
+function latestClarityVersion(epoch: EpochString): ClarityVersionString {
+  if (epoch === "2.0" || epoch === "2.05") return "Clarity1";
+  if (epoch === "3.0") return "Clarity3";
+  return "Clarity2";
+}
+
+function parseValues(dimension: string) {
+  const values = (dimension.split("=")[1] ?? "").split(",").filter((value) => value.length > 0);
+  if (values.length === 0) {
+    throw new Error(`Invalid matrix "${dimension}", expected a list of values: epochs=2.05,2.1`);
+  }
+  return values;
+}
+
+/*
+  Epoch and Clarity version combinations of the `--matrix` option, e.g. `epochs=2.05,2.1 clarity=1,2`.
+  The combinations of a Clarity version with an epoch that doesn't support it are skipped.
+*/
+export function parseClarinetVitestsMatrix(matrix: string[]): MatrixCell[] {
+  let cellsEpochs: (EpochString | undefined)[] = [undefined];
+  let cellsClarityVersions: (ClarityVersionString | undefined)[] = [undefined];
+
+  for (const dimension of matrix) {
+    const name = dimension.split("=")[0];
+    if (name === "epochs") {
+      cellsEpochs = parseValues(dimension).map((epoch) => {
+        if (!epochs.includes(epoch as EpochString)) {
+          throw new Error(`Invalid epoch "${epoch}" in the matrix, expected one of ${epochs}`);
+        }
+        return epoch as EpochString;
+      });
+    } else if (name === "clarity") {
+      cellsClarityVersions = parseValues(dimension).map((version) => {
+        const clarityVersion = `Clarity${version}` as ClarityVersionString;
+        if (!clarityVersions.includes(clarityVersion)) {
+          throw new Error(`Invalid Clarity version "${version}" in the matrix, expected 1, 2 or 3`);
+        }
+        return clarityVersion;
+      });
+    } else {
+      throw new Error(`Invalid matrix "${dimension}", expected epochs=... or clarity=...`);
+    }
+  }
+
+  return cellsEpochs.flatMap((epoch) =>
+    cellsClarityVersions
+      .filter((version) => !epoch || !version || version <= latestClarityVersion(epoch))
+      .map((clarityVersion) => ({ epoch, clarityVersion })),
+  );
+}
+
+export function getMatrixCellName({ epoch, clarityVersion }: MatrixCell) {
+  return [epoch && `epoch ${epoch}`, clarityVersion].filter(Boolean).join(", ");
+}
@@ -16,6 +16,9 @@ import { vitestSetupFilePath, getClarinetVitestsArgv } from "@hirosystems/clarin
   The `getClarinetVitestsArgv()` will parse options passed to the command `vitest run --`
     - vitest run -- --manifest ./Clarinet.toml  # pass a custom path
     - vitest run -- --coverage --costs          # collect coverage and cost reports
+    - vitest run -- --epoch 2.1                 # deploy the contracts in epoch 2.1
+    - vitest run -- --clarity-version 1         # deploy the contracts in Clarity 1
+    - vitest run -- --failure-artifacts out     # write reports of the failing tests
 */
 
 export default defineConfig({
@@ -0,0 +1,110 @@
+import fs from "node:fs";
+import path from "node:path";
+import { describe, expect, it, beforeEach, afterEach } from "vitest";
+
+// test the built package and not the source code
+// makes it simpler to handle wasm build
+import { Simnet, initSimnet } from "..";
+import { parseClarinetVitestsMatrix } from "../src/vitest/matrix";
+
+let simnet: Simnet;
+
+const manifestPath = "tests/fixtures/Clarinet.toml";
+const deploymentPlanPath = path.join(
+  process.cwd(),
+  "tests/fixtures/deployments/default.simnet-plan.yaml",
+);
+
+function deleteExistingDeploymentPlan() {
+  if (fs.existsSync(deploymentPlanPath)) {
+    fs.unlinkSync(deploymentPlanPath);
+  }
+}
+
+function getCounterInterface() {
+  return simnet.getContractsInterfaces().get(`${simnet.deployer}.counter`)!;
+}
+
+beforeEach(async () => {
+  deleteExistingDeploymentPlan();
+  simnet = await initSimnet(manifestPath);
+});
+
+afterEach(() => {
+  deleteExistingDeploymentPlan();
+});
+
+describe("simnet sessions can override the epoch and clarity version", () => {
+  it("deploys the contracts with the manifest settings by default", () => {
+    const counter = getCounterInterface();
+    expect(counter.epoch).toBe("Epoch24");
+    expect(counter.clarity_version).toBe("Clarity2");
+  });
+
+  it("deploys the contracts in the given epoch", async () => {
+    await simnet.initSession(process.cwd(), manifestPath, "2.5");
+    const counter = getCounterInterface();
+    expect(counter.epoch).toBe("Epoch25");
+    expect(counter.clarity_version).toBe("Clarity2");
+
+    // clarity 2 is not available in epoch 2.05, the contracts are downgraded to clarity 1
+    await simnet.initSession(process.cwd(), manifestPath, "2.05");
+    const downgraded = getCounterInterface();
+    expect(downgraded.epoch).toBe("Epoch2_05");
+    expect(downgraded.clarity_version).toBe("Clarity1");
+
+    // the deployment plan on disk keeps the manifest settings
+    const plan = fs.readFileSync(deploymentPlanPath, "utf-8");
+    expect(plan).toContain("epoch: \"2.4\"");
+  });
+
+  it("deploys the contracts in the given clarity version", async () => {
+    await simnet.initSession(process.cwd(), manifestPath, "2.5", "Clarity1");
+    const counter = getCounterInterface();
+    expect(counter.epoch).toBe("Epoch25");
+    expect(counter.clarity_version).toBe("Clarity1");
+
+    await simnet.initSession(process.cwd(), manifestPath, undefined, "Clarity1");
+    expect(getCounterInterface().epoch).toBe("Epoch24");
+    expect(getCounterInterface().clarity_version).toBe("Clarity1");
+  });
+
+  it("rejects a clarity version not supported by the epoch", async () => {
+    await expect(
+      simnet.initSession(process.cwd(), manifestPath, "2.05", "Clarity2"),
+    ).rejects.toThrow("Clarity 2 can not be used with 2.05");
+    await expect(simnet.initSession(process.cwd(), manifestPath, "1.0" as any)).rejects.toThrow(
+      "Invalid epoch 1.0",
+    );
+  });
+});
+
+describe("epochs and clarity versions matrix", () => {
+  it("lists the epochs", () => {
+    expect(parseClarinetVitestsMatrix(["epochs=2.05,2.1,2.4"])).toStrictEqual([
+      { epoch: "2.05", clarityVersion: undefined },
+      { epoch: "2.1", clarityVersion: undefined },
+      { epoch: "2.4", clarityVersion: undefined },
+    ]);
+  });
+
+  it("combines the epochs and clarity versions", () => {
+    // clarity 3 is only available in epoch 3.0
+    expect(parseClarinetVitestsMatrix(["epochs=2.4,3.0", "clarity=2,3"])).toStrictEqual([
+      { epoch: "2.4", clarityVersion: "Clarity2" },
+      { epoch: "3.0", clarityVersion: "Clarity2" },
+      { epoch: "3.0", clarityVersion: "Clarity3" },
+    ]);
+    expect(parseClarinetVitestsMatrix(["clarity=1,2"])).toStrictEqual([
+      { epoch: undefined, clarityVersion: "Clarity1" },
+      { epoch: undefined, clarityVersion: "Clarity2" },
+    ]);
+  });
+
+  it("rejects invalid matrices", () => {
+    expect(() => parseClarinetVitestsMatrix(["epochs="])).toThrow("expected a list of values");
+    expect(() => parseClarinetVitestsMatrix(["epochs=2.6"])).toThrow('Invalid epoch "2.6"');
+    expect(() => parseClarinetVitestsMatrix(["clarity=4"])).toThrow('Invalid Clarity version "4"');
+    expect(() => parseClarinetVitestsMatrix(["costs=1"])).toThrow('Invalid matrix "costs=1"');
+  });
+});
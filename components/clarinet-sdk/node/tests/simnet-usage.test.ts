@@ -276,6 +276,109 @@ describe("simnet can read contracts data vars and maps", () => {
     const p = simnet.getMapEntry("counter", "participants", Cl.standardPrincipal(address1));
     expect(p).toStrictEqual(Cl.some(Cl.bool(true)));
   });
+
+  it("can get defined errors", () => {
+    const source = [
+      "(define-constant ERR_UNAUTHORIZED (err u401))",
+      "(define-constant ERR_NOT_FOUND (err u404))",
+      "(define-constant MAX_SUPPLY u1000)",
+    ].join("\n");
+    simnet.deployContract("errors", source, null, deployerAddr);
+
+    const errors = simnet.getDefinedErrors("errors");
+    expect(errors).toStrictEqual(
+      new Map([
+        ["ERR_NOT_FOUND", Cl.error(Cl.uint(404))],
+        ["ERR_UNAUTHORIZED", Cl.error(Cl.uint(401))],
+      ]),
+    );
+  });
+
+  it("can get the errors registry of the project", () => {
+    const source = "(define-constant ERR_UNAUTHORIZED (err u401))";
+    simnet.deployContract("errors", source, null, deployerAddr);
+
+    const registry = simnet.getErrorsRegistry();
+    expect(registry.get(`${deployerAddr}.errors`)).toStrictEqual(
+      new Map([["ERR_UNAUTHORIZED", Cl.error(Cl.uint(401))]]),
+    );
+    // contracts without error constants are not listed
+    expect(registry.has(`${deployerAddr}.counter`)).toBe(false);
+  });
+
+  it("can get the storage snapshot of the contracts", () => {
+    simnet.callPublicFn("counter", "increment", [], address1);
+
+    const snapshot = JSON.parse(simnet.getStorageSnapshot());
+    const counter = snapshot.contracts[`${deployerAddr}.counter`];
+    expect(counter.data_vars.count).toBe("u1");
+    expect(counter.maps.participants).toStrictEqual({ [`'${address1}`]: "true" });
+  });
+});
+
+describe("simnet can revert to a snapshot", () => {
+  it("can revert to a previous state", () => {
+    const height = simnet.blockHeight;
+    const snapshotId = simnet.snapshot();
+    simnet.callPublicFn("counter", "increment", [], address1);
+    expect(simnet.getDataVar("counter", "count")).toStrictEqual(Cl.uint(1));
+
+    simnet.revertTo(snapshotId);
+    expect(simnet.blockHeight).toBe(height);
+    expect(simnet.getDataVar("counter", "count")).toStrictEqual(Cl.uint(0));
+  });
+
+  it("keeps the snapshots taken at the same block height", () => {
+    const initial = simnet.snapshot();
+    simnet.mintSTX(address1, 1000n);
+    const funded = simnet.snapshot();
+    expect(funded).not.toBe(initial);
+
+    simnet.revertTo(initial);
+    expect(simnet.getAssetsMap().get("STX")?.get(address1)).toBe(100000000000000n);
+    // the snapshots taken after the restored one are discarded
+    expect(() => simnet.revertTo(funded)).toThrow();
+  });
+
+  it("can not revert without a snapshot", () => {
+    const snapshotId = simnet.snapshot();
+    expect(() => simnet.revertTo(snapshotId + 1)).toThrow();
+  });
+
+  it("can get assets map delta since a snapshot", () => {
+    const snapshotId = simnet.snapshot();
+    simnet.callPublicFn("counter", "increment", [], address1);
+    simnet.callPublicFn("counter", "increment", [], address1);
+
+    const delta = simnet.getAssetsMapDelta(snapshotId);
+    const STX = delta.get("STX")!;
+    expect(STX).toHaveLength(2);
+    expect(STX.get(address1)).toStrictEqual(-2000000n);
+    expect(STX.get(`${deployerAddr}.counter`)).toStrictEqual(2000000n);
+  });
+});
+
+describe("simnet can save, restore and fork its state", () => {
+  it("can restore a saved state", () => {
+    simnet.callPublicFn("counter", "increment", [], address1);
+    const state = simnet.saveState();
+    simnet.callPublicFn("counter", "increment", [], address1);
+    expect(simnet.getDataVar("counter", "count")).toStrictEqual(Cl.uint(2));
+
+    simnet.fromState(state);
+    expect(simnet.getDataVar("counter", "count")).toStrictEqual(Cl.uint(1));
+    expect(simnet.getContractsInterfaces().get(`${deployerAddr}.counter`)).toBeDefined();
+  });
+
+  it("can fork the simnet into isolated copies", () => {
+    simnet.callPublicFn("counter", "increment", [], address1);
+    const fork = simnet.fork();
+    fork.callPublicFn("counter", "increment", [], address1);
+
+    expect(fork.getDataVar("counter", "count")).toStrictEqual(Cl.uint(2));
+    expect(simnet.getDataVar("counter", "count")).toStrictEqual(Cl.uint(1));
+    expect(fork.blockHeight).toBe(simnet.blockHeight + 1);
+  });
 });
 
 describe("simnet can get contracts info and deploy contracts", () => {
@@ -382,6 +485,24 @@ describe("simnet can transfer stx", () => {
   });
 });
 
+describe("simnet enforces post-conditions", () => {
+  it("rolls back a call that does not meet its post-conditions", () => {
+    simnet.setPostConditions([], "deny");
+    expect(() => simnet.callPublicFn("counter", "increment", [], address1)).toThrow(
+      /Post-condition check failure/,
+    );
+    const count = simnet.getDataVar("counter", "count");
+    expect(count).toStrictEqual(Cl.uint(0));
+  });
+
+  it("discards the post-conditions after any transaction", () => {
+    simnet.setPostConditions([], "deny");
+    simnet.transferSTX(1000, address2, address1);
+    const res = simnet.callPublicFn("counter", "increment", [], address1);
+    expect(res.result).toStrictEqual(Cl.ok(Cl.bool(true)));
+  });
+});
+
 describe("the simnet can execute commands", () => {
   it("can mint_stx", () => {
     const result = simnet.executeCommand(
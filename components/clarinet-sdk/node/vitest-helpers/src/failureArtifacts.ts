@@ -0,0 +1,88 @@
+import fs from "node:fs";
+import path from "node:path";
+
+import { Cl, ClarityType, ClarityValue } from "@stacks/transactions";
+
+const tracedMethods = [
+  "callReadOnlyFn",
+  "callPublicFn",
+  "callPrivateFn",
+  "deployContract",
+  "transferSTX",
+  "mineBlock",
+  "execute",
+];
+
+export type TracedCall = {
+  method: string;
+  args: unknown[];
+  blockHeight: number;
+  result?: unknown;
+  error?: string;
+};
+
+export type FailureArtifact = {
+  test: string;
+  errors: string[];
+  blockHeight: number;
+  storage: unknown;
+  calls: TracedCall[];
+  costs?: unknown;
+};
+
+function isClarityValue(value: unknown): value is ClarityValue {
+  return (
+    typeof value === "object" &&
+    value !== null &&
+    "type" in value &&
+    Object.values(ClarityType).includes((value as ClarityValue).type)
+  );
+}
+
+function formatValue(value: unknown): unknown {
+  if (Array.isArray(value)) return value.map(formatValue);
+  if (isClarityValue(value)) return Cl.prettyPrint(value);
+  if (typeof value === "object" && value !== null) {
+    return Object.fromEntries(Object.entries(value).map(([k, v]) => [k, formatValue(v)]));
+  }
+  return value;
+}
+
+/*
+  Wraps the simnet so that the transactions it executes are recorded in `trace`,
+  with their arguments and results, to be reported if the test fails.
+*/
+export function traceSimnet(target: typeof simnet, trace: TracedCall[]): typeof simnet {
+  return new Proxy(target, {
+    get(target, prop, receiver) {
+      const value = Reflect.get(target, prop, receiver);
+      if (typeof prop !== "string" || !tracedMethods.includes(prop) || typeof value !== "function") {
+        return value;
+      }
+      return (...args: unknown[]) => {
+        const call: TracedCall = {
+          method: prop,
+          args: formatValue(args) as unknown[],
+          blockHeight: target.blockHeight,
+        };
+        trace.push(call);
+        try {
+          const response = value(...args);
+          call.result = formatValue(response);
+          return response;
+        } catch (e) {
+          call.error = e instanceof Error ? e.message : String(e);
+          throw e;
+        }
+      };
+    },
+  });
+}
+
+export function writeFailureArtifact(dir: string, artifact: FailureArtifact) {
+  fs.mkdirSync(dir, { recursive: true });
+  const fileName = `${artifact.test.replace(/[^\w.-]+/g, "_")}.json`;
+  const filePath = path.join(dir, fileName);
+  fs.writeFileSync(filePath, JSON.stringify(artifact, null, 2));
+  return filePath;
+}
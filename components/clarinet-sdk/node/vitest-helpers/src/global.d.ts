@@ -1,4 +1,5 @@
 import type { Simnet } from "../../dist/esm/node/src";
+import type { ClarityVersionString, EpochString } from "@hirosystems/clarinet-sdk-wasm";
 
 declare global {
   var simnet: Simnet;
@@ -8,6 +9,8 @@ declare global {
   var options: {
     clarinet: {
       manifestPath: string;
+      epoch?: EpochString;
+      clarityVersion?: ClarityVersionString;
       initBeforeEach: boolean;
       coverage: boolean;
       coverageFilename: string;
@@ -15,6 +18,7 @@ declare global {
       costsFilename: string;
       includeBootContracts: boolean;
       bootContractsPath: string;
+      failureArtifacts?: string;
     };
   };
 }
@@ -1,6 +1,7 @@
 import { Task, afterAll, beforeAll, beforeEach, afterEach } from "vitest";
 
 import "./clarityValuesMatchers";
+import { traceSimnet, writeFailureArtifact, type TracedCall } from "./failureArtifacts";
 
 function getFullTestName(task: Task, names: string[]) {
   const fullNames = [task.name, ...names];
@@ -15,13 +16,20 @@ function getFullTestName(task: Task, names: string[]) {
   If the session is initialised before each test, the reports are collected after each test.
   If the session is not initialised before each test, it'll be initialized in the `beforeAll`, which
   will run for all test file. In that case reports are collected in the after all.
+
+  With the `failureArtifacts` option, the simnet calls of each test are traced, and a failing test
+  writes the trace, the storage of the contracts and the costs in the `failureArtifacts` directory.
 */
 
+let untracedSimnet: typeof simnet | undefined;
+let callTrace: TracedCall[] = [];
+
 beforeEach(async (ctx) => {
-  const { coverage, initBeforeEach, manifestPath } = global.options.clarinet;
+  const { coverage, initBeforeEach, manifestPath, epoch, clarityVersion } =
+    global.options.clarinet;
 
   if (initBeforeEach) {
-    await simnet.initSession(process.cwd(), manifestPath);
+    await simnet.initSession(process.cwd(), manifestPath, epoch, clarityVersion);
   }
 
   if (coverage) {
@@ -29,24 +37,53 @@ beforeEach(async (ctx) => {
     const fullName = [ctx.task.file?.name || "", ...suiteTestNames].join("__");
     simnet.setCurrentTestName(fullName);
   }
+
+  if (global.options.clarinet.failureArtifacts) {
+    untracedSimnet ??= simnet;
+    callTrace = [];
+    global.simnet = traceSimnet(untracedSimnet, callTrace);
+  }
 });
 
-afterEach(async () => {
-  const { coverage, costs, initBeforeEach, includeBootContracts, bootContractsPath } =
-    global.options.clarinet;
+afterEach(async (ctx) => {
+  const {
+    coverage,
+    costs,
+    initBeforeEach,
+    includeBootContracts,
+    bootContractsPath,
+    failureArtifacts,
+  } = global.options.clarinet;
+
+  if (untracedSimnet) global.simnet = untracedSimnet;
 
+  let costsReport: string | undefined;
   if (initBeforeEach && (coverage || costs)) {
     const report = simnet.collectReport(includeBootContracts, bootContractsPath || "");
     if (coverage) coverageReports.push(report.coverage);
     if (costs) costsReports.push(report.costs);
+    costsReport = costs ? report.costs : undefined;
+  }
+
+  if (failureArtifacts && ctx.task.result?.state === "fail") {
+    const test = [ctx.task.file?.name || "", ...getFullTestName(ctx.task, [])].join("__");
+    const artifactPath = writeFailureArtifact(failureArtifacts, {
+      test,
+      errors: (ctx.task.result.errors || []).map((e) => e.message),
+      blockHeight: simnet.blockHeight,
+      storage: JSON.parse(simnet.getStorageSnapshot()),
+      calls: callTrace,
+      costs: costsReport ? JSON.parse(costsReport) : undefined,
+    });
+    console.log(`failure artifact written to ${artifactPath}`);
   }
 });
 
 beforeAll(async () => {
-  const { initBeforeEach, manifestPath } = global.options.clarinet;
+  const { initBeforeEach, manifestPath, epoch, clarityVersion } = global.options.clarinet;
 
   if (!initBeforeEach) {
-    await simnet.initSession(process.cwd(), manifestPath);
+    await simnet.initSession(process.cwd(), manifestPath, epoch, clarityVersion);
   }
 });
 
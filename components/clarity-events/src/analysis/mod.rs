@@ -445,6 +445,7 @@ pub struct EventCollector<'a, 'b> {
     pub type_checker: TypeChecker<'a, 'b>,
     pub last_entries: Vec<StacksTransactionEvent>,
     pub contract_identifier: Option<QualifiedContractIdentifier>,
+    pub ft_supplies: BTreeMap<ClarityName, Option<String>>,
 }
 
 impl<'a, 'b> EventCollector<'a, 'b> {
@@ -457,6 +458,7 @@ impl<'a, 'b> EventCollector<'a, 'b> {
             type_checker,
             last_entries: vec![],
             contract_identifier: None,
+            ft_supplies: BTreeMap::new(),
         }
     }
 
@@ -632,6 +634,21 @@ impl ASTVisitor<'_> for EventCollector<'_, '_> {
         true
     }
 
+    fn visit_define_ft(
+        &mut self,
+        _expr: &SymbolicExpression,
+        name: &ClarityName,
+        supply: Option<&SymbolicExpression>,
+    ) -> bool {
+        let supply = supply.map(|supply| match &supply.expr {
+            SymbolicExpressionType::AtomValue(value)
+            | SymbolicExpressionType::LiteralValue(value) => value.to_string(),
+            _ => supply.to_string(),
+        });
+        self.ft_supplies.insert(name.clone(), supply);
+        true
+    }
+
     fn visit_print(&mut self, expr: &SymbolicExpression, value: &SymbolicExpression) -> bool {
         let value_type_shape = self
             .type_checker
@@ -6,15 +6,20 @@ extern crate serde_derive;
 extern crate serde_json;
 
 pub mod analysis;
+pub mod catalog;
 
 use analysis::{EventCollector, Settings};
+use catalog::{build_contract_catalog, EventCatalog};
 use clap::{Parser, Subcommand};
 use clarinet_files::FileLocation;
 use clarity_repl::clarity::analysis::type_checker::v2_05::TypeChecker;
+use clarity_repl::clarity::ClarityVersion;
+use clarity_repl::repl::{ClarityCodeSource, ClarityContract, ContractDeployer};
 use clarity_repl::{
     clarity::{costs::LimitedCostTracker, EvaluationResult},
     repl::{Session, SessionSettings},
 };
+use std::path::Path;
 
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
@@ -28,6 +33,9 @@ enum Command {
     /// Format file
     #[clap(name = "scan", bin_name = "scan")]
     Scan(Scan),
+    /// Generate a catalog of the events and assets of contracts
+    #[clap(name = "catalog", bin_name = "catalog")]
+    Catalog(Catalog),
 }
 
 #[derive(Parser, PartialEq, Clone, Debug)]
@@ -36,6 +44,19 @@ struct Scan {
     pub file_path: String,
 }
 
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct Catalog {
+    /// Contracts file paths, in deployment order
+    #[clap(required = true)]
+    pub file_paths: Vec<String>,
+    /// Print the catalog as markdown instead of json
+    #[clap(long = "markdown")]
+    pub markdown: bool,
+    /// Write events-catalog.json and events-catalog.md in this directory
+    #[clap(long = "output-dir", short = 'o')]
+    pub output_dir: Option<String>,
+}
+
 pub fn main() {
     let opts: Opts = match Opts::try_parse() {
         Ok(opts) => opts,
@@ -85,5 +106,59 @@ pub fn main() {
                 }
             }
         }
+        Command::Catalog(cmd) => {
+            let mut session = Session::new(SessionSettings::default());
+            let mut contracts = vec![];
+            for file_path in cmd.file_paths.iter() {
+                let file = FileLocation::from_path_string(file_path).unwrap();
+                let source = file.read_content_as_utf8().unwrap();
+                let name = Path::new(file_path)
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .unwrap_or("contract")
+                    .to_string();
+                let contract = ClarityContract {
+                    code_source: ClarityCodeSource::ContractInMemory(source),
+                    name,
+                    deployer: ContractDeployer::DefaultDeployer,
+                    clarity_version: ClarityVersion::default_for_epoch(session.current_epoch),
+                    epoch: session.current_epoch,
+                };
+                let mut contract_analysis = match session.deploy_contract(&contract, false, None) {
+                    Ok(execution) => match execution.result {
+                        EvaluationResult::Contract(evaluation) => evaluation.contract.analysis,
+                        _ => {
+                            println!("{}: empty contract", file_path);
+                            std::process::exit(1);
+                        }
+                    },
+                    Err(e) => {
+                        println!("{}: {:?}", file_path, e);
+                        std::process::exit(1);
+                    }
+                };
+                contracts.push(build_contract_catalog(&mut session, &mut contract_analysis));
+            }
+
+            let catalog = EventCatalog { contracts };
+            let json = serde_json::to_string_pretty(&catalog).unwrap();
+            match cmd.output_dir {
+                Some(output_dir) => {
+                    let output_dir = Path::new(&output_dir);
+                    let write = |file_name: &str, content: String| {
+                        let path = output_dir.join(file_name);
+                        if let Err(e) = std::fs::write(&path, content) {
+                            println!("unable to write {}: {}", path.display(), e);
+                            std::process::exit(1);
+                        }
+                        println!("{} generated", path.display());
+                    };
+                    write("events-catalog.json", json);
+                    write("events-catalog.md", catalog.to_markdown());
+                }
+                None if cmd.markdown => println!("{}", catalog.to_markdown()),
+                None => println!("{}", json),
+            }
+        }
     };
 }
@@ -0,0 +1,204 @@
+use clarity_repl::clarity::analysis::type_checker::v2_05::TypeChecker;
+use clarity_repl::clarity::costs::LimitedCostTracker;
+use clarity_repl::clarity::vm::analysis::types::ContractAnalysis;
+use clarity_repl::repl::Session;
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+
+use crate::analysis::{EventCollector, Settings, StacksTransactionEvent};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EventCatalog {
+    pub contracts: Vec<ContractEventCatalog>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ContractEventCatalog {
+    pub contract_identifier: String,
+    pub fungible_tokens: Vec<FungibleTokenEntry>,
+    pub non_fungible_tokens: Vec<NonFungibleTokenEntry>,
+    /// Events emitted while deploying the contract
+    pub deployment_events: Vec<StacksTransactionEvent>,
+    /// Events emitted by the functions of the contract, functions without events are omitted
+    pub functions: Vec<FunctionEvents>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FungibleTokenEntry {
+    pub name: String,
+    pub asset_identifier: String,
+    pub supply: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NonFungibleTokenEntry {
+    pub name: String,
+    pub asset_identifier: String,
+    pub asset_type: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FunctionEvents {
+    pub name: String,
+    pub events: Vec<StacksTransactionEvent>,
+}
+
+/// Collect the print event shapes and the assets of a contract deployed in `session`.
+pub fn build_contract_catalog(
+    session: &mut Session,
+    contract_analysis: &mut ContractAnalysis,
+) -> ContractEventCatalog {
+    let mut analysis_db = session.interpreter.clarity_datastore.as_analysis_db();
+    let cost_track = LimitedCostTracker::new_free();
+    let type_checker = TypeChecker::new(&mut analysis_db, cost_track, true);
+    let mut event_collector = EventCollector::new(Settings::default(), type_checker);
+    let event_map = event_collector.run(contract_analysis);
+
+    let contract_id = &contract_analysis.contract_identifier;
+    let fungible_tokens = event_collector
+        .ft_supplies
+        .iter()
+        .map(|(name, supply)| FungibleTokenEntry {
+            name: name.to_string(),
+            asset_identifier: format!("{}::{}", contract_id, name),
+            supply: supply.clone(),
+        })
+        .collect();
+    let non_fungible_tokens = contract_analysis
+        .non_fungible_tokens
+        .iter()
+        .map(|(name, asset_type)| NonFungibleTokenEntry {
+            name: name.to_string(),
+            asset_identifier: format!("{}::{}", contract_id, name),
+            asset_type: asset_type.to_string(),
+        })
+        .collect();
+    let functions = event_map
+        .into_iter()
+        .filter_map(|(name, events)| match name {
+            Some(name) if !events.is_empty() => Some(FunctionEvents {
+                name: name.to_string(),
+                events,
+            }),
+            _ => None,
+        })
+        .collect();
+
+    ContractEventCatalog {
+        contract_identifier: contract_id.to_string(),
+        fungible_tokens,
+        non_fungible_tokens,
+        deployment_events: event_collector.last_entries.clone(),
+        functions,
+    }
+}
+
+pub fn markdown_events(events: &[StacksTransactionEvent], output: &mut Vec<String>) {
+    for event in events.iter() {
+        let event = json!(event);
+        let event_type = event["event_type"].as_str().unwrap_or_default().to_string();
+        let data = match event_type.as_str() {
+            "print" => event["print"]["data_type"].clone(),
+            _ => event.get(&event_type).cloned().unwrap_or(JsonValue::Null),
+        };
+        if data.is_null() {
+            output.push(format!("- `{}`", event_type));
+        } else {
+            output.push(format!("- `{}`: `{}`", event_type, data));
+        }
+    }
+}
+
+impl EventCatalog {
+    pub fn to_markdown(&self) -> String {
+        let mut output = vec!["# Events catalog".to_string()];
+        for contract in self.contracts.iter() {
+            output.push(format!("\n## `{}`", contract.contract_identifier));
+
+            if !contract.fungible_tokens.is_empty() {
+                output.push("\n### Fungible tokens\n".to_string());
+                output.push("| Name | Asset identifier | Supply |".to_string());
+                output.push("| --- | --- | --- |".to_string());
+                for ft in contract.fungible_tokens.iter() {
+                    output.push(format!(
+                        "| {} | `{}` | {} |",
+                        ft.name,
+                        ft.asset_identifier,
+                        ft.supply.as_deref().unwrap_or("unlimited")
+                    ));
+                }
+            }
+
+            if !contract.non_fungible_tokens.is_empty() {
+                output.push("\n### Non fungible tokens\n".to_string());
+                output.push("| Name | Asset identifier | Type |".to_string());
+                output.push("| --- | --- | --- |".to_string());
+                for nft in contract.non_fungible_tokens.iter() {
+                    output.push(format!(
+                        "| {} | `{}` | `{}` |",
+                        nft.name, nft.asset_identifier, nft.asset_type
+                    ));
+                }
+            }
+
+            if !contract.deployment_events.is_empty() {
+                output.push("\n### Deployment events\n".to_string());
+                markdown_events(&contract.deployment_events, &mut output);
+            }
+
+            if !contract.functions.is_empty() {
+                output.push("\n### Functions events".to_string());
+                for function in contract.functions.iter() {
+                    output.push(format!("\n#### `{}`\n", function.name));
+                    markdown_events(&function.events, &mut output);
+                }
+            }
+        }
+        output.push(String::new());
+        output.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clarity_repl::clarity::EvaluationResult;
+    use clarity_repl::repl::SessionSettings;
+
+    #[test]
+    fn test_build_contract_catalog() {
+        let snippet = [
+            "(define-fungible-token token u1000000)",
+            "(define-non-fungible-token badge uint)",
+            "(define-public (mint (amount uint))",
+            "  (begin",
+            "    (print { action: \"mint\", amount: amount })",
+            "    (ft-mint? token amount tx-sender)))",
+            "(define-read-only (get-one) u1)",
+        ]
+        .join("\n");
+        let mut session = Session::new(SessionSettings::default());
+        let mut contract_analysis = match session.eval(snippet, false).unwrap().result {
+            EvaluationResult::Contract(evaluation) => evaluation.contract.analysis,
+            _ => unreachable!(),
+        };
+        let catalog = build_contract_catalog(&mut session, &mut contract_analysis);
+
+        assert_eq!(catalog.fungible_tokens.len(), 1);
+        assert_eq!(
+            catalog.fungible_tokens[0].supply,
+            Some("u1000000".to_string())
+        );
+        assert_eq!(catalog.non_fungible_tokens[0].asset_type, "uint");
+        assert_eq!(catalog.functions.len(), 1);
+        assert_eq!(catalog.functions[0].name, "mint");
+        assert_eq!(catalog.functions[0].events.len(), 2);
+
+        let markdown = EventCatalog {
+            contracts: vec![catalog],
+        }
+        .to_markdown();
+        assert!(markdown.contains("#### `mint`"));
+        assert!(markdown.contains("- `mint_ft_event`"));
+    }
+}
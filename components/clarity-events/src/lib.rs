@@ -4,3 +4,4 @@ extern crate serde;
 extern crate serde_json;
 
 pub mod analysis;
+pub mod catalog;
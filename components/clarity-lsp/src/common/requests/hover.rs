@@ -1,15 +1,131 @@
+use clarity_repl::clarity::representations::Span;
 use clarity_repl::clarity::SymbolicExpression;
 use lsp_types::Position;
 
 use super::{api_ref::API_REF, helpers::get_expression_name_at_position};
 
+static DEFINE_FUNCTIONS: [&str; 3] = ["define-public", "define-read-only", "define-private"];
+
 pub fn get_expression_documentation(
     position: &Position,
     expressions: &Vec<SymbolicExpression>,
+    source: &str,
 ) -> Option<String> {
     let expression_name = get_expression_name_at_position(position, expressions)?;
 
-    API_REF
-        .get(&expression_name.to_string())
-        .map(|(documentation, _)| documentation.to_owned())
+    match API_REF.get(&expression_name.to_string()) {
+        Some((documentation, _)) => Some(documentation.to_owned()),
+        None => get_defined_function_signature(&expression_name, expressions, source)
+            .map(|signature| format!("```clarity\n{}\n```", signature)),
+    }
+}
+
+fn get_source_at_span(source: &str, span: &Span) -> Option<String> {
+    let mut text = vec![];
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index as u32 + 1;
+        if line_number < span.start_line || line_number > span.end_line {
+            continue;
+        }
+        let start = match line_number == span.start_line {
+            true => (span.start_column as usize).saturating_sub(1),
+            false => 0,
+        };
+        let end = match line_number == span.end_line {
+            true => span.end_column as usize,
+            false => line.chars().count(),
+        };
+        text.push(line.chars().take(end).skip(start).collect::<String>());
+    }
+    match text.is_empty() {
+        true => None,
+        false => Some(text.join(" ")),
+    }
+}
+
+/// Signature of a function defined in the contract, e.g. `(define-public (transfer (amount uint)))`
+fn get_defined_function_signature(
+    name: &str,
+    expressions: &[SymbolicExpression],
+    source: &str,
+) -> Option<String> {
+    for expression in expressions {
+        let Some([define, signature, ..]) = expression.match_list() else {
+            continue;
+        };
+        let Some(define) = define.match_atom() else {
+            continue;
+        };
+        if !DEFINE_FUNCTIONS.contains(&define.as_str()) {
+            continue;
+        }
+        let function_name = signature
+            .match_list()
+            .and_then(|signature| signature.first())
+            .and_then(|function_name| function_name.match_atom());
+        if function_name.map(|n| n.as_str()) != Some(name) {
+            continue;
+        }
+        let signature = get_source_at_span(source, &signature.span)?;
+        let signature = signature.split_whitespace().collect::<Vec<_>>().join(" ");
+        return Some(format!("({} {})", define, signature));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use clarity_repl::clarity::ast::build_ast_with_rules;
+    use clarity_repl::clarity::representations::Span;
+    use clarity_repl::clarity::vm::types::QualifiedContractIdentifier;
+    use clarity_repl::clarity::{ClarityVersion, StacksEpochId};
+    use lsp_types::Position;
+
+    use super::{get_expression_documentation, get_source_at_span};
+
+    #[test]
+    fn get_defined_function_documentation() {
+        let source = [
+            "(define-read-only (get-balance",
+            "    (owner principal))",
+            "  (ok u1))",
+            "(define-public (transfer (amount uint))",
+            "  (get-balance tx-sender))",
+        ]
+        .join("\n");
+        let contract_ast = build_ast_with_rules(
+            &QualifiedContractIdentifier::transient(),
+            &source,
+            &mut (),
+            ClarityVersion::Clarity2,
+            StacksEpochId::Epoch21,
+            clarity_repl::clarity::ast::ASTRules::Typical,
+        )
+        .unwrap();
+
+        let position = Position {
+            line: 5,
+            character: 5,
+        };
+        let documentation =
+            get_expression_documentation(&position, &contract_ast.expressions, &source);
+        assert_eq!(
+            documentation,
+            Some("```clarity\n(define-read-only (get-balance (owner principal)))\n```".to_string())
+        );
+    }
+
+    #[test]
+    fn get_source_at_span_from_column_zero() {
+        let span = Span {
+            start_line: 1,
+            start_column: 0,
+            end_line: 1,
+            end_column: 4,
+        };
+        assert_eq!(
+            get_source_at_span("(ok u1)", &span),
+            Some("(ok ".to_string())
+        );
+    }
 }
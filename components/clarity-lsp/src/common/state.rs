@@ -413,8 +413,11 @@ impl EditorState {
             line: position.line + 1,
             character: position.character + 1,
         };
-        let documentation =
-            get_expression_documentation(&position, contract.expressions.as_ref()?)?;
+        let documentation = get_expression_documentation(
+            &position,
+            contract.expressions.as_ref()?,
+            &contract.source,
+        )?;
 
         Some(Hover {
             contents: lsp_types::HoverContents::Markup(lsp_types::MarkupContent {
@@ -0,0 +1,308 @@
+use crate::repl::clarity_values::value_to_string;
+use crate::repl::Session;
+use clarity::vm::diagnostic::Diagnostic;
+use clarity::vm::{EvaluationResult, ExecutionResult};
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+
+/// Console commands provided by a project, mapping a name to the program implementing it.
+/// `::<name> <args>` spawns the program with `args` appended to its own arguments. The
+/// program drives the console session through its stdin and stdout:
+/// - the first line of its stdin is a JSON document describing the session
+/// - each line it prints on stdout is evaluated in the session, as a Clarity expression
+///   or a `::` command, and the result is written back on its stdin as a JSON line
+///   (`{"result": ..}`, `{"error": ..}`, `{"contract": ..}` or `{"output": [..]}`)
+///
+/// Its stdin stays open until it exits.
+///
+/// Unlike the `[project.plugins]` hooks, console commands can't be WebAssembly modules:
+/// a module only gets its input once, it couldn't read the results of its lines.
+#[derive(Debug, Clone, Default)]
+pub struct ConsoleCommands {
+    pub commands: BTreeMap<String, String>,
+    pub working_dir: Option<PathBuf>,
+}
+
+impl ConsoleCommands {
+    pub fn new(commands: BTreeMap<String, String>, working_dir: Option<PathBuf>) -> Self {
+        ConsoleCommands {
+            commands,
+            working_dir,
+        }
+    }
+
+    /// Name and arguments of `input` if it invokes one of the commands. The arguments
+    /// are split like a shell would, quotes included.
+    pub fn parse(&self, input: &str) -> Option<Result<(String, Vec<String>), String>> {
+        let name = input.split_whitespace().next()?.strip_prefix("::")?;
+        if !self.commands.contains_key(name) {
+            return None;
+        }
+        let args = shell_words::split(input)
+            .map(|words| words.into_iter().skip(1).collect())
+            .map_err(|e| format!("unable to parse the arguments of ::{} ({})", name, e));
+        Some(args.map(|args| (name.to_string(), args)))
+    }
+
+    /// Run the command `name`, calling `evaluate` for each line it prints. `evaluate`
+    /// returns the response written back to the command.
+    pub fn run<F>(
+        &self,
+        name: &str,
+        args: &[String],
+        context: &serde_json::Value,
+        mut evaluate: F,
+    ) -> Result<(), String>
+    where
+        F: FnMut(&str) -> serde_json::Value,
+    {
+        let command = self
+            .commands
+            .get(name)
+            .ok_or(format!("unknown console command '{}'", name))?;
+        let mut words = shell_words::split(command)
+            .map_err(|e| format!("unable to parse console command '{}' ({})", name, e))?
+            .into_iter();
+        let program = words
+            .next()
+            .ok_or(format!("console command '{}' is empty", name))?;
+        if program.ends_with(".wasm") {
+            return Err(format!(
+                "console command '{}' must be an executable, WebAssembly modules are not supported",
+                name
+            ));
+        }
+
+        let mut process = Command::new(program);
+        process
+            .args(words)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped());
+        if let Some(working_dir) = &self.working_dir {
+            process.current_dir(working_dir);
+        }
+        let mut child = process
+            .spawn()
+            .map_err(|e| format!("unable to spawn `{}` ({})", command, e))?;
+
+        // The responses are written from another thread, so that a command which does not
+        // read its stdin can't block the console. Broken pipes are ignored for the same reason.
+        let (responses, receiver) = mpsc::channel::<String>();
+        let stdin = child.stdin.take();
+        let writer = thread::spawn(move || {
+            let Some(mut stdin) = stdin else {
+                return;
+            };
+            for response in receiver {
+                if writeln!(stdin, "{}", response).is_err() {
+                    break;
+                }
+            }
+        });
+        let input = json!({
+            "command": name,
+            "args": args,
+            "session": context,
+        });
+        let _ = responses.send(input.to_string());
+
+        if let Some(stdout) = child.stdout.take() {
+            for line in BufReader::new(stdout).lines() {
+                let line = match line {
+                    Ok(line) => line,
+                    Err(e) => {
+                        let _ = child.kill();
+                        return Err(format!("unable to read from `{}` ({})", command, e));
+                    }
+                };
+                let line = line.trim();
+                if line.is_empty() {
+                    continue;
+                }
+                let _ = responses.send(evaluate(line).to_string());
+            }
+        }
+        drop(responses);
+
+        let status = child
+            .wait()
+            .map_err(|e| format!("unable to run `{}` ({})", command, e))?;
+        let _ = writer.join();
+        if !status.success() {
+            return Err(format!("`{}` failed ({})", command, status));
+        }
+        Ok(())
+    }
+}
+
+/// State of the session shared with the console commands when they start
+pub fn get_session_context(session: &mut Session) -> serde_json::Value {
+    let accounts: Vec<serde_json::Value> = session
+        .interpreter
+        .get_accounts()
+        .iter()
+        .map(|address| {
+            let balance = session.interpreter.get_balance_for_account(address, "STX");
+            json!({ "address": address, "balance": balance.to_string() })
+        })
+        .collect();
+    let contracts: Vec<String> = session
+        .contracts
+        .keys()
+        .map(|contract_id| contract_id.to_string())
+        .collect();
+    json!({
+        "tx_sender": session.get_tx_sender(),
+        "block_height": session.interpreter.get_block_height(),
+        "burn_block_height": session.interpreter.get_burn_block_height(),
+        "epoch": session.current_epoch.to_string(),
+        "accounts": accounts,
+        "contracts": contracts,
+    })
+}
+
+/// Response sent to a console command for one of its lines, `output` being the lines
+/// printed by the console and `result` the evaluation of a Clarity expression
+pub fn get_evaluation_response(
+    output: &[String],
+    result: &Option<Result<ExecutionResult, Vec<Diagnostic>>>,
+) -> serde_json::Value {
+    match result {
+        Some(Ok(execution)) => match &execution.result {
+            EvaluationResult::Snippet(snippet) => {
+                json!({ "result": value_to_string(&snippet.result) })
+            }
+            EvaluationResult::Contract(contract) => json!({
+                "contract": contract.contract.analysis.contract_identifier.to_string(),
+            }),
+        },
+        Some(Err(diagnostics)) => {
+            let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+            json!({ "error": messages.join("\n") })
+        }
+        None => json!({ "output": output }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repl::SessionSettings;
+    use clarity::types::StacksEpochId;
+
+    fn project_commands(name: &str, command: &str) -> ConsoleCommands {
+        let mut commands = BTreeMap::new();
+        commands.insert(name.to_string(), command.to_string());
+        ConsoleCommands::new(commands, Some(std::env::temp_dir()))
+    }
+
+    #[test]
+    fn test_parse_console_command() {
+        let console_commands = project_commands("mint-test-nfts", "deno run -A ./scripts/mint.ts");
+        let parse = |input: &str| console_commands.parse(input);
+
+        assert_eq!(
+            parse("::mint-test-nfts 50 \"Test NFT\" u1"),
+            Some(Ok((
+                "mint-test-nfts".to_string(),
+                vec!["50".to_string(), "Test NFT".to_string(), "u1".to_string()]
+            )))
+        );
+        assert_eq!(
+            parse("::mint-test-nfts"),
+            Some(Ok(("mint-test-nfts".to_string(), vec![])))
+        );
+        assert!(matches!(parse("::mint-test-nfts \"50"), Some(Err(_))));
+        assert_eq!(parse("::get_contracts"), None);
+        assert_eq!(parse("(mint-test-nfts u1)"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_console_command() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let script = temp_dir.path().join("console-command.sh");
+        std::fs::write(
+            &script,
+            [
+                "read -r context",
+                "echo \"(+ u1 $1)\"",
+                "read -r response",
+                "echo \"(print \\\"$2\\\")\"",
+                "read -r response",
+                "printf '%s\\n' \"$context\"",
+            ]
+            .join("\n"),
+        )
+        .unwrap();
+        let console_commands = project_commands("add", &format!("sh '{}'", script.display()));
+
+        let mut session = Session::new(SessionSettings::default());
+        session.update_epoch(StacksEpochId::Epoch25);
+        let context = get_session_context(&mut session);
+        let mut lines = vec![];
+        let args = ["u2".to_string(), "hello world".to_string()];
+        console_commands
+            .run("add", &args, &context, |line| {
+                lines.push(line.to_string());
+                let (_, output, result) = session.process_console_input(line);
+                get_evaluation_response(&output, &result)
+            })
+            .unwrap();
+
+        assert_eq!(lines[0], "(+ u1 u2)");
+        assert_eq!(lines[1], "(print \"hello world\")");
+        // the context received on the first line
+        let received: serde_json::Value = serde_json::from_str(&lines[2]).unwrap();
+        assert_eq!(received["command"], "add");
+        assert_eq!(received["args"], json!(["u2", "hello world"]));
+        assert_eq!(received["session"], context);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_console_command_responses() {
+        let script = [
+            "read -r context",
+            "echo \"(+ u1 u2)\"",
+            "read -r response",
+            "printf \"%s\\n\" \"$response\"",
+            "read -r response",
+        ];
+        let console_commands = project_commands("check", &format!("sh -c '{}'", script.join("; ")));
+        let mut session = Session::new(SessionSettings::default());
+        session.update_epoch(StacksEpochId::Epoch25);
+        let context = get_session_context(&mut session);
+        let mut responses = vec![];
+        console_commands
+            .run("check", &[], &context, |line| {
+                let (_, output, result) = session.process_console_input(line);
+                let response = get_evaluation_response(&output, &result);
+                responses.push((line.to_string(), response.clone()));
+                response
+            })
+            .unwrap();
+
+        // the command reads the result of its first line, and prints it back
+        assert_eq!(responses[0].1, json!({ "result": "u3" }));
+        assert_eq!(responses[1].0, responses[0].1.to_string());
+        assert!(responses[1].1.get("error").is_some());
+
+        let wasm = project_commands("wasm", "./plugins/mint.wasm 50");
+        assert!(wasm
+            .run("wasm", &[], &context, |_| json!({}))
+            .unwrap_err()
+            .contains("WebAssembly modules are not supported"));
+
+        let failing = project_commands("fail", "sh -c 'exit 3'");
+        assert!(failing
+            .run("fail", &[], &context, |_| json!({}))
+            .unwrap_err()
+            .contains("failed"));
+    }
+}
@@ -1,2 +1,3 @@
+pub mod console_commands;
 pub mod terminal;
 pub use terminal::Terminal;
@@ -1,6 +1,8 @@
+use super::console_commands::{get_evaluation_response, get_session_context, ConsoleCommands};
 use crate::repl::{settings::SessionSettings, Session};
 
-use clarity::vm::EvaluationResult;
+use clarity::vm::diagnostic::Diagnostic;
+use clarity::vm::{EvaluationResult, ExecutionResult};
 use rustyline::error::ReadlineError;
 use rustyline::DefaultEditor;
 
@@ -76,9 +78,15 @@ fn complete_input(str: &str) -> Result<Input, (char, char)> {
     }
 }
 
+/// Renders the input of `::export_tx <input>` as an unsigned transaction
+pub type TransactionExporter = Box<dyn FnMut(&mut Session, &str) -> Result<String, String>>;
+
 pub struct Terminal {
     pub session: Session,
     pub session_wasm: Option<Session>,
+    pub console_commands: ConsoleCommands,
+    /// Provided by clarinet, which knows the keys of the project accounts
+    pub transaction_exporter: Option<TransactionExporter>,
 }
 
 impl Terminal {
@@ -91,6 +99,8 @@ impl Terminal {
         Terminal {
             session,
             session_wasm,
+            console_commands: ConsoleCommands::default(),
+            transaction_exporter: None,
         }
     }
 
@@ -98,6 +108,8 @@ impl Terminal {
         Terminal {
             session,
             session_wasm,
+            console_commands: ConsoleCommands::default(),
+            transaction_exporter: None,
         }
     }
 
@@ -112,6 +124,18 @@ impl Terminal {
         if let Some(accounts) = self.session.get_accounts() {
             println!("{accounts}");
         }
+        let mut names: Vec<String> = self
+            .console_commands
+            .commands
+            .keys()
+            .map(|name| format!("::{}", name))
+            .collect();
+        if self.transaction_exporter.is_some() {
+            names.push("::export_tx <contract-call | contract>".to_string());
+        }
+        if !names.is_empty() {
+            println!("{} {}", black!("Project commands:"), names.join(", "));
+        }
 
         let mut editor = DefaultEditor::new().expect("Failed to initialize cli");
         let mut ctrl_c_acc = 0;
@@ -130,62 +154,17 @@ impl Terminal {
                     let input = input_buffer.join(" ");
                     match complete_input(&input) {
                         Ok(Input::Complete()) => {
-                            let (reload, output, result) =
-                                self.session.process_console_input(&input);
-
-                            if let Some(session_wasm) = &mut self.session_wasm {
-                                let (_, _, result_wasm) =
-                                    session_wasm.process_console_input(&input);
-
-                                if let (Some(result), Some(result_wasm)) = (result, result_wasm) {
-                                    match (result, result_wasm) {
-                                        (Ok(result), Ok(result_wasm)) => {
-                                            let value = match result.result {
-                                                EvaluationResult::Contract(contract_result) => {
-                                                    contract_result.result
-                                                }
-                                                EvaluationResult::Snippet(snippet_result) => {
-                                                    Some(snippet_result.result)
-                                                }
-                                            };
-                                            let value_wasm = match result_wasm.result {
-                                                EvaluationResult::Contract(contract_result) => {
-                                                    contract_result.result
-                                                }
-                                                EvaluationResult::Snippet(snippet_result) => {
-                                                    Some(snippet_result.result)
-                                                }
-                                            };
-                                            if value != value_wasm {
-                                                dbg!(value);
-                                                dbg!(value_wasm);
-                                                print_clarity_wasm_warning();
-                                            };
-                                        }
-                                        (Ok(result), Err(error_wasm)) => {
-                                            dbg!(result);
-                                            dbg!(error_wasm);
-                                            print_clarity_wasm_warning();
-                                        }
-                                        (Err(error), Ok(result_wasm)) => {
-                                            dbg!(error);
-                                            dbg!(result_wasm);
-                                            print_clarity_wasm_warning();
-                                        }
-                                        (Err(error), Err(error_wasm)) => {
-                                            if error != error_wasm {
-                                                dbg!(error);
-                                                dbg!(error_wasm);
-                                                print_clarity_wasm_warning();
-                                            }
-                                        }
-                                    };
+                            let reload = match self.console_commands.parse(&input) {
+                                Some(Ok((name, args))) => self.run_console_command(&name, &args),
+                                Some(Err(e)) => {
+                                    println!("{}", red!(e));
+                                    false
                                 }
-                            }
-
-                            for line in output {
-                                println!("{}", line);
-                            }
+                                None => match input.trim().strip_prefix("::export_tx ") {
+                                    Some(expression) => self.export_transaction(expression),
+                                    None => self.process_input(&input),
+                                },
+                            };
                             prompt = String::from(">> ");
                             self.session.executed.push(input.to_string());
                             let _ = editor.add_history_entry(input);
@@ -227,13 +206,124 @@ impl Terminal {
             .unwrap();
         reload
     }
+
+    /// Evaluate `input` in the session, and in the clarity-wasm session if enabled.
+    /// Returns true if the session must be reloaded.
+    fn process_input(&mut self, input: &str) -> bool {
+        let (reload, output, _) = self.evaluate_input(input);
+        for line in output {
+            println!("{}", line);
+        }
+        reload
+    }
+
+    fn evaluate_input(
+        &mut self,
+        input: &str,
+    ) -> (
+        bool,
+        Vec<String>,
+        Option<Result<ExecutionResult, Vec<Diagnostic>>>,
+    ) {
+        let (reload, output, result) = self.session.process_console_input(input);
+
+        if let Some(session_wasm) = &mut self.session_wasm {
+            let (_, _, result_wasm) = session_wasm.process_console_input(input);
+
+            if let (Some(result), Some(result_wasm)) = (&result, result_wasm) {
+                match (result, result_wasm) {
+                    (Ok(result), Ok(result_wasm)) => {
+                        let value = match &result.result {
+                            EvaluationResult::Contract(contract_result) => {
+                                contract_result.result.clone()
+                            }
+                            EvaluationResult::Snippet(snippet_result) => {
+                                Some(snippet_result.result.clone())
+                            }
+                        };
+                        let value_wasm = match result_wasm.result {
+                            EvaluationResult::Contract(contract_result) => contract_result.result,
+                            EvaluationResult::Snippet(snippet_result) => {
+                                Some(snippet_result.result)
+                            }
+                        };
+                        if value != value_wasm {
+                            dbg!(value);
+                            dbg!(value_wasm);
+                            print_clarity_wasm_warning();
+                        };
+                    }
+                    (Ok(result), Err(error_wasm)) => {
+                        dbg!(result);
+                        dbg!(error_wasm);
+                        print_clarity_wasm_warning();
+                    }
+                    (Err(error), Ok(result_wasm)) => {
+                        dbg!(error);
+                        dbg!(result_wasm);
+                        print_clarity_wasm_warning();
+                    }
+                    (Err(error), Err(error_wasm)) => {
+                        if *error != error_wasm {
+                            dbg!(error);
+                            dbg!(error_wasm);
+                            print_clarity_wasm_warning();
+                        }
+                    }
+                };
+            }
+        }
+
+        (reload, output, result)
+    }
+
+    /// Print the unsigned transaction of `expression`
+    fn export_transaction(&mut self, expression: &str) -> bool {
+        let Some(exporter) = self.transaction_exporter.as_mut() else {
+            println!(
+                "{}",
+                red!("::export_tx is only available in clarinet projects")
+            );
+            return false;
+        };
+        match exporter(&mut self.session, expression) {
+            Ok(export) => println!("{}", export),
+            Err(e) => println!("{}", red!(e)),
+        }
+        false
+    }
+
+    /// Run a project console command, evaluating each line it prints and sending the
+    /// result back to it
+    fn run_console_command(&mut self, name: &str, args: &[String]) -> bool {
+        let console_commands = self.console_commands.clone();
+        let context = get_session_context(&mut self.session);
+        let mut reload = false;
+        let result = console_commands.run(name, args, &context, |line| {
+            println!("{}", black!(format!(">> {}", line)));
+            let (line_reload, output, result) = self.evaluate_input(line);
+            for output_line in output.iter() {
+                println!("{}", output_line);
+            }
+            reload |= line_reload;
+            get_evaluation_response(&output, &result)
+        });
+        if let Err(e) = result {
+            println!("{}", red!(e));
+        }
+        reload
+    }
 }
 
-pub fn print_clarity_wasm_warning() {
-    println!("{} https://github.com/stacks-network/clarity-wasm/issues/new/choose {}",
+pub fn clarity_wasm_warning() -> String {
+    format!("{} https://github.com/stacks-network/clarity-wasm/issues/new/choose {}",
         yellow!("It appears that Clarity-Wasm is returning an unexpected result.\nPlease help improve the Stacks network by reporting this issue at"),
         yellow!("and include the errors above along with the source code that triggered this.\n")
-    );
+    )
+}
+
+pub fn print_clarity_wasm_warning() {
+    println!("{}", clarity_wasm_warning());
 }
 
 #[cfg(test)]
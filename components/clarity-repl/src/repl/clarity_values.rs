@@ -1,10 +1,12 @@
+use std::convert::TryFrom;
 use std::fmt::Write;
 
 use clarity::vm::{
-    types::{CharType, SequenceData},
-    Value,
+    types::{CharType, PrincipalData, SequenceData, TupleData},
+    ClarityName, Value,
 };
 use clarity::{codec::StacksMessageCodec, util::hash};
+use serde_json::{json, Map, Value as JsonValue};
 
 pub fn to_raw_value(value: &Value) -> String {
     let hex = value
@@ -71,15 +73,154 @@ pub fn value_to_string(value: &Value) -> String {
     }
 }
 
+/// Encode a Clarity value as JSON, with an explicit type annotation on every node:
+///
+/// ```json
+/// { "type": "tuple", "value": { "id": { "type": "uint", "value": "1" } } }
+/// ```
+///
+/// Integers are encoded as strings, since they do not fit in a JSON number,
+/// buffers as 0x-prefixed hex strings. Use `json_to_value` to decode.
+pub fn value_to_json(value: &Value) -> JsonValue {
+    match value {
+        Value::Int(int) => json!({ "type": "int", "value": int.to_string() }),
+        Value::UInt(uint) => json!({ "type": "uint", "value": uint.to_string() }),
+        Value::Bool(boolean) => json!({ "type": "bool", "value": boolean }),
+        Value::Principal(principal_data) => {
+            json!({ "type": "principal", "value": principal_data.to_string() })
+        }
+        Value::CallableContract(callable) => {
+            json!({ "type": "principal", "value": callable.contract_identifier.to_string() })
+        }
+        Value::Sequence(SequenceData::Buffer(buff_data)) => {
+            json!({ "type": "buff", "value": format!("0x{}", hash::to_hex(&buff_data.data)) })
+        }
+        Value::Sequence(SequenceData::String(CharType::ASCII(ascii_data))) => json!({
+            "type": "string-ascii",
+            "value": String::from_utf8_lossy(&ascii_data.data),
+        }),
+        Value::Sequence(SequenceData::String(CharType::UTF8(utf8_data))) => json!({
+            "type": "string-utf8",
+            "value": String::from_utf8_lossy(&utf8_data.data.concat()),
+        }),
+        Value::Sequence(SequenceData::List(list_data)) => json!({
+            "type": "list",
+            "value": list_data.data.iter().map(value_to_json).collect::<Vec<_>>(),
+        }),
+        Value::Tuple(tup_data) => {
+            let mut data = Map::new();
+            for (name, value) in &tup_data.data_map {
+                data.insert(name.to_string(), value_to_json(value));
+            }
+            json!({ "type": "tuple", "value": data })
+        }
+        Value::Optional(opt_data) => match &opt_data.data {
+            Some(value) => json!({ "type": "some", "value": value_to_json(value) }),
+            None => json!({ "type": "none" }),
+        },
+        Value::Response(res_data) => {
+            let committed = if res_data.committed { "ok" } else { "err" };
+            json!({ "type": committed, "value": value_to_json(&res_data.data) })
+        }
+    }
+}
+
+/// Decode a Clarity value from the JSON representation produced by `value_to_json`.
+pub fn json_to_value(json: &JsonValue) -> Result<Value, String> {
+    let value_type = json
+        .get("type")
+        .and_then(|t| t.as_str())
+        .ok_or_else(|| format!("missing value type in {}", json))?;
+    if value_type == "none" {
+        return Ok(Value::none());
+    }
+    let value = json
+        .get("value")
+        .ok_or_else(|| format!("missing {} value in {}", value_type, json))?;
+    let as_str = || {
+        value
+            .as_str()
+            .ok_or_else(|| format!("expected string for {} value, got {}", value_type, value))
+    };
+
+    match value_type {
+        "int" => match value.as_i64() {
+            Some(int) => Ok(Value::Int(int.into())),
+            None => as_str()?
+                .parse::<i128>()
+                .map(Value::Int)
+                .map_err(|e| format!("invalid int {}: {}", value, e)),
+        },
+        "uint" => match value.as_u64() {
+            Some(uint) => Ok(Value::UInt(uint.into())),
+            None => as_str()?
+                .parse::<u128>()
+                .map(Value::UInt)
+                .map_err(|e| format!("invalid uint {}: {}", value, e)),
+        },
+        "bool" => value
+            .as_bool()
+            .map(Value::Bool)
+            .ok_or_else(|| format!("expected boolean for bool value, got {}", value)),
+        "principal" => {
+            let principal = as_str()?;
+            PrincipalData::parse(principal.trim_start_matches('\''))
+                .map(Value::Principal)
+                .map_err(|e| format!("invalid principal {}: {}", principal, e))
+        }
+        "buff" => {
+            let hex = as_str()?;
+            let bytes = hash::hex_bytes(hex.trim_start_matches("0x"))
+                .map_err(|e| format!("invalid buffer {}: {:?}", hex, e))?;
+            Value::buff_from(bytes).map_err(|e| e.to_string())
+        }
+        "string-ascii" => {
+            Value::string_ascii_from_bytes(as_str()?.as_bytes().to_vec()).map_err(|e| e.to_string())
+        }
+        "string-utf8" => {
+            Value::string_utf8_from_bytes(as_str()?.as_bytes().to_vec()).map_err(|e| e.to_string())
+        }
+        "list" => {
+            let items = value
+                .as_array()
+                .ok_or_else(|| format!("expected array for list value, got {}", value))?;
+            let items = items
+                .iter()
+                .map(json_to_value)
+                .collect::<Result<Vec<_>, _>>()?;
+            Value::cons_list_unsanitized(items).map_err(|e| e.to_string())
+        }
+        "tuple" => {
+            let entries = value
+                .as_object()
+                .ok_or_else(|| format!("expected object for tuple value, got {}", value))?;
+            let mut data = vec![];
+            for (name, value) in entries.iter() {
+                let name = ClarityName::try_from(name.to_string())
+                    .map_err(|e| format!("invalid tuple key {}: {:?}", name, e))?;
+                data.push((name, json_to_value(value)?));
+            }
+            TupleData::from_data(data)
+                .map(Value::Tuple)
+                .map_err(|e| e.to_string())
+        }
+        "some" => Value::some(json_to_value(value)?).map_err(|e| e.to_string()),
+        "ok" => Value::okay(json_to_value(value)?).map_err(|e| e.to_string()),
+        "err" => Value::error(json_to_value(value)?).map_err(|e| e.to_string()),
+        _ => Err(format!("unknown value type '{}'", value_type)),
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::value_to_string;
+    use super::{json_to_value, value_to_json, value_to_string};
     use clarity::vm::types::{
         ASCIIData, CharType, ListData, ListTypeData, OptionalData, PrincipalData,
         QualifiedContractIdentifier, ResponseData, SequenceData, SequencedValue,
         StandardPrincipalData, TupleData, TypeSignature, UTF8Data, NONE,
     };
     use clarity::vm::{ClarityName, Value};
+    use serde_json::json;
     use std::convert::TryFrom;
 
     #[test]
@@ -158,4 +299,63 @@ mod tests {
         })));
         assert_eq!(s, "(list -321)");
     }
+
+    #[test]
+    fn test_value_to_json() {
+        let value = Value::Tuple(
+            TupleData::from_data(vec![
+                (
+                    ClarityName::try_from("amount".to_string()).unwrap(),
+                    Value::UInt(u128::MAX),
+                ),
+                (
+                    ClarityName::try_from("memo".to_string()).unwrap(),
+                    Value::some(Value::buff_from(vec![1, 2, 3]).unwrap()).unwrap(),
+                ),
+            ])
+            .unwrap(),
+        );
+        assert_eq!(
+            value_to_json(&value),
+            json!({
+                "type": "tuple",
+                "value": {
+                    "amount": { "type": "uint", "value": u128::MAX.to_string() },
+                    "memo": { "type": "some", "value": { "type": "buff", "value": "0x010203" } },
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_json_to_value_roundtrip() {
+        let values = vec![
+            Value::Int(-42),
+            Value::UInt(12345678909876),
+            Value::Bool(false),
+            Value::Principal(PrincipalData::Standard(StandardPrincipalData::transient())),
+            Value::Principal(PrincipalData::Contract(
+                QualifiedContractIdentifier::transient(),
+            )),
+            Value::buff_from(vec![]).unwrap(),
+            Value::string_ascii_from_bytes("Hello, \"world\"\n".as_bytes().to_vec()).unwrap(),
+            UTF8Data::to_value(&"Hello, 'world' \u{1F600}".as_bytes().to_vec()).unwrap(),
+            NONE.clone(),
+            Value::Response(ResponseData {
+                committed: false,
+                data: Box::new(Value::Int(-321)),
+            }),
+            Value::cons_list_unsanitized(vec![Value::UInt(1), Value::UInt(2)]).unwrap(),
+        ];
+        for value in values {
+            assert_eq!(json_to_value(&value_to_json(&value)), Ok(value));
+        }
+
+        assert_eq!(
+            json_to_value(&json!({ "type": "uint", "value": 1 })),
+            Ok(Value::UInt(1))
+        );
+        assert!(json_to_value(&json!({ "type": "int", "value": "u1" })).is_err());
+        assert!(json_to_value(&json!({ "type": "float", "value": "1.0" })).is_err());
+    }
 }
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use clarity::types::chainstate::BlockHeaderHash;
 use clarity::types::chainstate::BurnchainHeaderHash;
@@ -10,6 +10,7 @@ use clarity::types::chainstate::TrieHash;
 use clarity::types::chainstate::VRFSeed;
 use clarity::types::StacksEpochId;
 use clarity::util::hash::Sha512Trunc256Sum;
+use clarity::util::HexError;
 use clarity::vm::analysis::AnalysisDatabase;
 use clarity::vm::database::BurnStateDB;
 use clarity::vm::database::{ClarityBackingStore, HeadersDB};
@@ -68,7 +69,7 @@ pub struct StacksBlockInfo {
     stacks_block_time: u64,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct StacksConstants {
     pub burn_start_height: u32,
     pub pox_prepare_length: u32,
@@ -203,6 +204,18 @@ impl ClarityDatastore {
         }
     }
 
+    /// Keys starting with `prefix` and their value at the current chain tip, sorted by key
+    pub fn get_entries_with_prefix(&self, prefix: &str) -> Vec<(String, String)> {
+        let mut entries: Vec<(String, String)> = self
+            .store
+            .iter()
+            .filter(|(key, _)| key.starts_with(prefix))
+            .filter_map(|(key, data)| Some((key.clone(), self.get_latest_data(data)?)))
+            .collect();
+        entries.sort();
+        entries
+    }
+
     pub fn make_contract_hash_key(contract: &QualifiedContractIdentifier) -> String {
         format!("clarity-contract::{}", contract)
     }
@@ -524,6 +537,50 @@ impl Datastore {
         self.stacks_chain_height
     }
 
+    /// Move the chain tip straight to a new stacks block at `stacks_block_height`, anchored
+    /// in a burn block at `burn_block_height`, without building the blocks in between.
+    pub fn jump_to_chain_tip(
+        &mut self,
+        clarity_datastore: &mut ClarityDatastore,
+        burn_block_height: u32,
+        stacks_block_height: u32,
+    ) -> Result<(), String> {
+        if stacks_block_height <= self.stacks_chain_height {
+            return Err(format!(
+                "stacks block height {} is not above the current height {}",
+                stacks_block_height, self.stacks_chain_height
+            ));
+        }
+        if burn_block_height < self.burn_chain_height {
+            return Err(format!(
+                "burn block height {} is below the current height {}",
+                burn_block_height, self.burn_chain_height
+            ));
+        }
+        if burn_block_height > self.burn_chain_height {
+            let last_burn_block = self
+                .burn_blocks
+                .get(&height_to_burn_block_header_hash(self.burn_chain_height))
+                .expect("burn block missing in burn block table");
+            let burn_block_info = BurnBlockInfo {
+                burn_block_time: last_burn_block.burn_block_time
+                    + SECONDS_BETWEEN_BURN_BLOCKS
+                        * u64::from(burn_block_height - self.burn_chain_height),
+                burn_block_height,
+            };
+            self.burn_blocks.insert(
+                height_to_burn_block_header_hash(burn_block_height),
+                burn_block_info,
+            );
+            self.burn_chain_height = burn_block_height;
+        }
+        self.stacks_chain_height = stacks_block_height - 1;
+        self.advance_stacks_chain_tip(clarity_datastore, 1);
+        self.tenure_blocks_height
+            .insert(self.burn_chain_height, self.stacks_chain_height);
+        Ok(())
+    }
+
     pub fn set_current_epoch(
         &mut self,
         clarity_datastore: &mut ClarityDatastore,
@@ -775,6 +832,238 @@ impl BurnStateDB for Datastore {
     }
 }
 
+fn parse_hex<T>(
+    hex: &str,
+    from_hex: fn(&str) -> std::result::Result<T, HexError>,
+) -> std::result::Result<T, String> {
+    from_hex(hex).map_err(|e| format!("unable to parse {}: {}", hex, e))
+}
+
+/// Serializable state of a `ClarityDatastore`, block ids are hex encoded
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ClarityDatastoreState {
+    open_chain_tip: String,
+    current_chain_tip: String,
+    /// Values of each key, with the id of the block they were written in
+    store: BTreeMap<String, Vec<(String, String)>>,
+    /// Contract, key and value of each metadata entry
+    metadata: Vec<(String, String, String)>,
+    block_id_lookup: BTreeMap<String, String>,
+    height_at_chain_tip: BTreeMap<String, u32>,
+}
+
+impl ClarityDatastore {
+    pub fn save_state(&self) -> ClarityDatastoreState {
+        let mut metadata: Vec<(String, String, String)> = self
+            .metadata
+            .iter()
+            .map(|((contract, key), value)| (contract.clone(), key.clone(), value.clone()))
+            .collect();
+        metadata.sort();
+        ClarityDatastoreState {
+            open_chain_tip: self.open_chain_tip.to_hex(),
+            current_chain_tip: self.current_chain_tip.to_hex(),
+            store: self
+                .store
+                .iter()
+                .map(|(key, entries)| {
+                    let entries = entries
+                        .iter()
+                        .map(|StoreEntry(block_id, value)| (block_id.to_hex(), value.clone()))
+                        .collect();
+                    (key.clone(), entries)
+                })
+                .collect(),
+            metadata,
+            block_id_lookup: self
+                .block_id_lookup
+                .iter()
+                .map(|(id, lookup)| (id.to_hex(), lookup.to_hex()))
+                .collect(),
+            height_at_chain_tip: self
+                .height_at_chain_tip
+                .iter()
+                .map(|(id, height)| (id.to_hex(), *height))
+                .collect(),
+        }
+    }
+
+    pub fn from_state(state: &ClarityDatastoreState) -> std::result::Result<Self, String> {
+        let mut store = HashMap::new();
+        for (key, entries) in state.store.iter() {
+            let mut store_entries = vec![];
+            for (block_id, value) in entries.iter() {
+                store_entries.push(StoreEntry(
+                    parse_hex(block_id, StacksBlockId::from_hex)?,
+                    value.clone(),
+                ));
+            }
+            store.insert(key.clone(), store_entries);
+        }
+        let mut block_id_lookup = HashMap::new();
+        for (id, lookup) in state.block_id_lookup.iter() {
+            block_id_lookup.insert(
+                parse_hex(id, StacksBlockId::from_hex)?,
+                parse_hex(lookup, StacksBlockId::from_hex)?,
+            );
+        }
+        let mut height_at_chain_tip = HashMap::new();
+        for (id, height) in state.height_at_chain_tip.iter() {
+            height_at_chain_tip.insert(parse_hex(id, StacksBlockId::from_hex)?, *height);
+        }
+        Ok(ClarityDatastore {
+            open_chain_tip: parse_hex(&state.open_chain_tip, StacksBlockId::from_hex)?,
+            current_chain_tip: parse_hex(&state.current_chain_tip, StacksBlockId::from_hex)?,
+            store,
+            metadata: state
+                .metadata
+                .iter()
+                .map(|(contract, key, value)| ((contract.clone(), key.clone()), value.clone()))
+                .collect(),
+            block_id_lookup,
+            height_at_chain_tip,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+struct BurnBlockState {
+    hash: String,
+    time: u64,
+    height: u32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+struct StacksBlockState {
+    id: String,
+    header_hash: String,
+    burn_block_header_hash: String,
+    consensus_hash: String,
+    vrf_seed: String,
+    time: u64,
+}
+
+/// Serializable state of a `Datastore`, hashes are hex encoded
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct DatastoreState {
+    genesis_id: String,
+    burn_chain_height: u32,
+    burn_blocks: Vec<BurnBlockState>,
+    stacks_chain_height: u32,
+    stacks_blocks: Vec<StacksBlockState>,
+    sortition_lookup: BTreeMap<String, String>,
+    tenure_blocks_height: BTreeMap<u32, u32>,
+    consensus_hash_lookup: BTreeMap<String, String>,
+    current_epoch: StacksEpochId,
+    current_epoch_start_height: u32,
+    constants: StacksConstants,
+}
+
+impl Datastore {
+    pub fn save_state(&self) -> DatastoreState {
+        let mut burn_blocks: Vec<BurnBlockState> = self
+            .burn_blocks
+            .iter()
+            .map(|(hash, block)| BurnBlockState {
+                hash: hash.to_hex(),
+                time: block.burn_block_time,
+                height: block.burn_block_height,
+            })
+            .collect();
+        burn_blocks.sort_by_key(|block| block.height);
+        let mut stacks_blocks: Vec<StacksBlockState> = self
+            .stacks_blocks
+            .iter()
+            .map(|(id, block)| StacksBlockState {
+                id: id.to_hex(),
+                header_hash: block.block_header_hash.to_hex(),
+                burn_block_header_hash: block.burn_block_header_hash.to_hex(),
+                consensus_hash: block.consensus_hash.to_hex(),
+                vrf_seed: block.vrf_seed.to_hex(),
+                time: block.stacks_block_time,
+            })
+            .collect();
+        stacks_blocks.sort_by_key(|block| block.time);
+        DatastoreState {
+            genesis_id: self.genesis_id.to_hex(),
+            burn_chain_height: self.burn_chain_height,
+            burn_blocks,
+            stacks_chain_height: self.stacks_chain_height,
+            stacks_blocks,
+            sortition_lookup: self
+                .sortition_lookup
+                .iter()
+                .map(|(sortition_id, id)| (sortition_id.to_hex(), id.to_hex()))
+                .collect(),
+            tenure_blocks_height: self.tenure_blocks_height.clone().into_iter().collect(),
+            consensus_hash_lookup: self
+                .consensus_hash_lookup
+                .iter()
+                .map(|(hash, sortition_id)| (hash.to_hex(), sortition_id.to_hex()))
+                .collect(),
+            current_epoch: self.current_epoch,
+            current_epoch_start_height: self.current_epoch_start_height,
+            constants: self.constants.clone(),
+        }
+    }
+
+    pub fn from_state(state: &DatastoreState) -> std::result::Result<Self, String> {
+        let mut burn_blocks = HashMap::new();
+        for block in state.burn_blocks.iter() {
+            burn_blocks.insert(
+                parse_hex(&block.hash, BurnchainHeaderHash::from_hex)?,
+                BurnBlockInfo {
+                    burn_block_time: block.time,
+                    burn_block_height: block.height,
+                },
+            );
+        }
+        let mut stacks_blocks = HashMap::new();
+        for block in state.stacks_blocks.iter() {
+            stacks_blocks.insert(
+                parse_hex(&block.id, StacksBlockId::from_hex)?,
+                StacksBlockInfo {
+                    block_header_hash: parse_hex(&block.header_hash, BlockHeaderHash::from_hex)?,
+                    burn_block_header_hash: parse_hex(
+                        &block.burn_block_header_hash,
+                        BurnchainHeaderHash::from_hex,
+                    )?,
+                    consensus_hash: parse_hex(&block.consensus_hash, ConsensusHash::from_hex)?,
+                    vrf_seed: parse_hex(&block.vrf_seed, VRFSeed::from_hex)?,
+                    stacks_block_time: block.time,
+                },
+            );
+        }
+        let mut sortition_lookup = HashMap::new();
+        for (sortition_id, id) in state.sortition_lookup.iter() {
+            sortition_lookup.insert(
+                parse_hex(sortition_id, SortitionId::from_hex)?,
+                parse_hex(id, StacksBlockId::from_hex)?,
+            );
+        }
+        let mut consensus_hash_lookup = HashMap::new();
+        for (hash, sortition_id) in state.consensus_hash_lookup.iter() {
+            consensus_hash_lookup.insert(
+                parse_hex(hash, ConsensusHash::from_hex)?,
+                parse_hex(sortition_id, SortitionId::from_hex)?,
+            );
+        }
+        Ok(Datastore {
+            genesis_id: parse_hex(&state.genesis_id, StacksBlockId::from_hex)?,
+            burn_chain_height: state.burn_chain_height,
+            burn_blocks,
+            stacks_chain_height: state.stacks_chain_height,
+            stacks_blocks,
+            sortition_lookup,
+            tenure_blocks_height: state.tenure_blocks_height.clone().into_iter().collect(),
+            consensus_hash_lookup,
+            current_epoch: state.current_epoch,
+            current_epoch_start_height: state.current_epoch_start_height,
+            constants: state.constants.clone(),
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use clarity::types::StacksEpoch;
@@ -789,6 +1078,26 @@ mod tests {
         assert_eq!(datastore.stacks_chain_height, 5);
     }
 
+    #[test]
+    fn test_jump_to_chain_tip() {
+        let mut datastore = Datastore::default();
+        let mut clarity_datastore = ClarityDatastore::new();
+        datastore.advance_burn_chain_tip(&mut clarity_datastore, 2);
+        datastore
+            .jump_to_chain_tip(&mut clarity_datastore, 840_000, 150_000)
+            .unwrap();
+        assert_eq!(datastore.get_current_burn_block_height(), 840_000);
+        assert_eq!(datastore.get_current_stacks_block_height(), 150_000);
+        assert_eq!(clarity_datastore.get_current_block_height(), 150_000);
+        assert!(datastore.stacks_blocks.contains_key(&height_to_id(150_000)));
+        assert!(datastore
+            .jump_to_chain_tip(&mut clarity_datastore, 840_001, 150_000)
+            .is_err());
+        assert!(datastore
+            .jump_to_chain_tip(&mut clarity_datastore, 839_999, 150_001)
+            .is_err());
+    }
+
     #[test]
     fn test_set_current_epoch() {
         let mut datastore = Datastore::default();
@@ -3,8 +3,8 @@ use std::collections::{btree_map::Entry, BTreeMap, BTreeSet};
 use crate::analysis::annotation::{Annotation, AnnotationKind};
 use crate::analysis::ast_dependency_detector::{ASTDependencyDetector, Dependency};
 use crate::analysis::{self};
-use crate::repl::datastore::ClarityDatastore;
 use crate::repl::datastore::Datastore;
+use crate::repl::datastore::{ClarityDatastore, ClarityDatastoreState, DatastoreState};
 use crate::repl::Settings;
 use clarity::consts::CHAIN_ID_TESTNET;
 use clarity::types::StacksEpochId;
@@ -28,6 +28,7 @@ use clarity::vm::{events::*, ClarityVersion};
 use clarity::vm::{ContractEvaluationResult, EvalHook};
 use clarity::vm::{CostSynthesis, ExecutionResult, ParsedContract};
 
+use super::post_conditions::PostConditions;
 use super::{ClarityContract, DEFAULT_EPOCH};
 
 pub const BLOCK_LIMIT_MAINNET: ExecutionCost = ExecutionCost {
@@ -46,11 +47,22 @@ pub struct ClarityInterpreter {
     tx_sender: StandardPrincipalData,
     accounts: BTreeSet<String>,
     tokens: BTreeMap<String, BTreeMap<String, u128>>,
+    post_conditions: Option<PostConditions>,
 }
 
 #[derive(Debug)]
 pub struct Txid(pub [u8; 32]);
 
+/// Serializable state of the chain simulated by an interpreter
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct InterpreterState {
+    pub clarity_datastore: ClarityDatastoreState,
+    pub datastore: DatastoreState,
+    pub tx_sender: String,
+    pub accounts: BTreeSet<String>,
+    pub tokens: BTreeMap<String, BTreeMap<String, u128>>,
+}
+
 impl ClarityInterpreter {
     pub fn new(tx_sender: StandardPrincipalData, repl_settings: Settings) -> Self {
         Self {
@@ -60,9 +72,20 @@ impl ClarityInterpreter {
             accounts: BTreeSet::new(),
             tokens: BTreeMap::new(),
             datastore: Datastore::default(),
+            post_conditions: None,
         }
     }
 
+    /// Post-conditions enforced on the next contract call, which is
+    /// rolled back if they are not met.
+    pub fn set_post_conditions(&mut self, post_conditions: PostConditions) {
+        self.post_conditions = Some(post_conditions);
+    }
+
+    pub fn clear_post_conditions(&mut self) {
+        self.post_conditions = None;
+    }
+
     pub fn run(
         &mut self,
         contract: &ClarityContract,
@@ -356,6 +379,154 @@ impl ClarityInterpreter {
         Some(format!("0x{value_hex}"))
     }
 
+    /// Entries of a map as `(key, value)` hex pairs, sorted by key. Deleted entries are skipped.
+    pub fn get_map_entries(
+        &mut self,
+        contract_id: &QualifiedContractIdentifier,
+        map_name: &str,
+    ) -> Vec<(String, String)> {
+        let prefix = format!(
+            "{}::",
+            ClarityDatabase::make_key_for_trip(contract_id, StoreType::DataMap, map_name)
+        );
+        let none_hex = Value::none().serialize_to_hex().unwrap();
+        self.clarity_datastore
+            .get_entries_with_prefix(&prefix)
+            .into_iter()
+            .filter(|(_, value_hex)| *value_hex != none_hex)
+            .map(|(key, value_hex)| {
+                let key_hex = &key[prefix.len()..];
+                (format!("0x{key_hex}"), format!("0x{value_hex}"))
+            })
+            .collect()
+    }
+
+    pub fn set_data_var(
+        &mut self,
+        contract_id: &QualifiedContractIdentifier,
+        var_name: &str,
+        value: Value,
+    ) -> Result<(), String> {
+        let mut conn = ClarityDatabase::new(
+            &mut self.clarity_datastore,
+            &self.datastore,
+            &self.datastore,
+        );
+        conn.begin();
+        match conn.set_variable_unknown_descriptor(contract_id, var_name, value) {
+            Ok(_) => conn.commit().map_err(|e| e.to_string()),
+            Err(e) => {
+                conn.roll_back().map_err(|e| e.to_string())?;
+                Err(format!("unable to set {}.{}: {}", contract_id, var_name, e))
+            }
+        }
+    }
+
+    pub fn set_map_entry(
+        &mut self,
+        contract_id: &QualifiedContractIdentifier,
+        map_name: &str,
+        key: Value,
+        value: Value,
+    ) -> Result<(), String> {
+        let epoch = self.datastore.get_current_epoch();
+        let mut conn = ClarityDatabase::new(
+            &mut self.clarity_datastore,
+            &self.datastore,
+            &self.datastore,
+        );
+        conn.begin();
+        match conn.set_entry_unknown_descriptor(contract_id, map_name, key, value, &epoch) {
+            Ok(_) => conn.commit().map_err(|e| e.to_string()),
+            Err(e) => {
+                conn.roll_back().map_err(|e| e.to_string())?;
+                Err(format!("unable to set {}.{}: {}", contract_id, map_name, e))
+            }
+        }
+    }
+
+    pub fn set_ft_balance(
+        &mut self,
+        contract_id: &QualifiedContractIdentifier,
+        token_name: &str,
+        owner: &PrincipalData,
+        balance: u128,
+    ) -> Result<(), String> {
+        let mut conn = ClarityDatabase::new(
+            &mut self.clarity_datastore,
+            &self.datastore,
+            &self.datastore,
+        );
+        conn.begin();
+        match conn.set_ft_balance(contract_id, token_name, owner, balance) {
+            Ok(_) => conn.commit().map_err(|e| e.to_string()),
+            Err(e) => {
+                conn.roll_back().map_err(|e| e.to_string())?;
+                Err(format!(
+                    "unable to set {}::{}: {}",
+                    contract_id, token_name, e
+                ))
+            }
+        }
+    }
+
+    pub fn set_nft_owner(
+        &mut self,
+        contract_id: &QualifiedContractIdentifier,
+        asset_name: &str,
+        asset: &Value,
+        owner: &PrincipalData,
+    ) -> Result<(), String> {
+        let epoch = self.datastore.get_current_epoch();
+        let mut conn = ClarityDatabase::new(
+            &mut self.clarity_datastore,
+            &self.datastore,
+            &self.datastore,
+        );
+        conn.begin();
+        let result = conn.load_nft(contract_id, asset_name).and_then(|metadata| {
+            conn.set_nft_owner(
+                contract_id,
+                asset_name,
+                asset,
+                owner,
+                &metadata.key_type,
+                &epoch,
+            )
+        });
+        match result {
+            Ok(_) => conn.commit().map_err(|e| e.to_string()),
+            Err(e) => {
+                conn.roll_back().map_err(|e| e.to_string())?;
+                Err(format!(
+                    "unable to set {}::{}: {}",
+                    contract_id, asset_name, e
+                ))
+            }
+        }
+    }
+
+    pub fn get_contract_constants(
+        &mut self,
+        contract_id: &QualifiedContractIdentifier,
+    ) -> Result<BTreeMap<String, Value>, String> {
+        let mut conn = ClarityDatabase::new(
+            &mut self.clarity_datastore,
+            &self.datastore,
+            &self.datastore,
+        );
+        conn.begin();
+        let contract = conn.get_contract(contract_id);
+        conn.roll_back().map_err(|e| e.to_string())?;
+        let contract = contract.map_err(|e| format!("unable to load {}: {}", contract_id, e))?;
+        Ok(contract
+            .contract_context
+            .variables
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect())
+    }
+
     fn execute(
         &mut self,
         contract: &ClarityContract,
@@ -834,6 +1005,7 @@ impl ClarityInterpreter {
         allow_private: bool,
         mut eval_hooks: Vec<&mut dyn EvalHook>,
     ) -> Result<ExecutionResult, String> {
+        let post_conditions = self.post_conditions.take();
         let mut conn = ClarityDatabase::new(
             &mut self.clarity_datastore,
             &self.datastore,
@@ -910,6 +1082,19 @@ impl ClarityInterpreter {
             .flat_map(|b| b.events.clone())
             .collect::<Vec<_>>();
 
+        if let Some(post_conditions) = post_conditions {
+            if let Err(e) = post_conditions.check(&emitted_events) {
+                global_context.roll_back().expect("failed to roll back");
+                let err = format!("Post-condition check failure: {}", e);
+                if let Some(mut eval_hooks) = global_context.eval_hooks.take() {
+                    for hook in eval_hooks.iter_mut() {
+                        hook.did_complete(Err(err.clone()));
+                    }
+                }
+                return Err(err);
+            }
+        }
+
         let eval_result = EvaluationResult::Snippet(SnippetEvaluationResult { result: value });
         global_context.commit().unwrap();
 
@@ -1115,6 +1300,20 @@ impl ClarityInterpreter {
         }
     }
 
+    pub fn jump_to_chain_tip(
+        &mut self,
+        burn_block_height: u32,
+        stacks_block_height: u32,
+    ) -> Result<(), String> {
+        self.datastore.jump_to_chain_tip(
+            &mut self.clarity_datastore,
+            burn_block_height,
+            stacks_block_height,
+        )?;
+        self.set_tenure_height();
+        Ok(())
+    }
+
     pub fn set_tenure_height(&mut self) {
         let burn_block_height = self.get_burn_block_height();
         let mut conn = ClarityDatabase::new(
@@ -1184,6 +1383,26 @@ impl ClarityInterpreter {
         self.accounts.clone().into_iter().collect::<Vec<_>>()
     }
 
+    pub fn save_state(&self) -> InterpreterState {
+        InterpreterState {
+            clarity_datastore: self.clarity_datastore.save_state(),
+            datastore: self.datastore.save_state(),
+            tx_sender: self.tx_sender.to_string(),
+            accounts: self.accounts.clone(),
+            tokens: self.tokens.clone(),
+        }
+    }
+
+    pub fn restore_state(&mut self, state: &InterpreterState) -> Result<(), String> {
+        self.clarity_datastore = ClarityDatastore::from_state(&state.clarity_datastore)?;
+        self.datastore = Datastore::from_state(&state.datastore)?;
+        self.tx_sender =
+            PrincipalData::parse_standard_principal(&state.tx_sender).map_err(|e| e.to_string())?;
+        self.accounts = state.accounts.clone();
+        self.tokens = state.tokens.clone();
+        Ok(())
+    }
+
     pub fn get_balance_for_account(&self, account: &str, token: &str) -> u128 {
         match self.tokens.get(token) {
             Some(balances) => match balances.get(account) {
@@ -1604,6 +1823,99 @@ mod tests {
         assert_eq!(no_name, None);
     }
 
+    #[test]
+    fn test_set_map_entry_and_token_balances() {
+        let mut interpreter =
+            ClarityInterpreter::new(StandardPrincipalData::transient(), Settings::default());
+        let contract = ClarityContractBuilder::default()
+            .name("tokens")
+            .code_source(
+                [
+                    "(define-map people uint (string-ascii 10))",
+                    "(define-fungible-token gold)",
+                    "(define-non-fungible-token badge uint)",
+                    "(define-read-only (get-gold (who principal)) (ft-get-balance gold who))",
+                    "(define-read-only (get-badge-owner (id uint)) (nft-get-owner? badge id))",
+                ]
+                .join("\n"),
+            )
+            .build();
+        let deploy = deploy_contract(&mut interpreter, &contract);
+        assert!(deploy.is_ok());
+
+        let contract_id = QualifiedContractIdentifier {
+            issuer: StandardPrincipalData::transient(),
+            name: "tokens".into(),
+        };
+        let owner = PrincipalData::parse("ST1SJ3DTE5DN7X54YDH5D64R3BCB6A2AG2ZQ8YPD5").unwrap();
+        interpreter
+            .set_map_entry(
+                &contract_id,
+                "people",
+                Value::UInt(0),
+                Value::string_ascii_from_bytes("satoshi".into()).unwrap(),
+            )
+            .unwrap();
+        interpreter
+            .set_ft_balance(&contract_id, "gold", &owner, 42)
+            .unwrap();
+        interpreter
+            .set_nft_owner(&contract_id, "badge", &Value::UInt(7), &owner)
+            .unwrap();
+        assert!(interpreter
+            .set_map_entry(&contract_id, "unknown", Value::UInt(0), Value::UInt(0))
+            .is_err());
+
+        let name = interpreter.get_map_entry(&contract_id, "people", &Value::UInt(0));
+        assert_eq!(name, Some("0x0a0d000000077361746f736869".to_owned()));
+        let gold = run_snippet(
+            &mut interpreter,
+            &format!("(contract-call? .tokens get-gold '{})", owner),
+            ClarityVersion::Clarity2,
+        );
+        assert_eq!(gold, Value::UInt(42));
+        let badge_owner = run_snippet(
+            &mut interpreter,
+            "(contract-call? .tokens get-badge-owner u7)",
+            ClarityVersion::Clarity2,
+        );
+        assert_eq!(badge_owner, Value::some(Value::Principal(owner)).unwrap());
+    }
+
+    #[test]
+    fn test_get_map_entries() {
+        let mut interpreter =
+            ClarityInterpreter::new(StandardPrincipalData::transient(), Settings::default());
+        let contract = ClarityContractBuilder::default()
+            .code_source(
+                [
+                    "(define-map people uint (string-ascii 10))",
+                    "(map-insert people u1 \"nakamoto\")",
+                    "(map-insert people u0 \"satoshi\")",
+                    "(map-insert people u2 \"hal\")",
+                    "(map-delete people u2)",
+                ]
+                .join("\n"),
+            )
+            .build();
+
+        let deploy = deploy_contract(&mut interpreter, &contract);
+        assert!(deploy.is_ok());
+
+        let contract_id = QualifiedContractIdentifier {
+            issuer: StandardPrincipalData::transient(),
+            name: "contract".into(),
+        };
+        let entries = interpreter.get_map_entries(&contract_id, "people");
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].0, "0x0100000000000000000000000000000000");
+        assert_eq!(entries[0].1, "0x0a0d000000077361746f736869");
+        assert_eq!(entries[1].0, "0x0100000000000000000000000000000001");
+        assert!(interpreter
+            .get_map_entries(&contract_id, "unknown")
+            .is_empty());
+    }
+
     #[test]
     fn test_execute_stx_events() {
         let mut interpreter =
@@ -2227,4 +2539,71 @@ mod tests {
             "Runtime error while interpreting S1G2081040G2081040G2081040G208105NK8PE5.contract: Unchecked(NoSuchPublicFunction(\"S1G2081040G2081040G2081040G208105NK8PE5.contract\", \"private-func\"))"
         );
     }
+
+    #[test]
+    fn post_conditions_roll_back_contract_calls() {
+        use crate::repl::post_conditions::{
+            FungibleConditionCode, PostCondition, PostConditionMode, PostConditions,
+        };
+        use clarity::vm::types::AssetIdentifier;
+
+        let mut interpreter =
+            ClarityInterpreter::new(StandardPrincipalData::transient(), Settings::default());
+
+        let snippet = [
+            "(define-fungible-token token)",
+            "(define-data-var count uint u0)",
+            "(define-public (mint-and-burn)",
+            "  (begin",
+            "    (var-set count (+ (var-get count) u1))",
+            "    (try! (ft-mint? token u10 tx-sender))",
+            "    (ft-burn? token u10 tx-sender)))",
+            "(define-read-only (get-count) (var-get count))",
+        ]
+        .join("\n");
+        let contract = ClarityContractBuilder::default()
+            .code_source(snippet)
+            .build();
+        let deploy = deploy_contract(&mut interpreter, &contract);
+        assert!(deploy.is_ok());
+
+        let contract_id =
+            contract.expect_resolved_contract_identifier(Some(&StandardPrincipalData::transient()));
+        let sender = PrincipalData::Standard(StandardPrincipalData::transient());
+        let asset = AssetIdentifier {
+            contract_identifier: contract_id.clone(),
+            asset_name: "token".into(),
+        };
+        let call = |interpreter: &mut ClarityInterpreter, method: &str| {
+            interpreter.call_contract_fn(
+                &contract_id,
+                method,
+                &[],
+                StacksEpochId::Epoch24,
+                ClarityVersion::Clarity2,
+                false,
+                false,
+                vec![],
+            )
+        };
+
+        interpreter.set_post_conditions(PostConditions::new(PostConditionMode::Deny, vec![]));
+        let result = call(&mut interpreter, "mint-and-burn");
+        assert!(result
+            .unwrap_err()
+            .starts_with("Post-condition check failure"));
+        assert_execution_result_value(call(&mut interpreter, "get-count"), Value::UInt(0));
+
+        interpreter.set_post_conditions(PostConditions::new(
+            PostConditionMode::Deny,
+            vec![PostCondition::Fungible(
+                sender,
+                asset,
+                FungibleConditionCode::SentEq,
+                10,
+            )],
+        ));
+        assert!(call(&mut interpreter, "mint-and-burn").is_ok());
+        assert_execution_result_value(call(&mut interpreter, "get-count"), Value::UInt(1));
+    }
 }
@@ -3,8 +3,10 @@ pub mod clarity_values;
 pub mod datastore;
 pub mod diagnostic;
 pub mod interpreter;
+pub mod post_conditions;
 pub mod session;
 pub mod settings;
+pub mod sip018;
 pub mod tracer;
 
 #[cfg(any(feature = "cli", feature = "dap"))]
@@ -17,7 +19,7 @@ use std::path::PathBuf;
 
 use ::clarity::vm::types::{PrincipalData, QualifiedContractIdentifier, StandardPrincipalData};
 pub use interpreter::ClarityInterpreter;
-pub use session::Session;
+pub use session::{Session, SessionState};
 pub use settings::SessionSettings;
 pub use settings::{Settings, SettingsFile};
 
@@ -0,0 +1,423 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+use clarity::vm::events::{FTEventType, NFTEventType, STXEventType, StacksTransactionEvent};
+use clarity::vm::types::{AssetIdentifier, PrincipalData, Value};
+
+/// Behaviour for the assets moved by a transaction without being covered by a post-condition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PostConditionMode {
+    #[default]
+    Allow,
+    Deny,
+}
+
+impl FromStr for PostConditionMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "allow" => Ok(PostConditionMode::Allow),
+            "deny" => Ok(PostConditionMode::Deny),
+            _ => Err(format!(
+                "unknown post-condition mode '{}' (expected 'allow' or 'deny')",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FungibleConditionCode {
+    SentEq,
+    SentGt,
+    SentGe,
+    SentLt,
+    SentLe,
+}
+
+impl FungibleConditionCode {
+    pub fn check(&self, amount_sent_condition: u128, amount_sent: u128) -> bool {
+        match self {
+            FungibleConditionCode::SentEq => amount_sent == amount_sent_condition,
+            FungibleConditionCode::SentGt => amount_sent > amount_sent_condition,
+            FungibleConditionCode::SentGe => amount_sent >= amount_sent_condition,
+            FungibleConditionCode::SentLt => amount_sent < amount_sent_condition,
+            FungibleConditionCode::SentLe => amount_sent <= amount_sent_condition,
+        }
+    }
+}
+
+impl FromStr for FungibleConditionCode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sent-eq" => Ok(FungibleConditionCode::SentEq),
+            "sent-gt" => Ok(FungibleConditionCode::SentGt),
+            "sent-ge" => Ok(FungibleConditionCode::SentGe),
+            "sent-lt" => Ok(FungibleConditionCode::SentLt),
+            "sent-le" => Ok(FungibleConditionCode::SentLe),
+            _ => Err(format!(
+                "unknown fungible condition code '{}' (expected sent-eq, sent-gt, sent-ge, sent-lt or sent-le)",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for FungibleConditionCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let code = match self {
+            FungibleConditionCode::SentEq => "sent-eq",
+            FungibleConditionCode::SentGt => "sent-gt",
+            FungibleConditionCode::SentGe => "sent-ge",
+            FungibleConditionCode::SentLt => "sent-lt",
+            FungibleConditionCode::SentLe => "sent-le",
+        };
+        write!(f, "{}", code)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NonFungibleConditionCode {
+    Sent,
+    NotSent,
+}
+
+impl NonFungibleConditionCode {
+    pub fn check(&self, nft_sent_condition: &Value, nfts_sent: &[Value]) -> bool {
+        let was_sent = nfts_sent.contains(nft_sent_condition);
+        match self {
+            NonFungibleConditionCode::Sent => was_sent,
+            NonFungibleConditionCode::NotSent => !was_sent,
+        }
+    }
+}
+
+impl FromStr for NonFungibleConditionCode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sent" => Ok(NonFungibleConditionCode::Sent),
+            "not-sent" => Ok(NonFungibleConditionCode::NotSent),
+            _ => Err(format!(
+                "unknown non-fungible condition code '{}' (expected 'sent' or 'not-sent')",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for NonFungibleConditionCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NonFungibleConditionCode::Sent => write!(f, "sent"),
+            NonFungibleConditionCode::NotSent => write!(f, "not-sent"),
+        }
+    }
+}
+
+/// Post-condition on the assets sent by a principal, mirroring the ones
+/// that can be attached to a Stacks transaction.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PostCondition {
+    Stx(PrincipalData, FungibleConditionCode, u128),
+    Fungible(PrincipalData, AssetIdentifier, FungibleConditionCode, u128),
+    NonFungible(
+        PrincipalData,
+        AssetIdentifier,
+        Value,
+        NonFungibleConditionCode,
+    ),
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PostConditions {
+    pub mode: PostConditionMode,
+    pub conditions: Vec<PostCondition>,
+}
+
+#[derive(Default)]
+struct AssetsSent {
+    stx: HashMap<PrincipalData, u128>,
+    fungible: HashMap<(PrincipalData, AssetIdentifier), u128>,
+    non_fungible: HashMap<(PrincipalData, AssetIdentifier), Vec<Value>>,
+}
+
+impl AssetsSent {
+    fn from_events(events: &[StacksTransactionEvent]) -> AssetsSent {
+        let mut assets = AssetsSent::default();
+        for event in events.iter() {
+            match event {
+                StacksTransactionEvent::STXEvent(STXEventType::STXTransferEvent(data)) => {
+                    *assets.stx.entry(data.sender.clone()).or_default() += data.amount;
+                }
+                StacksTransactionEvent::STXEvent(STXEventType::STXBurnEvent(data)) => {
+                    *assets.stx.entry(data.sender.clone()).or_default() += data.amount;
+                }
+                StacksTransactionEvent::FTEvent(FTEventType::FTTransferEvent(data)) => {
+                    let key = (data.sender.clone(), data.asset_identifier.clone());
+                    *assets.fungible.entry(key).or_default() += data.amount;
+                }
+                StacksTransactionEvent::FTEvent(FTEventType::FTBurnEvent(data)) => {
+                    let key = (data.sender.clone(), data.asset_identifier.clone());
+                    *assets.fungible.entry(key).or_default() += data.amount;
+                }
+                StacksTransactionEvent::NFTEvent(NFTEventType::NFTTransferEvent(data)) => {
+                    let key = (data.sender.clone(), data.asset_identifier.clone());
+                    assets
+                        .non_fungible
+                        .entry(key)
+                        .or_default()
+                        .push(data.value.clone());
+                }
+                StacksTransactionEvent::NFTEvent(NFTEventType::NFTBurnEvent(data)) => {
+                    let key = (data.sender.clone(), data.asset_identifier.clone());
+                    assets
+                        .non_fungible
+                        .entry(key)
+                        .or_default()
+                        .push(data.value.clone());
+                }
+                _ => {}
+            }
+        }
+        assets
+    }
+}
+
+fn fmt_asset(asset: &AssetIdentifier) -> String {
+    format!("{}::{}", asset.contract_identifier, asset.asset_name)
+}
+
+impl PostConditions {
+    pub fn new(mode: PostConditionMode, conditions: Vec<PostCondition>) -> Self {
+        Self { mode, conditions }
+    }
+
+    /// Check the assets moved by the events of a transaction, the same way a
+    /// Stacks node would before committing it.
+    pub fn check(&self, events: &[StacksTransactionEvent]) -> Result<(), String> {
+        let assets = AssetsSent::from_events(events);
+
+        let mut checked_stx = HashSet::new();
+        let mut checked_assets = HashSet::new();
+        // non-fungible post-conditions only cover the token they name
+        let mut checked_tokens = vec![];
+        for condition in self.conditions.iter() {
+            match condition {
+                PostCondition::Stx(principal, code, amount) => {
+                    let sent = assets.stx.get(principal).copied().unwrap_or_default();
+                    if !code.check(*amount, sent) {
+                        return Err(format!(
+                            "{} sent {} uSTX, expected {} {}",
+                            principal, sent, code, amount
+                        ));
+                    }
+                    checked_stx.insert(principal.clone());
+                }
+                PostCondition::Fungible(principal, asset, code, amount) => {
+                    let key = (principal.clone(), asset.clone());
+                    let sent = assets.fungible.get(&key).copied().unwrap_or_default();
+                    if !code.check(*amount, sent) {
+                        return Err(format!(
+                            "{} sent {} {}, expected {} {}",
+                            principal,
+                            sent,
+                            fmt_asset(asset),
+                            code,
+                            amount
+                        ));
+                    }
+                    checked_assets.insert(key);
+                }
+                PostCondition::NonFungible(principal, asset, value, code) => {
+                    let key = (principal.clone(), asset.clone());
+                    let sent = assets
+                        .non_fungible
+                        .get(&key)
+                        .map(|values| values.as_slice())
+                        .unwrap_or_default();
+                    if !code.check(value, sent) {
+                        return Err(format!(
+                            "{} {} {}, expected {}",
+                            fmt_asset(asset),
+                            value,
+                            match code {
+                                NonFungibleConditionCode::Sent => "was not sent",
+                                NonFungibleConditionCode::NotSent => "was sent",
+                            },
+                            code
+                        ));
+                    }
+                    checked_tokens.push((principal, asset, value));
+                }
+            }
+        }
+
+        if self.mode == PostConditionMode::Deny {
+            if let Some(principal) = assets.stx.keys().find(|p| !checked_stx.contains(*p)) {
+                return Err(format!(
+                    "{} sent uSTX without a post-condition (deny mode)",
+                    principal
+                ));
+            }
+            let unchecked = assets
+                .fungible
+                .keys()
+                .find(|key| !checked_assets.contains(*key));
+            if let Some((principal, asset)) = unchecked {
+                return Err(format!(
+                    "{} sent {} without a post-condition (deny mode)",
+                    principal,
+                    fmt_asset(asset)
+                ));
+            }
+            for ((principal, asset), values) in assets.non_fungible.iter() {
+                if let Some(value) = values
+                    .iter()
+                    .find(|value| !checked_tokens.contains(&(principal, asset, *value)))
+                {
+                    return Err(format!(
+                        "{} sent {} {} without a post-condition (deny mode)",
+                        principal,
+                        fmt_asset(asset),
+                        value
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clarity::vm::events::{FTTransferEventData, NFTTransferEventData, STXBurnEventData};
+    use clarity::vm::types::{QualifiedContractIdentifier, StandardPrincipalData};
+    use clarity::vm::ClarityName;
+    use std::convert::TryFrom;
+
+    fn principal(address: &str) -> PrincipalData {
+        PrincipalData::parse(address).unwrap()
+    }
+
+    fn stx_burn(sender: &PrincipalData, amount: u128) -> StacksTransactionEvent {
+        StacksTransactionEvent::STXEvent(STXEventType::STXBurnEvent(STXBurnEventData {
+            sender: sender.clone(),
+            amount,
+        }))
+    }
+
+    #[test]
+    fn test_stx_post_conditions() {
+        let sender = principal("ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM");
+        let events = vec![stx_burn(&sender, 100), stx_burn(&sender, 50)];
+
+        let post_conditions = PostConditions::new(
+            PostConditionMode::Deny,
+            vec![PostCondition::Stx(
+                sender.clone(),
+                FungibleConditionCode::SentLe,
+                150,
+            )],
+        );
+        assert_eq!(post_conditions.check(&events), Ok(()));
+
+        let post_conditions = PostConditions::new(
+            PostConditionMode::Deny,
+            vec![PostCondition::Stx(
+                sender.clone(),
+                FungibleConditionCode::SentLt,
+                150,
+            )],
+        );
+        assert!(post_conditions.check(&events).is_err());
+
+        let post_conditions = PostConditions::new(PostConditionMode::Deny, vec![]);
+        assert!(post_conditions.check(&events).is_err());
+        let post_conditions = PostConditions::new(PostConditionMode::Allow, vec![]);
+        assert_eq!(post_conditions.check(&events), Ok(()));
+    }
+
+    #[test]
+    fn test_fungible_post_conditions_in_deny_mode() {
+        let sender = principal("ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM");
+        let asset = AssetIdentifier {
+            contract_identifier: QualifiedContractIdentifier::parse(
+                "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.token",
+            )
+            .unwrap(),
+            asset_name: ClarityName::try_from("token".to_string()).unwrap(),
+        };
+        let events = vec![
+            stx_burn(&sender, 10),
+            StacksTransactionEvent::FTEvent(FTEventType::FTTransferEvent(FTTransferEventData {
+                asset_identifier: asset.clone(),
+                sender: sender.clone(),
+                recipient: PrincipalData::Standard(StandardPrincipalData::transient()),
+                amount: 1000,
+            })),
+        ];
+
+        let stx_condition = PostCondition::Stx(sender.clone(), FungibleConditionCode::SentEq, 10);
+        let post_conditions =
+            PostConditions::new(PostConditionMode::Deny, vec![stx_condition.clone()]);
+        assert!(post_conditions.check(&events).is_err());
+
+        let post_conditions = PostConditions::new(
+            PostConditionMode::Deny,
+            vec![
+                stx_condition,
+                PostCondition::Fungible(sender, asset, FungibleConditionCode::SentGe, 1000),
+            ],
+        );
+        assert_eq!(post_conditions.check(&events), Ok(()));
+    }
+
+    #[test]
+    fn test_non_fungible_post_conditions_in_deny_mode() {
+        let sender = principal("ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM");
+        let asset = AssetIdentifier {
+            contract_identifier: QualifiedContractIdentifier::parse(
+                "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.nft",
+            )
+            .unwrap(),
+            asset_name: ClarityName::try_from("nft".to_string()).unwrap(),
+        };
+        let transfer = |id: u128| {
+            StacksTransactionEvent::NFTEvent(NFTEventType::NFTTransferEvent(NFTTransferEventData {
+                asset_identifier: asset.clone(),
+                sender: sender.clone(),
+                recipient: PrincipalData::Standard(StandardPrincipalData::transient()),
+                value: Value::UInt(id),
+            }))
+        };
+        let events = vec![transfer(1), transfer(2)];
+        let sent = |id: u128| {
+            PostCondition::NonFungible(
+                sender.clone(),
+                asset.clone(),
+                Value::UInt(id),
+                NonFungibleConditionCode::Sent,
+            )
+        };
+
+        // the post-condition on u1 doesn't cover u2
+        let post_conditions = PostConditions::new(PostConditionMode::Deny, vec![sent(1)]);
+        let error = post_conditions.check(&events).unwrap_err();
+        assert!(error.contains("u2 without a post-condition"));
+
+        let post_conditions = PostConditions::new(PostConditionMode::Deny, vec![sent(1), sent(2)]);
+        assert_eq!(post_conditions.check(&events), Ok(()));
+        let post_conditions = PostConditions::new(PostConditionMode::Allow, vec![sent(1)]);
+        assert_eq!(post_conditions.check(&events), Ok(()));
+    }
+}
@@ -1,20 +1,23 @@
 use super::boot::{STACKS_BOOT_CODE_MAINNET, STACKS_BOOT_CODE_TESTNET};
 use super::diagnostic::output_diagnostic;
+use super::interpreter::InterpreterState;
 use super::{ClarityCodeSource, ClarityContract, ClarityInterpreter, ContractDeployer};
 use crate::analysis::coverage::CoverageHook;
 use crate::repl::clarity_values::value_to_string;
+use crate::repl::sip018;
 use crate::repl::Settings;
 use crate::utils;
 use clarity::codec::StacksMessageCodec;
 use clarity::types::chainstate::StacksAddress;
 use clarity::types::StacksEpochId;
+use clarity::util::hash::bytes_to_hex;
 use clarity::vm::ast::ContractAST;
 use clarity::vm::diagnostic::{Diagnostic, Level};
 use clarity::vm::docs::{make_api_reference, make_define_reference, make_keyword_reference};
 use clarity::vm::functions::define::DefineFunctions;
 use clarity::vm::functions::NativeFunctions;
 use clarity::vm::types::{
-    PrincipalData, QualifiedContractIdentifier, StandardPrincipalData, Value,
+    FunctionType, PrincipalData, QualifiedContractIdentifier, StandardPrincipalData, Value,
 };
 use clarity::vm::variables::NativeVariables;
 use clarity::vm::{
@@ -28,6 +31,7 @@ use std::fmt;
 use std::num::ParseIntError;
 
 #[cfg(feature = "cli")]
+use clarity::vm::analysis::contract_interface_builder::ContractInterface;
 use clarity::vm::analysis::ContractAnalysis;
 
 use super::SessionSettings;
@@ -93,6 +97,35 @@ pub struct CostsReport {
     pub cost_result: CostSynthesis,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ContractState {
+    pub contract_id: String,
+    pub code: String,
+    /// Analysis of the contract, without its expressions and interface
+    pub analysis: ContractAnalysis,
+    pub contract_interface: Option<ContractInterface>,
+}
+
+/// State of the simulated chain of a session, meant to be saved to a file. The contracts
+/// are stored with their source and analysis, only their ast is rebuilt when the state is
+/// restored (the one of the boot contracts is not).
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct SessionState {
+    pub current_epoch: StacksEpochId,
+    pub interpreter: InterpreterState,
+    pub contracts: Vec<ContractState>,
+}
+
+impl SessionState {
+    pub fn from_json(content: &[u8]) -> Result<Self, String> {
+        serde_json::from_slice(content).map_err(|e| format!("unable to parse session state: {}", e))
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string(self).map_err(|e| format!("unable to serialize session state: {}", e))
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Session {
     pub settings: SessionSettings,
@@ -132,6 +165,93 @@ impl Session {
         }
     }
 
+    pub fn save_state(&self) -> SessionState {
+        SessionState {
+            current_epoch: self.current_epoch,
+            interpreter: self.interpreter.save_state(),
+            contracts: self
+                .contracts
+                .iter()
+                .map(|(contract_id, contract)| ContractState {
+                    contract_id: contract_id.to_string(),
+                    code: contract.code.clone(),
+                    analysis: contract.analysis.clone(),
+                    contract_interface: contract.analysis.contract_interface.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    /// New session in the state `state`, each session restored from the same state is
+    /// isolated from the others
+    pub fn from_state(settings: SessionSettings, state: &SessionState) -> Result<Self, String> {
+        let mut session = Session::new(settings);
+        session.interpreter.restore_state(&state.interpreter)?;
+        session.current_epoch = state.current_epoch;
+
+        for contract_state in state.contracts.iter() {
+            let contract_id = QualifiedContractIdentifier::parse(&contract_state.contract_id)
+                .map_err(|e| e.to_string())?;
+            // the contract is already in the datastore, only its ast is built again
+            let ast = match BOOT_CONTRACTS_DATA.get(&contract_id) {
+                Some((_, ast)) => ast.clone(),
+                None => {
+                    let contract = ClarityContract {
+                        code_source: ClarityCodeSource::ContractInMemory(
+                            contract_state.code.clone(),
+                        ),
+                        name: contract_id.name.to_string(),
+                        deployer: ContractDeployer::ContractIdentifier(contract_id.clone()),
+                        clarity_version: contract_state.analysis.clarity_version,
+                        epoch: contract_state.analysis.epoch,
+                    };
+                    let (ast, diagnostics, success) = session.interpreter.build_ast(&contract);
+                    if !success {
+                        let messages: Vec<String> =
+                            diagnostics.into_iter().map(|d| d.message).collect();
+                        return Err(format!(
+                            "unable to parse {}: {}",
+                            contract_id,
+                            messages.join(", ")
+                        ));
+                    }
+                    ast
+                }
+            };
+            let mut analysis = contract_state.analysis.clone();
+            analysis.expressions = ast.expressions.clone();
+            analysis.contract_interface = contract_state.contract_interface.clone();
+
+            let mut function_args = BTreeMap::new();
+            for (name, function_type) in analysis
+                .public_function_types
+                .iter()
+                .chain(analysis.read_only_function_types.iter())
+            {
+                let FunctionType::Fixed(function) = function_type else {
+                    continue;
+                };
+                let args: Vec<String> = function
+                    .args
+                    .iter()
+                    .map(|arg| format!("({} {})", arg.name, arg.signature))
+                    .collect();
+                function_args.insert(name.to_string(), args);
+            }
+            session.contracts.insert(
+                contract_id,
+                ParsedContract {
+                    contract_identifier: contract_state.contract_id.clone(),
+                    code: contract_state.code.clone(),
+                    function_args,
+                    ast,
+                    analysis,
+                },
+            );
+        }
+        Ok(session)
+    }
+
     pub fn enable_coverage(&mut self) {
         self.coverage_hook = Some(CoverageHook::new());
     }
@@ -285,6 +405,7 @@ impl Session {
             cmd if cmd.starts_with("::set_epoch") => self.set_epoch(cmd),
             cmd if cmd.starts_with("::encode") => self.encode(cmd),
             cmd if cmd.starts_with("::decode") => self.decode(cmd),
+            cmd if cmd.starts_with("::sip018_hash") => self.sip018_hash(cmd),
 
             _ => "Invalid command. Try `::help`".yellow().to_string(),
         }
@@ -852,6 +973,11 @@ impl Session {
             "{}",
             "::decode <bytes>\t\t\tDecode a Clarity Value bytes representation".yellow()
         ));
+        output.push(format!(
+            "{}",
+            "::sip018_hash <expr>\t\t\tCompute the SIP-018 hash of a { domain, message } tuple"
+                .yellow()
+        ));
 
         output.join("\n")
     }
@@ -1089,6 +1215,44 @@ impl Session {
         format!("{}", value_to_string(&value).green())
     }
 
+    pub fn sip018_hash(&mut self, cmd: &str) -> String {
+        let snippet = match cmd.split_once(' ') {
+            Some((_, snippet)) => snippet,
+            _ => return "Usage: ::sip018_hash <expr>".red().to_string(),
+        };
+
+        let value = match self.eval(snippet.to_string(), false) {
+            Ok(result) => match result.result {
+                EvaluationResult::Contract(contract_result) => match contract_result.result {
+                    Some(value) => value,
+                    None => return "No value".to_string(),
+                },
+                EvaluationResult::Snippet(snippet_result) => snippet_result.result,
+            },
+            Err(diagnostics) => {
+                let lines: Vec<String> = snippet.split('\n').map(|s| s.to_string()).collect();
+                let mut output: Vec<String> = diagnostics
+                    .iter()
+                    .flat_map(|d| output_diagnostic(d, "sip018", &lines))
+                    .collect();
+                output.push("hashing failed".into());
+                return output.join("\n");
+            }
+        };
+
+        let tuple = match value {
+            Value::Tuple(tuple) => tuple,
+            _ => return "Expected a { domain, message } tuple".red().to_string(),
+        };
+        let (Ok(domain), Ok(message)) = (tuple.get("domain"), tuple.get("message")) else {
+            return "Expected a { domain, message } tuple".red().to_string();
+        };
+        match sip018::structured_data_hash(domain, message) {
+            Ok(hash) => format!("0x{}", bytes_to_hex(&hash)).green().to_string(),
+            Err(e) => e.red().to_string(),
+        }
+    }
+
     #[cfg(feature = "cli")]
     pub fn get_costs(&mut self, output: &mut Vec<String>, cmd: &str) {
         let expr = match cmd.split_once(' ') {
@@ -1371,6 +1535,75 @@ mod tests {
         assert_eq!(result.result, expected_value);
     }
 
+    #[test]
+    fn restore_saved_state() {
+        let settings = SessionSettings {
+            include_boot_contracts: vec!["pox-4".into()],
+            ..Default::default()
+        };
+        let mut session = Session::new(settings);
+        session.update_epoch(StacksEpochId::Epoch25);
+        session.load_boot_contracts();
+
+        let snippet = "
+            (define-data-var x uint u0)
+            (define-read-only (get-x)
+                (var-get x))
+            (define-public (incr)
+                (begin
+                    (var-set x (+ (var-get x) u1))
+                    (ok (var-get x))))";
+        let contract = ClarityContract {
+            code_source: ClarityCodeSource::ContractInMemory(snippet.to_string()),
+            name: "contract".to_string(),
+            deployer: ContractDeployer::Address("ST000000000000000000002AMW42H".into()),
+            clarity_version: ClarityVersion::Clarity2,
+            epoch: StacksEpochId::Epoch25,
+        };
+        let _ = session.deploy_contract(&contract, false, None);
+        session.process_console_input("(contract-call? .contract incr)");
+        let _ = session.advance_chain_tip(10);
+
+        let json = session.save_state().to_json().unwrap();
+        let state = SessionState::from_json(json.as_bytes()).unwrap();
+        assert_eq!(state.to_json().unwrap(), json);
+
+        let mut fork = Session::from_state(SessionSettings::default(), &state).unwrap();
+        let mut other_fork = Session::from_state(SessionSettings::default(), &state).unwrap();
+        assert_eq!(fork.save_state().to_json().unwrap(), json);
+        // boot contracts are restored with their analysis, without analyzing them again
+        let pox_4 =
+            QualifiedContractIdentifier::parse("ST000000000000000000002AMW42H.pox-4").unwrap();
+        let restored_pox_4 = fork.contracts.get(&pox_4).unwrap();
+        assert_eq!(
+            restored_pox_4.analysis.public_function_types,
+            session.contracts[&pox_4].analysis.public_function_types
+        );
+        assert!(!restored_pox_4.analysis.expressions.is_empty());
+        assert_eq!(fork.current_epoch, StacksEpochId::Epoch25);
+        assert!(fork.contracts.contains_key(
+            &QualifiedContractIdentifier::parse("ST000000000000000000002AMW42H.contract").unwrap()
+        ));
+
+        // changes made to a fork are not seen by the others
+        fork.process_console_input("(contract-call? .contract incr)");
+        assert_eq!(
+            fork.process_console_input("(contract-call? .contract get-x)")
+                .1[0],
+            "u2".green().to_string()
+        );
+        assert_eq!(
+            other_fork
+                .process_console_input("(contract-call? .contract get-x)")
+                .1[0],
+            "u1".green().to_string()
+        );
+        assert_eq!(
+            other_fork.interpreter.get_block_height(),
+            session.interpreter.get_block_height()
+        );
+    }
+
     #[test]
     fn initial_accounts() {
         let address = "ST1SJ3DTE5DN7X54YDH5D64R3BCB6A2AG2ZQ8YPD5";
@@ -1561,6 +1794,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn sip018_hash() {
+        let mut session = Session::new(SessionSettings::default());
+        let result = session.sip018_hash(
+            "::sip018_hash { domain: { name: \"app\", version: \"1.0.0\", chain-id: u1 }, message: u1 }",
+        );
+        let domain = sip018::make_domain("app", "1.0.0", 1).unwrap();
+        let hash = sip018::structured_data_hash(&domain, &Value::UInt(1)).unwrap();
+        assert_eq!(
+            result,
+            format!("0x{}", bytes_to_hex(&hash)).green().to_string()
+        );
+
+        let result = session.sip018_hash("::sip018_hash u1");
+        assert_eq!(
+            result,
+            "Expected a { domain, message } tuple".red().to_string()
+        );
+    }
+
     #[test]
     fn clarity_epoch_mismatch() {
         let settings = SessionSettings::default();
@@ -0,0 +1,151 @@
+use std::convert::TryFrom;
+
+use clarity::types::{PrivateKey, PublicKey};
+use clarity::util::secp256k1::{MessageSignature, Secp256k1PrivateKey, Secp256k1PublicKey};
+use clarity::vm::types::TupleData;
+use clarity::vm::{ClarityName, Value};
+use sha2::{Digest, Sha256};
+
+/// "SIP018" in ascii
+pub const SIP018_MESSAGE_PREFIX: &[u8] = &[0x53, 0x49, 0x50, 0x30, 0x31, 0x38];
+
+/// Build the domain tuple `{ name, version, chain-id }` used to scope SIP-018 messages.
+pub fn make_domain(name: &str, version: &str, chain_id: u32) -> Result<Value, String> {
+    let entry = |key: &str, value: Value| {
+        ClarityName::try_from(key.to_string())
+            .map(|key| (key, value))
+            .map_err(|e| format!("{:?}", e))
+    };
+    let name =
+        Value::string_ascii_from_bytes(name.as_bytes().to_vec()).map_err(|e| e.to_string())?;
+    let version =
+        Value::string_ascii_from_bytes(version.as_bytes().to_vec()).map_err(|e| e.to_string())?;
+    let data = vec![
+        entry("name", name)?,
+        entry("version", version)?,
+        entry("chain-id", Value::UInt(chain_id.into()))?,
+    ];
+    TupleData::from_data(data)
+        .map(Value::Tuple)
+        .map_err(|e| e.to_string())
+}
+
+fn value_hash(value: &Value) -> Result<[u8; 32], String> {
+    let bytes = value
+        .serialize_to_vec()
+        .map_err(|e| format!("unable to serialize {}: {:?}", value, e))?;
+    Ok(Sha256::digest(bytes).into())
+}
+
+/// sha256(SIP018_MESSAGE_PREFIX || sha256(domain) || sha256(message)), the hash
+/// expected to be signed, and verified on-chain with `secp256k1-recover?`.
+pub fn structured_data_hash(domain: &Value, message: &Value) -> Result<[u8; 32], String> {
+    let mut hasher = Sha256::new();
+    hasher.update(SIP018_MESSAGE_PREFIX);
+    hasher.update(value_hash(domain)?);
+    hasher.update(value_hash(message)?);
+    Ok(hasher.finalize().into())
+}
+
+/// Sign a structured data message, returns the 65 bytes signature in the RSV format
+/// expected by `secp256k1-recover?` and `secp256k1-verify`.
+pub fn sign_structured_data(
+    domain: &Value,
+    message: &Value,
+    private_key: &Secp256k1PrivateKey,
+) -> Result<Vec<u8>, String> {
+    let hash = structured_data_hash(domain, message)?;
+    let MessageSignature(vrs) = private_key.sign(&hash).map_err(|e| e.to_string())?;
+    let mut rsv = vrs[1..].to_vec();
+    rsv.push(vrs[0]);
+    Ok(rsv)
+}
+
+/// Check that a RSV signature of a structured data message was produced by `public_key`.
+pub fn verify_structured_data(
+    domain: &Value,
+    message: &Value,
+    signature: &[u8],
+    public_key: &Secp256k1PublicKey,
+) -> Result<bool, String> {
+    if signature.len() != 65 {
+        return Err(format!(
+            "invalid signature length: {} (expected 65 bytes)",
+            signature.len()
+        ));
+    }
+    let hash = structured_data_hash(domain, message)?;
+    // MessageSignature uses the VRS format
+    let mut vrs = [0u8; 65];
+    vrs[0] = signature[64];
+    vrs[1..].copy_from_slice(&signature[..64]);
+    match Secp256k1PublicKey::recover_to_pubkey(&hash, &MessageSignature(vrs)) {
+        Ok(recovered) => Ok(recovered.to_bytes_compressed() == public_key.to_bytes_compressed()),
+        Err(_) => Ok(false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clarity::util::hash::hex_bytes;
+
+    #[test]
+    fn test_sign_and_verify_structured_data() {
+        let private_key = Secp256k1PrivateKey::from_hex(
+            "7287ba251d44a4d3fd9276c88ce34c5c52a038955511cccaf77e61068649c17801",
+        )
+        .unwrap();
+        let public_key = Secp256k1PublicKey::from_private(&private_key);
+        let domain = make_domain("Test App", "1.0.0", 1).unwrap();
+        let message = Value::string_ascii_from_bytes("Hello World".as_bytes().to_vec()).unwrap();
+
+        let signature = sign_structured_data(&domain, &message, &private_key).unwrap();
+        assert_eq!(signature.len(), 65);
+        assert_eq!(
+            verify_structured_data(&domain, &message, &signature, &public_key),
+            Ok(true)
+        );
+
+        let other_domain = make_domain("Test App", "1.0.1", 1).unwrap();
+        assert_eq!(
+            verify_structured_data(&other_domain, &message, &signature, &public_key),
+            Ok(false)
+        );
+        assert!(verify_structured_data(&domain, &message, &signature[1..], &public_key).is_err());
+    }
+
+    // test vectors of https://github.com/stacksgov/sips/blob/main/sips/sip-018/sip-018-signed-structured-data.md
+    #[test]
+    fn test_sip018_test_vectors() {
+        let domain = make_domain("Test App", "1.0.0", 1).unwrap();
+        let message = Value::string_ascii_from_bytes("Hello World".as_bytes().to_vec()).unwrap();
+        assert_eq!(
+            value_hash(&domain).unwrap().to_vec(),
+            hex_bytes("2538b5dc06c5ae2f11549261d7ae174d9f77a55a92b00f330884695497be5065").unwrap()
+        );
+        assert_eq!(
+            value_hash(&message).unwrap().to_vec(),
+            hex_bytes("5297eef9765c466d945ad1cb2c81b30b9fed6c165575dc9226e9edf78b8cd9e8").unwrap()
+        );
+        assert_eq!(
+            structured_data_hash(&domain, &message).unwrap().to_vec(),
+            hex_bytes("1bfdab6d4158313ce34073fbb8d6b0fc32c154d439def12247a0f44bb2225259").unwrap()
+        );
+
+        let private_key = Secp256k1PrivateKey::from_hex(
+            "753b7cc01a1a2e86221266a154af739463fce51219d97e4f856cd7200c3bd2a601",
+        )
+        .unwrap();
+        let public_key = Secp256k1PublicKey::from_private(&private_key);
+        let expected_signature = hex_bytes("8b94e45701d857c9f1d1d70e8b2ca076045dae4920fb0160be0642a68cd78de072ab527b5c5277a593baeb2a8b657c216b99f7abb5d14af35b4bf12ba6460ba401").unwrap();
+        assert_eq!(
+            sign_structured_data(&domain, &message, &private_key).unwrap(),
+            expected_signature
+        );
+        assert_eq!(
+            verify_structured_data(&domain, &message, &expected_signature, &public_key),
+            Ok(true)
+        );
+    }
+}
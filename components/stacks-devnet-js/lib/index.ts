@@ -11,6 +11,11 @@ const {
   stacksDevnetGetStacksApiUrl,
   stacksDevnetGetStacksExplorerUrl,
   stacksDevnetGetBitcoinExplorerUrl,
+  stacksDevnetGetBitcoinKeys,
+  stacksDevnetBuildBitcoinPsbt,
+  stacksDevnetBuildStackStxPsbt,
+  stacksDevnetSignBitcoinPsbt,
+  stacksDevnetBroadcastBitcoinPsbt,
 } = require("../native/index.node");
 import {
   BitcoinChainUpdate,
@@ -70,6 +75,37 @@ export interface Account {
   is_mainnet?: boolean;
 }
 
+/**
+ * Regtest bitcoin keys of a devnet account
+ * @export
+ * @interface BitcoinKeys
+ */
+export interface BitcoinKeys {
+  /**
+   * The P2PKH address of the account
+   * @type {string}
+   * @memberof BitcoinKeys
+   */
+  address: string;
+  /**
+   * The private key of the account, in the wallet import format
+   * @type {string}
+   * @memberof BitcoinKeys
+   */
+  wif: string;
+}
+
+/**
+ * Output of a bitcoin transaction built with `buildBitcoinPsbt`:
+ * - `payment`: send `amount` sats to `recipient`
+ * - `op_return`: embed `data` (80 bytes max) in an OP_RETURN output
+ * - `pre_stx`: `pre-stx` burnchain operation, to be spent by `buildStackStxPsbt`
+ */
+export type BitcoinOutput =
+  | { type: "payment"; recipient: string; amount: number }
+  | { type: "op_return"; data: number[] }
+  | { type: "pre_stx"; stacker: string };
+
 /**
  * Transaction helper to ease scheduling of Stacking operations that can be performed by the genesis accounts
  * @export
@@ -138,6 +174,12 @@ export interface DevnetConfig {
    * @memberof DevnetConfig
    */
   orchestrator_control_port?: number;
+  /**
+   * The port serving the Prometheus metrics of the event observer, disabled if not set
+   * @type {number}
+   * @memberof DevnetConfig
+   */
+  orchestrator_metrics_port?: number;
   /**
    * The port that should be used by bitcoind's data plane
    * @type {number}
@@ -545,6 +587,72 @@ export class DevnetNetworkOrchestrator {
     return stacksDevnetGetBitcoinExplorerUrl.call(this.handle);
   }
 
+  /**
+   * @summary Returns the regtest bitcoin keys of a devnet account
+   * @memberof DevnetNetworkOrchestrator
+   */
+  getBitcoinKeys(label: string): BitcoinKeys {
+    return stacksDevnetGetBitcoinKeys.call(this.handle, label);
+  }
+
+  /**
+   * @summary Returns an unsigned PSBT, base64 encoded, paying the outputs from the UTXOs of a devnet account
+   * @memberof DevnetNetworkOrchestrator
+   */
+  buildBitcoinPsbt(
+    sender: string,
+    outputs: BitcoinOutput[],
+    satsPerByte: number = 10
+  ): string {
+    return stacksDevnetBuildBitcoinPsbt.call(
+      this.handle,
+      sender,
+      outputs,
+      satsPerByte
+    );
+  }
+
+  /**
+   * @summary Returns an unsigned PSBT, base64 encoded, of a `stack-stx` burnchain operation locking `amount` µSTX
+   * of a devnet account for `cycles` reward cycles. It spends the `pre_stx` output of the transaction `preStxTxid`,
+   * which must be broadcasted first.
+   * @memberof DevnetNetworkOrchestrator
+   */
+  buildStackStxPsbt(
+    stacker: string,
+    preStxTxid: string,
+    amount: number,
+    cycles: number,
+    poxAddress: string,
+    satsPerByte: number = 10
+  ): string {
+    return stacksDevnetBuildStackStxPsbt.call(
+      this.handle,
+      stacker,
+      preStxTxid,
+      amount,
+      cycles,
+      poxAddress,
+      satsPerByte
+    );
+  }
+
+  /**
+   * @summary Sign the inputs of a PSBT spending from a devnet account
+   * @memberof DevnetNetworkOrchestrator
+   */
+  signBitcoinPsbt(signer: string, psbt: string): string {
+    return stacksDevnetSignBitcoinPsbt.call(this.handle, signer, psbt);
+  }
+
+  /**
+   * @summary Broadcast a signed PSBT to the bitcoin node, returns the txid
+   * @memberof DevnetNetworkOrchestrator
+   */
+  broadcastBitcoinPsbt(psbt: string): string {
+    return stacksDevnetBroadcastBitcoinPsbt.call(this.handle, psbt);
+  }
+
   /**
    * @summary Wait for the next Stacks block
    * @memberof DevnetNetworkOrchestrator
@@ -4,6 +4,10 @@ extern crate error_chain;
 
 mod serde;
 
+use clarinet_deployments::onchain::bitcoin_devnet::{
+    get_account_bitcoin_wif, op_return_output, payment_output, pre_stx_outputs, psbt_from_base64,
+    psbt_to_base64, sign_psbt, stack_stx_funding, DevnetBitcoinRpc, TxOut, Txid,
+};
 use clarinet_deployments::{get_default_deployment_path, load_deployment};
 use clarinet_files::bip39::{Language, Mnemonic};
 use clarinet_files::{
@@ -73,6 +77,10 @@ struct StacksDevnet {
     stacks_api_url: String,
     stacks_explorer_url: String,
     bitcoin_explorer_url: String,
+    accounts: BTreeMap<String, AccountConfig>,
+    bitcoin_rpc_host: String,
+    bitcoin_rpc_username: String,
+    bitcoin_rpc_password: String,
 }
 
 enum DevnetCommand {
@@ -188,6 +196,19 @@ impl StacksDevnet {
                 )
             })
             .expect("unable to read config");
+        let (accounts, bitcoin_rpc_host, bitcoin_rpc_username, bitcoin_rpc_password) = devnet
+            .network_config
+            .as_ref()
+            .and_then(|config| config.devnet.as_ref().map(|devnet| (config, devnet)))
+            .map(|(config, devnet)| {
+                (
+                    config.accounts.clone(),
+                    format!("localhost:{}", devnet.bitcoin_node_rpc_port),
+                    devnet.bitcoin_node_username.clone(),
+                    devnet.bitcoin_node_password.clone(),
+                )
+            })
+            .expect("unable to read config");
 
         thread::spawn(move || {
             #[allow(clippy::never_loop)]
@@ -366,6 +387,10 @@ impl StacksDevnet {
             stacks_api_url,
             stacks_explorer_url,
             bitcoin_explorer_url,
+            accounts,
+            bitcoin_rpc_host,
+            bitcoin_rpc_username,
+            bitcoin_rpc_password,
         }
     }
 
@@ -376,6 +401,88 @@ impl StacksDevnet {
             .map_err(|e| format!("broken channel: {}", e))??;
         Ok(true)
     }
+
+    fn get_account(&self, label: &str) -> Result<AccountConfig, String> {
+        self.accounts
+            .get(label)
+            .cloned()
+            .ok_or(format!("unknown devnet account {}", label))
+    }
+
+    fn bitcoin_rpc(&self) -> Result<DevnetBitcoinRpc, String> {
+        DevnetBitcoinRpc::new(
+            &self.bitcoin_rpc_host,
+            &self.bitcoin_rpc_username,
+            &self.bitcoin_rpc_password,
+        )
+    }
+}
+
+/// Largest integer a JS number represents exactly (`Number.MAX_SAFE_INTEGER`)
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_991.0;
+
+/// Integer of type `T` held by a JS number, `name` being used in the error
+fn js_number_to_int<T: TryFrom<u64>>(value: f64, name: &str) -> Result<T, String> {
+    let invalid = || format!("invalid {}: {} is not an integer in range", name, value);
+    if value.fract() != 0.0 || !(0.0..=MAX_SAFE_INTEGER).contains(&value) {
+        return Err(invalid());
+    }
+    T::try_from(value as u64).map_err(|_| invalid())
+}
+
+/// Outputs described by `{ type: "payment", recipient, amount }`,
+/// `{ type: "op_return", data }` or `{ type: "pre_stx", stacker }`
+fn js_to_bitcoin_outputs(
+    cx: &mut FunctionContext,
+    js_output: Handle<JsValue>,
+    sats_per_byte: u64,
+) -> NeonResult<Vec<TxOut>> {
+    let output = js_output.downcast_or_throw::<JsObject, _>(cx)?;
+    let output_type = output
+        .get(cx, "type")?
+        .downcast_or_throw::<JsString, _>(cx)?
+        .value(cx);
+    let outputs = match output_type.as_str() {
+        "payment" => {
+            let recipient = output
+                .get(cx, "recipient")?
+                .downcast_or_throw::<JsString, _>(cx)?
+                .value(cx);
+            let amount = output
+                .get(cx, "amount")?
+                .downcast_or_throw::<JsNumber, _>(cx)?
+                .value(cx);
+            js_number_to_int(amount, "amount")
+                .and_then(|amount| payment_output(&recipient, amount))
+                .map(|output| vec![output])
+        }
+        "op_return" => {
+            let mut data = vec![];
+            for byte in output
+                .get(cx, "data")?
+                .downcast_or_throw::<JsArray, _>(cx)?
+                .to_vec(cx)?
+            {
+                let byte = byte.downcast_or_throw::<JsNumber, _>(cx)?.value(cx);
+                let byte =
+                    js_number_to_int::<u8>(byte, "data byte").or_else(|e| cx.throw_error(e))?;
+                data.push(byte);
+            }
+            op_return_output(&data).map(|output| vec![output])
+        }
+        "pre_stx" => {
+            let stacker = output
+                .get(cx, "stacker")?
+                .downcast_or_throw::<JsString, _>(cx)?
+                .value(cx);
+            pre_stx_outputs(&stacker, stack_stx_funding(sats_per_byte))
+        }
+        output_type => Err(format!("unknown bitcoin output type {}", output_type)),
+    };
+    match outputs {
+        Ok(outputs) => Ok(outputs),
+        Err(e) => cx.throw_error(e),
+    }
 }
 
 impl StacksDevnet {
@@ -442,6 +549,7 @@ impl StacksDevnet {
                 stx_address,
                 btc_address,
                 derivation,
+                secret_key: None,
                 is_mainnet,
                 balance: balance as u64,
             };
@@ -478,6 +586,13 @@ impl StacksDevnet {
             overrides.orchestrator_control_port = Some(res.value(&mut cx) as u16);
         }
 
+        if let Ok(res) = devnet_settings
+            .get(&mut cx, "orchestrator_metrics_port")?
+            .downcast::<JsNumber, _>(&mut cx)
+        {
+            overrides.orchestrator_metrics_port = Some(res.value(&mut cx) as u16);
+        }
+
         if let Ok(res) = devnet_settings
             .get(&mut cx, "bitcoin_node_p2p_port")?
             .downcast::<JsNumber, _>(&mut cx)
@@ -910,6 +1025,118 @@ impl StacksDevnet {
         Ok(js_block)
     }
 
+    fn js_get_bitcoin_keys(mut cx: FunctionContext) -> JsResult<JsObject> {
+        let devnet = cx
+            .this()
+            .downcast_or_throw::<JsBox<StacksDevnet>, _>(&mut cx)?;
+        let label = cx.argument::<JsString>(0)?.value(&mut cx);
+
+        let account = match devnet.get_account(&label) {
+            Ok(account) => account,
+            Err(e) => return cx.throw_error(e),
+        };
+        let keys = cx.empty_object();
+        let address = cx.string(&account.btc_address);
+        keys.set(&mut cx, "address", address)?;
+        let wif = cx.string(get_account_bitcoin_wif(&account));
+        keys.set(&mut cx, "wif", wif)?;
+        Ok(keys)
+    }
+
+    fn js_build_bitcoin_psbt(mut cx: FunctionContext) -> JsResult<JsString> {
+        let devnet = cx
+            .this()
+            .downcast_or_throw::<JsBox<StacksDevnet>, _>(&mut cx)?;
+        let label = cx.argument::<JsString>(0)?.value(&mut cx);
+        let js_outputs = cx.argument::<JsArray>(1)?.to_vec(&mut cx)?;
+        let sats_per_byte = cx.argument::<JsNumber>(2)?.value(&mut cx);
+        let sats_per_byte =
+            js_number_to_int(sats_per_byte, "satsPerByte").or_else(|e| cx.throw_error(e))?;
+
+        let mut outputs = vec![];
+        for js_output in js_outputs.into_iter() {
+            outputs.append(&mut js_to_bitcoin_outputs(
+                &mut cx,
+                js_output,
+                sats_per_byte,
+            )?);
+        }
+        let psbt = devnet.get_account(&label).and_then(|sender| {
+            devnet
+                .bitcoin_rpc()?
+                .build_psbt(&sender, outputs, sats_per_byte)
+        });
+        match psbt {
+            Ok(psbt) => Ok(cx.string(psbt_to_base64(&psbt))),
+            Err(e) => cx.throw_error(e),
+        }
+    }
+
+    fn js_build_stack_stx_psbt(mut cx: FunctionContext) -> JsResult<JsString> {
+        let devnet = cx
+            .this()
+            .downcast_or_throw::<JsBox<StacksDevnet>, _>(&mut cx)?;
+        let label = cx.argument::<JsString>(0)?.value(&mut cx);
+        let pre_stx_txid = cx.argument::<JsString>(1)?.value(&mut cx);
+        let amount = cx.argument::<JsNumber>(2)?.value(&mut cx);
+        let amount = js_number_to_int(amount, "amount").or_else(|e| cx.throw_error(e))?;
+        let cycles = cx.argument::<JsNumber>(3)?.value(&mut cx);
+        let cycles = js_number_to_int(cycles, "cycles").or_else(|e| cx.throw_error(e))?;
+        let pox_address = cx.argument::<JsString>(4)?.value(&mut cx);
+        let sats_per_byte = cx.argument::<JsNumber>(5)?.value(&mut cx);
+        let sats_per_byte =
+            js_number_to_int(sats_per_byte, "satsPerByte").or_else(|e| cx.throw_error(e))?;
+
+        let psbt = devnet.get_account(&label).and_then(|stacker| {
+            let pre_stx_txid = Txid::from_str(&pre_stx_txid)
+                .map_err(|e| format!("invalid txid {}: {}", pre_stx_txid, e))?;
+            devnet.bitcoin_rpc()?.build_stack_stx_psbt(
+                &stacker,
+                &pre_stx_txid,
+                amount,
+                cycles,
+                &pox_address,
+                sats_per_byte,
+            )
+        });
+        match psbt {
+            Ok(psbt) => Ok(cx.string(psbt_to_base64(&psbt))),
+            Err(e) => cx.throw_error(e),
+        }
+    }
+
+    fn js_sign_bitcoin_psbt(mut cx: FunctionContext) -> JsResult<JsString> {
+        let devnet = cx
+            .this()
+            .downcast_or_throw::<JsBox<StacksDevnet>, _>(&mut cx)?;
+        let label = cx.argument::<JsString>(0)?.value(&mut cx);
+        let encoded_psbt = cx.argument::<JsString>(1)?.value(&mut cx);
+
+        let psbt = devnet.get_account(&label).and_then(|signer| {
+            let mut psbt = psbt_from_base64(&encoded_psbt)?;
+            sign_psbt(&mut psbt, &signer)?;
+            Ok(psbt)
+        });
+        match psbt {
+            Ok(psbt) => Ok(cx.string(psbt_to_base64(&psbt))),
+            Err(e) => cx.throw_error(e),
+        }
+    }
+
+    fn js_broadcast_bitcoin_psbt(mut cx: FunctionContext) -> JsResult<JsString> {
+        let devnet = cx
+            .this()
+            .downcast_or_throw::<JsBox<StacksDevnet>, _>(&mut cx)?;
+        let encoded_psbt = cx.argument::<JsString>(0)?.value(&mut cx);
+
+        let txid = psbt_from_base64(&encoded_psbt)
+            .and_then(|psbt| devnet.bitcoin_rpc()?.broadcast_psbt(psbt));
+        match txid {
+            Ok(txid) => Ok(cx.string(txid.to_string())),
+            Err(e) => cx.throw_error(e),
+        }
+    }
+
     fn js_get_bitcoin_node_url(mut cx: FunctionContext) -> JsResult<JsString> {
         let devnet = cx
             .this()
@@ -990,6 +1217,26 @@ fn main(mut cx: ModuleContext) -> NeonResult<()> {
         "stacksDevnetGetStacksApiUrl",
         StacksDevnet::js_get_stacks_api_url,
     )?;
+    cx.export_function(
+        "stacksDevnetGetBitcoinKeys",
+        StacksDevnet::js_get_bitcoin_keys,
+    )?;
+    cx.export_function(
+        "stacksDevnetBuildBitcoinPsbt",
+        StacksDevnet::js_build_bitcoin_psbt,
+    )?;
+    cx.export_function(
+        "stacksDevnetBuildStackStxPsbt",
+        StacksDevnet::js_build_stack_stx_psbt,
+    )?;
+    cx.export_function(
+        "stacksDevnetSignBitcoinPsbt",
+        StacksDevnet::js_sign_bitcoin_psbt,
+    )?;
+    cx.export_function(
+        "stacksDevnetBroadcastBitcoinPsbt",
+        StacksDevnet::js_broadcast_bitcoin_psbt,
+    )?;
     Ok(())
 }
 
@@ -1,6 +1,7 @@
 use chainhook_sdk::chainhooks::types::{ChainhookSpecificationNetworkMap, ChainhookStore};
 use chainhook_sdk::types::{BitcoinNetwork, StacksNetwork};
 use clarinet_files::FileLocation;
+use serde_json::Value as JsonValue;
 use std::fs::File;
 use std::io::BufReader;
 use std::path::PathBuf;
@@ -55,6 +56,169 @@ pub fn load_chainhooks(
     })
 }
 
+/// Lint the chainhook specs of the project, warnings are prefixed with the spec file
+pub fn lint_chainhooks(manifest_location: &FileLocation) -> Result<Vec<String>, String> {
+    let mut warnings = vec![];
+    for (path, relative_path) in get_chainhooks_files(manifest_location)?.into_iter() {
+        let Ok(content) = fs::read(&path) else {
+            continue;
+        };
+        // syntax errors are reported when the chainhooks are loaded
+        let Ok(spec) = serde_json::from_slice::<JsonValue>(&content) else {
+            continue;
+        };
+        for warning in lint_chainhook_specification(&spec) {
+            warnings.push(format!("{}: {}", relative_path, warning));
+        }
+    }
+    Ok(warnings)
+}
+
+/// Mistakes in a chainhook spec that parse correctly but won't behave as intended
+pub fn lint_chainhook_specification(spec: &JsonValue) -> Vec<String> {
+    let mut warnings = vec![];
+    let is_bitcoin = spec["chain"].as_str() == Some("bitcoin");
+    let Some(networks) = spec["networks"].as_object() else {
+        return warnings;
+    };
+    for (network, network_spec) in networks.iter() {
+        let mut warn = |message: String| warnings.push(format!("{} {}", network, message));
+        let is_hosted = matches!(network.as_str(), "mainnet" | "testnet");
+        let predicate = &network_spec["if_this"];
+
+        if is_bitcoin {
+            if let Some(op_return) = predicate["op_return"].as_object() {
+                for (rule, value) in op_return.iter() {
+                    let Some(value) = value.as_str() else {
+                        continue;
+                    };
+                    let hex = value.trim_start_matches("0x");
+                    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                        warn(format!("op_return.{} {} is not valid hex", rule, value));
+                    } else if hex.len() % 2 != 0 {
+                        warn(format!("op_return.{} {} has an odd length", rule, value));
+                    }
+                }
+            }
+            for scope in ["p2pkh", "p2sh", "p2wpkh", "p2wsh"] {
+                let Some(address) = predicate[scope]["equals"].as_str() else {
+                    continue;
+                };
+                if !is_bitcoin_address_for_network(address, network) {
+                    warn(format!(
+                        "{} address {} is not a {} address",
+                        scope, address, network
+                    ));
+                }
+            }
+        } else {
+            let mut principals = vec![];
+            collect_stacks_principals(predicate, &mut principals);
+            let expected_prefixes = match network.as_str() {
+                "mainnet" => ["SP", "SM"],
+                _ => ["ST", "SN"],
+            };
+            for principal in principals {
+                if !expected_prefixes
+                    .iter()
+                    .any(|prefix| principal.starts_with(prefix))
+                {
+                    warn(format!(
+                        "principal {} is not a {} address",
+                        principal, network
+                    ));
+                }
+            }
+            if predicate["scope"].as_str() == Some("block_height") {
+                let range: Option<Vec<u64>> = predicate["between"]
+                    .as_array()
+                    .and_then(|range| range.iter().map(|v| v.as_u64()).collect());
+                if let Some([lower, upper]) = range.as_deref() {
+                    if lower > upper {
+                        warn(format!(
+                            "block_height range [{}, {}] is empty",
+                            lower, upper
+                        ));
+                    }
+                }
+            }
+        }
+
+        let start_block = network_spec["start_block"].as_u64();
+        if let (Some(start_block), Some(end_block)) =
+            (start_block, network_spec["end_block"].as_u64())
+        {
+            if start_block > end_block {
+                warn(format!(
+                    "start_block {} is after end_block {}, the predicate can't match",
+                    start_block, end_block
+                ));
+            }
+        }
+        if network_spec["expire_after_occurrence"].as_u64() == Some(0) {
+            warn("expire_after_occurrence is 0, the predicate can't match".into());
+        }
+        if is_hosted && start_block.is_none() {
+            warn("has no start_block, the backfill will scan the whole chain".into());
+        }
+        if let Some(url) = network_spec["then_that"]["http_post"]["url"].as_str() {
+            if is_hosted && is_local_url(url) {
+                warn(format!(
+                    "http_post url {} is local, a hosted observer can't reach it",
+                    url
+                ));
+            }
+        }
+    }
+    warnings
+}
+
+fn is_bitcoin_address_for_network(address: &str, network: &str) -> bool {
+    let address = address.to_lowercase();
+    match network {
+        "mainnet" => ["1", "3", "bc1"].iter().any(|p| address.starts_with(p)),
+        "regtest" | "devnet" => ["m", "n", "2", "bcrt1"]
+            .iter()
+            .any(|p| address.starts_with(p)),
+        _ => ["m", "n", "2", "tb1"]
+            .iter()
+            .any(|p| address.starts_with(p)),
+    }
+}
+
+/// Stacks addresses used in a predicate, contract identifiers included
+fn collect_stacks_principals(value: &JsonValue, principals: &mut Vec<String>) {
+    match value {
+        JsonValue::String(value) => {
+            let address = value.split(['.', ':']).next().unwrap_or_default();
+            let is_principal = address.len() >= 39
+                && address.starts_with('S')
+                && address.chars().all(|c| c.is_ascii_alphanumeric());
+            if is_principal {
+                principals.push(address.to_string());
+            }
+        }
+        JsonValue::Array(values) => values
+            .iter()
+            .for_each(|value| collect_stacks_principals(value, principals)),
+        JsonValue::Object(map) => map
+            .values()
+            .for_each(|value| collect_stacks_principals(value, principals)),
+        _ => {}
+    }
+}
+
+fn is_local_url(url: &str) -> bool {
+    let host = url
+        .split("://")
+        .nth(1)
+        .unwrap_or(url)
+        .split(['/', ':'])
+        .next()
+        .unwrap_or_default();
+    matches!(host, "localhost" | "127.0.0.1" | "0.0.0.0") || host.ends_with(".localhost")
+}
+
 fn get_chainhooks_files(
     manifest_location: &FileLocation,
 ) -> Result<Vec<(PathBuf, String)>, String> {
@@ -82,3 +246,72 @@ fn get_chainhooks_files(
 
     Ok(hook_paths)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_lint_chainhook_specification() {
+        let bitcoin_spec = json!({
+            "chain": "bitcoin",
+            "uuid": "1",
+            "name": "op-returns",
+            "version": 1,
+            "networks": {
+                "regtest": {
+                    "if_this": { "scope": "outputs", "op_return": { "starts_with": "0xabc" } },
+                    "then_that": { "http_post": { "url": "http://localhost:3000/events" } }
+                },
+                "mainnet": {
+                    "if_this": {
+                        "scope": "outputs",
+                        "p2pkh": { "equals": "mr1iPkD9N3RJZZxXRk7xF9d36gffa6exNC" }
+                    },
+                    "then_that": { "http_post": { "url": "http://127.0.0.1:3000/events" } },
+                    "start_block": 840000
+                }
+            }
+        });
+        assert_eq!(
+            lint_chainhook_specification(&bitcoin_spec),
+            vec![
+                "regtest op_return.starts_with 0xabc has an odd length",
+                "mainnet p2pkh address mr1iPkD9N3RJZZxXRk7xF9d36gffa6exNC is not a mainnet address",
+                "mainnet http_post url http://127.0.0.1:3000/events is local, a hosted observer can't reach it",
+            ]
+        );
+
+        let stacks_spec = json!({
+            "chain": "stacks",
+            "uuid": "2",
+            "name": "transfers",
+            "version": 1,
+            "networks": {
+                "testnet": {
+                    "if_this": {
+                        "scope": "print_event",
+                        "contract_identifier": "SP2PABAF9FTAJYNFZH93XENAJ8FVY99RRM50D2JG9.nft-trait",
+                        "contains": "transfer"
+                    },
+                    "then_that": { "http_post": { "url": "https://example.com/events" } },
+                    "start_block": 120,
+                    "end_block": 100
+                },
+                "devnet": {
+                    "if_this": { "scope": "block_height", "between": [200, 100] },
+                    "then_that": { "file_append": { "path": "events.txt" } }
+                }
+            }
+        });
+        assert_eq!(
+            lint_chainhook_specification(&stacks_spec),
+            vec![
+                "testnet principal SP2PABAF9FTAJYNFZH93XENAJ8FVY99RRM50D2JG9 is not a testnet address",
+                "testnet start_block 120 is after end_block 100, the predicate can't match",
+                "devnet block_height range [200, 100] is empty",
+            ]
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use super::ChainsCoordinatorCommand;
 
+use crate::control_api::{start_control_api, ControlApiState};
 use crate::event::send_status_update;
 use crate::event::DevnetEvent;
 use crate::event::Status;
@@ -22,6 +23,7 @@ use clarinet_deployments::onchain::{
     apply_on_chain_deployment, DeploymentCommand, DeploymentEvent,
 };
 use clarinet_deployments::types::DeploymentSpecification;
+use clarinet_files::plugins::{run_plugins, LifecycleHook};
 use clarinet_files::PoxStackingOrder;
 use clarinet_files::StacksNetwork;
 use clarinet_files::DEFAULT_FIRST_BURN_HEADER_HEIGHT;
@@ -49,7 +51,7 @@ use std::str;
 use std::str::FromStr;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{channel, Receiver, Sender};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 #[derive(Deserialize)]
@@ -136,7 +138,7 @@ impl DevnetEventObserverConfig {
             display_stacks_ingestion_logs: true,
             bitcoin_network: chainhook_types::BitcoinNetwork::Regtest,
             stacks_network: chainhook_types::StacksNetwork::Devnet,
-            prometheus_monitoring_port: None,
+            prometheus_monitoring_port: devnet_config.orchestrator_metrics_port,
         };
 
         DevnetEventObserverConfig {
@@ -196,8 +198,30 @@ pub async fn start_chains_coordinator(
         &devnet_event_tx,
         Some(mining_command_tx.clone()),
         &boot_completed,
+        Some((
+            config.manifest.clone(),
+            json!({
+                "stacks_node_url": config.consolidated_stacks_rpc_url(),
+                "bitcoin_node_url": config.consolidated_bitcoin_rpc_url(),
+            }),
+        )),
     );
 
+    // Set-up the control API, allowing external test frameworks to drive the devnet over HTTP.
+    let control_api_state = ControlApiState {
+        config: config.clone(),
+        boot_completed: boot_completed.clone(),
+        mining_command_tx: Mutex::new(mining_command_tx.clone()),
+        orchestrator_terminator_tx: Mutex::new(orchestrator_terminator_tx.clone()),
+    };
+    let devnet_event_tx_moved = devnet_event_tx.clone();
+    let _ = hiro_system_kit::thread_named("Control API").spawn(move || {
+        let rt = hiro_system_kit::create_basic_runtime();
+        if let Err(e) = rt.block_on(start_control_api(control_api_state)) {
+            let _ = devnet_event_tx_moved.send(DevnetEvent::warning(e));
+        }
+    });
+
     let chainhooks_count = config
         .event_observer_config
         .registered_chainhooks
@@ -216,6 +240,12 @@ pub async fn start_chains_coordinator(
             .expect("Unable to terminate event observer");
     }
 
+    if let Some(port) = config.devnet_config.orchestrator_metrics_port {
+        let _ = devnet_event_tx.send(DevnetEvent::info(format!(
+            "Event observer metrics served on http://localhost:{port}/metrics",
+        )));
+    }
+
     // Spawn event observer
     let (observer_event_tx, observer_event_rx) = crossbeam_channel::unbounded();
     let event_observer_config = config.event_observer_config.clone();
@@ -543,6 +573,8 @@ pub fn perform_protocol_deployment(
             false,
             override_bitcoin_rpc_url,
             override_stacks_rpc_url,
+            None,
+            None,
         );
     });
 }
@@ -552,6 +584,7 @@ pub fn relay_devnet_protocol_deployment(
     devnet_event_tx: &Sender<DevnetEvent>,
     bitcoin_mining_tx: Option<Sender<BitcoinMiningCommand>>,
     boot_completed: &Arc<AtomicBool>,
+    devnet_ready_plugins: Option<(ProjectManifest, serde_json::Value)>,
 ) {
     let devnet_event_tx = devnet_event_tx.clone();
     let boot_completed = boot_completed.clone();
@@ -572,11 +605,18 @@ pub fn relay_devnet_protocol_deployment(
                     // Terminate
                     break;
                 }
+                DeploymentEvent::TransactionsRecorded(_) => {}
                 DeploymentEvent::DeploymentCompleted => {
                     boot_completed.store(true, Ordering::SeqCst);
                     if let Some(bitcoin_mining_tx) = bitcoin_mining_tx {
                         let _ = devnet_event_tx.send(DevnetEvent::BootCompleted(bitcoin_mining_tx));
                     }
+                    if let Some((manifest, context)) = devnet_ready_plugins {
+                        if let Err(e) = run_plugins(&manifest, LifecycleHook::DevnetReady, context)
+                        {
+                            let _ = devnet_event_tx.send(DevnetEvent::error(e));
+                        }
+                    }
                     break;
                 }
             }
@@ -751,11 +791,7 @@ pub async fn publish_stacking_orders(
                 let stacks_rpc = StacksRpc::new(&node_rpc_url_moved);
                 let nonce = stacks_rpc.get_nonce(&account.stx_address)?;
 
-                let (_, _, account_secret_key) = clarinet_files::compute_addresses(
-                    &account.mnemonic,
-                    &account.derivation,
-                    &StacksNetwork::Devnet.get_networks(),
-                );
+                let account_secret_key = account.secret_key_hex();
 
                 let (method, arguments) = get_stacking_tx_method_and_args(
                     pox_version,
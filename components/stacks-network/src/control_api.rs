@@ -0,0 +1,571 @@
+use crate::chains_coordinator::{BitcoinMiningCommand, DevnetEventObserverConfig};
+
+use clarinet_deployments::onchain::encode_stx_transfer;
+use clarinet_files::{AccountConfig, StacksNetwork};
+use clarity::vm::types::PrincipalData;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use serde_json::{json, Value as JsonValue};
+use stacks_codec::codec::TransactionAnchorMode;
+use stacks_rpc_client::StacksRpc;
+use std::convert::Infallible;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
+
+const FAUCET_TX_FEE: u64 = 1_000;
+/// Most bitcoin blocks mined by a single `/v1/mining/mine` request
+const MAX_BLOCKS_PER_REQUEST: u32 = 100;
+
+/// Handles shared by the control API with the chains coordinator and the orchestrator,
+/// used for driving the devnet from external test frameworks.
+pub struct ControlApiState {
+    pub config: DevnetEventObserverConfig,
+    pub boot_completed: Arc<AtomicBool>,
+    pub mining_command_tx: Mutex<Sender<BitcoinMiningCommand>>,
+    pub orchestrator_terminator_tx: Mutex<Sender<bool>>,
+}
+
+#[derive(Deserialize, Default)]
+struct MineRequest {
+    blocks: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct FaucetRequest {
+    recipient: String,
+    amount: u64,
+}
+
+#[derive(Deserialize)]
+struct SnapshotRequest {
+    name: String,
+}
+
+type ApiResult = Result<JsonValue, (StatusCode, String)>;
+
+/// Serve the control API on `localhost:<orchestrator_control_port>`.
+/// The routes are described by the OpenAPI document served on `/v1/openapi.json`.
+pub async fn start_control_api(state: ControlApiState) -> Result<(), String> {
+    let addr = SocketAddr::from((
+        [127, 0, 0, 1],
+        state.config.devnet_config.orchestrator_control_port,
+    ));
+    let state = Arc::new(state);
+    let make_service = make_service_fn(move |_conn| {
+        let state = state.clone();
+        async move { Ok::<_, Infallible>(service_fn(move |req| handle_request(req, state.clone()))) }
+    });
+
+    Server::try_bind(&addr)
+        .map_err(|e| format!("unable to start control API on {}: {}", addr, e))?
+        .serve(make_service)
+        .await
+        .map_err(|e| format!("control API terminated: {}", e))
+}
+
+async fn handle_request(
+    req: Request<Body>,
+    state: Arc<ControlApiState>,
+) -> Result<Response<Body>, Infallible> {
+    let method = req.method().clone();
+    let path = req.uri().path().to_string();
+    let body = match hyper::body::to_bytes(req.into_body()).await {
+        Ok(body) => body,
+        Err(e) => {
+            return Ok(json_response(
+                StatusCode::BAD_REQUEST,
+                json!({ "error": e.to_string() }),
+            ))
+        }
+    };
+
+    let result = match (&method, path.as_str()) {
+        (&Method::GET, "/v1/openapi.json") => Ok(openapi_specification()),
+        (&Method::GET, "/v1/status") => get_status(state).await,
+        (&Method::POST, "/v1/mining/mine") => mine_blocks(&state.mining_command_tx, &body),
+        (&Method::POST, "/v1/mining/start") => {
+            send_mining_command(&state.mining_command_tx, BitcoinMiningCommand::Start)
+        }
+        (&Method::POST, "/v1/mining/pause") => {
+            send_mining_command(&state.mining_command_tx, BitcoinMiningCommand::Pause)
+        }
+        (&Method::POST, "/v1/faucet") => faucet(state, &body).await,
+        (&Method::POST, "/v1/services/restart") => restart_services(&state),
+        (&Method::POST, "/v1/snapshots") => {
+            take_snapshot(&state.config.devnet_config.working_dir, &body)
+        }
+        _ => Err((
+            StatusCode::NOT_FOUND,
+            format!("no route for {} {}", method, path),
+        )),
+    };
+
+    let response = match result {
+        Ok(value) => json_response(StatusCode::OK, value),
+        Err((status, message)) => json_response(status, json!({ "error": message })),
+    };
+    Ok(response)
+}
+
+fn json_response(status: StatusCode, value: JsonValue) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json")
+        .body(Body::from(value.to_string()))
+        .expect("unable to build response")
+}
+
+fn bad_request<E: std::fmt::Display>(e: E) -> (StatusCode, String) {
+    (StatusCode::BAD_REQUEST, e.to_string())
+}
+
+fn internal_error<E: std::fmt::Display>(e: E) -> (StatusCode, String) {
+    (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+}
+
+async fn get_status(state: Arc<ControlApiState>) -> ApiResult {
+    let stacks_node_url = state.config.consolidated_stacks_rpc_url();
+    let moved_url = stacks_node_url.clone();
+    // StacksRpc relies on a blocking http client
+    let node_info = tokio::task::spawn_blocking(move || StacksRpc::new(&moved_url).get_info())
+        .await
+        .map_err(internal_error)?;
+
+    let stacks_node = match node_info {
+        Ok(info) => json!({
+            "ready": true,
+            "stacks_tip_height": info.stacks_tip_height,
+            "stacks_tip": info.stacks_tip,
+            "burn_block_height": info.burn_block_height,
+            "server_version": info.server_version,
+        }),
+        Err(e) => json!({ "ready": false, "error": e.to_string() }),
+    };
+
+    let accounts = state
+        .config
+        .accounts
+        .iter()
+        .map(|account| {
+            json!({
+                "label": account.label,
+                "stx_address": account.stx_address,
+                "btc_address": account.btc_address,
+                "balance": account.balance,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    Ok(json!({
+        "boot_completed": state.boot_completed.load(Ordering::SeqCst),
+        "stacks_node_url": stacks_node_url,
+        "bitcoin_node_url": state.config.consolidated_bitcoin_rpc_url(),
+        "stacks_node": stacks_node,
+        "accounts": accounts,
+    }))
+}
+
+fn send_mining_command(
+    mining_command_tx: &Mutex<Sender<BitcoinMiningCommand>>,
+    command: BitcoinMiningCommand,
+) -> ApiResult {
+    mining_command_tx
+        .lock()
+        .map_err(internal_error)?
+        .send(command)
+        .map_err(internal_error)?;
+    Ok(json!({ "ok": true }))
+}
+
+fn mine_blocks(mining_command_tx: &Mutex<Sender<BitcoinMiningCommand>>, body: &[u8]) -> ApiResult {
+    let request: MineRequest = match body.is_empty() {
+        true => MineRequest::default(),
+        false => serde_json::from_slice(body).map_err(bad_request)?,
+    };
+    let blocks = request.blocks.unwrap_or(1);
+    if blocks == 0 || blocks > MAX_BLOCKS_PER_REQUEST {
+        return Err(bad_request(format!(
+            "blocks must be between 1 and {}",
+            MAX_BLOCKS_PER_REQUEST
+        )));
+    }
+    for _ in 0..blocks {
+        send_mining_command(mining_command_tx, BitcoinMiningCommand::Mine)?;
+    }
+    Ok(json!({ "ok": true, "blocks": blocks }))
+}
+
+async fn faucet(state: Arc<ControlApiState>, body: &[u8]) -> ApiResult {
+    let request: FaucetRequest = serde_json::from_slice(body).map_err(bad_request)?;
+    let recipient = PrincipalData::parse(&request.recipient)
+        .map_err(|e| bad_request(format!("invalid recipient: {}", e)))?;
+    let faucet = faucet_account(
+        &state.config.accounts,
+        &state.config.devnet_config.faucet_account,
+    )
+    .map_err(internal_error)?;
+
+    let stacks_node_url = state.config.consolidated_stacks_rpc_url();
+    let txid = tokio::task::spawn_blocking(move || -> Result<String, String> {
+        let stacks_rpc = StacksRpc::new(&stacks_node_url);
+        let nonce = stacks_rpc
+            .get_nonce(&faucet.stx_address)
+            .map_err(|e| format!("unable to retrieve faucet nonce: {}", e))?;
+        let transaction = encode_stx_transfer(
+            recipient,
+            request.amount,
+            [0u8; 34],
+            &faucet,
+            nonce,
+            FAUCET_TX_FEE,
+            TransactionAnchorMode::Any,
+            &StacksNetwork::Devnet,
+        )?;
+        stacks_rpc
+            .post_transaction(&transaction)
+            .map(|res| res.txid)
+            .map_err(|e| format!("unable to broadcast faucet transaction: {}", e))
+    })
+    .await
+    .map_err(internal_error)?
+    .map_err(internal_error)?;
+
+    Ok(json!({ "txid": txid }))
+}
+
+/// Account funding the faucet transfers, labeled `devnet.faucet_account` in settings/Devnet.toml
+fn faucet_account(accounts: &[AccountConfig], label: &str) -> Result<AccountConfig, String> {
+    accounts
+        .iter()
+        .find(|account| account.label == label)
+        .cloned()
+        .ok_or(format!(
+            "faucet account {} not found in settings/Devnet.toml",
+            label
+        ))
+}
+
+/// Copy the chainstate of the nodes (`<working_dir>/data`) to `<working_dir>/snapshots/<name>`.
+/// Mining should be paused beforehand for a consistent copy.
+fn take_snapshot(working_dir: &str, body: &[u8]) -> ApiResult {
+    let request: SnapshotRequest = serde_json::from_slice(body).map_err(bad_request)?;
+    let valid_name = !request.name.is_empty()
+        && request
+            .name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid_name {
+        return Err(bad_request(format!(
+            "invalid snapshot name {:?}, only use letters, digits, '-' and '_'",
+            request.name
+        )));
+    }
+    let working_dir = PathBuf::from(working_dir);
+    let destination = working_dir.join("snapshots").join(&request.name);
+    if destination.exists() {
+        return Err(bad_request(format!(
+            "snapshot {} already exists",
+            request.name
+        )));
+    }
+    copy_dir(&working_dir.join("data"), &destination).map_err(internal_error)?;
+    Ok(json!({ "ok": true, "path": destination.display().to_string() }))
+}
+
+fn copy_dir(source: &Path, destination: &Path) -> Result<(), String> {
+    fs::create_dir_all(destination)
+        .map_err(|e| format!("unable to create {}: {}", destination.display(), e))?;
+    let entries =
+        fs::read_dir(source).map_err(|e| format!("unable to read {}: {}", source.display(), e))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("unable to read {}: {}", source.display(), e))?;
+        let path = entry.path();
+        let target = destination.join(entry.file_name());
+        if path.is_dir() {
+            copy_dir(&path, &target)?;
+        } else {
+            fs::copy(&path, &target)
+                .map_err(|e| format!("unable to copy {}: {}", path.display(), e))?;
+        }
+    }
+    Ok(())
+}
+
+fn restart_services(state: &ControlApiState) -> ApiResult {
+    state
+        .orchestrator_terminator_tx
+        .lock()
+        .map_err(internal_error)?
+        .send(false)
+        .map_err(internal_error)?;
+    Ok(json!({ "ok": true }))
+}
+
+fn openapi_specification() -> JsonValue {
+    let ok_response = json!({
+        "description": "Command accepted",
+        "content": { "application/json": { "schema": { "$ref": "#/components/schemas/Ok" } } }
+    });
+    let error_response = json!({
+        "description": "Command rejected",
+        "content": { "application/json": { "schema": { "$ref": "#/components/schemas/Error" } } }
+    });
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Clarinet devnet control API",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "Drive a running Clarinet devnet: inspect its status, control bitcoin mining, fund accounts, snapshot the chainstate and restart the nodes."
+        },
+        "paths": {
+            "/v1/status": {
+                "get": {
+                    "summary": "Devnet status, nodes urls and accounts",
+                    "responses": {
+                        "200": {
+                            "description": "Devnet status",
+                            "content": { "application/json": { "schema": { "$ref": "#/components/schemas/Status" } } }
+                        }
+                    }
+                }
+            },
+            "/v1/mining/mine": {
+                "post": {
+                    "summary": "Mine one or several bitcoin blocks",
+                    "requestBody": {
+                        "required": false,
+                        "content": { "application/json": { "schema": { "$ref": "#/components/schemas/MineRequest" } } }
+                    },
+                    "responses": { "200": ok_response, "400": error_response }
+                }
+            },
+            "/v1/mining/start": {
+                "post": {
+                    "summary": "Resume the automatic mining of bitcoin blocks",
+                    "responses": { "200": ok_response }
+                }
+            },
+            "/v1/mining/pause": {
+                "post": {
+                    "summary": "Pause the automatic mining of bitcoin blocks",
+                    "responses": { "200": ok_response }
+                }
+            },
+            "/v1/faucet": {
+                "post": {
+                    "summary": "Transfer STX from the faucet account",
+                    "requestBody": {
+                        "required": true,
+                        "content": { "application/json": { "schema": { "$ref": "#/components/schemas/FaucetRequest" } } }
+                    },
+                    "responses": {
+                        "200": {
+                            "description": "Transaction broadcasted",
+                            "content": { "application/json": { "schema": { "$ref": "#/components/schemas/FaucetResponse" } } }
+                        },
+                        "400": error_response,
+                        "500": error_response
+                    }
+                }
+            },
+            "/v1/services/restart": {
+                "post": {
+                    "summary": "Restart the bitcoin and stacks nodes",
+                    "responses": { "200": ok_response }
+                }
+            },
+            "/v1/snapshots": {
+                "post": {
+                    "summary": "Copy the chainstate of the nodes, mining should be paused beforehand",
+                    "requestBody": {
+                        "required": true,
+                        "content": { "application/json": { "schema": { "$ref": "#/components/schemas/SnapshotRequest" } } }
+                    },
+                    "responses": {
+                        "200": {
+                            "description": "Snapshot taken",
+                            "content": { "application/json": { "schema": { "$ref": "#/components/schemas/SnapshotResponse" } } }
+                        },
+                        "400": error_response,
+                        "500": error_response
+                    }
+                }
+            }
+        },
+        "components": {
+            "schemas": {
+                "Ok": {
+                    "type": "object",
+                    "properties": { "ok": { "type": "boolean" } }
+                },
+                "Error": {
+                    "type": "object",
+                    "properties": { "error": { "type": "string" } }
+                },
+                "MineRequest": {
+                    "type": "object",
+                    "properties": {
+                        "blocks": {
+                            "type": "integer",
+                            "minimum": 1,
+                            "maximum": MAX_BLOCKS_PER_REQUEST,
+                            "default": 1
+                        }
+                    }
+                },
+                "FaucetRequest": {
+                    "type": "object",
+                    "required": ["recipient", "amount"],
+                    "properties": {
+                        "recipient": { "type": "string", "description": "Standard or contract principal" },
+                        "amount": { "type": "integer", "description": "Amount in micro-STX" }
+                    }
+                },
+                "FaucetResponse": {
+                    "type": "object",
+                    "properties": { "txid": { "type": "string" } }
+                },
+                "SnapshotRequest": {
+                    "type": "object",
+                    "required": ["name"],
+                    "properties": {
+                        "name": { "type": "string", "description": "Letters, digits, '-' and '_'" }
+                    }
+                },
+                "SnapshotResponse": {
+                    "type": "object",
+                    "properties": {
+                        "ok": { "type": "boolean" },
+                        "path": { "type": "string" }
+                    }
+                },
+                "Status": {
+                    "type": "object",
+                    "properties": {
+                        "boot_completed": { "type": "boolean" },
+                        "stacks_node_url": { "type": "string" },
+                        "bitcoin_node_url": { "type": "string" },
+                        "stacks_node": {
+                            "type": "object",
+                            "properties": {
+                                "ready": { "type": "boolean" },
+                                "stacks_tip_height": { "type": "integer" },
+                                "stacks_tip": { "type": "string" },
+                                "burn_block_height": { "type": "integer" },
+                                "server_version": { "type": "string" },
+                                "error": { "type": "string" }
+                            }
+                        },
+                        "accounts": {
+                            "type": "array",
+                            "items": {
+                                "type": "object",
+                                "properties": {
+                                    "label": { "type": "string" },
+                                    "stx_address": { "type": "string" },
+                                    "btc_address": { "type": "string" },
+                                    "balance": { "type": "integer" }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clarinet_files::{NetworkManifest, NetworkManifestFile};
+    use std::sync::mpsc::channel;
+
+    #[test]
+    fn test_openapi_specification_references() {
+        let spec = openapi_specification();
+        let schemas = spec["components"]["schemas"].as_object().unwrap();
+        let serialized = spec.to_string();
+        for reference in serialized.split("#/components/schemas/").skip(1) {
+            let name = reference.split('"').next().unwrap();
+            assert!(schemas.contains_key(name), "missing schema {}", name);
+        }
+    }
+
+    #[test]
+    fn test_mine_blocks() {
+        let (tx, rx) = channel();
+        let mining_command_tx = Mutex::new(tx);
+
+        let result = mine_blocks(&mining_command_tx, br#"{"blocks":3}"#).unwrap();
+        assert_eq!(result["blocks"], 3);
+        assert_eq!(rx.try_iter().count(), 3);
+
+        mine_blocks(&mining_command_tx, b"").unwrap();
+        assert!(matches!(rx.try_recv(), Ok(BitcoinMiningCommand::Mine)));
+
+        let (status, _) = mine_blocks(&mining_command_tx, br#"{"blocks":0}"#).unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = mine_blocks(&mining_command_tx, br#"{"blocks":101}"#).unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = mine_blocks(&mining_command_tx, b"{").unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_faucet_account_from_devnet_settings() {
+        let mnemonic = "shadow private easily thought say logic fault paddle word top book during ignore notable orange flight clock image wealth health outside kitten belt reform";
+        let mut manifest_file: NetworkManifestFile = serde_json::from_value(json!({
+            "network": { "name": "devnet" },
+            "accounts": { "wallet_9": { "mnemonic": mnemonic, "balance": 1_000_000 } },
+            "devnet": { "faucet_account": "wallet_9" },
+        }))
+        .unwrap();
+        let manifest = NetworkManifest::from_network_manifest_file(
+            &mut manifest_file,
+            &StacksNetwork::Devnet.get_networks(),
+            None,
+            None,
+        )
+        .unwrap();
+        let accounts: Vec<AccountConfig> = manifest.accounts.into_values().collect();
+        let devnet_config = manifest.devnet.unwrap();
+
+        let faucet = faucet_account(&accounts, &devnet_config.faucet_account).unwrap();
+        assert_eq!(faucet.label, "wallet_9");
+        assert_eq!(faucet.balance, 1_000_000);
+        assert!(faucet_account(&accounts, "faucet").is_err());
+    }
+
+    #[test]
+    fn test_take_snapshot() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let working_dir = temp_dir.path();
+        fs::create_dir_all(working_dir.join("data/stacks/chainstate")).unwrap();
+        fs::write(
+            working_dir.join("data/stacks/chainstate/index.sqlite"),
+            "tip",
+        )
+        .unwrap();
+        let working_dir_str = working_dir.display().to_string();
+
+        let result = take_snapshot(&working_dir_str, br#"{"name":"after-deploy"}"#).unwrap();
+        let snapshot = working_dir.join("snapshots/after-deploy");
+        assert_eq!(result["path"], snapshot.display().to_string());
+        assert_eq!(
+            fs::read_to_string(snapshot.join("stacks/chainstate/index.sqlite")).unwrap(),
+            "tip"
+        );
+
+        let (status, _) =
+            take_snapshot(&working_dir_str, br#"{"name":"after-deploy"}"#).unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = take_snapshot(&working_dir_str, br#"{"name":"../data"}"#).unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
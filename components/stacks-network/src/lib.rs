@@ -5,6 +5,7 @@ extern crate serde_derive;
 
 mod chainhooks;
 pub mod chains_coordinator;
+mod control_api;
 mod event;
 mod log;
 mod orchestrator;
@@ -13,7 +14,7 @@ mod ui;
 pub use chainhook_sdk::observer::MempoolAdmissionData;
 pub use chainhook_sdk::{self, utils::Context};
 use chainhook_sdk::{chainhooks::types::ChainhookStore, observer::ObserverCommand};
-pub use chainhooks::{load_chainhooks, parse_chainhook_full_specification};
+pub use chainhooks::{lint_chainhooks, load_chainhooks, parse_chainhook_full_specification};
 use chains_coordinator::BitcoinMiningCommand;
 use clarinet_files::NetworkManifest;
 pub use event::DevnetEvent;
@@ -180,6 +181,7 @@ async fn do_run_devnet(
             devnet_events_tx,
             devnet_events_rx,
             moved_chains_coordinator_commands_tx,
+            orchestrator_terminator_tx,
             orchestrator_terminated_rx.expect(
                 "orchestrator_terminated_rx should be provided when display_dashboard set to true",
             ),
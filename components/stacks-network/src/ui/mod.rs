@@ -29,6 +29,7 @@ pub fn start_ui(
     devnet_events_tx: Sender<DevnetEvent>,
     devnet_events_rx: Receiver<DevnetEvent>,
     chains_coordinator_commands_tx: crossbeam_channel::Sender<ChainsCoordinatorCommand>,
+    orchestrator_terminator_tx: Sender<bool>,
     orchestrator_terminated_rx: Receiver<bool>,
     devnet_path: &str,
     subnet_enabled: bool,
@@ -39,6 +40,7 @@ pub fn start_ui(
         devnet_events_tx,
         devnet_events_rx,
         chains_coordinator_commands_tx,
+        orchestrator_terminator_tx,
         orchestrator_terminated_rx,
         devnet_path,
         subnet_enabled,
@@ -55,6 +57,7 @@ pub fn do_start_ui(
     devnet_events_tx: Sender<DevnetEvent>,
     devnet_events_rx: Receiver<DevnetEvent>,
     chains_coordinator_commands_tx: crossbeam_channel::Sender<ChainsCoordinatorCommand>,
+    orchestrator_terminator_tx: Sender<bool>,
     orchestrator_terminated_rx: Receiver<bool>,
     devnet_path: &str,
     subnet_enabled: bool,
@@ -155,6 +158,23 @@ pub fn do_start_ui(
                         );
                     }
                 }
+                (KeyModifiers::NONE, KeyCode::Char('r')) => {
+                    // same as `POST /v1/services/restart` on the control API
+                    if mining_command_tx.is_some() {
+                        let _ = orchestrator_terminator_tx.send(false);
+                        app.display_log(
+                            DevnetEvent::log_warning(
+                                "Restarting bitcoin-node and stacks-node".to_string(),
+                            ),
+                            ctx,
+                        );
+                    } else {
+                        app.display_log(
+                            DevnetEvent::log_error("Devnet not ready to restart".to_string()),
+                            ctx,
+                        );
+                    }
+                }
                 (KeyModifiers::NONE, KeyCode::Left) => app.on_left(),
                 (KeyModifiers::NONE, KeyCode::Up) => app.on_up(),
                 (KeyModifiers::NONE, KeyCode::Right) => app.on_right(),
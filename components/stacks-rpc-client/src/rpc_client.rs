@@ -95,6 +95,17 @@ pub struct Balance {
     pub nonce_proof: String,
 }
 
+/// Nonces of an address, as reported by the Stacks API
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct AddressNonces {
+    pub last_mempool_tx_nonce: Option<u64>,
+    pub last_executed_tx_nonce: Option<u64>,
+    pub possible_next_nonce: u64,
+    /// Nonces missing between the last executed nonce and the mempool transactions
+    pub detected_missing_nonces: Vec<u64>,
+    pub detected_mempool_nonces: Vec<u64>,
+}
+
 #[derive(Deserialize, Debug)]
 pub struct Contract {
     pub source: String,
@@ -180,6 +191,18 @@ impl StacksRpc {
         Ok(nonce)
     }
 
+    /// Requires the url of a Stacks API, the nodes do not expose their mempool by address
+    pub fn get_address_nonces(&self, address: &str) -> Result<AddressNonces, RpcError> {
+        let request_url = format!("{}/extended/v1/address/{}/nonces", self.url, address);
+
+        self.client
+            .get(request_url)
+            .send()
+            .map_err(|e| RpcError::Message(e.to_string()))?
+            .json::<AddressNonces>()
+            .map_err(|e| RpcError::Message(e.to_string()))
+    }
+
     pub fn get_pox_info(&self) -> Result<PoxInfo, RpcError> {
         let request_url = format!("{}/v2/pox", self.url);
 